@@ -1,17 +1,32 @@
+use std::collections::BTreeMap;
 use std::net::{SocketAddr, TcpListener};
 use std::sync::Arc;
 
+use assert_matches::assert_matches;
 use axum::body::Body;
 use axum::http::{Request, StatusCode};
 use axum::response::Response;
 use axum::Router;
+use futures::channel::mpsc;
+use futures::StreamExt;
+use libp2p::PeerId;
 use metrics::{counter, describe_counter};
 use metrics_exporter_prometheus::PrometheusBuilder;
+use papyrus_network::network_manager::{
+    AccessListUpdate,
+    ConnectedPeerInfo,
+    NetworkCommand,
+    NetworkInfo,
+    NetworkRequest,
+    PeerReputation,
+    Traffic,
+};
 use papyrus_storage::{table_names, test_utils};
 use pretty_assertions::assert_eq;
 use serde_json::{json, Value};
 use starknet_client::reader::MockStarknetReader;
 use starknet_client::writer::MockStarknetWriter;
+use tempfile::TempDir;
 use tower::ServiceExt;
 
 use crate::{app, is_ready, MONITORING_PREFIX};
@@ -19,6 +34,7 @@ use crate::{app, is_ready, MONITORING_PREFIX};
 const TEST_CONFIG_PRESENTATION: &str = "full_general_config_presentation";
 const PUBLIC_TEST_CONFIG_PRESENTATION: &str = "public_general_config_presentation";
 const SECRET: &str = "abcd";
+const ADMIN_SECRET: &str = "efgh";
 const TEST_VERSION: &str = "1.2.3-dev";
 const TEST_PEER_ID: &str = "peer_id";
 
@@ -32,8 +48,10 @@ fn setup_app() -> Router {
         serde_json::to_value(TEST_CONFIG_PRESENTATION).unwrap(),
         serde_json::to_value(PUBLIC_TEST_CONFIG_PRESENTATION).unwrap(),
         SECRET.to_string(),
+        ADMIN_SECRET.to_string(),
         None,
         TEST_PEER_ID.to_string(),
+        None,
     )
 }
 
@@ -139,6 +157,139 @@ async fn peer_id() {
     assert_eq!(body, TEST_PEER_ID);
 }
 
+fn setup_app_with_network() -> (Router, mpsc::Receiver<NetworkRequest>, TempDir) {
+    let ((storage_reader, _), temp_dir) = test_utils::get_test_storage();
+    let (network_request_sender, network_request_receiver) = mpsc::channel(1);
+    let app = app(
+        String::from("https://default_url"),
+        storage_reader,
+        TEST_VERSION,
+        serde_json::Value::default(),
+        serde_json::Value::default(),
+        String::new(),
+        ADMIN_SECRET.to_string(),
+        None,
+        TEST_PEER_ID.to_string(),
+        Some(network_request_sender),
+    );
+    (app, network_request_receiver, temp_dir)
+}
+
+async fn post_app(app: Router, method: &str) -> Response {
+    app.oneshot(
+        Request::builder()
+            .method("POST")
+            .uri(format!("/{MONITORING_PREFIX}/{method}").as_str())
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn without_network() {
+    for method in ["peerReputations", "networkInfo"] {
+        let app = setup_app();
+        let response = request_app(app, method).await;
+
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+}
+
+#[tokio::test]
+async fn peer_reputations() {
+    let (app, mut network_request_receiver, _temp_dir) = setup_app_with_network();
+    let peer_reputation = PeerReputation {
+        peer_id: PeerId::random(),
+        score: 0.5,
+        credit: 1.0,
+        misconduct_score: 0.5,
+        average_latency_millis: Some(100),
+        selection_weight: 1.5,
+        blocked_for_millis: 0,
+    };
+    let expected_peer_reputation = peer_reputation.clone();
+    tokio::spawn(async move {
+        let Some(NetworkRequest::GetPeerReputations(response_sender)) =
+            network_request_receiver.next().await
+        else {
+            panic!("Expected a request for the peer reputations.");
+        };
+        response_sender.send(vec![peer_reputation]).unwrap();
+    });
+
+    let response = request_app(app, "peerReputations").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Vec<PeerReputation> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, vec![expected_peer_reputation]);
+}
+
+#[tokio::test]
+async fn network_info() {
+    let (app, mut network_request_receiver, _temp_dir) = setup_app_with_network();
+    let network_info = NetworkInfo {
+        local_peer_id: PeerId::random(),
+        connected_peers: vec![ConnectedPeerInfo {
+            peer_id: PeerId::random(),
+            addresses: vec!["/ip4/127.0.0.1/tcp/10000".parse().unwrap()],
+            agent_version: Some("agent".to_string()),
+        }],
+        sqmr_sessions: vec![],
+        gossipsub_mesh: BTreeMap::from([("topic".to_string(), vec![PeerId::random()])]),
+        peer_reputations: vec![],
+        traffic: BTreeMap::from([(
+            "protocol".to_string(),
+            Traffic { bytes_sent: 1, bytes_received: 2 },
+        )]),
+    };
+    let expected_network_info = network_info.clone();
+    tokio::spawn(async move {
+        let Some(NetworkRequest::GetNetworkInfo(response_sender)) =
+            network_request_receiver.next().await
+        else {
+            panic!("Expected a request for the network info.");
+        };
+        response_sender.send(network_info).unwrap();
+    });
+
+    let response = request_app(app, "networkInfo").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: NetworkInfo = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, expected_network_info);
+}
+
+#[tokio::test]
+async fn network_admin_commands() {
+    let peer_id = PeerId::random();
+    let (app, mut network_request_receiver, _temp_dir) = setup_app_with_network();
+
+    let response =
+        post_app(app.clone(), &format!("banPeer/{ADMIN_SECRET}?peer_id={peer_id}")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_matches!(
+        network_request_receiver.next().await.unwrap(),
+        NetworkRequest::Command(command)
+            if command == NetworkCommand::UpdateAccessList(AccessListUpdate::DenyPeer(peer_id))
+    );
+
+    let response =
+        post_app(app.clone(), &format!("disconnectPeer/{ADMIN_SECRET}?peer_id={peer_id}")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_matches!(
+        network_request_receiver.next().await.unwrap(),
+        NetworkRequest::Command(command) if command == NetworkCommand::DisconnectPeer(peer_id)
+    );
+
+    let response = post_app(app, &format!("disconnectPeer/{SECRET}?peer_id={peer_id}")).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert!(!matches!(network_request_receiver.try_next(), Ok(Some(_))));
+}
+
 #[tokio::test]
 async fn ready() {
     let mut gateway_client_mock = MockStarknetWriter::new();
@@ -174,8 +325,10 @@ async fn with_metrics() {
         serde_json::Value::default(),
         serde_json::Value::default(),
         String::new(),
+        String::new(),
         Some(prometheus_handle),
         TEST_PEER_ID.to_string(),
+        None,
     );
 
     // Register a metric.
@@ -10,16 +10,26 @@ use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
 
-use axum::extract::Path;
+use axum::extract::{Path, Query};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::{Json, Router};
+use futures::channel::{mpsc, oneshot};
+use futures::SinkExt;
+use libp2p::{Multiaddr, PeerId};
 use metrics_exporter_prometheus::{BuildError, PrometheusBuilder, PrometheusHandle};
 use metrics_process::Collector;
 use papyrus_config::converters::{deserialize_optional_map, serialize_optional_map};
 use papyrus_config::dumping::{ser_generated_param, ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializationType, SerializedParam};
+use papyrus_network::network_manager::{
+    AccessListUpdate,
+    NetworkCommand,
+    NetworkInfo,
+    NetworkRequest,
+    PeerReputation,
+};
 use papyrus_storage::mmap_file::MMapFileStats;
 use papyrus_storage::{DbStats, StorageError, StorageReader};
 use rand::distributions::Alphanumeric;
@@ -44,14 +54,27 @@ pub struct MonitoringGatewayConfig {
     #[serde(default = "random_secret")]
     pub present_full_config_secret: String,
     pub starknet_url: String,
+    #[validate(length(min = 1))]
+    #[serde(default = "random_network_admin_secret")]
+    pub network_admin_secret: String,
 }
 
 fn random_secret() -> String {
-    let secret = thread_rng().sample_iter(&Alphanumeric).take(10).map(char::from).collect();
+    let secret = generate_secret();
     info!("The randomly generated config presentation secret is: {}", secret);
     secret
 }
 
+fn random_network_admin_secret() -> String {
+    let secret = generate_secret();
+    info!("The randomly generated network admin secret is: {}", secret);
+    secret
+}
+
+fn generate_secret() -> String {
+    thread_rng().sample_iter(&Alphanumeric).take(10).map(char::from).collect()
+}
+
 impl Default for MonitoringGatewayConfig {
     fn default() -> Self {
         MonitoringGatewayConfig {
@@ -61,6 +84,8 @@ impl Default for MonitoringGatewayConfig {
             // A constant value for testing purposes.
             present_full_config_secret: String::from("qwerty"),
             starknet_url: String::from("https://alpha-mainnet.starknet.io/"),
+            // A constant value for testing purposes.
+            network_admin_secret: String::from("qwerty"),
         }
     }
 }
@@ -98,6 +123,13 @@ impl SerializeConfig for MonitoringGatewayConfig {
                 "The URL of a centralized Starknet gateway.",
                 ParamPrivacyInput::Public,
             ),
+            ser_generated_param(
+                "network_admin_secret",
+                SerializationType::String,
+                "A secret for the network admin commands, such as dialing, disconnecting and \
+                 banning peers.",
+                ParamPrivacyInput::Private,
+            ),
         ])
     }
 }
@@ -119,6 +151,7 @@ pub struct MonitoringServer {
     version: &'static str,
     prometheus_handle: Option<PrometheusHandle>,
     own_peer_id: String,
+    network_request_sender: Option<mpsc::Sender<NetworkRequest>>,
 }
 
 impl MonitoringServer {
@@ -129,6 +162,7 @@ impl MonitoringServer {
         storage_reader: StorageReader,
         version: &'static str,
         own_peer_id: String,
+        network_request_sender: Option<mpsc::Sender<NetworkRequest>>,
     ) -> Result<Self, BuildError> {
         let prometheus_handle = if config.collect_metrics {
             let mut builder = PrometheusBuilder::new();
@@ -149,6 +183,7 @@ impl MonitoringServer {
             version,
             prometheus_handle,
             own_peer_id,
+            network_request_sender,
         })
     }
 
@@ -171,8 +206,10 @@ impl MonitoringServer {
             self.full_general_config_presentation.clone(),
             self.public_general_config_presentation.clone(),
             self.config.present_full_config_secret.clone(),
+            self.config.network_admin_secret.clone(),
             self.prometheus_handle.clone(),
             self.own_peer_id.clone(),
+            self.network_request_sender.clone(),
         );
         debug!("Starting monitoring gateway.");
         axum::Server::bind(&server_address).serve(app.into_make_service()).await
@@ -187,8 +224,10 @@ fn app(
     full_general_config_presentation: serde_json::Value,
     public_general_config_presentation: serde_json::Value,
     present_full_config_secret: String,
+    network_admin_secret: String,
     prometheus_handle: Option<PrometheusHandle>,
     own_peer_id: String,
+    network_request_sender: Option<mpsc::Sender<NetworkRequest>>,
 ) -> Router {
     let is_ready_retry_config =
         RetryConfig { retry_base_millis: 50, retry_max_delay_millis: 1000, max_retries: 0 };
@@ -208,6 +247,7 @@ fn app(
 
     let db_tables_stats_reader = storage_reader.clone();
     let mmap_files_stats_reader = storage_reader.clone();
+    let network_admin = NetworkAdmin { network_request_sender, secret: network_admin_secret };
 
     Router::new()
         .route(
@@ -250,6 +290,59 @@ fn app(
             get(move || is_ready(starknet_client, starknet_feeder_client)),
         )
         .route(format!("/{MONITORING_PREFIX}/peer_id").as_str(), get(move || async { own_peer_id }))
+        .route(
+            format!("/{MONITORING_PREFIX}/peerReputations").as_str(),
+            get({
+                let network_admin = network_admin.clone();
+                move || peer_reputations(network_admin.network_request_sender)
+            }),
+        )
+        .route(
+            format!("/{MONITORING_PREFIX}/networkInfo").as_str(),
+            get({
+                let network_admin = network_admin.clone();
+                move || network_info(network_admin.network_request_sender)
+            }),
+        )
+        .route(
+            format!("/{MONITORING_PREFIX}/dialPeer/:secret").as_str(),
+            post({
+                let network_admin = network_admin.clone();
+                move |secret, Query(AddressQuery { address })| {
+                    network_admin.run_command(secret, NetworkCommand::DialPeer(address))
+                }
+            }),
+        )
+        .route(
+            format!("/{MONITORING_PREFIX}/disconnectPeer/:secret").as_str(),
+            post({
+                let network_admin = network_admin.clone();
+                move |secret, Query(PeerIdQuery { peer_id })| {
+                    network_admin.run_command(secret, NetworkCommand::DisconnectPeer(peer_id))
+                }
+            }),
+        )
+        .route(
+            format!("/{MONITORING_PREFIX}/banPeer/:secret").as_str(),
+            post({
+                let network_admin = network_admin.clone();
+                move |secret, Query(PeerIdQuery { peer_id })| {
+                    network_admin.run_command(
+                        secret,
+                        NetworkCommand::UpdateAccessList(AccessListUpdate::DenyPeer(peer_id)),
+                    )
+                }
+            }),
+        )
+        .route(
+            format!("/{MONITORING_PREFIX}/unbanPeer/:secret").as_str(),
+            post(move |secret, Query(PeerIdQuery { peer_id })| {
+                network_admin.run_command(
+                    secret,
+                    NetworkCommand::UpdateAccessList(AccessListUpdate::UndenyPeer(peer_id)),
+                )
+            }),
+        )
 }
 
 async fn is_ready<TStarknetWriter: StarknetWriter, TStarknetReader: StarknetReader>(
@@ -314,6 +407,76 @@ async fn metrics(prometheus_handle: Option<PrometheusHandle>) -> Response {
     }
 }
 
+/// Returns the reputation table of the peers, sorted by descending score.
+/// In case the node doesn't run a network returns an empty response with status code 405: method
+/// not allowed.
+#[instrument(level = "debug", ret, skip(network_request_sender))]
+async fn peer_reputations(
+    network_request_sender: Option<mpsc::Sender<NetworkRequest>>,
+) -> Result<Json<Vec<PeerReputation>>, ServerError> {
+    let mut network_request_sender = network_request_sender.ok_or(ServerError::NoNetwork)?;
+    let (response_sender, response_receiver) = oneshot::channel();
+    network_request_sender
+        .send(NetworkRequest::GetPeerReputations(response_sender))
+        .await
+        .map_err(|_| ServerError::NetworkStopped)?;
+    Ok(response_receiver.await.map_err(|_| ServerError::NetworkStopped)?.into())
+}
+
+/// Returns the connected peers, the open SQMR sessions, the gossipsub mesh, the peer reputations
+/// and the traffic of each protocol.
+/// In case the node doesn't run a network returns an empty response with status code 405: method
+/// not allowed.
+#[instrument(level = "debug", ret, skip(network_request_sender))]
+async fn network_info(
+    network_request_sender: Option<mpsc::Sender<NetworkRequest>>,
+) -> Result<Json<NetworkInfo>, ServerError> {
+    let mut network_request_sender = network_request_sender.ok_or(ServerError::NoNetwork)?;
+    let (response_sender, response_receiver) = oneshot::channel();
+    network_request_sender
+        .send(NetworkRequest::GetNetworkInfo(response_sender))
+        .await
+        .map_err(|_| ServerError::NetworkStopped)?;
+    Ok(response_receiver.await.map_err(|_| ServerError::NetworkStopped)?.into())
+}
+
+#[derive(Deserialize)]
+struct AddressQuery {
+    address: Multiaddr,
+}
+
+#[derive(Deserialize)]
+struct PeerIdQuery {
+    peer_id: PeerId,
+}
+
+#[derive(Clone)]
+struct NetworkAdmin {
+    network_request_sender: Option<mpsc::Sender<NetworkRequest>>,
+    secret: String,
+}
+
+impl NetworkAdmin {
+    /// Sends an admin command to the network, if the given secret is correct.
+    #[instrument(level = "debug", ret, skip(self, given_secret))]
+    async fn run_command(
+        self,
+        given_secret: Path<String>,
+        command: NetworkCommand,
+    ) -> Result<StatusCode, ServerError> {
+        if given_secret.as_str() != self.secret {
+            return Err(ServerError::WrongSecret);
+        }
+        let mut network_request_sender =
+            self.network_request_sender.ok_or(ServerError::NoNetwork)?;
+        network_request_sender
+            .send(command.into())
+            .await
+            .map_err(|_| ServerError::NetworkStopped)?;
+        Ok(StatusCode::OK)
+    }
+}
+
 /// Returns the node version.
 #[instrument(level = "debug", ret)]
 async fn node_version(version: &'static str) -> String {
@@ -324,6 +487,12 @@ async fn node_version(version: &'static str) -> String {
 enum ServerError {
     #[error(transparent)]
     StorageError(#[from] StorageError),
+    #[error("The node doesn't run a network.")]
+    NoNetwork,
+    #[error("The network stopped running.")]
+    NetworkStopped,
+    #[error("Wrong secret.")]
+    WrongSecret,
 }
 
 impl IntoResponse for ServerError {
@@ -331,6 +500,9 @@ impl IntoResponse for ServerError {
         let (status, error_message) = match self {
             // TODO(dan): consider using a generic error message instead.
             ServerError::StorageError(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            ServerError::NoNetwork => (StatusCode::METHOD_NOT_ALLOWED, self.to_string()),
+            ServerError::NetworkStopped => (StatusCode::SERVICE_UNAVAILABLE, self.to_string()),
+            ServerError::WrongSecret => (StatusCode::FORBIDDEN, self.to_string()),
         };
         (status, error_message).into_response()
     }
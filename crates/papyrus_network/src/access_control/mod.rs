@@ -0,0 +1,232 @@
+//! Controls which peers the node is willing to be connected to. In a permissioned network only the
+//! allowlisted peers can connect, and in any network the denylisted peers can't connect.
+//! Connections are refused before they're handed to the other behaviours, so a refused peer never
+//! gets to open SQMR sessions or to exchange gossipsub messages.
+
+#[cfg(test)]
+mod test;
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::task::{Context, Poll, Waker};
+
+use libp2p::core::Endpoint;
+use libp2p::swarm::{
+    dummy,
+    CloseConnection,
+    ConnectionDenied,
+    ConnectionId,
+    FromSwarm,
+    NetworkBehaviour,
+    THandler,
+    THandlerInEvent,
+    THandlerOutEvent,
+    ToSwarm,
+};
+use libp2p::{Multiaddr, PeerId};
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::mixed_behaviour::BridgedBehaviour;
+use crate::{deserialize_list, list_to_string, mixed_behaviour, serialize_list};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct AccessControlConfig {
+    pub permissioned: bool,
+    #[serde(deserialize_with = "deserialize_list", serialize_with = "serialize_list")]
+    pub allowed_peers: Vec<PeerId>,
+    #[serde(deserialize_with = "deserialize_list", serialize_with = "serialize_list")]
+    pub denied_peers: Vec<PeerId>,
+}
+
+impl SerializeConfig for AccessControlConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "permissioned",
+                &self.permissioned,
+                "If true, only the peers in allowed_peers can be connected to this node.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "allowed_peers",
+                &list_to_string(&self.allowed_peers),
+                "Space separated ids of the peers that can be connected to this node when \
+                 permissioned is true.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "denied_peers",
+                &list_to_string(&self.denied_peers),
+                "Space separated ids of the peers that can never be connected to this node. Takes \
+                 precedence over allowed_peers.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+/// A runtime change to the lists of allowed and denied peers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessListUpdate {
+    AllowPeer(PeerId),
+    DisallowPeer(PeerId),
+    DenyPeer(PeerId),
+    UndenyPeer(PeerId),
+}
+
+#[derive(Debug)]
+pub enum ToOtherBehaviourEvent {
+    /// The peer was connected or known before, and it's no longer allowed to be connected.
+    PeerDisallowed { peer_id: PeerId },
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Peer {peer_id} is not allowed to connect to this node.")]
+pub struct PeerNotAllowedError {
+    pub peer_id: PeerId,
+}
+
+pub struct Behaviour {
+    permissioned: bool,
+    allowed_peers: HashSet<PeerId>,
+    denied_peers: HashSet<PeerId>,
+    pending_events: VecDeque<ToSwarm<ToOtherBehaviourEvent, THandlerInEvent<Self>>>,
+    waker: Option<Waker>,
+}
+
+impl Behaviour {
+    pub fn new(config: AccessControlConfig) -> Self {
+        Self {
+            permissioned: config.permissioned,
+            allowed_peers: config.allowed_peers.into_iter().collect(),
+            denied_peers: config.denied_peers.into_iter().collect(),
+            pending_events: VecDeque::new(),
+            waker: None,
+        }
+    }
+
+    pub fn is_peer_allowed(&self, peer_id: &PeerId) -> bool {
+        !self.denied_peers.contains(peer_id)
+            && (!self.permissioned || self.allowed_peers.contains(peer_id))
+    }
+
+    /// Applies the update. If the peer is no longer allowed, its connections are closed.
+    pub fn update(&mut self, update: AccessListUpdate) {
+        info!("Updating the peer access lists: {update:?}");
+        let peer_id = match update {
+            AccessListUpdate::AllowPeer(peer_id) => {
+                self.allowed_peers.insert(peer_id);
+                peer_id
+            }
+            AccessListUpdate::DisallowPeer(peer_id) => {
+                self.allowed_peers.remove(&peer_id);
+                peer_id
+            }
+            AccessListUpdate::DenyPeer(peer_id) => {
+                self.denied_peers.insert(peer_id);
+                peer_id
+            }
+            AccessListUpdate::UndenyPeer(peer_id) => {
+                self.denied_peers.remove(&peer_id);
+                peer_id
+            }
+        };
+        if self.is_peer_allowed(&peer_id) {
+            return;
+        }
+        self.pending_events
+            .push_back(ToSwarm::CloseConnection { peer_id, connection: CloseConnection::All });
+        self.pending_events
+            .push_back(ToSwarm::GenerateEvent(ToOtherBehaviourEvent::PeerDisallowed { peer_id }));
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn check_peer(&self, peer_id: PeerId) -> Result<(), ConnectionDenied> {
+        if self.is_peer_allowed(&peer_id) {
+            Ok(())
+        } else {
+            Err(ConnectionDenied::new(PeerNotAllowedError { peer_id }))
+        }
+    }
+}
+
+impl NetworkBehaviour for Behaviour {
+    type ConnectionHandler = dummy::ConnectionHandler;
+    type ToSwarm = ToOtherBehaviourEvent;
+
+    fn handle_pending_outbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        maybe_peer: Option<PeerId>,
+        _addresses: &[Multiaddr],
+        _effective_role: Endpoint,
+    ) -> Result<Vec<Multiaddr>, ConnectionDenied> {
+        // Dials without a peer id are checked once the connection is established.
+        if let Some(peer_id) = maybe_peer {
+            self.check_peer(peer_id)?;
+        }
+        Ok(vec![])
+    }
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        peer_id: PeerId,
+        _local_addr: &Multiaddr,
+        _remote_addr: &Multiaddr,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.check_peer(peer_id)?;
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        peer_id: PeerId,
+        _addr: &Multiaddr,
+        _role_override: Endpoint,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.check_peer(peer_id)?;
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn on_swarm_event(&mut self, _event: FromSwarm<'_>) {}
+
+    fn on_connection_handler_event(
+        &mut self,
+        _peer_id: PeerId,
+        _connection_id: ConnectionId,
+        _event: THandlerOutEvent<Self>,
+    ) {
+        // no events from dummy handler
+    }
+
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        match self.pending_events.pop_front() {
+            Some(event) => Poll::Ready(event),
+            None => {
+                self.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl From<ToOtherBehaviourEvent> for mixed_behaviour::Event {
+    fn from(event: ToOtherBehaviourEvent) -> Self {
+        mixed_behaviour::Event::ToOtherBehaviourEvent(
+            mixed_behaviour::ToOtherBehaviourEvent::AccessControl(event),
+        )
+    }
+}
+
+impl BridgedBehaviour for Behaviour {
+    fn on_other_behaviour_event(&mut self, _event: &mixed_behaviour::ToOtherBehaviourEvent) {}
+}
@@ -0,0 +1,141 @@
+use std::task::{Context, Poll};
+
+use futures::task::noop_waker_ref;
+use libp2p::core::Endpoint;
+use libp2p::swarm::{
+    CloseConnection,
+    ConnectionId,
+    ListenError,
+    NetworkBehaviour,
+    SwarmEvent,
+    ToSwarm,
+};
+use libp2p::{Multiaddr, PeerId, Swarm};
+use libp2p_swarm_test::SwarmExt;
+
+use super::{AccessControlConfig, AccessListUpdate, Behaviour, ToOtherBehaviourEvent};
+
+fn is_inbound_connection_allowed(behaviour: &mut Behaviour, peer_id: PeerId) -> bool {
+    behaviour
+        .handle_established_inbound_connection(
+            ConnectionId::new_unchecked(0),
+            peer_id,
+            &Multiaddr::empty(),
+            &Multiaddr::empty(),
+        )
+        .is_ok()
+}
+
+fn is_outbound_dial_allowed(behaviour: &mut Behaviour, peer_id: PeerId) -> bool {
+    behaviour
+        .handle_pending_outbound_connection(
+            ConnectionId::new_unchecked(0),
+            Some(peer_id),
+            &[],
+            Endpoint::Dialer,
+        )
+        .is_ok()
+}
+
+fn poll_pending_events(
+    behaviour: &mut Behaviour,
+) -> Vec<ToSwarm<ToOtherBehaviourEvent, void::Void>> {
+    let mut cx = Context::from_waker(noop_waker_ref());
+    let mut events = vec![];
+    while let Poll::Ready(event) = behaviour.poll(&mut cx) {
+        events.push(event);
+    }
+    events
+}
+
+#[test]
+fn open_network_allows_all_peers_but_denied_ones() {
+    let denied_peer = PeerId::random();
+    let mut behaviour = Behaviour::new(AccessControlConfig {
+        denied_peers: vec![denied_peer],
+        ..Default::default()
+    });
+
+    let peer = PeerId::random();
+    assert!(is_inbound_connection_allowed(&mut behaviour, peer));
+    assert!(is_outbound_dial_allowed(&mut behaviour, peer));
+    assert!(!is_inbound_connection_allowed(&mut behaviour, denied_peer));
+    assert!(!is_outbound_dial_allowed(&mut behaviour, denied_peer));
+}
+
+#[test]
+fn permissioned_network_allows_only_allowlisted_peers() {
+    let allowed_peer = PeerId::random();
+    let allowed_and_denied_peer = PeerId::random();
+    let mut behaviour = Behaviour::new(AccessControlConfig {
+        permissioned: true,
+        allowed_peers: vec![allowed_peer, allowed_and_denied_peer],
+        denied_peers: vec![allowed_and_denied_peer],
+    });
+
+    assert!(is_inbound_connection_allowed(&mut behaviour, allowed_peer));
+    assert!(!is_inbound_connection_allowed(&mut behaviour, PeerId::random()));
+    // The denylist takes precedence over the allowlist.
+    assert!(!is_inbound_connection_allowed(&mut behaviour, allowed_and_denied_peer));
+    assert!(!is_outbound_dial_allowed(&mut behaviour, PeerId::random()));
+}
+
+#[test]
+fn disallowing_a_peer_closes_its_connections() {
+    let peer_id = PeerId::random();
+    let mut behaviour = Behaviour::new(AccessControlConfig {
+        permissioned: true,
+        allowed_peers: vec![peer_id],
+        ..Default::default()
+    });
+
+    behaviour.update(AccessListUpdate::DenyPeer(peer_id));
+    behaviour.update(AccessListUpdate::UndenyPeer(peer_id));
+    assert!(behaviour.is_peer_allowed(&peer_id));
+    behaviour.update(AccessListUpdate::DisallowPeer(peer_id));
+    assert!(!behaviour.is_peer_allowed(&peer_id));
+    behaviour.update(AccessListUpdate::AllowPeer(peer_id));
+    assert!(behaviour.is_peer_allowed(&peer_id));
+
+    // Each of the updates that made the peer disallowed closes its connections.
+    let events = poll_pending_events(&mut behaviour);
+    assert_eq!(events.len(), 4);
+    for event_pair in events.chunks(2) {
+        assert!(matches!(
+            event_pair[0],
+            ToSwarm::CloseConnection { peer_id: closed_peer_id, connection: CloseConnection::All }
+                if closed_peer_id == peer_id
+        ));
+        assert!(matches!(
+            event_pair[1],
+            ToSwarm::GenerateEvent(ToOtherBehaviourEvent::PeerDisallowed {
+                peer_id: disallowed_peer_id
+            }) if disallowed_peer_id == peer_id
+        ));
+    }
+}
+
+#[tokio::test]
+async fn connection_from_non_allowlisted_peer_is_denied() {
+    let mut permissioned_swarm = Swarm::new_ephemeral(|_| {
+        Behaviour::new(AccessControlConfig { permissioned: true, ..Default::default() })
+    });
+    let (listen_address, _) = permissioned_swarm.listen().with_memory_addr_external().await;
+
+    let mut other_swarm = Swarm::new_ephemeral(|_| Behaviour::new(Default::default()));
+    let other_peer_id = *other_swarm.local_peer_id();
+    other_swarm.dial(listen_address).unwrap();
+    tokio::spawn(other_swarm.loop_on_next());
+
+    permissioned_swarm
+        .wait(|event| match event {
+            SwarmEvent::IncomingConnectionError { error: ListenError::Denied { .. }, .. } => {
+                Some(())
+            }
+            SwarmEvent::ConnectionEstablished { peer_id, .. } if peer_id == other_peer_id => {
+                panic!("A non-allowlisted peer was connected.")
+            }
+            _ => None,
+        })
+        .await;
+}
@@ -18,6 +18,7 @@ use libp2p::swarm::{
     ToSwarm,
 };
 use libp2p::{Multiaddr, PeerId};
+use tempfile::tempdir;
 use tokio::time::timeout;
 use void::Void;
 
@@ -34,6 +35,7 @@ const CONFIG: DiscoveryConfig = DiscoveryConfig {
         factor: 1,
     },
     heartbeat_interval: Duration::ZERO,
+    peer_store_path: None,
 };
 
 impl Unpin for Behaviour {}
@@ -65,7 +67,7 @@ async fn discovery_outputs_dial_request_and_query_on_start() {
     let bootstrap_peer_id = PeerId::random();
     let bootstrap_peer_address = Multiaddr::empty();
 
-    let mut behaviour = Behaviour::new(CONFIG, bootstrap_peer_id, bootstrap_peer_address);
+    let mut behaviour = Behaviour::new(CONFIG, vec![(bootstrap_peer_id, bootstrap_peer_address)]);
 
     let event = timeout(TIMEOUT, behaviour.next()).await.unwrap().unwrap();
     assert_matches!(
@@ -106,7 +108,7 @@ async fn discovery_redials_on_dial_failure() {
 
     let mut config = CONFIG.clone();
     config.heartbeat_interval = BOOTSTRAP_DIAL_SLEEP * 2;
-    let mut behaviour = Behaviour::new(config, bootstrap_peer_id, bootstrap_peer_address);
+    let mut behaviour = Behaviour::new(config, vec![(bootstrap_peer_id, bootstrap_peer_address)]);
 
     let event = timeout(TIMEOUT, behaviour.next()).await.unwrap().unwrap();
     assert_matches!(
@@ -190,7 +192,8 @@ async fn create_behaviour_and_connect_to_bootstrap_node(config: DiscoveryConfig)
     let bootstrap_peer_id = PeerId::random();
     let bootstrap_peer_address = Multiaddr::empty();
 
-    let mut behaviour = Behaviour::new(config, bootstrap_peer_id, bootstrap_peer_address.clone());
+    let mut behaviour =
+        Behaviour::new(config, vec![(bootstrap_peer_id, bootstrap_peer_address.clone())]);
 
     // Consume the dial event.
     timeout(TIMEOUT, behaviour.next()).await.unwrap();
@@ -265,7 +268,8 @@ async fn discovery_performs_queries_even_if_not_connected_to_bootstrap_peer() {
     let bootstrap_peer_id = PeerId::random();
     let bootstrap_peer_address = Multiaddr::empty();
 
-    let mut behaviour = Behaviour::new(config, bootstrap_peer_id, bootstrap_peer_address.clone());
+    let mut behaviour =
+        Behaviour::new(config, vec![(bootstrap_peer_id, bootstrap_peer_address.clone())]);
 
     // Consume the initial dial and query events.
     timeout(TIMEOUT, behaviour.next()).await.unwrap();
@@ -285,3 +289,101 @@ async fn discovery_performs_queries_even_if_not_connected_to_bootstrap_peer() {
         ToSwarm::GenerateEvent(ToOtherBehaviourEvent::RequestKadQuery(_peer_id))
     );
 }
+
+#[tokio::test]
+async fn discovery_dials_all_bootstrap_peers() {
+    let bootstrap_peer_ids = [PeerId::random(), PeerId::random()];
+    let mut config = CONFIG;
+    config.heartbeat_interval = BOOTSTRAP_DIAL_SLEEP * 2;
+    let mut behaviour = Behaviour::new(
+        config,
+        bootstrap_peer_ids.iter().map(|peer_id| (*peer_id, Multiaddr::empty())).collect(),
+    );
+
+    for bootstrap_peer_id in bootstrap_peer_ids {
+        let event = timeout(TIMEOUT, behaviour.next()).await.unwrap().unwrap();
+        assert_matches!(
+            event,
+            ToSwarm::Dial{opts} if opts.get_peer_id() == Some(bootstrap_peer_id)
+        );
+    }
+
+    // A failure to dial one bootstrap peer doesn't affect the other.
+    behaviour.on_swarm_event(FromSwarm::DialFailure(DialFailure {
+        peer_id: Some(bootstrap_peer_ids[0]),
+        error: &DialError::Aborted,
+        connection_id: ConnectionId::new_unchecked(0),
+    }));
+    behaviour.on_swarm_event(FromSwarm::ConnectionEstablished(ConnectionEstablished {
+        peer_id: bootstrap_peer_ids[1],
+        connection_id: ConnectionId::new_unchecked(1),
+        endpoint: &ConnectedPoint::Dialer {
+            address: Multiaddr::empty(),
+            role_override: Endpoint::Dialer,
+        },
+        failed_addresses: &[],
+        other_established: 0,
+    }));
+    let event = timeout(TIMEOUT, behaviour.next()).await.unwrap().unwrap();
+    assert_matches!(
+        event,
+        ToSwarm::GenerateEvent(ToOtherBehaviourEvent::FoundListenAddresses { peer_id, .. })
+        if peer_id == bootstrap_peer_ids[1]
+    );
+    let event = timeout(TIMEOUT, behaviour.next()).await.unwrap().unwrap();
+    assert_matches!(
+        event,
+        ToSwarm::GenerateEvent(ToOtherBehaviourEvent::RequestKadQuery(_peer_id))
+    );
+
+    let event =
+        check_event_happens_after_given_duration(&mut behaviour, BOOTSTRAP_DIAL_SLEEP).await;
+    assert_matches!(
+        event,
+        ToSwarm::Dial{opts} if opts.get_peer_id() == Some(bootstrap_peer_ids[0])
+    );
+}
+
+#[tokio::test]
+async fn discovery_records_connected_peers_and_dials_them_after_restart() {
+    let directory = tempdir().unwrap();
+    let mut config = CONFIG;
+    config.peer_store_path = Some(directory.path().join("peer_store.json"));
+    let peer_id = PeerId::random();
+    let address: Multiaddr = "/ip4/10.0.0.1/tcp/10000".parse().unwrap();
+
+    let mut behaviour = Behaviour::new(config.clone(), vec![]);
+    behaviour.on_swarm_event(FromSwarm::ConnectionEstablished(ConnectionEstablished {
+        peer_id,
+        connection_id: ConnectionId::new_unchecked(0),
+        endpoint: &ConnectedPoint::Dialer {
+            address: address.clone(),
+            role_override: Endpoint::Dialer,
+        },
+        failed_addresses: &[],
+        other_established: 0,
+    }));
+    // The store is written in the background on the next query, and dropping the behaviour waits
+    // for the write.
+    let event = timeout(TIMEOUT, behaviour.next()).await.unwrap().unwrap();
+    assert_matches!(
+        event,
+        ToSwarm::GenerateEvent(ToOtherBehaviourEvent::RequestKadQuery(_peer_id))
+    );
+    drop(behaviour);
+
+    let mut restarted_behaviour = Behaviour::new(config, vec![]);
+    let event = timeout(TIMEOUT, restarted_behaviour.next()).await.unwrap().unwrap();
+    assert_matches!(
+        event,
+        ToSwarm::GenerateEvent(ToOtherBehaviourEvent::FoundListenAddresses {
+            peer_id: event_peer_id,
+            listen_addresses,
+        }) if event_peer_id == peer_id && listen_addresses == vec![address]
+    );
+    let event = timeout(TIMEOUT, restarted_behaviour.next()).await.unwrap().unwrap();
+    assert_matches!(
+        event,
+        ToSwarm::Dial{opts} if opts.get_peer_id() == Some(peer_id)
+    );
+}
@@ -12,6 +12,8 @@ use libp2p_swarm_test::SwarmExt;
 use starknet_api::core::ChainId;
 
 use super::{Behaviour, DiscoveryConfig};
+use crate::access_control::AccessControlConfig;
+use crate::gossipsub_impl::GossipsubConfig;
 use crate::mixed_behaviour;
 use crate::mixed_behaviour::{BridgedBehaviour, MixedBehaviour};
 use crate::peer_manager::PeerManagerConfig;
@@ -28,12 +30,14 @@ impl DiscoveryMixedBehaviour {
     pub fn new(key: Keypair, bootstrap_peer_multiaddr: Option<Multiaddr>) -> Self {
         let mixed_behaviour = MixedBehaviour::new(
             key,
-            bootstrap_peer_multiaddr,
+            bootstrap_peer_multiaddr.into_iter().collect(),
             Default::default(),
             ChainId::Mainnet,
             None,
             DiscoveryConfig::default(),
             PeerManagerConfig::default(),
+            AccessControlConfig::default(),
+            GossipsubConfig::default(),
         );
         Self {
             identify: mixed_behaviour.identify,
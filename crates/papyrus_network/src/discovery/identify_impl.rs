@@ -8,7 +8,11 @@ pub const IDENTIFY_PROTOCOL_VERSION: &str = "/staknet/identify/0.1.0-rc.0";
 
 #[derive(Debug)]
 pub enum IdentifyToOtherBehaviourEvent {
-    FoundListenAddresses { peer_id: PeerId, listen_addresses: Vec<Multiaddr> },
+    FoundListenAddresses {
+        peer_id: PeerId,
+        listen_addresses: Vec<Multiaddr>,
+        agent_version: String,
+    },
 }
 
 impl From<identify::Event> for mixed_behaviour::Event {
@@ -30,6 +34,7 @@ impl From<identify::Event> for mixed_behaviour::Event {
                         IdentifyToOtherBehaviourEvent::FoundListenAddresses {
                             peer_id,
                             listen_addresses,
+                            agent_version: info.agent_version,
                         },
                     ),
                 )
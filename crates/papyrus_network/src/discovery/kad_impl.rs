@@ -3,7 +3,7 @@ use tracing::info;
 
 use super::identify_impl::IdentifyToOtherBehaviourEvent;
 use crate::mixed_behaviour::BridgedBehaviour;
-use crate::{mixed_behaviour, peer_manager};
+use crate::{access_control, mixed_behaviour, peer_manager};
 
 #[derive(Debug)]
 pub enum KadToOtherBehaviourEvent {}
@@ -23,7 +23,11 @@ impl<TStore: kad::store::RecordStore + Send + 'static> BridgedBehaviour for kad:
                 self.get_closest_peers(*peer_id);
             }
             mixed_behaviour::ToOtherBehaviourEvent::Identify(
-                IdentifyToOtherBehaviourEvent::FoundListenAddresses { peer_id, listen_addresses },
+                IdentifyToOtherBehaviourEvent::FoundListenAddresses {
+                    peer_id,
+                    listen_addresses,
+                    ..
+                },
             )
             | mixed_behaviour::ToOtherBehaviourEvent::Discovery(
                 super::ToOtherBehaviourEvent::FoundListenAddresses { peer_id, listen_addresses },
@@ -38,6 +42,9 @@ impl<TStore: kad::store::RecordStore + Send + 'static> BridgedBehaviour for kad:
             }
             mixed_behaviour::ToOtherBehaviourEvent::PeerManager(
                 peer_manager::ToOtherBehaviourEvent::PeerBlacklisted { peer_id },
+            )
+            | mixed_behaviour::ToOtherBehaviourEvent::AccessControl(
+                access_control::ToOtherBehaviourEvent::PeerDisallowed { peer_id },
             ) => {
                 self.remove_peer(peer_id);
             }
@@ -4,14 +4,19 @@ mod discovery_test;
 mod flow_test;
 pub mod identify_impl;
 pub mod kad_impl;
+pub(crate) mod peer_store;
+#[cfg(test)]
+mod peer_store_test;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
 use std::task::{ready, Context, Poll};
 use std::time::Duration;
 
-use futures::future::{pending, select, BoxFuture, Either};
-use futures::{pin_mut, Future, FutureExt};
-use libp2p::core::Endpoint;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use identify_impl::IdentifyToOtherBehaviourEvent;
+use libp2p::core::{ConnectedPoint, Endpoint};
 use libp2p::swarm::behaviour::ConnectionEstablished;
 use libp2p::swarm::dial_opts::{DialOpts, PeerCondition};
 use libp2p::swarm::{
@@ -24,6 +29,7 @@ use libp2p::swarm::{
     DialFailure,
     FromSwarm,
     NetworkBehaviour,
+    THandlerInEvent,
     ToSwarm,
 };
 use libp2p::{Multiaddr, PeerId};
@@ -31,27 +37,40 @@ use papyrus_config::converters::{
     deserialize_milliseconds_to_duration,
     deserialize_seconds_to_duration,
 };
-use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_optional_param,
+    ser_param,
+    SerializeConfig,
+};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use peer_store::PeerStore;
 use serde::{Deserialize, Serialize};
 use tokio_retry::strategy::ExponentialBackoff;
 
-use crate::mixed_behaviour;
 use crate::mixed_behaviour::BridgedBehaviour;
+use crate::{access_control, mixed_behaviour, peer_manager};
 
 pub struct Behaviour {
     config: DiscoveryConfig,
-    bootstrap_peer_address: Multiaddr,
-    bootstrap_peer_id: PeerId,
-    is_dialing_to_bootstrap_peer: bool,
-    // This needs to be boxed to allow polling it from a &mut.
-    sleep_future_for_dialing_bootstrap_peer: Option<BoxFuture<'static, ()>>,
-    is_connected_to_bootstrap_peer: bool,
-    is_bootstrap_in_kad_routing_table: bool,
-    bootstrap_dial_retry_strategy: ExponentialBackoff,
+    bootstrap_peers: Vec<BootstrapPeer>,
+    peer_store: Option<PeerStore>,
+    // Events reporting the peers loaded from the peer store, and dialing them.
+    stored_peer_events: VecDeque<ToSwarm<ToOtherBehaviourEvent, THandlerInEvent<Behaviour>>>,
     query_sleep_future: Option<BoxFuture<'static, ()>>,
 }
 
+struct BootstrapPeer {
+    peer_id: PeerId,
+    address: Multiaddr,
+    is_dialing: bool,
+    // This needs to be boxed to allow polling it from a &mut.
+    sleep_future_for_dialing: Option<BoxFuture<'static, ()>>,
+    is_connected: bool,
+    is_in_kad_routing_table: bool,
+    dial_retry_strategy: ExponentialBackoff,
+}
+
 #[derive(Debug)]
 pub enum ToOtherBehaviourEvent {
     RequestKadQuery(PeerId),
@@ -84,38 +103,55 @@ impl NetworkBehaviour for Behaviour {
 
     fn on_swarm_event(&mut self, event: FromSwarm<'_>) {
         match event {
-            FromSwarm::DialFailure(DialFailure { peer_id: Some(peer_id), .. })
-                if peer_id == self.bootstrap_peer_id =>
-            {
-                self.is_dialing_to_bootstrap_peer = false;
+            FromSwarm::DialFailure(DialFailure { peer_id: Some(peer_id), .. }) => {
+                let Some(bootstrap_peer) = self.bootstrap_peer_mut(peer_id) else {
+                    return;
+                };
+                bootstrap_peer.is_dialing = false;
                 // For the case that the reason for failure is consistent (e.g the bootstrap peer
                 // is down), we sleep before redialing
-                self.sleep_future_for_dialing_bootstrap_peer = Some(
-                    tokio::time::sleep(self.bootstrap_dial_retry_strategy.next().expect(
+                bootstrap_peer.sleep_future_for_dialing = Some(
+                    tokio::time::sleep(bootstrap_peer.dial_retry_strategy.next().expect(
                         "Dial sleep strategy ended even though it's an infinite iterator.",
                     ))
                     .boxed(),
                 );
             }
-            FromSwarm::ConnectionEstablished(ConnectionEstablished { peer_id, .. })
-                if peer_id == self.bootstrap_peer_id =>
-            {
-                self.is_connected_to_bootstrap_peer = true;
-                self.is_dialing_to_bootstrap_peer = false;
-                self.bootstrap_dial_retry_strategy =
-                    self.config.bootstrap_dial_retry_config.strategy();
+            FromSwarm::ConnectionEstablished(ConnectionEstablished {
+                peer_id, endpoint, ..
+            }) => {
+                if let Some(peer_store) = self.peer_store.as_mut() {
+                    let dialed_address = match endpoint {
+                        ConnectedPoint::Dialer { address, .. } => Some(address),
+                        ConnectedPoint::Listener { .. } => None,
+                    };
+                    peer_store.record_seen(peer_id, dialed_address);
+                }
+                let retry_strategy = self.config.bootstrap_dial_retry_config.strategy();
+                let Some(bootstrap_peer) = self.bootstrap_peer_mut(peer_id) else {
+                    return;
+                };
+                bootstrap_peer.is_connected = true;
+                bootstrap_peer.is_dialing = false;
+                bootstrap_peer.dial_retry_strategy = retry_strategy;
             }
             FromSwarm::ConnectionClosed(ConnectionClosed {
                 peer_id,
-                remaining_established,
+                remaining_established: 0,
                 ..
-            }) if peer_id == self.bootstrap_peer_id && remaining_established == 0 => {
-                self.is_connected_to_bootstrap_peer = false;
-                self.is_dialing_to_bootstrap_peer = false;
-                self.is_bootstrap_in_kad_routing_table = false;
+            }) => {
+                if let Some(peer_store) = self.peer_store.as_mut() {
+                    peer_store.record_seen(peer_id, None);
+                }
+                let Some(bootstrap_peer) = self.bootstrap_peer_mut(peer_id) else {
+                    return;
+                };
+                bootstrap_peer.is_connected = false;
+                bootstrap_peer.is_dialing = false;
+                bootstrap_peer.is_in_kad_routing_table = false;
             }
             FromSwarm::AddressChange(AddressChange { peer_id, .. })
-                if peer_id == self.bootstrap_peer_id =>
+                if self.bootstrap_peer_mut(peer_id).is_some() =>
             {
                 todo!();
             }
@@ -136,65 +172,57 @@ impl NetworkBehaviour for Behaviour {
         cx: &mut Context<'_>,
     ) -> Poll<ToSwarm<Self::ToSwarm, <Self::ConnectionHandler as ConnectionHandler>::FromBehaviour>>
     {
-        if self.is_connected_to_bootstrap_peer && !self.is_bootstrap_in_kad_routing_table {
-            self.is_bootstrap_in_kad_routing_table = true;
-            return Poll::Ready(ToSwarm::GenerateEvent(
-                ToOtherBehaviourEvent::FoundListenAddresses {
-                    peer_id: self.bootstrap_peer_id,
-                    listen_addresses: vec![self.bootstrap_peer_address.clone()],
-                },
-            ));
+        for bootstrap_peer in &mut self.bootstrap_peers {
+            if bootstrap_peer.is_connected && !bootstrap_peer.is_in_kad_routing_table {
+                bootstrap_peer.is_in_kad_routing_table = true;
+                return Poll::Ready(ToSwarm::GenerateEvent(
+                    ToOtherBehaviourEvent::FoundListenAddresses {
+                        peer_id: bootstrap_peer.peer_id,
+                        listen_addresses: vec![bootstrap_peer.address.clone()],
+                    },
+                ));
+            }
         }
 
-        // Unpacking self so that we can create 2 futures that use different members of self
-        let Self {
-            is_dialing_to_bootstrap_peer,
-            is_connected_to_bootstrap_peer,
-            sleep_future_for_dialing_bootstrap_peer,
-            bootstrap_peer_id,
-            bootstrap_peer_address,
-            query_sleep_future,
-            config,
-            ..
-        } = self;
+        if let Some(event) = self.stored_peer_events.pop_front() {
+            return Poll::Ready(event);
+        }
 
-        let bootstrap_dial_future = async move {
-            if !(*is_dialing_to_bootstrap_peer) && !(*is_connected_to_bootstrap_peer) {
-                if let Some(sleep_future) = sleep_future_for_dialing_bootstrap_peer {
-                    sleep_future.await;
+        for bootstrap_peer in &mut self.bootstrap_peers {
+            // If we're already connected to the bootstrap peer there's nothing to do.
+            // TODO(Shahak): register a waker here and wake it when we receive an event that we've
+            // disconnected from the bootstrap peer.
+            if bootstrap_peer.is_dialing || bootstrap_peer.is_connected {
+                continue;
+            }
+            if let Some(sleep_future) = bootstrap_peer.sleep_future_for_dialing.as_mut() {
+                if sleep_future.poll_unpin(cx).is_pending() {
+                    continue;
                 }
-                *is_dialing_to_bootstrap_peer = true;
-                *sleep_future_for_dialing_bootstrap_peer = None;
-                return ToSwarm::Dial {
-                    opts: DialOpts::peer_id(*bootstrap_peer_id)
-                    .addresses(vec![bootstrap_peer_address.clone()])
+            }
+            bootstrap_peer.is_dialing = true;
+            bootstrap_peer.sleep_future_for_dialing = None;
+            return Poll::Ready(ToSwarm::Dial {
+                opts: DialOpts::peer_id(bootstrap_peer.peer_id)
+                    .addresses(vec![bootstrap_peer.address.clone()])
                     // The peer manager might also be dialing to the bootstrap node.
                     .condition(PeerCondition::DisconnectedAndNotDialing)
                     .build(),
-                };
-            }
-            // We're already connected to the bootstrap peer. Nothing to do
-            // TODO(Shahak): register a waker here and wake it when we receive an event that we've
-            // disconnected from the bootstrap peer.
-            pending().await
-        };
-        pin_mut!(bootstrap_dial_future);
-        let kad_future = async move {
-            if let Some(sleep_future) = query_sleep_future {
-                sleep_future.await;
-            }
-            *query_sleep_future = Some(tokio::time::sleep(config.heartbeat_interval).boxed());
-            ToSwarm::GenerateEvent(ToOtherBehaviourEvent::RequestKadQuery(
-                libp2p::identity::PeerId::random(),
-            ))
-        };
-        pin_mut!(kad_future);
+            });
+        }
 
-        // polling both futures together since each of them contains sleep.
-        let select_future = select(bootstrap_dial_future, kad_future);
-        pin_mut!(select_future);
-        let (Either::Left((event, _)) | Either::Right((event, _))) = ready!(select_future.poll(cx));
-        Poll::Ready(event)
+        if let Some(sleep_future) = self.query_sleep_future.as_mut() {
+            ready!(sleep_future.poll_unpin(cx));
+        }
+        self.query_sleep_future = Some(tokio::time::sleep(self.config.heartbeat_interval).boxed());
+        // The peer store is written at most once per heartbeat, to avoid writing it on every
+        // connection change.
+        if let Some(peer_store) = self.peer_store.as_mut() {
+            peer_store.persist_if_dirty();
+        }
+        Poll::Ready(ToSwarm::GenerateEvent(ToOtherBehaviourEvent::RequestKadQuery(
+            libp2p::identity::PeerId::random(),
+        )))
     }
 }
 
@@ -203,6 +231,7 @@ pub struct DiscoveryConfig {
     pub bootstrap_dial_retry_config: RetryConfig,
     #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
     pub heartbeat_interval: Duration,
+    pub peer_store_path: Option<PathBuf>,
 }
 
 impl Default for DiscoveryConfig {
@@ -210,6 +239,7 @@ impl Default for DiscoveryConfig {
         Self {
             bootstrap_dial_retry_config: RetryConfig::default(),
             heartbeat_interval: Duration::from_millis(100),
+            peer_store_path: None,
         }
     }
 }
@@ -226,6 +256,14 @@ impl SerializeConfig for DiscoveryConfig {
             self.bootstrap_dial_retry_config.dump(),
             "bootstrap_dial_retry_config",
         ));
+        dump.extend(ser_optional_param(
+            &self.peer_store_path,
+            PathBuf::from("./data/peer_store.json"),
+            "peer_store_path",
+            "A file to record the peers this node connected to in. The recorded peers are dialed \
+             on startup, in addition to the bootstrap peers.",
+            ParamPrivacyInput::Public,
+        ));
         dump
     }
 }
@@ -278,35 +316,60 @@ impl RetryConfig {
 }
 
 impl Behaviour {
-    // TODO(shahak): Add support to discovery from multiple bootstrap nodes.
     // TODO(shahak): Add support to multiple addresses for bootstrap node.
-    pub fn new(
-        config: DiscoveryConfig,
-        bootstrap_peer_id: PeerId,
-        bootstrap_peer_address: Multiaddr,
-    ) -> Self {
-        let bootstrap_dial_retry_strategy = config.bootstrap_dial_retry_config.strategy();
-        Self {
-            config,
-            bootstrap_peer_id,
-            bootstrap_peer_address,
-            is_dialing_to_bootstrap_peer: false,
-            sleep_future_for_dialing_bootstrap_peer: None,
-            is_connected_to_bootstrap_peer: false,
-            is_bootstrap_in_kad_routing_table: false,
-            bootstrap_dial_retry_strategy,
-            query_sleep_future: None,
-        }
+    pub fn new(config: DiscoveryConfig, bootstrap_peers: Vec<(PeerId, Multiaddr)>) -> Self {
+        let bootstrap_peers = bootstrap_peers
+            .into_iter()
+            .map(|(peer_id, address)| BootstrapPeer {
+                peer_id,
+                address,
+                is_dialing: false,
+                sleep_future_for_dialing: None,
+                is_connected: false,
+                is_in_kad_routing_table: false,
+                dial_retry_strategy: config.bootstrap_dial_retry_config.strategy(),
+            })
+            .collect::<Vec<_>>();
+        let peer_store = config.peer_store_path.clone().map(PeerStore::load);
+        let stored_peer_events = peer_store
+            .iter()
+            .flat_map(|peer_store| peer_store.peers())
+            .filter(|(peer_id, stored_peer)| {
+                !stored_peer.addresses.is_empty()
+                    && !bootstrap_peers
+                        .iter()
+                        .any(|bootstrap_peer| bootstrap_peer.peer_id == **peer_id)
+            })
+            .flat_map(|(peer_id, stored_peer)| {
+                [
+                    ToSwarm::GenerateEvent(ToOtherBehaviourEvent::FoundListenAddresses {
+                        peer_id: *peer_id,
+                        listen_addresses: stored_peer.addresses.clone(),
+                    }),
+                    ToSwarm::Dial {
+                        opts: DialOpts::peer_id(*peer_id)
+                            .addresses(stored_peer.addresses.clone())
+                            .condition(PeerCondition::DisconnectedAndNotDialing)
+                            .build(),
+                    },
+                ]
+            })
+            .collect();
+        Self { config, bootstrap_peers, peer_store, stored_peer_events, query_sleep_future: None }
+    }
+
+    fn bootstrap_peer_mut(&mut self, peer_id: PeerId) -> Option<&mut BootstrapPeer> {
+        self.bootstrap_peers.iter_mut().find(|bootstrap_peer| bootstrap_peer.peer_id == peer_id)
     }
 
     #[cfg(test)]
     pub fn bootstrap_peer_id(&self) -> PeerId {
-        self.bootstrap_peer_id
+        self.bootstrap_peers[0].peer_id
     }
 
     #[cfg(test)]
     pub fn bootstrap_peer_address(&self) -> &Multiaddr {
-        &self.bootstrap_peer_address
+        &self.bootstrap_peers[0].address
     }
 }
 
@@ -319,5 +382,25 @@ impl From<ToOtherBehaviourEvent> for mixed_behaviour::Event {
 }
 
 impl BridgedBehaviour for Behaviour {
-    fn on_other_behaviour_event(&mut self, _event: &mixed_behaviour::ToOtherBehaviourEvent) {}
+    fn on_other_behaviour_event(&mut self, event: &mixed_behaviour::ToOtherBehaviourEvent) {
+        let Some(peer_store) = self.peer_store.as_mut() else {
+            return;
+        };
+        match event {
+            mixed_behaviour::ToOtherBehaviourEvent::Identify(
+                IdentifyToOtherBehaviourEvent::FoundListenAddresses {
+                    peer_id,
+                    listen_addresses,
+                    ..
+                },
+            ) => peer_store.record_listen_addresses(*peer_id, listen_addresses),
+            mixed_behaviour::ToOtherBehaviourEvent::PeerManager(
+                peer_manager::ToOtherBehaviourEvent::PeerBlacklisted { peer_id },
+            )
+            | mixed_behaviour::ToOtherBehaviourEvent::AccessControl(
+                access_control::ToOtherBehaviourEvent::PeerDisallowed { peer_id },
+            ) => peer_store.remove(peer_id),
+            _ => {}
+        }
+    }
 }
@@ -0,0 +1,166 @@
+//! A disk-backed record of the peers this node managed to connect to, so that after a restart the
+//! node can reconnect to the network without relying only on the bootstrap peers.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+// Once the store is full, the peers that weren't seen for the longest time are evicted.
+pub(crate) const MAX_STORED_PEERS: usize = 1000;
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub(crate) struct StoredPeer {
+    pub addresses: Vec<Multiaddr>,
+    /// Seconds since the unix epoch.
+    pub last_seen: u64,
+}
+
+type StoredPeers = BTreeMap<PeerId, StoredPeer>;
+
+pub(crate) struct PeerStore {
+    peers: StoredPeers,
+    is_dirty: bool,
+    // The peers are written to disk by a background thread, so that persisting them never blocks
+    // the swarm. Both are taken when the store is dropped.
+    writer_sender: Option<mpsc::Sender<StoredPeers>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl PeerStore {
+    /// Loads the store from `path`. A missing or unreadable file results in an empty store.
+    pub fn load(path: PathBuf) -> Self {
+        let peers = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|error| {
+                warn!("Failed to parse the peer store at {path:?}, starting empty. Error: {error}");
+                BTreeMap::new()
+            }),
+            Err(error) => {
+                debug!("No peer store was loaded from {path:?}. Error: {error}");
+                BTreeMap::new()
+            }
+        };
+        let (writer_sender, writer_receiver) = mpsc::channel();
+        let writer = thread::spawn(move || write_peers(path, writer_receiver));
+        Self { peers, is_dirty: false, writer_sender: Some(writer_sender), writer: Some(writer) }
+    }
+
+    pub fn peers(&self) -> &StoredPeers {
+        &self.peers
+    }
+
+    /// Marks the peer as seen now, adding the given address to its known addresses.
+    pub fn record_seen(&mut self, peer_id: PeerId, address: Option<&Multiaddr>) {
+        let peer = self.peers.entry(peer_id).or_default();
+        peer.last_seen = now();
+        if let Some(address) = address {
+            if !peer.addresses.contains(address) {
+                peer.addresses.push(address.clone());
+            }
+        }
+        self.is_dirty = true;
+        self.evict_if_full();
+    }
+
+    /// Replaces the known addresses of a peer with the addresses it reported listening on.
+    pub fn record_listen_addresses(&mut self, peer_id: PeerId, listen_addresses: &[Multiaddr]) {
+        if listen_addresses.is_empty() {
+            return;
+        }
+        let peer = self.peers.entry(peer_id).or_default();
+        peer.last_seen = now();
+        peer.addresses = listen_addresses.to_vec();
+        self.is_dirty = true;
+        self.evict_if_full();
+    }
+
+    pub fn remove(&mut self, peer_id: &PeerId) {
+        if self.peers.remove(peer_id).is_some() {
+            self.is_dirty = true;
+        }
+    }
+
+    /// Writes the store to disk in the background if it changed since the last time it was
+    /// written.
+    pub fn persist_if_dirty(&mut self) {
+        if !self.is_dirty {
+            return;
+        }
+        let Some(writer_sender) = self.writer_sender.as_ref() else {
+            return;
+        };
+        if writer_sender.send(self.peers.clone()).is_err() {
+            warn!("The writer of the peer store stopped, the peers weren't written.");
+            return;
+        }
+        self.is_dirty = false;
+    }
+
+    fn evict_if_full(&mut self) {
+        while self.peers.len() > MAX_STORED_PEERS {
+            let Some(oldest_peer_id) = self
+                .peers
+                .iter()
+                .min_by_key(|(_, peer)| peer.last_seen)
+                .map(|(peer_id, _)| *peer_id)
+            else {
+                return;
+            };
+            self.peers.remove(&oldest_peer_id);
+        }
+    }
+}
+
+impl Drop for PeerStore {
+    // Flushes the pending changes and waits for them to be written, so that the peers seen since
+    // the last heartbeat aren't lost on shutdown.
+    fn drop(&mut self) {
+        self.persist_if_dirty();
+        self.writer_sender.take();
+        if let Some(writer) = self.writer.take() {
+            if writer.join().is_err() {
+                warn!("The writer of the peer store panicked.");
+            }
+        }
+    }
+}
+
+// Writes the peers received on the channel until the store is dropped. Only the latest of the
+// pending peers is written.
+fn write_peers(path: PathBuf, receiver: mpsc::Receiver<StoredPeers>) {
+    while let Ok(mut peers) = receiver.recv() {
+        while let Ok(newer_peers) = receiver.try_recv() {
+            peers = newer_peers;
+        }
+        if let Err(error) = write_atomically(&path, &peers) {
+            warn!("Failed to write the peer store to {path:?}. Error: {error}");
+        }
+    }
+}
+
+// Writes to a temporary file which is then renamed, so a crash never leaves a corrupted store.
+pub(crate) fn write_atomically<T: Serialize>(path: &Path, value: &T) -> std::io::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    let temp_path = path.with_extension("tmp");
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(&serde_json::to_vec(value)?)?;
+    file.sync_all()?;
+    fs::rename(temp_path, path)
+}
+
+/// Seconds since the unix epoch.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
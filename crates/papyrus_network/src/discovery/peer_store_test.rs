@@ -0,0 +1,102 @@
+use std::fs;
+
+use libp2p::{Multiaddr, PeerId};
+use tempfile::tempdir;
+
+use super::peer_store::{PeerStore, MAX_STORED_PEERS};
+
+fn address(port: u16) -> Multiaddr {
+    format!("/ip4/10.0.0.1/tcp/{port}").parse().unwrap()
+}
+
+#[test]
+fn recorded_peers_are_reloaded() {
+    let directory = tempdir().unwrap();
+    let path = directory.path().join("peer_store.json");
+    let dialed_peer = PeerId::random();
+    let identified_peer = PeerId::random();
+
+    let mut peer_store = PeerStore::load(path.clone());
+    assert!(peer_store.peers().is_empty());
+    peer_store.record_seen(dialed_peer, Some(&address(1)));
+    peer_store.record_seen(dialed_peer, Some(&address(1)));
+    peer_store.record_seen(identified_peer, None);
+    peer_store.record_listen_addresses(identified_peer, &[address(2), address(3)]);
+    peer_store.persist_if_dirty();
+    let expected_peers = peer_store.peers().clone();
+    // Dropping the store waits for the background writes.
+    drop(peer_store);
+
+    let reloaded_peer_store = PeerStore::load(path);
+    assert_eq!(reloaded_peer_store.peers(), &expected_peers);
+    assert_eq!(reloaded_peer_store.peers()[&dialed_peer].addresses, vec![address(1)]);
+    assert_eq!(
+        reloaded_peer_store.peers()[&identified_peer].addresses,
+        vec![address(2), address(3)]
+    );
+}
+
+#[test]
+fn removed_peer_is_not_reloaded() {
+    let directory = tempdir().unwrap();
+    let path = directory.path().join("peer_store.json");
+    let peer_id = PeerId::random();
+
+    let mut peer_store = PeerStore::load(path.clone());
+    peer_store.record_seen(peer_id, Some(&address(1)));
+    peer_store.persist_if_dirty();
+    peer_store.remove(&peer_id);
+    peer_store.persist_if_dirty();
+    drop(peer_store);
+
+    assert!(PeerStore::load(path).peers().is_empty());
+}
+
+#[test]
+fn store_is_written_only_when_changed() {
+    let directory = tempdir().unwrap();
+    let path = directory.path().join("peer_store.json");
+
+    let mut peer_store = PeerStore::load(path.clone());
+    peer_store.persist_if_dirty();
+    drop(peer_store);
+    assert!(!path.exists());
+
+    let mut peer_store = PeerStore::load(path.clone());
+    peer_store.record_seen(PeerId::random(), None);
+    peer_store.persist_if_dirty();
+    drop(peer_store);
+    assert!(path.exists());
+}
+
+#[test]
+fn pending_changes_are_written_on_drop() {
+    let directory = tempdir().unwrap();
+    let path = directory.path().join("peer_store.json");
+    let peer_id = PeerId::random();
+
+    let mut peer_store = PeerStore::load(path.clone());
+    peer_store.record_seen(peer_id, Some(&address(1)));
+    drop(peer_store);
+
+    assert_eq!(PeerStore::load(path).peers()[&peer_id].addresses, vec![address(1)]);
+}
+
+#[test]
+fn corrupted_store_is_loaded_empty() {
+    let directory = tempdir().unwrap();
+    let path = directory.path().join("peer_store.json");
+    fs::write(&path, "not a peer store").unwrap();
+
+    assert!(PeerStore::load(path).peers().is_empty());
+}
+
+#[test]
+fn store_evicts_when_full() {
+    let directory = tempdir().unwrap();
+    let mut peer_store = PeerStore::load(directory.path().join("peer_store.json"));
+    for _ in 0..MAX_STORED_PEERS + 1 {
+        peer_store.record_seen(PeerId::random(), None);
+    }
+    assert_eq!(peer_store.peers().len(), MAX_STORED_PEERS);
+}
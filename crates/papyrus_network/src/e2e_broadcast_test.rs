@@ -7,8 +7,9 @@ use libp2p::{Multiaddr, Swarm};
 use libp2p_swarm_test::SwarmExt;
 use starknet_api::core::ChainId;
 
+use crate::access_control::AccessControlConfig;
 use crate::discovery::DiscoveryConfig;
-use crate::gossipsub_impl::Topic;
+use crate::gossipsub_impl::{GossipsubConfig, Topic};
 use crate::mixed_behaviour::MixedBehaviour;
 use crate::network_manager::{BroadcastTopicClientTrait, GenericNetworkManager};
 use crate::peer_manager::PeerManagerConfig;
@@ -21,12 +22,14 @@ async fn create_swarm(bootstrap_peer_multiaddr: Option<Multiaddr>) -> Swarm<Mixe
     let mut swarm = Swarm::new_ephemeral(|keypair| {
         MixedBehaviour::new(
             keypair.clone(),
-            bootstrap_peer_multiaddr,
+            bootstrap_peer_multiaddr.into_iter().collect(),
             sqmr::Config::default(),
             ChainId::Mainnet,
             None,
             DiscoveryConfig::default(),
             PeerManagerConfig::default(),
+            AccessControlConfig::default(),
+            GossipsubConfig::default(),
         )
     });
     // Not using SwarmExt::listen because it panics if the swarm emits other events
@@ -49,7 +52,7 @@ async fn create_swarm(bootstrap_peer_multiaddr: Option<Multiaddr>) -> Swarm<Mixe
 fn create_network_manager(
     swarm: Swarm<MixedBehaviour>,
 ) -> GenericNetworkManager<Swarm<MixedBehaviour>> {
-    GenericNetworkManager::generic_new(swarm, None, None)
+    GenericNetworkManager::generic_new(swarm, None, None, None, Default::default())
 }
 
 const BUFFER_SIZE: usize = 100;
@@ -1,5 +1,10 @@
-use libp2p::gossipsub::TopicHash;
+use std::collections::BTreeMap;
+
+use libp2p::gossipsub::{MessageId, PeerScoreThresholds, TopicHash, TopicScoreParams};
 use libp2p::{gossipsub, PeerId};
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
 use tracing::error;
 
 use crate::mixed_behaviour;
@@ -11,16 +16,127 @@ pub type Topic = gossipsub::IdentTopic;
 #[cfg(not(test))]
 pub type Topic = gossipsub::Sha256Topic;
 
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct GossipsubConfig {
+    pub enable_peer_scoring: bool,
+    pub invalid_message_deliveries_weight: f64,
+    pub invalid_message_deliveries_decay: f64,
+    pub gossip_threshold: f64,
+    pub publish_threshold: f64,
+    pub graylist_threshold: f64,
+    pub rejected_message_misconduct_score: f64,
+}
+
+impl Default for GossipsubConfig {
+    fn default() -> Self {
+        Self {
+            enable_peer_scoring: true,
+            invalid_message_deliveries_weight: -10.0,
+            invalid_message_deliveries_decay: 0.5,
+            gossip_threshold: -10.0,
+            publish_threshold: -50.0,
+            graylist_threshold: -80.0,
+            rejected_message_misconduct_score: 0.1,
+        }
+    }
+}
+
+impl SerializeConfig for GossipsubConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "enable_peer_scoring",
+                &self.enable_peer_scoring,
+                "Whether gossipsub scores peers and stops gossiping with peers whose score is low.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "invalid_message_deliveries_weight",
+                &self.invalid_message_deliveries_weight,
+                "The weight of the messages a peer sent that were rejected by the topic's \
+                 validator in the peer's score. Should be negative.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "invalid_message_deliveries_decay",
+                &self.invalid_message_deliveries_decay,
+                "The factor by which the count of rejected messages decays every decay interval. \
+                 Should be between 0 and 1.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "gossip_threshold",
+                &self.gossip_threshold,
+                "Peers with a score below this threshold don't receive or send gossip.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "publish_threshold",
+                &self.publish_threshold,
+                "Messages published by this node aren't sent to peers with a score below this \
+                 threshold. Should be lower than gossip_threshold.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "graylist_threshold",
+                &self.graylist_threshold,
+                "All the messages from peers with a score below this threshold are ignored. \
+                 Should be lower than publish_threshold.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "rejected_message_misconduct_score",
+                &self.rejected_message_misconduct_score,
+                "The misconduct score a peer gets for each message it sent that was rejected by \
+                 the topic's validator. A peer whose misconduct score reaches 1 is blacklisted.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+impl GossipsubConfig {
+    pub(crate) fn peer_score_thresholds(&self) -> PeerScoreThresholds {
+        PeerScoreThresholds {
+            gossip_threshold: self.gossip_threshold,
+            publish_threshold: self.publish_threshold,
+            graylist_threshold: self.graylist_threshold,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn topic_score_params(&self) -> TopicScoreParams {
+        TopicScoreParams {
+            invalid_message_deliveries_weight: self.invalid_message_deliveries_weight,
+            invalid_message_deliveries_decay: self.invalid_message_deliveries_decay,
+            // Not scoring by the amount of messages delivered in the mesh, since the traffic of
+            // topics such as consensus votes is too low and honest peers would be penalized.
+            mesh_message_deliveries_weight: 0.0,
+            mesh_failure_penalty_weight: 0.0,
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ExternalEvent {
     #[allow(dead_code)]
-    Received { originated_peer_id: PeerId, message: Bytes, topic_hash: TopicHash },
+    Received {
+        message_id: MessageId,
+        originated_peer_id: PeerId,
+        // The peer that sent us the message, which may be different than the originator.
+        propagation_source: PeerId,
+        message: Bytes,
+        topic_hash: TopicHash,
+    },
 }
 
 impl From<gossipsub::Event> for mixed_behaviour::Event {
     fn from(event: gossipsub::Event) -> Self {
         match event {
             gossipsub::Event::Message {
+                propagation_source,
+                message_id,
                 message: gossipsub::Message { data, topic, source, .. },
                 ..
             } => {
@@ -35,7 +151,9 @@ impl From<gossipsub::Event> for mixed_behaviour::Event {
                 };
                 mixed_behaviour::Event::ExternalEvent(mixed_behaviour::ExternalEvent::GossipSub(
                     ExternalEvent::Received {
+                        message_id,
                         originated_peer_id,
+                        propagation_source,
                         message: data,
                         topic_hash: topic,
                     },
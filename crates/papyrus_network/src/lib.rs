@@ -2,6 +2,7 @@
 /// to the [`Starknet p2p specs`]
 ///
 /// [`Starknet p2p specs`]: https://github.com/starknet-io/starknet-p2p-specs/
+mod access_control;
 mod discovery;
 #[cfg(test)]
 mod e2e_broadcast_test;
@@ -9,15 +10,24 @@ pub mod gossipsub_impl;
 mod mixed_behaviour;
 pub mod network_manager;
 mod peer_manager;
+mod rate_limiter;
 mod sqmr;
 #[cfg(test)]
 mod test_utils;
+#[cfg(test)]
+mod transport_test;
 pub mod utils;
 
 use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
 use std::time::Duration;
 
+use access_control::AccessControlConfig;
 use discovery::DiscoveryConfig;
+use gossipsub_impl::GossipsubConfig;
+use libp2p::core::multiaddr::Protocol;
 use libp2p::Multiaddr;
 use papyrus_config::converters::{
     deserialize_optional_vec_u8,
@@ -27,13 +37,16 @@ use papyrus_config::converters::{
 use papyrus_config::dumping::{
     append_sub_config_name,
     ser_optional_param,
+    ser_optional_sub_config,
     ser_param,
     SerializeConfig,
 };
 use papyrus_config::validators::validate_vec_u256;
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use peer_manager::PeerManagerConfig;
-use serde::{Deserialize, Serialize};
+use rate_limiter::RateLimitConfig;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use starknet_api::core::ChainId;
 use validator::Validate;
 
@@ -41,10 +54,18 @@ use validator::Validate;
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Validate)]
 pub struct NetworkConfig {
     pub port: u16,
+    pub enable_quic: bool,
+    pub enable_ipv6: bool,
+    #[serde(deserialize_with = "deserialize_list", serialize_with = "serialize_list")]
+    pub additional_listen_multiaddrs: Vec<Multiaddr>,
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
     pub session_timeout: Duration,
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
     pub idle_connection_timeout: Duration,
+    #[serde(deserialize_with = "deserialize_list", serialize_with = "serialize_list")]
+    pub bootstrap_peer_multiaddrs: Vec<Multiaddr>,
+    /// Deprecated, use `bootstrap_peer_multiaddrs`. Kept so that configs written before multiple
+    /// bootstrap peers were supported still load.
     pub bootstrap_peer_multiaddr: Option<Multiaddr>,
     #[validate(custom = "validate_vec_u256")]
     #[serde(deserialize_with = "deserialize_optional_vec_u8")]
@@ -53,6 +74,9 @@ pub struct NetworkConfig {
     pub chain_id: ChainId,
     pub discovery_config: DiscoveryConfig,
     pub peer_manager_config: PeerManagerConfig,
+    pub access_control_config: AccessControlConfig,
+    pub rate_limit_config: Option<RateLimitConfig>,
+    pub gossipsub_config: GossipsubConfig,
 }
 
 impl SerializeConfig for NetworkConfig {
@@ -61,7 +85,29 @@ impl SerializeConfig for NetworkConfig {
             ser_param(
                 "port",
                 &self.port,
-                "The port that the node listens on for incoming tcp connections.",
+                "The port that the node listens on for incoming tcp connections, and for incoming \
+                 quic connections if quic is enabled.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "enable_quic",
+                &self.enable_quic,
+                "Whether to also listen for quic connections on the udp port equal to `port`. \
+                 Peers that don't listen on quic are still connected to over tcp.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "enable_ipv6",
+                &self.enable_ipv6,
+                "Whether to also listen on all the ipv6 interfaces, in addition to all the ipv4 \
+                 interfaces.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "additional_listen_multiaddrs",
+                &list_to_string(&self.additional_listen_multiaddrs),
+                "Space separated multiaddresses to listen on in addition to the ones derived from \
+                 `port`, `enable_quic` and `enable_ipv6`.",
                 ParamPrivacyInput::Public,
             ),
             ser_param(
@@ -84,11 +130,19 @@ impl SerializeConfig for NetworkConfig {
                 ParamPrivacyInput::Public,
             ),
         ]);
+        config.extend([ser_param(
+            "bootstrap_peer_multiaddrs",
+            &list_to_string(&self.bootstrap_peer_multiaddrs),
+            "Space separated multiaddresses of the peers to connect to on startup. Each of them \
+             should include the peer's id. For more info: https://docs.libp2p.io/concepts/fundamentals/peers/",
+            ParamPrivacyInput::Public,
+        )]);
         config.extend(ser_optional_param(
             &self.bootstrap_peer_multiaddr,
             Multiaddr::empty(),
             "bootstrap_peer_multiaddr",
-            "The multiaddress of the peer node. It should include the peer's id. For more info: https://docs.libp2p.io/concepts/fundamentals/peers/",
+            "Deprecated, use `bootstrap_peer_multiaddrs` instead. If set, this peer is added to \
+             them.",
             ParamPrivacyInput::Public,
         ));
         config.extend([ser_param(
@@ -110,6 +164,12 @@ impl SerializeConfig for NetworkConfig {
         config.extend(append_sub_config_name(self.discovery_config.dump(), "discovery_config"));
         config
             .extend(append_sub_config_name(self.peer_manager_config.dump(), "peer_manager_config"));
+        config.extend(append_sub_config_name(
+            self.access_control_config.dump(),
+            "access_control_config",
+        ));
+        config.extend(ser_optional_sub_config(&self.rate_limit_config, "rate_limit_config"));
+        config.extend(append_sub_config_name(self.gossipsub_config.dump(), "gossipsub_config"));
         config
     }
 }
@@ -118,14 +178,79 @@ impl Default for NetworkConfig {
     fn default() -> Self {
         Self {
             port: 10000,
+            enable_quic: false,
+            enable_ipv6: false,
+            additional_listen_multiaddrs: Vec::new(),
             session_timeout: Duration::from_secs(120),
             idle_connection_timeout: Duration::from_secs(120),
+            bootstrap_peer_multiaddrs: Vec::new(),
             bootstrap_peer_multiaddr: None,
             secret_key: None,
             advertised_multiaddr: None,
             chain_id: ChainId::Mainnet,
             discovery_config: DiscoveryConfig::default(),
             peer_manager_config: PeerManagerConfig::default(),
+            access_control_config: AccessControlConfig::default(),
+            rate_limit_config: None,
+            gossipsub_config: GossipsubConfig::default(),
         }
     }
 }
+
+impl NetworkConfig {
+    /// The multiaddresses the node listens on.
+    pub fn listen_multiaddrs(&self) -> Vec<Multiaddr> {
+        let mut ip_addresses = vec![IpAddr::V4(Ipv4Addr::UNSPECIFIED)];
+        if self.enable_ipv6 {
+            ip_addresses.push(IpAddr::V6(Ipv6Addr::UNSPECIFIED));
+        }
+        let mut listen_multiaddrs = Vec::new();
+        for ip_address in ip_addresses {
+            let ip_multiaddr = Multiaddr::from(ip_address);
+            listen_multiaddrs.push(ip_multiaddr.clone().with(Protocol::Tcp(self.port)));
+            if self.enable_quic {
+                listen_multiaddrs
+                    .push(ip_multiaddr.with(Protocol::Udp(self.port)).with(Protocol::QuicV1));
+            }
+        }
+        listen_multiaddrs.extend(self.additional_listen_multiaddrs.iter().cloned());
+        listen_multiaddrs
+    }
+
+    /// The peers to connect to on startup, including the one of the deprecated
+    /// `bootstrap_peer_multiaddr`.
+    pub fn bootstrap_peers(&self) -> Vec<Multiaddr> {
+        let mut bootstrap_peers = self.bootstrap_peer_multiaddrs.clone();
+        if let Some(bootstrap_peer_multiaddr) = &self.bootstrap_peer_multiaddr {
+            if !bootstrap_peers.contains(bootstrap_peer_multiaddr) {
+                bootstrap_peers.push(bootstrap_peer_multiaddr.clone());
+            }
+        }
+        bootstrap_peers
+    }
+}
+
+fn list_to_string<T: Display>(list: &[T]) -> String {
+    list.iter().map(T::to_string).collect::<Vec<_>>().join(" ")
+}
+
+fn serialize_list<T, S>(list: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    serializer.serialize_str(&list_to_string(list))
+}
+
+fn deserialize_list<'de, T, D>(de: D) -> Result<Vec<T>, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    String::deserialize(de)?
+        .split_whitespace()
+        .map(T::from_str)
+        .collect::<Result<_, _>>()
+        .map_err(D::Error::custom)
+}
@@ -8,11 +8,13 @@ use libp2p::swarm::NetworkBehaviour;
 use libp2p::{gossipsub, identify, kad, Multiaddr, PeerId, StreamProtocol};
 use starknet_api::core::ChainId;
 
+use crate::access_control::AccessControlConfig;
 use crate::discovery::identify_impl::{IdentifyToOtherBehaviourEvent, IDENTIFY_PROTOCOL_VERSION};
 use crate::discovery::kad_impl::KadToOtherBehaviourEvent;
 use crate::discovery::DiscoveryConfig;
+use crate::gossipsub_impl::GossipsubConfig;
 use crate::peer_manager::PeerManagerConfig;
-use crate::{discovery, gossipsub_impl, peer_manager, sqmr};
+use crate::{access_control, discovery, gossipsub_impl, peer_manager, sqmr};
 
 const ONE_MEGA: usize = 1 << 20;
 
@@ -20,6 +22,9 @@ const ONE_MEGA: usize = 1 << 20;
 #[derive(NetworkBehaviour)]
 #[behaviour(out_event = "Event")]
 pub struct MixedBehaviour {
+    // Must be the first behaviour so that connections of disallowed peers are denied before any
+    // other behaviour handles them.
+    pub access_control: access_control::Behaviour,
     pub peer_manager: peer_manager::PeerManager,
     pub discovery: Toggle<discovery::Behaviour>,
     pub identify: identify::Behaviour,
@@ -44,6 +49,7 @@ pub enum ExternalEvent {
 #[derive(Debug)]
 pub enum ToOtherBehaviourEvent {
     NoOp,
+    AccessControl(access_control::ToOtherBehaviourEvent),
     Identify(IdentifyToOtherBehaviourEvent),
     Kad(KadToOtherBehaviourEvent),
     Discovery(discovery::ToOtherBehaviourEvent),
@@ -57,15 +63,18 @@ pub trait BridgedBehaviour {
 
 impl MixedBehaviour {
     // TODO(Shahak): get config details from network manager config
-    /// Panics if bootstrap_peer_multiaddr doesn't have a peer id.
+    /// Panics if one of bootstrap_peer_multiaddrs doesn't have a peer id.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         keypair: Keypair,
-        bootstrap_peer_multiaddr: Option<Multiaddr>,
+        bootstrap_peer_multiaddrs: Vec<Multiaddr>,
         streamed_bytes_config: sqmr::Config,
         chain_id: ChainId,
         node_version: Option<String>,
         discovery_config: DiscoveryConfig,
         peer_manager_config: PeerManagerConfig,
+        access_control_config: AccessControlConfig,
+        gossipsub_config: GossipsubConfig,
     ) -> Self {
         let public_key = keypair.public();
         let local_peer_id = PeerId::from_public_key(&public_key);
@@ -75,18 +84,24 @@ impl MixedBehaviour {
                 .expect("Failed to create StreamProtocol from a string that starts with /"),
         ]);
         Self {
+            access_control: access_control::Behaviour::new(access_control_config),
             peer_manager: peer_manager::PeerManager::new(peer_manager_config),
-            discovery: bootstrap_peer_multiaddr
-                .map(|bootstrap_peer_multiaddr| {
-                    discovery::Behaviour::new(
-                        discovery_config,
-                        DialOpts::from(bootstrap_peer_multiaddr.clone())
+            // Discovery is needed only if there's someone to discover the network from.
+            discovery: (!bootstrap_peer_multiaddrs.is_empty()
+                || discovery_config.peer_store_path.is_some())
+            .then(|| {
+                let bootstrap_peers = bootstrap_peer_multiaddrs
+                    .into_iter()
+                    .map(|bootstrap_peer_multiaddr| {
+                        let peer_id = DialOpts::from(bootstrap_peer_multiaddr.clone())
                             .get_peer_id()
-                            .expect("bootstrap_peer_multiaddr doesn't have a peer id"),
-                        bootstrap_peer_multiaddr.clone(),
-                    )
-                })
-                .into(),
+                            .expect("bootstrap_peer_multiaddr doesn't have a peer id");
+                        (peer_id, bootstrap_peer_multiaddr)
+                    })
+                    .collect();
+                discovery::Behaviour::new(discovery_config, bootstrap_peers)
+            })
+            .into(),
             identify: match node_version {
                 Some(version) => identify::Behaviour::new(
                     identify::Config::new(IDENTIFY_PROTOCOL_VERSION.to_string(), public_key)
@@ -104,18 +119,33 @@ impl MixedBehaviour {
                 kademlia_config,
             ),
             sqmr: sqmr::Behaviour::new(streamed_bytes_config),
-            gossipsub: gossipsub::Behaviour::new(
-                gossipsub::MessageAuthenticity::Signed(keypair),
-                gossipsub::ConfigBuilder::default()
-                    .max_transmit_size(ONE_MEGA)
-                    .build()
-                    .expect("Failed to build gossipsub config"),
-            )
+            gossipsub: new_gossipsub_behaviour(keypair, &gossipsub_config),
+        }
+    }
+}
+
+fn new_gossipsub_behaviour(keypair: Keypair, config: &GossipsubConfig) -> gossipsub::Behaviour {
+    let mut gossipsub = gossipsub::Behaviour::new(
+        gossipsub::MessageAuthenticity::Signed(keypair),
+        gossipsub::ConfigBuilder::default()
+            .max_transmit_size(ONE_MEGA)
+            // Messages are forwarded only after the network manager validates them.
+            .validate_messages()
+            .build()
+            .expect("Failed to build gossipsub config"),
+    )
+    .unwrap_or_else(|err_string| {
+        panic!("Failed creating gossipsub behaviour due to the following error: {err_string}")
+    });
+    if config.enable_peer_scoring {
+        gossipsub
+            .with_peer_score(gossipsub::PeerScoreParams::default(), config.peer_score_thresholds())
             .unwrap_or_else(|err_string| {
                 panic!(
-                    "Failed creating gossipsub behaviour due to the following error: {err_string}"
+                    "Failed enabling gossipsub peer scoring due to the following error: \
+                     {err_string}"
                 )
-            }),
-        }
+            });
     }
+    gossipsub
 }
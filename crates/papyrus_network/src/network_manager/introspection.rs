@@ -0,0 +1,131 @@
+//! The state of a running network manager, as it's presented for debugging and administration.
+
+use std::collections::{BTreeMap, HashMap};
+
+use libp2p::swarm::ConnectionId;
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+
+use crate::peer_manager::PeerReputation;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct NetworkInfo {
+    pub local_peer_id: PeerId,
+    pub connected_peers: Vec<ConnectedPeerInfo>,
+    pub sqmr_sessions: Vec<SqmrSessionInfo>,
+    /// The peers in the gossipsub mesh of each subscribed topic.
+    pub gossipsub_mesh: BTreeMap<String, Vec<PeerId>>,
+    pub peer_reputations: Vec<PeerReputation>,
+    /// The traffic of each SQMR protocol and broadcast topic since the node started.
+    pub traffic: BTreeMap<String, Traffic>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ConnectedPeerInfo {
+    pub peer_id: PeerId,
+    /// The remote address of each connection to the peer.
+    pub addresses: Vec<Multiaddr>,
+    /// The agent version the peer reported through identify, if it did.
+    pub agent_version: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum SessionDirection {
+    Inbound,
+    Outbound,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SqmrSessionInfo {
+    pub session_id: usize,
+    pub direction: SessionDirection,
+    pub protocol: String,
+    /// None for outbound sessions that weren't assigned a peer yet.
+    pub peer_id: Option<PeerId>,
+}
+
+/// The number of payload bytes sent and received, excluding the framing and the encryption.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct Traffic {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct TrafficCounters(BTreeMap<String, Traffic>);
+
+impl TrafficCounters {
+    pub fn record_sent(&mut self, name: impl AsRef<str>, num_bytes: usize) {
+        let traffic = self.0.entry(name.as_ref().to_string()).or_default();
+        traffic.bytes_sent = traffic.bytes_sent.saturating_add(to_u64(num_bytes));
+    }
+
+    pub fn record_received(&mut self, name: impl AsRef<str>, num_bytes: usize) {
+        let traffic = self.0.entry(name.as_ref().to_string()).or_default();
+        traffic.bytes_received = traffic.bytes_received.saturating_add(to_u64(num_bytes));
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, Traffic> {
+        self.0.clone()
+    }
+}
+
+#[derive(Default)]
+struct ConnectedPeer {
+    connections: HashMap<ConnectionId, Multiaddr>,
+    agent_version: Option<String>,
+}
+
+/// Tracks the connections of each peer and the info it reported through identify.
+#[derive(Default)]
+pub(crate) struct ConnectedPeers(HashMap<PeerId, ConnectedPeer>);
+
+impl ConnectedPeers {
+    pub fn add_connection(
+        &mut self,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+        address: Multiaddr,
+    ) {
+        self.0.entry(peer_id).or_default().connections.insert(connection_id, address);
+    }
+
+    pub fn remove_connection(&mut self, peer_id: PeerId, connection_id: ConnectionId) {
+        if let Some(connected_peer) = self.0.get_mut(&peer_id) {
+            connected_peer.connections.remove(&connection_id);
+            if connected_peer.connections.is_empty() {
+                self.0.remove(&peer_id);
+            }
+        }
+    }
+
+    pub fn get_connection_id(&self, peer_id: &PeerId) -> Option<ConnectionId> {
+        self.0
+            .get(peer_id)
+            .and_then(|connected_peer| connected_peer.connections.keys().next().copied())
+    }
+
+    pub fn set_agent_version(&mut self, peer_id: PeerId, agent_version: String) {
+        if let Some(connected_peer) = self.0.get_mut(&peer_id) {
+            connected_peer.agent_version = Some(agent_version);
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<ConnectedPeerInfo> {
+        let mut connected_peers = self
+            .0
+            .iter()
+            .map(|(peer_id, connected_peer)| ConnectedPeerInfo {
+                peer_id: *peer_id,
+                addresses: connected_peer.connections.values().cloned().collect(),
+                agent_version: connected_peer.agent_version.clone(),
+            })
+            .collect::<Vec<_>>();
+        connected_peers.sort_by_key(|connected_peer| connected_peer.peer_id);
+        connected_peers
+    }
+}
+
+fn to_u64(num_bytes: usize) -> u64 {
+    u64::try_from(num_bytes).unwrap_or(u64::MAX)
+}
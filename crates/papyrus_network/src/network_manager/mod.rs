@@ -1,3 +1,4 @@
+mod introspection;
 pub mod metrics;
 mod swarm_trait;
 #[cfg(test)]
@@ -7,8 +8,8 @@ pub mod test_utils;
 
 use std::collections::HashMap;
 use std::pin::Pin;
-use std::str::FromStr;
 use std::task::{Context, Poll};
+use std::time::Instant;
 
 use async_trait::async_trait;
 use futures::channel::mpsc::{Receiver, SendError, Sender};
@@ -24,15 +25,28 @@ use libp2p::{noise, yamux, Multiaddr, PeerId, StreamProtocol, Swarm, SwarmBuilde
 use metrics::NetworkMetrics;
 use papyrus_network_types::network_types::{BroadcastedMessageMetadata, OpaquePeerId};
 use sqmr::Bytes;
-use tracing::{debug, error, trace, warn};
-
+use tracing::{debug, error, info, trace, warn};
+
+pub use self::introspection::{
+    ConnectedPeerInfo,
+    NetworkInfo,
+    SessionDirection,
+    SqmrSessionInfo,
+    Traffic,
+};
+use self::introspection::{ConnectedPeers, TrafficCounters};
 use self::swarm_trait::SwarmTrait;
-use crate::gossipsub_impl::Topic;
+pub use crate::access_control::AccessListUpdate;
+use crate::discovery::identify_impl::IdentifyToOtherBehaviourEvent;
+use crate::gossipsub_impl::{GossipsubConfig, Topic};
 use crate::mixed_behaviour::{self, BridgedBehaviour};
+pub use crate::peer_manager::PeerReputation;
+use crate::peer_manager::{ReputationModifier, MALICIOUS};
+use crate::rate_limiter::{RateLimitConfig, RateLimitViolation, RateLimiter};
 use crate::sqmr::behaviour::SessionError;
 use crate::sqmr::{self, InboundSessionId, OutboundSessionId, SessionId};
 use crate::utils::{is_localhost, StreamHashMap};
-use crate::{gossipsub_impl, NetworkConfig};
+use crate::{discovery, gossipsub_impl, NetworkConfig};
 
 #[derive(thiserror::Error, Debug)]
 pub enum NetworkError {
@@ -44,6 +58,45 @@ pub enum NetworkError {
 
 // TODO(Shahak): Understand whats the correct thing to do here.
 const MESSAGE_METADATA_BUFFER_SIZE: usize = 100000;
+const NETWORK_COMMANDS_BUFFER_SIZE: usize = 100;
+
+/// The result of validating a message received on a broadcast topic. Only accepted messages are
+/// passed on to the subscriber and forwarded to other peers, and the peers that sent rejected
+/// messages are penalized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TopicValidationResult {
+    Accept,
+    Ignore,
+    Reject,
+}
+
+/// Validates the messages of a broadcast topic before they're passed on to the subscriber.
+pub type TopicValidator<T> = Box<dyn Fn(&T) -> TopicValidationResult + Send>;
+
+type BroadcastedMessageValidator = Box<dyn Fn(&Bytes) -> TopicValidationResult + Send>;
+
+/// A command that changes the behaviour of a running network manager.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetworkCommand {
+    UpdateAccessList(AccessListUpdate),
+    DialPeer(Multiaddr),
+    DisconnectPeer(PeerId),
+}
+
+/// A request to a running network manager. Either a command, or a query that is answered on the
+/// given sender.
+#[derive(Debug)]
+pub enum NetworkRequest {
+    Command(NetworkCommand),
+    GetPeerReputations(oneshot::Sender<Vec<PeerReputation>>),
+    GetNetworkInfo(oneshot::Sender<NetworkInfo>),
+}
+
+impl From<NetworkCommand> for NetworkRequest {
+    fn from(command: NetworkCommand) -> Self {
+        Self::Command(command)
+    }
+}
 
 pub struct GenericNetworkManager<SwarmT: SwarmTrait> {
     swarm: SwarmT,
@@ -53,17 +106,27 @@ pub struct GenericNetworkManager<SwarmT: SwarmTrait> {
     sqmr_outbound_payload_receivers: StreamHashMap<StreamProtocol, SqmrClientReceiver>,
     sqmr_outbound_response_senders: HashMap<OutboundSessionId, ResponsesSender>,
     sqmr_outbound_report_receivers_awaiting_assignment: HashMap<OutboundSessionId, ReportReceiver>,
+    sqmr_inbound_session_to_peer_and_protocol: HashMap<InboundSessionId, (PeerId, StreamProtocol)>,
+    sqmr_outbound_session_to_protocol: HashMap<OutboundSessionId, StreamProtocol>,
+    sqmr_rate_limiter: RateLimiter<StreamProtocol>,
+    broadcast_rate_limiter: RateLimiter<TopicHash>,
     // Splitting the broadcast receivers from the broadcasted senders in order to poll all
     // receivers simultaneously.
     // Each receiver has a matching sender and vice versa (i.e the maps have the same keys).
     messages_to_broadcast_receivers: StreamHashMap<TopicHash, Receiver<Bytes>>,
     broadcasted_messages_senders: HashMap<TopicHash, Sender<(Bytes, BroadcastedMessageMetadata)>>,
+    broadcasted_message_validators: HashMap<TopicHash, BroadcastedMessageValidator>,
+    gossipsub_config: GossipsubConfig,
     reported_peer_receivers: FuturesUnordered<BoxFuture<'static, Option<PeerId>>>,
     advertised_multiaddr: Option<Multiaddr>,
     reported_peers_receiver: Receiver<PeerId>,
     reported_peers_sender: Sender<PeerId>,
     continue_propagation_sender: Sender<BroadcastedMessageMetadata>,
     continue_propagation_receiver: Receiver<BroadcastedMessageMetadata>,
+    network_request_sender: Sender<NetworkRequest>,
+    network_request_receiver: Receiver<NetworkRequest>,
+    connected_peers: ConnectedPeers,
+    traffic_counters: TrafficCounters,
     metrics: Option<NetworkMetrics>,
 }
 
@@ -84,11 +147,12 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
                         topic_hash,
                     );
                 }
-                Some(Some(peer_id)) = self.reported_peer_receivers.next() => self.swarm.report_peer_as_malicious(peer_id),
-                Some(peer_id) = self.reported_peers_receiver.next() => self.swarm.report_peer_as_malicious(peer_id),
+                Some(Some(peer_id)) = self.reported_peer_receivers.next() => self.report_peer_as_malicious(peer_id),
+                Some(peer_id) = self.reported_peers_receiver.next() => self.report_peer_as_malicious(peer_id),
                 Some(broadcasted_message_metadata) = self.continue_propagation_receiver.next() => {
                     self.swarm.continue_propagation(broadcasted_message_metadata);
                 }
+                Some(request) = self.network_request_receiver.next() => self.handle_network_request(request),
             }
         }
     }
@@ -99,6 +163,8 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
         mut swarm: SwarmT,
         advertised_multiaddr: Option<Multiaddr>,
         metrics: Option<NetworkMetrics>,
+        rate_limit_config: Option<RateLimitConfig>,
+        gossipsub_config: GossipsubConfig,
     ) -> Self {
         if let Some(metrics) = metrics.as_ref() {
             metrics.register();
@@ -112,6 +178,8 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
             futures::channel::mpsc::channel(MESSAGE_METADATA_BUFFER_SIZE);
         let (continue_propagation_sender, continue_propagation_receiver) =
             futures::channel::mpsc::channel(MESSAGE_METADATA_BUFFER_SIZE);
+        let (network_request_sender, network_request_receiver) =
+            futures::channel::mpsc::channel(NETWORK_COMMANDS_BUFFER_SIZE);
         Self {
             swarm,
             inbound_protocol_to_buffer_size: HashMap::new(),
@@ -120,14 +188,24 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
             sqmr_outbound_payload_receivers: StreamHashMap::new(HashMap::new()),
             sqmr_outbound_response_senders: HashMap::new(),
             sqmr_outbound_report_receivers_awaiting_assignment: HashMap::new(),
+            sqmr_inbound_session_to_peer_and_protocol: HashMap::new(),
+            sqmr_outbound_session_to_protocol: HashMap::new(),
+            sqmr_rate_limiter: RateLimiter::new(rate_limit_config.clone()),
+            broadcast_rate_limiter: RateLimiter::new(rate_limit_config),
             messages_to_broadcast_receivers: StreamHashMap::new(HashMap::new()),
             broadcasted_messages_senders: HashMap::new(),
+            broadcasted_message_validators: HashMap::new(),
+            gossipsub_config,
             reported_peer_receivers,
             advertised_multiaddr,
             reported_peers_receiver,
             reported_peers_sender,
             continue_propagation_sender,
             continue_propagation_receiver,
+            network_request_sender,
+            network_request_receiver,
+            connected_peers: ConnectedPeers::default(),
+            traffic_counters: TrafficCounters::default(),
             metrics,
         }
     }
@@ -211,9 +289,51 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
         T: TryFrom<Bytes> + 'static,
         Bytes: From<T>,
     {
-        self.swarm.subscribe_to_topic(&topic)?;
+        self.register_broadcast_topic_inner(topic, buffer_size, None)
+    }
+
+    /// Same as `register_broadcast_topic`, except that each received message is validated before
+    /// it's passed on to the subscriber and forwarded to other peers. Messages that can't be
+    /// parsed are rejected.
+    /// Panics if this topic is already subscribed.
+    pub fn register_broadcast_topic_with_validator<T>(
+        &mut self,
+        topic: Topic,
+        buffer_size: usize,
+        validator: TopicValidator<T>,
+    ) -> Result<BroadcastTopicChannels<T>, SubscriptionError>
+    where
+        T: TryFrom<Bytes> + 'static,
+        Bytes: From<T>,
+    {
+        let message_validator: BroadcastedMessageValidator =
+            Box::new(move |message| match T::try_from(message.clone()) {
+                Ok(message) => validator(&message),
+                Err(_) => TopicValidationResult::Reject,
+            });
+        self.register_broadcast_topic_inner(topic, buffer_size, Some(message_validator))
+    }
+
+    fn register_broadcast_topic_inner<T>(
+        &mut self,
+        topic: Topic,
+        buffer_size: usize,
+        message_validator: Option<BroadcastedMessageValidator>,
+    ) -> Result<BroadcastTopicChannels<T>, SubscriptionError>
+    where
+        T: TryFrom<Bytes> + 'static,
+        Bytes: From<T>,
+    {
+        let score_params = self
+            .gossipsub_config
+            .enable_peer_scoring
+            .then(|| self.gossipsub_config.topic_score_params());
+        self.swarm.subscribe_to_topic(&topic, score_params)?;
 
         let topic_hash = topic.hash();
+        if let Some(message_validator) = message_validator {
+            self.broadcasted_message_validators.insert(topic_hash.clone(), message_validator);
+        }
 
         let (messages_to_broadcast_sender, messages_to_broadcast_receiver) =
             futures::channel::mpsc::channel(buffer_size);
@@ -264,18 +384,97 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
         })
     }
 
+    /// Returns a sender for requests that change or inspect the network manager while it's
+    /// running.
+    pub fn get_network_request_sender(&self) -> Sender<NetworkRequest> {
+        self.network_request_sender.clone()
+    }
+
+    fn handle_network_request(&mut self, request: NetworkRequest) {
+        match request {
+            NetworkRequest::Command(command) => self.handle_network_command(command),
+            NetworkRequest::GetPeerReputations(sender) => {
+                if sender.send(self.swarm.peer_reputations()).is_err() {
+                    debug!("The requester of the peer reputations dropped before the response.");
+                }
+            }
+            NetworkRequest::GetNetworkInfo(sender) => {
+                if sender.send(self.network_info()).is_err() {
+                    debug!("The requester of the network info dropped before the response.");
+                }
+            }
+        }
+    }
+
+    fn handle_network_command(&mut self, command: NetworkCommand) {
+        match command {
+            NetworkCommand::UpdateAccessList(update) => self.swarm.update_access_list(update),
+            NetworkCommand::DialPeer(address) => {
+                info!("Dialing {address:?} by an admin command.");
+                if let Err(error) = self.swarm.dial(address.clone()) {
+                    warn!("Failed to dial {address:?}. Error: {error:?}");
+                }
+            }
+            NetworkCommand::DisconnectPeer(peer_id) => {
+                info!("Disconnecting from peer {peer_id:?} by an admin command.");
+                self.swarm.disconnect_peer(peer_id);
+            }
+        }
+    }
+
+    fn network_info(&mut self) -> NetworkInfo {
+        let inbound_sessions = self.sqmr_inbound_session_to_peer_and_protocol.iter().map(
+            |(inbound_session_id, (peer_id, protocol))| SqmrSessionInfo {
+                session_id: inbound_session_id.value,
+                direction: SessionDirection::Inbound,
+                protocol: protocol.to_string(),
+                peer_id: Some(*peer_id),
+            },
+        );
+        let outbound_sessions =
+            self.sqmr_outbound_session_to_protocol.iter().map(|(outbound_session_id, protocol)| {
+                SqmrSessionInfo {
+                    session_id: outbound_session_id.value,
+                    direction: SessionDirection::Outbound,
+                    protocol: protocol.to_string(),
+                    peer_id: self
+                        .swarm
+                        .get_peer_id_from_session_id((*outbound_session_id).into())
+                        .ok(),
+                }
+            });
+        let mut sqmr_sessions = inbound_sessions.chain(outbound_sessions).collect::<Vec<_>>();
+        sqmr_sessions.sort_by_key(|session| (session.direction, session.session_id));
+        NetworkInfo {
+            local_peer_id: self.swarm.get_local_peer_id(),
+            connected_peers: self.connected_peers.snapshot(),
+            sqmr_sessions,
+            gossipsub_mesh: self.swarm.gossipsub_mesh(),
+            peer_reputations: self.swarm.peer_reputations(),
+            traffic: self.traffic_counters.snapshot(),
+        }
+    }
+
     fn handle_swarm_event(
         &mut self,
         event: SwarmEvent<mixed_behaviour::Event>,
     ) -> Result<(), NetworkError> {
         match event {
-            SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+            SwarmEvent::ConnectionEstablished { peer_id, connection_id, endpoint, .. } => {
                 debug!("Connected to peer id: {peer_id:?}");
+                self.connected_peers.add_connection(
+                    peer_id,
+                    connection_id,
+                    endpoint.get_remote_address().clone(),
+                );
                 if let Some(metrics) = self.metrics.as_ref() {
                     metrics.num_connected_peers.increment(1);
                 }
             }
-            SwarmEvent::ConnectionClosed { peer_id, cause, .. } => {
+            SwarmEvent::ConnectionClosed {
+                peer_id, connection_id, cause, num_established, ..
+            } => {
+                self.connected_peers.remove_connection(peer_id, connection_id);
                 match cause {
                     Some(connection_error) => {
                         debug!("Connection to {peer_id:?} closed due to {connection_error:?}.")
@@ -285,6 +484,10 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
                 if let Some(metrics) = self.metrics.as_ref() {
                     metrics.num_connected_peers.decrement(1);
                 }
+                if num_established == 0 {
+                    self.sqmr_rate_limiter.remove_peer(&peer_id);
+                    self.broadcast_rate_limiter.remove_peer(&peer_id);
+                }
             }
             SwarmEvent::Behaviour(event) => {
                 self.handle_behaviour_event(event)?;
@@ -357,9 +560,27 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
 
     // TODO(shahak): Move this logic to mixed_behaviour.
     fn handle_to_other_behaviour_event(&mut self, event: mixed_behaviour::ToOtherBehaviourEvent) {
-        if let mixed_behaviour::ToOtherBehaviourEvent::NoOp = event {
-            return;
+        match &event {
+            mixed_behaviour::ToOtherBehaviourEvent::NoOp => return,
+            // Addresses of disallowed peers aren't passed on so that discovery won't advertise
+            // them to other peers.
+            mixed_behaviour::ToOtherBehaviourEvent::Identify(
+                IdentifyToOtherBehaviourEvent::FoundListenAddresses { peer_id, .. },
+            )
+            | mixed_behaviour::ToOtherBehaviourEvent::Discovery(
+                discovery::ToOtherBehaviourEvent::FoundListenAddresses { peer_id, .. },
+            ) if !self.swarm.is_peer_allowed(peer_id) => {
+                debug!("Ignoring the listen addresses of the disallowed peer {peer_id:?}");
+                return;
+            }
+            mixed_behaviour::ToOtherBehaviourEvent::Identify(
+                IdentifyToOtherBehaviourEvent::FoundListenAddresses {
+                    peer_id, agent_version, ..
+                },
+            ) => self.connected_peers.set_agent_version(*peer_id, agent_version.clone()),
+            _ => {}
         }
+        self.swarm.behaviour_mut().access_control.on_other_behaviour_event(&event);
         self.swarm.behaviour_mut().identify.on_other_behaviour_event(&event);
         self.swarm.behaviour_mut().kademlia.on_other_behaviour_event(&event);
         if let Some(discovery) = self.swarm.behaviour_mut().discovery.as_mut() {
@@ -408,6 +629,18 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
             "Network received new inbound query from peer {peer_id:?}. Sending query to server. \
              {inbound_session_id:?}"
         );
+        if let Err(violation) = self.sqmr_rate_limiter.start_session(
+            peer_id,
+            protocol_name.clone(),
+            query.len(),
+            Instant::now(),
+        ) {
+            self.handle_sqmr_rate_limit_violation(peer_id, inbound_session_id, violation);
+            return;
+        }
+        self.traffic_counters.record_received(&protocol_name, query.len());
+        self.sqmr_inbound_session_to_peer_and_protocol
+            .insert(inbound_session_id, (peer_id, protocol_name.clone()));
         let (report_sender, report_receiver) = oneshot::channel::<()>();
         self.handle_new_report_receiver(peer_id, report_receiver);
         let Some(query_sender) = self.sqmr_inbound_payload_senders.get_mut(&protocol_name) else {
@@ -463,6 +696,10 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
         {
             self.handle_new_report_receiver(peer_id, report_receiver)
         }
+        self.swarm.record_outbound_session_response(outbound_session_id);
+        if let Some(protocol) = self.sqmr_outbound_session_to_protocol.get(&outbound_session_id) {
+            self.traffic_counters.record_received(protocol, response.len());
+        }
         if let Some(response_sender) =
             self.sqmr_outbound_response_senders.get_mut(&outbound_session_id)
         {
@@ -484,8 +721,12 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
         error!("Session {session_id:?} failed on {error:?}");
         self.report_session_removed_to_metrics(session_id);
         // TODO(Shahak): Handle reputation and retry.
+        if let SessionId::InboundSessionId(inbound_session_id) = session_id {
+            self.end_inbound_session_rate_limit(inbound_session_id);
+        }
         if let SessionId::OutboundSessionId(outbound_session_id) = session_id {
             self.sqmr_outbound_response_senders.remove(&outbound_session_id);
+            self.sqmr_outbound_session_to_protocol.remove(&outbound_session_id);
             if let Some(_report_receiver) =
                 self.sqmr_outbound_report_receivers_awaiting_assignment.remove(&outbound_session_id)
             {
@@ -500,8 +741,12 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
     fn handle_sqmr_event_session_finished_successfully(&mut self, session_id: SessionId) {
         debug!("Session completed successfully. {session_id:?}");
         self.report_session_removed_to_metrics(session_id);
+        if let SessionId::InboundSessionId(inbound_session_id) = session_id {
+            self.end_inbound_session_rate_limit(inbound_session_id);
+        }
         if let SessionId::OutboundSessionId(outbound_session_id) = session_id {
             self.sqmr_outbound_response_senders.remove(&outbound_session_id);
+            self.sqmr_outbound_session_to_protocol.remove(&outbound_session_id);
             if let Some(_report_receiver) =
                 self.sqmr_outbound_report_receivers_awaiting_assignment.remove(&outbound_session_id)
             {
@@ -509,6 +754,8 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
                     "Outbound session finished with no messages in it. Ignoring incoming reports \
                      for the session."
                 );
+            } else {
+                self.swarm.record_outbound_session_success(outbound_session_id);
             }
         }
     }
@@ -522,9 +769,62 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
         {
             broadcast_metrics.num_received_broadcast_messages.increment(1);
         }
-        let gossipsub_impl::ExternalEvent::Received { originated_peer_id, message, topic_hash } =
-            event;
+        let gossipsub_impl::ExternalEvent::Received {
+            message_id,
+            originated_peer_id,
+            propagation_source,
+            message,
+            topic_hash,
+        } = event;
         trace!("Received broadcast message with topic hash: {topic_hash:?}");
+        self.traffic_counters.record_received(topic_hash.as_str(), message.len());
+        if let Err(violation) = self.broadcast_rate_limiter.receive_message(
+            propagation_source,
+            topic_hash.clone(),
+            message.len(),
+            Instant::now(),
+        ) {
+            warn!(
+                "Peer {propagation_source:?} exceeded the rate limit of the topic with hash \
+                 {topic_hash:?}. {violation} Dropping its message."
+            );
+            self.swarm.report_message_validation_result(
+                &message_id,
+                &propagation_source,
+                TopicValidationResult::Ignore,
+            );
+            self.swarm.report_peer(propagation_source, ReputationModifier::Unstable);
+            return Ok(());
+        }
+        let validation_result = self
+            .broadcasted_message_validators
+            .get(&topic_hash)
+            .map_or(TopicValidationResult::Accept, |validator| validator(&message));
+        self.swarm.report_message_validation_result(
+            &message_id,
+            &propagation_source,
+            validation_result,
+        );
+        match validation_result {
+            TopicValidationResult::Accept => {}
+            TopicValidationResult::Ignore => {
+                debug!("Ignoring a message on the topic with hash {topic_hash:?}.");
+                return Ok(());
+            }
+            TopicValidationResult::Reject => {
+                warn!(
+                    "Peer {propagation_source:?} sent a message that was rejected on the topic \
+                     with hash {topic_hash:?}."
+                );
+                self.swarm.report_peer(
+                    propagation_source,
+                    ReputationModifier::Misconduct {
+                        misconduct_score: self.gossipsub_config.rejected_message_misconduct_score,
+                    },
+                );
+                return Ok(());
+            }
+        }
         let broadcasted_message_metadata = BroadcastedMessageMetadata {
             originator_id: OpaquePeerId::private_new(originated_peer_id),
             encoded_message_length: message.len(),
@@ -552,6 +852,29 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
         let (inbound_session_id, maybe_response) = res;
         match maybe_response {
             Some(response) => {
+                if let Some((peer_id, protocol)) =
+                    self.sqmr_inbound_session_to_peer_and_protocol.get(&inbound_session_id).cloned()
+                {
+                    if let Err(violation) = self.sqmr_rate_limiter.send_response(
+                        peer_id,
+                        protocol.clone(),
+                        response.len(),
+                        Instant::now(),
+                    ) {
+                        // The session is dropped without an event, so it's removed here. Dropping
+                        // the responses receiver stops the server from sending more responses.
+                        self.sqmr_inbound_response_receivers.remove(&inbound_session_id);
+                        self.report_session_removed_to_metrics(inbound_session_id.into());
+                        self.end_inbound_session_rate_limit(inbound_session_id);
+                        self.handle_sqmr_rate_limit_violation(
+                            peer_id,
+                            inbound_session_id,
+                            violation,
+                        );
+                        return;
+                    }
+                    self.traffic_counters.record_sent(&protocol, response.len());
+                }
                 trace!(
                     "Received response from server. Sending response to peer. \
                      {inbound_session_id:?}"
@@ -584,8 +907,22 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
         protocol: StreamProtocol,
         client_payload: SqmrClientPayload,
     ) {
-        let SqmrClientPayload { query, report_receiver, responses_sender } = client_payload;
-        let outbound_session_id = self.swarm.send_query(query, protocol.clone());
+        let SqmrClientPayload { query, report_receiver, responses_sender, peer_id } =
+            client_payload;
+        let query_len = query.len();
+        let outbound_session_id = match peer_id {
+            None => self.swarm.send_query(query, protocol.clone()),
+            Some(peer_id) => {
+                let Some(connection_id) = self.connected_peers.get_connection_id(&peer_id) else {
+                    // Dropping the responses sender ends the responses stream of the query.
+                    debug!("Not connected to {peer_id:?}. Dropping the query that was sent to it.");
+                    return;
+                };
+                self.swarm.send_query_to_peer(query, protocol.clone(), peer_id, connection_id)
+            }
+        };
+        self.traffic_counters.record_sent(&protocol, query_len);
+        self.sqmr_outbound_session_to_protocol.insert(outbound_session_id, protocol);
         if let Some(sqmr_metrics) =
             self.metrics.as_ref().and_then(|metrics| metrics.sqmr_metrics.as_ref())
         {
@@ -603,6 +940,7 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
             broadcast_metrics.num_sent_broadcast_messages.increment(1);
         }
         trace!("Sending broadcast message with topic hash: {topic_hash:?}");
+        self.traffic_counters.record_sent(topic_hash.as_str(), message.len());
         self.swarm.broadcast_message(message, topic_hash);
     }
 
@@ -624,6 +962,35 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
             }
         }
     }
+    fn handle_sqmr_rate_limit_violation(
+        &mut self,
+        peer_id: PeerId,
+        inbound_session_id: InboundSessionId,
+        violation: RateLimitViolation,
+    ) {
+        warn!(
+            "Peer {peer_id:?} exceeded the rate limit. {violation} Dropping session \
+             {inbound_session_id:?}"
+        );
+        self.swarm.report_peer(peer_id, ReputationModifier::Unstable);
+        self.swarm.drop_inbound_session(inbound_session_id).unwrap_or_else(|e| {
+            error!("Failed to drop session {inbound_session_id:?}. Error: {e:?}");
+        });
+    }
+
+    fn end_inbound_session_rate_limit(&mut self, inbound_session_id: InboundSessionId) {
+        if let Some((peer_id, protocol)) =
+            self.sqmr_inbound_session_to_peer_and_protocol.remove(&inbound_session_id)
+        {
+            self.sqmr_rate_limiter.end_session(peer_id, protocol);
+        }
+    }
+
+    fn report_peer_as_malicious(&mut self, peer_id: PeerId) {
+        self.swarm
+            .report_peer(peer_id, ReputationModifier::Misconduct { misconduct_score: MALICIOUS });
+    }
+
     fn handle_new_report_receiver(&self, peer_id: PeerId, report_receiver: oneshot::Receiver<()>) {
         self.reported_peer_receivers.push(
             report_receiver
@@ -664,23 +1031,22 @@ impl NetworkManager {
         node_version: Option<String>,
         metrics: Option<NetworkMetrics>,
     ) -> Self {
+        let listen_multiaddrs = config.listen_multiaddrs();
+        let bootstrap_peer_multiaddrs = config.bootstrap_peers();
         let NetworkConfig {
-            port,
             session_timeout,
             idle_connection_timeout,
-            bootstrap_peer_multiaddr,
             advertised_multiaddr,
             secret_key,
             chain_id,
             discovery_config,
             peer_manager_config,
+            access_control_config,
+            rate_limit_config,
+            gossipsub_config,
+            ..
         } = config;
-
-        // TODO(shahak): Add quic transport.
-        let listen_address_str = format!("/ip4/0.0.0.0/tcp/{port}");
-        let listen_address = Multiaddr::from_str(&listen_address_str)
-            .unwrap_or_else(|_| panic!("Unable to parse address {}", listen_address_str));
-        debug!("Creating swarm with listen address: {:?}", listen_address);
+        debug!("Creating swarm with listen addresses: {:?}", listen_multiaddrs);
 
         let key_pair = match secret_key {
             Some(secret_key) => {
@@ -692,33 +1058,37 @@ impl NetworkManager {
         .with_tokio()
         .with_tcp(Default::default(), noise::Config::new, yamux::Config::default)
         .expect("Error building TCP transport")
+        // The quic transport is always available for dialing, and only listened on if configured.
+        .with_quic()
         .with_dns()
         .expect("Error building DNS transport")
-        // TODO(Shahak): quic transpot does not work (failure appears in the command line when running in debug mode)
-        // .with_quic()
         .with_behaviour(|key| mixed_behaviour::MixedBehaviour::new(
                 key.clone(),
-                bootstrap_peer_multiaddr.clone(),
+                bootstrap_peer_multiaddrs.clone(),
                 sqmr::Config { session_timeout },
                 chain_id,
                 node_version,
                 discovery_config,
                 peer_manager_config,
+                access_control_config,
+                gossipsub_config.clone(),
             ))
         .expect("Error while building the swarm")
         .with_swarm_config(|cfg| cfg.with_idle_connection_timeout(idle_connection_timeout))
         .build();
 
-        swarm
-            .listen_on(listen_address.clone())
-            .unwrap_or_else(|_| panic!("Error while binding to {}", listen_address));
+        for listen_multiaddr in listen_multiaddrs {
+            swarm.listen_on(listen_multiaddr.clone()).unwrap_or_else(|error| {
+                panic!("Error while binding to {listen_multiaddr}: {error:?}")
+            });
+        }
 
         let advertised_multiaddr = advertised_multiaddr.map(|address| {
             address
                 .with_p2p(*swarm.local_peer_id())
                 .expect("advertised_multiaddr has a peer id different than the local peer id")
         });
-        Self::generic_new(swarm, advertised_multiaddr, metrics)
+        Self::generic_new(swarm, advertised_multiaddr, metrics, rate_limit_config, gossipsub_config)
     }
 
     pub fn get_local_peer_id(&self) -> String {
@@ -787,6 +1157,24 @@ where
     pub async fn send_new_query(
         &mut self,
         query: Query,
+    ) -> Result<ClientResponsesManager<Response>, SendError> {
+        self.send_query_inner(query, None).await
+    }
+
+    /// Sends the query to the given peer instead of a peer chosen by the peer manager. If we
+    /// aren't connected to the peer, the responses stream ends without any response.
+    pub async fn send_new_query_to_peer(
+        &mut self,
+        query: Query,
+        peer_id: OpaquePeerId,
+    ) -> Result<ClientResponsesManager<Response>, SendError> {
+        self.send_query_inner(query, Some(peer_id.private_get_peer_id())).await
+    }
+
+    async fn send_query_inner(
+        &mut self,
+        query: Query,
+        peer_id: Option<PeerId>,
     ) -> Result<ClientResponsesManager<Response>, SendError> {
         let (report_sender, report_receiver) = oneshot::channel::<()>();
         let (responses_sender, responses_receiver) =
@@ -795,7 +1183,7 @@ where
         let query = Bytes::from(query);
         let responses_sender =
             Box::new(responses_sender.with(|response| ready(Ok(Response::try_from(response)))));
-        let payload = SqmrClientPayload { query, report_receiver, responses_sender };
+        let payload = SqmrClientPayload { query, report_receiver, responses_sender, peer_id };
         self.sender.send(payload).await?;
         Ok(ClientResponsesManager { report_sender, responses_receiver })
     }
@@ -830,6 +1218,7 @@ pub struct SqmrClientPayload {
     query: Bytes,
     report_receiver: ReportReceiver,
     responses_sender: ResponsesSender,
+    peer_id: Option<PeerId>,
 }
 
 pub struct SqmrServerReceiver<Query, Response>
@@ -1,14 +1,22 @@
+use std::collections::BTreeMap;
+
 use futures::stream::Stream;
-use libp2p::gossipsub::{SubscriptionError, TopicHash};
+use libp2p::gossipsub::{
+    MessageAcceptance,
+    MessageId,
+    SubscriptionError,
+    TopicHash,
+    TopicScoreParams,
+};
 use libp2p::swarm::dial_opts::DialOpts;
-use libp2p::swarm::{DialError, NetworkBehaviour, SwarmEvent};
+use libp2p::swarm::{ConnectionId, DialError, NetworkBehaviour, SwarmEvent};
 use libp2p::{Multiaddr, PeerId, StreamProtocol, Swarm};
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
-use super::BroadcastedMessageMetadata;
+use super::{AccessListUpdate, BroadcastedMessageMetadata, TopicValidationResult};
 use crate::gossipsub_impl::Topic;
 use crate::mixed_behaviour;
-use crate::peer_manager::{ReputationModifier, MALICIOUS};
+use crate::peer_manager::{PeerReputation, ReputationModifier};
 use crate::sqmr::behaviour::SessionIdNotFoundError;
 use crate::sqmr::{Bytes, InboundSessionId, OutboundSessionId, SessionId};
 
@@ -23,8 +31,18 @@ pub trait SwarmTrait: Stream<Item = Event> + Unpin {
 
     fn send_query(&mut self, query: Vec<u8>, protocol: StreamProtocol) -> OutboundSessionId;
 
+    fn send_query_to_peer(
+        &mut self,
+        query: Vec<u8>,
+        protocol: StreamProtocol,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+    ) -> OutboundSessionId;
+
     fn dial(&mut self, peer_multiaddr: Multiaddr) -> Result<(), DialError>;
 
+    fn disconnect_peer(&mut self, peer_id: PeerId);
+
     fn close_inbound_session(
         &mut self,
         session_id: InboundSessionId,
@@ -39,16 +57,47 @@ pub trait SwarmTrait: Stream<Item = Event> + Unpin {
 
     fn add_external_address(&mut self, address: Multiaddr);
 
-    fn subscribe_to_topic(&mut self, topic: &Topic) -> Result<(), SubscriptionError>;
+    /// If score params are given, messages on this topic affect the score of the peers.
+    fn subscribe_to_topic(
+        &mut self,
+        topic: &Topic,
+        score_params: Option<TopicScoreParams>,
+    ) -> Result<(), SubscriptionError>;
 
     fn broadcast_message(&mut self, message: Bytes, topic_hash: TopicHash);
 
-    // TODO(Shahak): change this to report_peer and add an argument for the score.
-    fn report_peer_as_malicious(&mut self, peer_id: PeerId);
+    fn report_message_validation_result(
+        &mut self,
+        message_id: &MessageId,
+        propagation_source: &PeerId,
+        result: TopicValidationResult,
+    );
+
+    fn report_peer(&mut self, peer_id: PeerId, reputation_modifier: ReputationModifier);
+
+    fn record_outbound_session_response(&mut self, outbound_session_id: OutboundSessionId);
+
+    fn record_outbound_session_success(&mut self, outbound_session_id: OutboundSessionId);
+
+    fn peer_reputations(&mut self) -> Vec<PeerReputation>;
+
+    fn drop_inbound_session(
+        &mut self,
+        session_id: InboundSessionId,
+    ) -> Result<(), SessionIdNotFoundError>;
 
     fn add_new_supported_inbound_protocol(&mut self, protocol_name: StreamProtocol);
 
     fn continue_propagation(&mut self, message_metadata: BroadcastedMessageMetadata);
+
+    fn update_access_list(&mut self, update: AccessListUpdate);
+
+    fn is_peer_allowed(&self, peer_id: &PeerId) -> bool;
+
+    fn get_local_peer_id(&self) -> PeerId;
+
+    /// Returns the peers in the gossipsub mesh of each subscribed topic.
+    fn gossipsub_mesh(&self) -> BTreeMap<String, Vec<PeerId>>;
 }
 
 impl SwarmTrait for Swarm<mixed_behaviour::MixedBehaviour> {
@@ -64,10 +113,26 @@ impl SwarmTrait for Swarm<mixed_behaviour::MixedBehaviour> {
         self.behaviour_mut().sqmr.start_query(query, protocol)
     }
 
+    fn send_query_to_peer(
+        &mut self,
+        query: Vec<u8>,
+        protocol: StreamProtocol,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+    ) -> OutboundSessionId {
+        self.behaviour_mut().sqmr.start_query_with_peer(query, protocol, peer_id, connection_id)
+    }
+
     fn dial(&mut self, peer_multiaddr: Multiaddr) -> Result<(), DialError> {
         self.dial(DialOpts::from(peer_multiaddr))
     }
 
+    fn disconnect_peer(&mut self, peer_id: PeerId) {
+        if self.disconnect_peer_id(peer_id).is_err() {
+            debug!("Tried to disconnect from {peer_id:?} which isn't connected.");
+        }
+    }
+
     fn close_inbound_session(
         &mut self,
         session_id: InboundSessionId,
@@ -94,8 +159,20 @@ impl SwarmTrait for Swarm<mixed_behaviour::MixedBehaviour> {
         self.add_external_address(address);
     }
 
-    fn subscribe_to_topic(&mut self, topic: &Topic) -> Result<(), SubscriptionError> {
-        self.behaviour_mut().gossipsub.subscribe(topic).map(|_| ())
+    fn subscribe_to_topic(
+        &mut self,
+        topic: &Topic,
+        score_params: Option<TopicScoreParams>,
+    ) -> Result<(), SubscriptionError> {
+        self.behaviour_mut().gossipsub.subscribe(topic)?;
+        if let Some(score_params) = score_params {
+            if let Err(err) =
+                self.behaviour_mut().gossipsub.set_topic_params(topic.clone(), score_params)
+            {
+                warn!("Failed to set the score params of topic {topic}: {err}");
+            }
+        }
+        Ok(())
     }
 
     fn broadcast_message(&mut self, message: Bytes, topic_hash: TopicHash) {
@@ -110,11 +187,47 @@ impl SwarmTrait for Swarm<mixed_behaviour::MixedBehaviour> {
         }
     }
 
-    fn report_peer_as_malicious(&mut self, peer_id: PeerId) {
-        let _ = self
-            .behaviour_mut()
-            .peer_manager
-            .report_peer(peer_id, ReputationModifier::Misconduct { misconduct_score: MALICIOUS });
+    fn report_message_validation_result(
+        &mut self,
+        message_id: &MessageId,
+        propagation_source: &PeerId,
+        result: TopicValidationResult,
+    ) {
+        let acceptance = match result {
+            TopicValidationResult::Accept => MessageAcceptance::Accept,
+            TopicValidationResult::Ignore => MessageAcceptance::Ignore,
+            TopicValidationResult::Reject => MessageAcceptance::Reject,
+        };
+        if let Err(err) = self.behaviour_mut().gossipsub.report_message_validation_result(
+            message_id,
+            propagation_source,
+            acceptance,
+        ) {
+            warn!("Failed to forward the validated message {message_id:?}: {err:?}");
+        }
+    }
+
+    fn report_peer(&mut self, peer_id: PeerId, reputation_modifier: ReputationModifier) {
+        let _ = self.behaviour_mut().peer_manager.report_peer(peer_id, reputation_modifier);
+    }
+
+    fn record_outbound_session_response(&mut self, outbound_session_id: OutboundSessionId) {
+        self.behaviour_mut().peer_manager.record_session_response(outbound_session_id);
+    }
+
+    fn record_outbound_session_success(&mut self, outbound_session_id: OutboundSessionId) {
+        self.behaviour_mut().peer_manager.record_session_success(outbound_session_id);
+    }
+
+    fn peer_reputations(&mut self) -> Vec<PeerReputation> {
+        self.behaviour_mut().peer_manager.peer_reputations()
+    }
+
+    fn drop_inbound_session(
+        &mut self,
+        session_id: InboundSessionId,
+    ) -> Result<(), SessionIdNotFoundError> {
+        self.behaviour_mut().sqmr.drop_session(session_id.into())
     }
 
     fn add_new_supported_inbound_protocol(&mut self, protocol: StreamProtocol) {
@@ -123,4 +236,26 @@ impl SwarmTrait for Swarm<mixed_behaviour::MixedBehaviour> {
 
     // TODO(shahak): Implement this function.
     fn continue_propagation(&mut self, _message_metadata: BroadcastedMessageMetadata) {}
+
+    fn update_access_list(&mut self, update: AccessListUpdate) {
+        self.behaviour_mut().access_control.update(update);
+    }
+
+    fn is_peer_allowed(&self, peer_id: &PeerId) -> bool {
+        self.behaviour().access_control.is_peer_allowed(peer_id)
+    }
+
+    fn get_local_peer_id(&self) -> PeerId {
+        *self.local_peer_id()
+    }
+
+    fn gossipsub_mesh(&self) -> BTreeMap<String, Vec<PeerId>> {
+        let gossipsub = &self.behaviour().gossipsub;
+        gossipsub
+            .topics()
+            .map(|topic_hash| {
+                (topic_hash.to_string(), gossipsub.mesh_peers(topic_hash).copied().collect())
+            })
+            .collect()
+    }
 }
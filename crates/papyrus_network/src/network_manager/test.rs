@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
@@ -13,18 +13,33 @@ use futures::stream::Stream;
 use futures::{pin_mut, Future, SinkExt, StreamExt};
 use lazy_static::lazy_static;
 use libp2p::core::ConnectedPoint;
-use libp2p::gossipsub::{SubscriptionError, TopicHash};
+use libp2p::gossipsub::{MessageId, SubscriptionError, TopicHash, TopicScoreParams};
 use libp2p::swarm::ConnectionId;
 use libp2p::{Multiaddr, PeerId, StreamProtocol};
+use papyrus_network_types::network_types::OpaquePeerId;
 use tokio::select;
 use tokio::sync::Mutex;
 use tokio::time::sleep;
 
 use super::swarm_trait::{Event, SwarmTrait};
-use super::{BroadcastTopicChannels, GenericNetworkManager};
+use super::{
+    AccessListUpdate,
+    BroadcastTopicChannels,
+    ConnectedPeerInfo,
+    GenericNetworkManager,
+    NetworkCommand,
+    NetworkRequest,
+    PeerReputation,
+    SessionDirection,
+    SqmrSessionInfo,
+    TopicValidationResult,
+    Traffic,
+};
 use crate::gossipsub_impl::{self, Topic};
 use crate::mixed_behaviour;
 use crate::network_manager::{BroadcastTopicClientTrait, ServerQueryManager};
+use crate::peer_manager::ReputationModifier;
+use crate::rate_limiter::{RateLimitConfig, RateLimits, ResourceRateLimits};
 use crate::sqmr::behaviour::SessionIdNotFoundError;
 use crate::sqmr::{Bytes, GenericEvent, InboundSessionId, OutboundSessionId};
 
@@ -34,6 +49,7 @@ lazy_static! {
     static ref VEC1: Vec<u8> = vec![1, 2, 3, 4, 5];
     static ref VEC2: Vec<u8> = vec![6, 7, 8];
     static ref VEC3: Vec<u8> = vec![9, 10];
+    static ref LOCAL_PEER_ID: PeerId = PeerId::random();
 }
 
 #[derive(Default)]
@@ -42,7 +58,11 @@ struct MockSwarm {
     pub subscribed_topics: HashSet<TopicHash>,
     broadcasted_messages_senders: Vec<UnboundedSender<(Bytes, TopicHash)>>,
     reported_peer_senders: Vec<UnboundedSender<PeerId>>,
+    unstable_peer_senders: Vec<UnboundedSender<PeerId>>,
+    dropped_inbound_session_senders: Vec<UnboundedSender<InboundSessionId>>,
+    validation_result_senders: Vec<UnboundedSender<(MessageId, TopicValidationResult)>>,
     supported_inbound_protocols_senders: Vec<UnboundedSender<StreamProtocol>>,
+    access_list_update_senders: Vec<UnboundedSender<AccessListUpdate>>,
     inbound_session_id_to_response_sender: HashMap<InboundSessionId, UnboundedSender<Bytes>>,
     next_outbound_session_id: usize,
     first_polled_event_notifier: Option<oneshot::Sender<()>>,
@@ -95,12 +115,38 @@ impl MockSwarm {
         receiver
     }
 
+    pub fn get_unstable_peers_stream(&mut self) -> impl Stream<Item = PeerId> {
+        let (sender, receiver) = unbounded();
+        self.unstable_peer_senders.push(sender);
+        receiver
+    }
+
+    pub fn get_dropped_inbound_sessions_stream(&mut self) -> impl Stream<Item = InboundSessionId> {
+        let (sender, receiver) = unbounded();
+        self.dropped_inbound_session_senders.push(sender);
+        receiver
+    }
+
+    pub fn get_validation_results_stream(
+        &mut self,
+    ) -> impl Stream<Item = (MessageId, TopicValidationResult)> {
+        let (sender, receiver) = unbounded();
+        self.validation_result_senders.push(sender);
+        receiver
+    }
+
     pub fn get_supported_inbound_protocol(&mut self) -> impl Stream<Item = StreamProtocol> {
         let (sender, receiver) = unbounded();
         self.supported_inbound_protocols_senders.push(sender);
         receiver
     }
 
+    pub fn get_access_list_updates_stream(&mut self) -> impl Stream<Item = AccessListUpdate> {
+        let (sender, receiver) = unbounded();
+        self.access_list_update_senders.push(sender);
+        receiver
+    }
+
     fn create_response_events_for_query_each_num_becomes_response(
         &self,
         query: Vec<u8>,
@@ -144,6 +190,23 @@ impl SwarmTrait for MockSwarm {
         outbound_session_id
     }
 
+    fn send_query_to_peer(
+        &mut self,
+        query: Vec<u8>,
+        _protocol: StreamProtocol,
+        peer_id: PeerId,
+        _connection_id: ConnectionId,
+    ) -> OutboundSessionId {
+        let outbound_session_id = OutboundSessionId { value: self.next_outbound_session_id };
+        self.create_response_events_for_query_each_num_becomes_response(
+            query,
+            outbound_session_id,
+            peer_id,
+        );
+        self.next_outbound_session_id += 1;
+        outbound_session_id
+    }
+
     fn dial(&mut self, _peer: Multiaddr) -> Result<(), libp2p::swarm::DialError> {
         Ok(())
     }
@@ -167,7 +230,11 @@ impl SwarmTrait for MockSwarm {
 
     fn add_external_address(&mut self, _address: Multiaddr) {}
 
-    fn subscribe_to_topic(&mut self, topic: &Topic) -> Result<(), SubscriptionError> {
+    fn subscribe_to_topic(
+        &mut self,
+        topic: &Topic,
+        _score_params: Option<TopicScoreParams>,
+    ) -> Result<(), SubscriptionError> {
         self.subscribed_topics.insert(topic.hash());
         Ok(())
     }
@@ -178,11 +245,51 @@ impl SwarmTrait for MockSwarm {
         }
     }
 
-    fn report_peer_as_malicious(&mut self, peer_id: PeerId) {
-        for sender in &self.reported_peer_senders {
+    fn report_message_validation_result(
+        &mut self,
+        message_id: &MessageId,
+        _propagation_source: &PeerId,
+        result: TopicValidationResult,
+    ) {
+        for sender in &self.validation_result_senders {
+            sender.unbounded_send((message_id.clone(), result)).unwrap();
+        }
+    }
+
+    // Misconduct reports are sent to the reported peers streams and unstable reports are sent to
+    // the unstable peers streams.
+    fn report_peer(&mut self, peer_id: PeerId, reputation_modifier: ReputationModifier) {
+        let senders = match reputation_modifier {
+            ReputationModifier::Misconduct { .. } => &self.reported_peer_senders,
+            ReputationModifier::Unstable => &self.unstable_peer_senders,
+        };
+        for sender in senders {
             sender.unbounded_send(peer_id).unwrap();
         }
     }
+
+    fn record_outbound_session_response(&mut self, _outbound_session_id: OutboundSessionId) {}
+
+    fn record_outbound_session_success(&mut self, _outbound_session_id: OutboundSessionId) {}
+
+    fn peer_reputations(&mut self) -> Vec<PeerReputation> {
+        vec![]
+    }
+    fn drop_inbound_session(
+        &mut self,
+        inbound_session_id: InboundSessionId,
+    ) -> Result<(), SessionIdNotFoundError> {
+        for sender in &self.dropped_inbound_session_senders {
+            sender.unbounded_send(inbound_session_id).unwrap();
+        }
+        if let Some(responses_sender) =
+            self.inbound_session_id_to_response_sender.get(&inbound_session_id)
+        {
+            responses_sender.close_channel();
+        }
+        Ok(())
+    }
+
     fn add_new_supported_inbound_protocol(&mut self, protocol_name: StreamProtocol) {
         for sender in &self.supported_inbound_protocols_senders {
             sender.unbounded_send(protocol_name.clone()).unwrap();
@@ -200,6 +307,26 @@ impl SwarmTrait for MockSwarm {
     fn continue_propagation(&mut self, _message_metadata: super::BroadcastedMessageMetadata) {
         unimplemented!()
     }
+
+    fn update_access_list(&mut self, update: AccessListUpdate) {
+        for sender in &self.access_list_update_senders {
+            sender.unbounded_send(update).unwrap();
+        }
+    }
+
+    fn is_peer_allowed(&self, _peer_id: &PeerId) -> bool {
+        true
+    }
+
+    fn disconnect_peer(&mut self, _peer_id: PeerId) {}
+
+    fn get_local_peer_id(&self) -> PeerId {
+        *LOCAL_PEER_ID
+    }
+
+    fn gossipsub_mesh(&self) -> BTreeMap<String, Vec<PeerId>> {
+        BTreeMap::new()
+    }
 }
 
 const BUFFER_SIZE: usize = 100;
@@ -215,7 +342,8 @@ async fn register_sqmr_protocol_client_and_use_channels() {
     mock_swarm.first_polled_event_notifier = Some(event_notifier);
 
     // network manager to register subscriber
-    let mut network_manager = GenericNetworkManager::generic_new(mock_swarm, None, None);
+    let mut network_manager =
+        GenericNetworkManager::generic_new(mock_swarm, None, None, None, Default::default());
 
     // register subscriber and send payload
     let mut payload_sender = network_manager.register_sqmr_protocol_client::<Vec<u8>, Vec<u8>>(
@@ -252,6 +380,48 @@ async fn register_sqmr_protocol_client_and_use_channels() {
     assert_eq!(*response_receiver_length.lock().await, VEC1.len());
 }
 
+#[tokio::test]
+async fn query_sent_to_peer_runs_only_if_connected_to_it() {
+    let mut mock_swarm = MockSwarm::default();
+    let peer_id = PeerId::random();
+    mock_swarm.pending_events.push(get_test_connection_established_event(peer_id));
+    let (event_notifier, first_event_listner) = oneshot::channel();
+    mock_swarm.first_polled_event_notifier = Some(event_notifier);
+
+    let mut network_manager =
+        GenericNetworkManager::generic_new(mock_swarm, None, None, None, Default::default());
+    let mut payload_sender = network_manager.register_sqmr_protocol_client::<Vec<u8>, Vec<u8>>(
+        SIGNED_BLOCK_HEADER_PROTOCOL.to_string(),
+        BUFFER_SIZE,
+    );
+
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        _ = first_event_listner.then(|_| async move {
+            let responses = payload_sender
+                .send_new_query_to_peer(VEC1.clone(), OpaquePeerId::private_new(peer_id))
+                .await
+                .unwrap()
+                .take(VEC1.len())
+                .map(|result| result.unwrap())
+                .collect::<Vec<Vec<u8>>>()
+                .await;
+            assert_eq!(responses, VEC1.iter().map(|response| vec![*response]).collect::<Vec<_>>());
+
+            let unconnected_peer_responses = payload_sender
+                .send_new_query_to_peer(VEC1.clone(), OpaquePeerId::private_new(PeerId::random()))
+                .await
+                .unwrap()
+                .collect::<Vec<_>>()
+                .await;
+            assert!(unconnected_peer_responses.is_empty());
+        }) => {},
+        _ = sleep(Duration::from_secs(5)) => {
+            panic!("Test timed out");
+        }
+    }
+}
+
 // TODO(shahak): Add multiple protocols and multiple queries in the test.
 #[tokio::test]
 async fn process_incoming_query() {
@@ -277,7 +447,8 @@ async fn process_incoming_query() {
     let get_responses_fut = mock_swarm.get_responses_sent_to_inbound_session(inbound_session_id);
     let mut get_supported_inbound_protocol_fut = mock_swarm.get_supported_inbound_protocol();
 
-    let mut network_manager = GenericNetworkManager::generic_new(mock_swarm, None, None);
+    let mut network_manager =
+        GenericNetworkManager::generic_new(mock_swarm, None, None, None, Default::default());
 
     let mut inbound_payload_receiver = network_manager
         .register_sqmr_protocol_server::<Vec<u8>, Vec<u8>>(protocol.to_string(), BUFFER_SIZE);
@@ -313,7 +484,8 @@ async fn broadcast_message() {
     let mut mock_swarm = MockSwarm::default();
     let mut messages_we_broadcasted_stream = mock_swarm.stream_messages_we_broadcasted();
 
-    let mut network_manager = GenericNetworkManager::generic_new(mock_swarm, None, None);
+    let mut network_manager =
+        GenericNetworkManager::generic_new(mock_swarm, None, None, None, Default::default());
 
     let mut broadcast_topic_client = network_manager
         .register_broadcast_topic(topic.clone(), BUFFER_SIZE)
@@ -342,14 +514,17 @@ async fn receive_broadcasted_message_and_report_it() {
     let mut mock_swarm = MockSwarm::default();
     mock_swarm.pending_events.push(Event::Behaviour(mixed_behaviour::Event::ExternalEvent(
         mixed_behaviour::ExternalEvent::GossipSub(gossipsub_impl::ExternalEvent::Received {
+            message_id: MessageId::new(&message),
             originated_peer_id,
+            propagation_source: PeerId::random(),
             message: message.clone(),
             topic_hash: topic.hash(),
         }),
     )));
     let mut reported_peer_receiver = mock_swarm.get_reported_peers_stream();
 
-    let mut network_manager = GenericNetworkManager::generic_new(mock_swarm, None, None);
+    let mut network_manager =
+        GenericNetworkManager::generic_new(mock_swarm, None, None, None, Default::default());
 
     let BroadcastTopicChannels {
         mut broadcast_topic_client,
@@ -373,6 +548,319 @@ async fn receive_broadcasted_message_and_report_it() {
     }
 }
 
+#[tokio::test]
+async fn inbound_sessions_over_the_rate_limit_are_dropped() {
+    let protocol = SIGNED_BLOCK_HEADER_PROTOCOL;
+    let peer_id = PeerId::random();
+
+    let mut mock_swarm = MockSwarm::default();
+    for (value, query) in [VEC1.clone(), VEC2.clone()].into_iter().enumerate() {
+        mock_swarm.pending_events.push(Event::Behaviour(mixed_behaviour::Event::ExternalEvent(
+            mixed_behaviour::ExternalEvent::Sqmr(GenericEvent::NewInboundSession {
+                query,
+                inbound_session_id: InboundSessionId { value },
+                peer_id,
+                protocol_name: protocol.clone(),
+            }),
+        )));
+    }
+    let mut unstable_peers_stream = mock_swarm.get_unstable_peers_stream();
+    let mut dropped_inbound_sessions_stream = mock_swarm.get_dropped_inbound_sessions_stream();
+
+    let rate_limit_config = RateLimitConfig {
+        resource_limits: vec![ResourceRateLimits {
+            resource: protocol.to_string(),
+            limits: RateLimits { max_concurrent_sessions: 1, ..Default::default() },
+        }],
+        ..Default::default()
+    };
+    let mut network_manager = GenericNetworkManager::generic_new(
+        mock_swarm,
+        None,
+        None,
+        Some(rate_limit_config),
+        Default::default(),
+    );
+    let mut inbound_payload_receiver = network_manager
+        .register_sqmr_protocol_server::<Vec<u8>, Vec<u8>>(protocol.to_string(), BUFFER_SIZE);
+
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(TIMEOUT, async {
+            let first_query_manager = inbound_payload_receiver.next().await.unwrap();
+            assert_eq!(first_query_manager.query().as_ref().unwrap(), &*VEC1);
+            (unstable_peers_stream.next().await, dropped_inbound_sessions_stream.next().await)
+        }) => {
+            let (unstable_peer, dropped_inbound_session) = result.unwrap();
+            assert_eq!(unstable_peer, Some(peer_id));
+            assert_eq!(dropped_inbound_session, Some(InboundSessionId { value: 1 }));
+        }
+    }
+}
+
+#[tokio::test]
+async fn inbound_sessions_over_the_response_rate_limit_are_dropped() {
+    let protocol = SIGNED_BLOCK_HEADER_PROTOCOL;
+    let peer_id = PeerId::random();
+    let query = VEC3.clone();
+    let responses = vec![VEC1.clone(), VEC2.clone()];
+
+    let mut mock_swarm = MockSwarm::default();
+    let inbound_session_id = InboundSessionId { value: 0 };
+    mock_swarm.pending_events.push(Event::Behaviour(mixed_behaviour::Event::ExternalEvent(
+        mixed_behaviour::ExternalEvent::Sqmr(GenericEvent::NewInboundSession {
+            query: query.clone(),
+            inbound_session_id,
+            peer_id,
+            protocol_name: protocol.clone(),
+        }),
+    )));
+    let get_responses_fut = mock_swarm.get_responses_sent_to_inbound_session(inbound_session_id);
+    let mut unstable_peers_stream = mock_swarm.get_unstable_peers_stream();
+    let mut dropped_inbound_sessions_stream = mock_swarm.get_dropped_inbound_sessions_stream();
+
+    // The small query fits in the byte limit, but only the first of its responses does.
+    let max_bytes_per_second = u64::try_from(VEC1.len()).unwrap();
+    let rate_limit_config = RateLimitConfig {
+        default_limits: RateLimits { max_bytes_per_second, ..Default::default() },
+        ..Default::default()
+    };
+    let mut network_manager = GenericNetworkManager::generic_new(
+        mock_swarm,
+        None,
+        None,
+        Some(rate_limit_config),
+        Default::default(),
+    );
+    let mut inbound_payload_receiver = network_manager
+        .register_sqmr_protocol_server::<Vec<u8>, Vec<u8>>(protocol.to_string(), BUFFER_SIZE);
+
+    let responses_clone = responses.clone();
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(TIMEOUT, async move {
+            let ServerQueryManager { query: query_got, report_sender: _report_sender, mut responses_sender } =
+                inbound_payload_receiver.next().await.unwrap();
+            assert_eq!(query_got.unwrap(), query);
+            for response in responses_clone {
+                responses_sender.feed(response).await.unwrap();
+            }
+            responses_sender.sender.flush().await.unwrap();
+            (
+                get_responses_fut.await,
+                unstable_peers_stream.next().await,
+                dropped_inbound_sessions_stream.next().await,
+            )
+        }) => {
+            let (sent_responses, unstable_peer, dropped_inbound_session) = result.unwrap();
+            assert_eq!(sent_responses, vec![VEC1.clone()]);
+            assert_eq!(unstable_peer, Some(peer_id));
+            assert_eq!(dropped_inbound_session, Some(inbound_session_id));
+        }
+    }
+}
+
+#[tokio::test]
+async fn broadcasted_messages_over_the_rate_limit_are_dropped() {
+    let topic = Topic::new("TOPIC");
+    let propagation_source = PeerId::random();
+    let messages = [vec![1u8, 2u8, 3u8], vec![4u8, 5u8, 6u8]];
+
+    let mut mock_swarm = MockSwarm::default();
+    for message in &messages {
+        mock_swarm.pending_events.push(Event::Behaviour(mixed_behaviour::Event::ExternalEvent(
+            mixed_behaviour::ExternalEvent::GossipSub(gossipsub_impl::ExternalEvent::Received {
+                message_id: MessageId::new(message),
+                originated_peer_id: PeerId::random(),
+                propagation_source,
+                message: message.clone(),
+                topic_hash: topic.hash(),
+            }),
+        )));
+    }
+    let mut unstable_peers_stream = mock_swarm.get_unstable_peers_stream();
+
+    let rate_limit_config = RateLimitConfig {
+        default_limits: RateLimits { max_bytes_per_second: 4, ..Default::default() },
+        ..Default::default()
+    };
+    let mut network_manager = GenericNetworkManager::generic_new(
+        mock_swarm,
+        None,
+        None,
+        Some(rate_limit_config),
+        Default::default(),
+    );
+    // The client is kept since the network manager fails once it's dropped.
+    let BroadcastTopicChannels {
+        mut broadcasted_messages_receiver,
+        broadcast_topic_client: _broadcast_topic_client,
+    } = network_manager.register_broadcast_topic::<Bytes>(topic, BUFFER_SIZE).unwrap();
+
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(TIMEOUT, async {
+            let (first_message, _) = broadcasted_messages_receiver.next().await.unwrap();
+            assert_eq!(first_message.unwrap(), messages[0]);
+            unstable_peers_stream.next().await
+        }) => {
+            assert_eq!(result.unwrap(), Some(propagation_source));
+        }
+    }
+    // The second message was dropped, so the channel ends once the network manager is dropped.
+    assert!(broadcasted_messages_receiver.next().await.is_none());
+}
+
+#[tokio::test]
+async fn broadcast_topic_validator_filters_messages() {
+    let topic = Topic::new("TOPIC");
+    let propagation_source = PeerId::random();
+    let rejected_message = vec![0u8];
+    let ignored_message = vec![1u8];
+    let accepted_message = vec![2u8];
+
+    let mut mock_swarm = MockSwarm::default();
+    for message in [&rejected_message, &ignored_message, &accepted_message] {
+        mock_swarm.pending_events.push(Event::Behaviour(mixed_behaviour::Event::ExternalEvent(
+            mixed_behaviour::ExternalEvent::GossipSub(gossipsub_impl::ExternalEvent::Received {
+                message_id: MessageId::new(message),
+                originated_peer_id: PeerId::random(),
+                propagation_source,
+                message: message.clone(),
+                topic_hash: topic.hash(),
+            }),
+        )));
+    }
+    let mut validation_results_stream = mock_swarm.get_validation_results_stream();
+    let mut reported_peers_stream = mock_swarm.get_reported_peers_stream();
+
+    let mut network_manager =
+        GenericNetworkManager::generic_new(mock_swarm, None, None, None, Default::default());
+    let BroadcastTopicChannels {
+        mut broadcasted_messages_receiver,
+        broadcast_topic_client: _client,
+    } = network_manager
+        .register_broadcast_topic_with_validator::<Bytes>(
+            topic,
+            BUFFER_SIZE,
+            Box::new(|message| match message[0] {
+                0 => TopicValidationResult::Reject,
+                1 => TopicValidationResult::Ignore,
+                _ => TopicValidationResult::Accept,
+            }),
+        )
+        .unwrap();
+
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(TIMEOUT, async {
+            let validation_results =
+                validation_results_stream.by_ref().take(3).collect::<Vec<_>>().await;
+            let reported_peer = reported_peers_stream.next().await;
+            // Only the accepted message reaches the subscriber.
+            let (message, _) = broadcasted_messages_receiver.next().await.unwrap();
+            (validation_results, reported_peer, message.unwrap())
+        }) => {
+            let (validation_results, reported_peer, message) = result.unwrap();
+            assert_eq!(
+                validation_results,
+                vec![
+                    (MessageId::new(&rejected_message), TopicValidationResult::Reject),
+                    (MessageId::new(&ignored_message), TopicValidationResult::Ignore),
+                    (MessageId::new(&accepted_message), TopicValidationResult::Accept),
+                ]
+            );
+            assert_eq!(reported_peer, Some(propagation_source));
+            assert_eq!(message, accepted_message);
+        }
+    }
+}
+
+#[tokio::test]
+async fn network_command_updates_access_list() {
+    let mut mock_swarm = MockSwarm::default();
+    let mut access_list_updates_stream = mock_swarm.get_access_list_updates_stream();
+
+    let network_manager =
+        GenericNetworkManager::generic_new(mock_swarm, None, None, None, Default::default());
+    let mut network_request_sender = network_manager.get_network_request_sender();
+
+    let update = AccessListUpdate::DenyPeer(PeerId::random());
+    network_request_sender.send(NetworkCommand::UpdateAccessList(update).into()).await.unwrap();
+
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(TIMEOUT, access_list_updates_stream.next()) => {
+            assert_eq!(result.unwrap().unwrap(), update);
+        }
+    }
+}
+
+#[tokio::test]
+async fn network_info_reports_connections_sessions_and_traffic() {
+    let protocol: StreamProtocol = SIGNED_BLOCK_HEADER_PROTOCOL;
+    let peer_id = PeerId::random();
+    let inbound_session_id = InboundSessionId { value: 0 };
+
+    let mock_swarm = MockSwarm::default();
+    mock_swarm.pending_events.push(get_test_connection_established_event(peer_id));
+    mock_swarm.pending_events.push(Event::Behaviour(mixed_behaviour::Event::ExternalEvent(
+        mixed_behaviour::ExternalEvent::Sqmr(GenericEvent::NewInboundSession {
+            query: VEC1.clone(),
+            inbound_session_id,
+            peer_id,
+            protocol_name: protocol.clone(),
+        }),
+    )));
+
+    let mut network_manager =
+        GenericNetworkManager::generic_new(mock_swarm, None, None, None, Default::default());
+    let mut inbound_payload_receiver = network_manager
+        .register_sqmr_protocol_server::<Vec<u8>, Vec<u8>>(protocol.to_string(), BUFFER_SIZE);
+    let mut network_request_sender = network_manager.get_network_request_sender();
+
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(TIMEOUT, async {
+            // Keeping the query manager so that the session stays open.
+            let _query_manager = inbound_payload_receiver.next().await.unwrap();
+            let (network_info_sender, network_info_receiver) = oneshot::channel();
+            network_request_sender
+                .send(NetworkRequest::GetNetworkInfo(network_info_sender))
+                .await
+                .unwrap();
+            network_info_receiver.await.unwrap()
+        }) => {
+            let network_info = result.unwrap();
+            assert_eq!(network_info.local_peer_id, *LOCAL_PEER_ID);
+            assert_eq!(
+                network_info.connected_peers,
+                vec![ConnectedPeerInfo {
+                    peer_id,
+                    addresses: vec![Multiaddr::empty()],
+                    agent_version: None,
+                }]
+            );
+            assert_eq!(
+                network_info.sqmr_sessions,
+                vec![SqmrSessionInfo {
+                    session_id: inbound_session_id.value,
+                    direction: SessionDirection::Inbound,
+                    protocol: protocol.to_string(),
+                    peer_id: Some(peer_id),
+                }]
+            );
+            assert_eq!(
+                network_info.traffic,
+                BTreeMap::from([(
+                    protocol.to_string(),
+                    Traffic { bytes_sent: 0, bytes_received: VEC1.len().try_into().unwrap() },
+                )])
+            );
+        }
+    }
+}
+
 fn get_test_connection_established_event(mock_peer_id: PeerId) -> Event {
     Event::ConnectionEstablished {
         peer_id: mock_peer_id,
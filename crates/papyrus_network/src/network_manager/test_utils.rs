@@ -11,6 +11,7 @@ use libp2p::core::multiaddr::Protocol;
 use libp2p::gossipsub::SubscriptionError;
 use libp2p::identity::Keypair;
 use libp2p::{Multiaddr, PeerId};
+use papyrus_network_types::network_types::OpaquePeerId;
 
 use super::{
     BroadcastReceivedMessagesConverterFn,
@@ -165,12 +166,12 @@ pub fn create_connected_network_configs(mut ports: Vec<u16>) -> Vec<NetworkConfi
     for port in ports.iter() {
         configs.push(NetworkConfig {
             port: *port,
-            bootstrap_peer_multiaddr: Some(
+            bootstrap_peer_multiaddrs: vec![
                 Multiaddr::empty()
                     .with(Protocol::Ip4(Ipv4Addr::LOCALHOST))
                     .with(Protocol::Tcp(port0))
                     .with(Protocol::P2p(PeerId::from_public_key(&public_key0))),
-            ),
+            ],
             ..Default::default()
         });
     }
@@ -208,6 +209,7 @@ where
 
 pub struct MockClientResponsesManager<Query: TryFrom<Bytes>, Response: TryFrom<Bytes>> {
     query: Result<Query, <Query as TryFrom<Bytes>>::Error>,
+    peer_id: Option<PeerId>,
     report_receiver: ReportReceiver,
     responses_sender: ServerResponsesSender<Response>,
 }
@@ -217,6 +219,11 @@ impl<Query: TryFrom<Bytes>, Response: TryFrom<Bytes>> MockClientResponsesManager
         &self.query
     }
 
+    /// The peer the query was sent to, if the sender chose one.
+    pub fn peer_id(&self) -> Option<OpaquePeerId> {
+        self.peer_id.map(OpaquePeerId::private_new)
+    }
+
     pub async fn assert_reported(self, timeout: Duration) {
         tokio::time::timeout(timeout, self.report_receiver).await.unwrap().unwrap();
     }
@@ -234,12 +241,13 @@ where
     Bytes: From<Response>,
 {
     fn from(payload: SqmrClientPayload) -> Self {
-        let SqmrClientPayload { query, report_receiver, responses_sender } = payload;
+        let SqmrClientPayload { query, report_receiver, responses_sender, peer_id } = payload;
         let query = Query::try_from(query);
         let responses_sender =
             Box::new(responses_sender.with(|response: Response| ready(Ok(Bytes::from(response)))));
         Self {
             query,
+            peer_id,
             report_receiver,
             responses_sender: ServerResponsesSender { sender: responses_sender },
         }
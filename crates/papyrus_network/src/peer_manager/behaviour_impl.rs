@@ -183,6 +183,7 @@ impl NetworkBehaviour for PeerManager {
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<libp2p::swarm::ToSwarm<Self::ToSwarm, libp2p::swarm::THandlerInEvent<Self>>>
     {
+        self.persist_reputations_periodically();
         if let Some(event) = self.pending_events.pop() {
             return Poll::Ready(event);
         }
@@ -1,5 +1,6 @@
 use std::collections::{BTreeMap, HashMap};
-use std::time::Duration;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use futures::future::BoxFuture;
 use futures::FutureExt;
@@ -10,13 +11,16 @@ use papyrus_config::converters::{
     deserialize_milliseconds_to_duration,
     deserialize_seconds_to_duration,
 };
-use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::dumping::{ser_optional_param, ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
-use peer::Peer;
+use peer::{get_instant_now, Peer};
+use rand::distributions::{Distribution, WeightedIndex};
+use reputation::ReputationStore;
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
 pub use self::behaviour_impl::ToOtherBehaviourEvent;
+pub use self::reputation::PeerReputation;
 use crate::discovery::identify_impl::IdentifyToOtherBehaviourEvent;
 use crate::mixed_behaviour::BridgedBehaviour;
 use crate::sqmr::OutboundSessionId;
@@ -24,11 +28,15 @@ use crate::{discovery, mixed_behaviour, sqmr};
 
 pub(crate) mod behaviour_impl;
 pub(crate) mod peer;
+pub(crate) mod reputation;
 #[cfg(test)]
 mod test;
 
 pub const MALICIOUS: f64 = 1.0;
 
+// The reputations are written to the store at most once in this interval.
+const REPUTATION_PERSIST_INTERVAL: Duration = Duration::from_secs(30);
+
 #[cfg_attr(test, derive(Debug, PartialEq))]
 #[derive(Clone, Copy)]
 pub enum ReputationModifier {
@@ -44,8 +52,12 @@ pub struct PeerManager {
     peers: HashMap<PeerId, Peer>,
     // TODO(Shahak): consider implementing a cleanup mechanism to not store all queries forever
     session_to_peer_map: HashMap<OutboundSessionId, PeerId>,
+    // The assignment time of sessions that didn't receive a response yet, used to measure the
+    // latency of the peers.
+    session_assignment_times: HashMap<OutboundSessionId, Instant>,
     config: PeerManagerConfig,
-    last_peer_index: usize,
+    reputation_store: Option<ReputationStore>,
+    last_reputation_persist: Instant,
     // TODO(shahak): Change to VecDeque and awake when item is added.
     pending_events: Vec<ToSwarm<ToOtherBehaviourEvent, libp2p::swarm::THandlerInEvent<Self>>>,
     peers_pending_dial_with_sessions: HashMap<PeerId, Vec<OutboundSessionId>>,
@@ -61,6 +73,11 @@ pub struct PeerManagerConfig {
     malicious_timeout_seconds: Duration,
     #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
     unstable_timeout_millis: Duration,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    reputation_half_life_seconds: Duration,
+    successful_session_credit: f64,
+    max_credit: f64,
+    reputation_store_path: Option<PathBuf>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -79,13 +96,17 @@ impl Default for PeerManagerConfig {
             // TODO(shahak): Increase this once we're in a non-trusted setup.
             malicious_timeout_seconds: Duration::from_secs(1),
             unstable_timeout_millis: Duration::from_millis(1000),
+            reputation_half_life_seconds: Duration::from_secs(600),
+            successful_session_credit: 0.1,
+            max_credit: 10.0,
+            reputation_store_path: None,
         }
     }
 }
 
 impl SerializeConfig for PeerManagerConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        BTreeMap::from([
+        let mut dump = BTreeMap::from([
             ser_param(
                 "malicious_timeout_seconds",
                 &self.malicious_timeout_seconds.as_secs(),
@@ -98,7 +119,36 @@ impl SerializeConfig for PeerManagerConfig {
                 "The duration in milliseconds a peer blacklisted after being reported as unstable.",
                 ParamPrivacyInput::Public,
             ),
-        ])
+            ser_param(
+                "reputation_half_life_seconds",
+                &self.reputation_half_life_seconds.as_secs(),
+                "The duration in seconds in which the credit and the misconduct score of a peer \
+                 decay to half their value.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "successful_session_credit",
+                &self.successful_session_credit,
+                "The credit a peer gains for each outbound session it completed successfully.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_credit",
+                &self.max_credit,
+                "The maximal credit a peer can have. Peers are assigned to sessions with a \
+                 probability proportional to one plus their credit minus their misconduct score, \
+                 divided by one plus their average latency in seconds.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
+        dump.extend(ser_optional_param(
+            &self.reputation_store_path,
+            PathBuf::from("./data/peer_reputation.json"),
+            "reputation_store_path",
+            "A file to keep the reputation of the peers in across restarts.",
+            ParamPrivacyInput::Public,
+        ));
+        dump
     }
 }
 
@@ -106,11 +156,14 @@ impl SerializeConfig for PeerManagerConfig {
 impl PeerManager {
     pub(crate) fn new(config: PeerManagerConfig) -> Self {
         let peers = HashMap::new();
+        let reputation_store = config.reputation_store_path.clone().map(ReputationStore::load);
         Self {
             peers,
             session_to_peer_map: HashMap::new(),
+            session_assignment_times: HashMap::new(),
             config,
-            last_peer_index: 0,
+            reputation_store,
+            last_reputation_persist: get_instant_now(),
             pending_events: Vec::new(),
             peers_pending_dial_with_sessions: HashMap::new(),
             sessions_received_when_no_peers: Vec::new(),
@@ -119,8 +172,13 @@ impl PeerManager {
         }
     }
 
-    fn add_peer(&mut self, peer: Peer) {
+    fn add_peer(&mut self, mut peer: Peer) {
         info!("Peer Manager found new peer {:?}", peer.peer_id());
+        if let Some(reputation) = self.reputation_store.as_ref().and_then(|reputation_store| {
+            reputation_store.get(&peer.peer_id(), self.config.reputation_half_life_seconds)
+        }) {
+            *peer.reputation_mut() = reputation;
+        }
         self.peers.insert(peer.peer_id(), peer);
         // The new peer is unblocked so we don't need to wait for unblocked peer.
         self.sleep_waiting_for_unblocked_peer = None;
@@ -144,15 +202,7 @@ impl PeerManager {
             self.sessions_received_when_no_peers.push(outbound_session_id);
             return None;
         }
-        let peer = self
-            .peers
-            .iter()
-            .skip(self.last_peer_index)
-            .find(|(_, peer)| !peer.is_blocked())
-            .or_else(|| {
-                self.peers.iter().take(self.last_peer_index).find(|(_, peer)| !peer.is_blocked())
-            });
-        self.last_peer_index = (self.last_peer_index + 1) % self.peers.len();
+        let peer = self.choose_peer().and_then(|peer_id| self.peers.get_key_value(&peer_id));
         if peer.is_none() {
             info!(
                 "No unblocked peers. Waiting for a new peer to be connected or for a peer to \
@@ -173,6 +223,7 @@ impl PeerManager {
         peer.map(|(peer_id, peer)| {
             // TODO(Shahak): consider not allowing reassignment of the same session
             self.session_to_peer_map.insert(outbound_session_id, *peer_id);
+            self.session_assignment_times.insert(outbound_session_id, get_instant_now());
             let peer_connection_ids = peer.connection_ids();
             if !peer_connection_ids.is_empty() {
                 let connection_id = peer_connection_ids[0];
@@ -208,6 +259,21 @@ impl PeerManager {
         })
     }
 
+    /// Chooses a random unblocked peer, where the probability of each peer is proportional to its
+    /// selection weight.
+    fn choose_peer(&mut self) -> Option<PeerId> {
+        let half_life = self.config.reputation_half_life_seconds;
+        for peer in self.peers.values_mut() {
+            peer.decay_reputation(half_life);
+        }
+        let unblocked_peers =
+            self.peers.iter().filter(|(_, peer)| !peer.is_blocked()).collect::<Vec<_>>();
+        let weights = unblocked_peers.iter().map(|(_, peer)| peer.reputation().selection_weight());
+        // Fails only if there are no unblocked peers.
+        let weighted_index = WeightedIndex::new(weights).ok()?;
+        Some(*unblocked_peers[weighted_index.sample(&mut rand::thread_rng())].0)
+    }
+
     pub(crate) fn report_peer(
         &mut self,
         peer_id: PeerId,
@@ -216,6 +282,7 @@ impl PeerManager {
         self.pending_events
             .push(ToSwarm::GenerateEvent(ToOtherBehaviourEvent::PeerBlacklisted { peer_id }));
         if let Some(peer) = self.peers.get_mut(&peer_id) {
+            peer.decay_reputation(self.config.reputation_half_life_seconds);
             match reason {
                 ReputationModifier::Misconduct { misconduct_score } => {
                     peer.report(misconduct_score);
@@ -245,6 +312,96 @@ impl PeerManager {
             Err(PeerManagerError::NoSuchSession(outbound_session_id))
         }
     }
+
+    /// Records a response to the session. The first response is used to measure the latency of
+    /// the peer assigned to the session.
+    pub(crate) fn record_session_response(&mut self, outbound_session_id: OutboundSessionId) {
+        let Some(assignment_time) = self.session_assignment_times.remove(&outbound_session_id)
+        else {
+            return;
+        };
+        if let Some(peer) = self
+            .session_to_peer_map
+            .get(&outbound_session_id)
+            .and_then(|peer_id| self.peers.get_mut(peer_id))
+        {
+            peer.reputation_mut()
+                .record_latency(get_instant_now().saturating_duration_since(assignment_time));
+        }
+    }
+
+    /// Credits the peer assigned to the session for completing it.
+    pub(crate) fn record_session_success(&mut self, outbound_session_id: OutboundSessionId) {
+        self.session_assignment_times.remove(&outbound_session_id);
+        if let Some(peer) = self
+            .session_to_peer_map
+            .get(&outbound_session_id)
+            .and_then(|peer_id| self.peers.get_mut(peer_id))
+        {
+            peer.decay_reputation(self.config.reputation_half_life_seconds);
+            peer.reputation_mut()
+                .add_credit(self.config.successful_session_credit, self.config.max_credit);
+        }
+    }
+
+    /// Returns the reputation table of the known peers, sorted by descending score.
+    pub(crate) fn peer_reputations(&mut self) -> Vec<PeerReputation> {
+        let now = get_instant_now();
+        let half_life = self.config.reputation_half_life_seconds;
+        let mut peer_reputations = self
+            .peers
+            .values_mut()
+            .map(|peer| {
+                peer.decay_reputation(half_life);
+                let reputation = peer.reputation();
+                PeerReputation {
+                    peer_id: peer.peer_id(),
+                    score: reputation.score(),
+                    credit: reputation.credit,
+                    misconduct_score: reputation.misconduct_score,
+                    average_latency_millis: reputation
+                        .average_latency
+                        .map(|latency| latency.as_millis()),
+                    selection_weight: reputation.selection_weight(),
+                    blocked_for_millis: peer
+                        .blocked_until()
+                        .saturating_duration_since(now)
+                        .as_millis(),
+                }
+            })
+            .collect::<Vec<_>>();
+        peer_reputations.sort_by(|a, b| b.score.total_cmp(&a.score));
+        peer_reputations
+    }
+
+    fn persist_reputations(&mut self) {
+        let Some(reputation_store) = self.reputation_store.as_mut() else {
+            return;
+        };
+        let half_life = self.config.reputation_half_life_seconds;
+        for peer in self.peers.values_mut() {
+            peer.decay_reputation(half_life);
+        }
+        reputation_store.persist(
+            self.peers.iter().map(|(peer_id, peer)| (*peer_id, peer.reputation())),
+            half_life,
+        );
+        self.last_reputation_persist = get_instant_now();
+    }
+
+    fn persist_reputations_periodically(&mut self) {
+        if get_instant_now().saturating_duration_since(self.last_reputation_persist)
+            >= REPUTATION_PERSIST_INTERVAL
+        {
+            self.persist_reputations();
+        }
+    }
+}
+
+impl Drop for PeerManager {
+    fn drop(&mut self) {
+        self.persist_reputations();
+    }
 }
 
 impl From<ToOtherBehaviourEvent> for mixed_behaviour::Event {
@@ -262,7 +419,11 @@ impl BridgedBehaviour for PeerManager {
                 self.assign_peer_to_session(*outbound_session_id);
             }
             mixed_behaviour::ToOtherBehaviourEvent::Identify(
-                IdentifyToOtherBehaviourEvent::FoundListenAddresses { peer_id, listen_addresses },
+                IdentifyToOtherBehaviourEvent::FoundListenAddresses {
+                    peer_id,
+                    listen_addresses,
+                    ..
+                },
             )
             | mixed_behaviour::ToOtherBehaviourEvent::Discovery(
                 discovery::ToOtherBehaviourEvent::FoundListenAddresses {
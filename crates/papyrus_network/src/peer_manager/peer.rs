@@ -4,13 +4,16 @@ use libp2p::swarm::ConnectionId;
 use libp2p::{Multiaddr, PeerId};
 use tracing::info;
 
+use super::reputation::Reputation;
+
 #[derive(Clone)]
 pub struct Peer {
     peer_id: PeerId,
     multiaddr: Multiaddr,
     timed_out_until: Instant,
     connection_ids: Vec<ConnectionId>,
-    misconduct_score: f64,
+    reputation: Reputation,
+    last_decay: Instant,
 }
 
 impl Peer {
@@ -20,7 +23,8 @@ impl Peer {
             multiaddr,
             timed_out_until: get_instant_now(),
             connection_ids: Vec::new(),
-            misconduct_score: 0f64,
+            reputation: Reputation::default(),
+            last_decay: get_instant_now(),
         }
     }
 
@@ -66,25 +70,40 @@ impl Peer {
     }
 
     pub fn reset_misconduct_score(&mut self) {
-        self.misconduct_score = 0f64;
+        self.reputation.misconduct_score = 0f64;
     }
 
     pub fn report(&mut self, misconduct_score: f64) {
-        self.misconduct_score += misconduct_score;
+        self.reputation.misconduct_score += misconduct_score;
     }
 
     pub fn is_malicious(&self) -> bool {
-        1.0f64 <= self.misconduct_score
+        1.0f64 <= self.reputation.misconduct_score
+    }
+
+    pub fn reputation(&self) -> &Reputation {
+        &self.reputation
+    }
+
+    pub fn reputation_mut(&mut self) -> &mut Reputation {
+        &mut self.reputation
+    }
+
+    /// Decays the reputation by the time passed since it was last decayed.
+    pub fn decay_reputation(&mut self, half_life: Duration) {
+        let now = get_instant_now();
+        self.reputation.decay(now.saturating_duration_since(self.last_decay), half_life);
+        self.last_decay = now;
     }
 }
 
 #[cfg(not(test))]
-fn get_instant_now() -> Instant {
+pub(super) fn get_instant_now() -> Instant {
     Instant::now()
 }
 
 // In tests we simulate time passing using tokio, so we need to use tokio's Instant instead of std.
 #[cfg(test)]
-fn get_instant_now() -> Instant {
+pub(super) fn get_instant_now() -> Instant {
     tokio::time::Instant::now().into_std()
 }
@@ -0,0 +1,173 @@
+//! The reputation of the peers, which decides how likely each peer is to be assigned to an
+//! outbound session. Peers gain credit for sessions that finished successfully and lose reputation
+//! when they're reported for misconduct. Both the credit and the misconduct decay over time, so
+//! old behaviour is eventually forgotten.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::{fs, thread};
+
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::discovery::peer_store::{now, write_atomically};
+
+// The weight of a new latency sample in the average latency of a peer.
+const LATENCY_SAMPLE_WEIGHT: f64 = 0.2;
+// Even the worst peers are selected once in a while, so they get a chance to regain credit.
+const MIN_SELECTION_WEIGHT: f64 = 0.01;
+// A stored reputation whose credit and misconduct decayed below this is no longer stored.
+const NEGLIGIBLE_REPUTATION: f64 = 1e-3;
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Reputation {
+    pub credit: f64,
+    /// When the misconduct score reaches 1, the peer is considered malicious.
+    pub misconduct_score: f64,
+    pub average_latency: Option<Duration>,
+}
+
+impl Reputation {
+    pub fn score(&self) -> f64 {
+        self.credit - self.misconduct_score
+    }
+
+    /// Halves the credit and the misconduct score every `half_life`.
+    pub fn decay(&mut self, elapsed: Duration, half_life: Duration) {
+        if half_life.is_zero() {
+            self.credit = 0.0;
+            self.misconduct_score = 0.0;
+            return;
+        }
+        let factor = 0.5f64.powf(elapsed.as_secs_f64() / half_life.as_secs_f64());
+        self.credit *= factor;
+        self.misconduct_score *= factor;
+    }
+
+    pub fn add_credit(&mut self, credit: f64, max_credit: f64) {
+        self.credit = (self.credit + credit).min(max_credit);
+    }
+
+    pub fn record_latency(&mut self, latency: Duration) {
+        self.average_latency = Some(match self.average_latency {
+            Some(average_latency) => {
+                average_latency.mul_f64(1.0 - LATENCY_SAMPLE_WEIGHT)
+                    + latency.mul_f64(LATENCY_SAMPLE_WEIGHT)
+            }
+            None => latency,
+        });
+    }
+
+    /// The relative probability of assigning this peer to a session. Peers with a higher score and
+    /// a lower latency are preferred.
+    pub fn selection_weight(&self) -> f64 {
+        let latency_seconds =
+            self.average_latency.map(|latency| latency.as_secs_f64()).unwrap_or_default();
+        ((1.0 + self.score()) / (1.0 + latency_seconds)).max(MIN_SELECTION_WEIGHT)
+    }
+
+    fn is_negligible(&self) -> bool {
+        self.credit.abs() < NEGLIGIBLE_REPUTATION
+            && self.misconduct_score.abs() < NEGLIGIBLE_REPUTATION
+    }
+}
+
+/// A row in the reputation table that is shown for debugging.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PeerReputation {
+    pub peer_id: PeerId,
+    pub score: f64,
+    pub credit: f64,
+    pub misconduct_score: f64,
+    pub average_latency_millis: Option<u128>,
+    pub selection_weight: f64,
+    pub blocked_for_millis: u128,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct StoredReputation {
+    reputation: Reputation,
+    /// Seconds since the unix epoch.
+    updated_at: u64,
+}
+
+type StoredReputations = BTreeMap<PeerId, StoredReputation>;
+
+/// A disk-backed record of the reputation of the peers, so that it's kept across restarts.
+pub(crate) struct ReputationStore {
+    reputations: StoredReputations,
+    // The reputations are written to disk by a background thread, so that persisting them never
+    // blocks the swarm.
+    writer_sender: mpsc::Sender<StoredReputations>,
+}
+
+impl ReputationStore {
+    /// Loads the store from `path`. A missing or unreadable file results in an empty store.
+    pub fn load(path: PathBuf) -> Self {
+        let reputations = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|error| {
+                warn!(
+                    "Failed to parse the reputation store at {path:?}, starting empty. Error: \
+                     {error}"
+                );
+                BTreeMap::new()
+            }),
+            Err(error) => {
+                debug!("No reputation store was loaded from {path:?}. Error: {error}");
+                BTreeMap::new()
+            }
+        };
+        let (writer_sender, writer_receiver) = mpsc::channel();
+        thread::spawn(move || write_reputations(path, writer_receiver));
+        Self { reputations, writer_sender }
+    }
+
+    /// Returns the stored reputation of the peer, decayed by the time passed since it was stored.
+    pub fn get(&self, peer_id: &PeerId, half_life: Duration) -> Option<Reputation> {
+        self.reputations.get(peer_id).map(|stored_reputation| {
+            let mut reputation = stored_reputation.reputation.clone();
+            let elapsed = Duration::from_secs(now().saturating_sub(stored_reputation.updated_at));
+            reputation.decay(elapsed, half_life);
+            reputation
+        })
+    }
+
+    /// Updates the store with the given reputations and writes it to disk in the background.
+    /// Reputations that fully decayed are forgotten.
+    pub fn persist<'a>(
+        &mut self,
+        reputations: impl IntoIterator<Item = (PeerId, &'a Reputation)>,
+        half_life: Duration,
+    ) {
+        let updated_at = now();
+        for (peer_id, reputation) in reputations {
+            self.reputations
+                .insert(peer_id, StoredReputation { reputation: reputation.clone(), updated_at });
+        }
+        let peer_ids = self.reputations.keys().copied().collect::<Vec<_>>();
+        for peer_id in peer_ids {
+            if self.get(&peer_id, half_life).is_some_and(|reputation| reputation.is_negligible()) {
+                self.reputations.remove(&peer_id);
+            }
+        }
+        if self.writer_sender.send(self.reputations.clone()).is_err() {
+            warn!("The writer of the reputation store stopped, the reputations weren't written.");
+        }
+    }
+}
+
+// Writes the reputations received on the channel until the store is dropped. Only the latest of
+// the pending reputations is written.
+fn write_reputations(path: PathBuf, receiver: mpsc::Receiver<StoredReputations>) {
+    while let Ok(mut reputations) = receiver.recv() {
+        while let Ok(newer_reputations) = receiver.try_recv() {
+            reputations = newer_reputations;
+        }
+        if let Err(error) = write_atomically(&path, &reputations) {
+            warn!("Failed to write the reputation store to {path:?}. Error: {error}");
+        }
+    }
+}
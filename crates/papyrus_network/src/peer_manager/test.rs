@@ -3,7 +3,7 @@
 use core::{panic, time};
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use assert_matches::assert_matches;
 use futures::future::poll_fn;
@@ -19,6 +19,7 @@ use crate::discovery::identify_impl::IdentifyToOtherBehaviourEvent;
 use crate::mixed_behaviour;
 use crate::mixed_behaviour::BridgedBehaviour;
 use crate::peer_manager::peer::Peer;
+use crate::peer_manager::reputation::ReputationStore;
 use crate::peer_manager::{PeerManager, PeerManagerConfig, ReputationModifier, MALICIOUS};
 use crate::sqmr::OutboundSessionId;
 
@@ -36,86 +37,136 @@ impl Stream for PeerManager {
 }
 
 #[test]
-fn peer_assignment_round_robin() {
-    // Create a new peer manager
+fn peer_assignment_prefers_peers_with_higher_reputation() {
+    const NUM_SESSIONS: usize = 300;
     let mut peer_manager = PeerManager::new(PeerManagerConfig::default());
 
-    // Add two peers to the peer manager
-    let peer1 = Peer::new(PeerId::random(), Multiaddr::empty());
-    let peer2 = Peer::new(PeerId::random(), Multiaddr::empty());
-    peer_manager.add_peer(peer1.clone());
-    peer_manager.add_peer(peer2.clone());
-
-    // Create three queries
-    let session1 = OutboundSessionId { value: 1 };
-    let session2 = OutboundSessionId { value: 2 };
-    let session3 = OutboundSessionId { value: 3 };
-
-    // Assign peers to the queries in a round-robin fashion
-    let res1 = peer_manager.assign_peer_to_session(session1);
-    let res2 = peer_manager.assign_peer_to_session(session2);
-    let res3 = peer_manager.assign_peer_to_session(session3);
-
-    // Verify that the peers are assigned in a round-robin fashion
-    let is_peer1_first: bool;
-    match res1.unwrap() {
-        peer_id if peer_id == peer1.peer_id() => {
-            is_peer1_first = true;
-            assert_eq!(res2.unwrap(), peer2.peer_id());
-            assert_eq!(res3.unwrap(), peer1.peer_id());
-        }
-        peer_id if peer_id == peer2.peer_id() => {
-            is_peer1_first = false;
-            assert_eq!(res2.unwrap(), peer1.peer_id());
-            assert_eq!(res3.unwrap(), peer2.peer_id());
+    let mut good_peer = Peer::new(PeerId::random(), Multiaddr::empty());
+    good_peer.add_connection_id(ConnectionId::new_unchecked(0));
+    let mut bad_peer = Peer::new(PeerId::random(), Multiaddr::empty());
+    bad_peer.add_connection_id(ConnectionId::new_unchecked(1));
+    let good_peer_id = good_peer.peer_id();
+    let bad_peer_id = bad_peer.peer_id();
+    peer_manager.add_peer(good_peer);
+    peer_manager.add_peer(bad_peer);
+    peer_manager.get_mut_peer(good_peer_id).unwrap().reputation_mut().credit = 10.0;
+    peer_manager
+        .report_peer(bad_peer_id, ReputationModifier::Misconduct { misconduct_score: 0.5 })
+        .unwrap();
+
+    let mut num_good_peer_assignments = 0;
+    for value in 0..NUM_SESSIONS {
+        let peer_id = peer_manager.assign_peer_to_session(OutboundSessionId { value }).unwrap();
+        if peer_id == good_peer_id {
+            num_good_peer_assignments += 1;
+        } else {
+            assert_eq!(peer_id, bad_peer_id);
         }
-        peer_id => panic!("Unexpected peer_id: {:?}", peer_id),
     }
 
-    // check assignment events
+    // The good peer's weight is 22 times the bad peer's weight, so it should get the vast majority
+    // of the sessions, while the bad peer still gets some of them.
+    assert!(num_good_peer_assignments > NUM_SESSIONS * 3 / 4);
+    assert!(num_good_peer_assignments < NUM_SESSIONS);
+
+    // Check that the assignment events match the assigned peers' connections.
     while let Some(event) = peer_manager.next().now_or_never() {
         let Some(ToSwarm::GenerateEvent(ToOtherBehaviourEvent::SessionAssigned {
-            outbound_session_id,
             peer_id,
             connection_id,
+            ..
         })) = event
         else {
             continue;
         };
-        if is_peer1_first {
-            match outbound_session_id {
-                OutboundSessionId { value: 1 } => {
-                    assert_eq!(peer_id, peer1.peer_id());
-                    assert_eq!(connection_id, *peer1.connection_ids().first().unwrap())
-                }
-                OutboundSessionId { value: 2 } => {
-                    assert_eq!(peer_id, peer2.peer_id());
-                    assert_eq!(connection_id, *peer2.connection_ids().first().unwrap());
-                }
-                OutboundSessionId { value: 3 } => {
-                    assert_eq!(peer_id, peer1.peer_id());
-                    assert_eq!(connection_id, *peer1.connection_ids().first().unwrap());
-                }
-                _ => panic!("Unexpected outbound_session_id: {:?}", outbound_session_id),
-            }
+        let expected_connection_id = if peer_id == good_peer_id {
+            ConnectionId::new_unchecked(0)
         } else {
-            match outbound_session_id {
-                OutboundSessionId { value: 1 } => {
-                    assert_eq!(peer_id, peer2.peer_id());
-                    assert_eq!(connection_id, *peer2.connection_ids().first().unwrap());
-                }
-                OutboundSessionId { value: 2 } => {
-                    assert_eq!(peer_id, peer1.peer_id());
-                    assert_eq!(connection_id, *peer1.connection_ids().first().unwrap());
-                }
-                OutboundSessionId { value: 3 } => {
-                    assert_eq!(peer_id, peer2.peer_id());
-                    assert_eq!(connection_id, *peer2.connection_ids().first().unwrap());
-                }
-                _ => panic!("Unexpected outbound_session_id: {:?}", outbound_session_id),
-            }
-        }
+            ConnectionId::new_unchecked(1)
+        };
+        assert_eq!(connection_id, expected_connection_id);
+    }
+}
+
+#[tokio::test]
+async fn reputation_decays_over_time() {
+    const HALF_LIFE: Duration = Duration::from_secs(10);
+    let config =
+        PeerManagerConfig { reputation_half_life_seconds: HALF_LIFE, ..Default::default() };
+    let mut peer_manager = PeerManager::new(config);
+    let peer_id = PeerId::random();
+    peer_manager.add_peer(Peer::new(peer_id, Multiaddr::empty()));
+    peer_manager.get_mut_peer(peer_id).unwrap().reputation_mut().credit = 4.0;
+    peer_manager
+        .report_peer(peer_id, ReputationModifier::Misconduct { misconduct_score: 0.8 })
+        .unwrap();
+
+    tokio::time::pause();
+    tokio::time::advance(HALF_LIFE * 2).await;
+    tokio::time::resume();
+
+    let peer_reputations = peer_manager.peer_reputations();
+    assert_eq!(peer_reputations.len(), 1);
+    assert!((peer_reputations[0].credit - 1.0).abs() < 0.01);
+    assert!((peer_reputations[0].misconduct_score - 0.2).abs() < 0.01);
+}
+
+#[tokio::test]
+async fn successful_sessions_credit_the_peer_and_measure_its_latency() {
+    const LATENCY: Duration = Duration::from_millis(300);
+    let config =
+        PeerManagerConfig { successful_session_credit: 0.5, max_credit: 1.0, ..Default::default() };
+    let mut peer_manager = PeerManager::new(config);
+    let peer_id = PeerId::random();
+    let mut peer = Peer::new(peer_id, Multiaddr::empty());
+    peer.add_connection_id(ConnectionId::new_unchecked(0));
+    peer_manager.add_peer(peer);
+
+    tokio::time::pause();
+    for value in 0..3 {
+        let outbound_session_id = OutboundSessionId { value };
+        peer_manager.assign_peer_to_session(outbound_session_id).unwrap();
+        tokio::time::advance(LATENCY).await;
+        peer_manager.record_session_response(outbound_session_id);
+        peer_manager.record_session_success(outbound_session_id);
     }
+    tokio::time::resume();
+
+    let reputation = peer_manager.get_mut_peer(peer_id).unwrap().reputation().clone();
+    // The credit is capped by max_credit.
+    assert!((reputation.credit - 1.0).abs() < 0.01);
+    let average_latency = reputation.average_latency.unwrap();
+    assert!(average_latency >= LATENCY && average_latency < LATENCY * 2);
+}
+
+#[test]
+fn reputation_is_kept_across_restarts() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("peer_reputation.json");
+    let config =
+        PeerManagerConfig { reputation_store_path: Some(path.clone()), ..Default::default() };
+    let peer_id = PeerId::random();
+    let forgotten_peer_id = PeerId::random();
+
+    let mut peer_manager = PeerManager::new(config.clone());
+    peer_manager.add_peer(Peer::new(peer_id, Multiaddr::empty()));
+    peer_manager.add_peer(Peer::new(forgotten_peer_id, Multiaddr::empty()));
+    peer_manager.get_mut_peer(peer_id).unwrap().reputation_mut().credit = 3.0;
+    drop(peer_manager);
+
+    // The reputations are written in the background.
+    let half_life = PeerManagerConfig::default().reputation_half_life_seconds;
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while ReputationStore::load(path.clone()).get(&peer_id, half_life).is_none() {
+        assert!(Instant::now() < deadline, "The reputations weren't written.");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    let mut peer_manager = PeerManager::new(config);
+    peer_manager.add_peer(Peer::new(peer_id, Multiaddr::empty()));
+    assert!((peer_manager.get_mut_peer(peer_id).unwrap().reputation().credit - 3.0).abs() < 0.01);
+    // Peers without any reputation aren't stored.
+    assert!(ReputationStore::load(path).get(&forgotten_peer_id, half_life).is_none());
 }
 
 #[tokio::test]
@@ -156,8 +207,11 @@ async fn peer_assignment_no_unblocked_peers() {
     const BLOCKED_UNTIL: Duration = Duration::from_secs(5);
     const TIMEOUT: Duration = Duration::from_secs(1);
     // Create a new peer manager
-    let config =
-        PeerManagerConfig { malicious_timeout_seconds: TIMEOUT, unstable_timeout_millis: TIMEOUT };
+    let config = PeerManagerConfig {
+        malicious_timeout_seconds: TIMEOUT,
+        unstable_timeout_millis: TIMEOUT,
+        ..Default::default()
+    };
     let mut peer_manager: PeerManager = PeerManager::new(config.clone());
 
     // Create a session
@@ -417,7 +471,7 @@ fn assign_non_connected_peer_raises_dial_event() {
     let res_peer_id = peer_manager.assign_peer_to_session(outbound_session_id).unwrap();
 
     // check events
-    for event in peer_manager.pending_events {
+    for event in std::mem::take(&mut peer_manager.pending_events) {
         assert_matches!(event, ToSwarm::Dial {opts} if opts.get_peer_id() == Some(res_peer_id));
     }
 }
@@ -479,6 +533,7 @@ fn identify_on_unknown_peer_is_added_to_peer_manager() {
         IdentifyToOtherBehaviourEvent::FoundListenAddresses {
             peer_id,
             listen_addresses: vec![address.clone()],
+            agent_version: String::new(),
         },
     ));
 
@@ -0,0 +1,311 @@
+//! Limits the load each peer can put on this node, both by what it sends and by the responses it
+//! makes this node send. The limits are tracked separately for every peer and every SQMR protocol
+//! or broadcast topic.
+
+#[cfg(test)]
+mod test;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::hash::Hash;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use libp2p::PeerId;
+use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+
+use crate::{deserialize_list, list_to_string, serialize_list};
+
+// The amount of tokens a bucket can accumulate, in seconds of its rate.
+const BURST_DURATION: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RateLimits {
+    pub max_requests_per_second: u32,
+    pub max_concurrent_sessions: u32,
+    pub max_bytes_per_second: u64,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            max_requests_per_second: 20,
+            max_concurrent_sessions: 10,
+            max_bytes_per_second: 50 * (1 << 20),
+        }
+    }
+}
+
+impl SerializeConfig for RateLimits {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "max_requests_per_second",
+                &self.max_requests_per_second,
+                "The maximal number of inbound SQMR sessions per second a peer can open for a \
+                 protocol, and the maximal number of messages per second a peer can send on a \
+                 broadcast topic.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_concurrent_sessions",
+                &self.max_concurrent_sessions,
+                "The maximal number of inbound SQMR sessions a peer can have open at the same \
+                 time for a protocol.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_bytes_per_second",
+                &self.max_bytes_per_second,
+                "The maximal number of bytes per second a peer can send in the queries of a SQMR \
+                 protocol or in the messages of a broadcast topic, and separately, the maximal \
+                 number of bytes per second this node sends a peer in the responses of a SQMR \
+                 protocol.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+/// The limits of a single SQMR protocol or broadcast topic, written as
+/// `<protocol name or topic hash>:<requests per second>,<concurrent sessions>,<bytes per second>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResourceRateLimits {
+    pub resource: String,
+    pub limits: RateLimits,
+}
+
+impl Display for ResourceRateLimits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let RateLimits { max_requests_per_second, max_concurrent_sessions, max_bytes_per_second } =
+            self.limits;
+        write!(
+            f,
+            "{}:{max_requests_per_second},{max_concurrent_sessions},{max_bytes_per_second}",
+            self.resource
+        )
+    }
+}
+
+impl FromStr for ResourceRateLimits {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid rate limits \"{s}\". The expected format is <resource>:<requests per \
+                 second>,<concurrent sessions>,<bytes per second>"
+            )
+        };
+        let (resource, limits) = s.rsplit_once(':').ok_or_else(invalid)?;
+        let mut limits = limits.split(',');
+        let (
+            Some(max_requests_per_second),
+            Some(max_concurrent_sessions),
+            Some(max_bytes_per_second),
+            None,
+        ) = (limits.next(), limits.next(), limits.next(), limits.next())
+        else {
+            return Err(invalid());
+        };
+        Ok(Self {
+            resource: resource.to_owned(),
+            limits: RateLimits {
+                max_requests_per_second: max_requests_per_second.parse().map_err(|_| invalid())?,
+                max_concurrent_sessions: max_concurrent_sessions.parse().map_err(|_| invalid())?,
+                max_bytes_per_second: max_bytes_per_second.parse().map_err(|_| invalid())?,
+            },
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct RateLimitConfig {
+    pub default_limits: RateLimits,
+    #[serde(deserialize_with = "deserialize_list", serialize_with = "serialize_list")]
+    pub resource_limits: Vec<ResourceRateLimits>,
+}
+
+impl RateLimitConfig {
+    fn limits_of(&self, resource: &str) -> RateLimits {
+        self.resource_limits
+            .iter()
+            .find(|resource_limits| resource_limits.resource == resource)
+            .map_or(self.default_limits, |resource_limits| resource_limits.limits)
+    }
+}
+
+impl SerializeConfig for RateLimitConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        let mut config = append_sub_config_name(self.default_limits.dump(), "default_limits");
+        config.extend([ser_param(
+            "resource_limits",
+            &list_to_string(&self.resource_limits),
+            "Space separated limits of specific SQMR protocols and broadcast topics, overriding \
+             default_limits. Each entry is written as <protocol name or topic \
+             hash>:<max_requests_per_second>,<max_concurrent_sessions>,<max_bytes_per_second>.",
+            ParamPrivacyInput::Public,
+        )]);
+        config
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum RateLimitViolation {
+    #[error("Too many requests per second.")]
+    RequestsPerSecond,
+    #[error("Too many concurrent sessions.")]
+    ConcurrentSessions,
+    #[error("Too many bytes per second.")]
+    BytesPerSecond,
+    #[error("Too many response bytes per second.")]
+    ResponseBytesPerSecond,
+}
+
+// A token bucket implemented as a generic cell rate algorithm: instead of counting tokens, it keeps
+// the time at which the bucket will be full again. The bucket refills at `rate` tokens per second
+// and holds up to `BURST_DURATION` worth of tokens.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: u64,
+    full_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64, now: Instant) -> Self {
+        Self { rate, full_at: now }
+    }
+
+    fn try_take(&mut self, amount: u64, now: Instant) -> bool {
+        if self.rate == 0 {
+            return amount == 0;
+        }
+        let cost_nanos = u128::from(amount) * 1_000_000_000 / u128::from(self.rate);
+        let cost = Duration::from_nanos(u64::try_from(cost_nanos).unwrap_or(u64::MAX));
+        let Some(full_at) = self.full_at.max(now).checked_add(cost) else {
+            return false;
+        };
+        if full_at.saturating_duration_since(now) > BURST_DURATION {
+            return false;
+        }
+        self.full_at = full_at;
+        true
+    }
+}
+
+#[derive(Debug)]
+struct Usage {
+    requests: TokenBucket,
+    bytes: TokenBucket,
+    response_bytes: TokenBucket,
+    concurrent_sessions: u32,
+    max_concurrent_sessions: u32,
+}
+
+impl Usage {
+    fn new(limits: RateLimits, now: Instant) -> Self {
+        Self {
+            requests: TokenBucket::new(limits.max_requests_per_second.into(), now),
+            bytes: TokenBucket::new(limits.max_bytes_per_second, now),
+            response_bytes: TokenBucket::new(limits.max_bytes_per_second, now),
+            concurrent_sessions: 0,
+            max_concurrent_sessions: limits.max_concurrent_sessions,
+        }
+    }
+
+    // Registers a request with the given number of inbound bytes.
+    fn request(&mut self, num_bytes: usize, now: Instant) -> Result<(), RateLimitViolation> {
+        if !self.requests.try_take(1, now) {
+            return Err(RateLimitViolation::RequestsPerSecond);
+        }
+        if !self.bytes.try_take(u64::try_from(num_bytes).unwrap_or(u64::MAX), now) {
+            return Err(RateLimitViolation::BytesPerSecond);
+        }
+        Ok(())
+    }
+}
+
+/// Tracks the usage of each peer for each resource, where a resource is an SQMR protocol
+/// or a broadcast topic. If there's no config, nothing is limited.
+pub(crate) struct RateLimiter<Resource: Clone + Eq + Hash + Display> {
+    config: Option<RateLimitConfig>,
+    usages: HashMap<(PeerId, Resource), Usage>,
+}
+
+impl<Resource: Clone + Eq + Hash + Display> RateLimiter<Resource> {
+    pub fn new(config: Option<RateLimitConfig>) -> Self {
+        Self { config, usages: HashMap::new() }
+    }
+
+    fn usage(&mut self, peer_id: PeerId, resource: Resource, now: Instant) -> Option<&mut Usage> {
+        let config = self.config.as_ref()?;
+        Some(self.usages.entry((peer_id, resource)).or_insert_with_key(|(_, resource)| {
+            Usage::new(config.limits_of(&resource.to_string()), now)
+        }))
+    }
+
+    /// Registers a new inbound session with a query of the given size. On success, `end_session`
+    /// should be called once the session ends.
+    pub fn start_session(
+        &mut self,
+        peer_id: PeerId,
+        resource: Resource,
+        query_num_bytes: usize,
+        now: Instant,
+    ) -> Result<(), RateLimitViolation> {
+        let Some(usage) = self.usage(peer_id, resource, now) else {
+            return Ok(());
+        };
+        if usage.concurrent_sessions >= usage.max_concurrent_sessions {
+            return Err(RateLimitViolation::ConcurrentSessions);
+        }
+        usage.request(query_num_bytes, now)?;
+        usage.concurrent_sessions += 1;
+        Ok(())
+    }
+
+    pub fn end_session(&mut self, peer_id: PeerId, resource: Resource) {
+        if let Some(usage) = self.usages.get_mut(&(peer_id, resource)) {
+            usage.concurrent_sessions = usage.concurrent_sessions.saturating_sub(1);
+        }
+    }
+
+    /// Registers a single inbound message that isn't part of a session.
+    pub fn receive_message(
+        &mut self,
+        peer_id: PeerId,
+        resource: Resource,
+        num_bytes: usize,
+        now: Instant,
+    ) -> Result<(), RateLimitViolation> {
+        match self.usage(peer_id, resource, now) {
+            Some(usage) => usage.request(num_bytes, now),
+            None => Ok(()),
+        }
+    }
+
+    /// Registers a response of the given size that is sent to the peer in one of its inbound
+    /// sessions. A small query can ask for large responses, so the responses are limited too.
+    pub fn send_response(
+        &mut self,
+        peer_id: PeerId,
+        resource: Resource,
+        num_bytes: usize,
+        now: Instant,
+    ) -> Result<(), RateLimitViolation> {
+        let Some(usage) = self.usage(peer_id, resource, now) else {
+            return Ok(());
+        };
+        if !usage.response_bytes.try_take(u64::try_from(num_bytes).unwrap_or(u64::MAX), now) {
+            return Err(RateLimitViolation::ResponseBytesPerSecond);
+        }
+        Ok(())
+    }
+
+    /// Forgets the usage of a peer that is no longer connected.
+    pub fn remove_peer(&mut self, peer_id: &PeerId) {
+        self.usages.retain(|(usage_peer_id, _), _| usage_peer_id != peer_id);
+    }
+}
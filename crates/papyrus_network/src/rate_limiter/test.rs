@@ -0,0 +1,168 @@
+use std::time::{Duration, Instant};
+
+use libp2p::PeerId;
+
+use super::{RateLimitConfig, RateLimitViolation, RateLimiter, RateLimits, ResourceRateLimits};
+
+const LIMITS: RateLimits =
+    RateLimits { max_requests_per_second: 3, max_concurrent_sessions: 2, max_bytes_per_second: 10 };
+const PROTOCOL: &str = "/protocol";
+const OTHER_PROTOCOL: &str = "/other_protocol";
+
+fn config() -> RateLimitConfig {
+    RateLimitConfig { default_limits: LIMITS, resource_limits: vec![] }
+}
+
+#[test]
+fn no_config_means_no_limit() {
+    let mut rate_limiter = RateLimiter::new(None);
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+    for _ in 0..100 {
+        rate_limiter.start_session(peer_id, PROTOCOL, 1000, now).unwrap();
+        rate_limiter.receive_message(peer_id, PROTOCOL, 1000, now).unwrap();
+        rate_limiter.send_response(peer_id, PROTOCOL, 1000, now).unwrap();
+    }
+}
+
+#[test]
+fn concurrent_sessions_limit() {
+    let mut rate_limiter = RateLimiter::new(Some(config()));
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+    rate_limiter.start_session(peer_id, PROTOCOL, 0, now).unwrap();
+    rate_limiter.start_session(peer_id, PROTOCOL, 0, now).unwrap();
+    assert_eq!(
+        rate_limiter.start_session(peer_id, PROTOCOL, 0, now),
+        Err(RateLimitViolation::ConcurrentSessions)
+    );
+
+    // Limits are per peer and per protocol.
+    rate_limiter.start_session(PeerId::random(), PROTOCOL, 0, now).unwrap();
+    rate_limiter.start_session(peer_id, OTHER_PROTOCOL, 0, now).unwrap();
+
+    rate_limiter.end_session(peer_id, PROTOCOL);
+    rate_limiter.start_session(peer_id, PROTOCOL, 0, now).unwrap();
+}
+
+#[test]
+fn requests_per_second_limit() {
+    let mut rate_limiter = RateLimiter::new(Some(config()));
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+    for _ in 0..LIMITS.max_requests_per_second {
+        rate_limiter.start_session(peer_id, PROTOCOL, 0, now).unwrap();
+        rate_limiter.end_session(peer_id, PROTOCOL);
+    }
+    assert_eq!(
+        rate_limiter.start_session(peer_id, PROTOCOL, 0, now),
+        Err(RateLimitViolation::RequestsPerSecond)
+    );
+    assert_eq!(
+        rate_limiter.receive_message(peer_id, PROTOCOL, 1, now),
+        Err(RateLimitViolation::RequestsPerSecond)
+    );
+
+    // The requests are refilled gradually, one every third of a second.
+    let after_one_refill = now + Duration::from_millis(400);
+    rate_limiter.start_session(peer_id, PROTOCOL, 0, after_one_refill).unwrap();
+    rate_limiter.end_session(peer_id, PROTOCOL);
+    assert_eq!(
+        rate_limiter.start_session(peer_id, PROTOCOL, 0, after_one_refill),
+        Err(RateLimitViolation::RequestsPerSecond)
+    );
+}
+
+#[test]
+fn bytes_per_second_limit() {
+    let mut rate_limiter = RateLimiter::new(Some(config()));
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+    rate_limiter.start_session(peer_id, PROTOCOL, 6, now).unwrap();
+    assert_eq!(
+        rate_limiter.receive_message(peer_id, PROTOCOL, 5, now),
+        Err(RateLimitViolation::BytesPerSecond)
+    );
+
+    // Half a second refills half of the bytes.
+    let half_second_later = now + Duration::from_millis(500);
+    rate_limiter.receive_message(peer_id, PROTOCOL, 9, half_second_later).unwrap();
+    assert_eq!(
+        rate_limiter.receive_message(peer_id, PROTOCOL, 1, half_second_later),
+        Err(RateLimitViolation::BytesPerSecond)
+    );
+
+    // The bucket never holds more than a second's worth of bytes.
+    let much_later = now + Duration::from_secs(10);
+    assert_eq!(
+        rate_limiter.receive_message(peer_id, PROTOCOL, 11, much_later),
+        Err(RateLimitViolation::BytesPerSecond)
+    );
+    rate_limiter.receive_message(peer_id, PROTOCOL, 10, much_later).unwrap();
+}
+
+#[test]
+fn response_bytes_per_second_limit() {
+    let mut rate_limiter = RateLimiter::new(Some(config()));
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+    // A small query whose responses are large.
+    rate_limiter.start_session(peer_id, PROTOCOL, 1, now).unwrap();
+    rate_limiter.send_response(peer_id, PROTOCOL, 10, now).unwrap();
+    assert_eq!(
+        rate_limiter.send_response(peer_id, PROTOCOL, 1, now),
+        Err(RateLimitViolation::ResponseBytesPerSecond)
+    );
+    // The responses don't use up the bytes the peer can send.
+    rate_limiter.receive_message(peer_id, PROTOCOL, 9, now).unwrap();
+    rate_limiter.send_response(peer_id, OTHER_PROTOCOL, 10, now).unwrap();
+
+    let half_second_later = now + Duration::from_millis(500);
+    rate_limiter.send_response(peer_id, PROTOCOL, 5, half_second_later).unwrap();
+}
+
+#[test]
+fn resource_limits_override_default_limits() {
+    let other_protocol_limits = RateLimits { max_concurrent_sessions: 1, ..LIMITS };
+    let config = RateLimitConfig {
+        resource_limits: vec![ResourceRateLimits {
+            resource: OTHER_PROTOCOL.to_owned(),
+            limits: other_protocol_limits,
+        }],
+        ..config()
+    };
+    let mut rate_limiter = RateLimiter::new(Some(config));
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+    rate_limiter.start_session(peer_id, PROTOCOL, 0, now).unwrap();
+    rate_limiter.start_session(peer_id, PROTOCOL, 0, now).unwrap();
+    rate_limiter.start_session(peer_id, OTHER_PROTOCOL, 0, now).unwrap();
+    assert_eq!(
+        rate_limiter.start_session(peer_id, OTHER_PROTOCOL, 0, now),
+        Err(RateLimitViolation::ConcurrentSessions)
+    );
+}
+
+#[test]
+fn resource_limits_string_round_trip() {
+    let resource_limits =
+        ResourceRateLimits { resource: "/starknet/headers/0.1.0-rc.0".to_owned(), limits: LIMITS };
+    let as_string = resource_limits.to_string();
+    assert_eq!(as_string, "/starknet/headers/0.1.0-rc.0:3,2,10");
+    assert_eq!(as_string.parse::<ResourceRateLimits>().unwrap(), resource_limits);
+
+    for invalid in ["/protocol", "/protocol:3,2", "/protocol:3,2,10,1", "/protocol:3,x,10"] {
+        assert!(invalid.parse::<ResourceRateLimits>().is_err());
+    }
+}
+
+#[test]
+fn removed_peer_usage_is_forgotten() {
+    let mut rate_limiter = RateLimiter::new(Some(config()));
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+    rate_limiter.start_session(peer_id, PROTOCOL, 0, now).unwrap();
+    rate_limiter.start_session(peer_id, PROTOCOL, 0, now).unwrap();
+    rate_limiter.remove_peer(&peer_id);
+    rate_limiter.start_session(peer_id, PROTOCOL, 0, now).unwrap();
+}
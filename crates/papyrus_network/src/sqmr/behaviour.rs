@@ -154,6 +154,54 @@ impl Behaviour {
         outbound_session_id
     }
 
+    /// Start a query on the given connection of the given peer, without asking for a peer
+    /// assignment. Return the id of the new session.
+    pub fn start_query_with_peer(
+        &mut self,
+        query: Bytes,
+        protocol_name: StreamProtocol,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+    ) -> OutboundSessionId {
+        let outbound_session_id = self.next_outbound_session_id;
+        self.next_outbound_session_id.value += 1;
+
+        debug!(
+            "Network received new outbound query for peer {:?}. Starting {:?}.",
+            peer_id, outbound_session_id
+        );
+        self.create_outbound_session(
+            query,
+            protocol_name,
+            outbound_session_id,
+            peer_id,
+            connection_id,
+        );
+
+        outbound_session_id
+    }
+
+    fn create_outbound_session(
+        &mut self,
+        query: Bytes,
+        protocol_name: StreamProtocol,
+        outbound_session_id: OutboundSessionId,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+    ) {
+        self.session_id_to_peer_id_and_connection_id
+            .insert(outbound_session_id.into(), (peer_id, connection_id));
+        self.add_event_to_queue(ToSwarm::NotifyHandler {
+            peer_id,
+            handler: NotifyHandler::One(connection_id),
+            event: RequestFromBehaviourEvent::CreateOutboundSession {
+                query,
+                outbound_session_id,
+                protocol_name,
+            },
+        });
+    }
+
     /// Send a response message to an open inbound session.
     pub fn send_response(
         &mut self,
@@ -351,9 +399,6 @@ impl BridgedBehaviour for Behaviour {
             "Assigned peer {:?} to session {:?} with connection id: {:?}",
             peer_id, outbound_session_id, connection_id
         );
-        self.session_id_to_peer_id_and_connection_id
-            .insert((*outbound_session_id).into(), (*peer_id, *connection_id));
-
         let Some((query, protocol_name)) =
             self.outbound_sessions_pending_peer_assignment.remove(outbound_session_id)
         else {
@@ -364,15 +409,13 @@ impl BridgedBehaviour for Behaviour {
             return;
         };
 
-        self.add_event_to_queue(ToSwarm::NotifyHandler {
-            peer_id: *peer_id,
-            handler: NotifyHandler::One(*connection_id),
-            event: RequestFromBehaviourEvent::CreateOutboundSession {
-                query,
-                outbound_session_id: *outbound_session_id,
-                protocol_name,
-            },
-        });
+        self.create_outbound_session(
+            query,
+            protocol_name,
+            *outbound_session_id,
+            *peer_id,
+            *connection_id,
+        );
     }
 }
 
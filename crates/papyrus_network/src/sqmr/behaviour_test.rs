@@ -342,6 +342,30 @@ async fn create_and_process_outbound_session() {
     validate_no_events(&mut behaviour);
 }
 
+#[tokio::test]
+async fn outbound_session_with_given_peer_skips_peer_assignment() {
+    let mut behaviour = Behaviour::new(Config::get_test_config());
+
+    let peer_id = PeerId::random();
+
+    let outbound_session_id = behaviour.start_query_with_peer(
+        QUERY.clone(),
+        PROTOCOL_NAME.clone(),
+        peer_id,
+        ConnectionId::new_unchecked(0),
+    );
+
+    validate_create_outbound_session_event(&mut behaviour, &peer_id, &QUERY, &outbound_session_id)
+        .await;
+    validate_no_events(&mut behaviour);
+    assert_eq!(
+        behaviour
+            .get_peer_id_and_connection_id_from_session_id(outbound_session_id.into())
+            .unwrap(),
+        (peer_id, ConnectionId::new_unchecked(0))
+    );
+}
+
 // TODO(shahak): Test the other variants of SessionError.
 #[tokio::test]
 async fn connection_closed() {
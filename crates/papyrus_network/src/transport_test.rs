@@ -0,0 +1,152 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, TcpListener, UdpSocket};
+use std::time::Duration;
+
+use futures::StreamExt;
+use libp2p::core::multiaddr::Protocol;
+use libp2p::identity::Keypair;
+use libp2p::{Multiaddr, PeerId};
+use rstest::rstest;
+use tokio::time::timeout;
+
+use crate::gossipsub_impl::Topic;
+use crate::network_manager::test_utils::network_config_into_broadcast_channels;
+use crate::network_manager::BroadcastTopicClientTrait;
+use crate::sqmr::Bytes;
+use crate::{deserialize_list, list_to_string, NetworkConfig};
+
+const TIMEOUT: Duration = Duration::from_secs(20);
+const RETRY_INTERVAL: Duration = Duration::from_millis(200);
+const SECRET_KEY: [u8; 32] = [1u8; 32];
+
+// Finds a port that is free both for tcp and for udp.
+fn find_free_port() -> u16 {
+    loop {
+        let port =
+            TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap().local_addr().unwrap().port();
+        if UdpSocket::bind((Ipv4Addr::LOCALHOST, port)).is_ok() {
+            return port;
+        }
+    }
+}
+
+fn bootstrap_peer_multiaddr(ip_address: IpAddr, port: u16, quic: bool) -> Multiaddr {
+    let peer_id =
+        PeerId::from_public_key(&Keypair::ed25519_from_bytes(SECRET_KEY).unwrap().public());
+    let address = Multiaddr::from(ip_address);
+    let address = if quic {
+        address.with(Protocol::Udp(port)).with(Protocol::QuicV1)
+    } else {
+        address.with(Protocol::Tcp(port))
+    };
+    address.with(Protocol::P2p(peer_id))
+}
+
+#[test]
+fn listen_multiaddrs() {
+    let additional_multiaddr: Multiaddr = "/ip4/127.0.0.1/tcp/10001".parse().unwrap();
+    let config = NetworkConfig {
+        port: 10000,
+        enable_quic: true,
+        enable_ipv6: true,
+        additional_listen_multiaddrs: vec![additional_multiaddr.clone()],
+        ..Default::default()
+    };
+    let expected_multiaddrs: Vec<Multiaddr> = vec![
+        "/ip4/0.0.0.0/tcp/10000".parse().unwrap(),
+        "/ip4/0.0.0.0/udp/10000/quic-v1".parse().unwrap(),
+        "/ip6/::/tcp/10000".parse().unwrap(),
+        "/ip6/::/udp/10000/quic-v1".parse().unwrap(),
+        additional_multiaddr,
+    ];
+    assert_eq!(config.listen_multiaddrs(), expected_multiaddrs);
+
+    let default_config = NetworkConfig { port: 10000, ..Default::default() };
+    assert_eq!(default_config.listen_multiaddrs(), vec![expected_multiaddrs[0].clone()]);
+}
+
+#[test]
+fn deprecated_bootstrap_peer_is_added() {
+    let bootstrap_peers: Vec<Multiaddr> = vec![
+        "/ip4/127.0.0.1/tcp/10001".parse().unwrap(),
+        "/ip4/127.0.0.1/tcp/10002".parse().unwrap(),
+    ];
+    let config = NetworkConfig {
+        bootstrap_peer_multiaddrs: vec![bootstrap_peers[0].clone()],
+        bootstrap_peer_multiaddr: Some(bootstrap_peers[1].clone()),
+        ..Default::default()
+    };
+    assert_eq!(config.bootstrap_peers(), bootstrap_peers);
+
+    // A peer given in both params is connected to once.
+    let config =
+        NetworkConfig { bootstrap_peer_multiaddr: Some(bootstrap_peers[0].clone()), ..config };
+    assert_eq!(config.bootstrap_peers(), vec![bootstrap_peers[0].clone()]);
+}
+
+#[test]
+fn multiaddrs_string_conversion() {
+    let multiaddrs: Vec<Multiaddr> = vec![
+        "/ip4/127.0.0.1/tcp/10001".parse().unwrap(),
+        "/ip6/::1/udp/10001/quic-v1".parse().unwrap(),
+    ];
+    let multiaddrs_string = list_to_string(&multiaddrs);
+    assert_eq!(multiaddrs_string, "/ip4/127.0.0.1/tcp/10001 /ip6/::1/udp/10001/quic-v1");
+    assert_eq!(
+        deserialize_list::<Multiaddr, _>(serde_json::Value::String(multiaddrs_string)).unwrap(),
+        multiaddrs
+    );
+    assert!(
+        deserialize_list::<Multiaddr, _>(serde_json::Value::String(String::new()))
+            .unwrap()
+            .is_empty()
+    );
+}
+
+// Connects two nodes over the given transport, and checks that a broadcast message reaches from
+// one to the other.
+#[rstest]
+#[case::tcp_ipv4(IpAddr::V4(Ipv4Addr::LOCALHOST), false)]
+#[case::quic_ipv4(IpAddr::V4(Ipv4Addr::LOCALHOST), true)]
+#[case::tcp_ipv6(IpAddr::V6(Ipv6Addr::LOCALHOST), false)]
+#[case::quic_ipv6(IpAddr::V6(Ipv6Addr::LOCALHOST), true)]
+#[tokio::test]
+async fn broadcast_over_transport(#[case] ip_address: IpAddr, #[case] quic: bool) {
+    let topic = Topic::new("TOPIC");
+    let bootstrap_port = find_free_port();
+    let bootstrap_config = NetworkConfig {
+        port: bootstrap_port,
+        enable_quic: true,
+        enable_ipv6: true,
+        secret_key: Some(SECRET_KEY.to_vec()),
+        ..Default::default()
+    };
+    let config = NetworkConfig {
+        port: find_free_port(),
+        enable_quic: quic,
+        enable_ipv6: ip_address.is_ipv6(),
+        bootstrap_peer_multiaddrs: vec![bootstrap_peer_multiaddr(ip_address, bootstrap_port, quic)],
+        ..Default::default()
+    };
+
+    let mut bootstrap_channels =
+        network_config_into_broadcast_channels::<Bytes>(bootstrap_config, topic.clone());
+    let mut channels = network_config_into_broadcast_channels::<Bytes>(config, topic);
+
+    // The message is retried since it's dropped until the nodes are connected and subscribed to
+    // each other's topics.
+    let message = vec![1u8];
+    timeout(TIMEOUT, async {
+        loop {
+            channels.broadcast_topic_client.broadcast_message(message.clone()).await.unwrap();
+            if let Ok(Some((received_message, _))) =
+                timeout(RETRY_INTERVAL, bootstrap_channels.broadcasted_messages_receiver.next())
+                    .await
+            {
+                assert_eq!(received_message.unwrap(), message);
+                return;
+            }
+        }
+    })
+    .await
+    .expect("The broadcast message was not received.");
+}
@@ -1,4 +1,4 @@
-use core::net::Ipv4Addr;
+use core::net::{Ipv4Addr, Ipv6Addr};
 use std::collections::hash_map::{Keys, ValuesMut};
 use std::collections::HashMap;
 use std::hash::Hash;
@@ -38,6 +38,10 @@ impl<K: Unpin + Clone + Eq + Hash, V: Stream + Unpin> StreamHashMap<K, V> {
         self.map.get_mut(key)
     }
 
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.map.remove(key)
+    }
+
     pub fn insert(&mut self, key: K, value: V) -> Option<V> {
         let res = self.map.insert(key, value);
         for waker in self.wakers_waiting_for_new_stream.drain(..) {
@@ -77,12 +81,9 @@ impl<K: Unpin + Clone + Eq + Hash, V: Stream + Unpin> Stream for StreamHashMap<K
 }
 
 pub fn is_localhost(address: &Multiaddr) -> bool {
-    let maybe_ip4_address = address.iter().find_map(|protocol| match protocol {
-        Protocol::Ip4(ip4_address) => Some(ip4_address),
-        _ => None,
-    });
-    let Some(ip4_address) = maybe_ip4_address else {
-        return false;
-    };
-    ip4_address == Ipv4Addr::LOCALHOST
+    address.iter().any(|protocol| match protocol {
+        Protocol::Ip4(ip4_address) => ip4_address == Ipv4Addr::LOCALHOST,
+        Protocol::Ip6(ip6_address) => ip6_address == Ipv6Addr::LOCALHOST,
+        _ => false,
+    })
 }
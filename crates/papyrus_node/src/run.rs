@@ -7,6 +7,7 @@ use std::process::exit;
 use std::sync::Arc;
 use std::time::Duration;
 
+use futures::channel::mpsc::Sender;
 use futures::StreamExt;
 use papyrus_base_layer::ethereum_base_layer_contract::EthereumBaseLayerConfig;
 use papyrus_common::metrics::COLLECT_PROFILING_METRICS;
@@ -14,7 +15,7 @@ use papyrus_common::pending_classes::PendingClasses;
 use papyrus_config::presentation::get_config_presentation;
 use papyrus_config::validators::config_validate;
 use papyrus_monitoring_gateway::MonitoringServer;
-use papyrus_network::network_manager::NetworkManager;
+use papyrus_network::network_manager::{NetworkManager, NetworkRequest};
 use papyrus_network::{network_manager, NetworkConfig};
 use papyrus_p2p_sync::client::{P2pSyncClient, P2pSyncClientChannels};
 use papyrus_p2p_sync::server::{P2pSyncServer, P2pSyncServerChannels};
@@ -162,6 +163,7 @@ async fn spawn_rpc_server(
 fn spawn_monitoring_server(
     storage_reader: StorageReader,
     local_peer_id: String,
+    network_request_sender: Option<Sender<NetworkRequest>>,
     config: &NodeConfig,
 ) -> anyhow::Result<JoinHandle<anyhow::Result<()>>> {
     let monitoring_server = MonitoringServer::new(
@@ -171,6 +173,7 @@ fn spawn_monitoring_server(
         storage_reader,
         VERSION_FULL,
         local_peer_id,
+        network_request_sender,
     )?;
     Ok(tokio::spawn(async move { Ok(monitoring_server.run_server().await?) }))
 }
@@ -217,7 +220,7 @@ async fn spawn_sync_client(
     pending_classes: Arc<RwLock<PendingClasses>>,
     class_manager_client: SharedClassManagerClient,
 ) -> JoinHandle<anyhow::Result<()>> {
-    match (config.sync, config.p2p_sync) {
+    match (config.sync.clone(), config.p2p_sync.clone()) {
         (Some(_), Some(_)) => {
             panic!("One of --sync.#is_none or --p2p_sync.#is_none must be turned on");
         }
@@ -244,14 +247,18 @@ async fn spawn_sync_client(
                 .register_sqmr_protocol_client(Protocol::Transaction.into(), BUFFER_SIZE);
             let class_client_sender =
                 network_manager.register_sqmr_protocol_client(Protocol::Class.into(), BUFFER_SIZE);
+            let event_client_sender =
+                network_manager.register_sqmr_protocol_client(Protocol::Event.into(), BUFFER_SIZE);
             let p2p_sync_client_channels = P2pSyncClientChannels::new(
                 header_client_sender,
                 state_diff_client_sender,
                 transaction_client_sender,
                 class_client_sender,
+                event_client_sender,
             );
             let p2p_sync = P2pSyncClient::new(
                 p2p_sync_client_config,
+                config.storage.db_config.chain_id.clone(),
                 storage_reader,
                 storage_writer,
                 p2p_sync_client_channels,
@@ -321,6 +328,10 @@ async fn run_threads(
         spawn_monitoring_server(
             resources.storage_reader.clone(),
             resources.local_peer_id.clone(),
+            resources
+                .maybe_network_manager
+                .as_ref()
+                .map(|network_manager| network_manager.get_network_request_sender()),
             &config,
         )?
     };
@@ -1,24 +1,28 @@
-use std::cmp::min;
 use std::collections::HashMap;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_stream::stream;
 use futures::channel::mpsc::Receiver;
 use futures::future::BoxFuture;
-use futures::stream::BoxStream;
+use futures::stream::{BoxStream, SelectAll};
 use futures::{FutureExt, StreamExt};
 use papyrus_network::network_manager::{ClientResponsesManager, SqmrClientSender};
 use papyrus_protobuf::converters::ProtobufConversionError;
 use papyrus_protobuf::sync::{BlockHashOrNumber, DataOrFin, Direction, Query};
+use papyrus_storage::body::BodyStorageReader;
 use papyrus_storage::header::HeaderStorageReader;
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{StorageError, StorageReader, StorageWriter};
-use starknet_api::block::{BlockNumber, BlockSignature};
+use papyrus_sync::state_root::StateRootVerifier;
+use starknet_api::block::{BlockHash, BlockNumber, BlockSignature, StarknetVersion};
 use starknet_api::core::ClassHash;
+use starknet_api::transaction::TransactionHash;
 use starknet_class_manager_types::SharedClassManagerClient;
 use starknet_state_sync_types::state_sync_types::SyncBlock;
 use tracing::{debug, info, trace, warn};
 
+use super::commitments::VerificationParams;
+use super::download_scheduler::{BlockRange, DownloadScheduler, RangeEnd};
 use super::{P2pSyncClientError, STEP};
 
 pub type BlockDataResult = Result<Box<dyn BlockData>, P2pSyncClientError>;
@@ -31,6 +35,7 @@ pub(crate) trait BlockData: Send {
         self: Box<Self>,
         storage_writer: &'a mut StorageWriter,
         class_manager_client: &'a mut SharedClassManagerClient,
+        state_root_verifier: &'a mut Option<StateRootVerifier>,
     ) -> BoxFuture<'a, Result<(), P2pSyncClientError>>;
 }
 
@@ -38,6 +43,7 @@ pub(crate) enum BlockNumberLimit {
     Unlimited,
     HeaderMarker,
     StateDiffMarker,
+    BodyMarker,
 }
 
 pub(crate) trait BlockDataStreamBuilder<InputFromNetwork>
@@ -49,6 +55,9 @@ where
 
     const TYPE_DESCRIPTION: &'static str;
     const BLOCK_NUMBER_LIMIT: BlockNumberLimit;
+    /// Whether internal blocks are also held back until they're below the block number limit,
+    /// because they can't be written before the data of the protocol this stream depends on.
+    const INTERNAL_BLOCKS_WAIT_FOR_LIMIT: bool = false;
 
     // Async functions in trait don't work well with argument references
     /// Parse data for a specific block received from the network and return a future resolving to
@@ -57,8 +66,16 @@ where
         client_response_manager: &'a mut ClientResponsesManager<DataOrFin<InputFromNetwork>>,
         block_number: BlockNumber,
         storage_reader: &'a StorageReader,
+        verification_params: &'a VerificationParams,
     ) -> BoxFuture<'a, Result<Option<Self::Output>, ParseDataError>>;
 
+    /// Returns the hash of the block and the hash of its parent, for streams whose data carries
+    /// them. The blocks of each query are checked to form a chain, and the first block of each
+    /// query is checked against the block that was handed over before it.
+    fn block_and_parent_hashes(_output: &Self::Output) -> Option<(BlockHash, BlockHash)> {
+        None
+    }
+
     /// Get the starting block number for this stream.
     fn get_start_block_number(storage_reader: &StorageReader) -> Result<BlockNumber, StorageError>;
 
@@ -97,142 +114,258 @@ where
         .boxed()
     }
 
-    /// Create a stream for fetching and processing block data.
+    /// Create a stream for fetching and processing block data. The blocks are downloaded in
+    /// ranges that are queried concurrently, and are yielded in order.
+    #[allow(clippy::too_many_arguments)]
     fn create_stream<TQuery>(
         mut sqmr_sender: SqmrClientSender<TQuery, DataOrFin<InputFromNetwork>>,
         storage_reader: StorageReader,
+        verification_params: VerificationParams,
         mut internal_block_receiver: Option<Receiver<SyncBlock>>,
         wait_period_for_new_data: Duration,
         wait_period_for_other_protocol: Duration,
         num_blocks_per_query: u64,
+        num_concurrent_queries: usize,
     ) -> BoxStream<'static, BlockDataResult>
     where
         TQuery: From<Query> + Send + 'static,
         Vec<u8>: From<TQuery>,
     {
         stream! {
-            let mut current_block_number = Self::get_start_block_number(&storage_reader)?;
+            let mut scheduler = DownloadScheduler::new(
+                Self::get_start_block_number(&storage_reader)?,
+                num_blocks_per_query,
+                num_concurrent_queries,
+            );
             let mut internal_blocks_received = HashMap::new();
-            'send_query_and_parse_responses: loop {
-                let limit = match Self::BLOCK_NUMBER_LIMIT {
-                    BlockNumberLimit::Unlimited => num_blocks_per_query,
-                    BlockNumberLimit::HeaderMarker | BlockNumberLimit::StateDiffMarker => {
-                        let (last_block_number, description) = match Self::BLOCK_NUMBER_LIMIT {
-                            BlockNumberLimit::HeaderMarker => (storage_reader.begin_ro_txn()?.get_header_marker()?, "header"),
-                            BlockNumberLimit::StateDiffMarker => (storage_reader.begin_ro_txn()?.get_state_marker()?, "state diff"),
-                            _ => unreachable!(),
-                        };
-                        let limit = min(last_block_number.0 - current_block_number.0, num_blocks_per_query);
-                        if limit == 0 {
-                            trace!("{:?} sync is waiting for a new {}", Self::TYPE_DESCRIPTION, description);
-                            tokio::time::sleep(wait_period_for_other_protocol).await;
+            let mut ranges = SelectAll::new();
+            // The hash of the last downloaded block that was yielded. The ranges are downloaded
+            // concurrently, so the first block of a range can only be checked against it once the
+            // blocks before it were yielded.
+            let mut previous_block_hash = None;
+            loop {
+                let (end_limit, description) = match Self::BLOCK_NUMBER_LIMIT {
+                    BlockNumberLimit::Unlimited => (None, "block"),
+                    BlockNumberLimit::HeaderMarker => (Some(storage_reader.begin_ro_txn()?.get_header_marker()?), "header"),
+                    BlockNumberLimit::StateDiffMarker => (Some(storage_reader.begin_ro_txn()?.get_state_marker()?), "state diff"),
+                    BlockNumberLimit::BodyMarker => (Some(storage_reader.begin_ro_txn()?.get_body_marker()?), "body"),
+                };
+                let internal_block_allowed = |block_number: BlockNumber| {
+                    !Self::INTERNAL_BLOCKS_WAIT_FOR_LIMIT
+                        || end_limit.map_or(true, |end_limit| block_number < end_limit)
+                };
+
+                // Yield the blocks that are ready, preferring internal blocks over downloaded ones.
+                loop {
+                    let block_number = scheduler.next_block_number();
+                    if internal_block_allowed(block_number) {
+                        if let Some(block) = Self::get_internal_block_at(&mut internal_blocks_received, &mut internal_block_receiver, block_number)
+                            .now_or_never()
+                        {
+                            info!("Added internally {:?} for block {}.", Self::TYPE_DESCRIPTION, block_number);
+                            scheduler.skip_block();
+                            previous_block_hash = None;
+                            yield Ok(Box::<dyn BlockData>::from(Box::new(block)));
                             continue;
                         }
-                        limit
-                    },
-                };
-                if let Some(block) = Self::get_internal_block_at(&mut internal_blocks_received, &mut internal_block_receiver, current_block_number)
-                    .now_or_never()
-                {
-                    info!("Added internally {:?} for block {}.", Self::TYPE_DESCRIPTION, current_block_number);
+                    }
+                    let Some(block_and_parent_hashes) =
+                        scheduler.peek_next_block().map(Self::block_and_parent_hashes)
+                    else {
+                        break;
+                    };
+                    if let Some((block_hash, parent_hash)) = block_and_parent_hashes {
+                        if previous_block_hash.is_some_and(|previous_block_hash| previous_block_hash != parent_hash) {
+                            warn!(
+                                "The parent hash of {:?} for block {} doesn't match the previous \
+                                 block. Querying it again.",
+                                Self::TYPE_DESCRIPTION, block_number
+                            );
+                            scheduler.reject_next_block();
+                            break;
+                        }
+                        previous_block_hash = Some(block_hash);
+                    }
+                    let block = scheduler.pop_next_block().expect("The next block was downloaded.");
+                    info!("Added {:?} for block {}.", Self::TYPE_DESCRIPTION, block_number);
                     yield Ok(Box::<dyn BlockData>::from(Box::new(block)));
-                    current_block_number = current_block_number.unchecked_next();
-                    continue 'send_query_and_parse_responses;
                 }
 
-                let end_block_number = current_block_number.0 + limit;
-                debug!(
-                    "Sync sent query for {:?} for blocks [{}, {}) from network.",
-                    Self::TYPE_DESCRIPTION,
-                    current_block_number.0,
-                    end_block_number,
-                );
+                while let Some(range) = scheduler.next_range(end_limit) {
+                    debug!(
+                        "Sync sent query for {:?} for blocks [{}, {}) from network. Current range \
+                         size is {}.",
+                        Self::TYPE_DESCRIPTION,
+                        range.start.0,
+                        range.end.0,
+                        scheduler.range_size(),
+                    );
+                    let client_response_manager = sqmr_sender
+                        .send_new_query(
+                            TQuery::from(Query {
+                                start_block: BlockHashOrNumber::Number(range.start),
+                                direction: Direction::Forward,
+                                limit: range.len(),
+                                step: STEP,
+                            })
+                        ).await?;
+                    ranges.push(Self::download_range(
+                        client_response_manager,
+                        range,
+                        storage_reader.clone(),
+                        verification_params.clone(),
+                        wait_period_for_new_data,
+                    ));
+                }
 
-                // TODO(shahak): Use the report callback.
-                let mut client_response_manager = sqmr_sender
-                    .send_new_query(
-                        TQuery::from(Query {
-                            start_block: BlockHashOrNumber::Number(current_block_number),
-                            direction: Direction::Forward,
-                            limit,
-                            step: STEP,
-                        })
-                    ).await?;
-                while current_block_number.0 < end_block_number {
-                    tokio::select! {
-                        res = Self::parse_data_for_block(
-                            &mut client_response_manager, current_block_number, &storage_reader
-                        ) => {
-                            match res {
-                                Ok(Some(output)) => {
-                                    info!("Added {:?} for block {}.", Self::TYPE_DESCRIPTION, current_block_number);
-                                    current_block_number = current_block_number.unchecked_next();
-                                    yield Ok(Box::<dyn BlockData>::from(Box::new(output)));
-                                }
-                                Ok(None) => {
-                                    debug!(
-                                        "Query for {:?} on {:?} returned with partial data. Waiting {:?} before \
-                                         sending another query.",
-                                        Self::TYPE_DESCRIPTION, current_block_number, wait_period_for_new_data
-                                    );
-                                    tokio::time::sleep(wait_period_for_new_data).await;
-                                    continue 'send_query_and_parse_responses;
-                                },
-                                Err(ParseDataError::BadPeer(err)) => {
-                                    warn!(
-                                        "Query for {:?} on {:?} returned with bad peer error: {:?}. reporting \
-                                         peer and retrying query.",
-                                        Self::TYPE_DESCRIPTION, current_block_number, err
-                                    );
-                                    client_response_manager.report_peer();
-                                    continue 'send_query_and_parse_responses;
-                                },
-                                Err(ParseDataError::Fatal(err)) => {
-                                    yield Err(err);
-                                    return;
-                                },
+                if ranges.is_empty() {
+                    trace!("{:?} sync is waiting for a new {}", Self::TYPE_DESCRIPTION, description);
+                    tokio::time::sleep(wait_period_for_other_protocol).await;
+                    continue;
+                }
+
+                tokio::select! {
+                    Some(range_event) = ranges.next() => {
+                        match range_event {
+                            RangeEvent::Block(block_number, output) => {
+                                scheduler.add_block(block_number, output);
                             }
-                        }
-                        block = Self::get_internal_block_at(&mut internal_blocks_received, &mut internal_block_receiver, current_block_number) => {
-                                info!("Added internally {:?} for block {}.", Self::TYPE_DESCRIPTION, current_block_number);
-                                current_block_number = current_block_number.unchecked_next();
-                                yield Ok(Box::<dyn BlockData>::from(Box::new(block)));
-                                debug!("Network query ending at block {} for {:?} being ignored due to internal block", end_block_number, Self::TYPE_DESCRIPTION);
-                                continue 'send_query_and_parse_responses;
+                            RangeEvent::End { range, next_missing_block_number, range_end } => {
+                                scheduler.finish_range(range, next_missing_block_number, range_end);
+                            }
+                            RangeEvent::Fatal(err) => {
+                                yield Err(err);
+                                return;
                             }
                         }
                     }
+                    block = Self::get_internal_block_at(&mut internal_blocks_received, &mut internal_block_receiver, scheduler.next_block_number()), if internal_block_allowed(scheduler.next_block_number()) => {
+                        info!("Added internally {:?} for block {}.", Self::TYPE_DESCRIPTION, scheduler.next_block_number());
+                        scheduler.skip_block();
+                        previous_block_hash = None;
+                        yield Ok(Box::<dyn BlockData>::from(Box::new(block)));
+                    }
+                    // The ranges that ended were removed and the internal block can't be written
+                    // yet.
+                    else => {}
+                }
+            }
+        }.boxed()
+    }
 
-                // Consume the None message signaling the end of the query.
-                match client_response_manager.next().await {
-                    Some(Ok(DataOrFin(None))) => {
-                        debug!("Network query ending at block {} for {:?} finished", end_block_number, Self::TYPE_DESCRIPTION);
-                    },
-                    Some(_) => {
-                        warn!(
-                            "Query for {:?} returned more messages after {:?} even though it \
-                            should have returned Fin. reporting peer and retrying query.",
-                            Self::TYPE_DESCRIPTION, current_block_number
+    /// Parse the responses of a query for the given range. Streams the blocks that were parsed
+    /// and then how the query ended.
+    fn download_range(
+        mut client_response_manager: ClientResponsesManager<DataOrFin<InputFromNetwork>>,
+        range: BlockRange,
+        storage_reader: StorageReader,
+        verification_params: VerificationParams,
+        wait_period_for_new_data: Duration,
+    ) -> BoxStream<'static, RangeEvent<Self::Output>> {
+        stream! {
+            let start_time = Instant::now();
+            let mut current_block_number = range.start;
+            let mut previous_block_hash = None;
+            while current_block_number < range.end {
+                let mut parse_result = Self::parse_data_for_block(
+                    &mut client_response_manager, current_block_number, &storage_reader, &verification_params
+                ).await;
+                if let Ok(Some(output)) = &parse_result {
+                    if let Some((block_hash, parent_hash)) = Self::block_and_parent_hashes(output) {
+                        match previous_block_hash {
+                            Some(previous_block_hash) if previous_block_hash != parent_hash => {
+                                parse_result = Err(ParseDataError::BadPeer(BadPeerError::ParentHashMismatch {
+                                    block_number: current_block_number,
+                                    parent_hash,
+                                    previous_block_hash,
+                                }));
+                            }
+                            _ => previous_block_hash = Some(block_hash),
+                        }
+                    }
+                }
+                match parse_result {
+                    Ok(Some(output)) => {
+                        yield RangeEvent::Block(current_block_number, output);
+                        current_block_number = current_block_number.unchecked_next();
+                    }
+                    Ok(None) => {
+                        debug!(
+                            "Query for {:?} on {:?} returned with partial data. Waiting {:?} before \
+                             sending another query.",
+                            Self::TYPE_DESCRIPTION, current_block_number, wait_period_for_new_data
                         );
-                        client_response_manager.report_peer();
-                        continue 'send_query_and_parse_responses;
+                        drop(client_response_manager);
+                        tokio::time::sleep(wait_period_for_new_data).await;
+                        yield RangeEvent::End {
+                            range,
+                            next_missing_block_number: current_block_number,
+                            range_end: RangeEnd::MissingData,
+                        };
+                        return;
                     }
-
-                    None => {
+                    Err(ParseDataError::BadPeer(err)) => {
                         warn!(
-                            "Query for {:?} didn't send Fin after block {:?}. \
-                            Reporting peer and retrying query.",
-                            Self::TYPE_DESCRIPTION, current_block_number
+                            "Query for {:?} on {:?} returned with bad peer error: {:?}. reporting \
+                             peer and retrying query.",
+                            Self::TYPE_DESCRIPTION, current_block_number, err
                         );
                         client_response_manager.report_peer();
-                        continue 'send_query_and_parse_responses;
+                        yield RangeEvent::End {
+                            range,
+                            next_missing_block_number: current_block_number,
+                            range_end: RangeEnd::BadPeer,
+                        };
+                        return;
+                    }
+                    Err(ParseDataError::Fatal(err)) => {
+                        yield RangeEvent::Fatal(err);
+                        return;
                     }
                 }
             }
+
+            // Consume the None message signaling the end of the query.
+            let range_end = match client_response_manager.next().await {
+                Some(Ok(DataOrFin(None))) => {
+                    debug!("Network query ending at block {} for {:?} finished", range.end, Self::TYPE_DESCRIPTION);
+                    RangeEnd::Completed { elapsed: start_time.elapsed() }
+                },
+                Some(_) => {
+                    warn!(
+                        "Query for {:?} returned more messages after {:?} even though it \
+                        should have returned Fin. reporting peer.",
+                        Self::TYPE_DESCRIPTION, current_block_number
+                    );
+                    client_response_manager.report_peer();
+                    RangeEnd::BadPeer
+                }
+                None => {
+                    warn!(
+                        "Query for {:?} didn't send Fin after block {:?}. Reporting peer.",
+                        Self::TYPE_DESCRIPTION, current_block_number
+                    );
+                    client_response_manager.report_peer();
+                    RangeEnd::BadPeer
+                }
+            };
+            yield RangeEvent::End { range, next_missing_block_number: current_block_number, range_end };
         }.boxed()
     }
 }
 
+pub(crate) enum RangeEvent<Output> {
+    /// A block of the range was downloaded.
+    Block(BlockNumber, Output),
+    /// The query of the range ended. It's the last event of the range.
+    End {
+        range: BlockRange,
+        next_missing_block_number: BlockNumber,
+        range_end: RangeEnd,
+    },
+    Fatal(P2pSyncClientError),
+}
+
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum BadPeerError {
     #[error("The sender end of the response receivers for {type_description:?} was closed.")]
@@ -271,6 +404,57 @@ pub(crate) enum BadPeerError {
     ClassNotInStateDiff { class_hash: ClassHash },
     #[error("Received two classes with the same hash: {class_hash}.")]
     DuplicateClass { class_hash: ClassHash },
+    #[error(
+        "Expected to receive {expected} events for {block_number} from the network. Got {actual} \
+         events instead."
+    )]
+    NotEnoughEvents { expected: usize, actual: usize, block_number: u64 },
+    #[error(
+        "Received an event of transaction {transaction_hash} that isn't in {block_number}, or \
+         that comes after the transaction of a later event."
+    )]
+    EventOfUnexpectedTransaction { transaction_hash: TransactionHash, block_number: BlockNumber },
+    #[error(
+        "The hash of a transaction in {block_number} is {received}, but it doesn't match the hash \
+         calculated from the transaction."
+    )]
+    WrongTransactionHash { block_number: BlockNumber, received: TransactionHash },
+    #[error(
+        "The header of {block_number} claims Starknet version {starknet_version}, which is lower \
+         than the version {parent_starknet_version} of its parent."
+    )]
+    StarknetVersionDecreased {
+        block_number: BlockNumber,
+        starknet_version: StarknetVersion,
+        parent_starknet_version: StarknetVersion,
+    },
+    #[error(
+        "The header of {block_number} claims Starknet version {starknet_version}, whose block \
+         hash can't be verified, but the headers from {verification_height} must be verified."
+    )]
+    UnverifiableStarknetVersion {
+        block_number: BlockNumber,
+        starknet_version: StarknetVersion,
+        verification_height: BlockNumber,
+    },
+    #[error("The header of {block_number} is missing its {commitment}.")]
+    MissingCommitment { block_number: BlockNumber, commitment: &'static str },
+    #[error(
+        "The hash of {block_number} is {received}, but the hash calculated from its header is \
+         {calculated}."
+    )]
+    WrongBlockHash { block_number: BlockNumber, received: BlockHash, calculated: BlockHash },
+    #[error("The {commitment} commitment of the data of {block_number} doesn't match its header.")]
+    WrongCommitment { block_number: BlockNumber, commitment: &'static str },
+    #[error(
+        "The parent hash of {block_number} is {parent_hash}, but the hash of the previous block \
+         is {previous_block_hash}."
+    )]
+    ParentHashMismatch {
+        block_number: BlockNumber,
+        parent_hash: BlockHash,
+        previous_block_hash: BlockHash,
+    },
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -9,6 +9,7 @@ use papyrus_storage::class_manager::{ClassManagerStorageReader, ClassManagerStor
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{StorageError, StorageReader, StorageWriter};
 use papyrus_sync::define_metrics::SYNC_CLASS_MANAGER_MARKER;
+use papyrus_sync::state_root::StateRootVerifier;
 use starknet_api::block::BlockNumber;
 use starknet_api::core::ClassHash;
 use starknet_api::state::{DeclaredClasses, DeprecatedDeclaredClasses};
@@ -23,6 +24,7 @@ use super::block_data_stream_builder::{
     BlockNumberLimit,
     ParseDataError,
 };
+use super::commitments::VerificationParams;
 use super::P2pSyncClientError;
 
 #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
@@ -31,6 +33,7 @@ impl BlockData for (DeclaredClasses, DeprecatedDeclaredClasses, BlockNumber) {
         self: Box<Self>,
         storage_writer: &'a mut StorageWriter,
         class_manager_client: &'a mut SharedClassManagerClient,
+        _state_root_verifier: &'a mut Option<StateRootVerifier>,
     ) -> BoxFuture<'a, Result<(), P2pSyncClientError>> {
         async move {
             for (class_hash, class) in self.0 {
@@ -91,6 +94,7 @@ impl BlockDataStreamBuilder<(ApiContractClass, ClassHash)> for ClassStreamBuilde
         >,
         block_number: BlockNumber,
         storage_reader: &'a StorageReader,
+        _verification_params: &'a VerificationParams,
     ) -> BoxFuture<'a, Result<Option<Self::Output>, ParseDataError>> {
         async move {
             let (target_class_len, declared_classes, deprecated_declared_classes) = {
@@ -0,0 +1,237 @@
+//! Verifies the data downloaded from the network against the commitments in the block headers.
+//!
+//! Blocks from before Starknet 0.13.2 don't have a block hash that commits to their data, so only
+//! newer blocks are verified. Since the version is claimed by the header itself, a header may not
+//! claim a lower version than its parent, and from the configured verification height every header
+//! must be verifiable. Bodies whose header is missing a commitment (e.g. a header that was received
+//! internally) aren't verified either.
+
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::{db, StorageTxn};
+use starknet_api::block::{BlockHeader, BlockNumber};
+use starknet_api::block_hash::block_hash_calculator::{
+    calculate_block_commitments,
+    calculate_block_hash,
+    concat_counts,
+    BlockHashVersion,
+    BlockHeaderCommitments,
+    TransactionHashingData,
+    TransactionOutputForHash,
+};
+use starknet_api::block_hash::state_diff_hash::calculate_state_diff_hash;
+use starknet_api::core::ChainId;
+use starknet_api::state::ThinStateDiff;
+use starknet_api::transaction::fields::TransactionSignature;
+use starknet_api::transaction::{Event, Transaction, TransactionHash, TransactionOutput};
+
+use super::block_data_stream_builder::{BadPeerError, ParseDataError};
+
+/// The parameters the data downloaded from the network is verified with.
+#[derive(Clone, Debug)]
+pub(crate) struct VerificationParams {
+    /// The chain the hashes of the transactions are calculated for.
+    pub chain_id: ChainId,
+    /// The height from which every header must have a block hash that commits to its data.
+    pub block_hash_verification_height: Option<BlockNumber>,
+}
+
+/// Checks that the header doesn't claim a lower Starknet version than its parent, and that it's
+/// verifiable if it's at or above the verification height.
+pub(crate) fn verify_starknet_version(
+    header: &BlockHeader,
+    parent_header: Option<&BlockHeader>,
+    block_hash_verification_height: Option<BlockNumber>,
+) -> Result<(), BadPeerError> {
+    let block_number = header.block_header_without_hash.block_number;
+    let starknet_version = header.block_header_without_hash.starknet_version;
+    if let Some(parent_header) = parent_header {
+        let parent_starknet_version = parent_header.block_header_without_hash.starknet_version;
+        if starknet_version < parent_starknet_version {
+            return Err(BadPeerError::StarknetVersionDecreased {
+                block_number,
+                starknet_version,
+                parent_starknet_version,
+            });
+        }
+    }
+    if let Some(verification_height) = block_hash_verification_height {
+        if block_number >= verification_height && !is_verifiable(header) {
+            return Err(BadPeerError::UnverifiableStarknetVersion {
+                block_number,
+                starknet_version,
+                verification_height,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the hash of the header matches its content and its commitments.
+pub(crate) fn verify_block_hash(header: &BlockHeader) -> Result<(), BadPeerError> {
+    if !is_verifiable(header) {
+        return Ok(());
+    }
+    let block_number = header.block_header_without_hash.block_number;
+    let missing = |commitment| BadPeerError::MissingCommitment { block_number, commitment };
+    let state_diff_length = header.state_diff_length.ok_or_else(|| missing("state diff length"))?;
+    let commitments = BlockHeaderCommitments {
+        transaction_commitment: header
+            .transaction_commitment
+            .ok_or_else(|| missing("transaction commitment"))?,
+        event_commitment: header.event_commitment.ok_or_else(|| missing("event commitment"))?,
+        receipt_commitment: header
+            .receipt_commitment
+            .ok_or_else(|| missing("receipt commitment"))?,
+        state_diff_commitment: header
+            .state_diff_commitment
+            .ok_or_else(|| missing("state diff commitment"))?,
+        concatenated_counts: concat_counts(
+            header.n_transactions,
+            header.n_events,
+            state_diff_length,
+            header.block_header_without_hash.l1_da_mode,
+        ),
+    };
+    let calculated_block_hash =
+        calculate_block_hash(header.block_header_without_hash.clone(), commitments)
+            .expect("The block hash of a verifiable version should be calculable");
+    if calculated_block_hash != header.block_hash {
+        return Err(BadPeerError::WrongBlockHash {
+            block_number,
+            received: header.block_hash,
+            calculated: calculated_block_hash,
+        });
+    }
+    Ok(())
+}
+
+/// Checks the transactions and their outputs against the transaction and receipt commitments. The
+/// outputs are expected to be without their events, which are verified separately.
+pub(crate) fn verify_transactions(
+    header: &BlockHeader,
+    transactions: &[Transaction],
+    transaction_outputs: &[TransactionOutput],
+    transaction_hashes: &[TransactionHash],
+) -> Result<(), BadPeerError> {
+    let (Some(transaction_commitment), Some(receipt_commitment)) =
+        (header.transaction_commitment, header.receipt_commitment)
+    else {
+        return Ok(());
+    };
+    if !is_verifiable(header) {
+        return Ok(());
+    }
+    let transactions_data = transactions
+        .iter()
+        .zip(transaction_outputs)
+        .zip(transaction_hashes)
+        .map(|((transaction, transaction_output), transaction_hash)| {
+            hashing_data(transaction, transaction_output, *transaction_hash, Vec::new())
+        })
+        .collect::<Vec<_>>();
+    let commitments = block_commitments(header, &transactions_data);
+    let block_number = header.block_header_without_hash.block_number;
+    if commitments.transaction_commitment != transaction_commitment {
+        return Err(BadPeerError::WrongCommitment { block_number, commitment: "transaction" });
+    }
+    if commitments.receipt_commitment != receipt_commitment {
+        return Err(BadPeerError::WrongCommitment { block_number, commitment: "receipt" });
+    }
+    Ok(())
+}
+
+/// Checks the events of a block, grouped by the transaction that emitted them, against the event
+/// commitment. The transactions of the block are read from the storage.
+pub(crate) fn verify_events(
+    header: &BlockHeader,
+    txn: &StorageTxn<'_, db::RO>,
+    events: &[Vec<Event>],
+) -> Result<(), ParseDataError> {
+    let Some(event_commitment) = header.event_commitment else {
+        return Ok(());
+    };
+    if !is_verifiable(header) {
+        return Ok(());
+    }
+    let block_number = header.block_header_without_hash.block_number;
+    const BODY_MISSING: &str = "A body with number lower than the body marker is missing";
+    let transactions = txn.get_block_transactions(block_number)?.expect(BODY_MISSING);
+    let transaction_outputs = txn.get_block_transaction_outputs(block_number)?.expect(BODY_MISSING);
+    let transaction_hashes = txn.get_block_transaction_hashes(block_number)?.expect(BODY_MISSING);
+    let transactions_data = transactions
+        .iter()
+        .zip(transaction_outputs.iter())
+        .zip(transaction_hashes)
+        .zip(events.iter().cloned())
+        .map(|(((transaction, transaction_output), transaction_hash), events)| {
+            hashing_data(transaction, transaction_output, transaction_hash, events)
+        })
+        .collect::<Vec<_>>();
+    let commitments = block_commitments(header, &transactions_data);
+    if commitments.event_commitment != event_commitment {
+        return Err(BadPeerError::WrongCommitment { block_number, commitment: "event" }.into());
+    }
+    Ok(())
+}
+
+/// Checks the state diff against the state diff commitment.
+pub(crate) fn verify_state_diff(
+    header: &BlockHeader,
+    state_diff: &ThinStateDiff,
+) -> Result<(), BadPeerError> {
+    let Some(state_diff_commitment) = header.state_diff_commitment else {
+        return Ok(());
+    };
+    if !is_verifiable(header) {
+        return Ok(());
+    }
+    if calculate_state_diff_hash(state_diff) != state_diff_commitment {
+        return Err(BadPeerError::WrongCommitment {
+            block_number: header.block_header_without_hash.block_number,
+            commitment: "state diff",
+        });
+    }
+    Ok(())
+}
+
+fn is_verifiable(header: &BlockHeader) -> bool {
+    BlockHashVersion::try_from(header.block_header_without_hash.starknet_version).is_ok()
+}
+
+// Calculates the commitments of the given transactions. The state diff commitment is irrelevant.
+fn block_commitments(
+    header: &BlockHeader,
+    transactions_data: &[TransactionHashingData],
+) -> BlockHeaderCommitments {
+    calculate_block_commitments(
+        transactions_data,
+        &ThinStateDiff::default(),
+        header.block_header_without_hash.l1_da_mode,
+        &header.block_header_without_hash.starknet_version,
+    )
+}
+
+fn hashing_data(
+    transaction: &Transaction,
+    transaction_output: &TransactionOutput,
+    transaction_hash: TransactionHash,
+    events: Vec<Event>,
+) -> TransactionHashingData {
+    let transaction_signature = match transaction {
+        Transaction::Declare(declare) => declare.signature(),
+        Transaction::DeployAccount(deploy_account) => deploy_account.signature(),
+        Transaction::Invoke(invoke) => invoke.signature(),
+        Transaction::Deploy(_) | Transaction::L1Handler(_) => TransactionSignature::default(),
+    };
+    TransactionHashingData {
+        transaction_signature,
+        transaction_output: TransactionOutputForHash {
+            actual_fee: transaction_output.actual_fee(),
+            events,
+            execution_status: transaction_output.execution_status().clone(),
+            gas_consumed: transaction_output.execution_resources().gas_consumed,
+            messages_sent: transaction_output.messages_sent().clone(),
+        },
+        transaction_hash,
+    }
+}
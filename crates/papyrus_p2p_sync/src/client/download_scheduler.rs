@@ -0,0 +1,209 @@
+//! Splits the blocks a stream needs to download into ranges that are queried concurrently, and
+//! hands over the downloaded blocks in order so they can be written to the storage.
+//!
+//! Each range is sent as a separate query, so the network assigns the ranges to different peers.
+//! The part of a range that wasn't downloaded is queried again before any new range. The size of
+//! the ranges follows the throughput of the queries, so a slow peer is given fewer blocks to
+//! download.
+
+use std::cmp::{max, min};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use starknet_api::block::BlockNumber;
+
+/// The time it should take a peer to send a whole range. Ranges are resized so that the recent
+/// queries would have taken about this long.
+const TARGET_RANGE_DURATION: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct BlockRange {
+    pub start: BlockNumber,
+    /// Exclusive.
+    pub end: BlockNumber,
+}
+
+impl BlockRange {
+    pub fn len(&self) -> u64 {
+        self.end.0.saturating_sub(self.start.0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// How the query of a range ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RangeEnd {
+    /// The peer sent the whole range.
+    Completed { elapsed: Duration },
+    /// The peer doesn't have some of the blocks yet.
+    MissingData,
+    /// The peer misbehaved and was reported.
+    BadPeer,
+}
+
+pub(crate) struct DownloadScheduler<Output> {
+    /// The next block to hand over.
+    next_block_number: BlockNumber,
+    /// The first block that wasn't assigned to any range.
+    next_unassigned_block_number: BlockNumber,
+    /// Ranges that weren't fully downloaded, by their start.
+    failed_ranges: BTreeMap<BlockNumber, BlockNumber>,
+    /// Blocks that were downloaded but can't be handed over until the blocks before them are.
+    downloaded_blocks: BTreeMap<BlockNumber, Output>,
+    num_ranges_in_flight: usize,
+    max_ranges_in_flight: usize,
+    range_size: u64,
+    max_range_size: u64,
+}
+
+impl<Output> DownloadScheduler<Output> {
+    pub fn new(
+        start_block_number: BlockNumber,
+        max_range_size: u64,
+        max_ranges_in_flight: usize,
+    ) -> Self {
+        let max_range_size = max(max_range_size, 1);
+        Self {
+            next_block_number: start_block_number,
+            next_unassigned_block_number: start_block_number,
+            failed_ranges: BTreeMap::new(),
+            downloaded_blocks: BTreeMap::new(),
+            num_ranges_in_flight: 0,
+            max_ranges_in_flight: max(max_ranges_in_flight, 1),
+            range_size: max_range_size,
+            max_range_size,
+        }
+    }
+
+    pub fn next_block_number(&self) -> BlockNumber {
+        self.next_block_number
+    }
+
+    pub fn range_size(&self) -> u64 {
+        self.range_size
+    }
+
+    /// Returns the next range to query, if another query can be sent. Failed ranges are returned
+    /// first, and new ranges never pass `end_limit`. The caller must call `finish_range` once the
+    /// query of the returned range ends.
+    pub fn next_range(&mut self, end_limit: Option<BlockNumber>) -> Option<BlockRange> {
+        if self.num_ranges_in_flight >= self.max_ranges_in_flight {
+            return None;
+        }
+        while let Some((start, end)) = self.failed_ranges.pop_first() {
+            let range = BlockRange { start: max(start, self.next_block_number), end };
+            if !range.is_empty() {
+                self.num_ranges_in_flight += 1;
+                return Some(range);
+            }
+        }
+
+        // Limit how far ahead of the next block we download, so that a single slow range doesn't
+        // cause the other ranges to buffer an unbounded amount of blocks.
+        let max_lookahead = self.max_range_size.saturating_mul(
+            u64::try_from(self.max_ranges_in_flight).expect("usize should fit in u64"),
+        );
+        let lookahead_end = BlockNumber(self.next_block_number.0.saturating_add(max_lookahead));
+        let start = self.next_unassigned_block_number;
+        let mut end = min(BlockNumber(start.0.saturating_add(self.range_size)), lookahead_end);
+        if let Some(end_limit) = end_limit {
+            end = min(end, end_limit);
+        }
+        let range = BlockRange { start, end };
+        if range.is_empty() {
+            return None;
+        }
+        self.next_unassigned_block_number = end;
+        self.num_ranges_in_flight += 1;
+        Some(range)
+    }
+
+    /// Stores a downloaded block until all the blocks before it are handed over. Blocks that were
+    /// already handed over are dropped.
+    pub fn add_block(&mut self, block_number: BlockNumber, output: Output) {
+        if block_number >= self.next_block_number {
+            self.downloaded_blocks.insert(block_number, output);
+        }
+    }
+
+    /// Returns the next block if it was downloaded, without handing it over.
+    pub fn peek_next_block(&self) -> Option<&Output> {
+        self.downloaded_blocks.get(&self.next_block_number)
+    }
+
+    /// Drops the next block, which doesn't follow the blocks that were handed over, together with
+    /// the downloaded blocks after it that may be chained to it, and queries them again before
+    /// any new range.
+    pub fn reject_next_block(&mut self) {
+        let start = self.next_block_number;
+        let mut end = start;
+        while self.downloaded_blocks.remove(&end).is_some() {
+            end = end.unchecked_next();
+        }
+        if start < end {
+            self.failed_ranges.insert(start, end);
+        }
+    }
+
+    /// Hands over the next block if it was downloaded.
+    pub fn pop_next_block(&mut self) -> Option<Output> {
+        let output = self.downloaded_blocks.remove(&self.next_block_number)?;
+        self.advance();
+        Some(output)
+    }
+
+    /// Skips the next block, which was received from another source.
+    pub fn skip_block(&mut self) {
+        self.downloaded_blocks.remove(&self.next_block_number);
+        self.advance();
+    }
+
+    /// Registers the end of a range's query. `next_missing_block_number` is the first block of the
+    /// range that wasn't downloaded, and from it the range is queried again.
+    pub fn finish_range(
+        &mut self,
+        range: BlockRange,
+        next_missing_block_number: BlockNumber,
+        range_end: RangeEnd,
+    ) {
+        self.num_ranges_in_flight = self.num_ranges_in_flight.saturating_sub(1);
+        let next_missing_block_number = max(next_missing_block_number, range.start);
+        if next_missing_block_number < range.end {
+            if range.end == self.next_unassigned_block_number {
+                // Nothing was assigned after this range, so the rest of it can be queried as part
+                // of a new full-sized range.
+                self.next_unassigned_block_number =
+                    max(next_missing_block_number, self.next_block_number);
+            } else {
+                self.failed_ranges.insert(next_missing_block_number, range.end);
+            }
+        }
+        match range_end {
+            RangeEnd::Completed { elapsed } => self.adapt_range_size(range.len(), elapsed),
+            RangeEnd::MissingData => {}
+            RangeEnd::BadPeer => self.range_size = max(self.range_size / 2, 1),
+        }
+    }
+
+    fn adapt_range_size(&mut self, num_blocks: u64, elapsed: Duration) {
+        if num_blocks == 0 {
+            return;
+        }
+        let target_range_size = u128::from(num_blocks)
+            .saturating_mul(TARGET_RANGE_DURATION.as_millis())
+            / max(elapsed.as_millis(), 1);
+        let target_range_size = u64::try_from(target_range_size).unwrap_or(u64::MAX);
+        // Move halfway towards the target, so a single unusual query doesn't dictate the size.
+        let range_size = self.range_size.saturating_add(target_range_size) / 2;
+        self.range_size = range_size.clamp(1, self.max_range_size);
+    }
+
+    fn advance(&mut self) {
+        self.next_block_number = self.next_block_number.unchecked_next();
+        self.next_unassigned_block_number =
+            max(self.next_unassigned_block_number, self.next_block_number);
+    }
+}
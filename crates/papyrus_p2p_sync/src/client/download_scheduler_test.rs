@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use starknet_api::block::BlockNumber;
+
+use super::download_scheduler::{BlockRange, DownloadScheduler, RangeEnd};
+
+fn range(start: u64, end: u64) -> BlockRange {
+    BlockRange { start: BlockNumber(start), end: BlockNumber(end) }
+}
+
+fn add_blocks(scheduler: &mut DownloadScheduler<u64>, blocks: std::ops::Range<u64>) {
+    for block_number in blocks {
+        scheduler.add_block(BlockNumber(block_number), block_number);
+    }
+}
+
+fn pop_all_blocks(scheduler: &mut DownloadScheduler<u64>) -> Vec<u64> {
+    std::iter::from_fn(|| scheduler.pop_next_block()).collect()
+}
+
+#[test]
+fn blocks_are_handed_over_in_order() {
+    let mut scheduler = DownloadScheduler::new(BlockNumber(0), 5, 2);
+    assert_eq!(scheduler.next_range(None), Some(range(0, 5)));
+    assert_eq!(scheduler.next_range(None), Some(range(5, 10)));
+    // Both queries are in flight.
+    assert_eq!(scheduler.next_range(None), None);
+
+    add_blocks(&mut scheduler, 5..10);
+    scheduler.finish_range(
+        range(5, 10),
+        BlockNumber(10),
+        RangeEnd::Completed { elapsed: Duration::ZERO },
+    );
+    assert_eq!(pop_all_blocks(&mut scheduler), Vec::<u64>::new());
+
+    add_blocks(&mut scheduler, 0..3);
+    assert_eq!(pop_all_blocks(&mut scheduler), vec![0, 1, 2]);
+    add_blocks(&mut scheduler, 3..5);
+    assert_eq!(pop_all_blocks(&mut scheduler), (3..10).collect::<Vec<_>>());
+    assert_eq!(scheduler.next_block_number(), BlockNumber(10));
+}
+
+#[test]
+fn failed_range_is_queried_again_before_new_ranges() {
+    let mut scheduler = DownloadScheduler::<u64>::new(BlockNumber(0), 4, 2);
+    assert_eq!(scheduler.next_range(None), Some(range(0, 4)));
+    assert_eq!(scheduler.next_range(None), Some(range(4, 8)));
+
+    add_blocks(&mut scheduler, 0..2);
+    scheduler.finish_range(range(0, 4), BlockNumber(2), RangeEnd::BadPeer);
+    assert_eq!(scheduler.next_range(None), Some(range(2, 4)));
+    // A bad peer shrinks the ranges.
+    assert_eq!(scheduler.range_size(), 2);
+
+    scheduler.finish_range(range(4, 8), BlockNumber(6), RangeEnd::MissingData);
+    assert_eq!(scheduler.next_range(None), Some(range(6, 8)));
+    assert_eq!(scheduler.range_size(), 2);
+}
+
+#[test]
+fn range_size_follows_throughput() {
+    let mut scheduler = DownloadScheduler::<u64>::new(BlockNumber(0), 100, 1);
+    assert_eq!(scheduler.next_range(None), Some(range(0, 100)));
+    // The peer would have sent 50 blocks in the target duration of 10 seconds, so the range size
+    // moves halfway from 100 towards 50.
+    scheduler.finish_range(
+        range(0, 100),
+        BlockNumber(100),
+        RangeEnd::Completed { elapsed: Duration::from_secs(20) },
+    );
+    assert_eq!(scheduler.range_size(), 75);
+
+    add_blocks(&mut scheduler, 0..100);
+    pop_all_blocks(&mut scheduler);
+    assert_eq!(scheduler.next_range(None), Some(range(100, 175)));
+    // The range size never passes the configured maximum.
+    scheduler.finish_range(
+        range(100, 175),
+        BlockNumber(175),
+        RangeEnd::Completed { elapsed: Duration::from_millis(1) },
+    );
+    assert_eq!(scheduler.range_size(), 100);
+}
+
+#[test]
+fn new_ranges_respect_the_end_limit_and_the_lookahead() {
+    let mut scheduler = DownloadScheduler::<u64>::new(BlockNumber(0), 5, 3);
+    assert_eq!(scheduler.next_range(Some(BlockNumber(7))), Some(range(0, 5)));
+    assert_eq!(scheduler.next_range(Some(BlockNumber(7))), Some(range(5, 7)));
+    assert_eq!(scheduler.next_range(Some(BlockNumber(7))), None);
+
+    assert_eq!(scheduler.next_range(None), Some(range(7, 12)));
+    for (start, end) in [(5, 7), (7, 12)] {
+        add_blocks(&mut scheduler, start..end);
+        scheduler.finish_range(
+            range(start, end),
+            BlockNumber(end),
+            RangeEnd::Completed { elapsed: Duration::ZERO },
+        );
+    }
+    // Block 0 is still missing, so only the blocks up to 3 ranges ahead of it are assigned.
+    assert_eq!(scheduler.next_range(None), Some(range(12, 15)));
+    assert_eq!(scheduler.next_range(None), None);
+}
+
+#[test]
+fn skipped_blocks_are_not_downloaded_again() {
+    let mut scheduler = DownloadScheduler::<u64>::new(BlockNumber(0), 5, 1);
+    assert_eq!(scheduler.next_range(None), Some(range(0, 5)));
+    scheduler.finish_range(range(0, 5), BlockNumber(0), RangeEnd::MissingData);
+
+    scheduler.skip_block();
+    scheduler.skip_block();
+    assert_eq!(scheduler.next_range(None), Some(range(2, 7)));
+
+    // Blocks that were skipped while they were downloaded are dropped.
+    add_blocks(&mut scheduler, 2..5);
+    scheduler.skip_block();
+    assert_eq!(pop_all_blocks(&mut scheduler), vec![3, 4]);
+}
+
+#[test]
+fn rejected_blocks_are_queried_again_before_new_ranges() {
+    let mut scheduler = DownloadScheduler::new(BlockNumber(0), 4, 2);
+    assert_eq!(scheduler.next_range(None), Some(range(0, 4)));
+    assert_eq!(scheduler.next_range(None), Some(range(4, 8)));
+    add_blocks(&mut scheduler, 0..7);
+    scheduler.finish_range(
+        range(0, 4),
+        BlockNumber(4),
+        RangeEnd::Completed { elapsed: Duration::ZERO },
+    );
+
+    for block_number in 0..4 {
+        assert_eq!(scheduler.pop_next_block(), Some(block_number));
+    }
+    assert_eq!(scheduler.peek_next_block(), Some(&4));
+    // The downloaded blocks that follow the rejected block are dropped with it.
+    scheduler.reject_next_block();
+    assert_eq!(scheduler.peek_next_block(), None);
+    assert_eq!(scheduler.next_range(None), Some(range(4, 7)));
+    assert_eq!(scheduler.next_block_number(), BlockNumber(4));
+}
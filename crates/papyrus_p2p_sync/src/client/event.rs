@@ -0,0 +1,118 @@
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use papyrus_network::network_manager::ClientResponsesManager;
+use papyrus_protobuf::sync::DataOrFin;
+use papyrus_storage::body::{BodyStorageReader, BodyStorageWriter};
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::{StorageError, StorageReader, StorageWriter};
+use papyrus_sync::state_root::StateRootVerifier;
+use starknet_api::block::BlockNumber;
+use starknet_api::transaction::{Event, TransactionHash};
+use starknet_class_manager_types::SharedClassManagerClient;
+use starknet_state_sync_types::state_sync_types::SyncBlock;
+
+use super::block_data_stream_builder::{
+    BadPeerError,
+    BlockData,
+    BlockDataStreamBuilder,
+    BlockNumberLimit,
+    ParseDataError,
+};
+use super::commitments::{verify_events, VerificationParams};
+use super::P2pSyncClientError;
+
+// The events of a block, grouped by the transaction that emitted them.
+impl BlockData for (Vec<Vec<Event>>, BlockNumber) {
+    fn write_to_storage<'a>(
+        self: Box<Self>,
+        storage_writer: &'a mut StorageWriter,
+        _class_manager_client: &'a mut SharedClassManagerClient,
+        _state_root_verifier: &'a mut Option<StateRootVerifier>,
+    ) -> BoxFuture<'a, Result<(), P2pSyncClientError>> {
+        async move {
+            storage_writer.begin_rw_txn()?.append_events(self.1, self.0)?.commit()?;
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+pub(crate) struct EventStreamBuilder;
+
+impl BlockDataStreamBuilder<(Event, TransactionHash)> for EventStreamBuilder {
+    type Output = (Vec<Vec<Event>>, BlockNumber);
+
+    const TYPE_DESCRIPTION: &'static str = "events";
+    const BLOCK_NUMBER_LIMIT: BlockNumberLimit = BlockNumberLimit::BodyMarker;
+    const INTERNAL_BLOCKS_WAIT_FOR_LIMIT: bool = true;
+
+    fn parse_data_for_block<'a>(
+        events_response_manager: &'a mut ClientResponsesManager<
+            DataOrFin<(Event, TransactionHash)>,
+        >,
+        block_number: BlockNumber,
+        storage_reader: &'a StorageReader,
+        _verification_params: &'a VerificationParams,
+    ) -> BoxFuture<'a, Result<Option<Self::Output>, ParseDataError>> {
+        async move {
+            let header = storage_reader
+                .begin_ro_txn()?
+                .get_block_header(block_number)?
+                .expect("A header with number lower than the body marker is missing");
+            let target_events_len = header.n_events;
+            let transaction_hashes = storage_reader
+                .begin_ro_txn()?
+                .get_block_transaction_hashes(block_number)?
+                .expect("A body with number lower than the body marker is missing");
+
+            let mut events = vec![Vec::new(); transaction_hashes.len()];
+            // The events arrive in the order of the transactions that emitted them, so each event
+            // belongs to the transaction of the previous event or to a later one.
+            let mut transaction_offset = 0;
+            let mut current_events_len = 0;
+            while current_events_len < target_events_len {
+                let maybe_event = events_response_manager.next().await.ok_or(
+                    ParseDataError::BadPeer(BadPeerError::SessionEndedWithoutFin {
+                        type_description: Self::TYPE_DESCRIPTION,
+                    }),
+                )?;
+                let Some((event, transaction_hash)) = maybe_event?.0 else {
+                    if current_events_len == 0 {
+                        return Ok(None);
+                    } else {
+                        return Err(ParseDataError::BadPeer(BadPeerError::NotEnoughEvents {
+                            expected: target_events_len,
+                            actual: current_events_len,
+                            block_number: block_number.0,
+                        }));
+                    }
+                };
+                transaction_offset = transaction_hashes[transaction_offset..]
+                    .iter()
+                    .position(|hash| *hash == transaction_hash)
+                    .map(|position| transaction_offset + position)
+                    .ok_or(ParseDataError::BadPeer(BadPeerError::EventOfUnexpectedTransaction {
+                        transaction_hash,
+                        block_number,
+                    }))?;
+                events[transaction_offset].push(event);
+                current_events_len += 1;
+            }
+            verify_events(&header, &storage_reader.begin_ro_txn()?, &events)?;
+            Ok(Some((events, block_number)))
+        }
+        .boxed()
+    }
+
+    fn get_start_block_number(storage_reader: &StorageReader) -> Result<BlockNumber, StorageError> {
+        storage_reader.begin_ro_txn()?.get_event_marker()
+    }
+
+    // TODO(Eitan): Use real events once SyncBlock contains data required by full nodes
+    fn convert_sync_block_to_block_data(
+        block_number: BlockNumber,
+        sync_block: SyncBlock,
+    ) -> (Vec<Vec<Event>>, BlockNumber) {
+        (vec![Vec::new(); sync_block.transaction_hashes.len()], block_number)
+    }
+}
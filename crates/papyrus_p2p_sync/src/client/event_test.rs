@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use futures::FutureExt;
+use papyrus_protobuf::sync::{BlockHashOrNumber, DataOrFin, Direction, Query};
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::StorageScope;
+use papyrus_test_utils::{get_rng, GetTestInstance};
+use rand_chacha::ChaCha8Rng;
+use starknet_api::block::{BlockBody, BlockNumber};
+use starknet_api::transaction::{Event, FullTransaction, TransactionHash};
+use starknet_types_core::felt::Felt;
+
+use super::test_utils::{
+    random_bodies,
+    random_header,
+    run_test,
+    run_test_with_storage_scope,
+    wait_for_marker,
+    Action,
+    DataType,
+    SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+    TIMEOUT_FOR_TEST,
+};
+
+// The number of events each transaction of a block emits.
+const NUM_EVENTS_PER_TRANSACTION: [&[usize]; 3] = [&[2, 0, 1], &[], &[0, 3]];
+
+#[tokio::test]
+async fn event_basic_flow() {
+    let mut rng = get_rng();
+    let (block_bodies, block_events) = create_bodies_and_events(&mut rng);
+    let num_blocks = u64::try_from(block_bodies.len()).unwrap();
+
+    let mut actions = sync_headers_and_transactions(&mut rng, &block_bodies, &block_events);
+    actions.push(Action::ReceiveQuery(
+        Box::new(move |query| {
+            assert_eq!(
+                query,
+                Query {
+                    start_block: BlockHashOrNumber::Number(BlockNumber(0)),
+                    direction: Direction::Forward,
+                    limit: num_blocks,
+                    step: 1,
+                }
+            )
+        }),
+        DataType::Event,
+    ));
+    for (block_body, events) in block_bodies.iter().zip(block_events.iter()) {
+        for (transaction_hash, transaction_events) in
+            block_body.transaction_hashes.iter().zip(events.iter())
+        {
+            for event in transaction_events {
+                actions
+                    .push(Action::SendEvent(DataOrFin(Some((event.clone(), *transaction_hash)))));
+            }
+        }
+    }
+    actions.push(Action::SendEvent(DataOrFin(None)));
+
+    actions.push(Action::CheckStorage(Box::new(move |reader| {
+        async move {
+            wait_for_marker(
+                DataType::Event,
+                &reader,
+                BlockNumber(num_blocks),
+                SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+                TIMEOUT_FOR_TEST,
+            )
+            .await;
+
+            let txn = reader.begin_ro_txn().unwrap();
+            for (block_number, events) in block_events.into_iter().enumerate() {
+                let block_number = BlockNumber(block_number.try_into().unwrap());
+                let actual_events = txn
+                    .get_block_transaction_outputs(block_number)
+                    .unwrap()
+                    .unwrap()
+                    .iter()
+                    .map(|transaction_output| transaction_output.events().to_vec())
+                    .collect::<Vec<_>>();
+                assert_eq!(actual_events, events);
+            }
+        }
+        .boxed()
+    })));
+
+    run_test(
+        HashMap::from([
+            (DataType::Header, num_blocks),
+            (DataType::Transaction, num_blocks),
+            (DataType::Event, num_blocks),
+        ]),
+        None,
+        actions,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn event_of_unknown_transaction_reports_peer() {
+    let mut rng = get_rng();
+    let (block_bodies, block_events) = create_bodies_and_events(&mut rng);
+    let num_blocks = u64::try_from(block_bodies.len()).unwrap();
+
+    let mut actions = sync_headers_and_transactions(&mut rng, &block_bodies, &block_events);
+    actions.push(Action::ReceiveQuery(Box::new(|_query| ()), DataType::Event));
+    // The transaction hashes of the blocks are small numbers, so this hash isn't in any block.
+    let unknown_transaction_hash = TransactionHash(Felt::from(u64::MAX));
+    actions.push(Action::SendEvent(DataOrFin(Some((
+        Event::get_test_instance(&mut rng),
+        unknown_transaction_hash,
+    )))));
+    actions.push(Action::ValidateReportSent(DataType::Event));
+    actions.push(Action::CheckStorage(Box::new(|reader| {
+        async move {
+            assert_eq!(reader.begin_ro_txn().unwrap().get_event_marker().unwrap(), BlockNumber(0));
+        }
+        .boxed()
+    })));
+
+    run_test(
+        HashMap::from([
+            (DataType::Header, num_blocks),
+            (DataType::Transaction, num_blocks),
+            (DataType::Event, num_blocks),
+        ]),
+        None,
+        actions,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn events_are_not_synced_in_state_only_storage() {
+    let mut rng = get_rng();
+    let (block_bodies, block_events) = create_bodies_and_events(&mut rng);
+    let num_blocks = u64::try_from(block_bodies.len()).unwrap();
+
+    let mut actions = sync_headers_and_transactions(&mut rng, &block_bodies, &block_events);
+    // The bodies of a state only storage have no transactions to match the events against.
+    actions.push(Action::SleepToLetSyncAdvance);
+    actions.push(Action::ValidateNoQuery(DataType::Event));
+    actions.push(Action::CheckStorage(Box::new(|reader| {
+        async move {
+            assert_eq!(reader.begin_ro_txn().unwrap().get_event_marker().unwrap(), BlockNumber(0));
+        }
+        .boxed()
+    })));
+
+    run_test_with_storage_scope(
+        StorageScope::StateOnly,
+        HashMap::from([
+            (DataType::Header, num_blocks),
+            (DataType::Transaction, num_blocks),
+            (DataType::Event, num_blocks),
+        ]),
+        None,
+        actions,
+    )
+    .await;
+}
+
+fn create_bodies_and_events(rng: &mut ChaCha8Rng) -> (Vec<BlockBody>, Vec<Vec<Vec<Event>>>) {
+    let block_bodies = random_bodies(rng, NUM_EVENTS_PER_TRANSACTION.map(<[usize]>::len));
+    let block_events = NUM_EVENTS_PER_TRANSACTION
+        .iter()
+        .map(|num_events_per_transaction| {
+            num_events_per_transaction
+                .iter()
+                .map(|num_events| {
+                    std::iter::repeat_with(|| Event::get_test_instance(rng))
+                        .take(*num_events)
+                        .collect()
+                })
+                .collect()
+        })
+        .collect();
+    (block_bodies, block_events)
+}
+
+// Returns the actions that sync the headers and the transactions of the given blocks, and let the
+// events sync start.
+fn sync_headers_and_transactions(
+    rng: &mut ChaCha8Rng,
+    block_bodies: &[BlockBody],
+    block_events: &[Vec<Vec<Event>>],
+) -> Vec<Action> {
+    let num_blocks = block_bodies.len();
+    let mut actions = vec![
+        Action::RunP2pSync,
+        // We already validate the header query content in other tests.
+        Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+    ];
+    for (i, (block_body, events)) in block_bodies.iter().zip(block_events).enumerate() {
+        let mut header = random_header(
+            rng,
+            BlockNumber(i.try_into().unwrap()),
+            None,
+            Some(block_body.transactions.len()),
+        );
+        header.block_header.n_events = events.iter().map(Vec::len).sum();
+        actions.push(Action::SendHeader(DataOrFin(Some(header))));
+    }
+    actions.push(Action::SendHeader(DataOrFin(None)));
+    actions.push(Action::CheckStorage(Box::new(move |reader| {
+        async move {
+            wait_for_marker(
+                DataType::Header,
+                &reader,
+                BlockNumber(num_blocks.try_into().unwrap()),
+                SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+                TIMEOUT_FOR_TEST,
+            )
+            .await;
+        }
+        .boxed()
+    })));
+    actions.push(Action::SimulateWaitPeriodForOtherProtocol);
+
+    actions.push(Action::ReceiveQuery(Box::new(|_query| ()), DataType::Transaction));
+    for block_body in block_bodies.iter().cloned() {
+        for (transaction, (transaction_output, transaction_hash)) in block_body
+            .transactions
+            .into_iter()
+            .zip(block_body.transaction_outputs.into_iter().zip(block_body.transaction_hashes))
+        {
+            actions.push(Action::SendTransaction(DataOrFin(Some(FullTransaction {
+                transaction,
+                transaction_output,
+                transaction_hash,
+            }))));
+        }
+    }
+    actions.push(Action::SendTransaction(DataOrFin(None)));
+    actions.push(Action::CheckStorage(Box::new(move |reader| {
+        async move {
+            wait_for_marker(
+                DataType::Transaction,
+                &reader,
+                BlockNumber(num_blocks.try_into().unwrap()),
+                SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+                TIMEOUT_FOR_TEST,
+            )
+            .await;
+            // The bodies were written without their events.
+            assert_eq!(reader.begin_ro_txn().unwrap().get_event_marker().unwrap(), BlockNumber(0));
+        }
+        .boxed()
+    })));
+    actions.push(Action::SimulateWaitPeriodForOtherProtocol);
+    actions
+}
@@ -6,6 +6,7 @@ use papyrus_protobuf::sync::{DataOrFin, SignedBlockHeader};
 use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
 use papyrus_storage::{StorageError, StorageReader, StorageWriter};
 use papyrus_sync::define_metrics::{SYNC_HEADER_LATENCY_SEC, SYNC_HEADER_MARKER};
+use papyrus_sync::state_root::StateRootVerifier;
 use starknet_api::block::{BlockHash, BlockHeader, BlockNumber, BlockSignature};
 use starknet_api::hash::StarkHash;
 use starknet_class_manager_types::SharedClassManagerClient;
@@ -19,6 +20,7 @@ use super::block_data_stream_builder::{
     BlockNumberLimit,
     ParseDataError,
 };
+use super::commitments::{verify_block_hash, verify_starknet_version, VerificationParams};
 use super::{P2pSyncClientError, ALLOWED_SIGNATURES_LENGTH};
 
 impl BlockData for SignedBlockHeader {
@@ -27,6 +29,7 @@ impl BlockData for SignedBlockHeader {
         self: Box<Self>,
         storage_writer: &'a mut StorageWriter,
         _class_manager_client: &'a mut SharedClassManagerClient,
+        _state_root_verifier: &'a mut Option<StateRootVerifier>,
     ) -> BoxFuture<'a, Result<(), P2pSyncClientError>> {
         async move {
             storage_writer
@@ -82,7 +85,8 @@ impl BlockDataStreamBuilder<SignedBlockHeader> for HeaderStreamBuilder {
             DataOrFin<SignedBlockHeader>,
         >,
         block_number: BlockNumber,
-        _storage_reader: &'a StorageReader,
+        storage_reader: &'a StorageReader,
+        verification_params: &'a VerificationParams,
     ) -> BoxFuture<'a, Result<Option<Self::Output>, ParseDataError>> {
         async move {
             let maybe_signed_header = signed_headers_response_manager.next().await.ok_or(
@@ -93,8 +97,6 @@ impl BlockDataStreamBuilder<SignedBlockHeader> for HeaderStreamBuilder {
             let Some(signed_block_header) = maybe_signed_header?.0 else {
                 return Ok(None);
             };
-            // TODO(shahak): Check that parent_hash is the same as the previous block's hash
-            // and handle reverts.
             if block_number
                 != signed_block_header.block_header.block_header_without_hash.block_number
             {
@@ -111,11 +113,44 @@ impl BlockDataStreamBuilder<SignedBlockHeader> for HeaderStreamBuilder {
                     signatures: signed_block_header.signatures,
                 }));
             }
+            // The previous blocks of the same query are checked by the stream. This checks the
+            // first block of the query if its parent is already stored.
+            // TODO(shahak): Handle reverts.
+            let previous_header = match block_number.prev() {
+                Some(previous_block_number) => {
+                    storage_reader.begin_ro_txn()?.get_block_header(previous_block_number)?
+                }
+                None => None,
+            };
+            verify_starknet_version(
+                &signed_block_header.block_header,
+                previous_header.as_ref(),
+                verification_params.block_hash_verification_height,
+            )?;
+            verify_block_hash(&signed_block_header.block_header)?;
+            if let Some(previous_header) = previous_header {
+                let parent_hash =
+                    signed_block_header.block_header.block_header_without_hash.parent_hash;
+                if parent_hash != previous_header.block_hash {
+                    return Err(ParseDataError::BadPeer(BadPeerError::ParentHashMismatch {
+                        block_number,
+                        parent_hash,
+                        previous_block_hash: previous_header.block_hash,
+                    }));
+                }
+            }
             Ok(Some(signed_block_header))
         }
         .boxed()
     }
 
+    fn block_and_parent_hashes(output: &SignedBlockHeader) -> Option<(BlockHash, BlockHash)> {
+        Some((
+            output.block_header.block_hash,
+            output.block_header.block_header_without_hash.parent_hash,
+        ))
+    }
+
     fn get_start_block_number(storage_reader: &StorageReader) -> Result<BlockNumber, StorageError> {
         storage_reader.begin_ro_txn()?.get_header_marker()
     }
@@ -11,7 +11,8 @@ use papyrus_protobuf::sync::{
 };
 use papyrus_storage::header::HeaderStorageReader;
 use papyrus_test_utils::get_rng;
-use starknet_api::block::{BlockHeader, BlockHeaderWithoutHash, BlockNumber};
+use starknet_api::block::{BlockHash, BlockHeader, BlockHeaderWithoutHash, BlockNumber};
+use starknet_api::state::ThinStateDiff;
 use tokio::time::timeout;
 
 use super::test_utils::{
@@ -19,16 +20,22 @@ use super::test_utils::{
     random_header,
     run_test,
     setup,
+    setup_with_config,
+    test_parent_hash,
+    verifiable_header,
     wait_for_marker,
     Action,
     DataType,
     TestArgs,
     HEADER_QUERY_LENGTH,
     SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+    TEST_CONFIG,
     TIMEOUT_FOR_NEW_QUERY_AFTER_PARTIAL_RESPONSE,
     TIMEOUT_FOR_TEST,
+    UNVERIFIED_STARKNET_VERSION,
     WAIT_PERIOD_FOR_NEW_DATA,
 };
+use super::P2pSyncClientConfig;
 
 #[tokio::test]
 async fn signed_headers_basic_flow() {
@@ -42,6 +49,7 @@ async fn signed_headers_basic_flow() {
         mock_state_diff_response_manager: _mock_state_diff_response_manager,
         mock_transaction_response_manager: _mock_transaction_response_manager,
         mock_class_response_manager: _mock_class_response_manager,
+        mock_event_response_manager: _mock_event_response_manager,
         ..
     } = setup();
     let block_hashes_and_signatures =
@@ -79,6 +87,8 @@ async fn signed_headers_basic_flow() {
                             block_hash: *block_hash,
                             block_header_without_hash: BlockHeaderWithoutHash {
                                 block_number: BlockNumber(i.try_into().unwrap()),
+                                parent_hash: test_parent_hash(BlockNumber(i.try_into().unwrap())),
+                                starknet_version: UNVERIFIED_STARKNET_VERSION,
                                 ..Default::default()
                             },
                             state_diff_length: Some(0),
@@ -135,6 +145,7 @@ async fn sync_sends_new_header_query_if_it_got_partial_responses() {
         mock_state_diff_response_manager: _state_diff_receiver,
         mock_transaction_response_manager: _transaction_receiver,
         mock_class_response_manager: _class_receiver,
+        mock_event_response_manager: _event_receiver,
         ..
     } = setup();
     let block_hashes_and_signatures = create_block_hashes_and_signatures(NUM_ACTUAL_RESPONSES);
@@ -150,6 +161,8 @@ async fn sync_sends_new_header_query_if_it_got_partial_responses() {
                         block_hash,
                         block_header_without_hash: BlockHeaderWithoutHash {
                             block_number: BlockNumber(i.try_into().unwrap()),
+                            parent_hash: test_parent_hash(BlockNumber(i.try_into().unwrap())),
+                            starknet_version: UNVERIFIED_STARKNET_VERSION,
                             ..Default::default()
                         },
                         state_diff_length: Some(0),
@@ -198,6 +211,200 @@ async fn sync_sends_new_header_query_if_it_got_partial_responses() {
     }
 }
 
+#[tokio::test]
+async fn headers_are_downloaded_from_concurrent_queries() {
+    let TestArgs {
+        p2p_sync,
+        storage_reader,
+        mut mock_header_response_manager,
+        // The test will fail if we drop these
+        mock_state_diff_response_manager: _state_diff_receiver,
+        mock_transaction_response_manager: _transaction_receiver,
+        mock_class_response_manager: _class_receiver,
+        mock_event_response_manager: _event_receiver,
+        ..
+    } = setup_with_config(P2pSyncClientConfig { num_concurrent_queries: 2, ..TEST_CONFIG.clone() });
+    let mut rng = get_rng();
+
+    let parse_queries_future = async move {
+        let mut first_responses_manager = mock_header_response_manager.next().await.unwrap();
+        let mut second_responses_manager = mock_header_response_manager.next().await.unwrap();
+        for (responses_manager, start_block_number) in
+            [(&first_responses_manager, 0), (&second_responses_manager, HEADER_QUERY_LENGTH)]
+        {
+            assert_eq!(
+                *responses_manager.query(),
+                Ok(HeaderQuery(Query {
+                    start_block: BlockHashOrNumber::Number(BlockNumber(start_block_number)),
+                    direction: Direction::Forward,
+                    limit: HEADER_QUERY_LENGTH,
+                    step: 1,
+                }))
+            );
+        }
+
+        // The second range isn't written until the first range is downloaded.
+        for block_number in HEADER_QUERY_LENGTH..2 * HEADER_QUERY_LENGTH {
+            second_responses_manager
+                .send_response(DataOrFin(Some(random_header(
+                    &mut rng,
+                    BlockNumber(block_number),
+                    None,
+                    None,
+                ))))
+                .await
+                .unwrap();
+        }
+        second_responses_manager.send_response(DataOrFin(None)).await.unwrap();
+        tokio::time::sleep(SLEEP_DURATION_TO_LET_SYNC_ADVANCE).await;
+        assert_eq!(
+            storage_reader.begin_ro_txn().unwrap().get_header_marker().unwrap(),
+            BlockNumber(0)
+        );
+
+        // The first peer sends a wrong header, so its range is queried again.
+        first_responses_manager
+            .send_response(DataOrFin(Some(random_header(&mut rng, BlockNumber(1), None, None))))
+            .await
+            .unwrap();
+        first_responses_manager.assert_reported(TIMEOUT_FOR_TEST).await;
+
+        let mut retry_responses_manager = mock_header_response_manager.next().await.unwrap();
+        assert_eq!(
+            *retry_responses_manager.query(),
+            Ok(HeaderQuery(Query {
+                start_block: BlockHashOrNumber::Number(BlockNumber(0)),
+                direction: Direction::Forward,
+                limit: HEADER_QUERY_LENGTH,
+                step: 1,
+            }))
+        );
+        for block_number in 0..HEADER_QUERY_LENGTH {
+            retry_responses_manager
+                .send_response(DataOrFin(Some(random_header(
+                    &mut rng,
+                    BlockNumber(block_number),
+                    None,
+                    None,
+                ))))
+                .await
+                .unwrap();
+        }
+        wait_for_marker(
+            DataType::Header,
+            &storage_reader,
+            BlockNumber(2 * HEADER_QUERY_LENGTH),
+            SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+            TIMEOUT_FOR_TEST,
+        )
+        .await;
+    };
+
+    tokio::select! {
+        sync_result = p2p_sync.run() => {
+            sync_result.unwrap();
+            unreachable!("Return type Never should never be constructed.");
+        }
+        _ = parse_queries_future => {}
+    }
+}
+
+#[tokio::test]
+async fn header_not_following_the_previous_range_is_queried_again() {
+    let TestArgs {
+        p2p_sync,
+        storage_reader,
+        mut mock_header_response_manager,
+        // The test will fail if we drop these
+        mock_state_diff_response_manager: _state_diff_receiver,
+        mock_transaction_response_manager: _transaction_receiver,
+        mock_class_response_manager: _class_receiver,
+        mock_event_response_manager: _event_receiver,
+        ..
+    } = setup_with_config(P2pSyncClientConfig { num_concurrent_queries: 2, ..TEST_CONFIG.clone() });
+    let mut rng = get_rng();
+
+    let parse_queries_future = async move {
+        let mut first_responses_manager = mock_header_response_manager.next().await.unwrap();
+        let mut second_responses_manager = mock_header_response_manager.next().await.unwrap();
+
+        // Each range forms a chain by itself, but the first header of the second range doesn't
+        // follow the last header of the first range.
+        for block_number in HEADER_QUERY_LENGTH..2 * HEADER_QUERY_LENGTH {
+            let mut header = random_header(&mut rng, BlockNumber(block_number), None, None);
+            if block_number == HEADER_QUERY_LENGTH {
+                header.block_header.block_header_without_hash.parent_hash =
+                    BlockHash(test_parent_hash(BlockNumber(block_number)).0 + 1);
+            }
+            second_responses_manager.send_response(DataOrFin(Some(header))).await.unwrap();
+        }
+        second_responses_manager.send_response(DataOrFin(None)).await.unwrap();
+        for block_number in 0..HEADER_QUERY_LENGTH {
+            first_responses_manager
+                .send_response(DataOrFin(Some(random_header(
+                    &mut rng,
+                    BlockNumber(block_number),
+                    None,
+                    None,
+                ))))
+                .await
+                .unwrap();
+        }
+        first_responses_manager.send_response(DataOrFin(None)).await.unwrap();
+        wait_for_marker(
+            DataType::Header,
+            &storage_reader,
+            BlockNumber(HEADER_QUERY_LENGTH),
+            SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+            TIMEOUT_FOR_TEST,
+        )
+        .await;
+
+        // The second range is queried again and isn't written.
+        let mut retry_responses_manager = mock_header_response_manager.next().await.unwrap();
+        assert_eq!(
+            *retry_responses_manager.query(),
+            Ok(HeaderQuery(Query {
+                start_block: BlockHashOrNumber::Number(BlockNumber(HEADER_QUERY_LENGTH)),
+                direction: Direction::Forward,
+                limit: HEADER_QUERY_LENGTH,
+                step: 1,
+            }))
+        );
+        assert_eq!(
+            storage_reader.begin_ro_txn().unwrap().get_header_marker().unwrap(),
+            BlockNumber(HEADER_QUERY_LENGTH)
+        );
+        for block_number in HEADER_QUERY_LENGTH..2 * HEADER_QUERY_LENGTH {
+            retry_responses_manager
+                .send_response(DataOrFin(Some(random_header(
+                    &mut rng,
+                    BlockNumber(block_number),
+                    None,
+                    None,
+                ))))
+                .await
+                .unwrap();
+        }
+        wait_for_marker(
+            DataType::Header,
+            &storage_reader,
+            BlockNumber(2 * HEADER_QUERY_LENGTH),
+            SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+            TIMEOUT_FOR_TEST,
+        )
+        .await;
+    };
+
+    tokio::select! {
+        sync_result = p2p_sync.run() => {
+            sync_result.unwrap();
+            unreachable!("Return type Never should never be constructed.");
+        }
+        _ = parse_queries_future => {}
+    }
+}
+
 #[tokio::test]
 async fn wrong_block_number() {
     run_test(
@@ -225,4 +432,135 @@ async fn wrong_block_number() {
     .await;
 }
 
+#[tokio::test]
+async fn wrong_block_hash() {
+    let mut header = verifiable_header(&mut get_rng(), BlockNumber(0), &ThinStateDiff::default());
+    header.block_header.block_hash = BlockHash(header.block_header.block_hash.0 + 1);
+    validate_headers_fail(vec![header], 0).await;
+}
+
+#[tokio::test]
+async fn wrong_parent_hash() {
+    let mut rng = get_rng();
+    let header0 = random_header(&mut rng, BlockNumber(0), None, None);
+    let mut header1 = random_header(&mut rng, BlockNumber(1), None, None);
+    header1.block_header.block_header_without_hash.parent_hash =
+        BlockHash(header0.block_header.block_hash.0 + 1);
+    validate_headers_fail(vec![header0, header1], 1).await;
+}
+
+#[tokio::test]
+async fn starknet_version_lower_than_the_parent() {
+    let mut rng = get_rng();
+    let header0 = verifiable_header(&mut rng, BlockNumber(0), &ThinStateDiff::default());
+    let mut header1 = random_header(&mut rng, BlockNumber(1), None, None);
+    header1.block_header.block_header_without_hash.parent_hash = header0.block_header.block_hash;
+    run_test(
+        HashMap::from([(DataType::Header, 1)]),
+        None,
+        vec![
+            Action::RunP2pSync,
+            // We already validate the query content in other tests.
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+            Action::SendHeader(DataOrFin(Some(header0))),
+            Action::SendHeader(DataOrFin(None)),
+            // The second header is queried after the first one was stored, so its version is
+            // checked against it.
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+            Action::SendHeader(DataOrFin(Some(header1))),
+            Action::ValidateReportSent(DataType::Header),
+            Action::CheckStorage(Box::new(|reader| {
+                async move {
+                    assert_eq!(1, reader.begin_ro_txn().unwrap().get_header_marker().unwrap().0);
+                }
+                .boxed()
+            })),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn unverifiable_header_from_the_verification_height() {
+    let TestArgs {
+        p2p_sync,
+        storage_reader,
+        mut mock_header_response_manager,
+        // The test will fail if we drop these
+        mock_state_diff_response_manager: _state_diff_receiver,
+        mock_transaction_response_manager: _transaction_receiver,
+        mock_class_response_manager: _class_receiver,
+        mock_event_response_manager: _event_receiver,
+        ..
+    } = setup_with_config(P2pSyncClientConfig {
+        block_hash_verification_height: Some(BlockNumber(1)),
+        ..TEST_CONFIG.clone()
+    });
+    let mut rng = get_rng();
+
+    let parse_queries_future = async move {
+        let mut responses_manager = mock_header_response_manager.next().await.unwrap();
+        // Both headers claim a version that can't be verified, which is allowed only below the
+        // verification height.
+        for block_number in 0..2 {
+            responses_manager
+                .send_response(DataOrFin(Some(random_header(
+                    &mut rng,
+                    BlockNumber(block_number),
+                    None,
+                    None,
+                ))))
+                .await
+                .unwrap();
+        }
+        responses_manager.assert_reported(TIMEOUT_FOR_TEST).await;
+        wait_for_marker(
+            DataType::Header,
+            &storage_reader,
+            BlockNumber(1),
+            SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+            TIMEOUT_FOR_TEST,
+        )
+        .await;
+        assert_eq!(
+            storage_reader.begin_ro_txn().unwrap().get_header_marker().unwrap(),
+            BlockNumber(1)
+        );
+    };
+
+    tokio::select! {
+        sync_result = p2p_sync.run() => {
+            sync_result.unwrap();
+            unreachable!("Return type Never should never be constructed.");
+        }
+        _ = parse_queries_future => {}
+    }
+}
+
+// Sends the given headers and checks that the peer is reported and that only the headers before
+// `expected_header_marker` are written.
+async fn validate_headers_fail(headers: Vec<SignedBlockHeader>, expected_header_marker: u64) {
+    let num_headers = headers.len().try_into().unwrap();
+    let mut actions = vec![
+        Action::RunP2pSync,
+        // We already validate the query content in other tests.
+        Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+    ];
+    for header in headers {
+        actions.push(Action::SendHeader(DataOrFin(Some(header))));
+    }
+    actions.push(Action::ValidateReportSent(DataType::Header));
+    actions.push(Action::CheckStorage(Box::new(move |reader| {
+        async move {
+            assert_eq!(
+                expected_header_marker,
+                reader.begin_ro_txn().unwrap().get_header_marker().unwrap().0
+            );
+        }
+        .boxed()
+    })));
+
+    run_test(HashMap::from([(DataType::Header, num_headers)]), None, actions).await;
+}
+
 // TODO(shahak): Add more negative tests.
@@ -2,6 +2,13 @@ mod block_data_stream_builder;
 mod class;
 #[cfg(test)]
 mod class_test;
+mod commitments;
+mod download_scheduler;
+#[cfg(test)]
+mod download_scheduler_test;
+mod event;
+#[cfg(test)]
+mod event_test;
 mod header;
 #[cfg(test)]
 mod header_test;
@@ -21,6 +28,8 @@ use std::time::Duration;
 
 use block_data_stream_builder::{BlockDataResult, BlockDataStreamBuilder};
 use class::ClassStreamBuilder;
+use commitments::VerificationParams;
+use event::EventStreamBuilder;
 use futures::channel::mpsc::{Receiver, SendError, Sender};
 use futures::never::Never;
 use futures::stream::BoxStream;
@@ -28,24 +37,32 @@ use futures::{SinkExt as _, Stream};
 use header::HeaderStreamBuilder;
 use papyrus_common::pending_classes::ApiContractClass;
 use papyrus_config::converters::deserialize_milliseconds_to_duration;
-use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::dumping::{
+    ser_optional_param,
+    ser_optional_sub_config,
+    ser_param,
+    SerializeConfig,
+};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_network::network_manager::SqmrClientSender;
 use papyrus_protobuf::sync::{
     ClassQuery,
     DataOrFin,
+    EventQuery,
     HeaderQuery,
     SignedBlockHeader,
     StateDiffChunk,
     StateDiffQuery,
     TransactionQuery,
 };
-use papyrus_storage::{StorageError, StorageReader, StorageWriter};
+use papyrus_storage::{StorageError, StorageReader, StorageScope, StorageWriter};
+use papyrus_sync::state_root::{StateRootVerificationError, StateRootVerifier};
 use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockNumber;
-use starknet_api::core::ClassHash;
-use starknet_api::transaction::FullTransaction;
+use starknet_api::core::{ChainId, ClassHash};
+use starknet_api::transaction::{Event, FullTransaction, TransactionHash};
 use starknet_class_manager_types::SharedClassManagerClient;
+use starknet_patricia_storage::mdbx_storage::MdbxStorageConfig;
 use starknet_state_sync_types::state_sync_types::SyncBlock;
 use state_diff::StateDiffStreamBuilder;
 use tokio_stream::StreamExt;
@@ -56,22 +73,26 @@ use validator::Validate;
 const STEP: u64 = 1;
 const ALLOWED_SIGNATURES_LENGTH: usize = 1;
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Validate)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Validate)]
 pub struct P2pSyncClientConfig {
     pub num_headers_per_query: u64,
     pub num_block_state_diffs_per_query: u64,
     pub num_block_transactions_per_query: u64,
     pub num_block_classes_per_query: u64,
+    pub num_block_events_per_query: u64,
+    pub num_concurrent_queries: usize,
     #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
     pub wait_period_for_new_data: Duration,
     #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
     pub wait_period_for_other_protocol: Duration,
     pub buffer_size: usize,
+    pub block_hash_verification_height: Option<BlockNumber>,
+    pub state_root_verification: Option<MdbxStorageConfig>,
 }
 
 impl SerializeConfig for P2pSyncClientConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        BTreeMap::from_iter([
+        let mut config = BTreeMap::from_iter([
             ser_param(
                 "num_headers_per_query",
                 &self.num_headers_per_query,
@@ -97,6 +118,20 @@ impl SerializeConfig for P2pSyncClientConfig {
                 "The maximum amount of block's classes to ask from peers in each iteration.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "num_block_events_per_query",
+                &self.num_block_events_per_query,
+                "The maximum amount of blocks to ask their events from peers in each iteration.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "num_concurrent_queries",
+                &self.num_concurrent_queries,
+                "The maximum amount of queries each protocol sends at the same time. The blocks \
+                 are split between the queries, and each query is assigned to a peer by the \
+                 network.",
+                ParamPrivacyInput::Public,
+            ),
             ser_param(
                 "wait_period_for_new_data",
                 &self.wait_period_for_new_data.as_millis(),
@@ -117,7 +152,30 @@ impl SerializeConfig for P2pSyncClientConfig {
                 "Size of the buffer for read from the storage and for incoming responses.",
                 ParamPrivacyInput::Public,
             ),
-        ])
+        ]);
+        config.extend(ser_optional_param(
+            &self.block_hash_verification_height,
+            BlockNumber(0),
+            "block_hash_verification_height",
+            "The height from which the block hash of every header is verified against its data. \
+             Headers from this height that claim a Starknet version whose block hash doesn't \
+             commit to the block data are rejected. If unset, only the headers that claim a \
+             version whose block hash commits to the block data are verified.",
+            ParamPrivacyInput::Public,
+        ));
+        config.extend(ser_optional_sub_config(
+            &self.state_root_verification,
+            "state_root_verification",
+        ));
+        config
+            .get_mut("state_root_verification.#is_none")
+            .expect("An optional sub config should have an is_none flag.")
+            .description = "Flag for an optional field. If not set, the state diff of every block \
+                            is committed to a Patricia storage and the resulting state root is \
+                            verified against the header. On a node that already synced blocks, \
+                            the storage is first built from the stored state diffs."
+            .to_owned();
+        config
     }
 }
 
@@ -130,10 +188,14 @@ impl Default for P2pSyncClientConfig {
             num_block_state_diffs_per_query: 100,
             num_block_transactions_per_query: 100,
             num_block_classes_per_query: 100,
+            num_block_events_per_query: 100,
+            num_concurrent_queries: 4,
             wait_period_for_new_data: Duration::from_millis(50),
             wait_period_for_other_protocol: Duration::from_millis(50),
             // TODO(eitan): split this by protocol
             buffer_size: 100000,
+            block_hash_verification_height: None,
+            state_root_verification: None,
         }
     }
 }
@@ -153,18 +215,22 @@ pub enum P2pSyncClientError {
     StorageError(#[from] StorageError),
     #[error(transparent)]
     SendError(#[from] SendError),
+    #[error(transparent)]
+    StateRootVerificationError(#[from] StateRootVerificationError),
 }
 
 type HeaderSqmrSender = SqmrClientSender<HeaderQuery, DataOrFin<SignedBlockHeader>>;
 type StateSqmrDiffSender = SqmrClientSender<StateDiffQuery, DataOrFin<StateDiffChunk>>;
 type TransactionSqmrSender = SqmrClientSender<TransactionQuery, DataOrFin<FullTransaction>>;
 type ClassSqmrSender = SqmrClientSender<ClassQuery, DataOrFin<(ApiContractClass, ClassHash)>>;
+type EventSqmrSender = SqmrClientSender<EventQuery, DataOrFin<(Event, TransactionHash)>>;
 
 pub struct P2pSyncClientChannels {
     header_sender: HeaderSqmrSender,
     state_diff_sender: StateSqmrDiffSender,
     transaction_sender: TransactionSqmrSender,
     class_sender: ClassSqmrSender,
+    event_sender: EventSqmrSender,
 }
 
 impl P2pSyncClientChannels {
@@ -173,57 +239,93 @@ impl P2pSyncClientChannels {
         state_diff_sender: StateSqmrDiffSender,
         transaction_sender: TransactionSqmrSender,
         class_sender: ClassSqmrSender,
+        event_sender: EventSqmrSender,
     ) -> Self {
-        Self { header_sender, state_diff_sender, transaction_sender, class_sender }
+        Self { header_sender, state_diff_sender, transaction_sender, class_sender, event_sender }
     }
     pub(crate) fn create_stream(
         self,
         storage_reader: StorageReader,
+        chain_id: ChainId,
         config: P2pSyncClientConfig,
         internal_blocks_receivers: InternalBlocksReceivers,
     ) -> impl Stream<Item = BlockDataResult> + Send + 'static {
+        let verification_params = VerificationParams {
+            chain_id,
+            block_hash_verification_height: config.block_hash_verification_height,
+        };
         let header_stream = HeaderStreamBuilder::create_stream(
             self.header_sender,
             storage_reader.clone(),
+            verification_params.clone(),
             Some(internal_blocks_receivers.header_receiver),
             config.wait_period_for_new_data,
             config.wait_period_for_other_protocol,
             config.num_headers_per_query,
+            config.num_concurrent_queries,
         );
 
         let state_diff_stream = StateDiffStreamBuilder::create_stream(
             self.state_diff_sender,
             storage_reader.clone(),
+            verification_params.clone(),
             Some(internal_blocks_receivers.state_diff_receiver),
             config.wait_period_for_new_data,
             config.wait_period_for_other_protocol,
             config.num_block_state_diffs_per_query,
+            config.num_concurrent_queries,
         );
 
         let transaction_stream = TransactionStreamFactory::create_stream(
             self.transaction_sender,
             storage_reader.clone(),
+            verification_params.clone(),
             Some(internal_blocks_receivers.transaction_receiver),
             config.wait_period_for_new_data,
             config.wait_period_for_other_protocol,
             config.num_block_transactions_per_query,
+            config.num_concurrent_queries,
         );
 
         let class_stream = ClassStreamBuilder::create_stream(
             self.class_sender,
             storage_reader.clone(),
+            verification_params.clone(),
             Some(internal_blocks_receivers.class_receiver),
             config.wait_period_for_new_data,
             config.wait_period_for_other_protocol,
             config.num_block_classes_per_query,
+            config.num_concurrent_queries,
         );
 
-        header_stream.merge(state_diff_stream).merge(transaction_stream).merge(class_stream)
+        // Only a full archive stores the transactions the events are matched against, so the
+        // events aren't synced otherwise.
+        let event_stream: BoxStream<'static, BlockDataResult> =
+            match internal_blocks_receivers.event_receiver {
+                Some(event_receiver) => EventStreamBuilder::create_stream(
+                    self.event_sender,
+                    storage_reader.clone(),
+                    verification_params,
+                    Some(event_receiver),
+                    config.wait_period_for_new_data,
+                    config.wait_period_for_other_protocol,
+                    config.num_block_events_per_query,
+                    config.num_concurrent_queries,
+                ),
+                None => Box::pin(futures::stream::pending()),
+            };
+
+        header_stream
+            .merge(state_diff_stream)
+            .merge(transaction_stream)
+            .merge(class_stream)
+            .merge(event_stream)
     }
 }
 
 pub struct P2pSyncClient {
     config: P2pSyncClientConfig,
+    chain_id: ChainId,
     storage_reader: StorageReader,
     storage_writer: StorageWriter,
     p2p_sync_channels: P2pSyncClientChannels,
@@ -234,6 +336,7 @@ pub struct P2pSyncClient {
 impl P2pSyncClient {
     pub fn new(
         config: P2pSyncClientConfig,
+        chain_id: ChainId,
         storage_reader: StorageReader,
         storage_writer: StorageWriter,
         p2p_sync_channels: P2pSyncClientChannels,
@@ -242,6 +345,7 @@ impl P2pSyncClient {
     ) -> Self {
         Self {
             config,
+            chain_id,
             storage_reader,
             storage_writer,
             p2p_sync_channels,
@@ -257,17 +361,29 @@ impl P2pSyncClient {
         let InternalBlocksChannels {
             receivers: internal_blocks_receivers,
             senders: mut internal_blocks_senders,
-        } = InternalBlocksChannels::new();
+        } = InternalBlocksChannels::new(self.storage_reader.get_scope());
         let P2pSyncClient {
             config,
+            chain_id,
             storage_reader,
             mut storage_writer,
             p2p_sync_channels,
             mut internal_blocks_receiver,
             mut class_manager_client,
         } = self;
-        let mut data_stream =
-            p2p_sync_channels.create_stream(storage_reader, config, internal_blocks_receivers);
+        let mut state_root_verifier = config
+            .state_root_verification
+            .as_ref()
+            .map(|state_root_verification_config| {
+                StateRootVerifier::open(state_root_verification_config, storage_reader.clone())
+            })
+            .transpose()?;
+        let mut data_stream = p2p_sync_channels.create_stream(
+            storage_reader,
+            chain_id,
+            config,
+            internal_blocks_receivers,
+        );
 
         loop {
             tokio::select! {
@@ -277,7 +393,11 @@ impl P2pSyncClient {
                 }
                 data = data_stream.next() => {
                     let data = data.expect("Sync data stream should never end")?;
-                    data.write_to_storage(&mut storage_writer, &mut class_manager_client).await?;
+                    data.write_to_storage(
+                        &mut storage_writer,
+                        &mut class_manager_client,
+                        &mut state_root_verifier,
+                    ).await?;
                 }
             }
         }
@@ -289,6 +409,7 @@ pub(crate) struct InternalBlocksReceivers {
     state_diff_receiver: Receiver<SyncBlock>,
     transaction_receiver: Receiver<SyncBlock>,
     class_receiver: Receiver<SyncBlock>,
+    event_receiver: Option<Receiver<SyncBlock>>,
 }
 
 pub struct InternalBlocksSenders {
@@ -296,6 +417,7 @@ pub struct InternalBlocksSenders {
     state_diff_sender: Sender<SyncBlock>,
     transaction_sender: Sender<SyncBlock>,
     class_sender: Sender<SyncBlock>,
+    event_sender: Option<Sender<SyncBlock>>,
 }
 
 impl InternalBlocksSenders {
@@ -303,16 +425,29 @@ impl InternalBlocksSenders {
         let header_send = self.header_sender.send(sync_block.clone());
         let state_diff_send = self.state_diff_sender.send(sync_block.clone());
         let transaction_send = self.transaction_sender.send(sync_block.clone());
-        let class_send = self.class_sender.send(sync_block);
-        let res =
-            futures::future::join4(header_send, state_diff_send, transaction_send, class_send)
-                .await;
+        let class_send = self.class_sender.send(sync_block.clone());
+        let event_sender = &mut self.event_sender;
+        let event_send = async move {
+            match event_sender {
+                Some(event_sender) => event_sender.send(sync_block).await,
+                None => Ok(()),
+            }
+        };
+        let res = futures::future::join5(
+            header_send,
+            state_diff_send,
+            transaction_send,
+            class_send,
+            event_send,
+        )
+        .await;
         match res {
-            (Ok(()), Ok(()), Ok(()), Ok(())) => Ok(()),
-            (Err(e), _, _, _) => Err(e),
-            (_, Err(e), _, _) => Err(e),
-            (_, _, Err(e), _) => Err(e),
-            (_, _, _, Err(e)) => Err(e),
+            (Ok(()), Ok(()), Ok(()), Ok(()), Ok(())) => Ok(()),
+            (Err(e), _, _, _, _) => Err(e),
+            (_, Err(e), _, _, _) => Err(e),
+            (_, _, Err(e), _, _) => Err(e),
+            (_, _, _, Err(e), _) => Err(e),
+            (_, _, _, _, Err(e)) => Err(e),
         }
     }
 }
@@ -323,11 +458,18 @@ struct InternalBlocksChannels {
 }
 
 impl InternalBlocksChannels {
-    pub fn new() -> Self {
+    pub fn new(storage_scope: StorageScope) -> Self {
         let (header_sender, header_receiver) = futures::channel::mpsc::channel(100);
         let (state_diff_sender, state_diff_receiver) = futures::channel::mpsc::channel(100);
         let (transaction_sender, transaction_receiver) = futures::channel::mpsc::channel(100);
         let (class_sender, class_receiver) = futures::channel::mpsc::channel(100);
+        let (event_sender, event_receiver) = match storage_scope {
+            StorageScope::FullArchive => {
+                let (event_sender, event_receiver) = futures::channel::mpsc::channel(100);
+                (Some(event_sender), Some(event_receiver))
+            }
+            StorageScope::StateOnly | StorageScope::Pruned => (None, None),
+        };
 
         Self {
             receivers: InternalBlocksReceivers {
@@ -335,12 +477,14 @@ impl InternalBlocksChannels {
                 state_diff_receiver,
                 transaction_receiver,
                 class_receiver,
+                event_receiver,
             },
             senders: InternalBlocksSenders {
                 header_sender,
                 state_diff_sender,
                 transaction_sender,
                 class_sender,
+                event_sender,
             },
         }
     }
@@ -9,12 +9,14 @@ use papyrus_storage::header::HeaderStorageReader;
 use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
 use papyrus_storage::{StorageError, StorageReader, StorageWriter};
 use papyrus_sync::define_metrics::SYNC_STATE_MARKER;
+use papyrus_sync::state_root::StateRootVerifier;
 use starknet_api::block::BlockNumber;
 use starknet_api::state::ThinStateDiff;
 use starknet_class_manager_types::SharedClassManagerClient;
 use starknet_state_sync_types::state_sync_types::SyncBlock;
 
 use super::block_data_stream_builder::BadPeerError;
+use super::commitments::{verify_state_diff, VerificationParams};
 use crate::client::block_data_stream_builder::{
     BlockData,
     BlockDataStreamBuilder,
@@ -30,8 +32,18 @@ impl BlockData for (ThinStateDiff, BlockNumber) {
         self: Box<Self>,
         storage_writer: &'a mut StorageWriter,
         _class_manager_client: &'a mut SharedClassManagerClient,
+        state_root_verifier: &'a mut Option<StateRootVerifier>,
     ) -> BoxFuture<'a, Result<(), P2pSyncClientError>> {
         async move {
+            if let Some(state_root_verifier) = state_root_verifier {
+                let state_root = storage_writer
+                    .begin_rw_txn()?
+                    .get_block_header(self.1)?
+                    .expect("Expecting to have a header for every state diff.")
+                    .block_header_without_hash
+                    .state_root;
+                state_root_verifier.verify(self.1, &self.0, state_root).await?;
+            }
             storage_writer.begin_rw_txn()?.append_state_diff(self.1, self.0)?.commit()?;
             SYNC_STATE_MARKER.set(self.1.unchecked_next().0 as f64);
             Ok(())
@@ -55,17 +67,18 @@ impl BlockDataStreamBuilder<StateDiffChunk> for StateDiffStreamBuilder {
         >,
         block_number: BlockNumber,
         storage_reader: &'a StorageReader,
+        _verification_params: &'a VerificationParams,
     ) -> BoxFuture<'a, Result<Option<Self::Output>, ParseDataError>> {
         async move {
             let mut result = ThinStateDiff::default();
             let mut prev_result_len = 0;
             let mut current_state_diff_len = 0;
-            let target_state_diff_len = storage_reader
+            let header = storage_reader
                 .begin_ro_txn()?
                 .get_block_header(block_number)?
-                .expect("A header with number lower than the header marker is missing")
-                .state_diff_length
-                .ok_or(P2pSyncClientError::OldHeaderInStorage {
+                .expect("A header with number lower than the header marker is missing");
+            let target_state_diff_len =
+                header.state_diff_length.ok_or(P2pSyncClientError::OldHeaderInStorage {
                     block_number,
                     missing_field: "state_diff_length",
                 })?;
@@ -105,6 +118,7 @@ impl BlockDataStreamBuilder<StateDiffChunk> for StateDiffStreamBuilder {
             }
 
             validate_deprecated_declared_classes_non_conflicting(&result)?;
+            verify_state_diff(&header, &result)?;
             Ok(Some((result, block_number)))
         }
         .boxed()
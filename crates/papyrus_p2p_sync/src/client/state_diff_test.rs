@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
-use futures::FutureExt;
+use assert_matches::assert_matches;
+use futures::{FutureExt, StreamExt};
 use indexmap::indexmap;
 use papyrus_protobuf::sync::{
     BlockHashOrNumber,
@@ -13,21 +14,36 @@ use papyrus_protobuf::sync::{
     StateDiffChunk,
 };
 use papyrus_storage::state::StateStorageReader;
+use papyrus_sync::state_root::StateRootVerificationError;
 use papyrus_test_utils::get_rng;
 use starknet_api::block::BlockNumber;
-use starknet_api::core::{ascii_as_felt, ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::core::{
+    ascii_as_felt,
+    ClassHash,
+    CompiledClassHash,
+    ContractAddress,
+    GlobalRoot,
+    Nonce,
+};
 use starknet_api::state::{StorageKey, ThinStateDiff};
+use starknet_patricia_storage::mdbx_storage::MdbxStorageConfig;
 use starknet_types_core::felt::Felt;
+use tempfile::TempDir;
 
 use super::test_utils::{
     random_header,
     run_test,
+    setup_with_config,
+    verifiable_header,
     wait_for_marker,
     Action,
     DataType,
+    TestArgs,
     SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+    TEST_CONFIG,
     TIMEOUT_FOR_TEST,
 };
+use super::{P2pSyncClientConfig, P2pSyncClientError};
 
 #[tokio::test]
 async fn state_diff_basic_flow() {
@@ -321,6 +337,108 @@ async fn state_diff_conflicting() {
     .await;
 }
 
+#[tokio::test]
+async fn state_diff_with_wrong_commitment() {
+    let mut rng = get_rng();
+    let class_hash = ClassHash(ascii_as_felt("class_hash").unwrap());
+    let header = verifiable_header(
+        &mut rng,
+        BlockNumber(0),
+        &ThinStateDiff { deprecated_declared_classes: vec![class_hash], ..Default::default() },
+    );
+
+    run_test(
+        HashMap::from([(DataType::Header, 1), (DataType::StateDiff, 1)]),
+        None,
+        vec![
+            Action::RunP2pSync,
+            // We already validate the header query content in other tests.
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+            Action::SendHeader(DataOrFin(Some(header))),
+            Action::SendHeader(DataOrFin(None)),
+            // We already validate the state diff query content in other tests.
+            Action::ReceiveQuery(Box::new(|_query| ()), DataType::StateDiff),
+            // The state diff has the right length but a different class hash.
+            Action::SendStateDiff(DataOrFin(Some(StateDiffChunk::DeprecatedDeclaredClass(
+                DeprecatedDeclaredClass { class_hash: ClassHash::default() },
+            )))),
+            Action::ValidateReportSent(DataType::StateDiff),
+            Action::CheckStorage(Box::new(|reader| {
+                async move {
+                    assert_eq!(
+                        BlockNumber(0),
+                        reader.begin_ro_txn().unwrap().get_state_marker().unwrap()
+                    );
+                }
+                .boxed()
+            })),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn state_diff_with_wrong_state_root() {
+    let patricia_storage_dir = TempDir::new().unwrap();
+    let TestArgs {
+        p2p_sync,
+        storage_reader,
+        mut mock_header_response_manager,
+        mut mock_state_diff_response_manager,
+        // The test will fail if we drop these
+        mock_transaction_response_manager: _transaction_receiver,
+        mock_class_response_manager: _class_receiver,
+        mock_event_response_manager: _event_receiver,
+    } = setup_with_config(P2pSyncClientConfig {
+        state_root_verification: Some(MdbxStorageConfig {
+            path: patricia_storage_dir.path().to_path_buf(),
+            max_size: 1 << 30,
+            growth_step: 1 << 20,
+            cache_size: 1 << 10,
+        }),
+        ..TEST_CONFIG.clone()
+    });
+    let mut rng = get_rng();
+    let mut header = random_header(&mut rng, BlockNumber(0), Some(1), None);
+    // The state root of an empty state, which the nonce update below changes.
+    header.block_header.block_header_without_hash.state_root = GlobalRoot::default();
+
+    let parse_queries_future = async move {
+        let mut header_responses_manager = mock_header_response_manager.next().await.unwrap();
+        header_responses_manager.send_response(DataOrFin(Some(header))).await.unwrap();
+        header_responses_manager.send_response(DataOrFin(None)).await.unwrap();
+
+        let mut state_diff_responses_manager =
+            mock_state_diff_response_manager.next().await.unwrap();
+        state_diff_responses_manager
+            .send_response(DataOrFin(Some(StateDiffChunk::ContractDiff(ContractDiff {
+                contract_address: ContractAddress::from(1_u8),
+                nonce: Some(Nonce(Felt::ONE)),
+                ..Default::default()
+            }))))
+            .await
+            .unwrap();
+        // The sync should stop before writing the state diff.
+        futures::future::pending::<()>().await;
+    };
+
+    tokio::select! {
+        sync_result = p2p_sync.run() => {
+            assert_matches!(
+                sync_result,
+                Err(P2pSyncClientError::StateRootVerificationError(
+                    StateRootVerificationError::StateRootMismatch {
+                        block_number: BlockNumber(0),
+                        ..
+                    }
+                ))
+            );
+        }
+        _ = parse_queries_future => unreachable!("The queries future never ends."),
+    }
+    assert_eq!(storage_reader.begin_ro_txn().unwrap().get_state_marker().unwrap(), BlockNumber(0));
+}
+
 async fn validate_state_diff_fails(
     header_state_diff_lengths: Vec<usize>,
     state_diff_chunks: Vec<Option<StateDiffChunk>>,
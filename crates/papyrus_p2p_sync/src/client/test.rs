@@ -268,6 +268,8 @@ fn create_random_sync_block(
         sequencer,
         l1_da_mode,
         starknet_version,
+        l2_gas_consumed,
+        next_l2_gas_price,
     } = BlockHeaderWithoutHash::get_test_instance(&mut rng);
     let block_header_without_hash = BlockHeaderWithoutHash {
         block_number,
@@ -280,6 +282,8 @@ fn create_random_sync_block(
         sequencer,
         l1_da_mode,
         starknet_version,
+        l2_gas_consumed,
+        next_l2_gas_price,
     };
     SyncBlock { state_diff, transaction_hashes, block_header_without_hash }
 }
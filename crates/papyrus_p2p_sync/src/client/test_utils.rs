@@ -17,6 +17,7 @@ use papyrus_network::network_manager::GenericReceiver;
 use papyrus_protobuf::sync::{
     ClassQuery,
     DataOrFin,
+    EventQuery,
     HeaderQuery,
     Query,
     SignedBlockHeader,
@@ -28,22 +29,41 @@ use papyrus_storage::body::BodyStorageReader;
 use papyrus_storage::class_manager::ClassManagerStorageReader;
 use papyrus_storage::header::HeaderStorageReader;
 use papyrus_storage::state::StateStorageReader;
-use papyrus_storage::test_utils::get_test_storage;
-use papyrus_storage::StorageReader;
+use papyrus_storage::test_utils::{get_test_storage, get_test_storage_by_scope};
+use papyrus_storage::{StorageReader, StorageScope};
 use papyrus_test_utils::GetTestInstance;
 use rand::{Rng, RngCore};
 use rand_chacha::ChaCha8Rng;
 use starknet_api::block::{
+    BlockBody,
     BlockHash,
     BlockHeader,
     BlockHeaderWithoutHash,
     BlockNumber,
     BlockSignature,
+    StarknetVersion,
 };
-use starknet_api::core::ClassHash;
+use starknet_api::block_hash::block_hash_calculator::{
+    calculate_block_commitments,
+    calculate_block_hash,
+};
+use starknet_api::core::{ClassHash, Nonce};
 use starknet_api::crypto::utils::Signature;
 use starknet_api::hash::StarkHash;
-use starknet_api::transaction::FullTransaction;
+use starknet_api::state::ThinStateDiff;
+use starknet_api::test_utils::CHAIN_ID_FOR_TESTS;
+use starknet_api::transaction::{
+    Event,
+    FullTransaction,
+    InvokeTransaction,
+    InvokeTransactionOutput,
+    InvokeTransactionV1,
+    Transaction,
+    TransactionHash,
+    TransactionOptions,
+    TransactionOutput,
+};
+use starknet_api::transaction_hash::get_transaction_hash;
 use starknet_class_manager_types::MockClassManagerClient;
 use starknet_state_sync_types::state_sync_types::SyncBlock;
 use starknet_types_core::felt::Felt;
@@ -57,21 +77,29 @@ pub const HEADER_QUERY_LENGTH: u64 = 5;
 pub const STATE_DIFF_QUERY_LENGTH: u64 = 3;
 pub const CLASS_DIFF_QUERY_LENGTH: u64 = 3;
 pub const TRANSACTION_QUERY_LENGTH: u64 = 3;
+pub const EVENT_QUERY_LENGTH: u64 = 3;
 pub const SLEEP_DURATION_TO_LET_SYNC_ADVANCE: Duration = Duration::from_millis(10);
 pub const WAIT_PERIOD_FOR_NEW_DATA: Duration = Duration::from_secs(1);
 pub const WAIT_PERIOD_FOR_OTHER_PROTOCOL: Duration = Duration::from_secs(1);
+// Blocks of this version aren't verified against their hash and commitments, so the tests can send
+// arbitrary data.
+pub const UNVERIFIED_STARKNET_VERSION: StarknetVersion = StarknetVersion::V0_13_1;
 pub const TIMEOUT_FOR_NEW_QUERY_AFTER_PARTIAL_RESPONSE: Duration =
     WAIT_PERIOD_FOR_NEW_DATA.saturating_add(Duration::from_secs(1));
 
 lazy_static! {
-    static ref TEST_CONFIG: P2pSyncClientConfig = P2pSyncClientConfig {
+    pub(crate) static ref TEST_CONFIG: P2pSyncClientConfig = P2pSyncClientConfig {
         num_headers_per_query: HEADER_QUERY_LENGTH,
         num_block_state_diffs_per_query: STATE_DIFF_QUERY_LENGTH,
         num_block_transactions_per_query: TRANSACTION_QUERY_LENGTH,
         num_block_classes_per_query: CLASS_DIFF_QUERY_LENGTH,
+        num_block_events_per_query: EVENT_QUERY_LENGTH,
+        num_concurrent_queries: 1,
         wait_period_for_new_data: WAIT_PERIOD_FOR_NEW_DATA,
         wait_period_for_other_protocol: WAIT_PERIOD_FOR_OTHER_PROTOCOL,
         buffer_size: BUFFER_SIZE,
+        block_hash_verification_height: None,
+        state_root_verification: None,
     };
 }
 pub(crate) type HeaderTestPayload =
@@ -82,6 +110,8 @@ pub(crate) type TransactionTestPayload =
     MockClientResponsesManager<TransactionQuery, DataOrFin<FullTransaction>>;
 pub(crate) type ClassTestPayload =
     MockClientResponsesManager<ClassQuery, DataOrFin<(ApiContractClass, ClassHash)>>;
+pub(crate) type EventTestPayload =
+    MockClientResponsesManager<EventQuery, DataOrFin<(Event, TransactionHash)>>;
 
 // TODO(Eitan): Use SqmrSubscriberChannels once there is a utility function for testing
 pub struct TestArgs {
@@ -94,10 +124,15 @@ pub struct TestArgs {
     pub mock_transaction_response_manager: GenericReceiver<TransactionTestPayload>,
     #[allow(dead_code)]
     pub mock_class_response_manager: GenericReceiver<ClassTestPayload>,
+    #[allow(dead_code)]
+    pub mock_event_response_manager: GenericReceiver<EventTestPayload>,
 }
 
 pub fn setup() -> TestArgs {
-    let p2p_sync_config = *TEST_CONFIG;
+    setup_with_config(TEST_CONFIG.clone())
+}
+
+pub fn setup_with_config(p2p_sync_config: P2pSyncClientConfig) -> TestArgs {
     let buffer_size = p2p_sync_config.buffer_size;
     let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
     let (header_sender, mock_header_response_manager) =
@@ -108,15 +143,19 @@ pub fn setup() -> TestArgs {
         mock_register_sqmr_protocol_client(buffer_size);
     let (class_sender, mock_class_response_manager) =
         mock_register_sqmr_protocol_client(buffer_size);
+    let (event_sender, mock_event_response_manager) =
+        mock_register_sqmr_protocol_client(buffer_size);
     let p2p_sync_channels = P2pSyncClientChannels {
         header_sender,
         state_diff_sender,
         transaction_sender,
         class_sender,
+        event_sender,
     };
     let class_manager_client = Arc::new(MockClassManagerClient::new());
     let p2p_sync = P2pSyncClient::new(
         p2p_sync_config,
+        CHAIN_ID_FOR_TESTS.clone(),
         storage_reader.clone(),
         storage_writer,
         p2p_sync_channels,
@@ -130,6 +169,7 @@ pub fn setup() -> TestArgs {
         mock_state_diff_response_manager,
         mock_transaction_response_manager,
         mock_class_response_manager,
+        mock_event_response_manager,
     }
 }
 
@@ -141,6 +181,8 @@ pub enum DataType {
     StateDiff,
     #[allow(dead_code)]
     Class,
+    #[allow(dead_code)]
+    Event,
 }
 
 pub enum Action {
@@ -160,11 +202,16 @@ pub enum Action {
     /// Send a class as a response to a query we got from ReceiveQuery. Will panic if didn't
     /// call ReceiveQuery with DataType::Class before.
     SendClass(DataOrFin<(ApiContractClass, ClassHash)>),
+    /// Send an event as a response to a query we got from ReceiveQuery. Will panic if didn't
+    /// call ReceiveQuery with DataType::Event before.
+    SendEvent(DataOrFin<(Event, TransactionHash)>),
     /// Perform custom validations on the storage. Returns back the storage reader it received as
     /// input
     CheckStorage(Box<dyn FnOnce(StorageReader) -> BoxFuture<'static, ()>>),
     /// Check that a report was sent on the current header query.
     ValidateReportSent(DataType),
+    /// Check that the sync didn't send a query of the given data type.
+    ValidateNoQuery(DataType),
     /// Sends an internal block to the sync.
     #[allow(dead_code)]
     SendInternalBlock(SyncBlock),
@@ -179,6 +226,21 @@ pub async fn run_test(
     max_query_lengths: HashMap<DataType, u64>,
     class_manager_client: Option<MockClassManagerClient>,
     actions: Vec<Action>,
+) {
+    run_test_with_storage_scope(
+        StorageScope::FullArchive,
+        max_query_lengths,
+        class_manager_client,
+        actions,
+    )
+    .await
+}
+
+pub async fn run_test_with_storage_scope(
+    storage_scope: StorageScope,
+    max_query_lengths: HashMap<DataType, u64>,
+    class_manager_client: Option<MockClassManagerClient>,
+    actions: Vec<Action>,
 ) {
     let p2p_sync_config = P2pSyncClientConfig {
         num_headers_per_query: max_query_lengths.get(&DataType::Header).cloned().unwrap_or(1),
@@ -191,29 +253,36 @@ pub async fn run_test(
             .cloned()
             .unwrap_or(1),
         num_block_classes_per_query: max_query_lengths.get(&DataType::Class).cloned().unwrap_or(1),
+        num_block_events_per_query: max_query_lengths.get(&DataType::Event).cloned().unwrap_or(1),
+        num_concurrent_queries: 1,
         wait_period_for_new_data: WAIT_PERIOD_FOR_NEW_DATA,
         wait_period_for_other_protocol: WAIT_PERIOD_FOR_OTHER_PROTOCOL,
         buffer_size: BUFFER_SIZE,
+        block_hash_verification_height: None,
+        state_root_verification: None,
     };
     let class_manager_client = class_manager_client.unwrap_or_default();
     let class_manager_client = Arc::new(class_manager_client);
     let buffer_size = p2p_sync_config.buffer_size;
-    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage_by_scope(storage_scope);
     let (header_sender, mut mock_header_network) = mock_register_sqmr_protocol_client(buffer_size);
     let (state_diff_sender, mut mock_state_diff_network) =
         mock_register_sqmr_protocol_client(buffer_size);
     let (transaction_sender, mut mock_transaction_network) =
         mock_register_sqmr_protocol_client(buffer_size);
     let (class_sender, mut mock_class_network) = mock_register_sqmr_protocol_client(buffer_size);
+    let (event_sender, mut mock_event_network) = mock_register_sqmr_protocol_client(buffer_size);
     let p2p_sync_channels = P2pSyncClientChannels {
         header_sender,
         state_diff_sender,
         transaction_sender,
         class_sender,
+        event_sender,
     };
     let (mut internal_block_sender, internal_block_receiver) = mpsc::channel(buffer_size);
     let p2p_sync = P2pSyncClient::new(
         p2p_sync_config,
+        CHAIN_ID_FOR_TESTS.clone(),
         storage_reader.clone(),
         storage_writer,
         p2p_sync_channels,
@@ -225,6 +294,7 @@ pub async fn run_test(
     let mut state_diff_current_query_responses_manager = None;
     let mut transaction_current_query_responses_manager = None;
     let mut class_current_query_responses_manager = None;
+    let mut event_current_query_responses_manager = None;
 
     let (sync_future_sender, sync_future_receiver) = oneshot::channel();
     let mut sync_future_sender = Some(sync_future_sender);
@@ -259,6 +329,12 @@ pub async fn run_test(
                                     &mut class_current_query_responses_manager,
                                 ).await.0
                             }
+                            DataType::Event => {
+                                get_next_query_and_update_responses_manager(
+                                    &mut mock_event_network,
+                                    &mut event_current_query_responses_manager,
+                                ).await.0
+                            }
                         };
                         validate_query_fn(query);
                     }
@@ -282,6 +358,11 @@ pub async fn run_test(
                             .expect("Called SendClass without calling ReceiveQuery");
                         responses_manager.send_response(class_or_fin).await.unwrap();
                     }
+                    Action::SendEvent(event_or_fin) => {
+                        let responses_manager = event_current_query_responses_manager.as_mut()
+                            .expect("Called SendEvent without calling ReceiveQuery");
+                        responses_manager.send_response(event_or_fin).await.unwrap();
+                    }
                     Action::CheckStorage(check_storage_fn) => {
                         // We tried avoiding the clone here but it causes lifetime issues.
                         check_storage_fn(storage_reader.clone()).await;
@@ -314,6 +395,35 @@ pub async fn run_test(
                                 data type");
                         responses_manager.assert_reported(TIMEOUT_FOR_TEST).await;
                     }
+                    Action::ValidateReportSent(DataType::Event) => {
+                        let responses_manager = event_current_query_responses_manager.take()
+                            .expect(
+                                "Called ValidateReportSent without calling ReceiveQuery on the same
+                                data type");
+                        responses_manager.assert_reported(TIMEOUT_FOR_TEST).await;
+                    }
+                    Action::ValidateNoQuery(data_type) => {
+                        // A query is pending only if the stream yielded one. The stream may have
+                        // ended if the sync dropped the sender of the data type.
+                        let has_query = match data_type {
+                            DataType::Header => {
+                                matches!(mock_header_network.next().now_or_never(), Some(Some(_)))
+                            }
+                            DataType::StateDiff => {
+                                matches!(mock_state_diff_network.next().now_or_never(), Some(Some(_)))
+                            }
+                            DataType::Transaction => {
+                                matches!(mock_transaction_network.next().now_or_never(), Some(Some(_)))
+                            }
+                            DataType::Class => {
+                                matches!(mock_class_network.next().now_or_never(), Some(Some(_)))
+                            }
+                            DataType::Event => {
+                                matches!(mock_event_network.next().now_or_never(), Some(Some(_)))
+                            }
+                        };
+                        assert!(!has_query, "The sync sent an unexpected query.");
+                    }
                     Action::SendInternalBlock(sync_block) => {
                         internal_block_sender.send(sync_block).await.unwrap();
                     }
@@ -352,10 +462,11 @@ pub fn random_header(
 ) -> SignedBlockHeader {
     SignedBlockHeader {
         block_header: BlockHeader {
-            // TODO(shahak): Remove this once get_test_instance puts random values.
-            block_hash: BlockHash(rng.next_u64().into()),
+            block_hash: test_block_hash(block_number),
             block_header_without_hash: BlockHeaderWithoutHash {
                 block_number,
+                parent_hash: test_parent_hash(block_number),
+                starknet_version: UNVERIFIED_STARKNET_VERSION,
                 ..GetTestInstance::get_test_instance(rng)
             },
             state_diff_length: Some(state_diff_length.unwrap_or_else(|| rng.gen())),
@@ -370,6 +481,91 @@ pub fn random_header(
     }
 }
 
+/// A header of a block without transactions whose hash and commitments are correct, so that it
+/// passes the verification of the sync.
+pub fn verifiable_header(
+    rng: &mut ChaCha8Rng,
+    block_number: BlockNumber,
+    state_diff: &ThinStateDiff,
+) -> SignedBlockHeader {
+    let block_header_without_hash = BlockHeaderWithoutHash {
+        block_number,
+        parent_hash: test_parent_hash(block_number),
+        starknet_version: StarknetVersion::LATEST,
+        ..GetTestInstance::get_test_instance(rng)
+    };
+    let commitments = calculate_block_commitments(
+        &[],
+        state_diff,
+        block_header_without_hash.l1_da_mode,
+        &block_header_without_hash.starknet_version,
+    );
+    SignedBlockHeader {
+        block_header: BlockHeader {
+            block_hash: calculate_block_hash(
+                block_header_without_hash.clone(),
+                commitments.clone(),
+            )
+            .unwrap(),
+            block_header_without_hash,
+            state_diff_commitment: Some(commitments.state_diff_commitment),
+            transaction_commitment: Some(commitments.transaction_commitment),
+            event_commitment: Some(commitments.event_commitment),
+            receipt_commitment: Some(commitments.receipt_commitment),
+            state_diff_length: Some(state_diff.len()),
+            n_transactions: 0,
+            n_events: 0,
+        },
+        signatures: vec![BlockSignature::default()],
+    }
+}
+
+/// Bodies with the given numbers of transactions, whose hashes are calculated from the
+/// transactions on the chain of the tests so that they pass the verification of the sync. Random
+/// transactions are mostly equal, so the transactions are invokes that differ in their nonces.
+pub fn random_bodies(
+    rng: &mut ChaCha8Rng,
+    num_transactions_per_body: impl IntoIterator<Item = usize>,
+) -> Vec<BlockBody> {
+    let mut nonces = (0u64..).map(|nonce| Nonce(Felt::from(nonce)));
+    num_transactions_per_body
+        .into_iter()
+        .map(|num_transactions| {
+            let mut body = BlockBody::default();
+            for nonce in nonces.by_ref().take(num_transactions) {
+                let transaction = Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1 {
+                    nonce,
+                    ..GetTestInstance::get_test_instance(rng)
+                }));
+                let transaction_hash = get_transaction_hash(
+                    &transaction,
+                    &CHAIN_ID_FOR_TESTS,
+                    &TransactionOptions::default(),
+                )
+                .unwrap();
+                body.transactions.push(transaction);
+                // The events are synced by the events protocol, so the outputs have none.
+                body.transaction_outputs.push(TransactionOutput::Invoke(InvokeTransactionOutput {
+                    events: vec![],
+                    ..GetTestInstance::get_test_instance(rng)
+                }));
+                body.transaction_hashes.push(transaction_hash);
+            }
+            body
+        })
+        .collect()
+}
+
+/// The hash of the block with the given number in the headers the tests send, so that consecutive
+/// headers form a chain. It matches the hashes of `create_block_hashes_and_signatures`.
+pub fn test_block_hash(block_number: BlockNumber) -> BlockHash {
+    BlockHash(StarkHash::from(block_number.0))
+}
+
+pub fn test_parent_hash(block_number: BlockNumber) -> BlockHash {
+    block_number.prev().map(test_block_hash).unwrap_or_default()
+}
+
 pub fn create_block_hashes_and_signatures(n_blocks: u8) -> Vec<(BlockHash, BlockSignature)> {
     let mut bytes = [0u8; 32];
     (0u8..n_blocks)
@@ -405,6 +601,7 @@ pub(crate) async fn wait_for_marker(
             DataType::Transaction => txn.get_body_marker().unwrap(),
             DataType::StateDiff => txn.get_state_marker().unwrap(),
             DataType::Class => txn.get_class_manager_block_marker().unwrap(),
+            DataType::Event => txn.get_event_marker().unwrap(),
         };
 
         if storage_marker >= expected_marker {
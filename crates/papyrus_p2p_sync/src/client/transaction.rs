@@ -6,9 +6,16 @@ use papyrus_storage::body::{BodyStorageReader, BodyStorageWriter};
 use papyrus_storage::header::HeaderStorageReader;
 use papyrus_storage::{StorageError, StorageReader, StorageWriter};
 use papyrus_sync::define_metrics::{SYNC_BODY_MARKER, SYNC_PROCESSED_TRANSACTIONS};
+use papyrus_sync::state_root::StateRootVerifier;
 use papyrus_test_utils::{get_rng, GetTestInstance};
 use starknet_api::block::{BlockBody, BlockNumber};
-use starknet_api::transaction::{FullTransaction, Transaction, TransactionOutput};
+use starknet_api::transaction::{
+    FullTransaction,
+    Transaction,
+    TransactionOptions,
+    TransactionOutput,
+};
+use starknet_api::transaction_hash::validate_transaction_hash;
 use starknet_class_manager_types::SharedClassManagerClient;
 use starknet_state_sync_types::state_sync_types::SyncBlock;
 
@@ -19,6 +26,7 @@ use super::block_data_stream_builder::{
     BlockNumberLimit,
     ParseDataError,
 };
+use super::commitments::{verify_transactions, VerificationParams};
 use super::P2pSyncClientError;
 
 #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
@@ -27,11 +35,13 @@ impl BlockData for (BlockBody, BlockNumber) {
         self: Box<Self>,
         storage_writer: &'a mut StorageWriter,
         _class_manager_client: &'a mut SharedClassManagerClient,
+        _state_root_verifier: &'a mut Option<StateRootVerifier>,
     ) -> BoxFuture<'a, Result<(), P2pSyncClientError>> {
         async move {
             let num_txs =
                 self.0.transactions.len().try_into().expect("Failed to convert usize to u64");
-            storage_writer.begin_rw_txn()?.append_body(self.1, self.0)?.commit()?;
+            // The events are synced separately by the events stream.
+            storage_writer.begin_rw_txn()?.append_body_without_events(self.1, self.0)?.commit()?;
             SYNC_BODY_MARKER.set(self.1.unchecked_next().0 as f64);
             SYNC_PROCESSED_TRANSACTIONS.increment(num_txs);
             Ok(())
@@ -43,7 +53,6 @@ impl BlockData for (BlockBody, BlockNumber) {
 pub(crate) struct TransactionStreamFactory;
 
 impl BlockDataStreamBuilder<FullTransaction> for TransactionStreamFactory {
-    // TODO(Eitan): Add events protocol to BlockBody or split their write to storage
     type Output = (BlockBody, BlockNumber);
 
     const TYPE_DESCRIPTION: &'static str = "transactions";
@@ -53,15 +62,16 @@ impl BlockDataStreamBuilder<FullTransaction> for TransactionStreamFactory {
         transactions_response_manager: &'a mut ClientResponsesManager<DataOrFin<FullTransaction>>,
         block_number: BlockNumber,
         storage_reader: &'a StorageReader,
+        verification_params: &'a VerificationParams,
     ) -> BoxFuture<'a, Result<Option<Self::Output>, ParseDataError>> {
         async move {
             let mut block_body = BlockBody::default();
             let mut current_transaction_len = 0;
-            let target_transaction_len = storage_reader
+            let header = storage_reader
                 .begin_ro_txn()?
                 .get_block_header(block_number)?
-                .expect("A header with number lower than the header marker is missing")
-                .n_transactions;
+                .expect("A header with number lower than the header marker is missing");
+            let target_transaction_len = header.n_transactions;
             while current_transaction_len < target_transaction_len {
                 let maybe_transaction = transactions_response_manager.next().await.ok_or(
                     ParseDataError::BadPeer(BadPeerError::SessionEndedWithoutFin {
@@ -81,12 +91,32 @@ impl BlockDataStreamBuilder<FullTransaction> for TransactionStreamFactory {
                         }));
                     }
                 };
+                // A hash that can't be calculated for the transaction is treated as a wrong hash.
+                if !validate_transaction_hash(
+                    &transaction,
+                    &block_number,
+                    &verification_params.chain_id,
+                    transaction_hash,
+                    &TransactionOptions::default(),
+                )
+                .unwrap_or(false)
+                {
+                    return Err(ParseDataError::BadPeer(BadPeerError::WrongTransactionHash {
+                        block_number,
+                        received: transaction_hash,
+                    }));
+                }
                 block_body.transactions.push(transaction);
                 block_body.transaction_outputs.push(transaction_output);
-                // TODO(eitan): Validate transaction hash from untrusted sources
                 block_body.transaction_hashes.push(transaction_hash);
                 current_transaction_len += 1;
             }
+            verify_transactions(
+                &header,
+                &block_body.transactions,
+                &block_body.transaction_outputs,
+                &block_body.transaction_hashes,
+            )?;
             Ok(Some((block_body, block_number)))
         }
         .boxed()
@@ -4,11 +4,12 @@ use std::collections::HashMap;
 use futures::FutureExt;
 use papyrus_protobuf::sync::{BlockHashOrNumber, DataOrFin, Direction, Query};
 use papyrus_storage::body::BodyStorageReader;
-use papyrus_test_utils::{get_rng, get_test_body};
+use papyrus_test_utils::get_rng;
 use starknet_api::block::{BlockBody, BlockNumber};
 use starknet_api::transaction::{FullTransaction, TransactionHash};
 
 use super::test_utils::{
+    random_bodies,
     random_header,
     run_test,
     wait_for_marker,
@@ -25,18 +26,8 @@ async fn transaction_basic_flow() {
 
     let mut rng = get_rng();
 
-    let block_bodies = (0..NUM_BLOCKS)
-        // TODO(shahak): remove Some(0) once we separate events from transactions correctly.
-        .map(|i| {
-            let mut body = get_test_body(i.try_into().unwrap(), Some(0), None, None);
-            // get_test_body returns transaction hash in the range 0..num_transactions. We want to
-            // avoid collisions in transaction hash.
-            for transaction_hash in &mut body.transaction_hashes {
-                *transaction_hash = TransactionHash(transaction_hash.0 + NUM_BLOCKS * i);
-            }
-            body
-        })
-        .collect::<Vec<_>>();
+    let block_bodies =
+        random_bodies(&mut rng, (0..NUM_BLOCKS).map(|i| usize::try_from(i).unwrap()));
 
     let mut actions = vec![
         Action::RunP2pSync,
@@ -136,16 +127,12 @@ async fn transaction_basic_flow() {
                 let txn = reader.begin_ro_txn().unwrap();
                 let actual_transactions =
                     txn.get_block_transactions(block_number).unwrap().unwrap();
-                // TODO(alonl): Uncomment this once we fix protobuf conversion for receipt
-                // builtins.
-                // let actual_transaction_outputs =
-                //     txn.get_block_transaction_outputs(block_number).unwrap().unwrap();
+                let actual_transaction_outputs =
+                    txn.get_block_transaction_outputs(block_number).unwrap().unwrap();
                 let actual_transaction_hashes =
                     txn.get_block_transaction_hashes(block_number).unwrap().unwrap();
                 assert_eq!(actual_transactions, transactions);
-                // TODO(alonl): Uncomment this once we fix protobuf conversion for receipt
-                // builtins.
-                // assert_eq!(actual_transaction_outputs, transaction_outputs);
+                assert_eq!(actual_transaction_outputs, transaction_outputs);
                 assert_eq!(actual_transaction_hashes, transaction_hashes);
             }
             .boxed()
@@ -166,3 +153,59 @@ async fn transaction_basic_flow() {
     )
     .await;
 }
+
+#[tokio::test]
+async fn wrong_transaction_hash() {
+    let mut rng = get_rng();
+    let mut block_body = random_bodies(&mut rng, [1]).pop().unwrap();
+    block_body.transaction_hashes[0] = TransactionHash(block_body.transaction_hashes[0].0 + 1);
+
+    let mut actions = vec![
+        Action::RunP2pSync,
+        // We already validate the header query content in other tests.
+        Action::ReceiveQuery(Box::new(|_query| ()), DataType::Header),
+        Action::SendHeader(DataOrFin(Some(random_header(
+            &mut rng,
+            BlockNumber(0),
+            None,
+            Some(block_body.transactions.len()),
+        )))),
+        Action::SendHeader(DataOrFin(None)),
+        Action::CheckStorage(Box::new(|reader| {
+            async move {
+                wait_for_marker(
+                    DataType::Header,
+                    &reader,
+                    BlockNumber(1),
+                    SLEEP_DURATION_TO_LET_SYNC_ADVANCE,
+                    TIMEOUT_FOR_TEST,
+                )
+                .await;
+            }
+            .boxed()
+        })),
+        Action::SimulateWaitPeriodForOtherProtocol,
+        // We already validate the transaction query content in other tests.
+        Action::ReceiveQuery(Box::new(|_query| ()), DataType::Transaction),
+    ];
+    let BlockBody { transactions, transaction_outputs, transaction_hashes } = block_body;
+    for (transaction, (transaction_output, transaction_hash)) in
+        transactions.into_iter().zip(transaction_outputs.into_iter().zip(transaction_hashes))
+    {
+        actions.push(Action::SendTransaction(DataOrFin(Some(FullTransaction {
+            transaction,
+            transaction_output,
+            transaction_hash,
+        }))));
+    }
+    actions.push(Action::ValidateReportSent(DataType::Transaction));
+    actions.push(Action::CheckStorage(Box::new(|reader| {
+        async move {
+            assert_eq!(BlockNumber(0), reader.begin_ro_txn().unwrap().get_body_marker().unwrap());
+        }
+        .boxed()
+    })));
+
+    run_test(HashMap::from([(DataType::Header, 1), (DataType::Transaction, 1)]), None, actions)
+        .await;
+}
@@ -346,6 +346,12 @@ impl FetchBlockData for (Event, TransactionHash) {
         txn: &StorageTxn<'_, db::RO>,
         _class_manager_client: &mut SharedClassManagerClient,
     ) -> Result<Vec<Self>, P2pSyncServerError> {
+        // A body that was synced without its events can't be served until its events are synced.
+        if block_number >= txn.get_event_marker()? {
+            return Err(P2pSyncServerError::BlockNotFound {
+                block_hash_or_number: BlockHashOrNumber::Number(block_number),
+            });
+        }
         let transaction_outputs = txn.get_block_transaction_outputs(block_number)?.ok_or(
             P2pSyncServerError::BlockNotFound {
                 block_hash_or_number: BlockHashOrNumber::Number(block_number),
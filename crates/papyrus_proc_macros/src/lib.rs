@@ -2,7 +2,19 @@ use std::str::FromStr;
 
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
-use syn::{parse_macro_input, ExprLit, Ident, ItemFn, ItemTrait, LitBool, LitStr, Meta, TraitItem};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_macro_input,
+    ExprLit,
+    Ident,
+    ItemFn,
+    ItemTrait,
+    LitBool,
+    LitStr,
+    Meta,
+    Token,
+    TraitItem,
+};
 
 /// This macro is a wrapper around the "rpc" macro supplied by the jsonrpsee library that generates
 /// a server and client traits from a given trait definition. The wrapper gets a version id and
@@ -11,6 +23,9 @@ use syn::{parse_macro_input, ExprLit, Ident, ItemFn, ItemTrait, LitBool, LitStr,
 /// able to merge multiple versions of jsonrpc APIs into one server and not have a clash in method
 /// resolution.
 ///
+/// The generated trait is in the "starknet" namespace unless a namespace is given as a second
+/// argument, e.g. `#[versioned_rpc("V0_6_0", "papyrus")]`.
+///
 /// # Example:
 ///
 /// Given this code:
@@ -32,7 +47,10 @@ use syn::{parse_macro_input, ExprLit, Ident, ItemFn, ItemTrait, LitBool, LitStr,
 /// ```
 #[proc_macro_attribute]
 pub fn versioned_rpc(attr: TokenStream, input: TokenStream) -> TokenStream {
-    let version = parse_macro_input!(attr as syn::LitStr);
+    let args = parse_macro_input!(attr with Punctuated::<LitStr, Token![,]>::parse_terminated);
+    let mut args = args.into_iter();
+    let version = args.next().expect("versioned_rpc expects a version id");
+    let namespace = args.next().map_or_else(|| "starknet".to_string(), |lit| lit.value());
     let item_trait = parse_macro_input!(input as ItemTrait);
 
     let trait_name = &item_trait.ident;
@@ -82,7 +100,7 @@ pub fn versioned_rpc(attr: TokenStream, input: TokenStream) -> TokenStream {
 
     // generate the versioned trait with the new method signatures
     let versioned_trait = syn::ItemTrait {
-        attrs: vec![syn::parse_quote!(#[rpc(server, client, namespace = "starknet")])],
+        attrs: vec![syn::parse_quote!(#[rpc(server, client, namespace = #namespace)])],
         vis: visibility.clone(),
         unsafety: None,
         auto_token: None,
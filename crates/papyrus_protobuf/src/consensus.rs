@@ -44,6 +44,14 @@ pub struct StreamMessage<T: IntoFromProto, StreamId: IntoFromProto + Clone> {
     pub message_id: u64,
 }
 
+/// A request for the messages of a stream with IDs in `[first_message_id, last_message_id]`.
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct StreamMessagesRequest<StreamId: IntoFromProto + Clone> {
+    pub stream_id: StreamId,
+    pub first_message_id: u64,
+    pub last_message_id: u64,
+}
+
 /// This message must be sent first when proposing a new block.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ProposalInit {
@@ -22,6 +22,7 @@ use crate::consensus::{
     ProposalPart,
     StreamMessage,
     StreamMessageBody,
+    StreamMessagesRequest,
     TransactionBatch,
     Vote,
     VoteType,
@@ -166,6 +167,55 @@ where
     }
 }
 
+impl<StreamId> TryFrom<protobuf::StreamMessagesRequest> for StreamMessagesRequest<StreamId>
+where
+    StreamId: IntoFromProto + Clone,
+{
+    type Error = ProtobufConversionError;
+
+    fn try_from(value: protobuf::StreamMessagesRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            stream_id: value.stream_id.try_into()?,
+            first_message_id: value.first_message_id,
+            last_message_id: value.last_message_id,
+        })
+    }
+}
+
+impl<StreamId> From<StreamMessagesRequest<StreamId>> for protobuf::StreamMessagesRequest
+where
+    StreamId: IntoFromProto + Clone,
+{
+    fn from(value: StreamMessagesRequest<StreamId>) -> Self {
+        Self {
+            stream_id: value.stream_id.into(),
+            first_message_id: value.first_message_id,
+            last_message_id: value.last_message_id,
+        }
+    }
+}
+
+// Can't use auto_impl_into_and_try_from_vec_u8! with generics, see StreamMessage.
+impl<StreamId> From<StreamMessagesRequest<StreamId>> for Vec<u8>
+where
+    StreamId: IntoFromProto + Clone,
+{
+    fn from(value: StreamMessagesRequest<StreamId>) -> Self {
+        <protobuf::StreamMessagesRequest>::from(value).encode_to_vec()
+    }
+}
+
+impl<StreamId> TryFrom<Vec<u8>> for StreamMessagesRequest<StreamId>
+where
+    StreamId: IntoFromProto + Clone,
+{
+    type Error = ProtobufConversionError;
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        let protobuf_value = <protobuf::StreamMessagesRequest>::decode(&value[..])?;
+        Self::try_from(protobuf_value)
+    }
+}
+
 impl TryFrom<protobuf::ProposalInit> for ProposalInit {
     type Error = ProtobufConversionError;
     fn try_from(value: protobuf::ProposalInit) -> Result<Self, Self::Error> {
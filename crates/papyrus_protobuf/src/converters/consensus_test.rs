@@ -18,6 +18,7 @@ use crate::consensus::{
     ProposalPart,
     StreamMessage,
     StreamMessageBody,
+    StreamMessagesRequest,
     TransactionBatch,
     Vote,
 };
@@ -66,6 +67,19 @@ fn convert_stream_message_to_vec_u8_and_back() {
     assert_eq!(stream_message, res_data);
 }
 
+#[test]
+fn convert_stream_messages_request_to_vec_u8_and_back() {
+    let request = StreamMessagesRequest {
+        stream_id: TestStreamId(12),
+        first_message_id: 3,
+        last_message_id: 47,
+    };
+
+    let bytes_data: Vec<u8> = request.clone().into();
+    let res_data = StreamMessagesRequest::try_from(bytes_data).unwrap();
+    assert_eq!(request, res_data);
+}
+
 #[test]
 fn convert_vote_to_vec_u8_and_back() {
     let mut rng = get_rng();
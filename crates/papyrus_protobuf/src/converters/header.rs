@@ -9,6 +9,7 @@ use starknet_api::block::{
     BlockHeaderWithoutHash,
     BlockNumber,
     BlockSignature,
+    GasPrice,
     GasPricePerToken,
     StarknetVersion,
 };
@@ -21,6 +22,7 @@ use starknet_api::core::{
     TransactionCommitment,
 };
 use starknet_api::crypto::utils::Signature;
+use starknet_api::execution_resources::GasAmount;
 use starknet_api::hash::PoseidonHash;
 
 use super::common::{
@@ -170,6 +172,10 @@ impl TryFrom<protobuf::SignedBlockHeader> for SignedBlockHeader {
             .into(),
         };
 
+        let l2_gas_consumed = GasAmount(value.l2_gas_consumed);
+        let next_l2_gas_price =
+            value.next_l2_gas_price.map(|price| GasPrice(u128::from(price))).unwrap_or_default();
+
         let receipt_commitment = value
             .receipts
             .map(|receipts| receipts.try_into().map(ReceiptCommitment))
@@ -197,6 +203,8 @@ impl TryFrom<protobuf::SignedBlockHeader> for SignedBlockHeader {
                     timestamp,
                     l1_da_mode,
                     starknet_version,
+                    l2_gas_consumed,
+                    next_l2_gas_price,
                 },
                 state_diff_commitment,
                 state_diff_length,
@@ -277,6 +285,8 @@ impl From<(BlockHeader, Vec<BlockSignature>)> for protobuf::SignedBlockHeader {
                 header.block_header_without_hash.l1_da_mode,
             ),
             signatures: signatures.iter().map(|signature| (*signature).into()).collect(),
+            l2_gas_consumed: header.block_header_without_hash.l2_gas_consumed.0,
+            next_l2_gas_price: Some(header.block_header_without_hash.next_l2_gas_price.0.into()),
         }
     }
 }
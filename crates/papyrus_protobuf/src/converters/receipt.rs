@@ -250,43 +250,52 @@ impl From<L1HandlerTransactionOutput> for protobuf::receipt::L1Handler {
 
 type ProtobufBuiltinCounter = protobuf::receipt::execution_resources::BuiltinCounter;
 
+// Builtins that weren't used aren't part of the map, so a count of 0 isn't converted into an entry.
 impl TryFrom<ProtobufBuiltinCounter> for HashMap<Builtin, u64> {
     type Error = ProtobufConversionError;
     fn try_from(value: ProtobufBuiltinCounter) -> Result<Self, Self::Error> {
-        let mut builtin_instance_counter = HashMap::new();
-        builtin_instance_counter.insert(Builtin::RangeCheck, u64::from(value.range_check));
-        builtin_instance_counter.insert(Builtin::Pedersen, u64::from(value.pedersen));
-        builtin_instance_counter.insert(Builtin::Poseidon, u64::from(value.poseidon));
-        builtin_instance_counter.insert(Builtin::EcOp, u64::from(value.ec_op));
-        builtin_instance_counter.insert(Builtin::Ecdsa, u64::from(value.ecdsa));
-        builtin_instance_counter.insert(Builtin::Bitwise, u64::from(value.bitwise));
-        builtin_instance_counter.insert(Builtin::Keccak, u64::from(value.keccak));
-        builtin_instance_counter.insert(Builtin::SegmentArena, 0);
+        let builtin_instance_counter = [
+            (Builtin::RangeCheck, value.range_check),
+            (Builtin::Pedersen, value.pedersen),
+            (Builtin::Poseidon, value.poseidon),
+            (Builtin::EcOp, value.ec_op),
+            (Builtin::Ecdsa, value.ecdsa),
+            (Builtin::Bitwise, value.bitwise),
+            (Builtin::Keccak, value.keccak),
+            (Builtin::SegmentArena, value.segment_arena),
+            (Builtin::AddMod, value.add_mod),
+            (Builtin::MulMod, value.mul_mod),
+            (Builtin::RangeCheck96, value.range_check96),
+        ]
+        .into_iter()
+        .filter(|(_, count)| *count != 0)
+        .map(|(builtin, count)| (builtin, u64::from(count)))
+        .collect();
         Ok(builtin_instance_counter)
     }
 }
 
 impl From<HashMap<Builtin, u64>> for ProtobufBuiltinCounter {
     fn from(value: HashMap<Builtin, u64>) -> Self {
-        let builtin_counter = ProtobufBuiltinCounter {
-            range_check: u32::try_from(*value.get(&Builtin::RangeCheck).unwrap_or(&0))
+        let count = |builtin| {
+            u32::try_from(*value.get(&builtin).unwrap_or(&0))
                 // TODO(Shahak): should not panic
-                .expect("Failed to convert u64 to u32"),
-            pedersen: u32::try_from(*value.get(&Builtin::Pedersen).unwrap_or(&0))
-                .expect("Failed to convert u64 to u32"),
-            poseidon: u32::try_from(*value.get(&Builtin::Poseidon).unwrap_or(&0))
-                .expect("Failed to convert u64 to u32"),
-            ec_op: u32::try_from(*value.get(&Builtin::EcOp).unwrap_or(&0))
-                .expect("Failed to convert u64 to u32"),
-            ecdsa: u32::try_from(*value.get(&Builtin::Ecdsa).unwrap_or(&0))
-                .expect("Failed to convert u64 to u32"),
-            bitwise: u32::try_from(*value.get(&Builtin::Bitwise).unwrap_or(&0))
-                .expect("Failed to convert u64 to u32"),
-            keccak: u32::try_from(*value.get(&Builtin::Keccak).unwrap_or(&0))
-                .expect("Failed to convert u64 to u32"),
-            output: 0,
+                .expect("Failed to convert u64 to u32")
         };
-        builtin_counter
+        ProtobufBuiltinCounter {
+            range_check: count(Builtin::RangeCheck),
+            pedersen: count(Builtin::Pedersen),
+            poseidon: count(Builtin::Poseidon),
+            ec_op: count(Builtin::EcOp),
+            ecdsa: count(Builtin::Ecdsa),
+            bitwise: count(Builtin::Bitwise),
+            keccak: count(Builtin::Keccak),
+            output: 0,
+            segment_arena: count(Builtin::SegmentArena),
+            add_mod: count(Builtin::AddMod),
+            mul_mod: count(Builtin::MulMod),
+            range_check96: count(Builtin::RangeCheck96),
+        }
     }
 }
 
@@ -188,7 +188,10 @@ lazy_static! {
             (Builtin::Ecdsa, 5),
             (Builtin::Bitwise, 6),
             (Builtin::Keccak, 7),
-            (Builtin::SegmentArena, 0),
+            (Builtin::SegmentArena, 8),
+            (Builtin::AddMod, 9),
+            (Builtin::MulMod, 10),
+            (Builtin::RangeCheck96, 11),
         ]),
         memory_holes: 0,
         da_gas_consumed: GasVector::default(),
@@ -169,7 +169,7 @@ fn internal_server_error_with_msg(err: impl std::fmt::Display) -> ErrorObjectOwn
 
 fn verify_storage_scope(storage_reader: &StorageReader) -> RpcResult<()> {
     match storage_reader.get_scope() {
-        StorageScope::StateOnly => {
+        StorageScope::StateOnly | StorageScope::Pruned => {
             Err(internal_server_error_with_msg("Unsupported method in state-only scope."))
         }
         StorageScope::FullArchive => Ok(()),
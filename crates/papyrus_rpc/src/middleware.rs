@@ -63,10 +63,12 @@ fn add_version_to_method_name_in_body(
     let Ok(vec_body) = vec_body
         .iter_mut()
         .map(|body| {
-            let Some(stripped_method) = strip_starknet_from_method(body.method.as_ref()) else {
+            let Some((namespace, stripped_method)) =
+                split_namespace_from_method(body.method.as_ref())
+            else {
                 return Err(BoxError::from("Method name has unexpected format"));
             };
-            body.method = format!("starknet_{prefix}_{stripped_method}").into();
+            body.method = format!("{namespace}_{prefix}_{stripped_method}").into();
             Ok(body)
         })
         .collect::<Result<Vec<_>, _>>()
@@ -81,10 +83,11 @@ fn add_version_to_method_name_in_body(
 }
 
 /// this assumes that all methods are of the form:
-/// starknet_OnlyOneUnderScoreAndMethodNameIsCamleCased
-fn strip_starknet_from_method(method: &str) -> Option<&str> {
+/// namespace_OnlyOneUnderScoreAndMethodNameIsCamleCased
+/// where the namespace is "starknet", or "papyrus" for the methods that aren't part of the specs.
+fn split_namespace_from_method(method: &str) -> Option<(&str, &str)> {
     let split_method_name = method.split('_').collect::<Vec<_>>();
-    split_method_name.get(1).copied()
+    Some((split_method_name.first().copied()?, split_method_name.get(1).copied()?))
 }
 
 #[instrument(level = "debug", err)]
@@ -196,6 +196,26 @@ async fn test_version_middleware() {
     };
 }
 
+#[tokio::test]
+async fn version_middleware_keeps_papyrus_namespace() {
+    let params = serde_json::from_str(r#"[{"myParam": "myValue"}]"#).unwrap();
+    let request_body = serde_json::to_string(&jsonrpsee::types::Request::new(
+        "papyrus_myMethod".into(),
+        Some(params),
+        jsonrpsee::types::Id::Number(0),
+    ))
+    .unwrap();
+    let version_id = VERSION_CONFIG[0].0;
+    let req = Request::post(format!("http://localhost:8080/rpc/{}", version_id.name))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(request_body))
+        .unwrap();
+    let res = proxy_rpc_request(req).await.unwrap();
+    let body_bytes = get_json_rpc_body(res).await;
+    let body = serde_json::from_slice::<jsonrpsee::types::Request<'_>>(&body_bytes).unwrap();
+    assert_eq!(body.method, format!("papyrus_{}_myMethod", version_id.name));
+}
+
 #[test]
 fn get_block_status_test() {
     let (reader, mut writer) = get_test_storage().0;
@@ -78,17 +78,20 @@ use super::super::broadcasted_transaction::{
     BroadcastedTransaction,
 };
 use super::super::error::{
+    state_history_pruned,
     ContractError,
     JsonRpcError,
     TransactionExecutionError,
     BLOCK_NOT_FOUND,
     CLASS_HASH_NOT_FOUND,
     CONTRACT_NOT_FOUND,
+    INVALID_CONTINUATION_TOKEN,
     INVALID_TRANSACTION_HASH,
     INVALID_TRANSACTION_INDEX,
     NO_BLOCKS,
     PAGE_SIZE_TOO_BIG,
     TOO_MANY_KEYS_IN_FILTER,
+    TOO_MANY_STORAGE_KEYS,
     TRANSACTION_HASH_NOT_FOUND,
 };
 use super::super::execution::TransactionTrace;
@@ -130,14 +133,20 @@ use super::{
     BlockHashAndNumber,
     BlockId,
     CallRequest,
+    ClassHashUpdate,
     CompiledContractClass,
     ContinuationToken,
+    ContractHistory,
     EventFilter,
     EventsChunk,
     GatewayContractClass,
     JsonRpcV0_8Server as JsonRpcServer,
+    NonceUpdate,
+    PapyrusJsonRpcV0_8Server as PapyrusJsonRpcServer,
     SimulatedTransaction,
     SimulationFlag,
+    StorageKeyHistory,
+    StorageValueUpdate,
     TransactionTraceWithHash,
 };
 use crate::api::{BlockHashOrNumber, JsonRpcServerTrait, Tag};
@@ -157,7 +166,13 @@ use crate::{
 
 const DONT_IGNORE_L1_DA_MODE: bool = false;
 
+// The maximal chunk size of a papyrus_getContractHistory request.
+const MAX_CONTRACT_HISTORY_CHUNK_SIZE: usize = 1000;
+// The maximal number of storage keys in a papyrus_getContractHistory request.
+const MAX_CONTRACT_HISTORY_STORAGE_KEYS: usize = 100;
+
 /// Rpc server.
+#[derive(Clone)]
 pub struct JsonRpcServerImpl {
     pub chain_id: ChainId,
     pub execution_config: ExecutionConfig,
@@ -289,6 +304,10 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 ))
             },
             |txn, block_number| {
+                // The receipts of a block whose events weren't synced yet are incomplete.
+                if block_number >= txn.get_event_marker().map_err(internal_server_error)? {
+                    return Err(ErrorObjectOwned::from(BLOCK_NOT_FOUND));
+                }
                 let transactions = get_block_txs_by_number(txn, block_number)?;
                 let transaction_hashes = get_block_tx_hashes_by_number(txn, block_number)?;
                 Ok(Transactions::FullWithReceipts(
@@ -357,7 +376,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
             contract_address,
             key,
         )
-        .map_err(internal_server_error)?;
+        .map_err(state_read_error)?;
 
         // If the contract is not deployed, res will be 0. Checking if that's the case so that
         // we'll return an error instead.
@@ -368,7 +387,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
             txn.get_state_reader()
                 .map_err(internal_server_error)?
                 .get_class_hash_at(state_number, &contract_address)
-                .map_err(internal_server_error)?
+                .map_err(state_read_error)?
                 .ok_or_else(|| ErrorObjectOwned::from(CONTRACT_NOT_FOUND))?;
         }
         Ok(res)
@@ -667,7 +686,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
             maybe_pending_nonces.as_ref(),
             contract_address,
         )
-        .map_err(internal_server_error)?
+        .map_err(state_read_error)?
         .ok_or_else(|| ErrorObjectOwned::from(CONTRACT_NOT_FOUND))
     }
 
@@ -731,17 +750,33 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 "A block number that's greater than another block number should have a predecessor",
             );
         }
+        // The events of the blocks from the event marker on weren't synced yet, so the events are
+        // returned only up to it, without the pending events that follow the missing ones.
+        let event_marker = txn.get_event_marker().map_err(internal_server_error)?;
+        let include_pending_block = include_pending_block && event_marker > latest_block_number;
+        if let Some(last_block_with_events) = event_marker.prev() {
+            to_block_number = to_block_number.min(last_block_with_events);
+        }
 
         // Collect the requested events.
         // Once we collected enough events, we continue to check if there are any more events
         // corresponding to the requested filter. If there are, we return a continuation token
         // pointing to the next relevant event. Otherwise, we return a continuation token None.
         let mut filtered_events = vec![];
-        if start_event_index.0.0 <= latest_block_number {
-            for ((from_address, event_index), content) in txn
-                .iter_events(filter.address, start_event_index, to_block_number)
-                .map_err(internal_server_error)?
-            {
+        if start_event_index.0.0 <= latest_block_number && start_event_index.0.0 < event_marker {
+            // Without an address to filter by, the index of the events by their first key spares
+            // reading the events that don't match the first keys of the filter.
+            let events_iter = match (filter.address, filter.keys.first()) {
+                (None, Some(first_keys)) if !first_keys.is_empty() => txn
+                    .iter_events_by_first_keys(
+                        first_keys.iter().cloned().collect(),
+                        start_event_index,
+                        to_block_number,
+                    ),
+                _ => txn.iter_events(filter.address, start_event_index, to_block_number),
+            }
+            .map_err(internal_server_error)?;
+            for ((from_address, event_index), content) in events_iter {
                 let block_number = (event_index.0).0;
                 if block_number > to_block_number {
                     break;
@@ -1499,6 +1534,116 @@ impl JsonRpcServer for JsonRpcServerImpl {
     }
 }
 
+#[async_trait]
+impl PapyrusJsonRpcServer for JsonRpcServerImpl {
+    #[instrument(skip(self), level = "debug", err, ret)]
+    fn get_contract_history(
+        &self,
+        contract_address: ContractAddress,
+        storage_keys: Vec<StorageKey>,
+        from_block: BlockId,
+        to_block: BlockId,
+        chunk_size: usize,
+        continuation_token: Option<ContinuationToken>,
+    ) -> RpcResult<ContractHistory> {
+        if chunk_size > MAX_CONTRACT_HISTORY_CHUNK_SIZE {
+            return Err(ErrorObjectOwned::from(PAGE_SIZE_TOO_BIG));
+        }
+        if storage_keys.len() > MAX_CONTRACT_HISTORY_STORAGE_KEYS {
+            return Err(ErrorObjectOwned::from(TOO_MANY_STORAGE_KEYS));
+        }
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let mut from_block_number = get_accepted_block_number(&txn, from_block)?;
+        let to_block_number = get_accepted_block_number(&txn, to_block)?;
+        if let Some(continuation_token) = continuation_token {
+            let token_block_number = continuation_token
+                .0
+                .parse()
+                .map(BlockNumber)
+                .map_err(|_| ErrorObjectOwned::from(INVALID_CONTINUATION_TOKEN))?;
+            if !(from_block_number..=to_block_number).contains(&token_block_number) {
+                return Err(ErrorObjectOwned::from(INVALID_CONTINUATION_TOKEN));
+            }
+            from_block_number = token_block_number;
+        }
+        let block_range = from_block_number..to_block_number.unchecked_next();
+        let state_reader = txn.get_state_reader().map_err(internal_server_error)?;
+
+        // Reading chunk_size + 1 updates of each history is enough to find the first block whose
+        // updates don't fit in the chunk.
+        let class_hashes = state_reader
+            .get_class_hash_history(&contract_address, block_range.clone())
+            .map_err(state_read_error)?
+            .take(chunk_size + 1)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(state_read_error)?;
+        let nonces = state_reader
+            .get_nonce_history(&contract_address, block_range.clone())
+            .map_err(state_read_error)?
+            .take(chunk_size + 1)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(state_read_error)?;
+        let storage = storage_keys
+            .into_iter()
+            .map(|key| {
+                let updates = state_reader
+                    .get_storage_history(&contract_address, &key, block_range.clone())
+                    .map_err(state_read_error)?
+                    .take(chunk_size + 1)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(state_read_error)?;
+                Ok((key, updates))
+            })
+            .collect::<RpcResult<Vec<_>>>()?;
+
+        let mut block_numbers = class_hashes
+            .iter()
+            .map(|(block_number, _)| *block_number)
+            .chain(nonces.iter().map(|(block_number, _)| *block_number))
+            .chain(
+                storage
+                    .iter()
+                    .flat_map(|(_, updates)| updates.iter().map(|(block_number, _)| *block_number)),
+            )
+            .collect::<Vec<_>>();
+        block_numbers.sort_unstable();
+        let chunk_end = match block_numbers.get(chunk_size) {
+            None => block_range.end,
+            Some(first_block_not_in_chunk) if *first_block_not_in_chunk > block_numbers[0] => {
+                *first_block_not_in_chunk
+            }
+            // The updates of the first block don't fit in the chunk. They are returned anyway
+            // since the updates of a block are never split.
+            Some(_) => block_numbers[0].unchecked_next(),
+        };
+        let continuation_token =
+            (chunk_end < block_range.end).then(|| ContinuationToken(chunk_end.0.to_string()));
+
+        let class_hashes = class_hashes
+            .into_iter()
+            .take_while(|(block_number, _)| *block_number < chunk_end)
+            .map(|(block_number, class_hash)| ClassHashUpdate { block_number, class_hash })
+            .collect();
+        let nonces = nonces
+            .into_iter()
+            .take_while(|(block_number, _)| *block_number < chunk_end)
+            .map(|(block_number, nonce)| NonceUpdate { block_number, nonce })
+            .collect();
+        let storage = storage
+            .into_iter()
+            .map(|(key, updates)| StorageKeyHistory {
+                key,
+                updates: updates
+                    .into_iter()
+                    .take_while(|(block_number, _)| *block_number < chunk_end)
+                    .map(|(block_number, value)| StorageValueUpdate { block_number, value })
+                    .collect(),
+            })
+            .collect();
+        Ok(ContractHistory { class_hashes, nonces, storage, continuation_token })
+    }
+}
+
 async fn read_pending_data<Mode: TransactionKind>(
     pending_data: &Arc<RwLock<PendingData>>,
     txn: &StorageTxn<'_, Mode>,
@@ -1618,7 +1763,7 @@ impl JsonRpcServerImpl {
             maybe_pending_deployed_contracts_and_replaced_classes.as_ref().map(|t| (&t.0, &t.1)),
             contract_address,
         )
-        .map_err(internal_server_error)
+        .map_err(state_read_error)
     }
 
     async fn is_deployed(
@@ -1657,6 +1802,14 @@ impl JsonRpcServerImpl {
     }
 }
 
+// Converts an error of reading the state at some block, which might have been pruned.
+fn state_read_error(err: StorageError) -> ErrorObjectOwned {
+    match err {
+        StorageError::StateHistoryPruned { .. } => state_history_pruned(err.to_string()).into(),
+        err => internal_server_error(err),
+    }
+}
+
 fn get_non_pending_receipt<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
     transaction_index: TransactionIndex,
@@ -1673,6 +1826,10 @@ fn get_non_pending_receipt<Mode: TransactionKind>(
     if status == BlockStatus::Rejected {
         return Err(ErrorObjectOwned::from(BLOCK_NOT_FOUND));
     }
+    // The receipt of a transaction whose events weren't synced yet is incomplete.
+    if block_number >= txn.get_event_marker().map_err(internal_server_error)? {
+        return Err(ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND));
+    }
 
     let block_hash =
         get_block_header_by_number(txn, block_number).map_err(internal_server_error)?.block_hash;
@@ -1751,6 +1908,11 @@ impl JsonRpcServerTrait for JsonRpcServerImpl {
     }
 
     fn into_rpc_module(self) -> RpcModule<Self> {
-        self.into_rpc()
+        let papyrus_methods = PapyrusJsonRpcServer::into_rpc(self.clone());
+        let mut module = JsonRpcServer::into_rpc(self);
+        module
+            .merge(papyrus_methods)
+            .expect("The papyrus methods should not have the names of the starknet methods.");
+        module
     }
 }
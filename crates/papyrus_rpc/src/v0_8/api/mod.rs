@@ -267,6 +267,29 @@ pub trait JsonRpc {
     ) -> RpcResult<(CompiledContractClass, SierraVersion)>;
 }
 
+/// Papyrus extensions to the Starknet JSON-RPC API. These methods aren't part of the specs and are
+/// served under the "papyrus" namespace.
+#[versioned_rpc("V0_8", "papyrus")]
+#[async_trait]
+pub trait PapyrusJsonRpc {
+    /// Returns the updates of the class hash, the nonce and the given storage keys of the contract
+    /// at the given address, in the blocks between the given blocks (inclusive).
+    ///
+    /// At most chunk_size updates are returned, unless all the updates of the first block exceed
+    /// it, since the updates of a block are never split between chunks. If there are more updates,
+    /// the result contains a continuation token to pass in the next request.
+    #[method(name = "getContractHistory")]
+    fn get_contract_history(
+        &self,
+        contract_address: ContractAddress,
+        storage_keys: Vec<StorageKey>,
+        from_block: BlockId,
+        to_block: BlockId,
+        chunk_size: usize,
+        continuation_token: Option<ContinuationToken>,
+    ) -> RpcResult<ContractHistory>;
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[cfg_attr(test, derive(PartialEq))]
 #[serde(untagged)]
@@ -282,6 +305,39 @@ pub struct EventsChunk {
     pub continuation_token: Option<ContinuationToken>,
 }
 
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ContractHistory {
+    pub class_hashes: Vec<ClassHashUpdate>,
+    pub nonces: Vec<NonceUpdate>,
+    pub storage: Vec<StorageKeyHistory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub continuation_token: Option<ContinuationToken>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClassHashUpdate {
+    pub block_number: BlockNumber,
+    pub class_hash: ClassHash,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct NonceUpdate {
+    pub block_number: BlockNumber,
+    pub nonce: Nonce,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StorageKeyHistory {
+    pub key: StorageKey,
+    pub updates: Vec<StorageValueUpdate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StorageValueUpdate {
+    pub block_number: BlockNumber,
+    pub value: Felt,
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct EventFilter {
     #[serde(skip_serializing_if = "Option::is_none")]
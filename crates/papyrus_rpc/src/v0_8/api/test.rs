@@ -117,6 +117,7 @@ use super::super::block::{Block, GeneralBlockHeader, PendingBlockHeader};
 use super::super::broadcasted_transaction::BroadcastedDeclareTransaction;
 use super::super::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use super::super::error::{
+    state_history_pruned,
     unexpected_error,
     JsonRpcError,
     BLOCK_NOT_FOUND,
@@ -129,6 +130,7 @@ use super::super::error::{
     NO_BLOCKS,
     PAGE_SIZE_TOO_BIG,
     TOO_MANY_KEYS_IN_FILTER,
+    TOO_MANY_STORAGE_KEYS,
     TRANSACTION_HASH_NOT_FOUND,
 };
 use super::super::state::{
@@ -171,7 +173,16 @@ use super::super::write_api_result::{
     AddInvokeOkResult,
 };
 use super::api_impl::JsonRpcServerImpl;
-use super::{ContinuationToken, EventFilter, GatewayContractClass};
+use super::{
+    ClassHashUpdate,
+    ContinuationToken,
+    ContractHistory,
+    EventFilter,
+    GatewayContractClass,
+    NonceUpdate,
+    StorageKeyHistory,
+    StorageValueUpdate,
+};
 use crate::api::{BlockHashOrNumber, BlockId, Tag};
 use crate::syncing_state::SyncStatus;
 use crate::test_utils::{
@@ -2198,6 +2209,57 @@ async fn get_storage_at() {
     assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
 }
 
+#[tokio::test]
+async fn get_storage_at_pruned_state() {
+    let method_name = "starknet_V0_8_getStorageAt";
+    let (module, mut storage_writer) =
+        get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+    let address = contract_address!("0x11");
+    let key = storage_key!("0x12");
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    for i in 0..2_u8 {
+        let block_number = BlockNumber(i.into());
+        let header = BlockHeader {
+            block_hash: BlockHash(felt!(i)),
+            block_header_without_hash: BlockHeaderWithoutHash {
+                block_number,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let diff = starknet_api::state::ThinStateDiff {
+            deployed_contracts: indexmap! { address => ClassHash::default() },
+            storage_diffs: indexmap! { address => indexmap! { key => felt!(i) } },
+            ..Default::default()
+        };
+        txn = txn
+            .append_header(block_number, &header)
+            .unwrap()
+            .append_state_diff(block_number, diff)
+            .unwrap();
+    }
+    txn.prune_state_history(BlockNumber(2)).unwrap().commit().unwrap();
+
+    // The state after the last block wasn't pruned.
+    let res = module
+        .call::<_, Felt>(
+            method_name,
+            (address, key, BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1)))),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res, felt!(1_u8));
+
+    let err = module
+        .call::<_, Felt>(
+            method_name,
+            (address, key, BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0)))),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err.code() == state_history_pruned(String::new()).code);
+}
+
 fn generate_client_transaction_client_receipt_rpc_transaction_and_rpc_receipt(
     rng: &mut ChaCha8Rng,
 ) -> (ClientTransaction, ClientTransactionReceipt, Transaction, PendingTransactionReceipt) {
@@ -3486,6 +3548,73 @@ async fn get_events_invalid_ct() {
     .await;
 }
 
+#[tokio::test]
+async fn events_and_receipts_of_blocks_without_events_are_not_returned() {
+    let (module, mut storage_writer) =
+        get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+    let block_with_events = get_test_block(1, Some(1), None, None);
+    let mut block_without_events = get_test_block(1, None, None, None);
+    block_without_events.header.block_header_without_hash.block_number = BlockNumber(1);
+    block_without_events.header.block_hash = BlockHash(felt!("0x1"));
+    block_without_events.body.transaction_hashes[0] = tx_hash!(1);
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &block_with_events.header)
+        .unwrap()
+        .append_body(BlockNumber(0), block_with_events.body)
+        .unwrap()
+        .append_state_diff(BlockNumber(0), starknet_api::state::ThinStateDiff::default())
+        .unwrap()
+        .append_header(BlockNumber(1), &block_without_events.header)
+        .unwrap()
+        .append_body_without_events(BlockNumber(1), block_without_events.body.clone())
+        .unwrap()
+        .append_state_diff(BlockNumber(1), starknet_api::state::ThinStateDiff::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+    let filter = EventFilter { chunk_size: 10, ..Default::default() };
+    let transaction_hash = block_without_events.body.transaction_hashes[0];
+
+    let events_chunk =
+        module.call::<_, EventsChunk>("starknet_V0_8_getEvents", [filter.clone()]).await.unwrap();
+    assert_eq!(events_chunk.events.len(), 1);
+    assert_eq!(events_chunk.events[0].block_number, Some(BlockNumber(0)));
+    let err = module
+        .call::<_, TransactionReceipt>("starknet_V0_8_getTransactionReceipt", [transaction_hash])
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == TRANSACTION_HASH_NOT_FOUND.into());
+    let err = module
+        .call::<_, Block>(
+            "starknet_V0_8_getBlockWithReceipts",
+            [BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1)))],
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+
+    // Once the events are synced, they are returned.
+    let event = StarknetApiEvent { from_address: contract_address!("0x1"), ..Default::default() };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_events(BlockNumber(1), vec![vec![event.clone()]])
+        .unwrap()
+        .commit()
+        .unwrap();
+    let events_chunk =
+        module.call::<_, EventsChunk>("starknet_V0_8_getEvents", [filter]).await.unwrap();
+    assert_eq!(events_chunk.events.len(), 2);
+    assert_eq!(events_chunk.events[1].block_number, Some(BlockNumber(1)));
+    assert_eq!(events_chunk.events[1].event, event);
+    module
+        .call::<_, TransactionReceipt>("starknet_V0_8_getTransactionReceipt", [transaction_hash])
+        .await
+        .unwrap();
+}
+
 #[tokio::test]
 async fn serialize_returns_valid_json() {
     let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
@@ -3758,6 +3887,206 @@ async fn get_deprecated_class_state_mutability() {
     assert_eq!(entry.get("stateMutability").unwrap().as_str().unwrap(), "view");
 }
 
+#[tokio::test]
+async fn get_contract_history() {
+    let method_name = "papyrus_V0_8_getContractHistory";
+    let (module, mut storage_writer) =
+        get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+    let address = contract_address!("0x10");
+    let key = storage_key!("0x1");
+    let other_key = storage_key!("0x2");
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    for (i, state_diff) in (0..).zip([
+        starknet_api::state::ThinStateDiff {
+            deployed_contracts: indexmap! { address => class_hash!("0x1") },
+            storage_diffs: indexmap! { address => indexmap! { key => felt!("0x5") } },
+            ..Default::default()
+        },
+        starknet_api::state::ThinStateDiff {
+            nonces: indexmap! { address => Nonce(felt!("0x1")) },
+            ..Default::default()
+        },
+        starknet_api::state::ThinStateDiff {
+            deployed_contracts: indexmap! { address => class_hash!("0x2") },
+            storage_diffs: indexmap! { address => indexmap! { key => felt!("0x6") } },
+            ..Default::default()
+        },
+    ]) {
+        let block_number = BlockNumber(i);
+        let header = BlockHeader {
+            block_hash: BlockHash(felt!(i)),
+            block_header_without_hash: BlockHeaderWithoutHash {
+                block_number,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        txn = txn
+            .append_header(block_number, &header)
+            .unwrap()
+            .append_state_diff(block_number, state_diff)
+            .unwrap();
+    }
+    txn.commit().unwrap();
+    let block_id = |block_number| BlockId::HashOrNumber(BlockHashOrNumber::Number(block_number));
+
+    let res = module
+        .call::<_, ContractHistory>(
+            method_name,
+            (
+                address,
+                vec![key, other_key],
+                block_id(BlockNumber(1)),
+                BlockId::Tag(Tag::Latest),
+                10,
+                None::<ContinuationToken>,
+            ),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        res,
+        ContractHistory {
+            class_hashes: vec![ClassHashUpdate {
+                block_number: BlockNumber(2),
+                class_hash: class_hash!("0x2")
+            }],
+            nonces: vec![NonceUpdate { block_number: BlockNumber(1), nonce: Nonce(felt!("0x1")) }],
+            storage: vec![
+                StorageKeyHistory {
+                    key,
+                    updates: vec![StorageValueUpdate {
+                        block_number: BlockNumber(2),
+                        value: felt!("0x6")
+                    }],
+                },
+                StorageKeyHistory { key: other_key, updates: vec![] },
+            ],
+            continuation_token: None,
+        }
+    );
+
+    // Read the history in chunks of 2 updates. Block 0 has 3 updates (the deployment also sets the
+    // nonce), which are returned together, and the updates of block 2 don't fit in the chunk of
+    // block 1.
+    let mut updates_per_chunk = vec![];
+    let mut continuation_token = None;
+    loop {
+        let res = module
+            .call::<_, ContractHistory>(
+                method_name,
+                (
+                    address,
+                    vec![key],
+                    block_id(BlockNumber(0)),
+                    BlockId::Tag(Tag::Latest),
+                    2,
+                    continuation_token,
+                ),
+            )
+            .await
+            .unwrap();
+        updates_per_chunk.push((
+            res.class_hashes.len(),
+            res.nonces.len(),
+            res.storage[0].updates.len(),
+        ));
+        continuation_token = res.continuation_token;
+        if continuation_token.is_none() {
+            break;
+        }
+    }
+    assert_eq!(updates_per_chunk, vec![(1, 1, 1), (0, 1, 0), (1, 0, 1)]);
+
+    // The updates of a block aren't split even if they exceed the chunk size.
+    let res = module
+        .call::<_, ContractHistory>(
+            method_name,
+            (
+                address,
+                vec![key],
+                block_id(BlockNumber(0)),
+                BlockId::Tag(Tag::Latest),
+                1,
+                None::<ContinuationToken>,
+            ),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.class_hashes.len(), 1);
+    assert_eq!(res.nonces.len(), 1);
+    assert_eq!(res.storage[0].updates.len(), 1);
+    assert_eq!(res.continuation_token, Some(ContinuationToken("1".to_string())));
+
+    // A continuation token outside the requested blocks.
+    let err = module
+        .call::<_, ContractHistory>(
+            method_name,
+            (
+                address,
+                vec![key],
+                block_id(BlockNumber(1)),
+                BlockId::Tag(Tag::Latest),
+                2,
+                Some(ContinuationToken("0".to_string())),
+            ),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == INVALID_CONTINUATION_TOKEN.into());
+
+    // Too many storage keys.
+    let err = module
+        .call::<_, ContractHistory>(
+            method_name,
+            (
+                address,
+                vec![key; 101],
+                block_id(BlockNumber(0)),
+                BlockId::Tag(Tag::Latest),
+                2,
+                None::<ContinuationToken>,
+            ),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == TOO_MANY_STORAGE_KEYS.into());
+
+    // Chunk size too big.
+    let err = module
+        .call::<_, ContractHistory>(
+            method_name,
+            (
+                address,
+                vec![key],
+                block_id(BlockNumber(0)),
+                BlockId::Tag(Tag::Latest),
+                1001,
+                None::<ContinuationToken>,
+            ),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == PAGE_SIZE_TOO_BIG.into());
+
+    // Ask for a block that doesn't exist.
+    let err = module
+        .call::<_, ContractHistory>(
+            method_name,
+            (
+                address,
+                vec![key],
+                block_id(BlockNumber(0)),
+                block_id(BlockNumber(3)),
+                2,
+                None::<ContinuationToken>,
+            ),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+}
+
 // TODO(Yael): Add a test case for block_number which is not the latest.
 #[tokio::test]
 async fn get_compiled_class() {
@@ -125,6 +125,19 @@ pub fn unexpected_error(data: String) -> JsonRpcError<String> {
     JsonRpcError { code: 63, message: "An unexpected error occurred", data: Some(data) }
 }
 
+// Not part of the specs. Returned by nodes that keep the state history only for recent blocks.
+pub fn state_history_pruned(data: String) -> JsonRpcError<String> {
+    JsonRpcError {
+        code: 100,
+        message: "The state of the requested block was pruned",
+        data: Some(data),
+    }
+}
+
+// Not part of the specs. Returned by papyrus_getContractHistory.
+pub const TOO_MANY_STORAGE_KEYS: JsonRpcError<String> =
+    JsonRpcError { code: 101, message: "Too many storage keys requested", data: None };
+
 impl<T: Serialize> From<JsonRpcError<T>> for ErrorObjectOwned {
     fn from(err: JsonRpcError<T>) -> Self {
         ErrorObjectOwned::owned(err.code, err.message, err.data)
@@ -0,0 +1,108 @@
+use std::process::ExitCode;
+
+use clap::{Arg, ArgAction, Command};
+use papyrus_storage::db::DbConfig;
+use papyrus_storage::integrity::{check_storage, truncate_storage};
+use papyrus_storage::{StorageConfig, StorageScope};
+
+// Checks the integrity of a storage, and optionally repairs it by truncating it to the last
+// consistent height. Should run while no node is using the storage.
+pub fn main() -> ExitCode {
+    let matches = Command::new("Storage check")
+        .arg(
+            Arg::new("db_path")
+                .short('d')
+                .long("db_path")
+                .required(true)
+                .help("The path of the storage"),
+        )
+        .arg(
+            Arg::new("chain_id")
+                .short('c')
+                .long("chain_id")
+                .required(true)
+                .help("The chain id SN_MAIN/SN_SEPOLIA for example"),
+        )
+        .arg(
+            Arg::new("state_only")
+                .long("state_only")
+                .action(ArgAction::SetTrue)
+                .help("Whether the storage is a state-only or a pruned storage"),
+        )
+        .arg(
+            Arg::new("repair")
+                .long("repair")
+                .action(ArgAction::SetTrue)
+                .help("Truncate the storage to the last consistent height if issues are found"),
+        )
+        .get_matches();
+
+    let config = StorageConfig {
+        db_config: DbConfig {
+            path_prefix: matches.get_one::<String>("db_path").expect("Missing db_path").into(),
+            chain_id: matches
+                .get_one::<String>("chain_id")
+                .expect("Missing chain_id")
+                .to_string()
+                .into(),
+            enforce_file_exists: true,
+            ..Default::default()
+        },
+        // A pruned storage is opened as state-only so that its history isn't pruned meanwhile.
+        scope: if matches.get_flag("state_only") {
+            StorageScope::StateOnly
+        } else {
+            StorageScope::FullArchive
+        },
+        ..Default::default()
+    };
+    // The storage is written only when it's repaired, so a check alone doesn't take the writer.
+    println!("Opening storage");
+    let (reader, writer) = if matches.get_flag("repair") {
+        let (reader, writer) =
+            papyrus_storage::open_storage(config).expect("Should be able to open storage");
+        (reader, Some(writer))
+    } else {
+        let reader = papyrus_storage::open_storage_read_only(config)
+            .expect("Should be able to open storage");
+        (reader, None)
+    };
+
+    println!("Checking storage");
+    let report = check_storage(&reader).expect("Should be able to check the storage");
+    for (name, marker) in &report.markers {
+        println!("{name} marker: {marker}");
+    }
+    if report.is_consistent() {
+        println!("The storage is consistent");
+        return ExitCode::SUCCESS;
+    }
+    println!("Found {} issues:", report.issues.len());
+    for issue in &report.issues {
+        println!("  {issue}");
+    }
+
+    let Some(height) = report.consistent_height() else {
+        println!(
+            "Some of the issues can't be repaired by truncation, restore the storage from a \
+             snapshot"
+        );
+        return ExitCode::FAILURE;
+    };
+    let Some(mut writer) = writer else {
+        println!("Run with --repair to truncate the storage to height {height}");
+        return ExitCode::FAILURE;
+    };
+    println!("Truncating the storage to height {height}");
+    truncate_storage(&mut writer, height).expect("Should be able to truncate the storage");
+    let report = check_storage(&reader).expect("Should be able to check the storage");
+    if !report.is_consistent() {
+        println!("The storage is still inconsistent after the repair:");
+        for issue in &report.issues {
+            println!("  {issue}");
+        }
+        return ExitCode::FAILURE;
+    }
+    println!("The storage was repaired");
+    ExitCode::SUCCESS
+}
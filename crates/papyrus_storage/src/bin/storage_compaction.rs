@@ -0,0 +1,58 @@
+use clap::{Arg, ArgAction, Command};
+use papyrus_storage::compaction::compact_storage;
+use papyrus_storage::db::DbConfig;
+use papyrus_storage::{StorageConfig, StorageScope};
+
+// Compacts the mmap files of a storage. Should run while no node is using the storage.
+pub fn main() {
+    let matches = Command::new("Storage compaction")
+        .arg(
+            Arg::new("db_path")
+                .short('d')
+                .long("db_path")
+                .required(true)
+                .help("The path of the storage"),
+        )
+        .arg(
+            Arg::new("chain_id")
+                .short('c')
+                .long("chain_id")
+                .required(true)
+                .help("The chain id SN_MAIN/SN_SEPOLIA for example"),
+        )
+        .arg(
+            Arg::new("state_only")
+                .long("state_only")
+                .action(ArgAction::SetTrue)
+                .help("Whether the storage is a state-only or a pruned storage"),
+        )
+        .get_matches();
+
+    let config = StorageConfig {
+        db_config: DbConfig {
+            path_prefix: matches.get_one::<String>("db_path").expect("Missing db_path").into(),
+            chain_id: matches
+                .get_one::<String>("chain_id")
+                .expect("Missing chain_id")
+                .to_string()
+                .into(),
+            enforce_file_exists: true,
+            ..Default::default()
+        },
+        scope: if matches.get_flag("state_only") {
+            StorageScope::StateOnly
+        } else {
+            StorageScope::FullArchive
+        },
+        ..Default::default()
+    };
+    println!("Compacting storage");
+    let stats = compact_storage(config).expect("Should be able to compact the storage");
+    for (kind, file_stats) in &stats.files {
+        println!(
+            "{kind:?}: {} -> {} bytes, reclaimed {} bytes",
+            file_stats.size_before, file_stats.size_after, file_stats.reclaimed
+        );
+    }
+    println!("Reclaimed {} bytes in total", stats.reclaimed());
+}
@@ -0,0 +1,96 @@
+use std::path::PathBuf;
+
+use clap::{Arg, ArgMatches, Command};
+use papyrus_storage::db::DbConfig;
+use papyrus_storage::snapshot::{export_snapshot, import_snapshot, verify_snapshot};
+use papyrus_storage::StorageConfig;
+
+// Exports, verifies and imports consistent storage snapshots.
+// Note that export opens the storage, so it should run while no node is using it. To export from
+// a running node, use papyrus_storage::snapshot::export_snapshot on the node's storage reader.
+pub fn main() {
+    let matches = Command::new("Storage snapshot")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("export")
+                .about("Exports a snapshot of the storage.")
+                .arg(db_path_arg())
+                .arg(chain_id_arg())
+                .arg(snapshot_path_arg()),
+        )
+        .subcommand(
+            Command::new("verify")
+                .about("Verifies the files of a snapshot against its manifest.")
+                .arg(snapshot_path_arg()),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Imports a snapshot into an empty storage directory.")
+                .arg(snapshot_path_arg())
+                .arg(db_path_arg())
+                .arg(chain_id_arg()),
+        )
+        .get_matches();
+
+    let manifest = match matches.subcommand() {
+        Some(("export", args)) => {
+            let db_config = db_config(args);
+            println!("Opening storage");
+            let config = StorageConfig {
+                db_config: DbConfig { enforce_file_exists: true, ..db_config.clone() },
+                ..Default::default()
+            };
+            let (reader, _writer) =
+                papyrus_storage::open_storage(config).expect("Should be able to open storage");
+            println!("Exporting snapshot");
+            export_snapshot(&reader, &db_config, &snapshot_path(args))
+                .expect("Should be able to export the snapshot")
+        }
+        Some(("verify", args)) => {
+            println!("Verifying snapshot");
+            verify_snapshot(&snapshot_path(args)).expect("The snapshot should be valid")
+        }
+        Some(("import", args)) => {
+            println!("Importing snapshot");
+            import_snapshot(&snapshot_path(args), &db_config(args))
+                .expect("Should be able to import the snapshot")
+        }
+        _ => unreachable!("A subcommand is required"),
+    };
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&manifest).expect("Should be able to serialize the manifest")
+    );
+}
+
+fn db_path_arg() -> Arg {
+    Arg::new("db_path").short('d').long("db_path").required(true).help("The path of the storage")
+}
+
+fn chain_id_arg() -> Arg {
+    Arg::new("chain_id")
+        .short('c')
+        .long("chain_id")
+        .required(true)
+        .help("The chain id SN_MAIN/SN_SEPOLIA for example")
+}
+
+fn snapshot_path_arg() -> Arg {
+    Arg::new("snapshot_path")
+        .short('s')
+        .long("snapshot_path")
+        .required(true)
+        .help("The directory of the snapshot")
+}
+
+fn db_config(args: &ArgMatches) -> DbConfig {
+    DbConfig {
+        path_prefix: args.get_one::<String>("db_path").expect("Missing db_path").into(),
+        chain_id: args.get_one::<String>("chain_id").expect("Missing chain_id").to_string().into(),
+        ..Default::default()
+    }
+}
+
+fn snapshot_path(args: &ArgMatches) -> PathBuf {
+    args.get_one::<String>("snapshot_path").expect("Missing snapshot_path").into()
+}
@@ -5,11 +5,12 @@ use starknet_api::block::{BlockBody, BlockNumber};
 use starknet_api::transaction::TransactionOffsetInBlock;
 use test_case::test_case;
 
-use crate::body::{BodyStorageReader, BodyStorageWriter, TransactionIndex};
+use crate::body::events::{EventIndex, EventsReader};
+use crate::body::{set_events, BodyStorageReader, BodyStorageWriter, TransactionIndex};
 use crate::db::table_types::Table;
 use crate::db::{DbError, KeyAlreadyExistsError};
 use crate::test_utils::{get_test_storage, get_test_storage_by_scope};
-use crate::{OffsetKind, StorageError, StorageScope, StorageWriter};
+use crate::{OffsetKind, StorageError, StorageReader, StorageScope, StorageWriter};
 
 #[tokio::test]
 async fn append_body() {
@@ -411,3 +412,100 @@ fn update_offset_table() {
         file_offset_table.get(&txn.txn, &OffsetKind::TransactionOutput).unwrap().unwrap()
     );
 }
+
+#[tokio::test]
+async fn append_body_without_events_and_then_events() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let body = get_test_body(3, Some(2), None, None);
+    let events =
+        body.transaction_outputs.iter().map(|output| output.events().to_vec()).collect::<Vec<_>>();
+    let mut body_without_events = body.clone();
+    for tx_output in &mut body_without_events.transaction_outputs {
+        set_events(tx_output, vec![]);
+    }
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body_without_events(BlockNumber(0), body_without_events.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_event_marker().unwrap(), BlockNumber(0));
+    assert_eq!(
+        txn.get_block_transaction_outputs(BlockNumber(0)).unwrap().unwrap(),
+        body_without_events.transaction_outputs
+    );
+
+    // A body with events can't be appended before the events of the previous block.
+    let Err(err) = writer.begin_rw_txn().unwrap().append_body(BlockNumber(1), BlockBody::default())
+    else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(
+        err,
+        StorageError::MarkerMismatch { expected, found }
+        if expected == BlockNumber(0) && found == BlockNumber(1)
+    );
+
+    let Err(err) = writer.begin_rw_txn().unwrap().append_events(BlockNumber(0), vec![]) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(
+        err,
+        StorageError::EventsTransactionsMismatch { num_transactions: 3, num_event_lists: 0, .. }
+    );
+    let Err(err) = writer.begin_rw_txn().unwrap().append_events(BlockNumber(1), vec![]) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(err, StorageError::MarkerMismatch { .. });
+
+    let output_file_offset = get_transaction_output_file_offset(&reader);
+    writer.begin_rw_txn().unwrap().append_events(BlockNumber(0), events).unwrap().commit().unwrap();
+    // The outputs aren't written again with their events.
+    assert_eq!(get_transaction_output_file_offset(&reader), output_file_offset);
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_event_marker().unwrap(), BlockNumber(1));
+    assert_eq!(
+        txn.get_block_transaction_outputs(BlockNumber(0)).unwrap().unwrap(),
+        body.transaction_outputs
+    );
+    let tx_index = TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(1));
+    assert_eq!(txn.get_transaction_output(tx_index).unwrap().unwrap(), body.transaction_outputs[1]);
+    let contract_address = body.transaction_outputs[1].events()[0].from_address;
+    assert!(
+        txn.iter_events(Some(contract_address), event_index_of(tx_index), BlockNumber(0))
+            .unwrap()
+            .any(|((address, event_index), _)| address == contract_address
+                && event_index.0 == tx_index)
+    );
+    let event_index = event_index_of(TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(0)));
+    assert_eq!(txn.iter_events(None, event_index, BlockNumber(0)).unwrap().count(), 6);
+
+    let Err(err) = writer.begin_rw_txn().unwrap().append_events(BlockNumber(1), vec![]) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(
+        err,
+        StorageError::EventsForNonExistingBody { block_number } if block_number == BlockNumber(1)
+    );
+
+    writer.begin_rw_txn().unwrap().revert_body(BlockNumber(0)).unwrap().0.commit().unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(0));
+    assert_eq!(txn.get_event_marker().unwrap(), BlockNumber(0));
+    let transaction_events_table = txn.txn.open_table(&txn.tables.transaction_events).unwrap();
+    assert!(transaction_events_table.get(&txn.txn, &tx_index).unwrap().is_none());
+}
+
+fn get_transaction_output_file_offset(reader: &StorageReader) -> usize {
+    let txn = reader.begin_ro_txn().unwrap();
+    let file_offset_table = txn.txn.open_table(&txn.tables.file_offsets).unwrap();
+    file_offset_table.get(&txn.txn, &OffsetKind::TransactionOutput).unwrap().unwrap()
+}
+
+fn event_index_of(tx_index: TransactionIndex) -> EventIndex {
+    EventIndex(tx_index, Default::default())
+}
@@ -57,11 +57,18 @@ use starknet_api::transaction::{
     Event,
     EventContent,
     EventIndexInTransactionOutput,
+    EventKey,
     TransactionOutput,
 };
 
 use super::TransactionMetadataTable;
-use crate::body::{EventsTableKey, TransactionIndex};
+use crate::body::{
+    get_transaction_output_with_events,
+    EventsByFirstKeyTableKey,
+    EventsTableKey,
+    TransactionEventsTable,
+    TransactionIndex,
+};
 use crate::db::serialization::{NoVersionValueWrapper, VersionZeroWrapper};
 use crate::db::table_types::{CommonPrefix, DbCursor, DbCursorTrait, NoValue, SimpleTable, Table};
 use crate::db::{DbTransaction, RO};
@@ -91,6 +98,25 @@ pub trait EventsReader<'txn, 'env> {
         event_index: EventIndex,
         to_block_number: BlockNumber,
     ) -> StorageResult<EventIter<'txn, 'env>>;
+
+    /// Returns an iterator over the events whose first key is one of the given keys, by the order
+    /// of the event index. Only the transactions that emitted such events are read, so this is
+    /// much faster than filtering the events of [`iter_events`](EventsReader::iter_events) when
+    /// the keys are rare.
+    ///
+    /// # Arguments
+    /// * first_keys - the keys to return the events whose first key is one of them.
+    /// * event_index - event index to start iterate from it.
+    /// * to_block_number - block number to stop iterate at it.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`](crate::StorageError) if there was an error.
+    fn iter_events_by_first_keys(
+        &'env self,
+        first_keys: Vec<EventKey>,
+        event_index: EventIndex,
+        to_block_number: BlockNumber,
+    ) -> StorageResult<EventIter<'txn, 'env>>;
 }
 
 // TODO(DanB): support all read transactions (including RW).
@@ -109,20 +135,52 @@ impl<'txn, 'env> EventsReader<'txn, 'env> for StorageTxn<'env, RO> {
 
         Ok(EventIter::ByEventIndex(self.iter_events_by_event_index(event_index, to_block_number)?))
     }
+
+    fn iter_events_by_first_keys(
+        &'env self,
+        first_keys: Vec<EventKey>,
+        event_index: EventIndex,
+        to_block_number: BlockNumber,
+    ) -> StorageResult<EventIter<'txn, 'env>> {
+        let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
+        let events_by_first_key_table = self.open_table(&self.tables.events_by_first_key)?;
+        let mut cursors = Vec::with_capacity(first_keys.len());
+        let mut next_tx_indices = Vec::with_capacity(first_keys.len());
+        for first_key in &first_keys {
+            let mut cursor = events_by_first_key_table.cursor(&self.txn)?;
+            let entry = cursor.lower_bound(&(first_key.clone(), event_index.0))?;
+            next_tx_indices.push(tx_index_of_first_key(entry, first_key));
+            cursors.push(cursor);
+        }
+        Ok(EventIter::ByFirstKeys(EventIterByFirstKeys {
+            txn: &self.txn,
+            file_handlers: &self.file_handlers,
+            first_keys,
+            cursors,
+            next_tx_indices,
+            events_queue: VecDeque::new(),
+            transaction_metadata_table,
+            transaction_events_table: self.open_table(&self.tables.transaction_events)?,
+            start_event_index: event_index,
+            to_block_number,
+        }))
+    }
 }
 
 // TODO(dvir): add transaction hash to the return value. In the RPC when returning events this is
 // with the transaction hash. We can do it efficiently here because we anyway read the relevant
 // entry in the transaction_metadata table..
 #[allow(missing_docs)]
-/// A wrapper of two iterators [`EventIterByContractAddress`] and [`EventIterByEventIndex`].
+/// A wrapper of the iterators [`EventIterByContractAddress`], [`EventIterByEventIndex`] and
+/// [`EventIterByFirstKeys`].
 pub enum EventIter<'txn, 'env> {
     ByContractAddress(EventIterByContractAddress<'env, 'txn>),
     ByEventIndex(EventIterByEventIndex<'txn>),
+    ByFirstKeys(EventIterByFirstKeys<'env, 'txn>),
 }
 
-/// This iterator is a wrapper of two iterators [`EventIterByContractAddress`]
-/// and [`EventIterByEventIndex`].
+/// This iterator is a wrapper of the iterators [`EventIterByContractAddress`],
+/// [`EventIterByEventIndex`] and [`EventIterByFirstKeys`].
 /// With this wrapper we can execute the same code, regardless the
 /// type of iteration used.
 impl Iterator for EventIter<'_, '_> {
@@ -132,6 +190,7 @@ impl Iterator for EventIter<'_, '_> {
         match self {
             EventIter::ByContractAddress(it) => it.next(),
             EventIter::ByEventIndex(it) => it.next(),
+            EventIter::ByFirstKeys(it) => it.next(),
         }
         .unwrap_or(None)
     }
@@ -150,6 +209,7 @@ pub struct EventIterByContractAddress<'env, 'txn> {
     events_queue: VecDeque<((ContractAddress, EventIndex), EventContent)>,
     cursor: EventsTableCursor<'txn>,
     transaction_metadata_table: TransactionMetadataTable<'env>,
+    transaction_events_table: TransactionEventsTable<'env>,
 }
 
 impl EventIterByContractAddress<'_, '_> {
@@ -168,9 +228,13 @@ impl EventIterByContractAddress<'_, '_> {
                 self.transaction_metadata_table.get(self.txn, &tx_index)?.unwrap_or_else(|| {
                     panic!("Transaction metadata not found for transaction index: {tx_index:?}")
                 });
-            let tx_output = self
-                .file_handles
-                .get_transaction_output_unchecked(tx_metadata.tx_output_location)?;
+            let tx_output = get_transaction_output_with_events(
+                self.txn,
+                self.file_handles,
+                &self.transaction_events_table,
+                tx_index,
+                &tx_metadata,
+            )?;
             // TODO(dvir): don't clone the events here.
             self.events_queue =
                 get_events_from_tx(tx_output.events().into(), tx_index, contract_address, 0);
@@ -186,7 +250,9 @@ impl EventIterByContractAddress<'_, '_> {
 /// First by the block number, then by the transaction offset in the block,
 /// and finally, by the event index in the transaction output.
 pub struct EventIterByEventIndex<'txn> {
+    txn: &'txn DbTransaction<'txn, RO>,
     file_handlers: &'txn FileHandlers<RO>,
+    transaction_events_table: TransactionEventsTable<'txn>,
     tx_current: Option<(TransactionIndex, TransactionOutput)>,
     tx_cursor: TransactionMetadataTableCursor<'txn>,
     event_index_in_tx_current: EventIndexInTransactionOutput,
@@ -239,8 +305,13 @@ impl EventIterByEventIndex<'_> {
             };
             self.tx_current = Some((
                 tx_index,
-                self.file_handlers
-                    .get_transaction_output_unchecked(tx_metadata.tx_output_location)?,
+                get_transaction_output_with_events(
+                    self.txn,
+                    self.file_handlers,
+                    &self.transaction_events_table,
+                    tx_index,
+                    &tx_metadata,
+                )?,
             ));
             self.event_index_in_tx_current = EventIndexInTransactionOutput(0);
         }
@@ -249,6 +320,91 @@ impl EventIterByEventIndex<'_> {
     }
 }
 
+/// This iterator goes over the events whose first key is one of the given keys, by the order of
+/// the event index. The transactions that emitted such events are found in the index of the
+/// transactions by the first keys of their events.
+pub struct EventIterByFirstKeys<'env, 'txn> {
+    txn: &'txn DbTransaction<'env, RO>,
+    file_handlers: &'txn FileHandlers<RO>,
+    first_keys: Vec<EventKey>,
+    // A cursor of the index for each of the first keys.
+    cursors: Vec<EventsByFirstKeyTableCursor<'txn>>,
+    // The next transaction that emitted an event with each of the first keys. None if there are no
+    // more such transactions.
+    next_tx_indices: Vec<Option<TransactionIndex>>,
+    // Queue of events to return from the iterator. When this queue is empty, we need to fetch the
+    // events of the next transaction.
+    events_queue: VecDeque<((ContractAddress, EventIndex), EventContent)>,
+    transaction_metadata_table: TransactionMetadataTable<'env>,
+    transaction_events_table: TransactionEventsTable<'env>,
+    start_event_index: EventIndex,
+    to_block_number: BlockNumber,
+}
+
+impl EventIterByFirstKeys<'_, '_> {
+    /// Returns the next event. If there are no more events, returns None.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`](crate::StorageError) if there was an error.
+    fn next(&mut self) -> StorageResult<Option<((ContractAddress, EventIndex), EventContent)>> {
+        while self.events_queue.is_empty() {
+            let Some(tx_index) = self.next_tx_indices.iter().flatten().min().copied() else {
+                return Ok(None);
+            };
+            if tx_index.0 > self.to_block_number {
+                return Ok(None);
+            }
+            // A transaction may appear under several of the keys.
+            for ((first_key, cursor), next_tx_index) in
+                self.first_keys.iter().zip(&mut self.cursors).zip(&mut self.next_tx_indices)
+            {
+                if *next_tx_index == Some(tx_index) {
+                    *next_tx_index = tx_index_of_first_key(cursor.next()?, first_key);
+                }
+            }
+
+            let tx_metadata =
+                self.transaction_metadata_table.get(self.txn, &tx_index)?.unwrap_or_else(|| {
+                    panic!("Transaction metadata not found for transaction index: {tx_index:?}")
+                });
+            let tx_output = get_transaction_output_with_events(
+                self.txn,
+                self.file_handlers,
+                &self.transaction_events_table,
+                tx_index,
+                &tx_metadata,
+            )?;
+            let start_index =
+                if tx_index == self.start_event_index.0 { self.start_event_index.1.0 } else { 0 };
+            for (i, event) in tx_output.events().iter().enumerate().skip(start_index) {
+                if event
+                    .content
+                    .keys
+                    .first()
+                    .is_some_and(|first_key| self.first_keys.contains(first_key))
+                {
+                    let key = (
+                        event.from_address,
+                        EventIndex(tx_index, EventIndexInTransactionOutput(i)),
+                    );
+                    self.events_queue.push_back((key, event.content.clone()));
+                }
+            }
+        }
+
+        Ok(self.events_queue.pop_front())
+    }
+}
+
+// Returns the transaction index of an entry in the index by first keys, if the entry is of the
+// given key.
+fn tx_index_of_first_key(
+    entry: Option<(EventsByFirstKeyTableKey, NoValue)>,
+    first_key: &EventKey,
+) -> Option<TransactionIndex> {
+    entry.and_then(|((key, tx_index), _)| (key == *first_key).then_some(tx_index))
+}
+
 impl<'txn, 'env> StorageTxn<'env, RO>
 where
     'env: 'txn,
@@ -265,6 +421,7 @@ where
         key: (ContractAddress, EventIndex),
     ) -> StorageResult<EventIterByContractAddress<'env, 'txn>> {
         let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
+        let transaction_events_table = self.open_table(&self.tables.transaction_events)?;
         let events_table = self.open_table(&self.tables.events)?;
         let mut cursor = events_table.cursor(&self.txn)?;
         let events_queue = if let Some((contract_address, tx_index)) =
@@ -274,9 +431,13 @@ where
                 transaction_metadata_table.get(&self.txn, &tx_index)?.unwrap_or_else(|| {
                     panic!("Transaction metadata not found for transaction index: {tx_index:?}")
                 });
-            let tx_output = self
-                .file_handlers
-                .get_transaction_output_unchecked(tx_metadata.tx_output_location)?;
+            let tx_output = get_transaction_output_with_events(
+                &self.txn,
+                &self.file_handlers,
+                &transaction_events_table,
+                tx_index,
+                &tx_metadata,
+            )?;
 
             // In case of we get tx_index different from the key, it means we need to start a new
             // transaction which means the first event.
@@ -300,6 +461,7 @@ where
             events_queue,
             cursor,
             transaction_metadata_table,
+            transaction_events_table,
         })
     }
 
@@ -318,19 +480,27 @@ where
         to_block_number: BlockNumber,
     ) -> StorageResult<EventIterByEventIndex<'txn>> {
         let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
+        let transaction_events_table = self.open_table(&self.tables.transaction_events)?;
         let mut tx_cursor = transaction_metadata_table.cursor(&self.txn)?;
         let first_txn_location = tx_cursor.lower_bound(&event_index.0)?;
         let first_relevant_transaction = match first_txn_location {
             None => None,
             Some((tx_index, tx_metadata)) => Some((
                 tx_index,
-                self.file_handlers
-                    .get_transaction_output_unchecked(tx_metadata.tx_output_location)?,
+                get_transaction_output_with_events(
+                    &self.txn,
+                    &self.file_handlers,
+                    &transaction_events_table,
+                    tx_index,
+                    &tx_metadata,
+                )?,
             )),
         };
 
         let mut it = EventIterByEventIndex {
+            txn: &self.txn,
             file_handlers: &self.file_handlers,
+            transaction_events_table,
             tx_current: first_relevant_transaction,
             tx_cursor,
             event_index_in_tx_current: event_index.1,
@@ -360,6 +530,9 @@ fn get_events_from_tx(
 /// A cursor of the events table.
 type EventsTableCursor<'txn> =
     DbCursor<'txn, RO, EventsTableKey, NoVersionValueWrapper<NoValue>, CommonPrefix>;
+/// A cursor of the index of transactions by the first keys of their events.
+type EventsByFirstKeyTableCursor<'txn> =
+    DbCursor<'txn, RO, EventsByFirstKeyTableKey, NoVersionValueWrapper<NoValue>, CommonPrefix>;
 /// A cursor of the transaction outputs table.
 type TransactionMetadataTableCursor<'txn> =
     DbCursor<'txn, RO, TransactionIndex, VersionZeroWrapper<TransactionMetadata>, SimpleTable>;
@@ -4,17 +4,19 @@ use assert_matches::assert_matches;
 use papyrus_test_utils::get_test_block;
 use pretty_assertions::assert_eq;
 use starknet_api::block::BlockNumber;
+use starknet_api::felt;
 use starknet_api::transaction::{
     Event,
     EventContent,
     EventData,
     EventIndexInTransactionOutput,
+    EventKey,
     TransactionOffsetInBlock,
 };
 
 use crate::body::events::{get_events_from_tx, EventIndex, EventsReader};
 use crate::body::{BodyStorageWriter, TransactionIndex};
-use crate::db::table_types::Table;
+use crate::db::table_types::{DbCursorTrait, Table};
 use crate::header::HeaderStorageWriter;
 use crate::test_utils::get_test_storage;
 
@@ -129,6 +131,66 @@ fn iter_events_by_index() {
     assert_eq!(event_iter.into_iter().collect::<Vec<_>>(), emitted_events);
 }
 
+#[test]
+fn iter_events_by_first_keys() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let keys = vec![EventKey(felt!("0x1")), EventKey(felt!("0x2")), EventKey(felt!("0x3"))];
+    let block = get_test_block(4, Some(5), None, Some(vec![keys.clone()]));
+    let block_number = block.header.block_header_without_hash.block_number;
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // Create the events emitted starting from event index ((0,1),2) whose first key is one of the
+    // first two keys.
+    let first_keys = vec![keys[0].clone(), keys[1].clone()];
+    let event_index = EventIndex(
+        TransactionIndex(block_number, TransactionOffsetInBlock(1)),
+        EventIndexInTransactionOutput(2),
+    );
+    let mut emitted_events = vec![];
+    for (tx_i, tx_output) in block.body.transaction_outputs.iter().enumerate() {
+        for (event_i, event) in tx_output.events().iter().enumerate() {
+            let current_event_index = EventIndex(
+                TransactionIndex(block_number, TransactionOffsetInBlock(tx_i)),
+                EventIndexInTransactionOutput(event_i),
+            );
+            if current_event_index >= event_index && first_keys.contains(&event.content.keys[0]) {
+                emitted_events
+                    .push(((event.from_address, current_event_index), event.content.clone()));
+            }
+        }
+    }
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let event_iter = txn.iter_events_by_first_keys(first_keys, event_index, block_number).unwrap();
+    assert_eq!(event_iter.into_iter().collect::<Vec<_>>(), emitted_events);
+    drop(txn);
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .revert_header(block_number)
+        .unwrap()
+        .0
+        .revert_body(block_number)
+        .unwrap()
+        .0
+        .commit()
+        .unwrap();
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let mut event_iter = txn.iter_events_by_first_keys(keys, event_index, block_number).unwrap();
+    assert!(event_iter.next().is_none());
+    let events_by_first_key_table = txn.txn.open_table(&txn.tables.events_by_first_key).unwrap();
+    assert_eq!(events_by_first_key_table.cursor(&txn.txn).unwrap().next().unwrap(), None);
+}
+
 #[test]
 fn revert_events() {
     let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
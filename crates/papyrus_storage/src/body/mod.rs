@@ -50,6 +50,8 @@ use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockBody, BlockNumber};
 use starknet_api::core::ContractAddress;
 use starknet_api::transaction::{
+    Event,
+    EventKey,
     Transaction,
     TransactionHash,
     TransactionOffsetInBlock,
@@ -81,6 +83,11 @@ type TransactionHashToIdxTable<'env> =
 type EventsTableKey = (ContractAddress, TransactionIndex);
 type EventsTable<'env> =
     TableHandle<'env, EventsTableKey, NoVersionValueWrapper<NoValue>, CommonPrefix>;
+pub(crate) type EventsByFirstKeyTableKey = (EventKey, TransactionIndex);
+pub(crate) type EventsByFirstKeyTable<'env> =
+    TableHandle<'env, EventsByFirstKeyTableKey, NoVersionValueWrapper<NoValue>, CommonPrefix>;
+pub(crate) type TransactionEventsTable<'env> =
+    TableHandle<'env, TransactionIndex, VersionZeroWrapper<Vec<Event>>, SimpleTable>;
 
 /// The index of a transaction in a block.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize, PartialOrd, Ord)]
@@ -92,6 +99,10 @@ pub trait BodyStorageReader {
     /// The body marker is the first block number that doesn't exist yet.
     fn get_body_marker(&self) -> StorageResult<BlockNumber>;
 
+    /// The event marker is the first block number whose events don't exist yet. It's behind the
+    /// body marker only when bodies were appended without their events.
+    fn get_event_marker(&self) -> StorageResult<BlockNumber>;
+
     /// Returns the transaction and its execution status at the given index.
     fn get_transaction(
         &self,
@@ -155,6 +166,24 @@ where
     // TODO(yair): make this work without consuming the body.
     fn append_body(self, block_number: BlockNumber, block_body: BlockBody) -> StorageResult<Self>;
 
+    /// Appends a block body whose events are appended later with
+    /// [`append_events`](BodyStorageWriter::append_events). The transaction outputs in the body
+    /// should have no events. Until the events are appended, the event marker stays behind the
+    /// body marker.
+    fn append_body_without_events(
+        self,
+        block_number: BlockNumber,
+        block_body: BlockBody,
+    ) -> StorageResult<Self>;
+
+    /// Appends the events of a block whose body was appended without events. `events` holds the
+    /// events emitted by each transaction of the block, in the order of the transactions.
+    fn append_events(
+        self,
+        block_number: BlockNumber,
+        events: Vec<Vec<Event>>,
+    ) -> StorageResult<Self>;
+
     /// Removes a block body from the storage and returns the removed data.
     fn revert_body(
         self,
@@ -168,6 +197,11 @@ impl<Mode: TransactionKind> BodyStorageReader for StorageTxn<'_, Mode> {
         Ok(markers_table.get(&self.txn, &MarkerKind::Body)?.unwrap_or_default())
     }
 
+    fn get_event_marker(&self) -> StorageResult<BlockNumber> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        Ok(markers_table.get(&self.txn, &MarkerKind::Event)?.unwrap_or_default())
+    }
+
     // TODO(dvir): add option to get transaction with its hash.
     fn get_transaction(
         &self,
@@ -191,8 +225,14 @@ impl<Mode: TransactionKind> BodyStorageReader for StorageTxn<'_, Mode> {
         else {
             return Ok(None);
         };
-        let transaction_output =
-            self.file_handlers.get_transaction_output_unchecked(tx_metadata.tx_output_location)?;
+        let transaction_events_table = self.open_table(&self.tables.transaction_events)?;
+        let transaction_output = get_transaction_output_with_events(
+            &self.txn,
+            &self.file_handlers,
+            &transaction_events_table,
+            transaction_index,
+            &tx_metadata,
+        )?;
         Ok(Some(transaction_output))
     }
 
@@ -277,7 +317,11 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
         &self,
         block_number: BlockNumber,
         transaction_metadata_table: TransactionMetadataTable<'env>,
-        tx_metadata_to_tx_object: fn(TransactionMetadata, &FileHandlers<Mode>) -> StorageResult<T>,
+        tx_metadata_to_tx_object: impl Fn(
+            TransactionIndex,
+            TransactionMetadata,
+            &FileHandlers<Mode>,
+        ) -> StorageResult<T>,
     ) -> StorageResult<Option<Vec<T>>> {
         if self.get_body_marker()? <= block_number {
             return Ok(None);
@@ -289,11 +333,11 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
         // TODO(dvir): consider initializing with capacity based on the get_block_transactions_count
         // function.
         let mut res = Vec::new();
-        while let Some((TransactionIndex(current_block_number, _), tx_metadata)) = current {
-            if current_block_number != block_number {
+        while let Some((tx_index, tx_metadata)) = current {
+            if tx_index.0 != block_number {
                 break;
             }
-            let tx_output = tx_metadata_to_tx_object(tx_metadata, &self.file_handlers)?;
+            let tx_output = tx_metadata_to_tx_object(tx_index, tx_metadata, &self.file_handlers)?;
             res.push(tx_output);
             current = cursor.next()?;
         }
@@ -305,11 +349,18 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
         block_number: BlockNumber,
         transaction_metadata_table: TransactionMetadataTable<'env>,
     ) -> StorageResult<Option<Vec<TransactionOutput>>> {
+        let transaction_events_table = self.open_table(&self.tables.transaction_events)?;
         self.get_vector_of_transaction_objects(
             block_number,
             transaction_metadata_table,
-            |tx_metadata, file_handlers| {
-                file_handlers.get_transaction_output_unchecked(tx_metadata.tx_output_location)
+            |tx_index, tx_metadata, file_handlers| {
+                get_transaction_output_with_events(
+                    &self.txn,
+                    file_handlers,
+                    &transaction_events_table,
+                    tx_index,
+                    &tx_metadata,
+                )
             },
         )
     }
@@ -322,7 +373,7 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
         self.get_vector_of_transaction_objects(
             block_number,
             transaction_metadata_table,
-            |tx_metadata, file_handlers| {
+            |_tx_index, tx_metadata, file_handlers| {
                 file_handlers.get_transaction_unchecked(tx_metadata.tx_location)
             },
         )
@@ -336,7 +387,7 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
         self.get_vector_of_transaction_objects(
             block_number,
             transaction_metadata_table,
-            |tx_metadata, _file_handlers| Ok(tx_metadata.tx_hash),
+            |_tx_index, tx_metadata, _file_handlers| Ok(tx_metadata.tx_hash),
         )
     }
 }
@@ -347,23 +398,72 @@ impl BodyStorageWriter for StorageTxn<'_, RW> {
         let markers_table = self.open_table(&self.tables.markers)?;
         update_marker(&self.txn, &markers_table, block_number)?;
 
-        if self.scope != StorageScope::StateOnly {
-            let events_table = self.open_table(&self.tables.events)?;
-            let transaction_hash_to_idx_table =
-                self.open_table(&self.tables.transaction_hash_to_idx)?;
-            let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
-            let file_offset_table = self.txn.open_table(&self.tables.file_offsets)?;
-
-            write_transactions(
-                &block_body,
-                &self.txn,
-                &self.file_handlers,
-                &file_offset_table,
-                &transaction_hash_to_idx_table,
-                &transaction_metadata_table,
-                &events_table,
+        self.write_body(block_number, &block_body)?;
+        Ok(self)
+    }
+
+    fn append_body_without_events(
+        self,
+        block_number: BlockNumber,
+        block_body: BlockBody,
+    ) -> StorageResult<Self> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        let body_marker = markers_table.get(&self.txn, &MarkerKind::Body)?.unwrap_or_default();
+        if body_marker != block_number {
+            return Err(StorageError::MarkerMismatch {
+                expected: body_marker,
+                found: block_number,
+            });
+        };
+        markers_table.upsert(&self.txn, &MarkerKind::Body, &block_number.unchecked_next())?;
+
+        self.write_body(block_number, &block_body)?;
+        Ok(self)
+    }
+
+    fn append_events(
+        self,
+        block_number: BlockNumber,
+        events: Vec<Vec<Event>>,
+    ) -> StorageResult<Self> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        let event_marker = self.get_event_marker()?;
+        if event_marker != block_number {
+            return Err(StorageError::MarkerMismatch {
+                expected: event_marker,
+                found: block_number,
+            });
+        };
+        if self.get_body_marker()? <= block_number {
+            return Err(StorageError::EventsForNonExistingBody { block_number });
+        }
+        markers_table.upsert(&self.txn, &MarkerKind::Event, &block_number.unchecked_next())?;
+
+        if self.scope != StorageScope::FullArchive {
+            return Ok(self);
+        }
+        let num_transactions = self.get_block_transactions_count(block_number)?.unwrap_or_default();
+        if events.len() != num_transactions {
+            return Err(StorageError::EventsTransactionsMismatch {
                 block_number,
-            )?;
+                num_transactions,
+                num_event_lists: events.len(),
+            });
+        }
+        let events_table = self.open_table(&self.tables.events)?;
+        let events_by_first_key_table = self.open_table(&self.tables.events_by_first_key)?;
+        let transaction_events_table = self.open_table(&self.tables.transaction_events)?;
+
+        // The outputs in the file are immutable, so the events are stored in their own table and
+        // merged into the outputs when they are read.
+        for (offset, tx_events) in events.into_iter().enumerate() {
+            if tx_events.is_empty() {
+                continue;
+            }
+            let tx_index = TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+            write_events(&tx_events, &self.txn, &events_table, tx_index)?;
+            write_events_by_first_key(&tx_events, &self.txn, &events_by_first_key_table, tx_index)?;
+            transaction_events_table.insert(&self.txn, &tx_index, &tx_events)?;
         }
 
         Ok(self)
@@ -390,7 +490,7 @@ impl BodyStorageWriter for StorageTxn<'_, RW> {
         }
 
         let reverted_block_body = 'reverted_block_body: {
-            if self.scope == StorageScope::StateOnly {
+            if self.scope != StorageScope::FullArchive {
                 break 'reverted_block_body None;
             }
 
@@ -398,6 +498,8 @@ impl BodyStorageWriter for StorageTxn<'_, RW> {
             let transaction_hash_to_idx_table =
                 self.open_table(&self.tables.transaction_hash_to_idx)?;
             let events_table = self.open_table(&self.tables.events)?;
+            let events_by_first_key_table = self.open_table(&self.tables.events_by_first_key)?;
+            let transaction_events_table = self.open_table(&self.tables.transaction_events)?;
 
             let transactions = self
                 .get_block_transactions(block_number)?
@@ -417,19 +519,51 @@ impl BodyStorageWriter for StorageTxn<'_, RW> {
 
                 for event in tx_output.events().iter() {
                     events_table.delete(&self.txn, &(event.from_address, tx_index))?;
+                    if let Some(first_key) = event.content.keys.first() {
+                        events_by_first_key_table
+                            .delete(&self.txn, &(first_key.clone(), tx_index))?;
+                    }
                 }
+                transaction_events_table.delete(&self.txn, &tx_index)?;
                 transaction_hash_to_idx_table.delete(&self.txn, tx_hash)?;
                 transaction_metadata_table.delete(&self.txn, &tx_index)?;
             }
             Some((transactions, transaction_outputs, transaction_hashes))
         };
 
+        let event_marker = self.get_event_marker()?;
         markers_table.upsert(&self.txn, &MarkerKind::Body, &block_number)?;
-        markers_table.upsert(&self.txn, &MarkerKind::Event, &block_number)?;
+        markers_table.upsert(&self.txn, &MarkerKind::Event, &event_marker.min(block_number))?;
         Ok((self, reverted_block_body))
     }
 }
 
+impl StorageTxn<'_, RW> {
+    fn write_body(&self, block_number: BlockNumber, block_body: &BlockBody) -> StorageResult<()> {
+        if self.scope != StorageScope::FullArchive {
+            return Ok(());
+        }
+        let events_table = self.open_table(&self.tables.events)?;
+        let events_by_first_key_table = self.open_table(&self.tables.events_by_first_key)?;
+        let transaction_hash_to_idx_table =
+            self.open_table(&self.tables.transaction_hash_to_idx)?;
+        let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
+        let file_offset_table = self.txn.open_table(&self.tables.file_offsets)?;
+
+        write_transactions(
+            block_body,
+            &self.txn,
+            &self.file_handlers,
+            &file_offset_table,
+            &transaction_hash_to_idx_table,
+            &transaction_metadata_table,
+            &events_table,
+            &events_by_first_key_table,
+            block_number,
+        )
+    }
+}
+
 // TODO(dvir): consider enforcing that the block_body transactions, transaction_outputs and
 // transaction_hashes to be the same size.
 #[allow(clippy::too_many_arguments)]
@@ -441,6 +575,7 @@ fn write_transactions<'env>(
     transaction_hash_to_idx_table: &'env TransactionHashToIdxTable<'env>,
     transaction_metadata_table: &'env TransactionMetadataTable<'env>,
     events_table: &'env EventsTable<'env>,
+    events_by_first_key_table: &'env EventsByFirstKeyTable<'env>,
     block_number: BlockNumber,
 ) -> StorageResult<()> {
     for (index, ((tx, tx_output), tx_hash)) in block_body
@@ -454,7 +589,13 @@ fn write_transactions<'env>(
         let transaction_index = TransactionIndex(block_number, tx_offset_in_block);
         let tx_location = file_handlers.append_transaction(tx);
         let tx_output_location = file_handlers.append_transaction_output(tx_output);
-        write_events(tx_output, txn, events_table, transaction_index)?;
+        write_events(tx_output.events(), txn, events_table, transaction_index)?;
+        write_events_by_first_key(
+            tx_output.events(),
+            txn,
+            events_by_first_key_table,
+            transaction_index,
+        )?;
         transaction_hash_to_idx_table.insert(txn, tx_hash, &transaction_index)?;
         transaction_metadata_table.append(
             txn,
@@ -478,14 +619,14 @@ fn write_transactions<'env>(
 
 // This function assumes that the `transaction_index` is the last index used to call it.
 fn write_events<'env>(
-    tx_output: &TransactionOutput,
+    events: &[Event],
     txn: &DbTransaction<'env, RW>,
     events_table: &'env EventsTable<'env>,
     transaction_index: TransactionIndex,
 ) -> StorageResult<()> {
     let mut contract_addresses_set = HashSet::new();
 
-    for event in tx_output.events().iter() {
+    for event in events {
         contract_addresses_set.insert(event.from_address);
     }
 
@@ -498,6 +639,55 @@ fn write_events<'env>(
     Ok(())
 }
 
+// Indexes the transaction by the first keys of its events. This function assumes that the
+// `transaction_index` is the last index used to call it.
+pub(crate) fn write_events_by_first_key<'env>(
+    events: &[Event],
+    txn: &DbTransaction<'env, RW>,
+    events_by_first_key_table: &'env EventsByFirstKeyTable<'env>,
+    transaction_index: TransactionIndex,
+) -> StorageResult<()> {
+    let first_keys: HashSet<_> =
+        events.iter().filter_map(|event| event.content.keys.first()).collect();
+    for first_key in first_keys {
+        events_by_first_key_table.append_greater_sub_key(
+            txn,
+            &(first_key.clone(), transaction_index),
+            &NoValue,
+        )?;
+    }
+    Ok(())
+}
+
+// Reads a transaction output from the file, with the events that were appended to the storage after
+// its body, if there are any.
+pub(crate) fn get_transaction_output_with_events<'env, Mode: TransactionKind>(
+    txn: &'env DbTransaction<'env, Mode>,
+    file_handlers: &FileHandlers<Mode>,
+    transaction_events_table: &'env TransactionEventsTable<'env>,
+    transaction_index: TransactionIndex,
+    tx_metadata: &TransactionMetadata,
+) -> StorageResult<TransactionOutput> {
+    let mut tx_output =
+        file_handlers.get_transaction_output_unchecked(tx_metadata.tx_output_location)?;
+    if tx_output.events().is_empty() {
+        if let Some(events) = transaction_events_table.get(txn, &transaction_index)? {
+            set_events(&mut tx_output, events);
+        }
+    }
+    Ok(tx_output)
+}
+
+fn set_events(tx_output: &mut TransactionOutput, events: Vec<Event>) {
+    match tx_output {
+        TransactionOutput::Declare(output) => output.events = events,
+        TransactionOutput::Deploy(output) => output.events = events,
+        TransactionOutput::DeployAccount(output) => output.events = events,
+        TransactionOutput::Invoke(output) => output.events = events,
+        TransactionOutput::L1Handler(output) => output.events = events,
+    }
+}
+
 fn update_marker<'env>(
     txn: &DbTransaction<'env, RW>,
     markers_table: &'env MarkersTable<'env>,
@@ -0,0 +1,224 @@
+//! Offline compaction of the mmap files of the storage.
+//!
+//! The mmap files are append-only, so reverted blocks, deleted classes and migrations leave dead
+//! objects in them. [`compact_storage`] rewrites each file with only the objects that the tables
+//! of the storage point to, and updates their locations and the file offsets.
+//!
+//! The tables are updated in a single transaction together with a mark that the compaction of
+//! the file is pending, and only then the compacted file replaces the original file. If the
+//! compaction is interrupted, opening the storage completes the replacement of the files whose
+//! compaction was committed and discards the rest.
+
+#[cfg(test)]
+#[path = "compaction_test.rs"]
+mod compaction_test;
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::db::serialization::{NoVersionValueWrapper, ValueSerde};
+use crate::db::table_types::{DbCursorTrait, NoValue, SimpleTable, Table};
+use crate::db::{DbConfig, DbReader, DbWriter, TableIdentifier, RW};
+use crate::mmap_file::{FileCompactor, LocationInFile};
+use crate::state::data::IndexedDeprecatedContractClass;
+use crate::{
+    open_storage,
+    OffsetKind,
+    StorageConfig,
+    StorageResult,
+    StorageScope,
+    StorageTxn,
+    TransactionMetadata,
+};
+
+/// The result of compacting a file.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+pub struct FileCompactionStats {
+    /// The size of the data in the file before the compaction.
+    pub size_before: usize,
+    /// The size of the data in the file after the compaction.
+    pub size_after: usize,
+    /// The space reclaimed by the compaction.
+    pub reclaimed: usize,
+}
+
+/// The result of compacting the mmap files of the storage.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+pub struct CompactionStats {
+    /// The statistics of each compacted file.
+    pub files: BTreeMap<OffsetKind, FileCompactionStats>,
+}
+
+impl CompactionStats {
+    /// Returns the total space reclaimed by the compaction.
+    pub fn reclaimed(&self) -> usize {
+        self.files.values().map(|file_stats| file_stats.reclaimed).sum()
+    }
+}
+
+/// Compacts the mmap files of the storage. The storage is opened by this function, so no other
+/// process should use it meanwhile.
+///
+/// # Errors
+/// Returns [`StorageError`](crate::StorageError) if the storage couldn't be opened, or a file
+/// couldn't be compacted. The files that were compacted before the error stay compacted.
+pub fn compact_storage(mut config: StorageConfig) -> StorageResult<CompactionStats> {
+    // Pruning doesn't touch the files, but there is no need to prune meanwhile.
+    if config.scope == StorageScope::Pruned {
+        config.scope = StorageScope::StateOnly;
+    }
+    let (_reader, mut writer) = open_storage(config.clone())?;
+    let mut stats = CompactionStats::default();
+    for kind in OffsetKind::ALL {
+        // The transactions aren't stored in other scopes.
+        let is_transaction_kind =
+            matches!(kind, OffsetKind::TransactionOutput | OffsetKind::Transaction);
+        if is_transaction_kind && config.scope != StorageScope::FullArchive {
+            continue;
+        }
+
+        let path = config.db_config.path().join(kind.file_name());
+        let compacted_path = compacted_file_path(&path);
+        let mut compactor = FileCompactor::new(&path, &compacted_path)?;
+        let txn = writer.begin_rw_txn()?;
+        match kind {
+            OffsetKind::ThinStateDiff => {
+                relocate(&txn, &txn.tables.state_diffs, &mut compactor, |location| location)?
+            }
+            OffsetKind::ContractClass => {
+                relocate(&txn, &txn.tables.declared_classes, &mut compactor, |location| location)?
+            }
+            OffsetKind::Casm => {
+                relocate(&txn, &txn.tables.casms, &mut compactor, |location| location)?
+            }
+            OffsetKind::DeprecatedContractClass => relocate(
+                &txn,
+                &txn.tables.deprecated_declared_classes,
+                &mut compactor,
+                |indexed_class: &mut IndexedDeprecatedContractClass| {
+                    &mut indexed_class.location_in_file
+                },
+            )?,
+            OffsetKind::TransactionOutput => relocate(
+                &txn,
+                &txn.tables.transaction_metadata,
+                &mut compactor,
+                |tx_metadata: &mut TransactionMetadata| &mut tx_metadata.tx_output_location,
+            )?,
+            OffsetKind::Transaction => relocate(
+                &txn,
+                &txn.tables.transaction_metadata,
+                &mut compactor,
+                |tx_metadata: &mut TransactionMetadata| &mut tx_metadata.tx_location,
+            )?,
+        }
+        let size_after = compactor.finish()?;
+
+        let file_offsets_table = txn.open_table(&txn.tables.file_offsets)?;
+        let pending_compactions_table = txn.open_table(&txn.tables.pending_compactions)?;
+        let size_before = file_offsets_table.get(&txn.txn, &kind)?.unwrap_or_default();
+        file_offsets_table.upsert(&txn.txn, &kind, &size_after)?;
+        pending_compactions_table.upsert(&txn.txn, &kind, &NoValue)?;
+        txn.commit()?;
+
+        replace_file(&compacted_path, &path)?;
+        let txn = writer.begin_rw_txn()?;
+        let pending_compactions_table = txn.open_table(&txn.tables.pending_compactions)?;
+        pending_compactions_table.delete(&txn.txn, &kind)?;
+        txn.commit()?;
+
+        let file_stats = FileCompactionStats {
+            size_before,
+            size_after,
+            reclaimed: size_before.saturating_sub(size_after),
+        };
+        info!("Compacted the {:?} file: {:?}.", kind, file_stats);
+        stats.files.insert(kind, file_stats);
+    }
+    Ok(stats)
+}
+
+// Completes the compactions that were committed but whose files weren't replaced yet, and deletes
+// the files of compactions that weren't committed. Should be called before the files are opened.
+pub(crate) fn complete_pending_compactions(
+    db_config: &DbConfig,
+    db_reader: &DbReader,
+    db_writer: &mut DbWriter,
+    pending_compactions_table_id: &TableIdentifier<
+        OffsetKind,
+        NoVersionValueWrapper<NoValue>,
+        SimpleTable,
+    >,
+) -> StorageResult<()> {
+    let pending_kinds = {
+        let txn = db_reader.begin_ro_txn()?;
+        let pending_compactions_table = txn.open_table(pending_compactions_table_id)?;
+        let mut cursor = pending_compactions_table.cursor(&txn)?;
+        let mut pending_kinds = Vec::new();
+        while let Some((kind, NoValue)) = cursor.next()? {
+            pending_kinds.push(kind);
+        }
+        pending_kinds
+    };
+    for kind in OffsetKind::ALL {
+        let path = db_config.path().join(kind.file_name());
+        let compacted_path = compacted_file_path(&path);
+        if !pending_kinds.contains(&kind) {
+            if compacted_path.exists() {
+                debug!("Deleting the file of an uncommitted compaction of the {:?} file.", kind);
+                std::fs::remove_file(&compacted_path)?;
+            }
+            continue;
+        }
+        // The file may have been replaced before the compaction was marked as completed.
+        if compacted_path.exists() {
+            info!("Completing the compaction of the {:?} file.", kind);
+            replace_file(&compacted_path, &path)?;
+        }
+        let txn = db_writer.begin_rw_txn()?;
+        let pending_compactions_table = txn.open_table(pending_compactions_table_id)?;
+        pending_compactions_table.delete(&txn, &kind)?;
+        txn.commit()?;
+    }
+    Ok(())
+}
+
+fn compacted_file_path(path: &Path) -> PathBuf {
+    path.with_extension("dat.compacted")
+}
+
+// Atomically replaces the file at the given path, and makes the replacement durable.
+fn replace_file(compacted_path: &Path, path: &Path) -> StorageResult<()> {
+    std::fs::rename(compacted_path, path)?;
+    if let Some(dir) = path.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+// Copies the objects the table points to into the compacted file, and updates their locations.
+fn relocate<K, V>(
+    txn: &StorageTxn<'_, RW>,
+    table_id: &TableIdentifier<K, V, SimpleTable>,
+    compactor: &mut FileCompactor,
+    location_mut: impl Fn(&mut V::Value) -> &mut LocationInFile,
+) -> StorageResult<()>
+where
+    K: crate::db::serialization::Key + Debug,
+    V: ValueSerde + Debug,
+{
+    let table = txn.open_table(table_id)?;
+    let mut cursor = table.cursor(&txn.txn)?;
+    // Updating the value of an existing key doesn't move the cursor.
+    while let Some((key, mut value)) = cursor.next()? {
+        let location = location_mut(&mut value);
+        *location = compactor.copy(*location)?;
+        table.upsert(&txn.txn, &key, &value)?;
+    }
+    Ok(())
+}
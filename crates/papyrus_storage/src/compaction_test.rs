@@ -0,0 +1,118 @@
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockNumber};
+use starknet_api::state::ThinStateDiff;
+
+use crate::body::{BodyStorageReader, BodyStorageWriter};
+use crate::compaction::{compact_storage, compacted_file_path};
+use crate::db::table_types::{NoValue, Table};
+use crate::header::HeaderStorageWriter;
+use crate::integrity::check_storage;
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::{append_test_blocks, get_test_storage_with_config_by_scope};
+use crate::{open_storage, OffsetKind, StorageConfig, StorageScope};
+
+const NUM_BLOCKS: u64 = 3;
+
+// Returns a storage config of a storage whose last block was reverted, with the bodies and state
+// diffs of the remaining blocks.
+fn storage_with_reverted_block()
+-> (StorageConfig, Vec<BlockBody>, Vec<ThinStateDiff>, tempfile::TempDir) {
+    let ((_reader, mut writer), config, temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let (mut bodies, mut state_diffs) = append_test_blocks(&mut writer, NUM_BLOCKS);
+    let last_block = BlockNumber(NUM_BLOCKS - 1);
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .revert_header(last_block)
+        .unwrap()
+        .0
+        .revert_body(last_block)
+        .unwrap()
+        .0
+        .revert_state_diff(last_block)
+        .unwrap()
+        .0
+        .commit()
+        .unwrap();
+    bodies.pop();
+    state_diffs.pop();
+    (config, bodies, state_diffs, temp_dir)
+}
+
+#[test]
+fn compaction_reclaims_reverted_objects() {
+    let (config, bodies, state_diffs, _temp_dir) = storage_with_reverted_block();
+
+    let stats = compact_storage(config.clone()).unwrap();
+    assert!(stats.reclaimed() > 0);
+    for kind in [OffsetKind::ThinStateDiff, OffsetKind::TransactionOutput, OffsetKind::Transaction]
+    {
+        let file_stats = stats.files[&kind];
+        assert!(file_stats.reclaimed > 0, "Nothing was reclaimed from the {kind:?} file.");
+        assert_eq!(file_stats.size_before - file_stats.reclaimed, file_stats.size_after);
+    }
+    // There are no classes in the storage.
+    assert_eq!(stats.files[&OffsetKind::Casm].size_after, 0);
+
+    let (reader, writer) = open_storage(config.clone()).unwrap();
+    assert!(check_storage(&reader).unwrap().is_consistent());
+    let txn = reader.begin_ro_txn().unwrap();
+    for (i, (body, state_diff)) in (0..).zip(bodies.into_iter().zip(state_diffs)) {
+        let block_number = BlockNumber(i);
+        assert_eq!(txn.get_block_transactions(block_number).unwrap(), Some(body.transactions));
+        assert_eq!(
+            txn.get_block_transaction_outputs(block_number).unwrap(),
+            Some(body.transaction_outputs)
+        );
+        assert_eq!(txn.get_state_diff(block_number).unwrap(), Some(state_diff));
+    }
+    let file_offsets_table = txn.open_table(&txn.tables.file_offsets).unwrap();
+    for (kind, file_stats) in stats.files {
+        assert_eq!(file_offsets_table.get(&txn.txn, &kind).unwrap(), Some(file_stats.size_after));
+    }
+    drop(txn);
+    drop((reader, writer));
+
+    // A compacted storage has nothing to reclaim.
+    assert_eq!(compact_storage(config).unwrap().reclaimed(), 0);
+}
+
+#[test]
+fn uncommitted_compaction_is_discarded() {
+    let (config, _bodies, state_diffs, _temp_dir) = storage_with_reverted_block();
+    let compacted_path =
+        compacted_file_path(&config.db_config.path().join(OffsetKind::ThinStateDiff.file_name()));
+    std::fs::write(&compacted_path, b"partially compacted").unwrap();
+
+    let (reader, _writer) = open_storage(config).unwrap();
+    assert!(!compacted_path.exists());
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_state_diff(BlockNumber(0)).unwrap(),
+        Some(state_diffs[0].clone())
+    );
+}
+
+#[test]
+fn committed_compaction_is_completed() {
+    let (config, _bodies, state_diffs, _temp_dir) = storage_with_reverted_block();
+    let path = config.db_config.path().join(OffsetKind::ThinStateDiff.file_name());
+    let compacted_path = compacted_file_path(&path);
+    // Simulate a compaction that was committed before its file replaced the original file. The
+    // original file is corrupted so that reading from it fails.
+    let (reader, mut writer) = open_storage(config.clone()).unwrap();
+    std::fs::copy(&path, &compacted_path).unwrap();
+    let txn = writer.begin_rw_txn().unwrap();
+    let pending_compactions_table = txn.open_table(&txn.tables.pending_compactions).unwrap();
+    pending_compactions_table.upsert(&txn.txn, &OffsetKind::ThinStateDiff, &NoValue).unwrap();
+    txn.commit().unwrap();
+    drop((reader, writer));
+    std::fs::write(&path, b"corrupted").unwrap();
+
+    let (reader, _writer) = open_storage(config).unwrap();
+    assert!(!compacted_path.exists());
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_diff(BlockNumber(0)).unwrap(), Some(state_diffs[0].clone()));
+    let pending_compactions_table = txn.open_table(&txn.tables.pending_compactions).unwrap();
+    assert_eq!(pending_compactions_table.get(&txn.txn, &OffsetKind::ThinStateDiff).unwrap(), None);
+}
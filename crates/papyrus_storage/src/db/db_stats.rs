@@ -2,7 +2,7 @@ use human_bytes::human_bytes;
 use libmdbx::Info;
 use serde::{Deserialize, Serialize};
 
-use super::{DbReader, DbResult};
+use super::{DbReader, DbResult, DbTransaction, TransactionKind};
 
 /// A single table statistics.
 #[derive(Serialize, Deserialize, Debug)]
@@ -92,6 +92,14 @@ impl DbReader {
     }
 }
 
+impl<Mode: TransactionKind> DbTransaction<'_, Mode> {
+    // Returns the number of entries in a specific table, as seen by this transaction.
+    pub(crate) fn get_table_entries(&self, name: &str) -> DbResult<usize> {
+        let table = self.txn.open_table(Some(name))?;
+        Ok(self.txn.table_stat(&table)?.entries())
+    }
+}
+
 // Serialize bytes as a human readable string.
 // For example 1024*1024 bytes will be serialized as "1 MiB".
 fn readable_bytes<S>(bytes_num: &u64, s: S) -> Result<S::Ok, S::Error>
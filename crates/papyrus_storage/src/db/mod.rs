@@ -23,13 +23,15 @@ pub(crate) mod table_types;
 
 use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::ffi::CString;
 use std::fmt::Debug;
 use std::marker::PhantomData;
-use std::path::PathBuf;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
 use std::result;
 use std::sync::Arc;
 
-use libmdbx::{DatabaseFlags, Geometry, PageSize, WriteMap};
+use libmdbx::{DatabaseFlags, Geometry, Mode, PageSize, WriteMap};
 use papyrus_config::dumping::{ser_param, SerializeConfig};
 use papyrus_config::validators::validate_ascii;
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
@@ -40,10 +42,10 @@ use validator::Validate;
 
 use self::serialization::{Key, ValueSerde};
 use self::table_types::{DbCursor, DbCursorTrait};
-use crate::db::table_types::TableType;
+use crate::db::table_types::{CommonPrefix, SimpleTable, TableType};
 
 // Maximum number of Sub-Databases.
-const MAX_DBS: usize = 19;
+const MAX_DBS: usize = 23;
 
 // Note that NO_TLS mode is used by default.
 type EnvironmentKind = WriteMap;
@@ -220,6 +222,30 @@ pub(crate) fn open_env(config: &DbConfig) -> DbResult<(DbReader, DbWriter)> {
     Ok((DbReader { env: env.clone() }, DbWriter { env }))
 }
 
+/// Opens an existing MDBX environment for reading only. Another process may write to the
+/// environment meanwhile, and its geometry is the one set by that process.
+pub(crate) fn open_env_read_only(config: &DbConfig) -> DbResult<DbReader> {
+    let db_file_path = config.path().join("mdbx.dat");
+    if !db_file_path.exists() {
+        return Err(DbError::FileDoesNotExist(db_file_path));
+    }
+    const MAX_READERS: u32 = 1 << 13; // 8K readers
+    let env = Arc::new(
+        Environment::new()
+            .set_max_tables(MAX_DBS)
+            .set_max_readers(MAX_READERS)
+            .set_flags(DatabaseFlags {
+                mode: Mode::ReadOnly,
+                // Use the flags of the environment if it's already open by another process.
+                accede: true,
+                no_rdahead: true,
+                ..Default::default()
+            })
+            .open(&config.path())?,
+    );
+    Ok(DbReader { env })
+}
+
 // Size in bytes.
 const MDBX_MIN_PAGESIZE: usize = 256;
 const MDBX_MAX_PAGESIZE: usize = 65536; // 64KB
@@ -249,14 +275,93 @@ impl DbReader {
     pub(crate) fn begin_ro_txn(&self) -> DbResult<DbReadTransaction<'_>> {
         Ok(DbReadTransaction { txn: self.env.begin_ro_txn()? })
     }
+
+    /// Writes a compacted copy of the database to the given path, which must not exist. The copy
+    /// is taken under a single read transaction, so it is consistent even while the database is
+    /// being written to.
+    pub(crate) fn copy_to(&self, path: &Path) -> DbResult<()> {
+        let path = CString::new(path.as_os_str().as_bytes())
+            .expect("A path shouldn't contain a null byte.");
+        // SAFETY: the environment is open for as long as self lives, and the path is a valid
+        // null-terminated string.
+        let result_code = unsafe {
+            mdbx_sys::mdbx_env_copy(self.env.ptr(), path.as_ptr(), mdbx_sys::MDBX_CP_COMPACT)
+        };
+        if result_code != 0 {
+            return Err(DbError::Inner(libmdbx::Error::from_err_code(result_code)));
+        }
+        Ok(())
+    }
 }
 
 type DbReadTransaction<'env> = DbTransaction<'env, RO>;
 
+/// Provides the identifiers of the tables of the database.
+pub(crate) trait TableProvider {
+    fn simple_table<K: Key + Debug, V: ValueSerde + Debug>(
+        &mut self,
+        name: &'static str,
+    ) -> DbResult<TableIdentifier<K, V, SimpleTable>>;
+
+    fn common_prefix_table<MainKey: Key + Debug, SubKey: Key + Debug, V: ValueSerde + Debug>(
+        &mut self,
+        name: &'static str,
+    ) -> DbResult<TableIdentifier<(MainKey, SubKey), V, CommonPrefix>>
+    where
+        (MainKey, SubKey): Key + Debug;
+}
+
+// Creates the tables that don't exist.
+impl TableProvider for DbWriter {
+    fn simple_table<K: Key + Debug, V: ValueSerde + Debug>(
+        &mut self,
+        name: &'static str,
+    ) -> DbResult<TableIdentifier<K, V, SimpleTable>> {
+        self.create_simple_table(name)
+    }
+
+    fn common_prefix_table<MainKey: Key + Debug, SubKey: Key + Debug, V: ValueSerde + Debug>(
+        &mut self,
+        name: &'static str,
+    ) -> DbResult<TableIdentifier<(MainKey, SubKey), V, CommonPrefix>>
+    where
+        (MainKey, SubKey): Key + Debug,
+    {
+        self.create_common_prefix_table(name)
+    }
+}
+
+// A reader can't create tables, so opening a table that doesn't exist fails.
+impl TableProvider for DbReader {
+    fn simple_table<K: Key + Debug, V: ValueSerde + Debug>(
+        &mut self,
+        name: &'static str,
+    ) -> DbResult<TableIdentifier<K, V, SimpleTable>> {
+        Ok(TableIdentifier::new(name))
+    }
+
+    fn common_prefix_table<MainKey: Key + Debug, SubKey: Key + Debug, V: ValueSerde + Debug>(
+        &mut self,
+        name: &'static str,
+    ) -> DbResult<TableIdentifier<(MainKey, SubKey), V, CommonPrefix>>
+    where
+        (MainKey, SubKey): Key + Debug,
+    {
+        Ok(TableIdentifier::new(name))
+    }
+}
+
 impl DbWriter {
     pub(crate) fn begin_rw_txn(&mut self) -> DbResult<DbWriteTransaction<'_>> {
         Ok(DbWriteTransaction { txn: self.env.begin_rw_txn()? })
     }
+
+    // Returns another writer to the same environment, for writing from a background thread. MDBX
+    // allows a single write transaction at a time, so the write transactions of the writers wait
+    // for each other.
+    pub(crate) fn another_writer(&self) -> Self {
+        Self { env: self.env.clone() }
+    }
 }
 
 type DbWriteTransaction<'env> = DbTransaction<'env, RW>;
@@ -301,6 +406,25 @@ pub(crate) struct TableIdentifier<K: Key + Debug, V: ValueSerde + Debug, T: Tabl
     _table_type: PhantomData<T>,
 }
 
+impl<K: Key + Debug, V: ValueSerde + Debug, T: TableType> TableIdentifier<K, V, T> {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _key_type: PhantomData {},
+            _value_type: PhantomData {},
+            _table_type: PhantomData {},
+        }
+    }
+
+    // The same table with the value type of an older storage version, for migrations that rewrite
+    // the values of the table.
+    pub(crate) fn with_value_type<OtherV: ValueSerde + Debug>(
+        &self,
+    ) -> TableIdentifier<K, OtherV, T> {
+        TableIdentifier::new(self.name)
+    }
+}
+
 pub(crate) struct TableHandle<'env, K: Key + Debug, V: ValueSerde + Debug, T: TableType> {
     database: libmdbx::Table<'env>,
     name: &'static str,
@@ -2,7 +2,7 @@ use assert_matches::assert_matches;
 
 use super::{DupSortTableType, DupSortUtils};
 use crate::db::db_test::get_test_env;
-use crate::db::serialization::NoVersionValueWrapper;
+use crate::db::serialization::{NoVersionValueWrapper, VersionZeroWrapper};
 use crate::db::table_types::dup_sort_tables::add_one;
 use crate::db::table_types::test_utils::{random_table_test, table_test, TableKey, TableValue};
 use crate::db::table_types::Table;
@@ -28,6 +28,23 @@ fn common_prefix_append_greater_sub_key() {
     append_greater_sub_key_test(DbWriter::create_common_prefix_table);
 }
 
+#[test]
+fn common_prefix_get_of_versioned_value() {
+    let ((_reader, mut writer), _temp_dir) = get_test_env();
+    let table_id: TableIdentifier<TableKey, VersionZeroWrapper<u32>, _> =
+        writer.create_common_prefix_table("table").unwrap();
+
+    let txn = writer.begin_rw_txn().unwrap();
+    let handle = txn.open_table(&table_id).unwrap();
+    handle.upsert(&txn, &(1, 1), &11).unwrap();
+    handle.insert(&txn, &(1, 2), &12).unwrap();
+
+    // The values are stored without their version, so they are read without it.
+    assert_eq!(handle.get(&txn, &(1, 1)).unwrap(), Some(11));
+    assert_eq!(handle.get(&txn, &(1, 2)).unwrap(), Some(12));
+    assert_eq!(handle.get(&txn, &(1, 3)).unwrap(), None);
+}
+
 #[allow(clippy::type_complexity)]
 fn append_greater_sub_key_test<T>(
     create_table: fn(
@@ -1,2 +1,237 @@
 // This file should contain the deprecated structs and the corresponding migration logic.
 // Check file history for examples.
+
+use starknet_api::block::{BlockHash, BlockNumber, BlockTimestamp, GasPricePerToken};
+use starknet_api::core::{
+    EventCommitment,
+    GlobalRoot,
+    ReceiptCommitment,
+    SequencerContractAddress,
+    StateDiffCommitment,
+    TransactionCommitment,
+};
+use starknet_api::data_availability::L1DataAvailabilityMode;
+
+use crate::body::{write_events_by_first_key, TransactionIndex};
+use crate::db::serialization::{StorageSerdeEx, VersionZeroWrapper};
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::db::{TransactionKind, RO, RW};
+use crate::header::{HeaderStorageReader, StorageBlockHeader};
+use crate::migrations::{Migration, MigrationBatch, VersionKind};
+use crate::version::VersionStorageReader;
+use crate::{StorageError, StorageResult, StorageTxn};
+
+// The migrations between major storage versions, see the migrations module. A migration whose
+// source version is no longer supported should be removed together with its deprecated structs.
+pub(crate) fn registered_migrations() -> Vec<Box<dyn Migration>> {
+    vec![
+        Box::new(AddFeeMarketFieldsToHeaders { kind: VersionKind::State }),
+        Box::new(AddFeeMarketFieldsToHeaders { kind: VersionKind::Blocks }),
+        Box::new(BuildEventsByFirstKeyIndex),
+    ]
+}
+
+// The stored header of blocks version 5, before the fee market fields were added.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub(crate) struct StorageBlockHeaderV5 {
+    pub block_hash: BlockHash,
+    pub parent_hash: BlockHash,
+    pub block_number: BlockNumber,
+    pub l1_gas_price: GasPricePerToken,
+    pub l1_data_gas_price: GasPricePerToken,
+    pub l2_gas_price: GasPricePerToken,
+    pub state_root: GlobalRoot,
+    pub sequencer: SequencerContractAddress,
+    pub timestamp: BlockTimestamp,
+    pub l1_da_mode: L1DataAvailabilityMode,
+    pub state_diff_commitment: Option<StateDiffCommitment>,
+    pub transaction_commitment: Option<TransactionCommitment>,
+    pub event_commitment: Option<EventCommitment>,
+    pub receipt_commitment: Option<ReceiptCommitment>,
+    pub state_diff_length: Option<usize>,
+    pub n_transactions: usize,
+    pub n_events: usize,
+}
+
+impl From<StorageBlockHeaderV5> for StorageBlockHeader {
+    // The blocks stored before the fee market have no L2 gas consumption nor next L2 gas price.
+    fn from(header: StorageBlockHeaderV5) -> Self {
+        Self {
+            block_hash: header.block_hash,
+            parent_hash: header.parent_hash,
+            block_number: header.block_number,
+            l1_gas_price: header.l1_gas_price,
+            l1_data_gas_price: header.l1_data_gas_price,
+            l2_gas_price: header.l2_gas_price,
+            state_root: header.state_root,
+            sequencer: header.sequencer,
+            timestamp: header.timestamp,
+            l1_da_mode: header.l1_da_mode,
+            state_diff_commitment: header.state_diff_commitment,
+            transaction_commitment: header.transaction_commitment,
+            event_commitment: header.event_commitment,
+            receipt_commitment: header.receipt_commitment,
+            state_diff_length: header.state_diff_length,
+            n_transactions: header.n_transactions,
+            n_events: header.n_events,
+            l2_gas_consumed: Default::default(),
+            next_l2_gas_price: Default::default(),
+        }
+    }
+}
+
+// Blocks version 6 and state version 6 added the L2 gas consumption and the next L2 gas price to
+// the headers. The headers are kept in every storage scope, but only a full archive storage has a
+// blocks version, so the migration is registered under both versions. A storage that has a blocks
+// version migrates its headers with it, and the migration of the state version is a no-op there.
+pub(crate) struct AddFeeMarketFieldsToHeaders {
+    pub(crate) kind: VersionKind,
+}
+
+impl AddFeeMarketFieldsToHeaders {
+    fn migrates_headers<Mode: TransactionKind>(
+        &self,
+        txn: &StorageTxn<'_, Mode>,
+    ) -> StorageResult<bool> {
+        Ok(self.kind == VersionKind::Blocks || txn.get_blocks_version()?.is_none())
+    }
+}
+
+impl Migration for AddFeeMarketFieldsToHeaders {
+    fn name(&self) -> &'static str {
+        match self.kind {
+            VersionKind::State => "add_fee_market_fields_to_headers_of_state",
+            VersionKind::Blocks => "add_fee_market_fields_to_headers",
+        }
+    }
+
+    fn kind(&self) -> VersionKind {
+        self.kind
+    }
+
+    fn source_major(&self) -> u32 {
+        5
+    }
+
+    fn estimate_items(&self, txn: &StorageTxn<'_, RO>) -> StorageResult<u64> {
+        if !self.migrates_headers(txn)? {
+            return Ok(0);
+        }
+        Ok(txn.get_header_marker()?.0)
+    }
+
+    fn migrate_batch<'env>(
+        &self,
+        txn: StorageTxn<'env, RW>,
+        start_key: Option<&[u8]>,
+        max_items: usize,
+    ) -> StorageResult<(StorageTxn<'env, RW>, MigrationBatch)> {
+        if !self.migrates_headers(&txn)? {
+            return Ok((txn, MigrationBatch { migrated_items: 0, next_key: None }));
+        }
+        let start_block_number = match start_key {
+            Some(mut key) => {
+                BlockNumber::deserialize(&mut key).ok_or_else(|| StorageError::DBInconsistency {
+                    msg: "Invalid checkpoint of the headers migration.".to_owned(),
+                })?
+            }
+            None => BlockNumber(0),
+        };
+        let old_headers_table_id =
+            txn.tables.headers.with_value_type::<VersionZeroWrapper<StorageBlockHeaderV5>>();
+        // Read the batch before rewriting it, so the cursor doesn't run over the rewritten values.
+        let (old_headers, next_key) = {
+            let old_headers_table = txn.open_table(&old_headers_table_id)?;
+            let mut cursor = old_headers_table.cursor(&txn.txn)?;
+            let mut current = cursor.lower_bound(&start_block_number)?;
+            let mut old_headers = Vec::new();
+            while old_headers.len() < max_items {
+                let Some(entry) = current.take() else {
+                    break;
+                };
+                old_headers.push(entry);
+                current = cursor.next()?;
+            }
+            let next_key = match current {
+                Some((block_number, _)) => Some(block_number.serialize()?),
+                None => None,
+            };
+            (old_headers, next_key)
+        };
+        let migrated_items = u64::try_from(old_headers.len()).expect("usize should fit in u64");
+        let headers_table = txn.open_table(&txn.tables.headers)?;
+        for (block_number, old_header) in old_headers {
+            headers_table.upsert(&txn.txn, &block_number, &old_header.into())?;
+        }
+        Ok((txn, MigrationBatch { migrated_items, next_key }))
+    }
+}
+
+// Blocks version 7 added the index of the transactions by the first keys of their events.
+pub(crate) struct BuildEventsByFirstKeyIndex;
+
+impl Migration for BuildEventsByFirstKeyIndex {
+    fn name(&self) -> &'static str {
+        "build_events_by_first_key_index"
+    }
+
+    fn kind(&self) -> VersionKind {
+        VersionKind::Blocks
+    }
+
+    fn source_major(&self) -> u32 {
+        6
+    }
+
+    fn estimate_items(&self, txn: &StorageTxn<'_, RO>) -> StorageResult<u64> {
+        let entries = txn.txn.get_table_entries(txn.tables.transaction_metadata.name)?;
+        Ok(entries.try_into().expect("usize should fit in u64"))
+    }
+
+    fn migrate_batch<'env>(
+        &self,
+        txn: StorageTxn<'env, RW>,
+        start_key: Option<&[u8]>,
+        max_items: usize,
+    ) -> StorageResult<(StorageTxn<'env, RW>, MigrationBatch)> {
+        let start_tx_index = match start_key {
+            Some(mut key) => TransactionIndex::deserialize(&mut key).ok_or_else(|| {
+                StorageError::DBInconsistency {
+                    msg: "Invalid checkpoint of the events by first key index migration."
+                        .to_owned(),
+                }
+            })?,
+            None => TransactionIndex(BlockNumber(0), Default::default()),
+        };
+        let (migrated_items, next_key) = {
+            let transaction_metadata_table = txn.open_table(&txn.tables.transaction_metadata)?;
+            let events_by_first_key_table = txn.open_table(&txn.tables.events_by_first_key)?;
+            let mut cursor = transaction_metadata_table.cursor(&txn.txn)?;
+            let mut current = cursor.lower_bound(&start_tx_index)?;
+            let mut migrated_items = 0;
+            while let Some((tx_index, tx_metadata)) = &current {
+                if migrated_items == max_items {
+                    break;
+                }
+                let tx_output = txn
+                    .file_handlers
+                    .get_transaction_output_unchecked(tx_metadata.tx_output_location)?;
+                write_events_by_first_key(
+                    tx_output.events(),
+                    &txn.txn,
+                    &events_by_first_key_table,
+                    *tx_index,
+                )?;
+                migrated_items += 1;
+                current = cursor.next()?;
+            }
+            let next_key = match current {
+                Some((tx_index, _)) => Some(tx_index.serialize()?),
+                None => None,
+            };
+            (migrated_items, next_key)
+        };
+        let migrated_items = u64::try_from(migrated_items).expect("usize should fit in u64");
+        Ok((txn, MigrationBatch { migrated_items, next_key }))
+    }
+}
@@ -1,4 +1,4 @@
-mod migrations;
+pub(crate) mod migrations;
 mod serializers;
 #[cfg(test)]
 mod test_instances;
@@ -1,2 +1,41 @@
 // This file should contain the serialization logic for the deprecated structs. Usually, using the
 // auto_storage_serde macro.
+
+use starknet_api::block::{BlockHash, BlockNumber, BlockTimestamp, GasPricePerToken};
+use starknet_api::core::{
+    EventCommitment,
+    GlobalRoot,
+    ReceiptCommitment,
+    SequencerContractAddress,
+    StateDiffCommitment,
+    TransactionCommitment,
+};
+use starknet_api::data_availability::L1DataAvailabilityMode;
+
+use crate::db::serialization::{StorageSerde, StorageSerdeError};
+use crate::deprecated::migrations::StorageBlockHeaderV5;
+use crate::serialization::serializers::auto_storage_serde;
+#[cfg(test)]
+use crate::serialization::serializers_test::{create_storage_serde_test, StorageSerdeTest};
+
+auto_storage_serde! {
+    pub struct StorageBlockHeaderV5 {
+        pub block_hash: BlockHash,
+        pub parent_hash: BlockHash,
+        pub block_number: BlockNumber,
+        pub l1_gas_price: GasPricePerToken,
+        pub l1_data_gas_price: GasPricePerToken,
+        pub l2_gas_price: GasPricePerToken,
+        pub state_root: GlobalRoot,
+        pub sequencer: SequencerContractAddress,
+        pub timestamp: BlockTimestamp,
+        pub l1_da_mode: L1DataAvailabilityMode,
+        pub state_diff_commitment: Option<StateDiffCommitment>,
+        pub transaction_commitment: Option<TransactionCommitment>,
+        pub event_commitment: Option<EventCommitment>,
+        pub receipt_commitment: Option<ReceiptCommitment>,
+        pub state_diff_length: Option<usize>,
+        pub n_transactions: usize,
+        pub n_events: usize,
+    }
+}
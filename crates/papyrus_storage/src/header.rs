@@ -46,6 +46,7 @@ use starknet_api::block::{
     BlockNumber,
     BlockSignature,
     BlockTimestamp,
+    GasPrice,
     GasPricePerToken,
     StarknetVersion,
 };
@@ -58,6 +59,7 @@ use starknet_api::core::{
     TransactionCommitment,
 };
 use starknet_api::data_availability::L1DataAvailabilityMode;
+use starknet_api::execution_resources::GasAmount;
 use tracing::debug;
 
 use crate::db::serialization::NoVersionValueWrapper;
@@ -65,6 +67,8 @@ use crate::db::table_types::{DbCursorTrait, SimpleTable, Table};
 use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
 use crate::{MarkerKind, MarkersTable, StorageError, StorageResult, StorageTxn};
 
+// The headers are kept in every storage scope, so a change of their format needs a migration of
+// the state version as well as of the blocks version.
 #[derive(Debug, Default, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
 pub(crate) struct StorageBlockHeader {
     pub block_hash: BlockHash,
@@ -84,6 +88,8 @@ pub(crate) struct StorageBlockHeader {
     pub state_diff_length: Option<usize>,
     pub n_transactions: usize,
     pub n_events: usize,
+    pub l2_gas_consumed: GasAmount,
+    pub next_l2_gas_price: GasPrice,
 }
 
 type BlockHashToNumberTable<'env> =
@@ -178,6 +184,8 @@ impl<Mode: TransactionKind> HeaderStorageReader for StorageTxn<'_, Mode> {
                 timestamp: block_header.timestamp,
                 l1_da_mode: block_header.l1_da_mode,
                 starknet_version,
+                l2_gas_consumed: block_header.l2_gas_consumed,
+                next_l2_gas_price: block_header.next_l2_gas_price,
             },
             state_diff_commitment: block_header.state_diff_commitment,
             transaction_commitment: block_header.transaction_commitment,
@@ -264,6 +272,8 @@ impl HeaderStorageWriter for StorageTxn<'_, RW> {
             state_diff_length: block_header.state_diff_length,
             n_transactions: block_header.n_transactions,
             n_events: block_header.n_events,
+            l2_gas_consumed: block_header.block_header_without_hash.l2_gas_consumed,
+            next_l2_gas_price: block_header.block_header_without_hash.next_l2_gas_price,
         };
 
         headers_table.append(&self.txn, &block_number, &storage_block_header)?;
@@ -369,6 +379,8 @@ impl HeaderStorageWriter for StorageTxn<'_, RW> {
                     timestamp: reverted_header.timestamp,
                     l1_da_mode: reverted_header.l1_da_mode,
                     starknet_version,
+                    l2_gas_consumed: reverted_header.l2_gas_consumed,
+                    next_l2_gas_price: reverted_header.next_l2_gas_price,
                 },
                 state_diff_commitment: reverted_header.state_diff_commitment,
                 transaction_commitment: reverted_header.transaction_commitment,
@@ -0,0 +1,557 @@
+//! Integrity checks of the storage.
+//!
+//! [`check_storage`] verifies that a storage is internally consistent, e.g. after a crash or a disk
+//! issue. It checks that:
+//! - The markers are consistent with each other, and the data below them exists.
+//! - The file offsets and the locations of the objects point into the written part of the mmap
+//!   files, and the objects deserialize.
+//! - The index of the transactions by their hashes matches the bodies.
+//! - The state diffs agree with the contract storage and nonces tables.
+//! - The block hashes re-compute from the stored headers.
+//!
+//! Logical inconsistencies can be repaired with [`truncate_storage`] to the height returned by
+//! [`IntegrityReport::consistent_height`]. Truncation reverts the blocks, so it requires their data
+//! to be readable. A storage whose data is lost should be restored from a
+//! [snapshot](crate::snapshot) instead.
+//!
+//! # Example
+//! ```
+//! use papyrus_storage::integrity::check_storage;
+//! use papyrus_storage::{open_storage, StorageConfig};
+//! # use papyrus_storage::db::DbConfig;
+//! # use starknet_api::core::ChainId;
+//!
+//! # let dir_handle = tempfile::tempdir().unwrap();
+//! # let dir = dir_handle.path().to_path_buf();
+//! # let db_config = DbConfig {
+//! #     path_prefix: dir,
+//! #     chain_id: ChainId::Mainnet,
+//! #     enforce_file_exists: false,
+//! #     min_size: 1 << 20,    // 1MB
+//! #     max_size: 1 << 35,    // 32GB
+//! #     growth_step: 1 << 26, // 64MB
+//! # };
+//! # let storage_config = StorageConfig{db_config, ..Default::default()};
+//! let (reader, _writer) = open_storage(storage_config)?;
+//! let report = check_storage(&reader)?;
+//! assert!(report.is_consistent());
+//! # Ok::<(), papyrus_storage::StorageError>(())
+//! ```
+
+#[cfg(test)]
+#[path = "integrity_test.rs"]
+mod integrity_test;
+
+use std::collections::BTreeMap;
+
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::block_hash::block_hash_calculator::{
+    calculate_block_hash,
+    concat_counts,
+    BlockHeaderCommitments,
+};
+use starknet_api::transaction::TransactionHash;
+
+use crate::base_layer::BaseLayerStorageWriter;
+use crate::body::{BodyStorageWriter, TransactionIndex};
+use crate::class_manager::ClassManagerStorageWriter;
+use crate::db::serialization::ValueSerde;
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::db::RO;
+use crate::header::{HeaderStorageReader, HeaderStorageWriter};
+use crate::mmap_file::{FileHandler, LocationInFile, Reader};
+use crate::state::StateStorageWriter;
+use crate::{
+    MarkerKind,
+    OffsetKind,
+    StorageReader,
+    StorageResult,
+    StorageScope,
+    StorageTxn,
+    StorageWriter,
+};
+
+/// An inconsistency found in the storage.
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum IntegrityIssue {
+    #[error(
+        "The {lower} marker {lower_marker} is greater than the {upper} marker {upper_marker}."
+    )]
+    MarkersOutOfOrder {
+        lower: &'static str,
+        lower_marker: BlockNumber,
+        upper: &'static str,
+        upper_marker: BlockNumber,
+    },
+    #[error("The {table} table is missing block {block_number}, which is below its marker.")]
+    MissingBlockData { table: &'static str, block_number: BlockNumber },
+    #[error(
+        "The written part of the {kind:?} file ends at {offset}, beyond its size {file_size}."
+    )]
+    OffsetOutOfFile { kind: OffsetKind, offset: usize, file_size: usize },
+    #[error(
+        "An object of the {kind:?} file of block {block_number:?} ends at {end_offset}, beyond \
+         the written part of the file that ends at {file_end}."
+    )]
+    LocationOutOfFile {
+        kind: OffsetKind,
+        block_number: Option<BlockNumber>,
+        end_offset: usize,
+        file_end: usize,
+    },
+    #[error("An object of the {kind:?} file of block {block_number:?} can't be deserialized.")]
+    UndeserializableObject { kind: OffsetKind, block_number: Option<BlockNumber> },
+    #[error(
+        "The index entry of transaction {transaction_hash} doesn't match transaction \
+         {transaction_index:?} of the bodies."
+    )]
+    TransactionHashMismatch {
+        transaction_hash: TransactionHash,
+        transaction_index: TransactionIndex,
+    },
+    #[error("The state diff of block {block_number} doesn't agree with the {table} table.")]
+    StateDiffMismatch { block_number: BlockNumber, table: &'static str },
+    #[error(
+        "The hash of block {block_number} is {stored}, but its header hashes to {calculated}."
+    )]
+    BlockHashMismatch { block_number: BlockNumber, stored: BlockHash, calculated: BlockHash },
+}
+
+impl IntegrityIssue {
+    /// Returns the lowest block that truncating to fixes the issue, or None if the issue isn't of
+    /// a specific block.
+    pub fn block_number(&self) -> Option<BlockNumber> {
+        match self {
+            IntegrityIssue::MarkersOutOfOrder { upper_marker, .. } => Some(*upper_marker),
+            IntegrityIssue::MissingBlockData { block_number, .. }
+            | IntegrityIssue::StateDiffMismatch { block_number, .. }
+            | IntegrityIssue::BlockHashMismatch { block_number, .. } => Some(*block_number),
+            IntegrityIssue::OffsetOutOfFile { .. } => None,
+            IntegrityIssue::LocationOutOfFile { block_number, .. }
+            | IntegrityIssue::UndeserializableObject { block_number, .. } => *block_number,
+            IntegrityIssue::TransactionHashMismatch { transaction_index, .. } => {
+                Some(transaction_index.0)
+            }
+        }
+    }
+
+    /// Returns whether truncating the storage fixes the issue. Truncation reverts the blocks, so
+    /// it can't fix data that is missing or unreadable.
+    pub fn is_repairable(&self) -> bool {
+        match self {
+            IntegrityIssue::MarkersOutOfOrder { .. }
+            | IntegrityIssue::TransactionHashMismatch { .. }
+            | IntegrityIssue::StateDiffMismatch { .. }
+            | IntegrityIssue::BlockHashMismatch { .. } => true,
+            IntegrityIssue::MissingBlockData { .. }
+            | IntegrityIssue::OffsetOutOfFile { .. }
+            | IntegrityIssue::LocationOutOfFile { .. }
+            | IntegrityIssue::UndeserializableObject { .. } => false,
+        }
+    }
+}
+
+/// The result of checking the integrity of the storage.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IntegrityReport {
+    /// The markers of the storage by their names.
+    pub markers: BTreeMap<&'static str, BlockNumber>,
+    /// The inconsistencies found in the storage.
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    /// Returns whether no inconsistencies were found.
+    pub fn is_consistent(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns the height the storage should be truncated to in order to fix its issues, or None
+    /// if the storage is consistent or some of its issues can't be repaired by truncation.
+    pub fn consistent_height(&self) -> Option<BlockNumber> {
+        if !self.issues.iter().all(IntegrityIssue::is_repairable) {
+            return None;
+        }
+        self.issues.iter().filter_map(IntegrityIssue::block_number).min()
+    }
+}
+
+/// Checks the integrity of the storage, in a single read transaction.
+///
+/// # Errors
+/// Returns [`StorageError`](crate::StorageError) if the storage couldn't be read. Inconsistencies
+/// are returned in the report.
+pub fn check_storage(reader: &StorageReader) -> StorageResult<IntegrityReport> {
+    let txn = reader.begin_ro_txn()?;
+    let mut checker = IntegrityChecker::new(&txn)?;
+    checker.check_markers()?;
+    checker.check_file_offsets()?;
+    checker.check_headers()?;
+    checker.check_state_diffs()?;
+    checker.check_classes()?;
+    if txn.scope == StorageScope::FullArchive {
+        checker.check_transactions()?;
+    }
+    Ok(IntegrityReport { markers: checker.markers_by_name(), issues: checker.issues })
+}
+
+/// Truncates the storage to the given height by reverting all the blocks from it onwards, and
+/// lowers the markers that are above it.
+///
+/// # Errors
+/// Returns [`StorageError`](crate::StorageError) if a block couldn't be reverted, e.g. because its
+/// state history was pruned.
+pub fn truncate_storage(writer: &mut StorageWriter, height: BlockNumber) -> StorageResult<()> {
+    let markers = writer.begin_rw_txn()?.get_markers()?;
+    let top_marker = markers.values().copied().max().unwrap_or_default();
+    for block_number in (height.0..top_marker.0).rev().map(BlockNumber) {
+        let txn = writer
+            .begin_rw_txn()?
+            .try_revert_base_layer_marker(block_number)?
+            .try_revert_class_manager_marker(block_number)?;
+        let (txn, ..) = txn.revert_header(block_number)?;
+        let (txn, _) = txn.revert_body(block_number)?;
+        let (txn, _) = txn.revert_state_diff(block_number)?;
+        txn.commit()?;
+    }
+
+    // Markers that are out of order aren't reverted with the blocks.
+    let txn = writer.begin_rw_txn()?;
+    let markers = txn.get_markers()?;
+    let marker = |kind| markers.get(&kind).copied().unwrap_or_default();
+    let state_marker = marker(MarkerKind::State);
+    let clamped_markers = [
+        (MarkerKind::Class, marker(MarkerKind::Class).min(state_marker)),
+        (MarkerKind::CompiledClass, marker(MarkerKind::CompiledClass).min(state_marker)),
+        (MarkerKind::Event, marker(MarkerKind::Event).min(marker(MarkerKind::Body))),
+        (MarkerKind::BaseLayerBlock, marker(MarkerKind::BaseLayerBlock).min(height)),
+        (MarkerKind::ClassManagerBlock, marker(MarkerKind::ClassManagerBlock).min(height)),
+    ];
+    {
+        let markers_table = txn.open_table(&txn.tables.markers)?;
+        for (kind, clamped_marker) in clamped_markers {
+            if marker(kind) != clamped_marker {
+                markers_table.upsert(&txn.txn, &kind, &clamped_marker)?;
+            }
+        }
+    }
+    txn.commit()
+}
+
+// The pairs of markers where the first should not be greater than the second. Note that the
+// compiled class marker advances over blocks without declared classes regardless of the class
+// marker.
+const MARKERS_ORDER: [(MarkerKind, MarkerKind); 7] = [
+    (MarkerKind::CompiledClass, MarkerKind::State),
+    (MarkerKind::Class, MarkerKind::State),
+    (MarkerKind::State, MarkerKind::Header),
+    (MarkerKind::Body, MarkerKind::Header),
+    (MarkerKind::Event, MarkerKind::Body),
+    (MarkerKind::BaseLayerBlock, MarkerKind::Header),
+    (MarkerKind::PrunedState, MarkerKind::State),
+];
+
+impl MarkerKind {
+    fn name(&self) -> &'static str {
+        match self {
+            MarkerKind::Header => "header",
+            MarkerKind::Body => "body",
+            MarkerKind::Event => "event",
+            MarkerKind::State => "state",
+            MarkerKind::Class => "class",
+            MarkerKind::CompiledClass => "compiled_class",
+            MarkerKind::BaseLayerBlock => "base_layer_block",
+            MarkerKind::ClassManagerBlock => "class_manager_block",
+            MarkerKind::PrunedState => "pruned_state",
+        }
+    }
+}
+
+impl<Mode: crate::db::TransactionKind> StorageTxn<'_, Mode> {
+    fn get_markers(&self) -> StorageResult<BTreeMap<MarkerKind, BlockNumber>> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        let mut cursor = markers_table.cursor(&self.txn)?;
+        let mut markers = BTreeMap::new();
+        while let Some((kind, marker)) = cursor.next()? {
+            markers.insert(kind, marker);
+        }
+        Ok(markers)
+    }
+}
+
+struct IntegrityChecker<'txn, 'env> {
+    txn: &'txn StorageTxn<'env, RO>,
+    markers: BTreeMap<MarkerKind, BlockNumber>,
+    // The end of the written and existing part of each file.
+    file_ends: BTreeMap<OffsetKind, usize>,
+    issues: Vec<IntegrityIssue>,
+}
+
+impl<'txn, 'env> IntegrityChecker<'txn, 'env> {
+    fn new(txn: &'txn StorageTxn<'env, RO>) -> StorageResult<Self> {
+        Ok(Self {
+            txn,
+            markers: txn.get_markers()?,
+            file_ends: BTreeMap::new(),
+            issues: Vec::new(),
+        })
+    }
+
+    fn marker(&self, kind: MarkerKind) -> BlockNumber {
+        self.markers.get(&kind).copied().unwrap_or_default()
+    }
+
+    fn markers_by_name(&self) -> BTreeMap<&'static str, BlockNumber> {
+        self.markers.iter().map(|(kind, marker)| (kind.name(), *marker)).collect()
+    }
+
+    fn check_markers(&mut self) -> StorageResult<()> {
+        for (lower, upper) in MARKERS_ORDER {
+            let (lower_marker, upper_marker) = (self.marker(lower), self.marker(upper));
+            if lower_marker > upper_marker {
+                self.issues.push(IntegrityIssue::MarkersOutOfOrder {
+                    lower: lower.name(),
+                    lower_marker,
+                    upper: upper.name(),
+                    upper_marker,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_file_offsets(&mut self) -> StorageResult<()> {
+        let file_offsets_table = self.txn.open_table(&self.txn.tables.file_offsets)?;
+        let file_handlers = &self.txn.file_handlers;
+        for kind in OffsetKind::ALL {
+            let offset = file_offsets_table.get(&self.txn.txn, &kind)?.unwrap_or_default();
+            let file_size = match kind {
+                OffsetKind::ThinStateDiff => file_handlers.thin_state_diff.stats(),
+                OffsetKind::ContractClass => file_handlers.contract_class.stats(),
+                OffsetKind::Casm => file_handlers.casm.stats(),
+                OffsetKind::DeprecatedContractClass => {
+                    file_handlers.deprecated_contract_class.stats()
+                }
+                OffsetKind::TransactionOutput => file_handlers.transaction_output.stats(),
+                OffsetKind::Transaction => file_handlers.transaction.stats(),
+            }
+            .size();
+            if offset > file_size {
+                self.issues.push(IntegrityIssue::OffsetOutOfFile { kind, offset, file_size });
+            }
+            self.file_ends.insert(kind, offset.min(file_size));
+        }
+        Ok(())
+    }
+
+    // Returns the object at the given location, or None if the location is outside the written
+    // part of the file or the object can't be deserialized.
+    fn read_object<V: ValueSerde>(
+        &mut self,
+        file_handler: &FileHandler<V, RO>,
+        kind: OffsetKind,
+        location: LocationInFile,
+        block_number: Option<BlockNumber>,
+    ) -> StorageResult<Option<V::Value>> {
+        let file_end = self.file_ends[&kind];
+        let end_offset = location.next_offset();
+        if end_offset > file_end {
+            self.issues.push(IntegrityIssue::LocationOutOfFile {
+                kind,
+                block_number,
+                end_offset,
+                file_end,
+            });
+            return Ok(None);
+        }
+        let object = file_handler.get(location)?;
+        if object.is_none() {
+            self.issues.push(IntegrityIssue::UndeserializableObject { kind, block_number });
+        }
+        Ok(object)
+    }
+
+    fn check_headers(&mut self) -> StorageResult<()> {
+        for block_number in (0..self.marker(MarkerKind::Header).0).map(BlockNumber) {
+            let Some(header) = self.txn.get_block_header(block_number)? else {
+                self.issues
+                    .push(IntegrityIssue::MissingBlockData { table: "headers", block_number });
+                continue;
+            };
+            // Only headers from Starknet 0.13.2 onwards have all the commitments to re-compute
+            // their hash from.
+            let (
+                Some(transaction_commitment),
+                Some(event_commitment),
+                Some(receipt_commitment),
+                Some(state_diff_commitment),
+                Some(state_diff_length),
+            ) = (
+                header.transaction_commitment,
+                header.event_commitment,
+                header.receipt_commitment,
+                header.state_diff_commitment,
+                header.state_diff_length,
+            )
+            else {
+                continue;
+            };
+            let commitments = BlockHeaderCommitments {
+                transaction_commitment,
+                event_commitment,
+                receipt_commitment,
+                state_diff_commitment,
+                concatenated_counts: concat_counts(
+                    header.n_transactions,
+                    header.n_events,
+                    state_diff_length,
+                    header.block_header_without_hash.l1_da_mode,
+                ),
+            };
+            let Ok(calculated) =
+                calculate_block_hash(header.block_header_without_hash.clone(), commitments)
+            else {
+                continue;
+            };
+            if calculated != header.block_hash {
+                self.issues.push(IntegrityIssue::BlockHashMismatch {
+                    block_number,
+                    stored: header.block_hash,
+                    calculated,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_state_diffs(&mut self) -> StorageResult<()> {
+        let state_diffs_table = self.txn.open_table(&self.txn.tables.state_diffs)?;
+        let contract_storage_table = self.txn.open_table(&self.txn.tables.contract_storage)?;
+        let nonces_table = self.txn.open_table(&self.txn.tables.nonces)?;
+        let file_handlers = &self.txn.file_handlers;
+        // The state history before the pruned state marker was deleted.
+        let pruned_state_marker = self.marker(MarkerKind::PrunedState);
+        for block_number in (0..self.marker(MarkerKind::State).0).map(BlockNumber) {
+            let Some(location) = state_diffs_table.get(&self.txn.txn, &block_number)? else {
+                self.issues
+                    .push(IntegrityIssue::MissingBlockData { table: "state_diffs", block_number });
+                continue;
+            };
+            let Some(state_diff) = self.read_object(
+                &file_handlers.thin_state_diff,
+                OffsetKind::ThinStateDiff,
+                location,
+                Some(block_number),
+            )?
+            else {
+                continue;
+            };
+            if block_number < pruned_state_marker {
+                continue;
+            }
+            'storage_diffs: for (address, storage_diffs) in &state_diff.storage_diffs {
+                for (key, value) in storage_diffs {
+                    let stored = contract_storage_table
+                        .get(&self.txn.txn, &((*address, *key), block_number))?;
+                    if stored != Some(*value) {
+                        self.issues.push(IntegrityIssue::StateDiffMismatch {
+                            block_number,
+                            table: "contract_storage",
+                        });
+                        break 'storage_diffs;
+                    }
+                }
+            }
+            for (address, nonce) in &state_diff.nonces {
+                if nonces_table.get(&self.txn.txn, &(*address, block_number))? != Some(*nonce) {
+                    self.issues
+                        .push(IntegrityIssue::StateDiffMismatch { block_number, table: "nonces" });
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_classes(&mut self) -> StorageResult<()> {
+        let declared_classes_table = self.txn.open_table(&self.txn.tables.declared_classes)?;
+        let declared_classes_block_table =
+            self.txn.open_table(&self.txn.tables.declared_classes_block)?;
+        let casms_table = self.txn.open_table(&self.txn.tables.casms)?;
+        let deprecated_declared_classes_table =
+            self.txn.open_table(&self.txn.tables.deprecated_declared_classes)?;
+        let file_handlers = &self.txn.file_handlers;
+
+        let mut cursor = declared_classes_table.cursor(&self.txn.txn)?;
+        while let Some((class_hash, location)) = cursor.next()? {
+            let block_number = declared_classes_block_table.get(&self.txn.txn, &class_hash)?;
+            self.read_object(
+                &file_handlers.contract_class,
+                OffsetKind::ContractClass,
+                location,
+                block_number,
+            )?;
+        }
+        let mut cursor = casms_table.cursor(&self.txn.txn)?;
+        while let Some((class_hash, location)) = cursor.next()? {
+            let block_number = declared_classes_block_table.get(&self.txn.txn, &class_hash)?;
+            self.read_object(&file_handlers.casm, OffsetKind::Casm, location, block_number)?;
+        }
+        let mut cursor = deprecated_declared_classes_table.cursor(&self.txn.txn)?;
+        while let Some((_, indexed_class)) = cursor.next()? {
+            self.read_object(
+                &file_handlers.deprecated_contract_class,
+                OffsetKind::DeprecatedContractClass,
+                indexed_class.location_in_file,
+                Some(indexed_class.block_number),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn check_transactions(&mut self) -> StorageResult<()> {
+        let transaction_metadata_table =
+            self.txn.open_table(&self.txn.tables.transaction_metadata)?;
+        let transaction_hash_to_idx_table =
+            self.txn.open_table(&self.txn.tables.transaction_hash_to_idx)?;
+        let file_handlers = &self.txn.file_handlers;
+
+        let mut cursor = transaction_metadata_table.cursor(&self.txn.txn)?;
+        while let Some((transaction_index, tx_metadata)) = cursor.next()? {
+            let block_number = Some(transaction_index.0);
+            self.read_object(
+                &file_handlers.transaction,
+                OffsetKind::Transaction,
+                tx_metadata.tx_location,
+                block_number,
+            )?;
+            self.read_object(
+                &file_handlers.transaction_output,
+                OffsetKind::TransactionOutput,
+                tx_metadata.tx_output_location,
+                block_number,
+            )?;
+            let indexed = transaction_hash_to_idx_table.get(&self.txn.txn, &tx_metadata.tx_hash)?;
+            if indexed != Some(transaction_index) {
+                self.issues.push(IntegrityIssue::TransactionHashMismatch {
+                    transaction_hash: tx_metadata.tx_hash,
+                    transaction_index,
+                });
+            }
+        }
+
+        // Look for index entries of transactions that aren't in the bodies.
+        let mut cursor = transaction_hash_to_idx_table.cursor(&self.txn.txn)?;
+        while let Some((transaction_hash, transaction_index)) = cursor.next()? {
+            let tx_metadata = transaction_metadata_table.get(&self.txn.txn, &transaction_index)?;
+            if tx_metadata.is_none_or(|tx_metadata| tx_metadata.tx_hash != transaction_hash) {
+                self.issues.push(IntegrityIssue::TransactionHashMismatch {
+                    transaction_hash,
+                    transaction_index,
+                });
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::{contract_address, felt, storage_key};
+
+use crate::body::BodyStorageReader;
+use crate::db::table_types::Table;
+use crate::header::HeaderStorageReader;
+use crate::integrity::{check_storage, truncate_storage, IntegrityIssue};
+use crate::state::StateStorageReader;
+use crate::test_utils::{append_test_blocks, get_test_storage};
+use crate::{MarkerKind, OffsetKind, StorageReader, StorageWriter};
+
+const NUM_BLOCKS: u64 = 3;
+
+fn storage_with_blocks() -> ((StorageReader, StorageWriter), tempfile::TempDir) {
+    let ((reader, mut writer), temp_dir) = get_test_storage();
+    append_test_blocks(&mut writer, NUM_BLOCKS);
+    ((reader, writer), temp_dir)
+}
+
+#[test]
+fn consistent_storage() {
+    let ((reader, _writer), _temp_dir) = storage_with_blocks();
+    let report = check_storage(&reader).unwrap();
+    assert!(report.is_consistent(), "{:?}", report.issues);
+    assert_eq!(report.markers["header"], BlockNumber(NUM_BLOCKS));
+    assert_eq!(report.markers["body"], BlockNumber(NUM_BLOCKS));
+    assert_eq!(report.markers["state"], BlockNumber(NUM_BLOCKS));
+    assert_eq!(report.consistent_height(), None);
+}
+
+#[test]
+fn mismatching_transaction_hash_index_is_repaired() {
+    let ((reader, mut writer), _temp_dir) = storage_with_blocks();
+    let txn = writer.begin_rw_txn().unwrap();
+    let transaction_hash = txn.get_block_transaction_hashes(BlockNumber(1)).unwrap().unwrap()[0];
+    let transaction_hash_to_idx_table =
+        txn.open_table(&txn.tables.transaction_hash_to_idx).unwrap();
+    transaction_hash_to_idx_table.delete(&txn.txn, &transaction_hash).unwrap();
+    txn.commit().unwrap();
+
+    let report = check_storage(&reader).unwrap();
+    assert_matches!(
+        report.issues.as_slice(),
+        [IntegrityIssue::TransactionHashMismatch { transaction_hash: hash, .. }]
+            if *hash == transaction_hash
+    );
+    assert_eq!(report.consistent_height(), Some(BlockNumber(1)));
+
+    truncate_storage(&mut writer, BlockNumber(1)).unwrap();
+    let report = check_storage(&reader).unwrap();
+    assert!(report.is_consistent(), "{:?}", report.issues);
+    assert_eq!(report.markers["header"], BlockNumber(1));
+    assert_eq!(report.markers["body"], BlockNumber(1));
+    assert_eq!(report.markers["state"], BlockNumber(1));
+}
+
+#[test]
+fn state_diff_mismatch() {
+    let ((reader, mut writer), _temp_dir) = storage_with_blocks();
+    let txn = writer.begin_rw_txn().unwrap();
+    let contract_storage_table = txn.open_table(&txn.tables.contract_storage).unwrap();
+    contract_storage_table
+        .upsert(
+            &txn.txn,
+            &((contract_address!("0x1"), storage_key!("0x2")), BlockNumber(2)),
+            &felt!("0x100"),
+        )
+        .unwrap();
+    txn.commit().unwrap();
+
+    let report = check_storage(&reader).unwrap();
+    assert_eq!(
+        report.issues,
+        vec![IntegrityIssue::StateDiffMismatch {
+            block_number: BlockNumber(2),
+            table: "contract_storage"
+        }]
+    );
+    assert_eq!(report.consistent_height(), Some(BlockNumber(2)));
+}
+
+#[test]
+fn markers_out_of_order_are_repaired() {
+    let ((reader, mut writer), _temp_dir) = storage_with_blocks();
+    let txn = writer.begin_rw_txn().unwrap();
+    let markers_table = txn.open_table(&txn.tables.markers).unwrap();
+    markers_table.upsert(&txn.txn, &MarkerKind::Class, &BlockNumber(NUM_BLOCKS + 2)).unwrap();
+    txn.commit().unwrap();
+
+    let report = check_storage(&reader).unwrap();
+    assert_eq!(
+        report.issues,
+        vec![IntegrityIssue::MarkersOutOfOrder {
+            lower: "class",
+            lower_marker: BlockNumber(NUM_BLOCKS + 2),
+            upper: "state",
+            upper_marker: BlockNumber(NUM_BLOCKS),
+        }]
+    );
+    assert_eq!(report.consistent_height(), Some(BlockNumber(NUM_BLOCKS)));
+
+    truncate_storage(&mut writer, BlockNumber(NUM_BLOCKS)).unwrap();
+    let report = check_storage(&reader).unwrap();
+    assert!(report.is_consistent(), "{:?}", report.issues);
+    assert_eq!(report.markers["class"], BlockNumber(NUM_BLOCKS));
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(NUM_BLOCKS));
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(NUM_BLOCKS));
+}
+
+#[test]
+fn event_marker_above_body_marker_is_repaired() {
+    let ((reader, mut writer), _temp_dir) = storage_with_blocks();
+    let txn = writer.begin_rw_txn().unwrap();
+    let markers_table = txn.open_table(&txn.tables.markers).unwrap();
+    markers_table.upsert(&txn.txn, &MarkerKind::Event, &BlockNumber(NUM_BLOCKS + 1)).unwrap();
+    txn.commit().unwrap();
+
+    let report = check_storage(&reader).unwrap();
+    assert_eq!(
+        report.issues,
+        vec![IntegrityIssue::MarkersOutOfOrder {
+            lower: "event",
+            lower_marker: BlockNumber(NUM_BLOCKS + 1),
+            upper: "body",
+            upper_marker: BlockNumber(NUM_BLOCKS),
+        }]
+    );
+    assert_eq!(report.consistent_height(), Some(BlockNumber(NUM_BLOCKS)));
+
+    truncate_storage(&mut writer, BlockNumber(NUM_BLOCKS)).unwrap();
+    let report = check_storage(&reader).unwrap();
+    assert!(report.is_consistent(), "{:?}", report.issues);
+    assert_eq!(report.markers["event"], BlockNumber(NUM_BLOCKS));
+    assert_eq!(report.markers["body"], BlockNumber(NUM_BLOCKS));
+}
+
+#[test]
+fn location_beyond_file_offset_is_not_repairable() {
+    let ((reader, mut writer), _temp_dir) = storage_with_blocks();
+    let txn = writer.begin_rw_txn().unwrap();
+    let file_offsets_table = txn.open_table(&txn.tables.file_offsets).unwrap();
+    file_offsets_table.upsert(&txn.txn, &OffsetKind::ThinStateDiff, &0).unwrap();
+    txn.commit().unwrap();
+
+    let report = check_storage(&reader).unwrap();
+    assert_eq!(report.issues.len(), usize::try_from(NUM_BLOCKS).unwrap());
+    assert_matches!(
+        report.issues[0],
+        IntegrityIssue::LocationOutOfFile {
+            kind: OffsetKind::ThinStateDiff,
+            block_number: Some(BlockNumber(0)),
+            file_end: 0,
+            ..
+        }
+    );
+    assert_eq!(report.consistent_height(), None);
+}
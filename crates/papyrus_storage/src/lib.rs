@@ -53,10 +53,12 @@
 //!
 //! The storage version is composed of two components: [`STORAGE_VERSION_STATE`] for the state and
 //! [`STORAGE_VERSION_BLOCKS`] for blocks. Each version consists of a major and a minor version. A
-//! higher major version indicates that a re-sync is necessary, while a higher minor version
+//! higher major version indicates that a re-sync is necessary, unless a migration from the
+//! existing major version is registered (see [`migrations`]), while a higher minor version
 //! indicates a change that is migratable.
 //!
-//! When a storage is opened with [`StorageScope::StateOnly`], only the state version must match.
+//! When a storage is opened with [`StorageScope::StateOnly`] or [`StorageScope::Pruned`], only the
+//! state version must match.
 //! For storage opened with [`StorageScope::FullArchive`], both versions must match the crate's
 //! versions.
 //!
@@ -80,6 +82,7 @@ pub mod body;
 pub mod class;
 pub mod class_hash;
 pub mod class_manager;
+pub mod compaction;
 pub mod compiled_class;
 #[cfg(feature = "document_calls")]
 pub mod document_calls;
@@ -89,13 +92,19 @@ pub mod storage_metrics;
 pub mod compression_utils;
 pub mod db;
 pub mod header;
+pub mod integrity;
+pub mod migrations;
 pub mod mmap_file;
 mod serialization;
+pub mod snapshot;
 pub mod state;
 mod version;
 
 mod deprecated;
 
+#[cfg(test)]
+#[path = "read_only_test.rs"]
+mod read_only_test;
 #[cfg(test)]
 mod test_instances;
 
@@ -111,9 +120,10 @@ use body::events::EventIndex;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use db::db_stats::{DbTableStats, DbWholeStats};
 use db::serialization::{Key, NoVersionValueWrapper, ValueSerde, VersionZeroWrapper};
-use db::table_types::{CommonPrefix, NoValue, Table, TableType};
+use db::table_types::{CommonPrefix, DbCursorTrait, NoValue, Table, TableType};
 use mmap_file::{
     open_file,
+    open_file_read_only,
     FileHandler,
     LocationInFile,
     MMapFileError,
@@ -129,16 +139,18 @@ use starknet_api::block::{BlockHash, BlockNumber, BlockSignature, StarknetVersio
 use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::state::{SierraContractClass, StateNumber, StorageKey, ThinStateDiff};
-use starknet_api::transaction::{Transaction, TransactionHash, TransactionOutput};
+use starknet_api::transaction::{Event, EventKey, Transaction, TransactionHash, TransactionOutput};
 use starknet_types_core::felt::Felt;
 use tracing::{debug, info, warn};
 use validator::Validate;
 use version::{StorageVersionError, Version};
 
 use crate::body::TransactionIndex;
+use crate::compaction::complete_pending_compactions;
 use crate::db::table_types::SimpleTable;
 use crate::db::{
     open_env,
+    open_env_read_only,
     DbConfig,
     DbError,
     DbReader,
@@ -146,20 +158,24 @@ use crate::db::{
     DbWriter,
     TableHandle,
     TableIdentifier,
+    TableProvider,
     TransactionKind,
     RO,
     RW,
 };
+use crate::deprecated::migrations::registered_migrations;
 use crate::header::StorageBlockHeader;
+use crate::migrations::{run_migrations, MigrationCheckpoint, MigrationConfig, MigrationPlan};
 use crate::mmap_file::MMapFileStats;
 use crate::state::data::IndexedDeprecatedContractClass;
+use crate::state::pruning::{spawn_state_pruner, StatePrunerHandle, StatePruningConfig};
 use crate::version::{VersionStorageReader, VersionStorageWriter};
 
 // For more details on the storage version, see the module documentation.
 /// The current version of the storage state code.
-pub const STORAGE_VERSION_STATE: Version = Version { major: 5, minor: 0 };
+pub const STORAGE_VERSION_STATE: Version = Version { major: 6, minor: 0 };
 /// The current version of the storage blocks code.
-pub const STORAGE_VERSION_BLOCKS: Version = Version { major: 5, minor: 0 };
+pub const STORAGE_VERSION_BLOCKS: Version = Version { major: 7, minor: 1 };
 
 /// Opens a storage and returns a [`StorageReader`] and a [`StorageWriter`].
 pub fn open_storage(
@@ -174,33 +190,13 @@ pub fn open_storage(
     }
 
     let (db_reader, mut db_writer) = open_env(&storage_config.db_config)?;
-    let tables = Arc::new(Tables {
-        block_hash_to_number: db_writer.create_simple_table("block_hash_to_number")?,
-        block_signatures: db_writer.create_simple_table("block_signatures")?,
-        casms: db_writer.create_simple_table("casms")?,
-        contract_storage: db_writer.create_common_prefix_table("contract_storage")?,
-        declared_classes: db_writer.create_simple_table("declared_classes")?,
-        declared_classes_block: db_writer.create_simple_table("declared_classes_block")?,
-        deprecated_declared_classes: db_writer
-            .create_simple_table("deprecated_declared_classes")?,
-        deployed_contracts: db_writer.create_simple_table("deployed_contracts")?,
-        events: db_writer.create_common_prefix_table("events")?,
-        headers: db_writer.create_simple_table("headers")?,
-        markers: db_writer.create_simple_table("markers")?,
-        nonces: db_writer.create_common_prefix_table("nonces")?,
-        file_offsets: db_writer.create_simple_table("file_offsets")?,
-        state_diffs: db_writer.create_simple_table("state_diffs")?,
-        transaction_hash_to_idx: db_writer.create_simple_table("transaction_hash_to_idx")?,
-        transaction_metadata: db_writer.create_simple_table("transaction_metadata")?,
-
-        // Version tables.
-        starknet_version: db_writer.create_simple_table("starknet_version")?,
-        storage_version: db_writer.create_simple_table("storage_version")?,
-
-        // Class hashes.
-        class_hash_to_executable_class_hash: db_writer
-            .create_simple_table("class_hash_to_executable_class_hash")?,
-    });
+    let tables = Arc::new(open_tables(&mut db_writer)?);
+    complete_pending_compactions(
+        &storage_config.db_config,
+        &db_reader,
+        &mut db_writer,
+        &tables.pending_compactions,
+    )?;
     let (file_writers, file_readers) = open_storage_files(
         &storage_config.db_config,
         storage_config.mmap_file_config,
@@ -214,13 +210,113 @@ pub fn open_storage(
         scope: storage_config.scope,
         file_readers,
     };
-    let writer = StorageWriter { db_writer, tables, scope: storage_config.scope, file_writers };
+    let mut writer = StorageWriter {
+        db_writer,
+        tables,
+        scope: storage_config.scope,
+        file_writers,
+        _state_pruner: None,
+    };
 
-    let writer = set_version_if_needed(reader.clone(), writer)?;
+    run_migrations(
+        &reader,
+        &mut writer,
+        &storage_config.migration_config,
+        &registered_migrations(),
+    )?;
+    writer = set_version_if_needed(reader.clone(), writer)?;
     verify_storage_version(reader.clone())?;
+    if storage_config.scope == StorageScope::Pruned {
+        let pruner_writer = StorageWriter {
+            db_writer: writer.db_writer.another_writer(),
+            tables: writer.tables.clone(),
+            scope: writer.scope,
+            file_writers: writer.file_writers.clone(),
+            _state_pruner: None,
+        };
+        writer._state_pruner =
+            Some(spawn_state_pruner(pruner_writer, storage_config.state_pruning_config));
+    }
     Ok((reader, writer))
 }
 
+/// Opens an existing storage for reading only and returns a [`StorageReader`].
+///
+/// Unlike [`open_storage`], neither the database nor the files are written to, so the storage
+/// may be used by a node in another process meanwhile. The storage must have been initialized by
+/// [`open_storage`] with the same version of the crate.
+pub fn open_storage_read_only(storage_config: StorageConfig) -> StorageResult<StorageReader> {
+    info!("Opening storage for reading only: {}", storage_config.db_config.path_prefix.display());
+    let mut db_reader = open_env_read_only(&storage_config.db_config)?;
+    let tables = Arc::new(open_tables(&mut db_reader)?);
+    {
+        let txn = db_reader.begin_ro_txn()?;
+        let pending_compactions_table = txn.open_table(&tables.pending_compactions)?;
+        if pending_compactions_table.cursor(&txn)?.next()?.is_some() {
+            return Err(StorageError::ReadOnlyStorageUnavailable {
+                reason: "The storage has a compaction that wasn't completed.".to_owned(),
+            });
+        }
+    }
+    let file_readers = open_storage_files_read_only(
+        &storage_config.db_config,
+        storage_config.mmap_file_config,
+        &db_reader,
+        &tables.file_offsets,
+    )?;
+    let reader = StorageReader { db_reader, tables, scope: storage_config.scope, file_readers };
+
+    match get_storage_version(reader.clone())? {
+        None => {
+            return Err(StorageError::ReadOnlyStorageUnavailable {
+                reason: "The storage wasn't initialized.".to_owned(),
+            });
+        }
+        Some(StorageVersion::StateOnly(_)) if storage_config.scope == StorageScope::FullArchive => {
+            return Err(StorageError::StorageVersionInconsistency(
+                StorageVersionError::InconsistentStorageScope,
+            ));
+        }
+        Some(_) => {}
+    }
+    verify_storage_version(reader.clone())?;
+    Ok(reader)
+}
+
+// Returns the identifiers of the tables of the storage.
+fn open_tables(provider: &mut impl TableProvider) -> StorageResult<Tables> {
+    Ok(Tables {
+        block_hash_to_number: provider.simple_table("block_hash_to_number")?,
+        block_signatures: provider.simple_table("block_signatures")?,
+        casms: provider.simple_table("casms")?,
+        contract_storage: provider.common_prefix_table("contract_storage")?,
+        declared_classes: provider.simple_table("declared_classes")?,
+        declared_classes_block: provider.simple_table("declared_classes_block")?,
+        deprecated_declared_classes: provider.simple_table("deprecated_declared_classes")?,
+        deployed_contracts: provider.simple_table("deployed_contracts")?,
+        events: provider.common_prefix_table("events")?,
+        events_by_first_key: provider.common_prefix_table("events_by_first_key")?,
+        headers: provider.simple_table("headers")?,
+        markers: provider.simple_table("markers")?,
+        migration_checkpoints: provider.simple_table("migration_checkpoints")?,
+        nonces: provider.common_prefix_table("nonces")?,
+        pending_compactions: provider.simple_table("pending_compactions")?,
+        file_offsets: provider.simple_table("file_offsets")?,
+        state_diffs: provider.simple_table("state_diffs")?,
+        transaction_events: provider.simple_table("transaction_events")?,
+        transaction_hash_to_idx: provider.simple_table("transaction_hash_to_idx")?,
+        transaction_metadata: provider.simple_table("transaction_metadata")?,
+
+        // Version tables.
+        starknet_version: provider.simple_table("starknet_version")?,
+        storage_version: provider.simple_table("storage_version")?,
+
+        // Class hashes.
+        class_hash_to_executable_class_hash: provider
+            .simple_table("class_hash_to_executable_class_hash")?,
+    })
+}
+
 // In case storage version does not exist, set it to the crate version.
 // Expected to happen once - when the node is launched for the first time.
 // If the storage scope has changed, update accordingly.
@@ -247,11 +343,11 @@ fn set_version_if_needed(
         StorageVersion::FullArchive(FullArchiveVersion { state_version: _, blocks_version: _ }) => {
             // TODO(yael): consider optimizing by deleting the block's data if the scope has changed
             // to StateOnly
-            if writer.scope == StorageScope::StateOnly {
+            if writer.scope != StorageScope::FullArchive {
                 // Deletion of the block's version is required here. It ensures that the node knows
                 // that the storage operates in StateOnly mode and prevents the operator from
                 // running it in FullArchive mode again.
-                debug!("Changing the storage scope from FullArchive to StateOnly.");
+                debug!("Changing the storage scope from FullArchive to {:?}.", writer.scope);
                 writer.begin_rw_txn()?.delete_blocks_version()?.commit()?;
             }
         }
@@ -411,6 +507,9 @@ pub enum StorageScope {
     /// Stores the data describing the current state. In this mode the transaction, events and
     /// state-diffs are not stored.
     StateOnly,
+    /// Like [`StorageScope::StateOnly`], but keeps the state history only for a window of recent
+    /// blocks. Older history is deleted in the background, see [`StatePruningConfig`].
+    Pruned,
 }
 
 /// A struct for starting RO transactions ([`StorageTxn`]) to the storage.
@@ -462,6 +561,8 @@ pub struct StorageWriter {
     file_writers: FileHandlers<RW>,
     tables: Arc<Tables>,
     scope: StorageScope,
+    // Stops the state pruner once the writer is dropped.
+    _state_pruner: Option<StatePrunerHandle>,
 }
 
 impl StorageWriter {
@@ -500,9 +601,11 @@ impl<Mode: TransactionKind> StorageTxn<'_, Mode> {
         &self,
         table_id: &TableIdentifier<K, V, T>,
     ) -> StorageResult<TableHandle<'_, K, V, T>> {
-        if self.scope == StorageScope::StateOnly {
+        if self.scope != StorageScope::FullArchive {
             let unused_tables = [
                 self.tables.events.name,
+                self.tables.events_by_first_key.name,
+                self.tables.transaction_events.name,
                 self.tables.transaction_hash_to_idx.name,
                 self.tables.transaction_metadata.name,
             ];
@@ -536,11 +639,17 @@ struct_field_names! {
         // TODO(dvir): consider use here also the CommonPrefix table type.
         deployed_contracts: TableIdentifier<(ContractAddress, BlockNumber), VersionZeroWrapper<ClassHash>, SimpleTable>,
         events: TableIdentifier<(ContractAddress, TransactionIndex), NoVersionValueWrapper<NoValue>, CommonPrefix>,
+        events_by_first_key: TableIdentifier<(EventKey, TransactionIndex), NoVersionValueWrapper<NoValue>, CommonPrefix>,
         headers: TableIdentifier<BlockNumber, VersionZeroWrapper<StorageBlockHeader>, SimpleTable>,
         markers: TableIdentifier<MarkerKind, VersionZeroWrapper<BlockNumber>, SimpleTable>,
+        migration_checkpoints: TableIdentifier<String, NoVersionValueWrapper<MigrationCheckpoint>, SimpleTable>,
         nonces: TableIdentifier<(ContractAddress, BlockNumber), VersionZeroWrapper<Nonce>, CommonPrefix>,
+        pending_compactions: TableIdentifier<OffsetKind, NoVersionValueWrapper<NoValue>, SimpleTable>,
         file_offsets: TableIdentifier<OffsetKind, NoVersionValueWrapper<usize>, SimpleTable>,
         state_diffs: TableIdentifier<BlockNumber, VersionZeroWrapper<LocationInFile>, SimpleTable>,
+        // The events of transactions whose body was appended without events, so that their outputs
+        // aren't written again once the events arrive.
+        transaction_events: TableIdentifier<TransactionIndex, VersionZeroWrapper<Vec<Event>>, SimpleTable>,
         transaction_hash_to_idx: TableIdentifier<TransactionHash, NoVersionValueWrapper<TransactionIndex>, SimpleTable>,
         // TODO(dvir): consider not saving transaction hash and calculating it from the transaction on demand.
         transaction_metadata: TableIdentifier<TransactionIndex, VersionZeroWrapper<TransactionMetadata>, SimpleTable>,
@@ -620,6 +729,28 @@ pub enum StorageError {
          {block_number}."
     )]
     BlockSignatureForNonExistingBlock { block_number: BlockNumber, block_signature: BlockSignature },
+    #[error("Attempt to write the events of block {block_number} before its body.")]
+    EventsForNonExistingBody { block_number: BlockNumber },
+    #[error(
+        "Attempt to write the events of {num_event_lists} transactions for block {block_number}, \
+         which has {num_transactions} transactions."
+    )]
+    EventsTransactionsMismatch {
+        block_number: BlockNumber,
+        num_transactions: usize,
+        num_event_lists: usize,
+    },
+    #[error(
+        "The state history before block {pruned_state_marker} was pruned, so the state \
+         {state_number:?} is unavailable."
+    )]
+    StateHistoryPruned { state_number: StateNumber, pruned_state_marker: BlockNumber },
+    #[error(
+        "The storage was opened in migration dry-run mode with pending migrations: {plans:?}."
+    )]
+    MigrationDryRun { plans: Vec<MigrationPlan> },
+    #[error("The storage can't be opened for reading only: {reason}")]
+    ReadOnlyStorageUnavailable { reason: String },
 }
 
 /// A type alias that maps to std::result::Result<T, StorageError>.
@@ -634,6 +765,8 @@ pub struct StorageConfig {
     #[validate]
     pub mmap_file_config: MmapFileConfig,
     pub scope: StorageScope,
+    pub state_pruning_config: StatePruningConfig,
+    pub migration_config: MigrationConfig,
 }
 
 impl SerializeConfig for StorageConfig {
@@ -647,6 +780,12 @@ impl SerializeConfig for StorageConfig {
         dumped_config
             .extend(append_sub_config_name(self.mmap_file_config.dump(), "mmap_file_config"));
         dumped_config.extend(append_sub_config_name(self.db_config.dump(), "db_config"));
+        dumped_config.extend(append_sub_config_name(
+            self.state_pruning_config.dump(),
+            "state_pruning_config",
+        ));
+        dumped_config
+            .extend(append_sub_config_name(self.migration_config.dump(), "migration_config"));
         dumped_config
     }
 }
@@ -666,6 +805,7 @@ pub struct DbStats {
 // - CompiledClass <= Class <= State <= Header
 // - Body <= Header
 // - BaseLayerBlock <= Header
+// - PrunedState <= State
 // Event is currently unsupported.
 pub(crate) enum MarkerKind {
     Header,
@@ -676,6 +816,8 @@ pub(crate) enum MarkerKind {
     CompiledClass,
     BaseLayerBlock,
     ClassManagerBlock,
+    // The first state number whose state history wasn't pruned.
+    PrunedState,
 }
 
 pub(crate) type MarkersTable<'env> =
@@ -823,7 +965,7 @@ fn open_storage_files(
         table.get(&db_transaction, &OffsetKind::ThinStateDiff)?.unwrap_or_default();
     let (thin_state_diff_writer, thin_state_diff_reader) = open_file(
         mmap_file_config.clone(),
-        db_config.path().join("thin_state_diff.dat"),
+        db_config.path().join(OffsetKind::ThinStateDiff.file_name()),
         thin_state_diff_offset,
     )?;
 
@@ -831,19 +973,22 @@ fn open_storage_files(
         table.get(&db_transaction, &OffsetKind::ContractClass)?.unwrap_or_default();
     let (contract_class_writer, contract_class_reader) = open_file(
         mmap_file_config.clone(),
-        db_config.path().join("contract_class.dat"),
+        db_config.path().join(OffsetKind::ContractClass.file_name()),
         contract_class_offset,
     )?;
 
     let casm_offset = table.get(&db_transaction, &OffsetKind::Casm)?.unwrap_or_default();
-    let (casm_writer, casm_reader) =
-        open_file(mmap_file_config.clone(), db_config.path().join("casm.dat"), casm_offset)?;
+    let (casm_writer, casm_reader) = open_file(
+        mmap_file_config.clone(),
+        db_config.path().join(OffsetKind::Casm.file_name()),
+        casm_offset,
+    )?;
 
     let deprecated_contract_class_offset =
         table.get(&db_transaction, &OffsetKind::DeprecatedContractClass)?.unwrap_or_default();
     let (deprecated_contract_class_writer, deprecated_contract_class_reader) = open_file(
         mmap_file_config.clone(),
-        db_config.path().join("deprecated_contract_class.dat"),
+        db_config.path().join(OffsetKind::DeprecatedContractClass.file_name()),
         deprecated_contract_class_offset,
     )?;
 
@@ -851,14 +996,17 @@ fn open_storage_files(
         table.get(&db_transaction, &OffsetKind::TransactionOutput)?.unwrap_or_default();
     let (transaction_output_writer, transaction_output_reader) = open_file(
         mmap_file_config.clone(),
-        db_config.path().join("transaction_output.dat"),
+        db_config.path().join(OffsetKind::TransactionOutput.file_name()),
         transaction_output_offset,
     )?;
 
     let transaction_offset =
         table.get(&db_transaction, &OffsetKind::Transaction)?.unwrap_or_default();
-    let (transaction_writer, transaction_reader) =
-        open_file(mmap_file_config, db_config.path().join("transaction.dat"), transaction_offset)?;
+    let (transaction_writer, transaction_reader) = open_file(
+        mmap_file_config,
+        db_config.path().join(OffsetKind::Transaction.file_name()),
+        transaction_offset,
+    )?;
 
     Ok((
         FileHandlers {
@@ -880,6 +1028,53 @@ fn open_storage_files(
     ))
 }
 
+fn open_storage_files_read_only(
+    db_config: &DbConfig,
+    mmap_file_config: MmapFileConfig,
+    db_reader: &DbReader,
+    file_offsets_table: &TableIdentifier<OffsetKind, NoVersionValueWrapper<usize>, SimpleTable>,
+) -> StorageResult<FileHandlers<RO>> {
+    let db_transaction = db_reader.begin_ro_txn()?;
+    let table = db_transaction.open_table(file_offsets_table)?;
+    let offset = |kind| -> StorageResult<usize> {
+        Ok(table.get(&db_transaction, &kind)?.unwrap_or_default())
+    };
+    let path = |kind: OffsetKind| db_config.path().join(kind.file_name());
+
+    Ok(FileHandlers {
+        thin_state_diff: open_file_read_only(
+            mmap_file_config.clone(),
+            path(OffsetKind::ThinStateDiff),
+            offset(OffsetKind::ThinStateDiff)?,
+        )?,
+        contract_class: open_file_read_only(
+            mmap_file_config.clone(),
+            path(OffsetKind::ContractClass),
+            offset(OffsetKind::ContractClass)?,
+        )?,
+        casm: open_file_read_only(
+            mmap_file_config.clone(),
+            path(OffsetKind::Casm),
+            offset(OffsetKind::Casm)?,
+        )?,
+        deprecated_contract_class: open_file_read_only(
+            mmap_file_config.clone(),
+            path(OffsetKind::DeprecatedContractClass),
+            offset(OffsetKind::DeprecatedContractClass)?,
+        )?,
+        transaction_output: open_file_read_only(
+            mmap_file_config.clone(),
+            path(OffsetKind::TransactionOutput),
+            offset(OffsetKind::TransactionOutput)?,
+        )?,
+        transaction: open_file_read_only(
+            mmap_file_config,
+            path(OffsetKind::Transaction),
+            offset(OffsetKind::Transaction)?,
+        )?,
+    })
+}
+
 /// Represents a kind of mmap file.
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, PartialOrd, Ord)]
 pub enum OffsetKind {
@@ -897,6 +1092,30 @@ pub enum OffsetKind {
     Transaction,
 }
 
+impl OffsetKind {
+    /// All the kinds of mmap files.
+    pub const ALL: [OffsetKind; 6] = [
+        OffsetKind::ThinStateDiff,
+        OffsetKind::ContractClass,
+        OffsetKind::Casm,
+        OffsetKind::DeprecatedContractClass,
+        OffsetKind::TransactionOutput,
+        OffsetKind::Transaction,
+    ];
+
+    /// The name of the file, in the storage directory, that holds this kind of data.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            OffsetKind::ThinStateDiff => "thin_state_diff.dat",
+            OffsetKind::ContractClass => "contract_class.dat",
+            OffsetKind::Casm => "casm.dat",
+            OffsetKind::DeprecatedContractClass => "deprecated_contract_class.dat",
+            OffsetKind::TransactionOutput => "transaction_output.dat",
+            OffsetKind::Transaction => "transaction.dat",
+        }
+    }
+}
+
 /// A storage query. Used for benchmarking in the storage_benchmark binary.
 // TODO(dvir): add more queries (especially get casm).
 // TODO(dvir): consider move this, maybe to test_utils.
@@ -0,0 +1,282 @@
+//! Migrations of the storage between major versions.
+//!
+//! A change of a major storage version (see [`STORAGE_VERSION_STATE`] and
+//! [`STORAGE_VERSION_BLOCKS`]) means that the storage format is incompatible with the previous
+//! code. If a migration from the existing major version is registered, it runs when the storage is
+//! opened instead of requiring a re-sync. Migrations run in batches, each in its own RW
+//! transaction together with a checkpoint of its progress, so a migration that was interrupted
+//! resumes from the last committed batch.
+//!
+//! With [`MigrationConfig::dry_run`] set, the pending migrations aren't run. Instead, opening the
+//! storage fails with [`StorageError::MigrationDryRun`] that estimates their work.
+//!
+//! [`STORAGE_VERSION_STATE`]: crate::STORAGE_VERSION_STATE
+//! [`STORAGE_VERSION_BLOCKS`]: crate::STORAGE_VERSION_BLOCKS
+
+#[cfg(test)]
+#[path = "migrations_test.rs"]
+mod migrations_test;
+
+use std::collections::BTreeMap;
+
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::db::table_types::Table;
+use crate::db::{TransactionKind, RO, RW};
+use crate::version::{Version, VersionStorageReader, VERSION_BLOCKS_KEY, VERSION_STATE_KEY};
+use crate::{
+    StorageError,
+    StorageReader,
+    StorageResult,
+    StorageTxn,
+    StorageWriter,
+    STORAGE_VERSION_BLOCKS,
+    STORAGE_VERSION_STATE,
+};
+
+/// The configuration of the storage migrations.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct MigrationConfig {
+    /// The maximal number of items a migration processes in a single transaction.
+    pub batch_size: usize,
+    /// Whether to only estimate the pending migrations instead of running them.
+    pub dry_run: bool,
+}
+
+impl Default for MigrationConfig {
+    fn default() -> Self {
+        Self { batch_size: 10000, dry_run: false }
+    }
+}
+
+impl SerializeConfig for MigrationConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "batch_size",
+                &self.batch_size,
+                "The maximal number of items a storage migration processes in a single \
+                 transaction.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "dry_run",
+                &self.dry_run,
+                "If true, opening a storage that needs migrations fails with an estimate of the \
+                 migrations instead of running them.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+/// The part of the storage a version refers to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VersionKind {
+    /// The state, versioned by [`STORAGE_VERSION_STATE`](crate::STORAGE_VERSION_STATE).
+    State,
+    /// The blocks, versioned by [`STORAGE_VERSION_BLOCKS`](crate::STORAGE_VERSION_BLOCKS).
+    Blocks,
+}
+
+/// A migration that is pending on a storage.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MigrationPlan {
+    /// The name of the migration.
+    pub name: &'static str,
+    /// The part of the storage the migration applies to.
+    pub kind: VersionKind,
+    /// The version of the storage before the migration.
+    pub from_version: Version,
+    /// The version of the storage after the migration.
+    pub to_version: Version,
+    /// The estimated number of items the migration processes.
+    pub estimated_items: u64,
+}
+
+// The progress of a migration, saved with every batch so that the migration can resume from it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct MigrationCheckpoint {
+    pub(crate) migrated_items: u64,
+    // The serialized key to continue the migration from.
+    pub(crate) next_key: Vec<u8>,
+}
+
+// The result of migrating a batch.
+pub(crate) struct MigrationBatch {
+    pub(crate) migrated_items: u64,
+    // The serialized key of the next item to migrate, or None if the migration is done.
+    pub(crate) next_key: Option<Vec<u8>>,
+}
+
+// A migration of a part of the storage from a major version to the next one.
+pub(crate) trait Migration {
+    // A unique name of the migration, under which its progress is saved.
+    fn name(&self) -> &'static str;
+
+    fn kind(&self) -> VersionKind;
+
+    // The major version the migration upgrades. The storage is at version {source_major + 1, 0}
+    // after the migration.
+    fn source_major(&self) -> u32;
+
+    // An estimation of the number of items the migration processes, for progress reports.
+    fn estimate_items(&self, txn: &StorageTxn<'_, RO>) -> StorageResult<u64>;
+
+    // Migrates at most `max_items` items, starting from the item with the given serialized key or
+    // from the first item if it's None.
+    fn migrate_batch<'env>(
+        &self,
+        txn: StorageTxn<'env, RW>,
+        start_key: Option<&[u8]>,
+        max_items: usize,
+    ) -> StorageResult<(StorageTxn<'env, RW>, MigrationBatch)>;
+}
+
+// Runs the migrations that upgrade the major versions of the storage towards the versions of the
+// crate. Storage whose major version has no registered migration is left for the version
+// verification to reject.
+pub(crate) fn run_migrations(
+    reader: &StorageReader,
+    writer: &mut StorageWriter,
+    config: &MigrationConfig,
+    migrations: &[Box<dyn Migration>],
+) -> StorageResult<()> {
+    let pending_migrations = pending_migrations(reader, migrations)?;
+    if pending_migrations.is_empty() {
+        return Ok(());
+    }
+    if config.dry_run {
+        for (plan, _) in &pending_migrations {
+            info!(
+                "Pending storage migration {} of the {:?} version from {} to {}, estimated to \
+                 process {} items.",
+                plan.name, plan.kind, plan.from_version, plan.to_version, plan.estimated_items
+            );
+        }
+        return Err(StorageError::MigrationDryRun {
+            plans: pending_migrations.into_iter().map(|(plan, _)| plan).collect(),
+        });
+    }
+    for (plan, migration) in pending_migrations {
+        run_migration(writer, config.batch_size, &plan, migration)?;
+    }
+    Ok(())
+}
+
+// Returns the chain of migrations that upgrade the existing versions, in the order they run.
+fn pending_migrations<'a>(
+    reader: &StorageReader,
+    migrations: &'a [Box<dyn Migration>],
+) -> StorageResult<Vec<(MigrationPlan, &'a dyn Migration)>> {
+    let txn = reader.begin_ro_txn()?;
+    let mut pending_migrations = Vec::new();
+    for (kind, existing_version, crate_version) in [
+        (VersionKind::State, txn.get_state_version()?, STORAGE_VERSION_STATE),
+        (VersionKind::Blocks, txn.get_blocks_version()?, STORAGE_VERSION_BLOCKS),
+    ] {
+        // A new storage has nothing to migrate, and a state-only storage has no blocks version.
+        let Some(mut from_version) = existing_version else {
+            continue;
+        };
+        while from_version.major < crate_version.major {
+            let Some(migration) = migrations.iter().find(|migration| {
+                migration.kind() == kind && migration.source_major() == from_version.major
+            }) else {
+                break;
+            };
+            // The last migration lands on the version of the crate, whose minor may be newer.
+            let to_version = if from_version.major + 1 == crate_version.major {
+                crate_version.clone()
+            } else {
+                Version { major: from_version.major + 1, minor: 0 }
+            };
+            pending_migrations.push((
+                MigrationPlan {
+                    name: migration.name(),
+                    kind,
+                    from_version,
+                    to_version: to_version.clone(),
+                    estimated_items: migration.estimate_items(&txn)?,
+                },
+                migration.as_ref(),
+            ));
+            from_version = to_version;
+        }
+    }
+    Ok(pending_migrations)
+}
+
+fn run_migration(
+    writer: &mut StorageWriter,
+    batch_size: usize,
+    plan: &MigrationPlan,
+    migration: &dyn Migration,
+) -> StorageResult<()> {
+    info!(
+        "Running storage migration {} of the {:?} version from {} to {}.",
+        plan.name, plan.kind, plan.from_version, plan.to_version
+    );
+    loop {
+        let txn = writer.begin_rw_txn()?;
+        let checkpoint = txn.get_migration_checkpoint(plan.name)?;
+        let start_key = checkpoint.as_ref().map(|checkpoint| checkpoint.next_key.as_slice());
+        let (txn, batch) = migration.migrate_batch(txn, start_key, batch_size)?;
+        let migrated_items = checkpoint.map(|checkpoint| checkpoint.migrated_items).unwrap_or(0)
+            + batch.migrated_items;
+        let Some(next_key) = batch.next_key else {
+            // The version is updated in the same transaction as the last batch, so a migration is
+            // never run twice.
+            txn.delete_migration_checkpoint(plan.name)?
+                .set_migrated_version(plan.kind, &plan.to_version)?
+                .commit()?;
+            info!("Storage migration {} is done after {migrated_items} items.", plan.name);
+            return Ok(());
+        };
+        txn.set_migration_checkpoint(plan.name, &MigrationCheckpoint { migrated_items, next_key })?
+            .commit()?;
+        info!(
+            "Storage migration {}: migrated {migrated_items} out of about {} items.",
+            plan.name, plan.estimated_items
+        );
+    }
+}
+
+impl<Mode: TransactionKind> StorageTxn<'_, Mode> {
+    fn get_migration_checkpoint(&self, name: &str) -> StorageResult<Option<MigrationCheckpoint>> {
+        let checkpoints_table = self.open_table(&self.tables.migration_checkpoints)?;
+        Ok(checkpoints_table.get(&self.txn, &name.to_owned())?)
+    }
+}
+
+impl StorageTxn<'_, RW> {
+    fn set_migration_checkpoint(
+        self,
+        name: &str,
+        checkpoint: &MigrationCheckpoint,
+    ) -> StorageResult<Self> {
+        let checkpoints_table = self.open_table(&self.tables.migration_checkpoints)?;
+        checkpoints_table.upsert(&self.txn, &name.to_owned(), checkpoint)?;
+        Ok(self)
+    }
+
+    fn delete_migration_checkpoint(self, name: &str) -> StorageResult<Self> {
+        let checkpoints_table = self.open_table(&self.tables.migration_checkpoints)?;
+        checkpoints_table.delete(&self.txn, &name.to_owned())?;
+        Ok(self)
+    }
+
+    // Unlike the version writer, allows changing the major version.
+    fn set_migrated_version(self, kind: VersionKind, version: &Version) -> StorageResult<Self> {
+        let version_table = self.open_table(&self.tables.storage_version)?;
+        let key = match kind {
+            VersionKind::State => VERSION_STATE_KEY,
+            VersionKind::Blocks => VERSION_BLOCKS_KEY,
+        };
+        version_table.upsert(&self.txn, &key.to_owned(), version)?;
+        Ok(self)
+    }
+}
@@ -0,0 +1,379 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use assert_matches::assert_matches;
+use papyrus_test_utils::get_test_block;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockHeader, BlockHeaderWithoutHash, BlockNumber};
+use starknet_api::felt;
+use starknet_api::transaction::{
+    EventIndexInTransactionOutput,
+    EventKey,
+    TransactionOffsetInBlock,
+};
+
+use crate::body::events::{EventIndex, EventsReader};
+use crate::body::{BodyStorageWriter, TransactionIndex};
+use crate::db::serialization::{StorageSerdeEx, VersionZeroWrapper};
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::db::{RO, RW};
+use crate::deprecated::migrations::{registered_migrations, StorageBlockHeaderV5};
+use crate::header::{HeaderStorageReader, HeaderStorageWriter};
+use crate::migrations::{
+    run_migrations,
+    Migration,
+    MigrationBatch,
+    MigrationConfig,
+    MigrationPlan,
+    VersionKind,
+};
+use crate::test_utils::{get_test_storage, get_test_storage_with_config_by_scope};
+use crate::version::{Version, VersionStorageReader};
+use crate::{
+    open_storage,
+    StorageError,
+    StorageReader,
+    StorageResult,
+    StorageScope,
+    StorageTxn,
+    StorageWriter,
+    STORAGE_VERSION_BLOCKS,
+    STORAGE_VERSION_STATE,
+};
+
+const NUM_BLOCKS: u64 = 5;
+const OLD_BLOCKS_VERSION: Version = Version { major: STORAGE_VERSION_BLOCKS.major - 1, minor: 3 };
+
+// Increments the number of transactions of each header, so that a header that was migrated twice
+// is detectable.
+#[derive(Default)]
+struct IncrementTransactionCounts {
+    failing_batch: Option<usize>,
+    num_batches: AtomicUsize,
+}
+
+impl Migration for IncrementTransactionCounts {
+    fn name(&self) -> &'static str {
+        "increment_transaction_counts"
+    }
+
+    fn kind(&self) -> VersionKind {
+        VersionKind::Blocks
+    }
+
+    fn source_major(&self) -> u32 {
+        OLD_BLOCKS_VERSION.major
+    }
+
+    fn estimate_items(&self, txn: &StorageTxn<'_, RO>) -> StorageResult<u64> {
+        Ok(txn.get_header_marker()?.0)
+    }
+
+    fn migrate_batch<'env>(
+        &self,
+        txn: StorageTxn<'env, RW>,
+        start_key: Option<&[u8]>,
+        max_items: usize,
+    ) -> StorageResult<(StorageTxn<'env, RW>, MigrationBatch)> {
+        if Some(self.num_batches.fetch_add(1, Ordering::SeqCst)) == self.failing_batch {
+            return Err(StorageError::DBInconsistency { msg: "Simulated crash.".to_owned() });
+        }
+        let mut block_number = start_key
+            .map(|mut key| BlockNumber::deserialize(&mut key).unwrap())
+            .unwrap_or_default();
+        let mut migrated_items = 0;
+        let headers_table = txn.open_table(&txn.tables.headers)?;
+        while migrated_items < u64::try_from(max_items).unwrap() {
+            let Some(mut header) = headers_table.get(&txn.txn, &block_number)? else {
+                return Ok((txn, MigrationBatch { migrated_items, next_key: None }));
+            };
+            header.n_transactions += 1;
+            headers_table.upsert(&txn.txn, &block_number, &header)?;
+            block_number = block_number.unchecked_next();
+            migrated_items += 1;
+        }
+        let next_key = Some(block_number.serialize()?);
+        Ok((txn, MigrationBatch { migrated_items, next_key }))
+    }
+}
+
+// Returns a storage with headers in the old blocks version.
+fn old_storage() -> ((StorageReader, StorageWriter), tempfile::TempDir) {
+    let ((reader, mut writer), temp_dir) = get_test_storage();
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for i in 0..NUM_BLOCKS {
+        let header = BlockHeader {
+            block_hash: BlockHash(i.into()),
+            block_header_without_hash: BlockHeaderWithoutHash {
+                block_number: BlockNumber(i),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        txn = txn.append_header(BlockNumber(i), &header).unwrap();
+    }
+    txn.set_migrated_version(VersionKind::Blocks, &OLD_BLOCKS_VERSION).unwrap().commit().unwrap();
+    ((reader, writer), temp_dir)
+}
+
+fn transaction_counts(reader: &StorageReader) -> Vec<usize> {
+    let txn = reader.begin_ro_txn().unwrap();
+    (0..NUM_BLOCKS)
+        .map(|i| txn.get_block_header(BlockNumber(i)).unwrap().unwrap().n_transactions)
+        .collect()
+}
+
+#[test]
+fn migration_runs_in_batches() {
+    let ((reader, mut writer), _temp_dir) = old_storage();
+    let config = MigrationConfig { batch_size: 2, dry_run: false };
+    let migrations: Vec<Box<dyn Migration>> = vec![Box::<IncrementTransactionCounts>::default()];
+    run_migrations(&reader, &mut writer, &config, &migrations).unwrap();
+
+    assert_eq!(transaction_counts(&reader), vec![1; 5]);
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_blocks_version().unwrap(), Some(STORAGE_VERSION_BLOCKS));
+    assert_eq!(txn.get_migration_checkpoint("increment_transaction_counts").unwrap(), None);
+    drop(txn);
+
+    // A migrated storage has nothing to migrate.
+    run_migrations(&reader, &mut writer, &config, &migrations).unwrap();
+    assert_eq!(transaction_counts(&reader), vec![1; 5]);
+}
+
+#[test]
+fn interrupted_migration_resumes() {
+    let ((reader, mut writer), _temp_dir) = old_storage();
+    let config = MigrationConfig { batch_size: 2, dry_run: false };
+    let crashing_migrations: Vec<Box<dyn Migration>> =
+        vec![Box::new(IncrementTransactionCounts { failing_batch: Some(1), ..Default::default() })];
+    assert_matches!(
+        run_migrations(&reader, &mut writer, &config, &crashing_migrations),
+        Err(StorageError::DBInconsistency { .. })
+    );
+    // Only the first batch was committed.
+    assert_eq!(transaction_counts(&reader), vec![1, 1, 0, 0, 0]);
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_blocks_version().unwrap(),
+        Some(OLD_BLOCKS_VERSION)
+    );
+
+    let migrations: Vec<Box<dyn Migration>> = vec![Box::<IncrementTransactionCounts>::default()];
+    run_migrations(&reader, &mut writer, &config, &migrations).unwrap();
+    assert_eq!(transaction_counts(&reader), vec![1; 5]);
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_blocks_version().unwrap(),
+        Some(STORAGE_VERSION_BLOCKS)
+    );
+}
+
+#[test]
+fn dry_run_estimates_without_migrating() {
+    let ((reader, mut writer), _temp_dir) = old_storage();
+    let config = MigrationConfig { batch_size: 2, dry_run: true };
+    let migrations: Vec<Box<dyn Migration>> = vec![Box::<IncrementTransactionCounts>::default()];
+    let Err(StorageError::MigrationDryRun { plans }) =
+        run_migrations(&reader, &mut writer, &config, &migrations)
+    else {
+        panic!("Expected a dry-run error.");
+    };
+    assert_eq!(
+        plans,
+        vec![MigrationPlan {
+            name: "increment_transaction_counts",
+            kind: VersionKind::Blocks,
+            from_version: OLD_BLOCKS_VERSION,
+            to_version: STORAGE_VERSION_BLOCKS,
+            estimated_items: NUM_BLOCKS,
+        }]
+    );
+    assert_eq!(transaction_counts(&reader), vec![0; 5]);
+}
+
+#[test]
+fn storage_without_a_migration_is_untouched() {
+    let ((reader, mut writer), _temp_dir) = old_storage();
+    let config = MigrationConfig::default();
+    run_migrations(&reader, &mut writer, &config, &[]).unwrap();
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_blocks_version().unwrap(),
+        Some(OLD_BLOCKS_VERSION)
+    );
+}
+
+#[test]
+fn events_by_first_key_index_is_built() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let key = EventKey(felt!("0x1"));
+    let block = get_test_block(3, Some(2), None, Some(vec![vec![key.clone()]]));
+    let block_number = block.header.block_header_without_hash.block_number;
+    let txn = writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body)
+        .unwrap();
+    // Simulate a storage from before the index was added.
+    let events_by_first_key_table = txn.open_table(&txn.tables.events_by_first_key).unwrap();
+    let mut cursor = events_by_first_key_table.cursor(&txn.txn).unwrap();
+    let index_entries = std::iter::from_fn(|| cursor.next().unwrap()).collect::<Vec<_>>();
+    drop(cursor);
+    assert_eq!(index_entries.len(), 3);
+    for (key, _) in &index_entries {
+        events_by_first_key_table.delete(&txn.txn, key).unwrap();
+    }
+    let old_version = Version { major: 6, minor: 0 };
+    txn.set_migrated_version(VersionKind::Blocks, &old_version).unwrap().commit().unwrap();
+
+    let config = MigrationConfig { batch_size: 2, dry_run: false };
+    run_migrations(&reader, &mut writer, &config, &registered_migrations()).unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_blocks_version().unwrap(), Some(STORAGE_VERSION_BLOCKS));
+    let event_index = EventIndex(
+        TransactionIndex(block_number, TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(0),
+    );
+    let events = txn.iter_events_by_first_keys(vec![key], event_index, block_number).unwrap();
+    assert_eq!(events.count(), 6);
+}
+
+#[test]
+fn headers_get_fee_market_fields() {
+    let ((reader, mut writer), _temp_dir) = old_storage();
+    // Rewrite the headers in the format of blocks version 5.
+    let txn = writer.begin_rw_txn().unwrap();
+    let old_headers_table_id =
+        txn.tables.headers.with_value_type::<VersionZeroWrapper<StorageBlockHeaderV5>>();
+    let old_headers_table = txn.open_table(&old_headers_table_id).unwrap();
+    for i in 0..NUM_BLOCKS {
+        let old_header = StorageBlockHeaderV5 {
+            block_hash: BlockHash(i.into()),
+            block_number: BlockNumber(i),
+            n_transactions: 2,
+            ..Default::default()
+        };
+        old_headers_table.upsert(&txn.txn, &BlockNumber(i), &old_header).unwrap();
+    }
+    let old_version = Version { major: 5, minor: 0 };
+    txn.set_migrated_version(VersionKind::Blocks, &old_version).unwrap().commit().unwrap();
+
+    let config = MigrationConfig { batch_size: 2, dry_run: false };
+    run_migrations(&reader, &mut writer, &config, &registered_migrations()).unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_blocks_version().unwrap(), Some(STORAGE_VERSION_BLOCKS));
+    for i in 0..NUM_BLOCKS {
+        let header = txn.get_block_header(BlockNumber(i)).unwrap().unwrap();
+        assert_eq!(header.block_hash, BlockHash(i.into()));
+        assert_eq!(header.n_transactions, 2);
+        assert_eq!(header.block_header_without_hash.l2_gas_consumed, Default::default());
+        assert_eq!(header.block_header_without_hash.next_l2_gas_price, Default::default());
+    }
+}
+
+#[test]
+fn storage_at_blocks_version_5_is_migrated_when_opened() {
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let key = EventKey(felt!("0x1"));
+    let block = get_test_block(3, Some(2), None, Some(vec![vec![key.clone()]]));
+    let block_number = block.header.block_header_without_hash.block_number;
+    let txn = writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body)
+        .unwrap();
+    // Write the block as it was stored at blocks version 5: the header in its old format and the
+    // events without their index by first keys.
+    let old_headers_table_id =
+        txn.tables.headers.with_value_type::<VersionZeroWrapper<StorageBlockHeaderV5>>();
+    let old_headers_table = txn.open_table(&old_headers_table_id).unwrap();
+    let old_header = StorageBlockHeaderV5 {
+        block_hash: block.header.block_hash,
+        parent_hash: block.header.block_header_without_hash.parent_hash,
+        block_number,
+        n_transactions: block.header.n_transactions,
+        ..Default::default()
+    };
+    old_headers_table.upsert(&txn.txn, &block_number, &old_header).unwrap();
+    let events_by_first_key_table = txn.open_table(&txn.tables.events_by_first_key).unwrap();
+    let mut cursor = events_by_first_key_table.cursor(&txn.txn).unwrap();
+    let index_entries = std::iter::from_fn(|| cursor.next().unwrap()).collect::<Vec<_>>();
+    drop(cursor);
+    for (key, _) in &index_entries {
+        events_by_first_key_table.delete(&txn.txn, key).unwrap();
+    }
+    let old_version = Version { major: 5, minor: 0 };
+    txn.set_migrated_version(VersionKind::Blocks, &old_version)
+        .unwrap()
+        .set_migrated_version(VersionKind::State, &old_version)
+        .unwrap()
+        .commit()
+        .unwrap();
+    drop(reader);
+    drop(writer);
+
+    let (reader, _writer) = open_storage(config).unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_version().unwrap(), Some(STORAGE_VERSION_STATE));
+    assert_eq!(txn.get_blocks_version().unwrap(), Some(STORAGE_VERSION_BLOCKS));
+    let header = txn.get_block_header(block_number).unwrap().unwrap();
+    assert_eq!(header.block_hash, block.header.block_hash);
+    assert_eq!(header.n_transactions, block.header.n_transactions);
+    let event_index = EventIndex(
+        TransactionIndex(block_number, TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(0),
+    );
+    let events = txn.iter_events_by_first_keys(vec![key], event_index, block_number).unwrap();
+    assert_eq!(events.count(), 6);
+}
+
+#[test]
+fn state_only_storage_at_state_version_5_is_migrated_when_opened() {
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::StateOnly);
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for i in 0..NUM_BLOCKS {
+        let header = BlockHeader {
+            block_hash: BlockHash(i.into()),
+            block_header_without_hash: BlockHeaderWithoutHash {
+                block_number: BlockNumber(i),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        txn = txn.append_header(BlockNumber(i), &header).unwrap();
+    }
+    // Rewrite the headers in the format of state version 5.
+    let old_headers_table_id =
+        txn.tables.headers.with_value_type::<VersionZeroWrapper<StorageBlockHeaderV5>>();
+    let old_headers_table = txn.open_table(&old_headers_table_id).unwrap();
+    for i in 0..NUM_BLOCKS {
+        let old_header = StorageBlockHeaderV5 {
+            block_hash: BlockHash(i.into()),
+            block_number: BlockNumber(i),
+            n_transactions: 2,
+            ..Default::default()
+        };
+        old_headers_table.upsert(&txn.txn, &BlockNumber(i), &old_header).unwrap();
+    }
+    let old_version = Version { major: 5, minor: 0 };
+    txn.set_migrated_version(VersionKind::State, &old_version).unwrap().commit().unwrap();
+    drop(reader);
+    drop(writer);
+
+    let (reader, _writer) = open_storage(config).unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_version().unwrap(), Some(STORAGE_VERSION_STATE));
+    assert_eq!(txn.get_blocks_version().unwrap(), None);
+    for i in 0..NUM_BLOCKS {
+        let header = txn.get_block_header(BlockNumber(i)).unwrap().unwrap();
+        assert_eq!(header.block_hash, BlockHash(i.into()));
+        assert_eq!(header.n_transactions, 2);
+        assert_eq!(header.block_header_without_hash.l2_gas_consumed, Default::default());
+        assert_eq!(header.block_header_without_hash.next_l2_gas_price, Default::default());
+    }
+}
@@ -10,12 +10,13 @@ mod mmap_file_test;
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
 use std::marker::PhantomData;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::result;
 use std::sync::{Arc, Mutex};
 
-use memmap2::{MmapMut, MmapOptions};
+use memmap2::{MmapOptions, MmapRaw};
 use papyrus_config::dumping::{ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 #[cfg(test)]
@@ -139,7 +140,7 @@ struct MMapFile<V: ValueSerde> {
     config: MmapFileConfig,
     file: File,
     size: usize,
-    mmap: MmapMut,
+    mmap: MmapRaw,
     offset: usize,
     should_flush: bool,
     _value_type: PhantomData<V>,
@@ -173,7 +174,7 @@ pub(crate) fn open_file<V: ValueSerde>(
 ) -> MmapFileResult<(FileHandler<V, RW>, FileHandler<V, RO>)> {
     let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
     let size = file.metadata()?.len();
-    let mmap = unsafe { MmapOptions::new().len(config.max_size).map_mut(&file)? };
+    let mmap = MmapOptions::new().len(config.max_size).map_raw(&file)?;
     let mmap_ptr = mmap.as_ptr();
     let mmap_file = MMapFile {
         config,
@@ -199,6 +200,34 @@ pub(crate) fn open_file<V: ValueSerde>(
     Ok((write_file_handler, read_file_handler))
 }
 
+/// Open a memory mapped file for reading only. The file may be written to by another process
+/// meanwhile, but objects beyond the given offset may not have been written yet.
+#[instrument(level = "debug", err)]
+pub(crate) fn open_file_read_only<V: ValueSerde>(
+    config: MmapFileConfig,
+    path: PathBuf,
+    offset: usize,
+) -> MmapFileResult<FileHandler<V, RO>> {
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mmap = MmapOptions::new().len(config.max_size).map_raw_read_only(&file)?;
+    let mmap_ptr = mmap.as_ptr();
+    let mmap_file = MMapFile {
+        config,
+        file,
+        mmap,
+        size: size.try_into().expect("size should fit in usize"),
+        offset,
+        should_flush: false,
+        _value_type: PhantomData {},
+    };
+    Ok(FileHandler {
+        memory_ptr: mmap_ptr,
+        mmap_file: Arc::new(Mutex::new(mmap_file)),
+        _mode: PhantomData,
+    })
+}
+
 /// A wrapper around `MMapFile` that provides both write and read interfaces.
 #[derive(Clone, Debug)]
 pub(crate) struct FileHandler<V: ValueSerde, Mode: TransactionKind> {
@@ -233,8 +262,15 @@ impl<V: ValueSerde + Debug> Writer<V> for FileHandler<V, RW> {
             let mut mmap_file = self.mmap_file.lock().expect("Lock should not be poisoned");
             offset = mmap_file.offset;
             trace!("Inserting object at offset: {}", offset);
-            let mmap_slice = &mut mmap_file.mmap[offset..];
-            mmap_slice[..len].copy_from_slice(&serialized);
+            // SAFETY: the file was grown to have room for an object of the maximal size at the
+            // offset, and the memory map is larger than the file.
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    serialized.as_ptr(),
+                    mmap_file.mmap.as_mut_ptr().add(offset),
+                    len,
+                );
+            }
             mmap_file
                 .mmap
                 .flush_async_range(offset, len)
@@ -279,6 +315,51 @@ pub struct MMapFileStats {
     offset: usize,
 }
 
+/// Copies objects of a file to a new file without the space between them, e.g. of reverted
+/// objects.
+pub(crate) struct FileCompactor {
+    source: File,
+    target: BufWriter<File>,
+    offset: usize,
+}
+
+impl FileCompactor {
+    /// Creates the target file, overriding it if it exists.
+    pub(crate) fn new(source_path: &Path, target_path: &Path) -> MmapFileResult<Self> {
+        Ok(Self {
+            source: File::open(source_path)?,
+            target: BufWriter::new(File::create(target_path)?),
+            offset: 0,
+        })
+    }
+
+    /// Copies the object at the given location of the source file to the end of the target file
+    /// and returns its location in the target file.
+    pub(crate) fn copy(&mut self, location: LocationInFile) -> MmapFileResult<LocationInFile> {
+        let mut bytes = vec![0; location.len];
+        self.source.seek(SeekFrom::Start(location.offset.try_into()?))?;
+        self.source.read_exact(&mut bytes)?;
+        self.target.write_all(&bytes)?;
+        let new_location = LocationInFile { offset: self.offset, len: location.len };
+        self.offset = new_location.next_offset();
+        Ok(new_location)
+    }
+
+    /// Syncs the target file to the disk and returns its size.
+    pub(crate) fn finish(self) -> MmapFileResult<usize> {
+        let target = self.target.into_inner().map_err(|err| err.into_error())?;
+        target.sync_all()?;
+        Ok(self.offset)
+    }
+}
+
+impl MMapFileStats {
+    /// Returns the current size of the file.
+    pub(crate) fn size(&self) -> usize {
+        self.size
+    }
+}
+
 impl<V: ValueSerde, Mode: TransactionKind> FileHandler<V, Mode> {
     pub fn stats(&self) -> MMapFileStats {
         let mmap_file = self.mmap_file.lock().expect("Lock should not be poisoned");
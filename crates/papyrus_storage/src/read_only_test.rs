@@ -0,0 +1,104 @@
+use std::process::Command;
+
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockHeader, BlockHeaderWithoutHash, BlockNumber};
+use starknet_api::state::ThinStateDiff;
+
+use crate::db::DbError;
+use crate::header::{HeaderStorageReader, HeaderStorageWriter};
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::{get_test_config, get_test_config_with_path};
+use crate::version::StorageVersionError;
+use crate::{
+    open_storage,
+    open_storage_read_only,
+    StorageConfig,
+    StorageError,
+    StorageScope,
+    StorageWriter,
+};
+
+const WRITER_PATH_ENV: &str = "READ_ONLY_TEST_WRITER_PATH";
+
+fn append_block(writer: &mut StorageWriter, block_number: BlockNumber) {
+    let header = BlockHeader {
+        block_hash: BlockHash(block_number.0.into()),
+        block_header_without_hash: BlockHeaderWithoutHash { block_number, ..Default::default() },
+        ..Default::default()
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &header)
+        .unwrap()
+        .append_state_diff(block_number, ThinStateDiff::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+}
+
+// Appends a block to the storage whose path is in the environment. Run in a separate process by
+// append_block_in_writer_process, since a process can't open a storage twice.
+#[test]
+#[ignore]
+fn writer_process() {
+    let Ok(path) = std::env::var(WRITER_PATH_ENV) else {
+        return;
+    };
+    let (reader, mut writer) = open_storage(get_test_config_with_path(None, path.into())).unwrap();
+    let block_number = reader.begin_ro_txn().unwrap().get_header_marker().unwrap();
+    append_block(&mut writer, block_number);
+}
+
+fn append_block_in_writer_process(config: &StorageConfig) {
+    let status = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "read_only_test::writer_process", "--ignored", "--quiet"])
+        .env(WRITER_PATH_ENV, &config.db_config.path_prefix)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn read_only_storage_reads_concurrent_writes() {
+    let (config, _temp_dir) = get_test_config(None);
+    let (reader, mut writer) = open_storage(config.clone()).unwrap();
+    append_block(&mut writer, BlockNumber(0));
+    drop((reader, writer));
+
+    let read_only_reader = open_storage_read_only(config.clone()).unwrap();
+    let txn = read_only_reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_state_diff(BlockNumber(0)).unwrap(), Some(ThinStateDiff::default()));
+
+    append_block_in_writer_process(&config);
+    // An open transaction keeps seeing its snapshot, and a new one sees the new block.
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(1));
+    drop(txn);
+    let txn = read_only_reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(2));
+    assert_eq!(txn.get_state_diff(BlockNumber(1)).unwrap(), Some(ThinStateDiff::default()));
+}
+
+#[test]
+fn read_only_storage_must_exist() {
+    let (config, _temp_dir) = get_test_config(None);
+    assert_matches!(
+        open_storage_read_only(config).err(),
+        Some(StorageError::InnerError(DbError::FileDoesNotExist(_)))
+    );
+}
+
+#[test]
+fn read_only_storage_scope_must_match() {
+    let (mut config, _temp_dir) = get_test_config(Some(StorageScope::StateOnly));
+    drop(open_storage(config.clone()).unwrap());
+    config.scope = StorageScope::FullArchive;
+    assert_matches!(
+        open_storage_read_only(config).err(),
+        Some(StorageError::StorageVersionInconsistency(
+            StorageVersionError::InconsistentStorageScope
+        ))
+    );
+}
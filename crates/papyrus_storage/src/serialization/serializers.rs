@@ -138,6 +138,7 @@ use crate::compression_utils::{
 use crate::db::serialization::{StorageSerde, StorageSerdeError};
 use crate::db::table_types::NoValue;
 use crate::header::StorageBlockHeader;
+use crate::migrations::MigrationCheckpoint;
 use crate::mmap_file::LocationInFile;
 #[cfg(test)]
 use crate::serialization::serializers_test::{create_storage_serde_test, StorageSerdeTest};
@@ -174,6 +175,8 @@ auto_storage_serde! {
         pub state_diff_length: Option<usize>,
         pub n_transactions: usize,
         pub n_events: usize,
+        pub l2_gas_consumed: GasAmount,
+        pub next_l2_gas_price: GasPrice,
     }
     pub struct BlockSignature(pub Signature);
     pub enum BlockStatus {
@@ -326,6 +329,7 @@ auto_storage_serde! {
         CompiledClass = 5,
         BaseLayerBlock = 6,
         ClassManagerBlock = 7,
+        PrunedState = 8,
     }
     pub struct MessageToL1 {
         pub to_address: EthAddress,
@@ -461,6 +465,10 @@ auto_storage_serde! {
         pub major: u32,
         pub minor: u32,
     }
+    pub struct MigrationCheckpoint {
+        pub migrated_items: u64,
+        pub next_key: Vec<u8>,
+    }
 
     pub struct CasmContractEntryPoints {
         pub external: Vec<CasmContractEntryPoint>,
@@ -512,6 +520,7 @@ auto_storage_serde! {
     (ContractAddress, Nonce);
     (ContractAddress, StorageKey);
     (ContractAddress, TransactionIndex);
+    (EventKey, TransactionIndex);
     ((ContractAddress, StorageKey), BlockNumber);
     (usize, Vec<Hint>);
     (usize, Vec<String>);
@@ -0,0 +1,289 @@
+//! Export and import of consistent storage snapshots.
+//!
+//! A snapshot is a directory that holds a point-in-time copy of the storage files and a manifest
+//! that describes them:
+//! ```text
+//! <snapshot dir>/manifest.json
+//! <snapshot dir>/<chain id>/mdbx.dat
+//! <snapshot dir>/<chain id>/<mmap files>
+//! ```
+//! The database is copied under a single read transaction, and each mmap file is copied up to the
+//! offset recorded in that copy, so the snapshot is consistent even when exported from a storage
+//! that is being written to. The snapshot can be imported by copying it into an empty
+//! [`path_prefix`](crate::db::DbConfig::path_prefix) and opening the storage there.
+
+#[cfg(test)]
+#[path = "snapshot_test.rs"]
+mod snapshot_test;
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ChainId;
+use tracing::info;
+
+use crate::db::table_types::Table;
+use crate::db::{open_env, DbConfig, DbError};
+use crate::version::{Version, VERSION_BLOCKS_KEY, VERSION_STATE_KEY};
+use crate::{
+    MarkerKind,
+    OffsetKind,
+    StorageError,
+    StorageReader,
+    STORAGE_VERSION_BLOCKS,
+    STORAGE_VERSION_STATE,
+};
+
+/// The name of the manifest file in a snapshot directory.
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+const DB_FILE_NAME: &str = "mdbx.dat";
+const DB_LOCK_FILE_NAME: &str = "mdbx.lck";
+
+/// Describes the content of a storage snapshot.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SnapshotManifest {
+    /// The chain of the storage.
+    pub chain_id: ChainId,
+    /// The first block whose header isn't in the snapshot.
+    pub header_marker: BlockNumber,
+    /// The first block whose body isn't in the snapshot.
+    pub body_marker: BlockNumber,
+    /// The first block whose state diff isn't in the snapshot.
+    pub state_marker: BlockNumber,
+    /// The storage version of the state.
+    pub state_version: Version,
+    /// The storage version of the blocks. Missing for storage that doesn't hold the blocks.
+    pub blocks_version: Option<Version>,
+    /// The files of the snapshot, by their name in the storage directory.
+    pub files: BTreeMap<String, SnapshotFile>,
+}
+
+/// A file in a storage snapshot.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SnapshotFile {
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// The hex encoded SHA-256 digest of the file.
+    pub sha256: String,
+}
+
+#[allow(missing_docs)]
+/// Errors that may occur while exporting or importing a snapshot.
+#[derive(thiserror::Error, Debug)]
+pub enum SnapshotError {
+    #[error("The directory {0} is not empty.")]
+    NonEmptyDirectory(PathBuf),
+    #[error(
+        "The snapshot is of chain {snapshot_chain_id}, but the storage is configured for chain \
+         {storage_chain_id}."
+    )]
+    ChainIdMismatch { snapshot_chain_id: ChainId, storage_chain_id: ChainId },
+    #[error("The snapshot file {file_name} doesn't match its size or checksum in the manifest.")]
+    FileMismatch { file_name: String },
+    #[error("The snapshot chain id {0} can't name a directory in the snapshot.")]
+    InvalidChainId(ChainId),
+    #[error("The snapshot manifest lists {file_name}, which isn't a storage file.")]
+    UnexpectedFile { file_name: String },
+    #[error("The snapshot manifest doesn't list the storage file {file_name}.")]
+    MissingFile { file_name: String },
+    #[error(
+        "Snapshot storage version {snapshot_version} is incompatible with the crate's storage \
+         version {crate_version}."
+    )]
+    IncompatibleVersion { snapshot_version: Version, crate_version: Version },
+    #[error(transparent)]
+    Db(#[from] DbError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    IO(#[from] io::Error),
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+}
+
+/// A result of a snapshot operation.
+pub type SnapshotResult<V> = Result<V, SnapshotError>;
+
+/// Exports a consistent snapshot of the storage into `target_dir`, which must be empty or
+/// nonexistent. `db_config` is the configuration the storage was opened with. The storage may be
+/// written to during the export.
+pub fn export_snapshot(
+    reader: &StorageReader,
+    db_config: &DbConfig,
+    target_dir: &Path,
+) -> SnapshotResult<SnapshotManifest> {
+    verify_empty_dir(target_dir)?;
+    let snapshot_db_config = DbConfig {
+        path_prefix: target_dir.to_path_buf(),
+        enforce_file_exists: true,
+        ..db_config.clone()
+    };
+    let snapshot_path = snapshot_db_config.path();
+    fs::create_dir_all(&snapshot_path)?;
+
+    info!("Copying the database to {}.", snapshot_path.display());
+    reader.db_reader.copy_to(&snapshot_path.join(DB_FILE_NAME))?;
+
+    // The markers and the offsets are read from the copy, so they match its content.
+    let (header_marker, body_marker, state_marker, state_version, blocks_version, offsets) = {
+        let (snapshot_db_reader, _snapshot_db_writer) = open_env(&snapshot_db_config)?;
+        let txn = snapshot_db_reader.begin_ro_txn()?;
+        let markers_table = txn.open_table(&reader.tables.markers)?;
+        let file_offsets_table = txn.open_table(&reader.tables.file_offsets)?;
+        let storage_version_table = txn.open_table(&reader.tables.storage_version)?;
+        let state_version = storage_version_table
+            .get(&txn, &VERSION_STATE_KEY.to_string())?
+            .ok_or_else(|| StorageError::DBInconsistency {
+                msg: "The storage has no state version.".to_owned(),
+            })?;
+        let mut offsets = Vec::new();
+        for offset_kind in OffsetKind::ALL {
+            let offset = file_offsets_table.get(&txn, &offset_kind)?.unwrap_or_default();
+            offsets.push((offset_kind, u64::try_from(offset).expect("usize should fit in u64")));
+        }
+        (
+            markers_table.get(&txn, &MarkerKind::Header)?.unwrap_or_default(),
+            markers_table.get(&txn, &MarkerKind::Body)?.unwrap_or_default(),
+            markers_table.get(&txn, &MarkerKind::State)?.unwrap_or_default(),
+            state_version,
+            storage_version_table.get(&txn, &VERSION_BLOCKS_KEY.to_string())?,
+            offsets,
+        )
+    };
+    // Opening the copy created a lock file that isn't part of the snapshot.
+    fs::remove_file(snapshot_path.join(DB_LOCK_FILE_NAME))?;
+
+    for (offset_kind, offset) in offsets {
+        info!("Copying {}.", offset_kind.file_name());
+        let source = File::open(db_config.path().join(offset_kind.file_name()))?;
+        let mut target = File::create(snapshot_path.join(offset_kind.file_name()))?;
+        io::copy(&mut source.take(offset), &mut target)?;
+    }
+
+    let mut files = BTreeMap::new();
+    for file_name in snapshot_file_names() {
+        files.insert(file_name.to_owned(), describe_file(&snapshot_path.join(file_name))?);
+    }
+    let manifest = SnapshotManifest {
+        chain_id: db_config.chain_id.clone(),
+        header_marker,
+        body_marker,
+        state_marker,
+        state_version,
+        blocks_version,
+        files,
+    };
+    fs::write(target_dir.join(MANIFEST_FILE_NAME), serde_json::to_vec_pretty(&manifest)?)?;
+    info!("Exported a snapshot up to block {header_marker} to {}.", target_dir.display());
+    Ok(manifest)
+}
+
+/// Checks the files of the snapshot in `snapshot_dir` against its manifest, and that its storage
+/// version can be opened by this crate. The manifest must list exactly the storage files, under a
+/// chain id that names a directory in the snapshot. Returns the manifest.
+pub fn verify_snapshot(snapshot_dir: &Path) -> SnapshotResult<SnapshotManifest> {
+    let manifest: SnapshotManifest =
+        serde_json::from_slice(&fs::read(snapshot_dir.join(MANIFEST_FILE_NAME))?)?;
+    verify_chain_id(&manifest.chain_id)?;
+    if let Some(file_name) = manifest
+        .files
+        .keys()
+        .find(|file_name| !snapshot_file_names().any(|name| name == *file_name))
+    {
+        return Err(SnapshotError::UnexpectedFile { file_name: file_name.clone() });
+    }
+    if let Some(file_name) =
+        snapshot_file_names().find(|file_name| !manifest.files.contains_key(*file_name))
+    {
+        return Err(SnapshotError::MissingFile { file_name: file_name.to_owned() });
+    }
+    verify_version(&manifest.state_version, &STORAGE_VERSION_STATE)?;
+    if let Some(blocks_version) = &manifest.blocks_version {
+        verify_version(blocks_version, &STORAGE_VERSION_BLOCKS)?;
+    }
+    let snapshot_path = snapshot_dir.join(manifest.chain_id.to_string());
+    for (file_name, expected_file) in &manifest.files {
+        if describe_file(&snapshot_path.join(file_name))? != *expected_file {
+            return Err(SnapshotError::FileMismatch { file_name: file_name.clone() });
+        }
+    }
+    Ok(manifest)
+}
+
+/// Verifies the snapshot in `snapshot_dir` and copies it into the storage directory of
+/// `db_config`, whose path prefix must be empty or nonexistent. Returns the manifest.
+pub fn import_snapshot(
+    snapshot_dir: &Path,
+    db_config: &DbConfig,
+) -> SnapshotResult<SnapshotManifest> {
+    verify_empty_dir(&db_config.path_prefix)?;
+    let manifest = verify_snapshot(snapshot_dir)?;
+    if manifest.chain_id != db_config.chain_id {
+        return Err(SnapshotError::ChainIdMismatch {
+            snapshot_chain_id: manifest.chain_id,
+            storage_chain_id: db_config.chain_id.clone(),
+        });
+    }
+    let snapshot_path = snapshot_dir.join(manifest.chain_id.to_string());
+    let storage_path = db_config.path();
+    fs::create_dir_all(&storage_path)?;
+    for file_name in manifest.files.keys() {
+        info!("Copying {file_name}.");
+        fs::copy(snapshot_path.join(file_name), storage_path.join(file_name))?;
+    }
+    info!(
+        "Imported a snapshot up to block {} to {}.",
+        manifest.header_marker,
+        db_config.path_prefix.display()
+    );
+    Ok(manifest)
+}
+
+// The names of the files of a snapshot in the storage directory.
+fn snapshot_file_names() -> impl Iterator<Item = &'static str> {
+    std::iter::once(DB_FILE_NAME).chain(OffsetKind::ALL.iter().map(OffsetKind::file_name))
+}
+
+// The files of the snapshot are read from the directory named after the chain id, so it must not
+// lead out of the snapshot.
+fn verify_chain_id(chain_id: &ChainId) -> SnapshotResult<()> {
+    let dir_name = chain_id.to_string();
+    if dir_name.is_empty()
+        || dir_name == "."
+        || dir_name.contains("..")
+        || dir_name.contains(['/', '\\'])
+    {
+        return Err(SnapshotError::InvalidChainId(chain_id.clone()));
+    }
+    Ok(())
+}
+
+fn verify_empty_dir(dir: &Path) -> SnapshotResult<()> {
+    if dir.exists() && fs::read_dir(dir)?.next().is_some() {
+        return Err(SnapshotError::NonEmptyDirectory(dir.to_path_buf()));
+    }
+    Ok(())
+}
+
+// A snapshot can be opened by the crate if a storage of its version can.
+fn verify_version(snapshot_version: &Version, crate_version: &Version) -> SnapshotResult<()> {
+    if snapshot_version.major != crate_version.major || snapshot_version.minor > crate_version.minor
+    {
+        return Err(SnapshotError::IncompatibleVersion {
+            snapshot_version: snapshot_version.clone(),
+            crate_version: crate_version.clone(),
+        });
+    }
+    Ok(())
+}
+
+fn describe_file(path: &Path) -> SnapshotResult<SnapshotFile> {
+    let mut hasher = Sha256::new();
+    let size = io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(SnapshotFile { size, sha256: format!("{:x}", hasher.finalize()) })
+}
@@ -0,0 +1,174 @@
+use assert_matches::assert_matches;
+use papyrus_test_utils::{get_rng, GetTestInstance};
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockHeader, BlockHeaderWithoutHash, BlockNumber};
+use starknet_api::core::ChainId;
+use starknet_api::state::ThinStateDiff;
+use tempfile::tempdir;
+
+use crate::db::DbConfig;
+use crate::header::{HeaderStorageReader, HeaderStorageWriter};
+use crate::snapshot::{
+    export_snapshot,
+    import_snapshot,
+    verify_snapshot,
+    SnapshotError,
+    SnapshotManifest,
+    MANIFEST_FILE_NAME,
+};
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::get_test_storage_with_config_by_scope;
+use crate::{open_storage, StorageConfig, StorageScope};
+
+fn header(block_number: BlockNumber) -> BlockHeader {
+    BlockHeader {
+        block_hash: BlockHash(block_number.0.into()),
+        block_header_without_hash: BlockHeaderWithoutHash { block_number, ..Default::default() },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn export_and_import_roundtrip() {
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let state_diff = ThinStateDiff::get_test_instance(&mut get_rng());
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &header(BlockNumber(0)))
+        .unwrap()
+        .append_state_diff(BlockNumber(0), state_diff.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let snapshot_dir = tempdir().unwrap();
+    let manifest = export_snapshot(&reader, &config.db_config, snapshot_dir.path()).unwrap();
+    assert_eq!(manifest.header_marker, BlockNumber(1));
+    assert_eq!(manifest.state_marker, BlockNumber(1));
+    assert_eq!(manifest.chain_id, config.db_config.chain_id);
+    assert_eq!(verify_snapshot(snapshot_dir.path()).unwrap(), manifest);
+
+    // Blocks written after the export aren't in the snapshot.
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(1), &header(BlockNumber(1)))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let storage_dir = tempdir().unwrap();
+    let db_config =
+        DbConfig { path_prefix: storage_dir.path().join("imported"), ..config.db_config.clone() };
+    import_snapshot(snapshot_dir.path(), &db_config).unwrap();
+    let (imported_reader, _imported_writer) =
+        open_storage(StorageConfig { db_config, ..config }).unwrap();
+    let txn = imported_reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_block_header(BlockNumber(0)).unwrap(), Some(header(BlockNumber(0))));
+    assert_eq!(txn.get_state_diff(BlockNumber(0)).unwrap(), Some(state_diff));
+}
+
+#[test]
+fn export_to_non_empty_directory_fails() {
+    let ((reader, _writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let snapshot_dir = tempdir().unwrap();
+    std::fs::write(snapshot_dir.path().join("file"), b"content").unwrap();
+    assert_matches!(
+        export_snapshot(&reader, &config.db_config, snapshot_dir.path()),
+        Err(SnapshotError::NonEmptyDirectory(_))
+    );
+}
+
+#[test]
+fn corrupted_snapshot_fails_verification() {
+    let ((reader, _writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let snapshot_dir = tempdir().unwrap();
+    export_snapshot(&reader, &config.db_config, snapshot_dir.path()).unwrap();
+    let file_path =
+        snapshot_dir.path().join(config.db_config.chain_id.to_string()).join("transaction.dat");
+    std::fs::write(file_path, b"corrupted").unwrap();
+
+    let storage_dir = tempdir().unwrap();
+    let db_config = DbConfig { path_prefix: storage_dir.path().to_path_buf(), ..config.db_config };
+    assert_matches!(
+        import_snapshot(snapshot_dir.path(), &db_config),
+        Err(SnapshotError::FileMismatch { file_name }) if file_name == "transaction.dat"
+    );
+}
+
+#[test]
+fn import_of_another_chain_fails() {
+    let ((reader, _writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let snapshot_dir = tempdir().unwrap();
+    export_snapshot(&reader, &config.db_config, snapshot_dir.path()).unwrap();
+
+    let storage_dir = tempdir().unwrap();
+    let db_config = DbConfig {
+        path_prefix: storage_dir.path().to_path_buf(),
+        chain_id: ChainId::Other("other_chain".to_owned()),
+        ..config.db_config
+    };
+    assert_matches!(
+        import_snapshot(snapshot_dir.path(), &db_config),
+        Err(SnapshotError::ChainIdMismatch { .. })
+    );
+}
+
+// Exports a snapshot of an empty storage, changes its manifest and returns the error of importing
+// it.
+fn import_with_changed_manifest(
+    change_manifest: impl FnOnce(&mut SnapshotManifest),
+) -> SnapshotError {
+    let ((reader, _writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let snapshot_dir = tempdir().unwrap();
+    let mut manifest = export_snapshot(&reader, &config.db_config, snapshot_dir.path()).unwrap();
+    change_manifest(&mut manifest);
+    std::fs::write(
+        snapshot_dir.path().join(MANIFEST_FILE_NAME),
+        serde_json::to_vec_pretty(&manifest).unwrap(),
+    )
+    .unwrap();
+
+    let storage_dir = tempdir().unwrap();
+    let db_config = DbConfig {
+        path_prefix: storage_dir.path().join("imported"),
+        chain_id: manifest.chain_id,
+        ..config.db_config
+    };
+    let error = import_snapshot(snapshot_dir.path(), &db_config).unwrap_err();
+    assert!(!db_config.path_prefix.exists());
+    error
+}
+
+#[test]
+fn manifest_with_a_file_outside_the_snapshot_fails_verification() {
+    let file_name = "../escape.dat".to_owned();
+    let error = import_with_changed_manifest(|manifest| {
+        let file = manifest.files["transaction.dat"].clone();
+        manifest.files.insert(file_name.clone(), file);
+    });
+    assert_matches!(error, SnapshotError::UnexpectedFile { file_name: name } if name == file_name);
+}
+
+#[test]
+fn manifest_with_a_chain_id_outside_the_snapshot_fails_verification() {
+    let error = import_with_changed_manifest(|manifest| {
+        manifest.chain_id = ChainId::Other("../other_chain".to_owned());
+    });
+    assert_matches!(error, SnapshotError::InvalidChainId(_));
+}
+
+#[test]
+fn manifest_without_a_storage_file_fails_verification() {
+    let error = import_with_changed_manifest(|manifest| {
+        manifest.files.remove("casm.dat");
+    });
+    assert_matches!(error, SnapshotError::MissingFile { file_name } if file_name == "casm.dat");
+}
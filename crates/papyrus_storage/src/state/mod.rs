@@ -51,10 +51,12 @@
 
 #[doc(hidden)]
 pub mod data;
+pub mod pruning;
 #[cfg(test)]
 mod state_test;
 
 use std::collections::HashSet;
+use std::ops::Range;
 
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use indexmap::IndexMap;
@@ -66,7 +68,7 @@ use starknet_api::state::{SierraContractClass, StateNumber, StorageKey, ThinStat
 use starknet_types_core::felt::Felt;
 use tracing::debug;
 
-use crate::db::serialization::{NoVersionValueWrapper, VersionZeroWrapper};
+use crate::db::serialization::{Key, NoVersionValueWrapper, ValueSerde, VersionZeroWrapper};
 use crate::db::table_types::{CommonPrefix, DbCursorTrait, SimpleTable, Table};
 use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
 #[cfg(feature = "document_calls")]
@@ -130,6 +132,9 @@ pub trait StateStorageReader<Mode: TransactionKind> {
     fn get_state_diff(&self, block_number: BlockNumber) -> StorageResult<Option<ThinStateDiff>>;
     /// Returns a state reader.
     fn get_state_reader(&self) -> StorageResult<StateReader<'_, Mode>>;
+    /// The pruned state marker is the first state number that can be queried. The state history
+    /// before it was pruned.
+    fn get_pruned_state_marker(&self) -> StorageResult<BlockNumber>;
 }
 
 type RevertedStateDiff = (
@@ -156,6 +161,10 @@ where
         self,
         block_number: BlockNumber,
     ) -> StorageResult<(Self, Option<RevertedStateDiff>)>;
+
+    /// Deletes the state values that were superseded before the given block, so that the state
+    /// can't be queried anymore at state numbers before it. Stops at the state marker.
+    fn prune_state_history(self, up_to: BlockNumber) -> StorageResult<Self>;
 }
 
 impl<Mode: TransactionKind> StateStorageReader<Mode> for StorageTxn<'_, Mode> {
@@ -180,6 +189,11 @@ impl<Mode: TransactionKind> StateStorageReader<Mode> for StorageTxn<'_, Mode> {
     fn get_state_reader(&self) -> StorageResult<StateReader<'_, Mode>> {
         StateReader::new(self)
     }
+
+    fn get_pruned_state_marker(&self) -> StorageResult<BlockNumber> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        Ok(markers_table.get(&self.txn, &MarkerKind::PrunedState)?.unwrap_or_default())
+    }
 }
 
 /// A single coherent state at a single point in time,
@@ -193,6 +207,7 @@ pub struct StateReader<'env, Mode: TransactionKind> {
     storage_table: ContractStorageTable<'env>,
     markers_table: MarkersTable<'env>,
     file_handlers: &'env FileHandlers<Mode>,
+    pruned_state_marker: BlockNumber,
 }
 
 impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
@@ -215,6 +230,8 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         let nonces_table = txn.txn.open_table(&txn.tables.nonces)?;
         let storage_table = txn.txn.open_table(&txn.tables.contract_storage)?;
         let markers_table = txn.txn.open_table(&txn.tables.markers)?;
+        let pruned_state_marker =
+            markers_table.get(&txn.txn, &MarkerKind::PrunedState)?.unwrap_or_default();
         Ok(StateReader {
             txn: &txn.txn,
             declared_classes_table,
@@ -225,9 +242,21 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
             storage_table,
             markers_table,
             file_handlers: &txn.file_handlers,
+            pruned_state_marker,
         })
     }
 
+    // Returns an error if the history of the given state was pruned.
+    fn verify_not_pruned(&self, state_number: StateNumber) -> StorageResult<()> {
+        if state_number.0 < self.pruned_state_marker {
+            return Err(StorageError::StateHistoryPruned {
+                state_number,
+                pruned_state_marker: self.pruned_state_marker,
+            });
+        }
+        Ok(())
+    }
+
     /// Returns the class hash at a given state number.
     /// If class hash is not found, returns `None`.
     ///
@@ -237,6 +266,9 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
     ///
     /// # Errors
     /// Returns [`StorageError`] if there was an error searching the table.
+    ///
+    /// Returns [`StorageError`]::StateHistoryPruned if the state at the given state number was
+    /// pruned.
     pub fn get_class_hash_at(
         &self,
         state_number: StateNumber,
@@ -245,6 +277,7 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         // TODO(dvir): create an attribute instead of this.
         #[cfg(feature = "document_calls")]
         add_query(StorageQuery::GetClassHashAt(state_number, *address));
+        self.verify_not_pruned(state_number)?;
 
         let first_irrelevant_block: BlockNumber = state_number.block_after();
         let db_key = (*address, first_irrelevant_block);
@@ -268,6 +301,9 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
     ///
     /// # Errors
     /// Returns [`StorageError`] if there was an error searching the table.
+    ///
+    /// Returns [`StorageError`]::StateHistoryPruned if the state at the given state number was
+    /// pruned.
     pub fn get_nonce_at(
         &self,
         state_number: StateNumber,
@@ -275,6 +311,7 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
     ) -> StorageResult<Option<Nonce>> {
         #[cfg(feature = "document_calls")]
         add_query(StorageQuery::GetNonceAt(state_number, *address));
+        self.verify_not_pruned(state_number)?;
 
         // State diff updates are indexed by the block_number at which they occurred.
         let block_number: BlockNumber = state_number.block_after();
@@ -291,6 +328,9 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
     ///
     /// # Errors
     /// Returns [`StorageError`] if there was an error searching the table.
+    ///
+    /// Returns [`StorageError`]::StateHistoryPruned if the state at the given state number was
+    /// pruned.
     pub fn get_storage_at(
         &self,
         state_number: StateNumber,
@@ -299,6 +339,7 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
     ) -> StorageResult<Felt> {
         #[cfg(feature = "document_calls")]
         add_query(StorageQuery::GetStorageAt(state_number, *address, *key));
+        self.verify_not_pruned(state_number)?;
 
         // The updates to the storage key are indexed by the block_number at which they occurred.
         let first_irrelevant_block: BlockNumber = state_number.block_after();
@@ -322,6 +363,77 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         }
     }
 
+    /// Returns an iterator over the updates of the class hash of a contract in the blocks of the
+    /// given range, ordered by block number. Each update is the block number of the deployment or
+    /// the class replacement, and the new class hash.
+    ///
+    /// # Arguments
+    /// * address - contract address to search for.
+    /// * block_range - the blocks to search in.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error searching the table.
+    ///
+    /// Returns [`StorageError`]::StateHistoryPruned if the history of the first block in the range
+    /// was pruned.
+    pub fn get_class_hash_history(
+        &self,
+        address: &ContractAddress,
+        block_range: Range<BlockNumber>,
+    ) -> StorageResult<impl Iterator<Item = StorageResult<(BlockNumber, ClassHash)>> + '_> {
+        self.verify_not_pruned(StateNumber(block_range.start))?;
+        let cursor = self.deployed_contracts_table.cursor(self.txn)?;
+        Ok(history_iter(cursor, *address, block_range))
+    }
+
+    /// Returns an iterator over the updates of the nonce of a contract in the blocks of the given
+    /// range, ordered by block number. Each update is the block number in which the nonce changed,
+    /// and the new nonce.
+    ///
+    /// # Arguments
+    /// * address - contract address to search for.
+    /// * block_range - the blocks to search in.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error searching the table.
+    ///
+    /// Returns [`StorageError`]::StateHistoryPruned if the history of the first block in the range
+    /// was pruned.
+    pub fn get_nonce_history(
+        &self,
+        address: &ContractAddress,
+        block_range: Range<BlockNumber>,
+    ) -> StorageResult<impl Iterator<Item = StorageResult<(BlockNumber, Nonce)>> + '_> {
+        self.verify_not_pruned(StateNumber(block_range.start))?;
+        let cursor = self.nonces_table.cursor(self.txn)?;
+        Ok(history_iter(cursor, *address, block_range))
+    }
+
+    /// Returns an iterator over the updates of a storage value of a contract in the blocks of the
+    /// given range, ordered by block number. Each update is the block number in which the value
+    /// changed, and the new value.
+    ///
+    /// # Arguments
+    /// * address - contract address to search for.
+    /// * key - key to search for.
+    /// * block_range - the blocks to search in.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error searching the table.
+    ///
+    /// Returns [`StorageError`]::StateHistoryPruned if the history of the first block in the range
+    /// was pruned.
+    pub fn get_storage_history(
+        &self,
+        address: &ContractAddress,
+        key: &StorageKey,
+        block_range: Range<BlockNumber>,
+    ) -> StorageResult<impl Iterator<Item = StorageResult<(BlockNumber, Felt)>> + '_> {
+        self.verify_not_pruned(StateNumber(block_range.start))?;
+        let cursor = self.storage_table.cursor(self.txn)?;
+        Ok(history_iter(cursor, (*address, *key), block_range))
+    }
+
     /// Returns the class definition at a given state number.
     ///
     /// If class_hash is not found, returns `None`.
@@ -407,6 +519,48 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
     }
 }
 
+// Returns an iterator over the values of the entries of a table keyed by (prefix, block number)
+// with the given prefix, for the blocks in the given range.
+fn history_iter<Cursor, Prefix>(
+    mut cursor: Cursor,
+    prefix: Prefix,
+    block_range: Range<BlockNumber>,
+) -> impl Iterator<Item = StorageResult<(BlockNumber, <Cursor::Value as ValueSerde>::Value)>>
+where
+    Cursor: DbCursorTrait<Key = (Prefix, BlockNumber)>,
+    Prefix: Clone + PartialEq,
+    (Prefix, BlockNumber): Key,
+{
+    let mut is_first = true;
+    let mut is_done = false;
+    std::iter::from_fn(move || {
+        if is_done {
+            return None;
+        }
+        let entry = if is_first {
+            is_first = false;
+            cursor.lower_bound(&(prefix.clone(), block_range.start))
+        } else {
+            cursor.next()
+        };
+        match entry {
+            Ok(Some(((entry_prefix, block_number), value)))
+                if entry_prefix == prefix && block_number < block_range.end =>
+            {
+                Some(Ok((block_number, value)))
+            }
+            Ok(_) => {
+                is_done = true;
+                None
+            }
+            Err(err) => {
+                is_done = true;
+                Some(Err(err.into()))
+            }
+        }
+    })
+}
+
 impl StateStorageWriter for StorageTxn<'_, RW> {
     #[latency_histogram("storage_append_thin_state_diff_latency_seconds", false)]
     fn append_state_diff(
@@ -492,6 +646,14 @@ impl StateStorageWriter for StorageTxn<'_, RW> {
             );
             return Ok((self, None));
         };
+        // Reverting a block requires the state before it.
+        let pruned_state_marker = self.get_pruned_state_marker()?;
+        if block_number < pruned_state_marker {
+            return Err(StorageError::StateHistoryPruned {
+                state_number: StateNumber(block_number),
+                pruned_state_marker,
+            });
+        }
 
         let thin_state_diff = self
             .get_state_diff(block_number)?
@@ -547,6 +709,41 @@ impl StateStorageWriter for StorageTxn<'_, RW> {
             )),
         ))
     }
+
+    #[latency_histogram("storage_prune_state_history_latency_seconds", false)]
+    fn prune_state_history(self, up_to: BlockNumber) -> StorageResult<Self> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        let deployed_contracts_table = self.open_table(&self.tables.deployed_contracts)?;
+        let nonces_table = self.open_table(&self.tables.nonces)?;
+        let storage_table = self.open_table(&self.tables.contract_storage)?;
+
+        let up_to = up_to.min(self.get_state_marker()?);
+        let mut pruned_state_marker = self.get_pruned_state_marker()?;
+        // Pruning a block deletes the values that its state diff superseded, which were needed
+        // only for the state right before it.
+        while pruned_state_marker < up_to {
+            let block_number = pruned_state_marker;
+            let thin_state_diff = self
+                .get_state_diff(block_number)?
+                .unwrap_or_else(|| panic!("Missing state diff for block {block_number}."));
+            delete_superseded_class_hashes(
+                &self.txn,
+                block_number,
+                &thin_state_diff,
+                &deployed_contracts_table,
+            )?;
+            delete_superseded_storage_values(
+                &self.txn,
+                block_number,
+                &thin_state_diff,
+                &storage_table,
+            )?;
+            delete_superseded_nonces(&self.txn, block_number, &thin_state_diff, &nonces_table)?;
+            pruned_state_marker = block_number.unchecked_next();
+        }
+        markers_table.upsert(&self.txn, &MarkerKind::PrunedState, &pruned_state_marker)?;
+        Ok(self)
+    }
 }
 
 #[latency_histogram("storage_update_marker_to_next_block_latency_seconds", true)]
@@ -787,6 +984,65 @@ fn delete_nonces<'env>(
     Ok(())
 }
 
+// The deployed contracts of a state diff include the contracts whose class was replaced.
+fn delete_superseded_class_hashes<'env>(
+    txn: &'env DbTransaction<'env, RW>,
+    block_number: BlockNumber,
+    thin_state_diff: &ThinStateDiff,
+    deployed_contracts_table: &'env DeployedContractsTable<'env>,
+) -> StorageResult<()> {
+    for address in thin_state_diff.deployed_contracts.keys() {
+        let mut cursor = deployed_contracts_table.cursor(txn)?;
+        cursor.lower_bound(&(*address, block_number))?;
+        if let Some(((got_address, got_block_number), _)) = cursor.prev()? {
+            if got_address == *address {
+                deployed_contracts_table.delete(txn, &(got_address, got_block_number))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn delete_superseded_storage_values<'env>(
+    txn: &'env DbTransaction<'env, RW>,
+    block_number: BlockNumber,
+    thin_state_diff: &ThinStateDiff,
+    storage_table: &'env ContractStorageTable<'env>,
+) -> StorageResult<()> {
+    for (address, storage_entries) in &thin_state_diff.storage_diffs {
+        for key in storage_entries.keys() {
+            let mut cursor = storage_table.cursor(txn)?;
+            cursor.lower_bound(&((*address, *key), block_number))?;
+            if let Some((((got_address, got_key), got_block_number), _)) = cursor.prev()? {
+                if got_address == *address && got_key == *key {
+                    storage_table.delete(txn, &((got_address, got_key), got_block_number))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Nonces that were added for newly deployed contracts didn't supersede any nonce, so only the
+// nonces of the state diff are relevant.
+fn delete_superseded_nonces<'env>(
+    txn: &'env DbTransaction<'env, RW>,
+    block_number: BlockNumber,
+    thin_state_diff: &ThinStateDiff,
+    nonces_table: &'env NoncesTable<'env>,
+) -> StorageResult<()> {
+    for address in thin_state_diff.nonces.keys() {
+        let mut cursor = nonces_table.cursor(txn)?;
+        cursor.lower_bound(&(*address, block_number))?;
+        if let Some(((got_address, got_block_number), _)) = cursor.prev()? {
+            if got_address == *address {
+                nonces_table.delete(txn, &(got_address, got_block_number))?;
+            }
+        }
+    }
+    Ok(())
+}
+
 fn get_nonce_at<'env, Mode: TransactionKind>(
     first_irrelevant_block: BlockNumber,
     address: &ContractAddress,
@@ -0,0 +1,117 @@
+//! Background pruning of the state history for storage opened with [`StorageScope::Pruned`].
+//!
+//! The state tables keep a value for each block in which it was changed, so that the state can be
+//! queried at any block. The pruner deletes the values that were superseded before the window of
+//! recent blocks, and from then on, queries for a state before the window fail with
+//! [`StorageError::StateHistoryPruned`].
+//!
+//! [`StorageScope::Pruned`]: crate::StorageScope::Pruned
+//! [`StorageError::StateHistoryPruned`]: crate::StorageError::StateHistoryPruned
+
+#[cfg(test)]
+#[path = "pruning_test.rs"]
+mod pruning_test;
+
+use std::cmp::min;
+use std::collections::BTreeMap;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use papyrus_config::converters::deserialize_seconds_to_duration;
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
+use tracing::{debug, error};
+
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::{StorageResult, StorageWriter};
+
+// The maximal number of blocks pruned in a single write transaction, so that the pruner doesn't
+// hold the storage from the sync for long.
+const MAX_BLOCKS_PER_TRANSACTION: u64 = 100;
+
+/// The configuration of the state pruning. Used only when the storage scope is
+/// [`StorageScope::Pruned`](crate::StorageScope::Pruned).
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct StatePruningConfig {
+    /// The number of recent blocks whose state history is kept.
+    pub history_window: u64,
+    /// The time to wait between pruning rounds.
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub pruning_interval: Duration,
+}
+
+impl Default for StatePruningConfig {
+    fn default() -> Self {
+        Self { history_window: 5000, pruning_interval: Duration::from_secs(60) }
+    }
+}
+
+impl SerializeConfig for StatePruningConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "history_window",
+                &self.history_window,
+                "The number of recent blocks whose state history is kept when the storage scope \
+                 is Pruned. Must be larger than the depth of any expected revert.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "pruning_interval",
+                &self.pruning_interval.as_secs(),
+                "Time in seconds between rounds of deleting the state history that left the \
+                 history window.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+// Stops the pruner thread when dropped.
+pub(crate) struct StatePrunerHandle {
+    _stop_sender: Sender<()>,
+}
+
+// Spawns a thread that periodically prunes the state history outside the window of recent blocks.
+pub(crate) fn spawn_state_pruner(
+    mut writer: StorageWriter,
+    config: StatePruningConfig,
+) -> StatePrunerHandle {
+    let (stop_sender, stop_receiver) = channel::<()>();
+    thread::Builder::new()
+        .name("state_pruner".to_owned())
+        .spawn(move || {
+            loop {
+                if let Err(err) = prune_outside_window(&mut writer, config.history_window) {
+                    error!("Failed to prune the state history: {err}.");
+                }
+                match stop_receiver.recv_timeout(config.pruning_interval) {
+                    Err(RecvTimeoutError::Timeout) => {}
+                    // The handle was dropped.
+                    Ok(()) | Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        })
+        .expect("Failed to spawn the state pruner thread.");
+    StatePrunerHandle { _stop_sender: stop_sender }
+}
+
+fn prune_outside_window(writer: &mut StorageWriter, history_window: u64) -> StorageResult<()> {
+    loop {
+        let txn = writer.begin_rw_txn()?;
+        let target_marker = txn.get_state_marker()?.0.saturating_sub(history_window);
+        let pruned_state_marker = txn.get_pruned_state_marker()?;
+        if pruned_state_marker.0 >= target_marker {
+            return Ok(());
+        }
+        let up_to = BlockNumber(min(
+            target_marker,
+            pruned_state_marker.0.saturating_add(MAX_BLOCKS_PER_TRANSACTION),
+        ));
+        debug!("Pruning the state history up to block {up_to}.");
+        txn.prune_state_history(up_to)?.commit()?;
+    }
+}
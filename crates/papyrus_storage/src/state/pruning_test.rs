@@ -0,0 +1,119 @@
+use std::time::{Duration, Instant};
+
+use assert_matches::assert_matches;
+use indexmap::indexmap;
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::Nonce;
+use starknet_api::state::{StateNumber, ThinStateDiff};
+use starknet_api::{class_hash, contract_address, felt, storage_key};
+
+use crate::db::table_types::Table;
+use crate::state::pruning::StatePruningConfig;
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::{get_test_config, get_test_storage};
+use crate::{open_storage, StorageError, StorageScope, StorageWriter};
+
+// Each block changes the storage value of the contract, and the first two blocks also change its
+// nonce and its class.
+fn append_state_diffs(writer: &mut StorageWriter, num_blocks: u64) {
+    let address = contract_address!("0x1");
+    for i in 0..num_blocks {
+        let mut state_diff = ThinStateDiff {
+            storage_diffs: indexmap! { address => indexmap! { storage_key!("0x2") => felt!(i) } },
+            ..Default::default()
+        };
+        if i < 2 {
+            state_diff.deployed_contracts = indexmap! { address => class_hash!(i) };
+            state_diff.nonces = indexmap! { address => Nonce(felt!(i)) };
+        }
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_state_diff(BlockNumber(i), state_diff)
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+}
+
+#[test]
+fn prune_state_history() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_state_diffs(&mut writer, 3);
+    writer.begin_rw_txn().unwrap().prune_state_history(BlockNumber(2)).unwrap().commit().unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_pruned_state_marker().unwrap(), BlockNumber(2));
+    let state_reader = txn.get_state_reader().unwrap();
+    let address = contract_address!("0x1");
+    let key = storage_key!("0x2");
+
+    // The state from the pruned state marker is intact.
+    let state2 = StateNumber(BlockNumber(2));
+    assert_eq!(state_reader.get_storage_at(state2, &address, &key).unwrap(), felt!(1_u8));
+    assert_eq!(state_reader.get_nonce_at(state2, &address).unwrap(), Some(Nonce(felt!(1_u8))));
+    assert_eq!(state_reader.get_class_hash_at(state2, &address).unwrap(), Some(class_hash!(1_u8)));
+    let state3 = StateNumber(BlockNumber(3));
+    assert_eq!(state_reader.get_storage_at(state3, &address, &key).unwrap(), felt!(2_u8));
+
+    // Older states can't be queried.
+    let state1 = StateNumber(BlockNumber(1));
+    assert_matches!(
+        state_reader.get_storage_at(state1, &address, &key),
+        Err(StorageError::StateHistoryPruned { state_number, pruned_state_marker: BlockNumber(2) })
+        if state_number == state1
+    );
+    assert_matches!(
+        state_reader.get_nonce_at(state1, &address),
+        Err(StorageError::StateHistoryPruned { .. })
+    );
+    assert_matches!(
+        state_reader.get_class_hash_at(state1, &address),
+        Err(StorageError::StateHistoryPruned { .. })
+    );
+
+    // Only the superseded values were deleted.
+    let storage_table = txn.open_table(&txn.tables.contract_storage).unwrap();
+    assert_eq!(storage_table.get(&txn.txn, &((address, key), BlockNumber(0))).unwrap(), None);
+    assert!(storage_table.get(&txn.txn, &((address, key), BlockNumber(1))).unwrap().is_some());
+    let nonces_table = txn.open_table(&txn.tables.nonces).unwrap();
+    assert_eq!(nonces_table.get(&txn.txn, &(address, BlockNumber(0))).unwrap(), None);
+    let deployed_contracts_table = txn.open_table(&txn.tables.deployed_contracts).unwrap();
+    assert_eq!(deployed_contracts_table.get(&txn.txn, &(address, BlockNumber(0))).unwrap(), None);
+}
+
+#[test]
+fn prune_state_history_stops_at_state_marker() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_state_diffs(&mut writer, 2);
+    writer.begin_rw_txn().unwrap().prune_state_history(BlockNumber(5)).unwrap().commit().unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_pruned_state_marker().unwrap(), BlockNumber(2));
+}
+
+#[test]
+fn revert_pruned_state_diff_fails() {
+    let ((_reader, mut writer), _temp_dir) = get_test_storage();
+    append_state_diffs(&mut writer, 2);
+    writer.begin_rw_txn().unwrap().prune_state_history(BlockNumber(2)).unwrap().commit().unwrap();
+    let Err(err) = writer.begin_rw_txn().unwrap().revert_state_diff(BlockNumber(1)) else {
+        panic!("Reverting a pruned state diff should fail.");
+    };
+    assert_matches!(err, StorageError::StateHistoryPruned { .. });
+}
+
+#[test]
+fn pruner_keeps_history_window() {
+    let (mut config, _temp_dir) = get_test_config(Some(StorageScope::Pruned));
+    config.state_pruning_config =
+        StatePruningConfig { history_window: 2, pruning_interval: Duration::from_millis(10) };
+    let (reader, mut writer) = open_storage(config).unwrap();
+    append_state_diffs(&mut writer, 5);
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+    let start = Instant::now();
+    while reader.begin_ro_txn().unwrap().get_pruned_state_marker().unwrap() != BlockNumber(3) {
+        assert!(start.elapsed() < TIMEOUT, "The pruner didn't prune the state history in time.");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
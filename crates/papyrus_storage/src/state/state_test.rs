@@ -15,7 +15,7 @@ use crate::class::{ClassStorageReader, ClassStorageWriter};
 use crate::compiled_class::{CasmStorageReader, CasmStorageWriter};
 use crate::state::{StateStorageReader, StateStorageWriter};
 use crate::test_utils::get_test_storage;
-use crate::StorageWriter;
+use crate::{StorageResult, StorageWriter};
 
 #[test]
 fn get_class_definition_at() {
@@ -787,3 +787,100 @@ fn declare_revert_declare_scenario() {
             .is_some()
     );
 }
+
+#[test]
+fn get_state_history() {
+    let address = contract_address!("0x10");
+    let other_address = contract_address!("0x11");
+    let key = storage_key!("0x1");
+    let state_diff =
+        |block_number: u64, class_hash: Option<ClassHash>, value: Option<Felt>| ThinStateDiff {
+            deployed_contracts: class_hash
+                .map(|class_hash| indexmap! { address => class_hash })
+                .unwrap_or_default(),
+            storage_diffs: indexmap! {
+                address => value.map(|value| indexmap! { key => value }).unwrap_or_default(),
+                other_address => indexmap! { key => felt!(block_number) },
+            },
+            nonces: value
+                .map(|_| indexmap! { address => Nonce(felt!(block_number)) })
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            state_diff(0, Some(class_hash!("0x1")), Some(felt!("0x5"))),
+        )
+        .unwrap()
+        .append_state_diff(BlockNumber(1), state_diff(1, None, None))
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(2),
+            state_diff(2, Some(class_hash!("0x2")), Some(felt!("0x6"))),
+        )
+        .unwrap()
+        .append_state_diff(BlockNumber(3), state_diff(3, None, Some(felt!("0x7"))))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let state_reader = txn.get_state_reader().unwrap();
+    assert_eq!(
+        state_reader
+            .get_storage_history(&address, &key, BlockNumber(0)..BlockNumber(4))
+            .unwrap()
+            .collect::<StorageResult<Vec<_>>>()
+            .unwrap(),
+        vec![
+            (BlockNumber(0), felt!("0x5")),
+            (BlockNumber(2), felt!("0x6")),
+            (BlockNumber(3), felt!("0x7"))
+        ]
+    );
+    assert_eq!(
+        state_reader
+            .get_storage_history(&address, &key, BlockNumber(1)..BlockNumber(3))
+            .unwrap()
+            .collect::<StorageResult<Vec<_>>>()
+            .unwrap(),
+        vec![(BlockNumber(2), felt!("0x6"))]
+    );
+    assert_eq!(
+        state_reader
+            .get_storage_history(&address, &storage_key!("0x2"), BlockNumber(0)..BlockNumber(4))
+            .unwrap()
+            .collect::<StorageResult<Vec<_>>>()
+            .unwrap(),
+        vec![]
+    );
+    assert_eq!(
+        state_reader
+            .get_nonce_history(&address, BlockNumber(1)..BlockNumber(10))
+            .unwrap()
+            .collect::<StorageResult<Vec<_>>>()
+            .unwrap(),
+        vec![(BlockNumber(2), Nonce(felt!(2_u64))), (BlockNumber(3), Nonce(felt!(3_u64)))]
+    );
+    assert_eq!(
+        state_reader
+            .get_class_hash_history(&address, BlockNumber(0)..BlockNumber(4))
+            .unwrap()
+            .collect::<StorageResult<Vec<_>>>()
+            .unwrap(),
+        vec![(BlockNumber(0), class_hash!("0x1")), (BlockNumber(2), class_hash!("0x2"))]
+    );
+    assert_eq!(
+        state_reader
+            .get_class_hash_history(&other_address, BlockNumber(0)..BlockNumber(4))
+            .unwrap()
+            .collect::<StorageResult<Vec<_>>>()
+            .unwrap(),
+        vec![]
+    );
+}
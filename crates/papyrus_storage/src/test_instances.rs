@@ -1,5 +1,5 @@
 use papyrus_test_utils::{auto_impl_get_test_instance, get_number_of_variants, GetTestInstance};
-use starknet_api::block::{BlockHash, BlockNumber, BlockTimestamp, GasPricePerToken};
+use starknet_api::block::{BlockHash, BlockNumber, BlockTimestamp, GasPrice, GasPricePerToken};
 use starknet_api::core::{
     EventCommitment,
     GlobalRoot,
@@ -9,6 +9,7 @@ use starknet_api::core::{
     TransactionCommitment,
 };
 use starknet_api::data_availability::L1DataAvailabilityMode;
+use starknet_api::execution_resources::GasAmount;
 use starknet_api::transaction::{
     EventIndexInTransactionOutput,
     TransactionHash,
@@ -18,6 +19,7 @@ use starknet_api::transaction::{
 use crate::body::TransactionIndex;
 use crate::compression_utils::IsCompressed;
 use crate::header::StorageBlockHeader;
+use crate::migrations::MigrationCheckpoint;
 use crate::mmap_file::LocationInFile;
 use crate::state::data::IndexedDeprecatedContractClass;
 use crate::version::Version;
@@ -42,6 +44,8 @@ auto_impl_get_test_instance! {
         pub state_diff_length: Option<usize>,
         pub n_transactions: usize,
         pub n_events: usize,
+        pub l2_gas_consumed: GasAmount,
+        pub next_l2_gas_price: GasPrice,
     }
 
     struct EventIndex(pub TransactionIndex, pub EventIndexInTransactionOutput);
@@ -62,6 +66,7 @@ auto_impl_get_test_instance! {
         CompiledClass = 5,
         BaseLayerBlock = 6,
         ClassManagerBlock = 7,
+        PrunedState = 8,
     }
     pub enum OffsetKind {
         ThinStateDiff = 0,
@@ -79,4 +84,8 @@ auto_impl_get_test_instance! {
         pub major: u32,
         pub minor: u32,
     }
+    pub struct MigrationCheckpoint {
+        pub migrated_items: u64,
+        pub next_key: Vec<u8>,
+    }
 }
@@ -24,6 +24,7 @@ fn build_storage_config(storage_scope: StorageScope, path_prefix: PathBuf) -> St
         },
         scope: storage_scope,
         mmap_file_config: get_mmap_file_test_config(),
+        ..Default::default()
     }
 }
 
@@ -136,3 +137,62 @@ impl TestStorageBuilder {
         }
     }
 }
+
+/// Appends blocks with transactions, events and a non-empty state diff to the storage, each in its
+/// own transaction, and returns their bodies and state diffs. The blocks don't have classes.
+#[cfg(test)]
+pub(crate) fn append_test_blocks(
+    writer: &mut StorageWriter,
+    num_blocks: u64,
+) -> (Vec<starknet_api::block::BlockBody>, Vec<starknet_api::state::ThinStateDiff>) {
+    use indexmap::indexmap;
+    use papyrus_test_utils::get_test_block;
+    use starknet_api::block::{BlockHash, BlockHeader, BlockHeaderWithoutHash, BlockNumber};
+    use starknet_api::core::Nonce;
+    use starknet_api::state::ThinStateDiff;
+    use starknet_api::transaction::TransactionHash;
+    use starknet_api::{contract_address, felt, storage_key};
+
+    use crate::body::BodyStorageWriter;
+    use crate::header::HeaderStorageWriter;
+    use crate::state::StateStorageWriter;
+
+    let mut bodies = Vec::new();
+    let mut state_diffs = Vec::new();
+    for i in 0..num_blocks {
+        let block_number = BlockNumber(i);
+        let header = BlockHeader {
+            block_hash: BlockHash(i.into()),
+            block_header_without_hash: BlockHeaderWithoutHash {
+                block_number,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let state_diff = ThinStateDiff {
+            storage_diffs: indexmap! {
+                contract_address!("0x1") => indexmap! { storage_key!("0x2") => felt!(i) },
+            },
+            nonces: indexmap! { contract_address!("0x1") => Nonce(felt!(i)) },
+            ..Default::default()
+        };
+        let mut body = get_test_block(2, Some(1), None, None).body;
+        for (j, transaction_hash) in (0..).zip(body.transaction_hashes.iter_mut()) {
+            *transaction_hash = TransactionHash(felt!(i * 2 + j));
+        }
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(block_number, &header)
+            .unwrap()
+            .append_body(block_number, body.clone())
+            .unwrap()
+            .append_state_diff(block_number, state_diff.clone())
+            .unwrap()
+            .commit()
+            .unwrap();
+        bodies.push(body);
+        state_diffs.push(state_diff);
+    }
+    (bodies, state_diffs)
+}
@@ -2,14 +2,16 @@
 #[path = "version_test.rs"]
 mod version_test;
 
+use serde::{Deserialize, Serialize};
+
 use crate::db::table_types::Table;
 use crate::db::{TransactionKind, RW};
 use crate::{StorageError, StorageResult, StorageTxn};
 
-const VERSION_STATE_KEY: &str = "storage_version_state";
-const VERSION_BLOCKS_KEY: &str = "storage_version_blocks";
+pub(crate) const VERSION_STATE_KEY: &str = "storage_version_state";
+pub(crate) const VERSION_BLOCKS_KEY: &str = "storage_version_blocks";
 
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Version {
     pub major: u32,
     pub minor: u32,
@@ -3,6 +3,7 @@ use pretty_assertions::assert_eq;
 use rand::Rng;
 
 use crate::db::table_types::Table;
+use crate::deprecated::migrations::registered_migrations;
 use crate::test_utils::{
     get_test_storage,
     get_test_storage_by_scope,
@@ -216,8 +217,11 @@ fn get_different_major_version(version: Version) -> Version {
     // with a high enough probability to be less and more than the current version.
     let minor = rng.gen_range(0..=2 * version.minor);
     let mut major = rng.gen_range(0..=2 * version.major);
-    if major == version.major {
-        major += 1;
+    // Older major versions may have a chain of migrations to the current one.
+    let migratable_majors: Vec<_> =
+        registered_migrations().iter().map(|migration| migration.source_major()).collect();
+    if major == version.major || migratable_majors.contains(&major) {
+        major = version.major + 1;
     }
     Version { major, minor }
 }
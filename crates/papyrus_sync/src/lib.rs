@@ -5,6 +5,7 @@
 pub mod define_metrics;
 mod pending_sync;
 pub mod sources;
+pub mod state_root;
 #[cfg(test)]
 mod sync_test;
 
@@ -21,7 +22,7 @@ use futures_util::{pin_mut, select, Stream, StreamExt};
 use indexmap::IndexMap;
 use papyrus_common::pending_classes::PendingClasses;
 use papyrus_config::converters::deserialize_seconds_to_duration;
-use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::dumping::{ser_optional_sub_config, ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_proc_macros::latency_histogram;
 use papyrus_storage::base_layer::{BaseLayerStorageReader, BaseLayerStorageWriter};
@@ -41,6 +42,7 @@ use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContract
 use starknet_api::state::{StateDiff, ThinStateDiff};
 use starknet_class_manager_types::{ClassManagerClientError, SharedClassManagerClient};
 use starknet_client::reader::PendingData;
+use starknet_patricia_storage::mdbx_storage::MdbxStorageConfig;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, instrument, trace, warn};
 
@@ -60,6 +62,7 @@ use crate::pending_sync::sync_pending_data;
 use crate::sources::base_layer::{BaseLayerSourceTrait, EthereumBaseLayerSource};
 use crate::sources::central::{CentralError, CentralSource, CentralSourceTrait};
 use crate::sources::pending::{PendingError, PendingSource, PendingSourceTrait};
+use crate::state_root::{StateRootVerificationError, StateRootVerifier};
 
 // TODO(shahak): Consider adding genesis hash to the config to support chains that have
 // different genesis hash.
@@ -73,7 +76,7 @@ const PENDING_SLEEP_DURATION: Duration = Duration::from_millis(500);
 // Sleep duration, in seconds, between sync progress checks.
 const SLEEP_TIME_SYNC_PROGRESS: Duration = Duration::from_secs(300);
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct SyncConfig {
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
     pub block_propagation_sleep_duration: Duration,
@@ -85,11 +88,12 @@ pub struct SyncConfig {
     pub state_updates_max_stream_size: u32,
     pub verify_blocks: bool,
     pub collect_pending_data: bool,
+    pub state_root_verification: Option<MdbxStorageConfig>,
 }
 
 impl SerializeConfig for SyncConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        BTreeMap::from_iter([
+        let mut config = BTreeMap::from_iter([
             ser_param(
                 "block_propagation_sleep_duration",
                 &self.block_propagation_sleep_duration.as_secs(),
@@ -133,7 +137,20 @@ impl SerializeConfig for SyncConfig {
                 "Whether to collect data on pending blocks.",
                 ParamPrivacyInput::Public,
             ),
-        ])
+        ]);
+        config.extend(ser_optional_sub_config(
+            &self.state_root_verification,
+            "state_root_verification",
+        ));
+        config
+            .get_mut("state_root_verification.#is_none")
+            .expect("An optional sub config should have an is_none flag.")
+            .description = "Flag for an optional field. If not set, the state root of every \
+                            synced block is verified against a Patricia storage. Enabling it on a \
+                            node that already synced blocks first builds the storage from the \
+                            stored state diffs, which fails if they were pruned."
+            .to_owned();
+        config
     }
 }
 
@@ -147,6 +164,7 @@ impl Default for SyncConfig {
             state_updates_max_stream_size: 1000,
             verify_blocks: true,
             collect_pending_data: false,
+            state_root_verification: None,
         }
     }
 }
@@ -169,6 +187,7 @@ pub struct GenericStateSync<
     writer: StorageWriter,
     sequencer_pub_key: Option<SequencerPublicKey>,
     class_manager_client: Option<SharedClassManagerClient>,
+    state_root_verifier: Option<StateRootVerifier>,
 }
 
 pub type StateSyncResult = Result<(), StateSyncError>;
@@ -211,6 +230,8 @@ pub enum StateSyncError {
     SequencerPubKeyChanged { old: SequencerPublicKey, new: SequencerPublicKey },
     #[error(transparent)]
     ClassManagerClientError(#[from] ClassManagerClientError),
+    #[error(transparent)]
+    StateRootVerificationError(#[from] StateRootVerificationError),
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -251,6 +272,10 @@ impl<
 {
     pub async fn run(mut self) -> StateSyncResult {
         info!("State sync started.");
+        if let Some(state_root_verification_config) = &self.config.state_root_verification {
+            self.state_root_verifier =
+                Some(StateRootVerifier::open(state_root_verification_config, self.reader.clone())?);
+        }
         loop {
             match self.sync_while_ok().await {
                 // A recoverable error occurred. Sleep and try syncing again.
@@ -286,7 +311,8 @@ impl<
                 | StateSyncError::BaseLayerHashMismatch { .. }
                 | StateSyncError::ClassManagerClientError(_)
                 | StateSyncError::BaseLayerBlockWithoutMatchingHeader { .. } => true,
-                StateSyncError::SequencerPubKeyChanged { .. } => false,
+                StateSyncError::SequencerPubKeyChanged { .. }
+                | StateSyncError::StateRootVerificationError(_) => false,
             }
         }
     }
@@ -477,7 +503,6 @@ impl<
         state_diff: StateDiff,
         deployed_contract_class_definitions: IndexMap<ClassHash, DeprecatedContractClass>,
     ) -> StateSyncResult {
-        // TODO(dan): verifications - verify state diff against stored header.
         debug!("Storing state diff.");
         trace!("StateDiff data: {state_diff:#?}");
 
@@ -485,6 +510,16 @@ impl<
         // classes.
         let (thin_state_diff, classes, deprecated_classes) =
             ThinStateDiff::from_state_diff(state_diff);
+        if let Some(state_root_verifier) = &mut self.state_root_verifier {
+            let state_root = self
+                .reader
+                .begin_ro_txn()?
+                .get_block_header(block_number)?
+                .expect("Expecting to have a header for every state diff.")
+                .block_header_without_hash
+                .state_root;
+            state_root_verifier.verify(block_number, &thin_state_diff, state_root).await?;
+        }
         self.writer
             .begin_rw_txn()?
             .append_state_diff(block_number, thin_state_diff)?
@@ -834,6 +869,7 @@ impl StateSync {
             writer,
             sequencer_pub_key: None,
             class_manager_client,
+            state_root_verifier: None,
         }
     }
 }
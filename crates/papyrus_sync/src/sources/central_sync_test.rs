@@ -106,6 +106,7 @@ fn get_test_sync_config(verify_blocks: bool) -> SyncConfig {
         state_updates_max_stream_size: STREAM_SIZE,
         verify_blocks,
         collect_pending_data: false,
+        state_root_verification: None,
     }
 }
 
@@ -134,6 +135,7 @@ async fn run_sync(
         writer,
         sequencer_pub_key: None,
         class_manager_client,
+        state_root_verifier: None,
     };
 
     state_sync.run().await?;
@@ -717,7 +719,7 @@ async fn sequencer_pub_key_management() {
         writer,
         central_mock,
         base_layer_mock,
-        config,
+        config.clone(),
         class_manager_client,
     );
 
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::{StorageError, StorageReader};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::GlobalRoot;
+use starknet_api::state::ThinStateDiff;
+use starknet_committer::block_committer::commit::commit_block_with_storage;
+use starknet_committer::block_committer::errors::BlockCommitmentError;
+use starknet_committer::block_committer::input::{
+    ConfigImpl,
+    ContractAddress,
+    Input,
+    StarknetStorageKey,
+    StarknetStorageValue,
+    StateDiff,
+};
+use starknet_committer::patricia_merkle_tree::types::{ClassHash, CompiledClassHash, Nonce};
+use starknet_patricia::hash::hash_trait::HashOutput;
+use starknet_patricia_storage::errors::StorageError as PatriciaStorageError;
+use starknet_patricia_storage::mdbx_storage::{MdbxStorage, MdbxStorageConfig};
+use starknet_patricia_storage::storage_trait::{
+    create_db_key,
+    DbKey,
+    DbKeyPrefix,
+    DbValue,
+    Storage,
+};
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Poseidon, StarkHash};
+use tracing::level_filters::LevelFilter;
+use tracing::{debug, info};
+
+#[cfg(test)]
+#[path = "state_root_test.rs"]
+mod state_root_test;
+
+pub type StateRootVerificationResult<T> = Result<T, StateRootVerificationError>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum StateRootVerificationError {
+    #[error(transparent)]
+    StorageError(#[from] StorageError),
+    #[error(transparent)]
+    PatriciaStorageError(#[from] PatriciaStorageError),
+    #[error(transparent)]
+    BlockCommitmentError(#[from] BlockCommitmentError),
+    #[error(
+        "Can't build the tries of block {block_number} because its state diff isn't in the \
+         storage. State root verification must be enabled before the state diffs are pruned."
+    )]
+    MissingStateDiff { block_number: BlockNumber },
+    #[error(
+        "State root mismatch in block {block_number}. Header state root: {expected_state_root:?}, \
+         computed state root: {computed_state_root:?}."
+    )]
+    StateRootMismatch {
+        block_number: BlockNumber,
+        expected_state_root: GlobalRoot,
+        computed_state_root: GlobalRoot,
+    },
+}
+
+const GLOBAL_STATE_VERSION: &[u8] = b"STARKNET_STATE_V0";
+const TRIE_ROOTS_PREFIX: &[u8] = b"trie_roots";
+
+// The roots of the contracts trie and the classes trie.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct TrieRoots {
+    pub contracts_trie_root: HashOutput,
+    pub classes_trie_root: HashOutput,
+}
+
+impl TrieRoots {
+    pub(crate) fn global_root(&self) -> GlobalRoot {
+        // Before the first class was declared, the global root is the root of the contracts trie.
+        if self.classes_trie_root.0 == Felt::ZERO {
+            return GlobalRoot(self.contracts_trie_root.0);
+        }
+        GlobalRoot(Poseidon::hash_array(&[
+            Felt::from_bytes_be_slice(GLOBAL_STATE_VERSION),
+            self.contracts_trie_root.0,
+            self.classes_trie_root.0,
+        ]))
+    }
+}
+
+/// Maintains the Patricia forest of the synced state in a persistent storage, and verifies the
+/// state roots of the synced blocks against it.
+///
+/// Since the trie nodes are stored by their hash, the nodes of reverted blocks don't need to be
+/// removed; the roots of every block are stored so that a reverted block can be committed again on
+/// top of its parent. If the roots of the parent of a verified block are missing, e.g. when the
+/// verification is enabled on a node that already synced, the tries are first built from the state
+/// diffs in the node's storage.
+pub struct StateRootVerifier {
+    storage: MdbxStorage,
+    storage_reader: StorageReader,
+}
+
+impl StateRootVerifier {
+    pub fn open(
+        config: &MdbxStorageConfig,
+        storage_reader: StorageReader,
+    ) -> StateRootVerificationResult<Self> {
+        Ok(Self { storage: MdbxStorage::open(config)?, storage_reader })
+    }
+
+    /// Commits the state diff of the given block on top of the tries of its parent and verifies
+    /// that the resulting global root is the state root of the block. The roots of the block are
+    /// stored only if the verification succeeded.
+    pub async fn verify(
+        &mut self,
+        block_number: BlockNumber,
+        state_diff: &ThinStateDiff,
+        state_root: GlobalRoot,
+    ) -> StateRootVerificationResult<()> {
+        let parent_roots = match block_number.prev() {
+            None => TrieRoots::default(),
+            Some(parent_block_number) => match self.get_trie_roots(parent_block_number)? {
+                Some(parent_roots) => parent_roots,
+                None => self.build_stored_tries(parent_block_number).await?,
+            },
+        };
+        self.commit_and_verify(block_number, state_diff, state_root, parent_roots).await?;
+        debug!("Verified the state root of block {block_number}.");
+        Ok(())
+    }
+
+    // Commits the stored state diffs of the blocks since the last block with stored roots, up to
+    // and including the given block, and returns the roots of the given block.
+    async fn build_stored_tries(
+        &mut self,
+        last_block_number: BlockNumber,
+    ) -> StateRootVerificationResult<TrieRoots> {
+        let mut first_block_number = last_block_number;
+        let mut roots = TrieRoots::default();
+        while let Some(block_number) = first_block_number.prev() {
+            if let Some(stored_roots) = self.get_trie_roots(block_number)? {
+                roots = stored_roots;
+                break;
+            }
+            first_block_number = block_number;
+        }
+        info!(
+            "Building the tries of blocks {first_block_number} to {last_block_number} from the \
+             stored state diffs."
+        );
+        for block_number in (first_block_number.0..=last_block_number.0).map(BlockNumber) {
+            let (state_diff, state_root) = {
+                let txn = self.storage_reader.begin_ro_txn()?;
+                let state_diff = txn
+                    .get_state_diff(block_number)?
+                    .ok_or(StateRootVerificationError::MissingStateDiff { block_number })?;
+                let state_root = txn
+                    .get_block_header(block_number)?
+                    .expect("Expecting to have a header for every state diff.")
+                    .block_header_without_hash
+                    .state_root;
+                (state_diff, state_root)
+            };
+            roots = self.commit_and_verify(block_number, &state_diff, state_root, roots).await?;
+        }
+        info!("Built the tries of blocks up to {last_block_number}.");
+        Ok(roots)
+    }
+
+    async fn commit_and_verify(
+        &mut self,
+        block_number: BlockNumber,
+        state_diff: &ThinStateDiff,
+        state_root: GlobalRoot,
+        parent_roots: TrieRoots,
+    ) -> StateRootVerificationResult<TrieRoots> {
+        let input = Input {
+            storage: HashMap::new(),
+            state_diff: to_committer_state_diff(state_diff),
+            contracts_trie_root_hash: parent_roots.contracts_trie_root,
+            classes_trie_root_hash: parent_roots.classes_trie_root,
+            config: ConfigImpl::new(false, LevelFilter::WARN),
+        };
+        let filled_forest = commit_block_with_storage(input, &self.storage).await?;
+        let roots = TrieRoots {
+            contracts_trie_root: filled_forest.get_contract_root_hash(),
+            classes_trie_root: filled_forest.get_compiled_class_root_hash(),
+        };
+        let computed_state_root = roots.global_root();
+        if computed_state_root != state_root {
+            return Err(StateRootVerificationError::StateRootMismatch {
+                block_number,
+                expected_state_root: state_root,
+                computed_state_root,
+            });
+        }
+        filled_forest.write_to_storage(&mut self.storage)?;
+        self.storage.set(trie_roots_key(block_number), serialize_trie_roots(&roots))?;
+        Ok(roots)
+    }
+
+    pub(crate) fn get_trie_roots(
+        &self,
+        block_number: BlockNumber,
+    ) -> StateRootVerificationResult<Option<TrieRoots>> {
+        Ok(self
+            .storage
+            .get(&trie_roots_key(block_number))?
+            .map(|value| deserialize_trie_roots(&value)))
+    }
+}
+
+fn trie_roots_key(block_number: BlockNumber) -> DbKey {
+    create_db_key(DbKeyPrefix::new(TRIE_ROOTS_PREFIX), &block_number.0.to_be_bytes())
+}
+
+fn serialize_trie_roots(roots: &TrieRoots) -> DbValue {
+    DbValue(
+        [roots.contracts_trie_root.0.to_bytes_be(), roots.classes_trie_root.0.to_bytes_be()]
+            .concat(),
+    )
+}
+
+fn deserialize_trie_roots(value: &DbValue) -> TrieRoots {
+    let (contracts_trie_root, classes_trie_root) = value.0.split_at(32);
+    TrieRoots {
+        contracts_trie_root: HashOutput(Felt::from_bytes_be_slice(contracts_trie_root)),
+        classes_trie_root: HashOutput(Felt::from_bytes_be_slice(classes_trie_root)),
+    }
+}
+
+pub(crate) fn to_committer_state_diff(state_diff: &ThinStateDiff) -> StateDiff {
+    StateDiff {
+        address_to_class_hash: state_diff
+            .deployed_contracts
+            .iter()
+            .map(|(address, class_hash)| {
+                (ContractAddress(*address.0.key()), ClassHash(class_hash.0))
+            })
+            .collect(),
+        address_to_nonce: state_diff
+            .nonces
+            .iter()
+            .map(|(address, nonce)| (ContractAddress(*address.0.key()), Nonce(nonce.0)))
+            .collect(),
+        class_hash_to_compiled_class_hash: state_diff
+            .declared_classes
+            .iter()
+            .map(|(class_hash, compiled_class_hash)| {
+                (ClassHash(class_hash.0), CompiledClassHash(compiled_class_hash.0))
+            })
+            .collect(),
+        storage_updates: state_diff
+            .storage_diffs
+            .iter()
+            .map(|(address, storage_diff)| {
+                (
+                    ContractAddress(*address.0.key()),
+                    storage_diff
+                        .iter()
+                        .map(|(key, value)| {
+                            (StarknetStorageKey(*key.0.key()), StarknetStorageValue(*value))
+                        })
+                        .collect(),
+                )
+            })
+            .collect(),
+    }
+}
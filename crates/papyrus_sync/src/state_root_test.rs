@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use assert_matches::assert_matches;
+use indexmap::indexmap;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageWriter;
+use starknet_api::block::{BlockHeader, BlockHeaderWithoutHash, BlockNumber};
+use starknet_api::core::{ClassHash, CompiledClassHash, GlobalRoot, Nonce};
+use starknet_api::state::ThinStateDiff;
+use starknet_api::{contract_address, felt, storage_key};
+use starknet_committer::block_committer::commit::commit_block_with_storage;
+use starknet_committer::block_committer::input::{ConfigImpl, Input};
+use starknet_patricia::hash::hash_trait::HashOutput;
+use starknet_patricia_storage::map_storage::MapStorage;
+use starknet_patricia_storage::mdbx_storage::MdbxStorageConfig;
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Pedersen, Poseidon, StarkHash};
+use tempfile::TempDir;
+use tracing::level_filters::LevelFilter;
+
+use crate::state_root::{
+    to_committer_state_diff,
+    StateRootVerificationError,
+    StateRootVerifier,
+    TrieRoots,
+};
+
+// Returns a verifier and a writer to the node storage it reads from, and the directories of both
+// storages.
+fn open_verifier() -> (StateRootVerifier, StorageWriter, (TempDir, TempDir)) {
+    let ((storage_reader, storage_writer), storage_temp_dir) = get_test_storage();
+    let temp_dir = TempDir::new().unwrap();
+    let config = MdbxStorageConfig {
+        path: temp_dir.path().to_path_buf(),
+        max_size: 1 << 30,
+        growth_step: 1 << 20,
+        cache_size: 1 << 10,
+    };
+    let verifier = StateRootVerifier::open(&config, storage_reader).unwrap();
+    (verifier, storage_writer, (temp_dir, storage_temp_dir))
+}
+
+fn store_block(
+    storage_writer: &mut StorageWriter,
+    block_number: BlockNumber,
+    state_diff: ThinStateDiff,
+    state_root: GlobalRoot,
+) {
+    let header = BlockHeader {
+        block_header_without_hash: BlockHeaderWithoutHash {
+            block_number,
+            state_root,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &header)
+        .unwrap()
+        .append_state_diff(block_number, state_diff)
+        .unwrap()
+        .commit()
+        .unwrap();
+}
+
+fn state_diffs() -> Vec<ThinStateDiff> {
+    vec![
+        ThinStateDiff {
+            deployed_contracts: indexmap! {
+                contract_address!("0x10") => ClassHash(felt!("0x100")),
+                contract_address!("0x11") => ClassHash(felt!("0x101")),
+            },
+            storage_diffs: indexmap! {
+                contract_address!("0x10") => indexmap! {
+                    storage_key!("0x1") => felt!("0x1000"),
+                    storage_key!("0x2") => felt!("0x1001"),
+                },
+            },
+            nonces: indexmap! { contract_address!("0x11") => Nonce(felt!("0x1")) },
+            ..Default::default()
+        },
+        ThinStateDiff {
+            storage_diffs: indexmap! {
+                contract_address!("0x11") => indexmap! { storage_key!("0x3") => felt!("0x1002") },
+            },
+            declared_classes: indexmap! {
+                ClassHash(felt!("0x102")) => CompiledClassHash(felt!("0x200")),
+            },
+            ..Default::default()
+        },
+    ]
+}
+
+// Computes the state roots of the given blocks independently of the verifier.
+async fn expected_state_roots(state_diffs: &[ThinStateDiff]) -> Vec<GlobalRoot> {
+    let mut storage = MapStorage::default();
+    let mut roots = TrieRoots::default();
+    let mut state_roots = Vec::new();
+    for state_diff in state_diffs {
+        let input = Input {
+            storage: HashMap::new(),
+            state_diff: to_committer_state_diff(state_diff),
+            contracts_trie_root_hash: roots.contracts_trie_root,
+            classes_trie_root_hash: roots.classes_trie_root,
+            config: ConfigImpl::new(false, LevelFilter::WARN),
+        };
+        let filled_forest = commit_block_with_storage(input, &storage).await.unwrap();
+        filled_forest.write_to_storage(&mut storage).unwrap();
+        roots = TrieRoots {
+            contracts_trie_root: filled_forest.get_contract_root_hash(),
+            classes_trie_root: filled_forest.get_compiled_class_root_hash(),
+        };
+        state_roots.push(roots.global_root());
+    }
+    state_roots
+}
+
+#[test]
+fn global_root_without_classes_is_contracts_trie_root() {
+    let contracts_trie_root = HashOutput(felt!("0x123"));
+    let roots = TrieRoots { contracts_trie_root, classes_trie_root: HashOutput::default() };
+    assert_eq!(roots.global_root(), GlobalRoot(contracts_trie_root.0));
+
+    let roots = TrieRoots { contracts_trie_root, classes_trie_root: HashOutput(felt!("0x456")) };
+    assert_ne!(roots.global_root(), GlobalRoot(contracts_trie_root.0));
+}
+
+// The height of the Starknet tries. The root of a trie with a single leaf is an edge node from the
+// root to the leaf, whose hash is H(leaf hash, leaf index) + 251.
+const TRIE_HEIGHT: u8 = 251;
+
+#[tokio::test]
+async fn verify_fixed_state_roots() {
+    let address = contract_address!("0x10");
+    let class_hash = ClassHash(felt!("0x100"));
+    let key = storage_key!("0x1");
+    let value = felt!("0x1000");
+    let declared_class_hash = ClassHash(felt!("0x102"));
+    let compiled_class_hash = CompiledClassHash(felt!("0x200"));
+    let state_diffs = [
+        ThinStateDiff {
+            deployed_contracts: indexmap! { address => class_hash },
+            storage_diffs: indexmap! { address => indexmap! { key => value } },
+            ..Default::default()
+        },
+        ThinStateDiff {
+            declared_classes: indexmap! { declared_class_hash => compiled_class_hash },
+            ..Default::default()
+        },
+    ];
+
+    // The roots are computed from the definitions of the Starknet state commitment, independently
+    // of the committer.
+    let storage_root = Pedersen::hash(&value, &key.0) + Felt::from(TRIE_HEIGHT);
+    let contract_state_hash = Pedersen::hash(
+        &Pedersen::hash(&Pedersen::hash(&class_hash.0, &storage_root), &Felt::ZERO),
+        &Felt::ZERO,
+    );
+    let contracts_trie_root =
+        Pedersen::hash(&contract_state_hash, address.0.key()) + Felt::from(TRIE_HEIGHT);
+    let class_leaf_hash = Poseidon::hash(
+        &Felt::from_bytes_be_slice(b"CONTRACT_CLASS_LEAF_V0"),
+        &compiled_class_hash.0,
+    );
+    let classes_trie_root =
+        Poseidon::hash(&class_leaf_hash, &declared_class_hash.0) + Felt::from(TRIE_HEIGHT);
+    // No classes were declared in the first block, so its state root is the contracts trie root.
+    let state_roots = [
+        GlobalRoot(contracts_trie_root),
+        GlobalRoot(Poseidon::hash_array(&[
+            Felt::from_bytes_be_slice(b"STARKNET_STATE_V0"),
+            contracts_trie_root,
+            classes_trie_root,
+        ])),
+    ];
+
+    let (mut verifier, _storage_writer, _temp_dirs) = open_verifier();
+    for (i, (state_diff, state_root)) in (0..).zip(state_diffs.iter().zip(state_roots)) {
+        verifier.verify(BlockNumber(i), state_diff, state_root).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn verify_state_roots() {
+    let state_diffs = state_diffs();
+    let state_roots = expected_state_roots(&state_diffs).await;
+    let (mut verifier, _storage_writer, _temp_dirs) = open_verifier();
+
+    for (i, (state_diff, state_root)) in (0..).zip(state_diffs.iter().zip(&state_roots)) {
+        verifier.verify(BlockNumber(i), state_diff, *state_root).await.unwrap();
+    }
+    assert_eq!(
+        verifier.get_trie_roots(BlockNumber(1)).unwrap().unwrap().global_root(),
+        state_roots[1]
+    );
+
+    // A reverted block is committed again on top of its parent.
+    verifier.verify(BlockNumber(1), &state_diffs[1], state_roots[1]).await.unwrap();
+}
+
+#[tokio::test]
+async fn state_root_mismatch() {
+    let state_diffs = state_diffs();
+    let state_roots = expected_state_roots(&state_diffs).await;
+    let (mut verifier, _storage_writer, _temp_dirs) = open_verifier();
+
+    verifier.verify(BlockNumber(0), &state_diffs[0], state_roots[0]).await.unwrap();
+    let result = verifier.verify(BlockNumber(1), &state_diffs[1], state_roots[0]).await;
+    assert_matches!(
+        result,
+        Err(StateRootVerificationError::StateRootMismatch { block_number: BlockNumber(1), expected_state_root, computed_state_root })
+        if expected_state_root == state_roots[0] && computed_state_root == state_roots[1]
+    );
+    assert_eq!(verifier.get_trie_roots(BlockNumber(1)).unwrap(), None);
+}
+
+#[tokio::test]
+async fn build_tries_from_stored_state_diffs() {
+    let state_diffs = state_diffs();
+    let state_roots = expected_state_roots(&state_diffs).await;
+    let (mut verifier, mut storage_writer, _temp_dirs) = open_verifier();
+    store_block(&mut storage_writer, BlockNumber(0), state_diffs[0].clone(), state_roots[0]);
+
+    // The roots of block 0 are missing, so they are built from its stored state diff.
+    verifier.verify(BlockNumber(1), &state_diffs[1], state_roots[1]).await.unwrap();
+    assert_eq!(
+        verifier.get_trie_roots(BlockNumber(0)).unwrap().unwrap().global_root(),
+        state_roots[0]
+    );
+    assert_eq!(
+        verifier.get_trie_roots(BlockNumber(1)).unwrap().unwrap().global_root(),
+        state_roots[1]
+    );
+}
+
+#[tokio::test]
+async fn stored_state_root_mismatch() {
+    let state_diffs = state_diffs();
+    let state_roots = expected_state_roots(&state_diffs).await;
+    let (mut verifier, mut storage_writer, _temp_dirs) = open_verifier();
+    store_block(&mut storage_writer, BlockNumber(0), state_diffs[0].clone(), state_roots[1]);
+
+    let result = verifier.verify(BlockNumber(1), &state_diffs[1], state_roots[1]).await;
+    assert_matches!(
+        result,
+        Err(StateRootVerificationError::StateRootMismatch { block_number: BlockNumber(0), .. })
+    );
+    assert_eq!(verifier.get_trie_roots(BlockNumber(0)).unwrap(), None);
+}
+
+#[tokio::test]
+async fn missing_parent_state_diff() {
+    let (mut verifier, _storage_writer, _temp_dirs) = open_verifier();
+    let result = verifier.verify(BlockNumber(1), &state_diffs()[1], GlobalRoot::default()).await;
+    assert_matches!(
+        result,
+        Err(StateRootVerificationError::MissingStateDiff { block_number: BlockNumber(0) })
+    );
+}
@@ -195,6 +195,7 @@ fn store_base_layer_block_test() {
         writer,
         sequencer_pub_key: None,
         class_manager_client: None,
+        state_root_verifier: None,
     };
 
     // Trying to store a block without a header in the storage.
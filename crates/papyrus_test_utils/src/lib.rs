@@ -450,6 +450,8 @@ auto_impl_get_test_instance! {
         pub timestamp: BlockTimestamp,
         pub l1_da_mode: L1DataAvailabilityMode,
         pub starknet_version: StarknetVersion,
+        pub l2_gas_consumed: GasAmount,
+        pub next_l2_gas_price: GasPrice,
     }
     pub struct BlockNumber(pub u64);
     pub struct BlockSignature(pub Signature);
@@ -208,6 +208,10 @@ pub struct BlockHeaderWithoutHash {
     pub timestamp: BlockTimestamp,
     pub l1_da_mode: L1DataAvailabilityMode,
     pub starknet_version: StarknetVersion,
+    /// The L2 gas consumed by the block's transactions.
+    pub l2_gas_consumed: GasAmount,
+    /// The L2 gas price (in fri) of the next block, derived from this block by EIP-1559.
+    pub next_l2_gas_price: GasPrice,
 }
 
 /// The [transactions](`crate::transaction::Transaction`) and their
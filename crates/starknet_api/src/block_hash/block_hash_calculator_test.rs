@@ -45,7 +45,8 @@ macro_rules! test_hash_changes {
             let header = BlockHeaderWithoutHash {
                 l1_da_mode: L1DataAvailabilityMode::Blob,
                 starknet_version: BlockHashVersion::V0_13_4.into(),
-                $($header_field: $header_value),*
+                $($header_field: $header_value),*,
+                ..Default::default()
             };
             let commitments = BlockHeaderCommitments {
                 $($commitments_field: $commitments_value),*
@@ -90,6 +91,7 @@ fn test_block_hash_regression(
         l2_gas_price: GasPricePerToken { price_in_fri: 11_u8.into(), price_in_wei: 12_u8.into() },
         starknet_version: block_hash_version.clone().into(),
         parent_hash: BlockHash(Felt::from(11_u8)),
+        ..Default::default()
     };
     let transactions_data = vec![TransactionHashingData {
         transaction_signature: TransactionSignature(vec![Felt::TWO, Felt::THREE]),
@@ -11,6 +11,7 @@ use starknet_api::block::{
     BlockHeaderWithoutHash,
     BlockNumber,
     BlockTimestamp,
+    GasPrice,
     GasPricePerToken,
     StarknetVersion,
 };
@@ -23,6 +24,7 @@ use starknet_api::core::{
     TransactionCommitment,
 };
 use starknet_api::data_availability::L1DataAvailabilityMode;
+use starknet_api::execution_resources::GasAmount;
 #[cfg(doc)]
 use starknet_api::transaction::TransactionOutput as starknet_api_transaction_output;
 use starknet_api::transaction::{TransactionHash, TransactionOffsetInBlock};
@@ -329,6 +331,8 @@ impl Block {
                 l1_data_gas_price: self.l1_data_gas_price(),
                 l1_da_mode: self.l1_da_mode(),
                 starknet_version: self.starknet_version(),
+                l2_gas_consumed: GasAmount(self.l2_gas_consumed().unwrap_or_default()),
+                next_l2_gas_price: GasPrice(self.next_l2_gas_price().unwrap_or_default().into()),
             },
             state_diff_commitment: self.state_diff_commitment(),
             transaction_commitment,
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use starknet_patricia::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices};
 use starknet_patricia_storage::map_storage::MapStorage;
+use starknet_patricia_storage::storage_trait::Storage;
 use tracing::{info, warn};
 
 use crate::block_committer::errors::BlockCommitmentError;
@@ -15,7 +16,18 @@ use crate::patricia_merkle_tree::types::{ClassHash, Nonce};
 
 type BlockCommitmentResult<T> = Result<T, BlockCommitmentError>;
 
-pub async fn commit_block(input: Input<ConfigImpl>) -> BlockCommitmentResult<FilledForest> {
+pub async fn commit_block(mut input: Input<ConfigImpl>) -> BlockCommitmentResult<FilledForest> {
+    let storage = MapStorage::from(std::mem::take(&mut input.storage));
+    commit_block_with_storage(input, &storage).await
+}
+
+/// Commits the state diff of the input on top of the tries in the given storage. The `storage`
+/// field of the input is ignored. The new nodes are not written to the storage, see
+/// [FilledForest::write_to_storage].
+pub async fn commit_block_with_storage(
+    input: Input<ConfigImpl>,
+    storage: &impl Storage,
+) -> BlockCommitmentResult<FilledForest> {
     let (mut storage_tries_indices, mut contracts_trie_indices, mut classes_trie_indices) =
         get_all_modified_indices(&input.state_diff);
     let forest_sorted_indices = ForestSortedIndices {
@@ -29,7 +41,7 @@ pub async fn commit_block(input: Input<ConfigImpl>) -> BlockCommitmentResult<Fil
     let actual_storage_updates = input.state_diff.actual_storage_updates();
     let actual_classes_updates = input.state_diff.actual_classes_updates();
     let (mut original_forest, original_contracts_trie_leaves) = OriginalSkeletonForest::create(
-        MapStorage::from(input.storage),
+        storage,
         input.contracts_trie_root_hash,
         input.classes_trie_root_hash,
         &actual_storage_updates,
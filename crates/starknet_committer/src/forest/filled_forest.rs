@@ -5,7 +5,7 @@ use starknet_patricia::patricia_merkle_tree::filled_tree::tree::FilledTree;
 use starknet_patricia::patricia_merkle_tree::node_data::leaf::LeafModifications;
 use starknet_patricia::patricia_merkle_tree::types::NodeIndex;
 use starknet_patricia::patricia_merkle_tree::updated_skeleton_tree::tree::UpdatedSkeletonTreeImpl;
-use starknet_patricia_storage::storage_trait::Storage;
+use starknet_patricia_storage::storage_trait::{Storage, StorageResult};
 use tracing::info;
 
 use crate::block_committer::input::{ContractAddress, StarknetStorageValue};
@@ -29,7 +29,7 @@ pub struct FilledForest {
 }
 
 impl FilledForest {
-    pub fn write_to_storage(&self, storage: &mut impl Storage) {
+    pub fn write_to_storage(&self, storage: &mut impl Storage) -> StorageResult<()> {
         // Serialize all trees to one hash map.
         let new_db_objects = self
             .storage_tries
@@ -40,7 +40,7 @@ impl FilledForest {
             .collect();
 
         // Store the new hash map
-        storage.mset(new_db_objects);
+        storage.mset(new_db_objects)
     }
 
     pub fn get_contract_root_hash(&self) -> HashOutput {
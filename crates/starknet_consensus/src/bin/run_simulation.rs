@@ -329,9 +329,8 @@ async fn build_node(data_dir: &str, logs_dir: &str, i: usize, papyrus_args: &Pap
         ));
     } else {
         cmd.push_str(&format!(
-            "--network.bootstrap_peer_multiaddr.#is_none false --network.bootstrap_peer_multiaddr \
-             /ip4/127.0.0.1/tcp/{}/p2p/{} 2>&1 | sed -r 's/\\x1B\\[[0-9;]*[mK]//g' > \
-             {}/validator0x{:x}.txt",
+            "--network.bootstrap_peer_multiaddrs /ip4/127.0.0.1/tcp/{}/p2p/{} 2>&1 | sed -r \
+             's/\\x1B\\[[0-9;]*[mK]//g' > {}/validator0x{:x}.txt",
             *BOOTNODE_TCP_PORT, BOOT_NODE_PEER_ID, logs_dir, validator_id
         ));
     }
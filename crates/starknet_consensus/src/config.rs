@@ -9,11 +9,16 @@ use papyrus_config::converters::{
     deserialize_float_seconds_to_duration,
     deserialize_seconds_to_duration,
 };
-use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_optional_sub_config,
+    ser_param,
+    SerializeConfig,
+};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_protobuf::consensus::DEFAULT_VALIDATOR_ID;
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 use crate::types::ValidatorId;
 
@@ -26,6 +31,7 @@ pub struct ConsensusConfig {
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
     pub startup_delay: Duration,
     /// Timeouts configuration for consensus.
+    #[validate]
     pub timeouts: TimeoutsConfig,
     /// The duration (seconds) between sync attempts.
     #[serde(deserialize_with = "deserialize_float_seconds_to_duration")]
@@ -98,7 +104,11 @@ impl Default for ConsensusConfig {
 }
 
 /// Configuration for consensus timeouts.
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+///
+/// Following the Tendermint paper, each timeout grows linearly with the round number:
+/// `timeout(round) = base + round * delta`. If `adaptive` is set, the base is derived from the
+/// observed network latency instead of the fixed value.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Validate)]
 pub struct TimeoutsConfig {
     /// The timeout for a proposal.
     #[serde(deserialize_with = "deserialize_float_seconds_to_duration")]
@@ -109,11 +119,23 @@ pub struct TimeoutsConfig {
     /// The timeout for a precommit.
     #[serde(deserialize_with = "deserialize_float_seconds_to_duration")]
     pub precommit_timeout: Duration,
+    /// The increment of the proposal timeout per round.
+    #[serde(deserialize_with = "deserialize_float_seconds_to_duration")]
+    pub proposal_timeout_delta: Duration,
+    /// The increment of the prevote timeout per round.
+    #[serde(deserialize_with = "deserialize_float_seconds_to_duration")]
+    pub prevote_timeout_delta: Duration,
+    /// The increment of the precommit timeout per round.
+    #[serde(deserialize_with = "deserialize_float_seconds_to_duration")]
+    pub precommit_timeout_delta: Duration,
+    /// If set, the base timeouts adapt to the observed proposal and quorum latencies.
+    #[validate]
+    pub adaptive: Option<AdaptiveTimeoutsConfig>,
 }
 
 impl SerializeConfig for TimeoutsConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        BTreeMap::from_iter([
+        let mut config = BTreeMap::from_iter([
             ser_param(
                 "proposal_timeout",
                 &self.proposal_timeout.as_secs_f64(),
@@ -132,7 +154,27 @@ impl SerializeConfig for TimeoutsConfig {
                 "The timeout (seconds) for a precommit.",
                 ParamPrivacyInput::Public,
             ),
-        ])
+            ser_param(
+                "proposal_timeout_delta",
+                &self.proposal_timeout_delta.as_secs_f64(),
+                "The increment (seconds) of the proposal timeout per round.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "prevote_timeout_delta",
+                &self.prevote_timeout_delta.as_secs_f64(),
+                "The increment (seconds) of the prevote timeout per round.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "precommit_timeout_delta",
+                &self.precommit_timeout_delta.as_secs_f64(),
+                "The increment (seconds) of the precommit timeout per round.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
+        config.extend(ser_optional_sub_config(&self.adaptive, "adaptive"));
+        config
     }
 }
 
@@ -142,6 +184,154 @@ impl Default for TimeoutsConfig {
             proposal_timeout: Duration::from_secs_f64(3.0),
             prevote_timeout: Duration::from_secs_f64(1.0),
             precommit_timeout: Duration::from_secs_f64(1.0),
+            proposal_timeout_delta: Duration::from_secs_f64(0.5),
+            prevote_timeout_delta: Duration::from_secs_f64(0.1),
+            precommit_timeout_delta: Duration::from_secs_f64(0.1),
+            adaptive: None,
+        }
+    }
+}
+
+/// Configuration for timeouts which adapt to the observed network latency.
+///
+/// The latencies are tracked as an exponentially weighted moving average (EWMA). The base timeout
+/// is `latency_multiplier * EWMA`, clamped to `[min_timeout, max_timeout]`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Validate)]
+#[validate(schema(function = "validate_adaptive_timeouts_config"))]
+pub struct AdaptiveTimeoutsConfig {
+    /// The weight given to a new latency sample in the EWMA.
+    #[validate(range(min = 0.0, max = 1.0))]
+    pub ewma_alpha: f64,
+    /// The factor by which the latency estimate is multiplied to get the base timeout.
+    #[validate(range(min = 1.0))]
+    pub latency_multiplier: f64,
+    /// The lower bound for an adaptive base timeout.
+    #[serde(deserialize_with = "deserialize_float_seconds_to_duration")]
+    pub min_timeout: Duration,
+    /// The upper bound for an adaptive base timeout.
+    #[serde(deserialize_with = "deserialize_float_seconds_to_duration")]
+    pub max_timeout: Duration,
+}
+
+impl SerializeConfig for AdaptiveTimeoutsConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "ewma_alpha",
+                &self.ewma_alpha,
+                "The weight (0 to 1) given to a new latency sample in the moving average.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "latency_multiplier",
+                &self.latency_multiplier,
+                "The factor by which the latency estimate is multiplied to get the base timeout.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "min_timeout",
+                &self.min_timeout.as_secs_f64(),
+                "The lower bound (seconds) for an adaptive base timeout.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_timeout",
+                &self.max_timeout.as_secs_f64(),
+                "The upper bound (seconds) for an adaptive base timeout.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+fn validate_adaptive_timeouts_config(
+    config: &AdaptiveTimeoutsConfig,
+) -> Result<(), ValidationError> {
+    if config.min_timeout > config.max_timeout {
+        return Err(ValidationError::new("min_timeout must not be greater than max_timeout"));
+    }
+    Ok(())
+}
+
+impl Default for AdaptiveTimeoutsConfig {
+    fn default() -> Self {
+        Self {
+            ewma_alpha: 0.2,
+            latency_multiplier: 3.0,
+            min_timeout: Duration::from_secs_f64(0.5),
+            max_timeout: Duration::from_secs_f64(10.0),
+        }
+    }
+}
+
+/// Configuration for the stream handler, which delivers proposals streamed over the network.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Validate)]
+pub struct StreamHandlerConfig {
+    /// The maximal number of messages in a single stream. Larger streams are dropped.
+    #[validate(range(min = 1))]
+    pub max_messages_per_stream: u64,
+    /// The maximal number of inbound streams a single peer may have open concurrently.
+    #[validate(range(min = 1))]
+    pub max_streams_per_peer: usize,
+    /// How long an inbound stream may go without progress before requesting the missing
+    /// messages from the stream's originator.
+    #[serde(deserialize_with = "deserialize_float_seconds_to_duration")]
+    pub retransmission_timeout: Duration,
+    /// How many times to request the missing messages of a stream before dropping it.
+    pub max_retransmission_attempts: u32,
+    /// The maximal total size (bytes) of the messages of our recent outbound streams, kept in
+    /// order to answer retransmission requests. The oldest streams are evicted first.
+    pub retransmission_cache_bytes: usize,
+}
+
+impl SerializeConfig for StreamHandlerConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "max_messages_per_stream",
+                &self.max_messages_per_stream,
+                "The maximal number of messages in a single stream. Larger streams are dropped.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_streams_per_peer",
+                &self.max_streams_per_peer,
+                "The maximal number of inbound streams a single peer may have open concurrently.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "retransmission_timeout",
+                &self.retransmission_timeout.as_secs_f64(),
+                "How long (seconds) an inbound stream may go without progress before requesting \
+                 the missing messages from the stream's originator.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_retransmission_attempts",
+                &self.max_retransmission_attempts,
+                "How many times to request the missing messages of a stream before dropping it.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "retransmission_cache_bytes",
+                &self.retransmission_cache_bytes,
+                "The maximal total size (bytes) of the messages of our recent outbound streams, \
+                 kept in order to answer retransmission requests. The oldest streams are evicted \
+                 first.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+impl Default for StreamHandlerConfig {
+    fn default() -> Self {
+        Self {
+            max_messages_per_stream: 10000,
+            max_streams_per_peer: 20,
+            retransmission_timeout: Duration::from_secs_f64(1.0),
+            max_retransmission_attempts: 3,
+            retransmission_cache_bytes: 1 << 25, // 32MB
         }
     }
 }
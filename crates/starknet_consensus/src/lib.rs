@@ -35,9 +35,12 @@ pub mod simulation_network_receiver;
 pub mod stream_handler;
 
 mod manager;
+#[cfg(test)]
+pub(crate) mod simulator;
 #[allow(missing_docs)]
 mod single_height_consensus;
 #[allow(missing_docs)]
 mod state_machine;
 #[cfg(test)]
 pub(crate) mod test_utils;
+mod timeouts;
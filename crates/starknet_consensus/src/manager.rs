@@ -27,6 +27,7 @@ use tracing::{debug, error, info, instrument, trace, warn};
 use crate::config::TimeoutsConfig;
 use crate::metrics::{register_metrics, CONSENSUS_BLOCK_NUMBER};
 use crate::single_height_consensus::{ShcReturn, SingleHeightConsensus};
+use crate::timeouts::TimeoutStrategy;
 use crate::types::{BroadcastVoteChannel, ConsensusContext, ConsensusError, Decision, ValidatorId};
 
 /// Run consensus indefinitely.
@@ -128,7 +129,7 @@ struct MultiHeightManager<ContextT: ConsensusContext> {
     future_votes: BTreeMap<u64, Vec<Vote>>,
     // Mapping: { Height : { Round : (Init, Receiver)}}
     cached_proposals: BTreeMap<u64, BTreeMap<u32, ProposalReceiverTuple<ContextT::ProposalPart>>>,
-    timeouts: TimeoutsConfig,
+    timeouts: TimeoutStrategy,
 }
 
 impl<ContextT: ConsensusContext> MultiHeightManager<ContextT> {
@@ -138,7 +139,7 @@ impl<ContextT: ConsensusContext> MultiHeightManager<ContextT> {
             validator_id,
             future_votes: BTreeMap::new(),
             cached_proposals: BTreeMap::new(),
-            timeouts,
+            timeouts: TimeoutStrategy::new(timeouts),
         }
     }
 
@@ -187,9 +188,34 @@ impl<ContextT: ConsensusContext> MultiHeightManager<ContextT> {
             validators,
             self.timeouts.clone(),
         );
+        let res = self
+            .run_shc(
+                context,
+                height,
+                &mut shc,
+                sync_retry_interval,
+                broadcast_channels,
+                proposal_receiver,
+            )
+            .await;
+        // Carry the latencies observed during this height into the next one.
+        self.timeouts = shc.timeouts().clone();
+        res
+    }
+
+    // Drive `shc` until a decision is reached, either by consensus or via sync.
+    async fn run_shc(
+        &mut self,
+        context: &mut ContextT,
+        height: BlockNumber,
+        shc: &mut SingleHeightConsensus,
+        sync_retry_interval: Duration,
+        broadcast_channels: &mut BroadcastVoteChannel,
+        proposal_receiver: &mut mpsc::Receiver<mpsc::Receiver<ContextT::ProposalPart>>,
+    ) -> Result<RunHeightRes, ConsensusError> {
         let mut shc_events = FuturesUnordered::new();
 
-        match self.start_height(context, height, &mut shc).await? {
+        match self.start_height(context, height, shc).await? {
             ShcReturn::Decision(decision) => return Ok(RunHeightRes::Decision(decision)),
             ShcReturn::Tasks(tasks) => {
                 for task in tasks {
@@ -203,10 +229,10 @@ impl<ContextT: ConsensusContext> MultiHeightManager<ContextT> {
             let shc_return = tokio::select! {
                 message = broadcast_channels.broadcasted_messages_receiver.next() => {
                     self.handle_vote(
-                        context, height, &mut shc, message, broadcast_channels).await?
+                        context, height, shc, message, broadcast_channels).await?
                 },
                 Some(content_receiver) = proposal_receiver.next() => {
-                    self.handle_proposal(context, height, &mut shc, content_receiver).await?
+                    self.handle_proposal(context, height, shc, content_receiver).await?
                 },
                 Some(shc_event) = shc_events.next() => {
                     shc.handle_event(context, shc_event).await?
@@ -29,6 +29,7 @@ lazy_static! {
         prevote_timeout: Duration::from_millis(100),
         precommit_timeout: Duration::from_millis(100),
         proposal_timeout: Duration::from_millis(100),
+        ..Default::default()
     };
 }
 
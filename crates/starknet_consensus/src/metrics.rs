@@ -4,9 +4,15 @@ use starknet_sequencer_metrics::metrics::MetricGauge;
 define_metrics!(
     Consensus => {
         MetricGauge { CONSENSUS_BLOCK_NUMBER, "consensus_block_number", "The block number consensus is working to decide" },
+        MetricGauge { CONSENSUS_PROPOSAL_TIMEOUT, "consensus_proposal_timeout", "The proposal timeout (seconds) most recently scheduled by consensus" },
+        MetricGauge { CONSENSUS_PREVOTE_TIMEOUT, "consensus_prevote_timeout", "The prevote timeout (seconds) most recently scheduled by consensus" },
+        MetricGauge { CONSENSUS_PRECOMMIT_TIMEOUT, "consensus_precommit_timeout", "The precommit timeout (seconds) most recently scheduled by consensus" },
     },
 );
 
 pub(crate) fn register_metrics() {
     CONSENSUS_BLOCK_NUMBER.register();
+    CONSENSUS_PROPOSAL_TIMEOUT.register();
+    CONSENSUS_PREVOTE_TIMEOUT.register();
+    CONSENSUS_PRECOMMIT_TIMEOUT.register();
 }
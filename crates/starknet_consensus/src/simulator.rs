@@ -0,0 +1,659 @@
+//! A deterministic, in-process simulator for running multiple nodes of consensus.
+//!
+//! [`Simulation`] runs `N` instances of [`run_consensus`] on a single runtime, each with a
+//! [`SimContext`] which routes its messages through a shared [`SimNetwork`]. The simulation is
+//! meant to be run with tokio's paused clock, so time is virtual and timeouts are reached without
+//! actually waiting.
+//!
+//! Faults are a pure function of the seed and the message being sent, similar to
+//! [`NetworkReceiver`](crate::simulation_network_receiver::NetworkReceiver), so they are
+//! repeatable regardless of the order in which the runtime schedules the nodes. Supported faults:
+//! - Network partitions over a window of time.
+//! - Dropped, delayed and reordered messages before the global stabilization time (GST).
+//! - Byzantine validators, see [`ByzantineBehavior`].
+//! - Nodes crashing and restarting from their last decided height.
+//!
+//! The simulation checks safety (no two different decisions for the same height) throughout the
+//! run, and liveness by running until every honest node reaches a target height.
+
+#[cfg(test)]
+#[path = "simulator_test.rs"]
+mod simulator_test;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::channel::{mpsc, oneshot};
+use futures::{SinkExt, StreamExt};
+use papyrus_network::network_manager::test_utils::{
+    mock_register_broadcast_topic,
+    MockBroadcastedMessagesSender,
+    TestSubscriberChannels,
+};
+use papyrus_network_types::network_types::BroadcastedMessageMetadata;
+use papyrus_protobuf::consensus::{
+    ConsensusBlockInfo,
+    ProposalFin,
+    ProposalInit,
+    ProposalPart,
+    Vote,
+    DEFAULT_VALIDATOR_ID,
+};
+use papyrus_test_utils::{get_rng, GetTestInstance};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::data_availability::L1DataAvailabilityMode;
+use starknet_types_core::felt::Felt;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{debug, info};
+
+use crate::config::TimeoutsConfig;
+use crate::run_consensus;
+use crate::types::{ConsensusContext, ConsensusError, ProposalCommitment, Round, ValidatorId};
+
+const CHANNEL_SIZE: usize = 1000;
+const SYNC_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+const LIVENESS_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Misbehavior of a Byzantine validator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ByzantineBehavior {
+    /// Send a vote for a different block to half of the peers.
+    Equivocate,
+    /// Build proposals but never send them to the peers.
+    WithholdProposals,
+    /// Send proposals whose content doesn't match the proposal commitment.
+    InvalidProposals,
+}
+
+/// Messages between nodes in different groups are dropped while the partition is active.
+#[derive(Debug, Clone)]
+pub(crate) struct Partition {
+    pub start: Duration,
+    pub end: Duration,
+    /// Nodes, by index, which can communicate with each other. Nodes which don't appear in any
+    /// group are isolated.
+    pub groups: Vec<Vec<usize>>,
+}
+
+/// A node which crashes at `at` and restarts `restart_after` later.
+#[derive(Debug, Clone)]
+pub(crate) struct Crash {
+    pub node: usize,
+    pub at: Duration,
+    pub restart_after: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SimulationConfig {
+    pub num_nodes: usize,
+    pub seed: u64,
+    /// The run succeeds once every honest node decided (or synced) this height.
+    pub target_height: u64,
+    /// The virtual time after which the run is stopped, even if the target wasn't reached.
+    pub time_limit: Duration,
+    /// Global stabilization time. After it, no messages are dropped and every message arrives
+    /// within `max_delay_after_gst`.
+    pub gst: Duration,
+    pub drop_probability: f64,
+    pub max_delay_before_gst: Duration,
+    pub max_delay_after_gst: Duration,
+    /// How long after a decision it can be learned by other nodes via sync.
+    pub sync_delay: Duration,
+    pub timeouts: TimeoutsConfig,
+    pub partitions: Vec<Partition>,
+    /// Misbehaving nodes, by index.
+    pub byzantine: HashMap<usize, ByzantineBehavior>,
+    pub crashes: Vec<Crash>,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            num_nodes: 4,
+            seed: 0,
+            target_height: 5,
+            time_limit: Duration::from_secs(600),
+            gst: Duration::ZERO,
+            drop_probability: 0.0,
+            max_delay_before_gst: Duration::from_millis(500),
+            max_delay_after_gst: Duration::from_millis(100),
+            sync_delay: Duration::from_secs(30),
+            timeouts: TimeoutsConfig::default(),
+            partitions: Vec::new(),
+            byzantine: HashMap::new(),
+            crashes: Vec::new(),
+        }
+    }
+}
+
+/// Two nodes decided on different blocks for the same height.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SafetyViolation {
+    pub height: u64,
+    pub first: (usize, ProposalCommitment),
+    pub second: (usize, ProposalCommitment),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SimulationReport {
+    /// Mapping: { Height : { Node : (Decision round, Decided block) } }. Only includes decisions
+    /// reached via consensus, not via sync.
+    pub decisions: BTreeMap<u64, BTreeMap<usize, (Round, ProposalCommitment)>>,
+    pub safety_violations: Vec<SafetyViolation>,
+    /// The virtual time at which all honest nodes reached the target height, if they did.
+    pub target_reached_at: Option<Duration>,
+}
+
+impl SimulationReport {
+    /// Asserts that no safety violations occurred and that the honest nodes reached the target
+    /// height within `bound` after GST.
+    pub(crate) fn assert_safe_and_live(&self, gst: Duration, bound: Duration) {
+        assert!(self.safety_violations.is_empty(), "{:?}", self.safety_violations);
+        let reached_at = self.target_reached_at.expect("Honest nodes didn't reach the target.");
+        assert!(
+            reached_at <= gst + bound,
+            "Target reached at {reached_at:?}, expected by {:?}",
+            gst + bound
+        );
+    }
+}
+
+/// The decisions shared by all nodes. Used to check safety and to simulate sync.
+#[derive(Debug, Default)]
+struct Ledger {
+    // Mapping: { Height : (Node, Block, Time) }, the first decision for each height.
+    first_decisions: BTreeMap<u64, (usize, ProposalCommitment, Duration)>,
+    decisions: BTreeMap<u64, BTreeMap<usize, (Round, ProposalCommitment)>>,
+    safety_violations: Vec<SafetyViolation>,
+    // The last height each node decided or synced.
+    node_heights: HashMap<usize, u64>,
+}
+
+impl Ledger {
+    fn record_decision(
+        &mut self,
+        node: usize,
+        height: u64,
+        round: Round,
+        block: ProposalCommitment,
+        now: Duration,
+    ) {
+        let (first_node, first_block, _) =
+            *self.first_decisions.entry(height).or_insert((node, block, now));
+        if first_block != block {
+            self.safety_violations.push(SafetyViolation {
+                height,
+                first: (first_node, first_block),
+                second: (node, block),
+            });
+        }
+        self.decisions.entry(height).or_default().insert(node, (round, block));
+        self.record_height(node, height);
+    }
+
+    fn record_height(&mut self, node: usize, height: u64) {
+        let node_height = self.node_heights.entry(node).or_default();
+        *node_height = (*node_height).max(height);
+    }
+}
+
+struct NodeInbox {
+    votes: mpsc::UnboundedSender<Vote>,
+    proposals: mpsc::UnboundedSender<mpsc::Receiver<ProposalPart>>,
+}
+
+/// Routes messages between the nodes, injecting faults according to the config.
+pub(crate) struct SimNetwork {
+    config: SimulationConfig,
+    start: Instant,
+    // `None` while a node is crashed.
+    inboxes: Vec<Option<NodeInbox>>,
+    // Used so that resends of the same message get a new fault decision.
+    sent_count: HashMap<u64, u64>,
+}
+
+impl SimNetwork {
+    fn new(config: SimulationConfig) -> Self {
+        let inboxes = (0..config.num_nodes).map(|_| None).collect();
+        Self { config, start: Instant::now(), inboxes, sent_count: HashMap::new() }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn is_partitioned(&self, from: usize, to: usize, now: Duration) -> bool {
+        self.config.partitions.iter().any(|partition| {
+            (partition.start..partition.end).contains(&now)
+                && !partition
+                    .groups
+                    .iter()
+                    .any(|group| group.contains(&from) && group.contains(&to))
+        })
+    }
+
+    // Returns the delay with which the message should be delivered, or `None` if it is dropped.
+    fn route(&mut self, from: usize, to: usize, message_hash: u64) -> Option<Duration> {
+        let now = self.elapsed();
+        if self.is_partitioned(from, to, now) {
+            return None;
+        }
+        let mut hasher = DefaultHasher::new();
+        (self.config.seed, from, to, message_hash).hash(&mut hasher);
+        let count = self.sent_count.entry(hasher.finish()).or_default();
+        *count += 1;
+        count.hash(&mut hasher);
+        let drop_sample = hasher.finish();
+        drop_sample.hash(&mut hasher);
+        let delay_sample = hasher.finish();
+
+        let before_gst = now < self.config.gst;
+        if before_gst && to_probability(drop_sample) < self.config.drop_probability {
+            return None;
+        }
+        let max_delay = if before_gst {
+            self.config.max_delay_before_gst
+        } else {
+            self.config.max_delay_after_gst
+        };
+        Some(max_delay.mul_f64(to_probability(delay_sample)))
+    }
+
+    fn send_vote(&mut self, from: usize, to: usize, vote: Vote) {
+        let mut hasher = DefaultHasher::new();
+        vote.hash(&mut hasher);
+        let Some(delay) = self.route(from, to, hasher.finish()) else {
+            debug!(from, to, ?vote, "Dropping vote.");
+            return;
+        };
+        let Some(inbox) = &self.inboxes[to] else {
+            return;
+        };
+        let sender = inbox.votes.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            // The node may have crashed in the meantime.
+            let _ = sender.unbounded_send(vote);
+        });
+    }
+
+    fn send_proposal(
+        &mut self,
+        from: usize,
+        to: usize,
+        init: ProposalInit,
+        parts: Vec<ProposalPart>,
+    ) {
+        let mut hasher = DefaultHasher::new();
+        (init.height.0, init.round, init.valid_round, init.proposer).hash(&mut hasher);
+        let Some(delay) = self.route(from, to, hasher.finish()) else {
+            debug!(from, to, ?init, "Dropping proposal.");
+            return;
+        };
+        let Some(inbox) = &self.inboxes[to] else {
+            return;
+        };
+        let sender = inbox.proposals.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let (mut content_sender, content_receiver) = mpsc::channel(CHANNEL_SIZE);
+            for part in parts {
+                content_sender.send(part).await.expect("Content receiver should be alive");
+            }
+            let _ = sender.unbounded_send(content_receiver);
+        });
+    }
+}
+
+fn to_probability(sample: u64) -> f64 {
+    #[allow(clippy::as_conversions)]
+    let prob = (sample as f64) / (u64::MAX as f64);
+    prob
+}
+
+fn validator_id(node: usize) -> ValidatorId {
+    ValidatorId::from(DEFAULT_VALIDATOR_ID + u64::try_from(node).expect("Node index fits in u64"))
+}
+
+// The content of an honest proposal. The commitment is derived from it, so reproposals in later
+// rounds keep the same commitment.
+fn block_info(height: BlockNumber, builder: ValidatorId, round: Round) -> ConsensusBlockInfo {
+    ConsensusBlockInfo {
+        height,
+        timestamp: round.into(),
+        builder,
+        l1_da_mode: L1DataAvailabilityMode::Calldata,
+        l2_gas_price_fri: 0,
+        l1_gas_price_wei: 0,
+        l1_data_gas_price_wei: 0,
+        eth_to_fri_rate: 0,
+    }
+}
+
+fn commitment(block_info: &ConsensusBlockInfo) -> ProposalCommitment {
+    let mut hasher = DefaultHasher::new();
+    (block_info.height.0, block_info.timestamp, block_info.builder).hash(&mut hasher);
+    BlockHash(Felt::from(hasher.finish()))
+}
+
+/// The context of a single simulated node.
+pub(crate) struct SimContext {
+    node: usize,
+    validators: Vec<ValidatorId>,
+    behavior: Option<ByzantineBehavior>,
+    network: Arc<Mutex<SimNetwork>>,
+    ledger: Arc<Mutex<Ledger>>,
+    // The content of proposals this node built or validated, used for reproposals.
+    proposals: Arc<Mutex<HashMap<ProposalCommitment, ConsensusBlockInfo>>>,
+}
+
+impl SimContext {
+    fn broadcast_proposal(&self, init: ProposalInit, parts: Vec<ProposalPart>) {
+        let mut network = self.network.lock().unwrap();
+        for to in (0..self.validators.len()).filter(|to| *to != self.node) {
+            network.send_proposal(self.node, to, init, parts.clone());
+        }
+    }
+}
+
+#[async_trait]
+impl ConsensusContext for SimContext {
+    type ProposalPart = ProposalPart;
+
+    async fn build_proposal(
+        &mut self,
+        init: ProposalInit,
+        _timeout: Duration,
+    ) -> oneshot::Receiver<ProposalCommitment> {
+        let block_info = block_info(init.height, init.proposer, init.round);
+        let id = commitment(&block_info);
+        self.proposals.lock().unwrap().insert(id, block_info.clone());
+
+        match self.behavior {
+            Some(ByzantineBehavior::WithholdProposals) => {
+                debug!(node = self.node, ?init, "Withholding proposal.");
+            }
+            Some(ByzantineBehavior::InvalidProposals) => {
+                let fin = ProposalFin { proposal_commitment: BlockHash(id.0 + Felt::ONE) };
+                let parts = vec![
+                    ProposalPart::Init(init),
+                    ProposalPart::BlockInfo(block_info),
+                    ProposalPart::Fin(fin),
+                ];
+                self.broadcast_proposal(init, parts);
+            }
+            _ => {
+                let fin = ProposalFin { proposal_commitment: id };
+                let parts = vec![
+                    ProposalPart::Init(init),
+                    ProposalPart::BlockInfo(block_info),
+                    ProposalPart::Fin(fin),
+                ];
+                self.broadcast_proposal(init, parts);
+            }
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        sender.send(id).expect("Receiver should be alive");
+        receiver
+    }
+
+    async fn validate_proposal(
+        &mut self,
+        init: ProposalInit,
+        timeout: Duration,
+        mut content: mpsc::Receiver<ProposalPart>,
+    ) -> oneshot::Receiver<(ProposalCommitment, ProposalFin)> {
+        let (sender, receiver) = oneshot::channel();
+        let proposals = self.proposals.clone();
+        tokio::spawn(async move {
+            let validate = async {
+                let mut block_info = None;
+                while let Some(part) = content.next().await {
+                    match part {
+                        ProposalPart::BlockInfo(info) => block_info = Some(info),
+                        ProposalPart::Fin(fin) => return Some((block_info?, fin)),
+                        _ => {}
+                    }
+                }
+                None
+            };
+            let Ok(Some((block_info, fin))) = tokio::time::timeout(timeout, validate).await else {
+                return;
+            };
+            if block_info.height != init.height {
+                return;
+            }
+            let id = commitment(&block_info);
+            proposals.lock().unwrap().insert(id, block_info);
+            let _ = sender.send((id, fin));
+        });
+        receiver
+    }
+
+    async fn repropose(&mut self, id: ProposalCommitment, init: ProposalInit) {
+        let block_info = self
+            .proposals
+            .lock()
+            .unwrap()
+            .get(&id)
+            .cloned()
+            .expect("Reproposed block should have been built or validated");
+        let parts = vec![
+            ProposalPart::Init(init),
+            ProposalPart::BlockInfo(block_info),
+            ProposalPart::Fin(ProposalFin { proposal_commitment: id }),
+        ];
+        self.broadcast_proposal(init, parts);
+    }
+
+    async fn validators(&self, _height: BlockNumber) -> Vec<ValidatorId> {
+        self.validators.clone()
+    }
+
+    fn proposer(&self, height: BlockNumber, round: Round) -> ValidatorId {
+        let height = usize::try_from(height.0).expect("Height fits in usize");
+        let round = usize::try_from(round).expect("Round fits in usize");
+        self.validators[(height + round) % self.validators.len()]
+    }
+
+    async fn broadcast(&mut self, message: Vote) -> Result<(), ConsensusError> {
+        let mut network = self.network.lock().unwrap();
+        for to in (0..self.validators.len()).filter(|to| *to != self.node) {
+            let mut vote = message.clone();
+            if self.behavior == Some(ByzantineBehavior::Equivocate) && to % 2 == 1 {
+                vote.block_hash = Some(BlockHash(Felt::from(u64::MAX) + Felt::from(vote.round)));
+            }
+            network.send_vote(self.node, to, vote);
+        }
+        Ok(())
+    }
+
+    async fn decision_reached(
+        &mut self,
+        block: ProposalCommitment,
+        precommits: Vec<Vote>,
+    ) -> Result<(), ConsensusError> {
+        let height = precommits[0].height;
+        let round = precommits[0].round;
+        info!(node = self.node, height, round, ?block, "Decision reached.");
+        let now = self.network.lock().unwrap().elapsed();
+        self.ledger.lock().unwrap().record_decision(self.node, height, round, block, now);
+        Ok(())
+    }
+
+    async fn try_sync(&mut self, height: BlockNumber) -> bool {
+        let now = self.network.lock().unwrap().elapsed();
+        let sync_delay = self.network.lock().unwrap().config.sync_delay;
+        let mut ledger = self.ledger.lock().unwrap();
+        let Some((_, _, decided_at)) = ledger.first_decisions.get(&height.0) else {
+            return false;
+        };
+        if *decided_at + sync_delay > now {
+            return false;
+        }
+        ledger.record_height(self.node, height.0);
+        true
+    }
+
+    async fn set_height_and_round(&mut self, _height: BlockNumber, _round: Round) {}
+}
+
+/// Runs the nodes of a simulation. See the module level documentation.
+pub(crate) struct Simulation {
+    config: SimulationConfig,
+    network: Arc<Mutex<SimNetwork>>,
+    ledger: Arc<Mutex<Ledger>>,
+    nodes: Vec<Option<JoinHandle<()>>>,
+}
+
+impl Simulation {
+    pub(crate) fn new(config: SimulationConfig) -> Self {
+        let network = Arc::new(Mutex::new(SimNetwork::new(config.clone())));
+        let nodes = (0..config.num_nodes).map(|_| None).collect();
+        Self { config, network, ledger: Default::default(), nodes }
+    }
+
+    /// Run the simulation until every honest node reaches the target height or the time limit
+    /// passes. Should be run on a runtime with a paused clock.
+    pub(crate) async fn run(mut self) -> SimulationReport {
+        for node in 0..self.config.num_nodes {
+            self.start_node(node);
+        }
+        let mut crashes = self.config.crashes.clone();
+        crashes.sort_by_key(|crash| crash.at);
+        let mut restarts: Vec<(Duration, usize)> = Vec::new();
+
+        let start = self.network.lock().unwrap().start;
+        let mut target_reached_at = None;
+        while start.elapsed() < self.config.time_limit {
+            tokio::time::sleep(LIVENESS_CHECK_INTERVAL).await;
+            let now = start.elapsed();
+            while crashes.first().is_some_and(|crash| crash.at <= now) {
+                let crash = crashes.remove(0);
+                self.crash_node(crash.node);
+                restarts.push((crash.at + crash.restart_after, crash.node));
+            }
+            restarts.retain(|(restart_at, node)| {
+                if *restart_at > now {
+                    return true;
+                }
+                self.start_node(*node);
+                false
+            });
+            if self.honest_nodes_reached_target() {
+                target_reached_at = Some(now);
+                break;
+            }
+        }
+
+        for node in self.nodes.iter_mut().filter_map(Option::take) {
+            node.abort();
+        }
+        let ledger = self.ledger.lock().unwrap();
+        SimulationReport {
+            decisions: ledger.decisions.clone(),
+            safety_violations: ledger.safety_violations.clone(),
+            target_reached_at,
+        }
+    }
+
+    fn honest_nodes_reached_target(&self) -> bool {
+        let ledger = self.ledger.lock().unwrap();
+        (0..self.config.num_nodes).filter(|node| !self.config.byzantine.contains_key(node)).all(
+            |node| ledger.node_heights.get(&node).is_some_and(|h| *h >= self.config.target_height),
+        )
+    }
+
+    fn crash_node(&mut self, node: usize) {
+        info!(node, "Crashing node.");
+        if let Some(handle) = self.nodes[node].take() {
+            handle.abort();
+        }
+        self.network.lock().unwrap().inboxes[node] = None;
+    }
+
+    // Starts (or restarts) a node from the height after the last one it decided or synced.
+    fn start_node(&mut self, node: usize) {
+        let start_height = self
+            .ledger
+            .lock()
+            .unwrap()
+            .node_heights
+            .get(&node)
+            .map_or(BlockNumber(0), |height| BlockNumber(*height).unchecked_next());
+        info!(node, ?start_height, "Starting node.");
+
+        let TestSubscriberChannels { mock_network, subscriber_channels } =
+            mock_register_broadcast_topic().expect("Failed to create mock network");
+        let (votes_sender, votes_receiver) = mpsc::unbounded();
+        let (proposals_sender, proposals_receiver) = mpsc::unbounded();
+        let (mut proposal_receiver_sender, proposal_receiver) = mpsc::channel(CHANNEL_SIZE);
+        self.network.lock().unwrap().inboxes[node] =
+            Some(NodeInbox { votes: votes_sender, proposals: proposals_sender });
+
+        let validators = (0..self.config.num_nodes).map(validator_id).collect();
+        let context = SimContext {
+            node,
+            validators,
+            behavior: self.config.byzantine.get(&node).copied(),
+            network: self.network.clone(),
+            ledger: self.ledger.clone(),
+            proposals: Default::default(),
+        };
+        let timeouts = self.config.timeouts.clone();
+        let handle = tokio::spawn(async move {
+            let mut broadcasted_messages_sender = mock_network.broadcasted_messages_sender;
+            let forward_votes = forward_votes(votes_receiver, &mut broadcasted_messages_sender);
+            let forward_proposals =
+                proposals_receiver.map(Ok).forward(&mut proposal_receiver_sender);
+            // Keep the rest of the mock network alive so that consensus can report and propagate.
+            let mut continue_propagation = mock_network.continue_propagation_receiver;
+            let mut reported_messages = mock_network.reported_messages_receiver;
+            let drain = async {
+                loop {
+                    tokio::select! {
+                        Some(_) = continue_propagation.next() => {},
+                        Some(_) = reported_messages.next() => {},
+                        else => break,
+                    }
+                }
+            };
+            let consensus = run_consensus(
+                context,
+                start_height,
+                start_height,
+                validator_id(node),
+                Duration::ZERO,
+                timeouts,
+                SYNC_RETRY_INTERVAL,
+                subscriber_channels.into(),
+                proposal_receiver,
+            );
+            tokio::select! {
+                res = consensus => panic!("Consensus of node {node} stopped: {res:?}"),
+                _ = forward_votes => {},
+                _ = forward_proposals => {},
+                _ = drain => {},
+            }
+        });
+        self.nodes[node] = Some(handle);
+    }
+}
+
+async fn forward_votes(
+    mut votes_receiver: mpsc::UnboundedReceiver<Vote>,
+    broadcasted_messages_sender: &mut MockBroadcastedMessagesSender<Vote>,
+) {
+    while let Some(vote) = votes_receiver.next().await {
+        let metadata = BroadcastedMessageMetadata::get_test_instance(&mut get_rng());
+        if broadcasted_messages_sender.send((vote, metadata)).await.is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use starknet_api::block::BlockHash;
+use starknet_types_core::felt::Felt;
+
+use super::{
+    ByzantineBehavior,
+    Crash,
+    Ledger,
+    Partition,
+    SafetyViolation,
+    Simulation,
+    SimulationConfig,
+};
+
+const LIVENESS_BOUND: Duration = Duration::from_secs(120);
+
+#[tokio::test(start_paused = true)]
+async fn honest_nodes_decide() {
+    let config = SimulationConfig::default();
+    let report = Simulation::new(config.clone()).run().await;
+
+    report.assert_safe_and_live(config.gst, LIVENESS_BOUND);
+    for height in 0..=config.target_height {
+        assert_eq!(report.decisions[&height].len(), config.num_nodes);
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn runs_are_deterministic() {
+    let config = SimulationConfig {
+        seed: 7,
+        gst: Duration::from_secs(20),
+        drop_probability: 0.2,
+        max_delay_before_gst: Duration::from_secs(2),
+        ..Default::default()
+    };
+    let first = Simulation::new(config.clone()).run().await;
+    let second = Simulation::new(config.clone()).run().await;
+
+    first.assert_safe_and_live(config.gst, LIVENESS_BOUND);
+    assert_eq!(first.decisions, second.decisions);
+    assert_eq!(first.target_reached_at, second.target_reached_at);
+}
+
+#[tokio::test(start_paused = true)]
+async fn lossy_network_before_gst() {
+    let config = SimulationConfig {
+        seed: 3,
+        gst: Duration::from_secs(30),
+        drop_probability: 0.5,
+        max_delay_before_gst: Duration::from_secs(5),
+        ..Default::default()
+    };
+    let report = Simulation::new(config.clone()).run().await;
+
+    report.assert_safe_and_live(config.gst, LIVENESS_BOUND);
+}
+
+#[tokio::test(start_paused = true)]
+async fn progress_resumes_after_partition_heals() {
+    let gst = Duration::from_secs(30);
+    let config = SimulationConfig {
+        gst,
+        // Neither side has a quorum.
+        partitions: vec![Partition {
+            start: Duration::ZERO,
+            end: gst,
+            groups: vec![vec![0, 1], vec![2, 3]],
+        }],
+        ..Default::default()
+    };
+    let report = Simulation::new(config.clone()).run().await;
+
+    report.assert_safe_and_live(gst, LIVENESS_BOUND);
+    assert!(report.target_reached_at.unwrap() > gst);
+}
+
+#[tokio::test(start_paused = true)]
+async fn equivocating_validator() {
+    let config = SimulationConfig {
+        byzantine: HashMap::from([(3, ByzantineBehavior::Equivocate)]),
+        ..Default::default()
+    };
+    let report = Simulation::new(config.clone()).run().await;
+
+    report.assert_safe_and_live(config.gst, LIVENESS_BOUND);
+}
+
+#[tokio::test(start_paused = true)]
+async fn withholding_proposer() {
+    let config = SimulationConfig {
+        byzantine: HashMap::from([(1, ByzantineBehavior::WithholdProposals)]),
+        ..Default::default()
+    };
+    let report = Simulation::new(config.clone()).run().await;
+
+    report.assert_safe_and_live(config.gst, LIVENESS_BOUND);
+    // Height 1 is proposed by node 1 in round 0, so it must be decided in a later round, on a
+    // block built by another node.
+    let decisions = &report.decisions[&1];
+    assert_eq!(decisions.len(), config.num_nodes);
+    assert!(decisions.values().all(|(round, _)| *round > 0), "{decisions:?}");
+    let (_, decided_block) = decisions[&0];
+    assert!(decisions.values().all(|(_, block)| *block == decided_block), "{decisions:?}");
+}
+
+#[tokio::test(start_paused = true)]
+async fn invalid_proposals() {
+    let config = SimulationConfig {
+        byzantine: HashMap::from([(2, ByzantineBehavior::InvalidProposals)]),
+        ..Default::default()
+    };
+    let report = Simulation::new(config.clone()).run().await;
+
+    report.assert_safe_and_live(config.gst, LIVENESS_BOUND);
+}
+
+#[tokio::test(start_paused = true)]
+async fn crashed_node_catches_up() {
+    let config = SimulationConfig {
+        target_height: 10,
+        crashes: vec![Crash {
+            node: 0,
+            at: Duration::from_secs(1),
+            restart_after: Duration::from_secs(40),
+        }],
+        ..Default::default()
+    };
+    let report = Simulation::new(config.clone()).run().await;
+
+    report.assert_safe_and_live(config.gst, LIVENESS_BOUND);
+}
+
+#[test]
+fn ledger_detects_conflicting_decisions() {
+    let block_a = BlockHash(Felt::ONE);
+    let block_b = BlockHash(Felt::TWO);
+    let mut ledger = Ledger::default();
+    ledger.record_decision(0, 1, 0, block_a, Duration::ZERO);
+    ledger.record_decision(1, 1, 0, block_a, Duration::ZERO);
+    assert!(ledger.safety_violations.is_empty());
+
+    ledger.record_decision(2, 1, 0, block_b, Duration::ZERO);
+    assert_eq!(
+        ledger.safety_violations,
+        vec![SafetyViolation { height: 1, first: (0, block_a), second: (2, block_b) }]
+    );
+}
@@ -19,10 +19,11 @@ use enum_as_inner::EnumAsInner;
 use futures::channel::{mpsc, oneshot};
 use papyrus_protobuf::consensus::{ProposalFin, ProposalInit, Vote, VoteType};
 use starknet_api::block::BlockNumber;
+use tokio::time::Instant;
 use tracing::{debug, info, instrument, trace, warn};
 
-use crate::config::TimeoutsConfig;
 use crate::state_machine::{StateMachine, StateMachineEvent};
+use crate::timeouts::TimeoutStrategy;
 use crate::types::{
     ConsensusContext,
     ConsensusError,
@@ -164,13 +165,18 @@ pub(crate) struct SingleHeightConsensus {
     height: BlockNumber,
     validators: Vec<ValidatorId>,
     id: ValidatorId,
-    timeouts: TimeoutsConfig,
+    timeouts: TimeoutStrategy,
     state_machine: StateMachine,
+    // The current round of the state machine and when it started. Used to measure latencies.
+    round_start: (Round, Instant),
     proposals: HashMap<Round, Option<ProposalCommitment>>,
     prevotes: HashMap<(Round, ValidatorId), Vote>,
     precommits: HashMap<(Round, ValidatorId), Vote>,
     last_prevote: Option<Vote>,
     last_precommit: Option<Vote>,
+    // When the last vote of each type was sent, used to measure the quorum latency.
+    last_prevote_sent_at: Option<Instant>,
+    last_precommit_sent_at: Option<Instant>,
 }
 
 impl SingleHeightConsensus {
@@ -179,7 +185,7 @@ impl SingleHeightConsensus {
         is_observer: bool,
         id: ValidatorId,
         validators: Vec<ValidatorId>,
-        timeouts: TimeoutsConfig,
+        timeouts: TimeoutStrategy,
     ) -> Self {
         // TODO(matan): Use actual weights, not just `len`.
         let n_validators =
@@ -190,12 +196,15 @@ impl SingleHeightConsensus {
             validators,
             id,
             timeouts,
+            round_start: (state_machine.round(), Instant::now()),
             state_machine,
             proposals: HashMap::new(),
             prevotes: HashMap::new(),
             precommits: HashMap::new(),
             last_prevote: None,
             last_precommit: None,
+            last_prevote_sent_at: None,
+            last_precommit_sent_at: None,
         }
     }
 
@@ -235,7 +244,7 @@ impl SingleHeightConsensus {
             warn!("Round {} already has a proposal, ignoring", init.round);
             return Ok(ShcReturn::Tasks(Vec::new()));
         };
-        let timeout = self.timeouts.proposal_timeout;
+        let timeout = self.timeouts.schedule_proposal_timeout(init.round);
         info!(
             "Accepting {init:?}. node_round: {}, timeout: {timeout:?}",
             self.state_machine.round()
@@ -277,7 +286,7 @@ impl SingleHeightConsensus {
                 debug!("Rebroadcasting {last_vote:?}");
                 context.broadcast(last_vote.clone()).await?;
                 Ok(ShcReturn::Tasks(vec![ShcTask::Prevote(
-                    self.timeouts.prevote_timeout,
+                    self.timeouts.prevote_timeout(round),
                     StateMachineEvent::Prevote(proposal_id, round),
                 )]))
             }
@@ -294,7 +303,7 @@ impl SingleHeightConsensus {
                 debug!("Rebroadcasting {last_vote:?}");
                 context.broadcast(last_vote.clone()).await?;
                 Ok(ShcReturn::Tasks(vec![ShcTask::Precommit(
-                    self.timeouts.precommit_timeout,
+                    self.timeouts.precommit_timeout(round),
                     StateMachineEvent::Precommit(proposal_id, round),
                 )]))
             }
@@ -326,6 +335,9 @@ impl SingleHeightConsensus {
                     panic!("Proposal entry should exist from init. round: {round}")
                 });
                 assert!(old.is_none(), "Proposal already exists for this round: {round}. {old:?}");
+                if built_id.is_some() && round == self.round_start.0 {
+                    self.timeouts.observe_proposal_latency(self.round_start.1.elapsed());
+                }
                 let sm_events = self.state_machine.handle_event(
                     StateMachineEvent::Proposal(built_id, round, valid_round),
                     &leader_fn,
@@ -405,6 +417,10 @@ impl SingleHeightConsensus {
         context: &mut ContextT,
         mut events: VecDeque<StateMachineEvent>,
     ) -> Result<ShcReturn, ConsensusError> {
+        let sm_round = self.state_machine.round();
+        if sm_round != self.round_start.0 {
+            self.round_start = (sm_round, Instant::now());
+        }
         let mut ret_val = Vec::new();
         while let Some(event) = events.pop_front() {
             trace!("Handling sm event: {:?}", event);
@@ -443,14 +459,17 @@ impl SingleHeightConsensus {
                         .await?,
                     );
                 }
-                StateMachineEvent::TimeoutPropose(_) => {
-                    ret_val.push(ShcTask::TimeoutPropose(self.timeouts.proposal_timeout, event));
+                StateMachineEvent::TimeoutPropose(round) => {
+                    let timeout = self.timeouts.schedule_proposal_timeout(round);
+                    ret_val.push(ShcTask::TimeoutPropose(timeout, event));
                 }
-                StateMachineEvent::TimeoutPrevote(_) => {
-                    ret_val.push(ShcTask::TimeoutPrevote(self.timeouts.prevote_timeout, event));
+                StateMachineEvent::TimeoutPrevote(round) => {
+                    let timeout = self.timeouts.schedule_prevote_timeout(round);
+                    ret_val.push(ShcTask::TimeoutPrevote(timeout, event));
                 }
-                StateMachineEvent::TimeoutPrecommit(_) => {
-                    ret_val.push(ShcTask::TimeoutPrecommit(self.timeouts.precommit_timeout, event));
+                StateMachineEvent::TimeoutPrecommit(round) => {
+                    let timeout = self.timeouts.schedule_precommit_timeout(round);
+                    ret_val.push(ShcTask::TimeoutPrecommit(timeout, event));
                 }
             }
         }
@@ -474,7 +493,8 @@ impl SingleHeightConsensus {
         // handled by applying timeoutPropose when we are the leader.
         let init =
             ProposalInit { height: self.height, round, proposer: self.id, valid_round: None };
-        let fin_receiver = context.build_proposal(init, self.timeouts.proposal_timeout).await;
+        let fin_receiver =
+            context.build_proposal(init, self.timeouts.schedule_proposal_timeout(round)).await;
         vec![ShcTask::BuildProposal(round, fin_receiver)]
     }
 
@@ -517,20 +537,26 @@ impl SingleHeightConsensus {
         round: Round,
         vote_type: VoteType,
     ) -> Result<Vec<ShcTask>, ConsensusError> {
-        let (votes, last_vote, task) = match vote_type {
+        if vote_type == VoteType::Precommit && proposal_id.is_some() {
+            // A precommit for a value means we saw a quorum of prevotes for it.
+            self.observe_quorum_latency(VoteType::Prevote, round);
+        }
+        let (votes, last_vote, last_vote_sent_at, task) = match vote_type {
             VoteType::Prevote => (
                 &mut self.prevotes,
                 &mut self.last_prevote,
+                &mut self.last_prevote_sent_at,
                 ShcTask::Prevote(
-                    self.timeouts.prevote_timeout,
+                    self.timeouts.prevote_timeout(round),
                     StateMachineEvent::Prevote(proposal_id, round),
                 ),
             ),
             VoteType::Precommit => (
                 &mut self.precommits,
                 &mut self.last_precommit,
+                &mut self.last_precommit_sent_at,
                 ShcTask::Precommit(
-                    self.timeouts.precommit_timeout,
+                    self.timeouts.precommit_timeout(round),
                     StateMachineEvent::Precommit(proposal_id, round),
                 ),
             ),
@@ -562,11 +588,32 @@ impl SingleHeightConsensus {
             }
         };
 
+        *last_vote_sent_at = Some(Instant::now());
+
         info!("Broadcasting {vote:?}");
         context.broadcast(vote).await?;
         Ok(vec![task])
     }
 
+    // Record the time from sending our own vote of `vote_type` in `round` until a quorum was
+    // reached for that step.
+    fn observe_quorum_latency(&mut self, vote_type: VoteType, round: Round) {
+        let (last_vote, last_vote_sent_at) = match vote_type {
+            VoteType::Prevote => (&self.last_prevote, self.last_prevote_sent_at),
+            VoteType::Precommit => (&self.last_precommit, self.last_precommit_sent_at),
+        };
+        if let (Some(vote), Some(sent_at)) = (last_vote, last_vote_sent_at) {
+            if vote.round == round {
+                self.timeouts.observe_quorum_latency(sent_at.elapsed());
+            }
+        }
+    }
+
+    /// The timeouts used by this height, including the latencies observed during it.
+    pub(crate) fn timeouts(&self) -> &TimeoutStrategy {
+        &self.timeouts
+    }
+
     async fn handle_state_machine_decision(
         &mut self,
         proposal_id: ProposalCommitment,
@@ -610,6 +657,7 @@ impl SingleHeightConsensus {
             );
             return Err(invalid_decision(msg));
         }
+        self.observe_quorum_latency(VoteType::Precommit, round);
         Ok(ShcReturn::Decision(Decision { precommits: supporting_precommits, block }))
     }
 }
@@ -1,6 +1,9 @@
+use std::time::Duration;
+
 use futures::channel::{mpsc, oneshot};
 use futures::SinkExt;
 use lazy_static::lazy_static;
+use metrics_exporter_prometheus::PrometheusBuilder;
 use papyrus_protobuf::consensus::{ProposalFin, ProposalInit, Vote, DEFAULT_VALIDATOR_ID};
 use starknet_api::block::{BlockHash, BlockNumber};
 use starknet_types_core::felt::Felt;
@@ -8,9 +11,15 @@ use test_case::test_case;
 
 use super::SingleHeightConsensus;
 use crate::config::TimeoutsConfig;
+use crate::metrics::{
+    CONSENSUS_PRECOMMIT_TIMEOUT,
+    CONSENSUS_PREVOTE_TIMEOUT,
+    CONSENSUS_PROPOSAL_TIMEOUT,
+};
 use crate::single_height_consensus::{ShcEvent, ShcReturn, ShcTask};
 use crate::state_machine::StateMachineEvent;
 use crate::test_utils::{precommit, prevote, MockTestContext, TestBlock, TestProposalPart};
+use crate::timeouts::TimeoutStrategy;
 use crate::types::ValidatorId;
 
 lazy_static! {
@@ -23,7 +32,7 @@ lazy_static! {
     static ref BLOCK: TestBlock = TestBlock { content: vec![1, 2, 3], id: BlockHash(Felt::ONE) };
     static ref PROPOSAL_INIT: ProposalInit =
         ProposalInit { proposer: *PROPOSER_ID, ..Default::default() };
-    static ref TIMEOUTS: TimeoutsConfig = TimeoutsConfig::default();
+    static ref TIMEOUTS: TimeoutStrategy = TimeoutStrategy::default();
     static ref VALIDATE_PROPOSAL_EVENT: ShcEvent = ShcEvent::ValidateProposal(
         StateMachineEvent::Proposal(Some(BLOCK.id), PROPOSAL_INIT.round, PROPOSAL_INIT.valid_round,),
         Some(ProposalFin { proposal_commitment: BLOCK.id }),
@@ -35,25 +44,28 @@ const CHANNEL_SIZE: usize = 1;
 
 fn prevote_task(block_felt: Option<Felt>, round: u32) -> ShcTask {
     ShcTask::Prevote(
-        TIMEOUTS.prevote_timeout,
+        TIMEOUTS.prevote_timeout(round),
         StateMachineEvent::Prevote(block_felt.map(BlockHash), round),
     )
 }
 
 fn precommit_task(block_felt: Option<Felt>, round: u32) -> ShcTask {
     ShcTask::Precommit(
-        TIMEOUTS.precommit_timeout,
+        TIMEOUTS.precommit_timeout(round),
         StateMachineEvent::Precommit(block_felt.map(BlockHash), round),
     )
 }
 
 fn timeout_prevote_task(round: u32) -> ShcTask {
-    ShcTask::TimeoutPrevote(TIMEOUTS.prevote_timeout, StateMachineEvent::TimeoutPrevote(round))
+    ShcTask::TimeoutPrevote(
+        TIMEOUTS.prevote_timeout(round),
+        StateMachineEvent::TimeoutPrevote(round),
+    )
 }
 
 fn timeout_precommit_task(round: u32) -> ShcTask {
     ShcTask::TimeoutPrecommit(
-        TIMEOUTS.precommit_timeout,
+        TIMEOUTS.precommit_timeout(round),
         StateMachineEvent::TimeoutPrecommit(round),
     )
 }
@@ -431,3 +443,68 @@ async fn repropose() {
     assert_eq!(decision.block, BLOCK.id);
     assert!(decision.precommits.into_iter().all(|item| precommits.contains(&item)));
 }
+
+#[tokio::test]
+async fn timeout_gauges_follow_the_scheduled_round() {
+    let recorder = PrometheusBuilder::new().build_recorder();
+    let _recorder_guard = metrics::set_default_local_recorder(&recorder);
+    // The timeouts are in whole seconds since the metrics are parsed as integers.
+    let timeouts = TimeoutStrategy::new(TimeoutsConfig {
+        proposal_timeout_delta: Duration::from_secs(1),
+        ..Default::default()
+    });
+    let mut context = MockTestContext::new();
+    let mut shc = SingleHeightConsensus::new(
+        BlockNumber(0),
+        false,
+        *VALIDATOR_ID_1,
+        VALIDATORS.to_vec(),
+        timeouts.clone(),
+    );
+    context.expect_proposer().returning(move |_, _| *PROPOSER_ID);
+    context.expect_set_height_and_round().returning(move |_, _| ());
+
+    // Waiting for the proposal of round 0.
+    shc.start(&mut context).await.unwrap();
+    let metrics = recorder.handle().render();
+    assert_eq!(
+        CONSENSUS_PROPOSAL_TIMEOUT.parse_numeric_metric::<u64>(&metrics),
+        Some(timeouts.proposal_timeout(0).as_secs())
+    );
+
+    // No proposal arrives, so the node votes nil. A quorum of prevotes and precommits for nil
+    // schedules the vote timeouts of round 0.
+    context.expect_broadcast().returning(move |_| Ok(()));
+    shc.handle_event(&mut context, ShcEvent::TimeoutPropose(StateMachineEvent::TimeoutPropose(0)))
+        .await
+        .unwrap();
+    for voter in [*PROPOSER_ID, *VALIDATOR_ID_2, *VALIDATOR_ID_3] {
+        shc.handle_vote(&mut context, prevote(None, 0, 0, voter)).await.unwrap();
+    }
+    for voter in [*PROPOSER_ID, *VALIDATOR_ID_2, *VALIDATOR_ID_3] {
+        shc.handle_vote(&mut context, precommit(None, 0, 0, voter)).await.unwrap();
+    }
+    let metrics = recorder.handle().render();
+    assert_eq!(
+        CONSENSUS_PREVOTE_TIMEOUT.parse_numeric_metric::<u64>(&metrics),
+        Some(timeouts.prevote_timeout(0).as_secs())
+    );
+    assert_eq!(
+        CONSENSUS_PRECOMMIT_TIMEOUT.parse_numeric_metric::<u64>(&metrics),
+        Some(timeouts.precommit_timeout(0).as_secs())
+    );
+
+    // Advancing to round 1 schedules its longer proposal timeout.
+    shc.handle_event(
+        &mut context,
+        ShcEvent::TimeoutPrecommit(StateMachineEvent::TimeoutPrecommit(0)),
+    )
+    .await
+    .unwrap();
+    let metrics = recorder.handle().render();
+    assert_eq!(
+        CONSENSUS_PROPOSAL_TIMEOUT.parse_numeric_metric::<u64>(&metrics),
+        Some(timeouts.proposal_timeout(1).as_secs())
+    );
+    assert_ne!(timeouts.proposal_timeout(1), timeouts.proposal_timeout(0));
+}
@@ -2,18 +2,29 @@
 
 use std::cmp::Ordering;
 use std::collections::hash_map::Entry::{Occupied, Vacant};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
+use std::time::Duration;
 
 use futures::channel::mpsc;
 use futures::StreamExt;
-use papyrus_network::network_manager::{BroadcastTopicClientTrait, ReceivedBroadcastedMessage};
+use papyrus_network::network_manager::{
+    BroadcastTopicClientTrait,
+    ClientResponsesManager,
+    ReceivedBroadcastedMessage,
+    ServerQueryManager,
+    SqmrClientSender,
+    SqmrServerReceiver,
+};
 use papyrus_network::utils::StreamHashMap;
 use papyrus_network_types::network_types::{BroadcastedMessageMetadata, OpaquePeerId};
-use papyrus_protobuf::consensus::{StreamMessage, StreamMessageBody};
+use papyrus_protobuf::consensus::{StreamMessage, StreamMessageBody, StreamMessagesRequest};
 use papyrus_protobuf::converters::ProtobufConversionError;
-use tracing::{instrument, warn};
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+use tracing::{debug, instrument, warn};
+
+use crate::config::StreamHandlerConfig;
 
 #[cfg(test)]
 #[path = "stream_handler_test.rs"]
@@ -26,13 +37,25 @@ type MessageId = u64;
 // TODO(guy): make this configurable.
 pub const CHANNEL_BUFFER_LENGTH: usize = 100;
 
+/// Sends requests for missing messages of inbound streams to peers.
+pub type RetransmissionClient<StreamContent, StreamId> =
+    SqmrClientSender<StreamMessagesRequest<StreamId>, StreamMessage<StreamContent, StreamId>>;
+/// Receives requests from peers for messages of streams this node sent or received.
+pub type RetransmissionServer<StreamContent, StreamId> =
+    SqmrServerReceiver<StreamMessagesRequest<StreamId>, StreamMessage<StreamContent, StreamId>>;
+type RetransmissionQuery<StreamContent, StreamId> =
+    ServerQueryManager<StreamMessagesRequest<StreamId>, StreamMessage<StreamContent, StreamId>>;
+type RetransmissionResponses<StreamContent, StreamId> =
+    ClientResponsesManager<StreamMessage<StreamContent, StreamId>>;
+
 /// A combination of trait bounds needed for the content of the stream.
 pub trait StreamContentTrait:
-    Clone + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversionError> + Send
+    Clone + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversionError> + Send + 'static
 {
 }
 impl<StreamContent> StreamContentTrait for StreamContent where
-    StreamContent: Clone + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversionError> + Send
+    StreamContent:
+        Clone + Into<Vec<u8>> + TryFrom<Vec<u8>, Error = ProtobufConversionError> + Send + 'static
 {
 }
 /// A combination of trait bounds needed for the stream ID.
@@ -46,6 +69,7 @@ pub trait StreamIdTrait:
     + Display
     + Debug
     + Send
+    + 'static
 {
 }
 impl<StreamId> StreamIdTrait for StreamId where
@@ -58,6 +82,7 @@ impl<StreamId> StreamIdTrait for StreamId where
         + Display
         + Debug
         + Send
+        + 'static
 {
 }
 
@@ -76,6 +101,10 @@ struct StreamData<StreamContent: StreamContentTrait, StreamId: StreamIdTrait> {
     sender: mpsc::Sender<StreamContent>,
     // A buffer for messages that were received out of order.
     message_buffer: HashMap<MessageId, StreamMessage<StreamContent, StreamId>>,
+    // The last time a message was received for this stream.
+    last_progress: Instant,
+    // How many times the missing messages were requested since the last progress.
+    retransmission_attempts: u32,
 }
 
 impl<StreamContent: StreamContentTrait, StreamId: StreamIdTrait>
@@ -90,6 +119,8 @@ impl<StreamContent: StreamContentTrait, StreamId: StreamIdTrait>
             sender,
             receiver: Some(receiver),
             message_buffer: HashMap::new(),
+            last_progress: Instant::now(),
+            retransmission_attempts: 0,
         }
     }
 }
@@ -97,11 +128,14 @@ impl<StreamContent: StreamContentTrait, StreamId: StreamIdTrait>
 /// A StreamHandler is responsible for:
 /// - Buffering inbound messages and reporting them to the application in order.
 /// - Sending outbound messages to the network, wrapped in StreamMessage.
+/// - Requesting the missing messages of stalled inbound streams from peers, and answering such
+///   requests for recent streams.
 pub struct StreamHandler<StreamContent, StreamId, InboundReceiverT, OutboundSenderT>
 where
     StreamContent: StreamContentTrait,
     StreamId: StreamIdTrait,
 {
+    config: StreamHandlerConfig,
     // For each stream ID from the network, send the application a Receiver
     // that will receive the messages in order. This allows sending such Receivers.
     inbound_channel_sender: mpsc::Sender<mpsc::Receiver<StreamContent>>,
@@ -121,6 +155,22 @@ where
     outbound_sender: OutboundSenderT,
     // For each stream, keep track of the message_id of the last message sent.
     outbound_stream_number: HashMap<StreamId, MessageId>,
+    retransmission_client: RetransmissionClient<StreamContent, StreamId>,
+    retransmission_server: RetransmissionServer<StreamContent, StreamId>,
+    // The responses to in flight requests for missing messages, by the stream they were sent for.
+    retransmission_responses:
+        StreamHashMap<(PeerId, StreamId), RetransmissionResponses<StreamContent, StreamId>>,
+    // The messages of our recent outbound streams, used to answer requests from peers. Inbound
+    // messages are not cached, since we can't vouch for their content.
+    retransmission_cache:
+        HashMap<StreamId, BTreeMap<MessageId, StreamMessage<StreamContent, StreamId>>>,
+    // The order in which streams were added to the cache, so the oldest can be evicted.
+    retransmission_cache_order: VecDeque<StreamId>,
+    // The total encoded size of the messages in the cache, and of each cached stream.
+    retransmission_cache_bytes: usize,
+    retransmission_cache_stream_bytes: HashMap<StreamId, usize>,
+    // Ticks when it is time to check for stalled inbound streams.
+    retransmission_interval: Interval,
 }
 
 impl<StreamContent, StreamId, InboundReceiverT, OutboundSenderT>
@@ -134,12 +184,20 @@ where
 {
     /// Create a new StreamHandler.
     pub fn new(
+        config: StreamHandlerConfig,
         inbound_channel_sender: mpsc::Sender<mpsc::Receiver<StreamContent>>,
         inbound_receiver: InboundReceiverT,
         outbound_channel_receiver: mpsc::Receiver<(StreamId, mpsc::Receiver<StreamContent>)>,
         outbound_sender: OutboundSenderT,
+        retransmission_client: RetransmissionClient<StreamContent, StreamId>,
+        retransmission_server: RetransmissionServer<StreamContent, StreamId>,
     ) -> Self {
+        // An interval's period must be positive.
+        let period = config.retransmission_timeout.max(Duration::from_millis(1));
+        let mut retransmission_interval = tokio::time::interval_at(Instant::now() + period, period);
+        retransmission_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
         Self {
+            config,
             inbound_channel_sender,
             inbound_receiver,
             inbound_stream_data: HashMap::new(),
@@ -147,6 +205,14 @@ where
             outbound_sender,
             outbound_stream_receivers: StreamHashMap::new(HashMap::new()),
             outbound_stream_number: HashMap::new(),
+            retransmission_client,
+            retransmission_server,
+            retransmission_responses: StreamHashMap::new(HashMap::new()),
+            retransmission_cache: HashMap::new(),
+            retransmission_cache_order: VecDeque::new(),
+            retransmission_cache_bytes: 0,
+            retransmission_cache_stream_bytes: HashMap::new(),
+            retransmission_interval,
         }
     }
 
@@ -161,6 +227,9 @@ where
     /// - Outbound messages are wrapped as StreamMessage and sent to the network directly.
     /// - Inbound messages are stripped of StreamMessage and buffered until they can be sent in the
     ///   correct order to the application.
+    /// - Retransmitted messages are handled like inbound messages, and requests for retransmission
+    ///   are answered in the background from the cache of recent outbound streams.
+    /// - Periodically, the missing messages of stalled inbound streams are requested from peers.
     pub async fn handle_next_msg(&mut self) {
         tokio::select!(
             // New outbound stream.
@@ -175,6 +244,17 @@ where
             Some(message) = self.inbound_receiver.next() => {
                 self.handle_inbound_message(message);
             }
+            // A response to a request for missing messages.
+            Some((key, Some(message))) = self.retransmission_responses.next() => {
+                self.handle_retransmitted_message(key, message);
+            }
+            // A request from a peer for messages of a stream.
+            Some(query) = self.retransmission_server.next() => {
+                self.handle_retransmission_request(query);
+            }
+            _ = self.retransmission_interval.tick() => {
+                self.request_missing_messages().await;
+            }
         );
     }
 
@@ -253,6 +333,7 @@ where
             stream_id: stream_id.clone(),
             message_id: *self.outbound_stream_number.get(&stream_id).unwrap_or(&0),
         };
+        self.cache_message(message.clone());
         // TODO(guyn): reconsider the "expect" here.
         self.outbound_sender.broadcast_message(message).await.expect("Send should succeed");
         self.outbound_stream_number.insert(
@@ -268,6 +349,7 @@ where
             stream_id: stream_id.clone(),
             message_id: *self.outbound_stream_number.get(&stream_id).unwrap_or(&0),
         };
+        self.cache_message(message.clone());
         self.outbound_sender.broadcast_message(message).await.expect("Send should succeed");
         self.outbound_stream_number.remove(&stream_id);
     }
@@ -290,20 +372,71 @@ where
             }
         };
 
-        let peer_id = metadata.originator_id.clone();
+        let peer_id = metadata.originator_id;
         let stream_id = message.stream_id.clone();
         let key = (peer_id, stream_id);
 
-        let data = match self.inbound_stream_data.entry(key.clone()) {
-            // If data exists, remove it (it will be returned to hash map at end of function).
-            Occupied(entry) => entry.remove_entry().1,
-            Vacant(_) => {
+        // If data exists, remove it (it will be returned to hash map at end of function).
+        let data = match self.inbound_stream_data.remove(&key) {
+            Some(data) => data,
+            None => {
+                let num_peer_streams = self
+                    .inbound_stream_data
+                    .keys()
+                    .filter(|(peer_id, _)| *peer_id == key.0)
+                    .count();
+                if num_peer_streams >= self.config.max_streams_per_peer {
+                    warn!(
+                        "Peer has too many open streams, dropping the message. key: {:?}, \
+                         num_streams: {}",
+                        key, num_peer_streams
+                    );
+                    return;
+                }
                 // If we received a message for a stream that we have not seen before,
                 // we need to create a new receiver for it.
                 StreamData::new()
             }
         };
-        if let Some(data) = self.handle_message_inner(message, metadata, data) {
+        if let Some(data) = self.handle_message_inner(message, key.clone(), data) {
+            self.inbound_stream_data.insert(key, data);
+        }
+    }
+
+    // Handle a message that was retransmitted by the stream's originator in response to our
+    // request.
+    fn handle_retransmitted_message(
+        &mut self,
+        key: (PeerId, StreamId),
+        message: Result<StreamMessage<StreamContent, StreamId>, ProtobufConversionError>,
+    ) {
+        let message = match message {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("Error converting retransmitted message: {:?}", e);
+                return;
+            }
+        };
+        if message.stream_id != key.1 {
+            warn!(
+                "Received a retransmitted message for a stream that wasn't requested. key: {:?}, \
+                 stream_id: {}",
+                key, message.stream_id
+            );
+            return;
+        }
+        // The stream may have been completed or dropped since the request was sent.
+        let Some(data) = self.inbound_stream_data.remove(&key) else {
+            return;
+        };
+        // Ignore messages we already have, unlike gossiped duplicates these are expected.
+        if message.message_id < data.next_message_id
+            || data.message_buffer.contains_key(&message.message_id)
+        {
+            self.inbound_stream_data.insert(key, data);
+            return;
+        }
+        if let Some(data) = self.handle_message_inner(message, key.clone(), data) {
             self.inbound_stream_data.insert(key, data);
         }
     }
@@ -313,17 +446,25 @@ where
     fn handle_message_inner(
         &mut self,
         message: StreamMessage<StreamContent, StreamId>,
-        metadata: BroadcastedMessageMetadata,
+        key: (PeerId, StreamId),
         mut data: StreamData<StreamContent, StreamId>,
     ) -> Option<StreamData<StreamContent, StreamId>> {
-        let peer_id = metadata.originator_id;
-        let stream_id = message.stream_id.clone();
-        let key = (peer_id, stream_id);
         let message_id = message.message_id;
 
+        if message_id >= self.config.max_messages_per_stream {
+            warn!(
+                "Stream exceeded the maximal number of messages, dropping it. key: {:?}, \
+                 message_id: {}, max_messages_per_stream: {}",
+                key, message_id, self.config.max_messages_per_stream
+            );
+            return None;
+        }
+
         if data.max_message_id_received < message_id {
             data.max_message_id_received = message_id;
         }
+        data.last_progress = Instant::now();
+        data.retransmission_attempts = 0;
 
         // Check for Fin type message.
         match message.message {
@@ -411,6 +552,125 @@ where
         }
     }
 
+    // Store an outbound message so it can be retransmitted to peers, evicting the oldest streams
+    // while the cache exceeds its size in bytes.
+    fn cache_message(&mut self, message: StreamMessage<StreamContent, StreamId>) {
+        let stream_id = message.stream_id.clone();
+        let message_bytes = Vec::<u8>::from(message.clone()).len();
+        let messages = match self.retransmission_cache.entry(stream_id.clone()) {
+            Occupied(entry) => entry.into_mut(),
+            Vacant(entry) => {
+                self.retransmission_cache_order.push_back(stream_id.clone());
+                entry.insert(BTreeMap::new())
+            }
+        };
+        if let Some(replaced) = messages.insert(message.message_id, message) {
+            // Our own message IDs are unique per stream, unless the application reused a stream ID.
+            let replaced_bytes = Vec::<u8>::from(replaced).len();
+            self.retransmission_cache_bytes -= replaced_bytes;
+            *self.retransmission_cache_stream_bytes.entry(stream_id.clone()).or_default() -=
+                replaced_bytes;
+        }
+        self.retransmission_cache_bytes += message_bytes;
+        *self.retransmission_cache_stream_bytes.entry(stream_id).or_default() += message_bytes;
+        while self.retransmission_cache_bytes > self.config.retransmission_cache_bytes {
+            let evicted = self.retransmission_cache_order.pop_front().expect("Cache is not empty");
+            self.retransmission_cache.remove(&evicted);
+            self.retransmission_cache_bytes -= self
+                .retransmission_cache_stream_bytes
+                .remove(&evicted)
+                .expect("Cached stream has a size");
+        }
+    }
+
+    // Answer a peer's request for messages of a stream, with the messages we have in the cache.
+    // The responses are sent by a separate task, so a slow peer doesn't block the stream handler.
+    fn handle_retransmission_request(
+        &mut self,
+        mut query: RetransmissionQuery<StreamContent, StreamId>,
+    ) {
+        let request = match query.query() {
+            Ok(request) => request.clone(),
+            Err(e) => {
+                warn!("Error converting retransmission request: {:?}", e);
+                query.report_peer();
+                return;
+            }
+        };
+        let messages: Vec<_> = self
+            .retransmission_cache
+            .get(&request.stream_id)
+            .map(|messages| {
+                messages
+                    .range(request.first_message_id..=request.last_message_id)
+                    .map(|(_, message)| message.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        debug!(
+            "Retransmitting {} messages. stream_id: {}, first_message_id: {}, last_message_id: {}",
+            messages.len(),
+            request.stream_id,
+            request.first_message_id,
+            request.last_message_id
+        );
+        tokio::spawn(async move {
+            for message in messages {
+                if query.send_response(message).await.is_err() {
+                    return;
+                }
+            }
+            // Dropping the query closes the response stream, signaling the peer that we are done.
+        });
+    }
+
+    // Request the missing messages of inbound streams which didn't progress for
+    // `retransmission_timeout`. Streams which didn't progress after `max_retransmission_attempts`
+    // requests are dropped.
+    async fn request_missing_messages(&mut self) {
+        let now = Instant::now();
+        let in_flight: HashSet<_> = self.retransmission_responses.keys().cloned().collect();
+        let stalled: Vec<_> = self
+            .inbound_stream_data
+            .iter()
+            .filter(|(key, data)| {
+                now.duration_since(data.last_progress) >= self.config.retransmission_timeout
+                    && !in_flight.contains(*key)
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in stalled {
+            let data = self.inbound_stream_data.get_mut(&key).expect("Stalled stream exists");
+            if data.retransmission_attempts >= self.config.max_retransmission_attempts {
+                warn!(
+                    "Stream didn't progress after {} retransmission requests, dropping it. key: \
+                     {:?}, next_message_id: {}",
+                    data.retransmission_attempts, key, data.next_message_id
+                );
+                self.inbound_stream_data.remove(&key);
+                continue;
+            }
+            data.retransmission_attempts += 1;
+            data.last_progress = now;
+            let request = StreamMessagesRequest {
+                stream_id: key.1.clone(),
+                first_message_id: data.next_message_id,
+                last_message_id: data
+                    .fin_message_id
+                    .unwrap_or(self.config.max_messages_per_stream.saturating_sub(1)),
+            };
+            debug!("Requesting missing messages. key: {:?}, request: {:?}", key, request);
+            // Only the stream's originator is trusted with its content, so the request is sent
+            // to it rather than to an arbitrary peer.
+            match self.retransmission_client.send_new_query_to_peer(request, key.0.clone()).await {
+                Ok(responses) => {
+                    self.retransmission_responses.insert(key, responses);
+                }
+                Err(e) => warn!("Failed to request missing messages. key: {:?}, {:?}", key, e),
+            }
+        }
+    }
+
     // Tries to drain as many messages as possible from the buffer (in order),
     // DOES NOT guarantee that the buffer will be empty after calling this function.
     // Returns true if the receiver for this stream is dropped.
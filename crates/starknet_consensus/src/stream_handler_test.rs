@@ -5,6 +5,8 @@ use futures::stream::StreamExt;
 use futures::SinkExt;
 use papyrus_network::network_manager::test_utils::{
     mock_register_broadcast_topic,
+    mock_register_sqmr_protocol_client,
+    mock_register_sqmr_protocol_server,
     MockBroadcastedMessagesSender,
     TestSubscriberChannels,
 };
@@ -16,6 +18,7 @@ use papyrus_test_utils::{get_rng, GetTestInstance};
 use prost::DecodeError;
 
 use super::{MessageId, StreamHandler};
+use crate::config::StreamHandlerConfig;
 
 const CHANNEL_SIZE: usize = 100;
 
@@ -152,13 +155,19 @@ mod tests {
         let (inbound_channel_sender, inbound_channel_receiver) =
             mpsc::channel::<mpsc::Receiver<T>>(CHANNEL_SIZE);
 
+        let (retransmission_client, _) = mock_register_sqmr_protocol_client(CHANNEL_SIZE);
+        let (retransmission_server, _) = mock_register_sqmr_protocol_server(CHANNEL_SIZE);
+
         // TODO(guyn): We should also give the broadcast_topic_client to the StreamHandler
         // This will allow reporting to the network things like bad peers.
         let handler = StreamHandler::new(
+            StreamHandlerConfig::default(),
             inbound_channel_sender,
             inbound_receiver,
             outbound_channel_receiver,
             outbound_sender,
+            retransmission_client,
+            retransmission_server,
         );
 
         let inbound_metadata = BroadcastedMessageMetadata::get_test_instance(&mut get_rng());
@@ -553,14 +562,33 @@ mod tests_v2 {
 
     use futures::channel::mpsc::{self, Receiver, SendError, Sender};
     use futures::{FutureExt, SinkExt, StreamExt};
-    use papyrus_network::network_manager::{BroadcastTopicClientTrait, ReceivedBroadcastedMessage};
+    use papyrus_network::network_manager::test_utils::{
+        create_test_server_query_manager,
+        mock_register_sqmr_protocol_client,
+        mock_register_sqmr_protocol_server,
+        MockClientResponsesManager,
+    };
+    use papyrus_network::network_manager::{
+        BroadcastTopicClientTrait,
+        GenericReceiver,
+        ReceivedBroadcastedMessage,
+        ServerQueryManager,
+    };
     use papyrus_network_types::network_types::BroadcastedMessageMetadata;
-    use papyrus_protobuf::consensus::{ProposalInit, ProposalPart, StreamMessageBody};
+    use papyrus_protobuf::consensus::{
+        ProposalInit,
+        ProposalPart,
+        StreamMessageBody,
+        StreamMessagesRequest,
+    };
+    use papyrus_test_utils::{get_rng, GetTestInstance};
 
     use super::{TestStreamId, CHANNEL_SIZE};
+    use crate::config::StreamHandlerConfig;
     use crate::stream_handler::StreamHandler;
 
     type StreamMessage = papyrus_protobuf::consensus::StreamMessage<ProposalPart, TestStreamId>;
+    type Request = StreamMessagesRequest<TestStreamId>;
 
     struct FakeBroadcastClient {
         sender: Sender<StreamMessage>,
@@ -584,29 +612,62 @@ mod tests_v2 {
         }
     }
 
+    type TestStreamHandler = StreamHandler<
+        ProposalPart,
+        TestStreamId,
+        Receiver<ReceivedBroadcastedMessage<StreamMessage>>,
+        FakeBroadcastClient,
+    >;
+
+    // The mock network's side of the retransmission protocol.
+    struct RetransmissionMock {
+        // Catches the requests sent by the stream handler.
+        requests_receiver: GenericReceiver<MockClientResponsesManager<Request, StreamMessage>>,
+        // Sends requests from peers to the stream handler.
+        queries_sender: Sender<ServerQueryManager<Request, StreamMessage>>,
+    }
+
     #[allow(clippy::type_complexity)]
     fn setup() -> (
-        StreamHandler<
-            ProposalPart,
-            TestStreamId,
-            Receiver<ReceivedBroadcastedMessage<StreamMessage>>,
-            FakeBroadcastClient,
-        >,
+        TestStreamHandler,
         Sender<ReceivedBroadcastedMessage<StreamMessage>>,
         Receiver<Receiver<ProposalPart>>,
         Sender<(TestStreamId, Receiver<ProposalPart>)>,
         Receiver<StreamMessage>,
+    ) {
+        let (stream_handler, inbound, inbound_internal, outbound_internal, outbound, _) =
+            setup_with_config(StreamHandlerConfig::default());
+        (stream_handler, inbound, inbound_internal, outbound_internal, outbound)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn setup_with_config(
+        config: StreamHandlerConfig,
+    ) -> (
+        TestStreamHandler,
+        Sender<ReceivedBroadcastedMessage<StreamMessage>>,
+        Receiver<Receiver<ProposalPart>>,
+        Sender<(TestStreamId, Receiver<ProposalPart>)>,
+        Receiver<StreamMessage>,
+        RetransmissionMock,
     ) {
         let (inbound_internal_sender, inbound_internal_receiver) = mpsc::channel(CHANNEL_SIZE);
         let (inbound_network_sender, inbound_network_receiver) = mpsc::channel(CHANNEL_SIZE);
         let (outbound_internal_sender, outbound_internal_receiver) = mpsc::channel(CHANNEL_SIZE);
         let (outbound_network_sender, outbound_network_receiver) = mpsc::channel(CHANNEL_SIZE);
         let outbound_network_sender = FakeBroadcastClient { sender: outbound_network_sender };
+        let (retransmission_client, requests_receiver) =
+            mock_register_sqmr_protocol_client(CHANNEL_SIZE);
+        let (retransmission_server, queries_sender) =
+            mock_register_sqmr_protocol_server(CHANNEL_SIZE);
         let stream_handler = StreamHandler::new(
+            config,
             inbound_internal_sender,
             inbound_network_receiver,
             outbound_internal_receiver,
             outbound_network_sender,
+            retransmission_client,
+            retransmission_server,
         );
 
         (
@@ -615,6 +676,7 @@ mod tests_v2 {
             inbound_internal_receiver,
             outbound_internal_sender,
             outbound_network_receiver,
+            RetransmissionMock { requests_receiver, queries_sender },
         )
     }
 
@@ -629,6 +691,10 @@ mod tests_v2 {
         }
     }
 
+    fn build_init_part(round: u32) -> ProposalPart {
+        ProposalPart::Init(ProposalInit { round, ..Default::default() })
+    }
+
     fn build_fin_message(stream_id: u64, message_id: u32) -> StreamMessage {
         StreamMessage {
             message: StreamMessageBody::Fin,
@@ -739,4 +805,231 @@ mod tests_v2 {
             assert!(stream_ids.remove(&fin.stream_id.0));
         }
     }
+
+    fn received(
+        message: StreamMessage,
+        metadata: &BroadcastedMessageMetadata,
+    ) -> ReceivedBroadcastedMessage<StreamMessage> {
+        (Ok(message), metadata.clone())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn requests_missing_messages_of_stalled_stream() {
+        let stream_id = 1;
+        let (mut stream_handler, mut network_sender, mut inbound_internal_receiver, _, _, mut mock) =
+            setup_with_config(StreamHandlerConfig::default());
+        let metadata = BroadcastedMessageMetadata::get_test_instance(&mut get_rng());
+
+        // Message 1 is lost.
+        network_sender
+            .send(received(build_init_message(0, stream_id, 0), &metadata))
+            .await
+            .unwrap();
+        network_sender.send(received(build_fin_message(stream_id, 2), &metadata)).await.unwrap();
+        stream_handler.handle_next_msg().await;
+        stream_handler.handle_next_msg().await;
+        let mut receiver = inbound_internal_receiver.next().now_or_never().unwrap().unwrap();
+        assert_eq!(receiver.next().now_or_never().unwrap().unwrap(), build_init_part(0));
+        assert!(mock.requests_receiver.next().now_or_never().is_none());
+
+        // Once the stream stalls, the missing messages are requested.
+        stream_handler.handle_next_msg().await;
+        let mut request = mock.requests_receiver.next().await.unwrap();
+        assert_eq!(
+            request.query().as_ref().unwrap(),
+            &Request {
+                stream_id: TestStreamId(stream_id),
+                first_message_id: 1,
+                last_message_id: 2
+            }
+        );
+        // Only the stream's originator is asked for its messages.
+        assert_eq!(request.peer_id(), Some(metadata.originator_id.clone()));
+
+        // The retransmitted message completes the stream.
+        request.send_response(build_init_message(1, stream_id, 1)).await.unwrap();
+        stream_handler.handle_next_msg().await;
+        assert_eq!(receiver.next().now_or_never().unwrap().unwrap(), build_init_part(1));
+        assert!(receiver.next().now_or_never().unwrap().is_none());
+        assert!(stream_handler.inbound_stream_data.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drops_stream_after_max_retransmission_attempts() {
+        let config = StreamHandlerConfig { max_retransmission_attempts: 1, ..Default::default() };
+        let (mut stream_handler, mut network_sender, _, _, _, mut mock) = setup_with_config(config);
+        let metadata = BroadcastedMessageMetadata::get_test_instance(&mut get_rng());
+
+        network_sender.send(received(build_init_message(0, 1, 1), &metadata)).await.unwrap();
+        stream_handler.handle_next_msg().await;
+        assert_eq!(stream_handler.inbound_stream_data.len(), 1);
+
+        // The request goes unanswered, so the next time the stream stalls it is dropped.
+        stream_handler.handle_next_msg().await;
+        drop(mock.requests_receiver.next().await.unwrap());
+        assert_eq!(stream_handler.inbound_stream_data.len(), 1);
+        stream_handler.handle_next_msg().await;
+        assert!(stream_handler.inbound_stream_data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn answers_retransmission_requests() {
+        let stream_id = 1;
+        let num_messages = 3;
+        let (
+            mut stream_handler,
+            _,
+            _,
+            mut client_to_streamhandler_sender,
+            _network_receiver,
+            mut mock,
+        ) = setup_with_config(StreamHandlerConfig::default());
+
+        // Send a stream.
+        let (mut sender, stream_receiver) = mpsc::channel(CHANNEL_SIZE);
+        client_to_streamhandler_sender
+            .send((TestStreamId(stream_id), stream_receiver))
+            .await
+            .unwrap();
+        stream_handler.handle_next_msg().await;
+        for i in 0..num_messages {
+            sender.send(build_init_part(i)).await.unwrap();
+            stream_handler.handle_next_msg().await;
+        }
+        sender.close_channel();
+        stream_handler.handle_next_msg().await;
+
+        // A peer requests part of the stream, including the fin.
+        let request = Request {
+            stream_id: TestStreamId(stream_id),
+            first_message_id: 2,
+            last_message_id: 10,
+        };
+        let (query, _, responses) = create_test_server_query_manager(request);
+        mock.queries_sender.send(query).await.unwrap();
+        stream_handler.handle_next_msg().await;
+        assert_eq!(
+            responses.collect::<Vec<_>>().await,
+            vec![build_init_message(2, stream_id, 2), build_fin_message(stream_id, num_messages)]
+        );
+
+        // Requests for unknown streams are answered with no messages.
+        let request =
+            Request { stream_id: TestStreamId(7), first_message_id: 0, last_message_id: 10 };
+        let (query, _, responses) = create_test_server_query_manager(request);
+        mock.queries_sender.send(query).await.unwrap();
+        stream_handler.handle_next_msg().await;
+        assert!(responses.collect::<Vec<_>>().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn does_not_retransmit_inbound_messages() {
+        let stream_id = 1;
+        let (mut stream_handler, mut network_sender, _inbound_internal_receiver, _, _, mut mock) =
+            setup_with_config(StreamHandlerConfig::default());
+        let metadata = BroadcastedMessageMetadata::get_test_instance(&mut get_rng());
+
+        // A peer's message is buffered, but we can't vouch for it so it isn't served to others.
+        network_sender
+            .send(received(build_init_message(0, stream_id, 1), &metadata))
+            .await
+            .unwrap();
+        stream_handler.handle_next_msg().await;
+        let request = Request {
+            stream_id: TestStreamId(stream_id),
+            first_message_id: 0,
+            last_message_id: 10,
+        };
+        let (query, _, responses) = create_test_server_query_manager(request);
+        mock.queries_sender.send(query).await.unwrap();
+        stream_handler.handle_next_msg().await;
+        assert!(responses.collect::<Vec<_>>().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn retransmission_cache_is_bounded_by_bytes() {
+        let message_bytes = Vec::<u8>::from(build_init_message(0, 0, 0)).len();
+        let config = StreamHandlerConfig {
+            retransmission_cache_bytes: 2 * message_bytes,
+            ..Default::default()
+        };
+        let (
+            mut stream_handler,
+            _,
+            _,
+            mut client_to_streamhandler_sender,
+            _network_receiver,
+            mut mock,
+        ) = setup_with_config(config);
+
+        // Send one message on each of three streams. Only the two latest streams fit in the cache.
+        let mut senders = Vec::new();
+        for stream_id in 0..3 {
+            let (mut sender, stream_receiver) = mpsc::channel(CHANNEL_SIZE);
+            client_to_streamhandler_sender
+                .send((TestStreamId(stream_id), stream_receiver))
+                .await
+                .unwrap();
+            stream_handler.handle_next_msg().await;
+            sender.send(build_init_part(0)).await.unwrap();
+            stream_handler.handle_next_msg().await;
+            senders.push(sender);
+        }
+        assert_eq!(stream_handler.retransmission_cache_bytes, 2 * message_bytes);
+
+        for (stream_id, expected) in [
+            (0, vec![]),
+            (1, vec![build_init_message(0, 1, 0)]),
+            (2, vec![build_init_message(0, 2, 0)]),
+        ] {
+            let request = Request {
+                stream_id: TestStreamId(stream_id),
+                first_message_id: 0,
+                last_message_id: 10,
+            };
+            let (query, _, responses) = create_test_server_query_manager(request);
+            mock.queries_sender.send(query).await.unwrap();
+            stream_handler.handle_next_msg().await;
+            assert_eq!(responses.collect::<Vec<_>>().await, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn limits_streams_per_peer() {
+        let config = StreamHandlerConfig { max_streams_per_peer: 2, ..Default::default() };
+        let (mut stream_handler, mut network_sender, _inbound_internal_receiver, _, _, _) =
+            setup_with_config(config);
+        let metadata = BroadcastedMessageMetadata::get_test_instance(&mut get_rng());
+
+        for stream_id in 0..3 {
+            let message = build_init_message(0, stream_id, 0);
+            network_sender.send((Ok(message), metadata.clone())).await.unwrap();
+            stream_handler.handle_next_msg().await;
+        }
+        let stream_ids: BTreeSet<_> =
+            stream_handler.inbound_stream_data.keys().map(|(_, stream_id)| stream_id.0).collect();
+        assert_eq!(stream_ids, BTreeSet::from([0, 1]));
+    }
+
+    #[tokio::test]
+    async fn drops_streams_exceeding_max_messages() {
+        let stream_id = 1;
+        let config = StreamHandlerConfig { max_messages_per_stream: 2, ..Default::default() };
+        let (mut stream_handler, mut network_sender, mut inbound_internal_receiver, _, _, _) =
+            setup_with_config(config);
+        let metadata = BroadcastedMessageMetadata::get_test_instance(&mut get_rng());
+
+        for i in 0..3 {
+            let message = build_init_message(i, stream_id, i);
+            network_sender.send(received(message, &metadata)).await.unwrap();
+            stream_handler.handle_next_msg().await;
+        }
+        let receiver = inbound_internal_receiver.next().now_or_never().unwrap().unwrap();
+        // The stream is closed without a fin, after the messages within the limit.
+        assert_eq!(
+            receiver.collect::<Vec<_>>().await,
+            vec![build_init_part(0), build_init_part(1)]
+        );
+        assert!(stream_handler.inbound_stream_data.is_empty());
+    }
 }
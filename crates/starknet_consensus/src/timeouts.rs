@@ -0,0 +1,133 @@
+//! Choose the consensus timeouts for each round.
+//!
+//! [`TimeoutStrategy`] - computes the proposal, prevote and precommit timeouts for a round. The
+//! timeouts grow linearly with the round number, as described in the Tendermint paper, and may
+//! optionally adapt to the latencies observed in previous rounds and heights.
+
+#[cfg(test)]
+#[path = "timeouts_test.rs"]
+mod timeouts_test;
+
+use std::time::Duration;
+
+use crate::config::{AdaptiveTimeoutsConfig, TimeoutsConfig};
+use crate::metrics::{
+    CONSENSUS_PRECOMMIT_TIMEOUT,
+    CONSENSUS_PREVOTE_TIMEOUT,
+    CONSENSUS_PROPOSAL_TIMEOUT,
+};
+use crate::types::Round;
+
+/// An exponentially weighted moving average of latency samples.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Ewma {
+    value: Option<f64>,
+}
+
+impl Ewma {
+    fn observe(&mut self, sample: Duration, alpha: f64) {
+        let sample = sample.as_secs_f64();
+        self.value = Some(match self.value {
+            None => sample,
+            Some(value) => alpha * sample + (1.0 - alpha) * value,
+        });
+    }
+}
+
+/// Computes the timeouts for each round of consensus.
+///
+/// The strategy lives across heights, so latency estimates learned in one height are used to
+/// choose the timeouts of the next.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct TimeoutStrategy {
+    config: TimeoutsConfig,
+    // The time from the start of a round until a valid proposal was received.
+    proposal_latency: Ewma,
+    // The time from sending a vote until a quorum of votes was reached for that step.
+    quorum_latency: Ewma,
+}
+
+impl TimeoutStrategy {
+    pub(crate) fn new(config: TimeoutsConfig) -> Self {
+        Self { config, proposal_latency: Ewma::default(), quorum_latency: Ewma::default() }
+    }
+
+    /// The time to wait for a proposal in `round`.
+    pub(crate) fn proposal_timeout(&self, round: Round) -> Duration {
+        self.timeout(
+            self.config.proposal_timeout,
+            self.config.proposal_timeout_delta,
+            self.proposal_latency,
+            round,
+        )
+    }
+
+    /// The time to wait for a prevote quorum in `round`.
+    pub(crate) fn prevote_timeout(&self, round: Round) -> Duration {
+        self.timeout(
+            self.config.prevote_timeout,
+            self.config.prevote_timeout_delta,
+            self.quorum_latency,
+            round,
+        )
+    }
+
+    /// The time to wait for a precommit quorum in `round`.
+    pub(crate) fn precommit_timeout(&self, round: Round) -> Duration {
+        self.timeout(
+            self.config.precommit_timeout,
+            self.config.precommit_timeout_delta,
+            self.quorum_latency,
+            round,
+        )
+    }
+
+    /// Record the time it took a valid proposal to arrive, measured from the start of the round.
+    pub(crate) fn observe_proposal_latency(&mut self, latency: Duration) {
+        if let Some(adaptive) = &self.config.adaptive {
+            self.proposal_latency.observe(latency, adaptive.ewma_alpha);
+        }
+    }
+
+    /// Record the time it took to reach a quorum of votes, measured from sending our own vote.
+    pub(crate) fn observe_quorum_latency(&mut self, latency: Duration) {
+        if let Some(adaptive) = &self.config.adaptive {
+            self.quorum_latency.observe(latency, adaptive.ewma_alpha);
+        }
+    }
+
+    /// The proposal timeout of `round`, reported as the currently scheduled proposal timeout.
+    pub(crate) fn schedule_proposal_timeout(&self, round: Round) -> Duration {
+        let timeout = self.proposal_timeout(round);
+        CONSENSUS_PROPOSAL_TIMEOUT.set(timeout.as_secs_f64());
+        timeout
+    }
+
+    /// The prevote timeout of `round`, reported as the currently scheduled prevote timeout.
+    pub(crate) fn schedule_prevote_timeout(&self, round: Round) -> Duration {
+        let timeout = self.prevote_timeout(round);
+        CONSENSUS_PREVOTE_TIMEOUT.set(timeout.as_secs_f64());
+        timeout
+    }
+
+    /// The precommit timeout of `round`, reported as the currently scheduled precommit timeout.
+    pub(crate) fn schedule_precommit_timeout(&self, round: Round) -> Duration {
+        let timeout = self.precommit_timeout(round);
+        CONSENSUS_PRECOMMIT_TIMEOUT.set(timeout.as_secs_f64());
+        timeout
+    }
+
+    fn timeout(&self, base: Duration, delta: Duration, latency: Ewma, round: Round) -> Duration {
+        let base = match (&self.config.adaptive, latency.value) {
+            (Some(adaptive), Some(latency)) => adaptive_base(adaptive, latency),
+            _ => base,
+        };
+        base + delta * round
+    }
+}
+
+fn adaptive_base(config: &AdaptiveTimeoutsConfig, latency_seconds: f64) -> Duration {
+    Duration::from_secs_f64(latency_seconds * config.latency_multiplier)
+        .max(config.min_timeout)
+        .min(config.max_timeout)
+}
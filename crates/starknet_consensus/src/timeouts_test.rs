@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use validator::Validate;
+
+use super::TimeoutStrategy;
+use crate::config::{AdaptiveTimeoutsConfig, TimeoutsConfig};
+
+fn config(adaptive: Option<AdaptiveTimeoutsConfig>) -> TimeoutsConfig {
+    TimeoutsConfig {
+        proposal_timeout: Duration::from_secs(3),
+        prevote_timeout: Duration::from_secs(1),
+        precommit_timeout: Duration::from_secs(1),
+        proposal_timeout_delta: Duration::from_millis(500),
+        prevote_timeout_delta: Duration::from_millis(100),
+        precommit_timeout_delta: Duration::from_millis(200),
+        adaptive,
+    }
+}
+
+#[test]
+fn timeouts_grow_linearly_with_round() {
+    let strategy = TimeoutStrategy::new(config(None));
+
+    assert_eq!(strategy.proposal_timeout(0), Duration::from_secs(3));
+    assert_eq!(strategy.proposal_timeout(4), Duration::from_secs(5));
+    assert_eq!(strategy.prevote_timeout(0), Duration::from_secs(1));
+    assert_eq!(strategy.prevote_timeout(10), Duration::from_secs(2));
+    assert_eq!(strategy.precommit_timeout(5), Duration::from_secs(2));
+}
+
+#[test]
+fn latencies_ignored_when_not_adaptive() {
+    let mut strategy = TimeoutStrategy::new(config(None));
+    strategy.observe_proposal_latency(Duration::from_millis(10));
+    strategy.observe_quorum_latency(Duration::from_millis(10));
+
+    assert_eq!(strategy, TimeoutStrategy::new(config(None)));
+}
+
+#[test]
+fn adaptive_timeouts_follow_observed_latency() {
+    let adaptive = AdaptiveTimeoutsConfig {
+        ewma_alpha: 0.5,
+        latency_multiplier: 2.0,
+        min_timeout: Duration::from_millis(100),
+        max_timeout: Duration::from_secs(10),
+    };
+    let mut strategy = TimeoutStrategy::new(config(Some(adaptive)));
+
+    // Without samples, the configured base is used.
+    assert_eq!(strategy.proposal_timeout(0), Duration::from_secs(3));
+
+    strategy.observe_proposal_latency(Duration::from_millis(400));
+    assert_eq!(strategy.proposal_timeout(0), Duration::from_millis(800));
+    strategy.observe_proposal_latency(Duration::from_millis(800));
+    // EWMA: 0.5 * 800 + 0.5 * 400 = 600.
+    assert_eq!(strategy.proposal_timeout(0), Duration::from_millis(1200));
+    assert_eq!(strategy.proposal_timeout(2), Duration::from_millis(2200));
+
+    // The quorum latency is tracked separately.
+    assert_eq!(strategy.prevote_timeout(0), Duration::from_secs(1));
+    strategy.observe_quorum_latency(Duration::from_millis(150));
+    assert_eq!(strategy.prevote_timeout(0), Duration::from_millis(300));
+    assert_eq!(strategy.precommit_timeout(1), Duration::from_millis(500));
+}
+
+#[test]
+fn adaptive_timeouts_are_bounded() {
+    let adaptive = AdaptiveTimeoutsConfig {
+        ewma_alpha: 1.0,
+        latency_multiplier: 2.0,
+        min_timeout: Duration::from_millis(500),
+        max_timeout: Duration::from_secs(4),
+    };
+    let mut strategy = TimeoutStrategy::new(config(Some(adaptive)));
+
+    strategy.observe_proposal_latency(Duration::from_millis(1));
+    assert_eq!(strategy.proposal_timeout(0), Duration::from_millis(500));
+
+    strategy.observe_proposal_latency(Duration::from_secs(60));
+    assert_eq!(strategy.proposal_timeout(0), Duration::from_secs(4));
+    // The round increment is applied on top of the bound.
+    assert_eq!(strategy.proposal_timeout(2), Duration::from_secs(5));
+}
+
+#[test]
+fn adaptive_bounds_must_not_be_inverted() {
+    let adaptive = AdaptiveTimeoutsConfig {
+        min_timeout: Duration::from_secs(5),
+        max_timeout: Duration::from_secs(1),
+        ..Default::default()
+    };
+    assert!(adaptive.validate().is_err());
+    assert!(config(Some(adaptive)).validate().is_err());
+    assert!(config(Some(AdaptiveTimeoutsConfig::default())).validate().is_ok());
+}
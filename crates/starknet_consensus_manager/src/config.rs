@@ -6,7 +6,7 @@ use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_network::NetworkConfig;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockNumber;
-use starknet_consensus::config::ConsensusConfig;
+use starknet_consensus::config::{ConsensusConfig, StreamHandlerConfig};
 use starknet_consensus_orchestrator::cende::CendeConfig;
 use starknet_consensus_orchestrator::config::ContextConfig;
 use validator::Validate;
@@ -21,8 +21,11 @@ pub struct ConsensusManagerConfig {
     pub network_config: NetworkConfig,
     pub cende_config: CendeConfig,
     pub revert_config: RevertConfig,
+    #[validate]
+    pub stream_handler_config: StreamHandlerConfig,
     pub votes_topic: String,
     pub proposals_topic: String,
+    pub proposals_retransmission_protocol: String,
     pub broadcast_buffer_size: usize,
     pub immediate_active_height: BlockNumber,
 }
@@ -42,6 +45,12 @@ impl SerializeConfig for ConsensusManagerConfig {
                 "The topic for consensus proposals.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "proposals_retransmission_protocol",
+                &self.proposals_retransmission_protocol,
+                "The protocol for requesting missing messages of consensus proposals.",
+                ParamPrivacyInput::Public,
+            ),
             ser_param(
                 "broadcast_buffer_size",
                 &self.broadcast_buffer_size,
@@ -60,6 +69,10 @@ impl SerializeConfig for ConsensusManagerConfig {
         config.extend(append_sub_config_name(self.cende_config.dump(), "cende_config"));
         config.extend(append_sub_config_name(self.network_config.dump(), "network_config"));
         config.extend(append_sub_config_name(self.revert_config.dump(), "revert_config"));
+        config.extend(append_sub_config_name(
+            self.stream_handler_config.dump(),
+            "stream_handler_config",
+        ));
         config
     }
 }
@@ -72,8 +85,11 @@ impl Default for ConsensusManagerConfig {
             cende_config: CendeConfig::default(),
             network_config: NetworkConfig::default(),
             revert_config: RevertConfig::default(),
+            stream_handler_config: StreamHandlerConfig::default(),
             votes_topic: "consensus_votes".to_string(),
             proposals_topic: "consensus_proposals".to_string(),
+            proposals_retransmission_protocol: "/starknet/consensus/proposals_retransmission/0.1.0"
+                .to_string(),
             broadcast_buffer_size: 10000,
             immediate_active_height: BlockNumber::default(),
         }
@@ -10,7 +10,13 @@ use futures::channel::mpsc;
 use papyrus_network::gossipsub_impl::Topic;
 use papyrus_network::network_manager::metrics::{BroadcastNetworkMetrics, NetworkMetrics};
 use papyrus_network::network_manager::{BroadcastTopicChannels, NetworkManager};
-use papyrus_protobuf::consensus::{HeightAndRound, ProposalPart, StreamMessage, Vote};
+use papyrus_protobuf::consensus::{
+    HeightAndRound,
+    ProposalPart,
+    StreamMessage,
+    StreamMessagesRequest,
+    Vote,
+};
 use starknet_api::block::BlockNumber;
 use starknet_batcher_types::batcher_types::RevertBlockInput;
 use starknet_batcher_types::communication::SharedBatcherClient;
@@ -18,10 +24,14 @@ use starknet_class_manager_types::SharedClassManagerClient;
 use starknet_consensus::stream_handler::{StreamHandler, CHANNEL_BUFFER_LENGTH};
 use starknet_consensus::types::ConsensusError;
 use starknet_consensus_orchestrator::cende::CendeAmbassador;
-use starknet_consensus_orchestrator::sequencer_consensus_context::SequencerConsensusContext;
+use starknet_consensus_orchestrator::sequencer_consensus_context::{
+    validator_ids,
+    SequencerConsensusContext,
+};
 use starknet_infra_utils::type_name::short_type_name;
 use starknet_sequencer_infra::component_definitions::ComponentStarter;
 use starknet_state_sync_types::communication::SharedStateSyncClient;
+use tokio::sync::watch;
 use tracing::{error, info};
 
 use crate::config::ConsensusManagerConfig;
@@ -30,6 +40,7 @@ use crate::metrics::{
     CONSENSUS_NUM_RECEIVED_MESSAGES,
     CONSENSUS_NUM_SENT_MESSAGES,
 };
+use crate::vote_validator::VoteValidator;
 
 #[derive(Clone)]
 pub struct ConsensusManager {
@@ -72,13 +83,43 @@ impl ConsensusManager {
             )
             .expect("Failed to register broadcast topic");
 
+        let observer_height =
+            self.batcher_client.get_height().await.map(|h| h.height).map_err(|e| {
+                error!("Failed to get height from batcher: {:?}", e);
+                ConsensusError::Other("Failed to get height from batcher".to_string())
+            })?;
+        // The context publishes the height consensus is working on to the vote validator.
+        let (current_height_sender, current_height_receiver) = watch::channel(observer_height);
+        let vote_validator = VoteValidator::new(
+            validator_ids(self.config.context_config.num_validators),
+            current_height_receiver,
+            self.config.consensus_config.future_height_limit,
+        );
         let votes_broadcast_channels = network_manager
-            .register_broadcast_topic::<Vote>(
+            .register_broadcast_topic_with_validator::<Vote>(
                 Topic::new(self.config.votes_topic.clone()),
                 self.config.broadcast_buffer_size,
+                // Votes that can't be parsed are rejected before they're propagated. Votes that
+                // pass the validator are then validated by consensus.
+                Box::new(move |vote| vote_validator.validate(vote)),
             )
             .expect("Failed to register broadcast topic");
 
+        let retransmission_server = network_manager.register_sqmr_protocol_server::<
+            StreamMessagesRequest<HeightAndRound>,
+            StreamMessage<ProposalPart, HeightAndRound>,
+        >(
+            self.config.proposals_retransmission_protocol.clone(),
+            self.config.broadcast_buffer_size,
+        );
+        let retransmission_client = network_manager.register_sqmr_protocol_client::<
+            StreamMessagesRequest<HeightAndRound>,
+            StreamMessage<ProposalPart, HeightAndRound>,
+        >(
+            self.config.proposals_retransmission_protocol.clone(),
+            self.config.broadcast_buffer_size,
+        );
+
         let BroadcastTopicChannels {
             broadcasted_messages_receiver: inbound_network_receiver,
             broadcast_topic_client: outbound_network_sender,
@@ -89,17 +130,15 @@ impl ConsensusManager {
         let (outbound_internal_sender, outbound_internal_receiver) =
             mpsc::channel(CHANNEL_BUFFER_LENGTH);
         let stream_handler = StreamHandler::new(
+            self.config.stream_handler_config.clone(),
             inbound_internal_sender,
             inbound_network_receiver,
             outbound_internal_receiver,
             outbound_network_sender,
+            retransmission_client,
+            retransmission_server,
         );
 
-        let observer_height =
-            self.batcher_client.get_height().await.map(|h| h.height).map_err(|e| {
-                error!("Failed to get height from batcher: {:?}", e);
-                ConsensusError::Other("Failed to get height from batcher".to_string())
-            })?;
         let active_height = if self.config.immediate_active_height == observer_height {
             // Setting `start_height` is only used to enable consensus starting immediately without
             // observing the first height. This means consensus may return to a height
@@ -121,6 +160,7 @@ impl ConsensusManager {
                 self.config.cende_config.clone(),
                 Arc::clone(&self.class_manager_client),
             )),
+            current_height_sender,
         );
 
         let network_task = tokio::spawn(network_manager.run());
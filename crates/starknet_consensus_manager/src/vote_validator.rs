@@ -0,0 +1,58 @@
+#[cfg(test)]
+#[path = "vote_validator_test.rs"]
+mod vote_validator_test;
+
+use std::collections::HashSet;
+
+use papyrus_network::network_manager::TopicValidationResult;
+use papyrus_protobuf::consensus::Vote;
+use starknet_api::block::BlockNumber;
+use starknet_consensus::types::ValidatorId;
+use tokio::sync::watch;
+use tracing::debug;
+
+/// Validates the votes received on the votes topic before they're propagated to other peers.
+/// Votes of peers that aren't validators are provably invalid, so they're rejected and their
+/// propagator is penalized. Votes for heights outside the window around the current height are
+/// ignored without a penalty, since an honest validator sends them whenever this node is behind or
+/// ahead of it. Votes for recent past heights are ignored too, since honest validators may still
+/// send them.
+pub(crate) struct VoteValidator {
+    validators: HashSet<ValidatorId>,
+    current_height: watch::Receiver<BlockNumber>,
+    future_height_limit: u64,
+}
+
+impl VoteValidator {
+    pub(crate) fn new(
+        validators: impl IntoIterator<Item = ValidatorId>,
+        current_height: watch::Receiver<BlockNumber>,
+        future_height_limit: u32,
+    ) -> Self {
+        Self {
+            validators: validators.into_iter().collect(),
+            current_height,
+            future_height_limit: future_height_limit.into(),
+        }
+    }
+
+    pub(crate) fn validate(&self, vote: &Vote) -> TopicValidationResult {
+        if !self.validators.contains(&vote.voter) {
+            debug!("Rejecting a vote of {:?}, which is not a validator.", vote.voter);
+            return TopicValidationResult::Reject;
+        }
+        let current_height = self.current_height.borrow().0;
+        if vote.height.abs_diff(current_height) > self.future_height_limit {
+            debug!(
+                "Ignoring a vote for height {}, which is too far from the current height \
+                 {current_height}.",
+                vote.height
+            );
+            return TopicValidationResult::Ignore;
+        }
+        if vote.height < current_height {
+            return TopicValidationResult::Ignore;
+        }
+        TopicValidationResult::Accept
+    }
+}
@@ -0,0 +1,84 @@
+use papyrus_network::network_manager::TopicValidationResult;
+use papyrus_protobuf::consensus::{Vote, DEFAULT_VALIDATOR_ID};
+use starknet_api::block::BlockNumber;
+use starknet_consensus::types::ValidatorId;
+use tokio::sync::watch;
+
+use crate::vote_validator::VoteValidator;
+
+const CURRENT_HEIGHT: u64 = 100;
+const FUTURE_HEIGHT_LIMIT: u32 = 10;
+
+fn vote(voter: u64, height: u64) -> Vote {
+    Vote { voter: ValidatorId::from(voter), height, ..Default::default() }
+}
+
+fn vote_validator() -> (VoteValidator, watch::Sender<BlockNumber>) {
+    let (current_height_sender, current_height_receiver) =
+        watch::channel(BlockNumber(CURRENT_HEIGHT));
+    let validators = [DEFAULT_VALIDATOR_ID, DEFAULT_VALIDATOR_ID + 1].map(ValidatorId::from);
+    (
+        VoteValidator::new(validators, current_height_receiver, FUTURE_HEIGHT_LIMIT),
+        current_height_sender,
+    )
+}
+
+#[test]
+fn votes_of_non_validators_are_rejected() {
+    let (vote_validator, _current_height_sender) = vote_validator();
+    assert_eq!(
+        vote_validator.validate(&vote(DEFAULT_VALIDATOR_ID + 1, CURRENT_HEIGHT)),
+        TopicValidationResult::Accept
+    );
+    assert_eq!(
+        vote_validator.validate(&vote(DEFAULT_VALIDATOR_ID + 2, CURRENT_HEIGHT)),
+        TopicValidationResult::Reject
+    );
+}
+
+#[test]
+fn votes_by_height() {
+    let (vote_validator, current_height_sender) = vote_validator();
+    let future_height_limit = u64::from(FUTURE_HEIGHT_LIMIT);
+    for (height, expected_result) in [
+        (CURRENT_HEIGHT + future_height_limit, TopicValidationResult::Accept),
+        (CURRENT_HEIGHT + future_height_limit + 1, TopicValidationResult::Ignore),
+        (CURRENT_HEIGHT - 1, TopicValidationResult::Ignore),
+        (CURRENT_HEIGHT - future_height_limit - 1, TopicValidationResult::Ignore),
+    ] {
+        assert_eq!(vote_validator.validate(&vote(DEFAULT_VALIDATOR_ID, height)), expected_result);
+    }
+
+    // The validator follows the current height of consensus.
+    current_height_sender.send_replace(BlockNumber(CURRENT_HEIGHT + 1));
+    assert_eq!(
+        vote_validator.validate(&vote(DEFAULT_VALIDATOR_ID, CURRENT_HEIGHT)),
+        TopicValidationResult::Ignore
+    );
+}
+
+#[test]
+fn votes_of_validators_ahead_of_a_lagging_node_are_not_rejected() {
+    let (vote_validator, current_height_sender) = vote_validator();
+    // The node is still syncing while the validators vote far ahead of it.
+    current_height_sender.send_replace(BlockNumber(0));
+    let validators_height = CURRENT_HEIGHT + u64::from(FUTURE_HEIGHT_LIMIT);
+    for voter in [DEFAULT_VALIDATOR_ID, DEFAULT_VALIDATOR_ID + 1] {
+        assert_eq!(
+            vote_validator.validate(&vote(voter, validators_height)),
+            TopicValidationResult::Ignore
+        );
+    }
+
+    // Once the node catches up, the votes are accepted.
+    current_height_sender.send_replace(BlockNumber(validators_height));
+    assert_eq!(
+        vote_validator.validate(&vote(DEFAULT_VALIDATOR_ID, validators_height)),
+        TopicValidationResult::Accept
+    );
+    // A non-validator is rejected regardless of the height.
+    assert_eq!(
+        vote_validator.validate(&vote(DEFAULT_VALIDATOR_ID + 2, CURRENT_HEIGHT * 10)),
+        TopicValidationResult::Reject
+    );
+}
@@ -0,0 +1,39 @@
+//! Rebuilds a papyrus storage from the blobs written by the LocalDirectory Cende sink.
+//!
+//! The headers, bodies and state of the blocks are rebuilt. The blobs don't hold the block hashes
+//! and state roots, so the replayed headers don't have them. Running the tool again on the same
+//! storage continues from where it stopped.
+use std::path::PathBuf;
+
+use clap::Parser;
+use papyrus_storage::db::DbConfig;
+use papyrus_storage::{open_storage, StorageConfig, StorageScope};
+use starknet_api::core::ChainId;
+use starknet_consensus_orchestrator::cende::replay::replay_blobs;
+
+#[derive(Parser)]
+#[command(name = "Cende replay")]
+struct Args {
+    #[arg(long = "blobs_directory", help = "The directory the blobs were written to.")]
+    blobs_directory: PathBuf,
+    #[arg(long = "storage_path", help = "The directory of the storage to replay the blobs into.")]
+    storage_path: PathBuf,
+    #[arg(long = "chain_id", help = "The chain id of the storage.")]
+    chain_id: String,
+}
+
+fn main() {
+    let Args { blobs_directory, storage_path, chain_id } = Args::parse();
+    let storage_config = StorageConfig {
+        db_config: DbConfig {
+            path_prefix: storage_path,
+            chain_id: ChainId::from(chain_id),
+            ..Default::default()
+        },
+        scope: StorageScope::StateOnly,
+        ..Default::default()
+    };
+    let (_reader, mut writer) = open_storage(storage_config).expect("Failed to open the storage.");
+    let next_block = replay_blobs(&blobs_directory, &mut writer).expect("Failed to replay blobs.");
+    println!("Replayed the blobs up to block {next_block}.");
+}
@@ -1,11 +1,14 @@
+use std::io::Read;
 use std::sync::Arc;
 
+use flate2::read::GzDecoder;
 use rstest::rstest;
 use starknet_api::block::{BlockInfo, BlockNumber};
 use starknet_class_manager_types::MockClassManagerClient;
+use tempfile::tempdir;
 
-use super::{CendeAmbassador, RECORDER_WRITE_BLOB_PATH};
-use crate::cende::{BlobParameters, CendeConfig, CendeContext};
+use super::{blob_file_path, CendeAmbassador, RECORDER_WRITE_BLOB_PATH};
+use crate::cende::{BlobParameters, CendeConfig, CendeContext, CendeSinkType};
 
 const HEIGHT_TO_WRITE: BlockNumber = BlockNumber(10);
 
@@ -81,3 +84,42 @@ async fn no_write_at_skipped_height() {
 
     assert!(cende_ambassador.write_prev_height_blob(HEIGHT_TO_WRITE).await.unwrap());
 }
+
+#[tokio::test]
+async fn write_prev_height_blob_to_local_directory() {
+    let blobs_directory = tempdir().unwrap();
+    let cende_ambassador = CendeAmbassador::new(
+        CendeConfig {
+            sink: CendeSinkType::LocalDirectory,
+            blobs_directory: blobs_directory.path().to_path_buf(),
+            ..Default::default()
+        },
+        Arc::new(MockClassManagerClient::new()),
+    );
+    cende_ambassador
+        .prepare_blob_for_next_height(BlobParameters::with_block_number(HEIGHT_TO_WRITE))
+        .await
+        .unwrap();
+
+    assert!(
+        cende_ambassador.write_prev_height_blob(HEIGHT_TO_WRITE.unchecked_next()).await.unwrap()
+    );
+
+    let blob_file =
+        std::fs::File::open(blob_file_path(blobs_directory.path(), HEIGHT_TO_WRITE)).unwrap();
+    let mut serialized_blob = String::new();
+    GzDecoder::new(blob_file).read_to_string(&mut serialized_blob).unwrap();
+    let blob: serde_json::Value = serde_json::from_str(&serialized_blob).unwrap();
+    assert_eq!(blob["block_number"], HEIGHT_TO_WRITE.0);
+}
+
+#[tokio::test]
+async fn no_op_sink_always_succeeds() {
+    let cende_ambassador = CendeAmbassador::new(
+        CendeConfig { sink: CendeSinkType::NoOp, ..Default::default() },
+        Arc::new(MockClassManagerClient::new()),
+    );
+
+    // Succeeds even though there is no blob to write.
+    assert!(cende_ambassador.write_prev_height_blob(HEIGHT_TO_WRITE).await.unwrap());
+}
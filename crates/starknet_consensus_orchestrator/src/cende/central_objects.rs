@@ -424,6 +424,12 @@ pub(crate) struct CentralSierraContractClass {
     contract_class: SierraContractClass,
 }
 
+impl From<SierraContractClass> for CentralSierraContractClass {
+    fn from(contract_class: SierraContractClass) -> CentralSierraContractClass {
+        CentralSierraContractClass { contract_class }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub(crate) struct CentralCasmContractClass {
     compiled_class: CasmContractClass,
@@ -471,7 +477,7 @@ async fn get_contract_classes_if_declare(
         .get_sierra(class_hash)
         .await?
         .ok_or(CendeAmbassadorError::ClassNotFound { class_hash })?;
-    let hashed_sierra = (class_hash, CentralSierraContractClass { contract_class: sierra });
+    let hashed_sierra = (class_hash, sierra.into());
 
     Ok(Some((hashed_sierra, hashed_casm)))
 }
@@ -1,9 +1,13 @@
 #[cfg(test)]
 mod cende_test;
 mod central_objects;
+pub mod replay;
 
 use std::collections::BTreeMap;
+use std::fs::{self, File};
 use std::future::ready;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -21,6 +25,8 @@ use central_objects::{
     CentralStateDiff,
     CentralTransactionWritten,
 };
+use flate2::write::GzEncoder;
+use flate2::Compression;
 #[cfg(test)]
 use mockall::automock;
 use papyrus_config::dumping::{ser_optional_param, ser_param, SerializeConfig};
@@ -91,8 +97,7 @@ pub struct CendeAmbassador {
     // `None` indicates that there is no blob to write, and therefore, the node can't be the
     // proposer.
     prev_height_blob: Arc<Mutex<Option<AerospikeBlob>>>,
-    url: Url,
-    client: Client,
+    sink: BlobSink,
     skip_write_height: Option<BlockNumber>,
     class_manager: SharedClassManagerClient,
 }
@@ -104,29 +109,41 @@ impl CendeAmbassador {
     pub fn new(cende_config: CendeConfig, class_manager: SharedClassManagerClient) -> Self {
         CendeAmbassador {
             prev_height_blob: Arc::new(Mutex::new(None)),
-            url: cende_config
-                .recorder_url
-                .join(RECORDER_WRITE_BLOB_PATH)
-                .expect("Failed to join `RECORDER_WRITE_BLOB_PATH` with the Recorder URL"),
-            client: Client::new(),
+            sink: BlobSink::new(&cende_config),
             skip_write_height: cende_config.skip_write_height,
             class_manager,
         }
     }
 }
 
+/// The backend that blobs are written to.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CendeSinkType {
+    /// Post the blobs to the Pythonic cende_recorder.
+    #[default]
+    Http,
+    /// Write each blob as compressed JSON to a local directory, one file per height.
+    LocalDirectory,
+    /// Don't write the blobs at all.
+    NoOp,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct CendeConfig {
+    pub sink: CendeSinkType,
     pub recorder_url: Url,
+    pub blobs_directory: PathBuf,
     pub skip_write_height: Option<BlockNumber>,
 }
 
 impl Default for CendeConfig {
     fn default() -> Self {
         CendeConfig {
+            sink: CendeSinkType::default(),
             recorder_url: "https://recorder_url"
                 .parse()
                 .expect("recorder_url must be a valid Recorder URL"),
+            blobs_directory: PathBuf::from("./data/cende_blobs"),
             skip_write_height: None,
         }
     }
@@ -134,12 +151,26 @@ impl Default for CendeConfig {
 
 impl SerializeConfig for CendeConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        let mut config = BTreeMap::from_iter([ser_param(
-            "recorder_url",
-            &self.recorder_url,
-            "The URL of the Pythonic cende_recorder",
-            ParamPrivacyInput::Private,
-        )]);
+        let mut config = BTreeMap::from_iter([
+            ser_param(
+                "sink",
+                &self.sink,
+                "The backend to write blobs to: Http (the cende_recorder), LocalDirectory or NoOp.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "recorder_url",
+                &self.recorder_url,
+                "The URL of the Pythonic cende_recorder",
+                ParamPrivacyInput::Private,
+            ),
+            ser_param(
+                "blobs_directory",
+                &self.blobs_directory,
+                "The directory to write blobs to when using the LocalDirectory sink.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
         config.extend(ser_optional_param(
             &self.skip_write_height,
             BlockNumber(0),
@@ -156,6 +187,10 @@ impl SerializeConfig for CendeConfig {
 #[async_trait]
 impl CendeContext for CendeAmbassador {
     fn write_prev_height_blob(&self, current_height: BlockNumber) -> JoinHandle<bool> {
+        if let BlobSink::NoOp = self.sink {
+            debug!("Blobs are not written, skipping the blob of height {current_height}.");
+            return tokio::spawn(ready(true));
+        }
         debug!("Start writing to Aerospike previous height blob for height {current_height}.");
 
         // TODO(dvir): consider returning a future that will be spawned in the context instead.
@@ -170,7 +205,7 @@ impl CendeContext for CendeAmbassador {
         }
 
         let prev_height_blob = self.prev_height_blob.clone();
-        let request_builder = self.client.post(self.url.clone());
+        let sink = self.sink.clone();
 
         task::spawn(
             async move {
@@ -202,7 +237,7 @@ impl CendeContext for CendeAmbassador {
                 }
 
                 debug!("Writing blob to Aerospike.");
-                return send_write_blob(request_builder, blob).await;
+                return sink.write(blob).await;
             }
             .instrument(tracing::debug_span!("cende write_prev_height_blob height")),
         )
@@ -226,6 +261,85 @@ impl CendeContext for CendeAmbassador {
     }
 }
 
+// The backend blobs are written to, as configured by `CendeSinkType`.
+#[derive(Clone)]
+enum BlobSink {
+    Http { url: Url, client: Client },
+    LocalDirectory(PathBuf),
+    NoOp,
+}
+
+impl BlobSink {
+    fn new(cende_config: &CendeConfig) -> Self {
+        match cende_config.sink {
+            CendeSinkType::Http => BlobSink::Http {
+                url: cende_config
+                    .recorder_url
+                    .join(RECORDER_WRITE_BLOB_PATH)
+                    .expect("Failed to join `RECORDER_WRITE_BLOB_PATH` with the Recorder URL"),
+                client: Client::new(),
+            },
+            CendeSinkType::LocalDirectory => {
+                BlobSink::LocalDirectory(cende_config.blobs_directory.clone())
+            }
+            CendeSinkType::NoOp => BlobSink::NoOp,
+        }
+    }
+
+    async fn write(&self, blob: &AerospikeBlob) -> bool {
+        match self {
+            BlobSink::Http { url, client } => send_write_blob(client.post(url.clone()), blob).await,
+            BlobSink::LocalDirectory(directory) => write_blob_to_directory(directory, blob).await,
+            BlobSink::NoOp => true,
+        }
+    }
+}
+
+/// The file in `directory` that the blob of `block_number` is written to by the LocalDirectory
+/// sink.
+pub fn blob_file_path(directory: &Path, block_number: BlockNumber) -> PathBuf {
+    directory.join(format!("{}.json.gz", block_number.0))
+}
+
+async fn write_blob_to_directory(directory: &Path, blob: &AerospikeBlob) -> bool {
+    let serialized_blob = match serde_json::to_vec(blob) {
+        Ok(serialized_blob) => serialized_blob,
+        Err(err) => {
+            warn!("Failed to serialize blob with block number {}. Error: {err}", blob.block_number);
+            return false;
+        }
+    };
+    let path = blob_file_path(directory, blob.block_number);
+    let write_result = task::spawn_blocking(move || write_compressed(&path, &serialized_blob))
+        .await
+        .expect("Writing a blob to a file should not panic");
+    match write_result {
+        Ok(()) => {
+            debug!("Blob with block number {} was written to {directory:?}.", blob.block_number);
+            true
+        }
+        Err(err) => {
+            warn!(
+                "Failed to write blob with block number {} to {directory:?}. Error: {err}",
+                blob.block_number
+            );
+            false
+        }
+    }
+}
+
+// Writes to a temporary file which is then renamed, so a crash never leaves a partial blob behind.
+fn write_compressed(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    let temp_path = path.with_extension("tmp");
+    let mut encoder = GzEncoder::new(File::create(&temp_path)?, Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()?.sync_all()?;
+    fs::rename(temp_path, path)
+}
+
 async fn send_write_blob(request_builder: RequestBuilder, blob: &AerospikeBlob) -> bool {
     // TODO(dvir): use compression to reduce the size of the blob in the network.
     match request_builder.json(blob).send().await {
@@ -0,0 +1,652 @@
+//! Replays the blobs written by the LocalDirectory sink into a papyrus storage.
+//!
+//! The blobs hold everything the sequencer decided on: the block info, transactions and execution
+//! infos of each block, its state diff and the classes it declared. Replaying them rebuilds the
+//! headers, bodies and state of the chain, which makes the blobs directory an archival record of
+//! the sequencer.
+//!
+//! The block hash and the state root of a block are computed after its blob is written, so the
+//! replayed headers don't have them. Like the blocks that consensus passes to the state sync, a
+//! replayed block is stored under a hash derived from its number.
+
+#[cfg(test)]
+#[path = "replay_test.rs"]
+mod replay_test;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use flate2::read::GzDecoder;
+use indexmap::IndexMap;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::class::ClassStorageWriter;
+use papyrus_storage::compiled_class::CasmStorageWriter;
+use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
+use papyrus_storage::{StorageError, StorageWriter};
+use serde::Deserialize;
+use starknet_api::block::{
+    BlockBody,
+    BlockHash,
+    BlockHeader,
+    BlockHeaderWithoutHash,
+    BlockNumber,
+    BlockTimestamp,
+    GasPrice,
+    GasPricePerToken,
+    StarknetVersion,
+};
+use starknet_api::core::{
+    ClassHash,
+    CompiledClassHash,
+    ContractAddress,
+    EntryPointSelector,
+    EthAddress,
+    Nonce,
+    SequencerContractAddress,
+};
+use starknet_api::data_availability::{DataAvailabilityMode, L1DataAvailabilityMode};
+use starknet_api::execution_resources::{Builtin, ExecutionResources, GasAmount, GasVector};
+use starknet_api::state::{SierraContractClass, StorageKey, ThinStateDiff};
+use starknet_api::transaction::fields::{
+    AccountDeploymentData,
+    AllResourceBounds,
+    Calldata,
+    ContractAddressSalt,
+    Fee,
+    PaymasterData,
+    ResourceBounds,
+    Tip,
+    TransactionSignature,
+    ValidResourceBounds,
+};
+use starknet_api::transaction::{
+    DeclareTransaction,
+    DeclareTransactionOutput,
+    DeclareTransactionV3,
+    DeployAccountTransaction,
+    DeployAccountTransactionOutput,
+    DeployAccountTransactionV3,
+    Event,
+    EventContent,
+    InvokeTransaction,
+    InvokeTransactionOutput,
+    InvokeTransactionV3,
+    L1HandlerTransaction,
+    L1HandlerTransactionOutput,
+    L2ToL1Payload,
+    MessageToL1,
+    RevertedTransactionExecutionStatus,
+    Transaction,
+    TransactionExecutionStatus,
+    TransactionHash,
+    TransactionOutput,
+};
+use starknet_types_core::felt::Felt;
+use strum::IntoEnumIterator;
+use tracing::{debug, info};
+
+use super::blob_file_path;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ReplayError {
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error("Blob of block {block_number} is missing the compiled class of {class_hash}.")]
+    MissingCompiledClass { block_number: BlockNumber, class_hash: ClassHash },
+    #[error("Blob of block {block_number} is missing the contract class of {class_hash}.")]
+    MissingContractClass { block_number: BlockNumber, class_hash: ClassHash },
+    #[error(transparent)]
+    SerdeError(#[from] serde_json::Error),
+    #[error(transparent)]
+    StorageError(#[from] StorageError),
+    #[error(
+        "Blob of block {block_number} has {num_transactions} transactions but \
+         {num_execution_infos} execution infos."
+    )]
+    TransactionsExecutionInfosMismatch {
+        block_number: BlockNumber,
+        num_transactions: usize,
+        num_execution_infos: usize,
+    },
+    #[error("The blob file of block {expected} holds block {found}.")]
+    UnexpectedBlockNumber { expected: BlockNumber, found: BlockNumber },
+}
+
+pub type ReplayResult<T> = Result<T, ReplayError>;
+
+// The parts of a written blob that are needed to rebuild the storage. The rest of the fields are
+// ignored.
+#[derive(Debug, Deserialize)]
+struct RecordedBlob {
+    block_number: BlockNumber,
+    state_diff: RecordedStateDiff,
+    fee_market_info: RecordedFeeMarketInfo,
+    transactions: Vec<RecordedTransactionWritten>,
+    execution_infos: Vec<RecordedExecutionInfo>,
+    contract_classes: Vec<(ClassHash, RecordedSierraContractClass)>,
+    compiled_classes: Vec<(CompiledClassHash, RecordedCasmContractClass)>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedStateDiff {
+    address_to_class_hash: IndexMap<ContractAddress, ClassHash>,
+    nonces: IndexMap<DataAvailabilityMode, IndexMap<ContractAddress, Nonce>>,
+    storage_updates:
+        IndexMap<DataAvailabilityMode, IndexMap<ContractAddress, IndexMap<StorageKey, Felt>>>,
+    declared_classes: IndexMap<ClassHash, CompiledClassHash>,
+    block_info: RecordedBlockInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedBlockInfo {
+    block_timestamp: BlockTimestamp,
+    sequencer_address: ContractAddress,
+    l1_gas_price: RecordedResourcePrice,
+    l1_data_gas_price: RecordedResourcePrice,
+    l2_gas_price: RecordedResourcePrice,
+    use_kzg_da: bool,
+    starknet_version: Option<StarknetVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedResourcePrice {
+    price_in_wei: GasPrice,
+    price_in_fri: GasPrice,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedFeeMarketInfo {
+    l2_gas_consumed: u64,
+    next_l2_gas_price: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedTransactionWritten {
+    tx: RecordedTransaction,
+}
+
+// Only version 3 transactions are written to the blobs, so their version isn't read.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum RecordedTransaction {
+    #[serde(rename = "INVOKE_FUNCTION")]
+    Invoke(RecordedInvokeTransaction),
+    #[serde(rename = "DEPLOY_ACCOUNT")]
+    DeployAccount(RecordedDeployAccountTransaction),
+    #[serde(rename = "DECLARE")]
+    Declare(RecordedDeclareTransaction),
+    #[serde(rename = "L1_HANDLER")]
+    L1Handler(RecordedL1HandlerTransaction),
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedResourceBounds {
+    #[serde(rename = "L1_GAS")]
+    l1_gas: ResourceBounds,
+    #[serde(rename = "L2_GAS")]
+    l2_gas: ResourceBounds,
+    #[serde(rename = "L1_DATA_GAS")]
+    l1_data_gas: ResourceBounds,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedInvokeTransaction {
+    resource_bounds: RecordedResourceBounds,
+    tip: Tip,
+    signature: TransactionSignature,
+    nonce: Nonce,
+    sender_address: ContractAddress,
+    calldata: Calldata,
+    nonce_data_availability_mode: DataAvailabilityMode,
+    fee_data_availability_mode: DataAvailabilityMode,
+    paymaster_data: PaymasterData,
+    account_deployment_data: AccountDeploymentData,
+    hash_value: TransactionHash,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedDeployAccountTransaction {
+    resource_bounds: RecordedResourceBounds,
+    tip: Tip,
+    signature: TransactionSignature,
+    nonce: Nonce,
+    class_hash: ClassHash,
+    contract_address_salt: ContractAddressSalt,
+    sender_address: ContractAddress,
+    constructor_calldata: Calldata,
+    nonce_data_availability_mode: DataAvailabilityMode,
+    fee_data_availability_mode: DataAvailabilityMode,
+    paymaster_data: PaymasterData,
+    hash_value: TransactionHash,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedDeclareTransaction {
+    resource_bounds: RecordedResourceBounds,
+    tip: Tip,
+    signature: TransactionSignature,
+    nonce: Nonce,
+    class_hash: ClassHash,
+    compiled_class_hash: CompiledClassHash,
+    sender_address: ContractAddress,
+    nonce_data_availability_mode: DataAvailabilityMode,
+    fee_data_availability_mode: DataAvailabilityMode,
+    paymaster_data: PaymasterData,
+    account_deployment_data: AccountDeploymentData,
+    hash_value: TransactionHash,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedL1HandlerTransaction {
+    contract_address: ContractAddress,
+    entry_point_selector: EntryPointSelector,
+    calldata: Calldata,
+    nonce: Nonce,
+    hash_value: TransactionHash,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedExecutionInfo {
+    validate_call_info: Option<RecordedCallInfo>,
+    execute_call_info: Option<RecordedCallInfo>,
+    fee_transfer_call_info: Option<RecordedCallInfo>,
+    actual_fee: Fee,
+    da_gas: GasVector,
+    actual_resources: HashMap<String, u64>,
+    revert_error: Option<String>,
+    total_gas: GasVector,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedCallInfo {
+    call: RecordedCallEntryPoint,
+    execution: RecordedCallExecution,
+    inner_calls: Vec<RecordedCallInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedCallEntryPoint {
+    storage_address: ContractAddress,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedCallExecution {
+    events: Vec<RecordedOrderedEvent>,
+    l2_to_l1_messages: Vec<RecordedOrderedMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedOrderedEvent {
+    order: usize,
+    event: EventContent,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedOrderedMessage {
+    order: usize,
+    message: RecordedMessageToL1,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedMessageToL1 {
+    to_address: EthAddress,
+    payload: L2ToL1Payload,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedSierraContractClass {
+    contract_class: SierraContractClass,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedCasmContractClass {
+    compiled_class: CasmContractClass,
+}
+
+impl From<RecordedResourceBounds> for ValidResourceBounds {
+    fn from(resource_bounds: RecordedResourceBounds) -> ValidResourceBounds {
+        ValidResourceBounds::AllResources(AllResourceBounds {
+            l1_gas: resource_bounds.l1_gas,
+            l2_gas: resource_bounds.l2_gas,
+            l1_data_gas: resource_bounds.l1_data_gas,
+        })
+    }
+}
+
+impl RecordedTransaction {
+    fn into_transaction(self) -> (Transaction, TransactionHash) {
+        match self {
+            RecordedTransaction::Invoke(tx) => (
+                Transaction::Invoke(InvokeTransaction::V3(InvokeTransactionV3 {
+                    resource_bounds: tx.resource_bounds.into(),
+                    tip: tx.tip,
+                    signature: tx.signature,
+                    nonce: tx.nonce,
+                    sender_address: tx.sender_address,
+                    calldata: tx.calldata,
+                    nonce_data_availability_mode: tx.nonce_data_availability_mode,
+                    fee_data_availability_mode: tx.fee_data_availability_mode,
+                    paymaster_data: tx.paymaster_data,
+                    account_deployment_data: tx.account_deployment_data,
+                })),
+                tx.hash_value,
+            ),
+            RecordedTransaction::DeployAccount(tx) => (
+                Transaction::DeployAccount(DeployAccountTransaction::V3(
+                    DeployAccountTransactionV3 {
+                        resource_bounds: tx.resource_bounds.into(),
+                        tip: tx.tip,
+                        signature: tx.signature,
+                        nonce: tx.nonce,
+                        class_hash: tx.class_hash,
+                        contract_address_salt: tx.contract_address_salt,
+                        constructor_calldata: tx.constructor_calldata,
+                        nonce_data_availability_mode: tx.nonce_data_availability_mode,
+                        fee_data_availability_mode: tx.fee_data_availability_mode,
+                        paymaster_data: tx.paymaster_data,
+                    },
+                )),
+                tx.hash_value,
+            ),
+            RecordedTransaction::Declare(tx) => (
+                Transaction::Declare(DeclareTransaction::V3(DeclareTransactionV3 {
+                    resource_bounds: tx.resource_bounds.into(),
+                    tip: tx.tip,
+                    signature: tx.signature,
+                    nonce: tx.nonce,
+                    class_hash: tx.class_hash,
+                    compiled_class_hash: tx.compiled_class_hash,
+                    sender_address: tx.sender_address,
+                    nonce_data_availability_mode: tx.nonce_data_availability_mode,
+                    fee_data_availability_mode: tx.fee_data_availability_mode,
+                    paymaster_data: tx.paymaster_data,
+                    account_deployment_data: tx.account_deployment_data,
+                })),
+                tx.hash_value,
+            ),
+            RecordedTransaction::L1Handler(tx) => (
+                Transaction::L1Handler(L1HandlerTransaction {
+                    version: L1HandlerTransaction::VERSION,
+                    nonce: tx.nonce,
+                    contract_address: tx.contract_address,
+                    entry_point_selector: tx.entry_point_selector,
+                    calldata: tx.calldata,
+                }),
+                tx.hash_value,
+            ),
+        }
+    }
+
+    // The address of the contract a deploy account transaction deploys.
+    fn deployed_contract_address(&self) -> Option<ContractAddress> {
+        match self {
+            RecordedTransaction::DeployAccount(tx) => Some(tx.sender_address),
+            _ => None,
+        }
+    }
+}
+
+impl RecordedCallInfo {
+    // Collects the events and messages of the call and its inner calls.
+    fn collect_events_and_messages(
+        &self,
+        events: &mut Vec<(usize, Event)>,
+        messages: &mut Vec<(usize, MessageToL1)>,
+    ) {
+        let from_address = self.call.storage_address;
+        events.extend(self.execution.events.iter().map(|ordered_event| {
+            (ordered_event.order, Event { from_address, content: ordered_event.event.clone() })
+        }));
+        messages.extend(self.execution.l2_to_l1_messages.iter().map(|ordered_message| {
+            (
+                ordered_message.order,
+                MessageToL1 {
+                    from_address,
+                    to_address: ordered_message.message.to_address,
+                    payload: ordered_message.message.payload.clone(),
+                },
+            )
+        }));
+        for inner_call in &self.inner_calls {
+            inner_call.collect_events_and_messages(events, messages);
+        }
+    }
+}
+
+impl RecordedExecutionInfo {
+    // Returns the events and the messages of the transaction, each call info by its order in the
+    // execution.
+    fn events_and_messages(&self) -> (Vec<Event>, Vec<MessageToL1>) {
+        let mut all_events = Vec::new();
+        let mut all_messages = Vec::new();
+        for call_info in
+            [&self.validate_call_info, &self.execute_call_info, &self.fee_transfer_call_info]
+                .into_iter()
+                .flatten()
+        {
+            let mut events = Vec::new();
+            let mut messages = Vec::new();
+            call_info.collect_events_and_messages(&mut events, &mut messages);
+            events.sort_by_key(|(order, _)| *order);
+            messages.sort_by_key(|(order, _)| *order);
+            all_events.extend(events.into_iter().map(|(_, event)| event));
+            all_messages.extend(messages.into_iter().map(|(_, message)| message));
+        }
+        (all_events, all_messages)
+    }
+
+    fn execution_resources(&self) -> ExecutionResources {
+        // The resources are written as the number of steps and the usage of each builtin, by the
+        // name of the builtin with a "_builtin" suffix.
+        let builtin_instance_counter = Builtin::iter()
+            .filter_map(|builtin| {
+                let usage = self.actual_resources.get(&format!("{}_builtin", builtin.name()))?;
+                Some((builtin, *usage))
+            })
+            .collect();
+        ExecutionResources {
+            steps: self.actual_resources.get("n_steps").copied().unwrap_or_default(),
+            builtin_instance_counter,
+            memory_holes: 0,
+            da_gas_consumed: self.da_gas,
+            gas_consumed: self.total_gas,
+        }
+    }
+
+    fn into_transaction_output(
+        self,
+        transaction: &Transaction,
+        deployed_contract_address: Option<ContractAddress>,
+    ) -> TransactionOutput {
+        let (events, messages_sent) = self.events_and_messages();
+        let execution_resources = self.execution_resources();
+        let actual_fee = self.actual_fee;
+        let execution_status = match self.revert_error {
+            Some(revert_reason) => {
+                TransactionExecutionStatus::Reverted(RevertedTransactionExecutionStatus {
+                    revert_reason,
+                })
+            }
+            None => TransactionExecutionStatus::Succeeded,
+        };
+        match transaction {
+            Transaction::Declare(_) => TransactionOutput::Declare(DeclareTransactionOutput {
+                actual_fee,
+                messages_sent,
+                events,
+                execution_status,
+                execution_resources,
+            }),
+            Transaction::DeployAccount(_) => {
+                TransactionOutput::DeployAccount(DeployAccountTransactionOutput {
+                    actual_fee,
+                    messages_sent,
+                    events,
+                    contract_address: deployed_contract_address.unwrap_or_default(),
+                    execution_status,
+                    execution_resources,
+                })
+            }
+            Transaction::L1Handler(_) => TransactionOutput::L1Handler(L1HandlerTransactionOutput {
+                actual_fee,
+                messages_sent,
+                events,
+                execution_status,
+                execution_resources,
+            }),
+            // Deploy transactions aren't written to the blobs.
+            Transaction::Invoke(_) | Transaction::Deploy(_) => {
+                TransactionOutput::Invoke(InvokeTransactionOutput {
+                    actual_fee,
+                    messages_sent,
+                    events,
+                    execution_status,
+                    execution_resources,
+                })
+            }
+        }
+    }
+}
+
+impl From<RecordedResourcePrice> for GasPricePerToken {
+    fn from(price: RecordedResourcePrice) -> GasPricePerToken {
+        GasPricePerToken { price_in_fri: price.price_in_fri, price_in_wei: price.price_in_wei }
+    }
+}
+
+/// Replays the blobs in `directory` into the storage, starting at the first block missing from the
+/// storage's state and stopping at the first block without a blob. Returns the first block that
+/// wasn't replayed.
+pub fn replay_blobs(
+    directory: &Path,
+    storage_writer: &mut StorageWriter,
+) -> ReplayResult<BlockNumber> {
+    let mut block_number = storage_writer.begin_rw_txn()?.get_state_marker()?;
+    info!("Replaying blobs from {directory:?}, starting at block {block_number}.");
+    loop {
+        let path = blob_file_path(directory, block_number);
+        if !path.exists() {
+            info!("No blob for block {block_number}, done replaying.");
+            return Ok(block_number);
+        }
+        let blob = read_blob(&path)?;
+        if blob.block_number != block_number {
+            return Err(ReplayError::UnexpectedBlockNumber {
+                expected: block_number,
+                found: blob.block_number,
+            });
+        }
+        replay_blob(blob, storage_writer)?;
+        debug!("Replayed the blob of block {block_number}.");
+        block_number = block_number.unchecked_next();
+    }
+}
+
+fn read_blob(path: &Path) -> ReplayResult<RecordedBlob> {
+    let decoder = GzDecoder::new(BufReader::new(File::open(path)?));
+    Ok(serde_json::from_reader(decoder)?)
+}
+
+fn replay_blob(blob: RecordedBlob, storage_writer: &mut StorageWriter) -> ReplayResult<()> {
+    let block_number = blob.block_number;
+    if blob.transactions.len() != blob.execution_infos.len() {
+        return Err(ReplayError::TransactionsExecutionInfosMismatch {
+            block_number,
+            num_transactions: blob.transactions.len(),
+            num_execution_infos: blob.execution_infos.len(),
+        });
+    }
+    let block_info = blob.state_diff.block_info;
+    let fee_market_info = blob.fee_market_info;
+    let state_diff = ThinStateDiff {
+        deployed_contracts: blob.state_diff.address_to_class_hash,
+        storage_diffs: blob.state_diff.storage_updates.into_values().flatten().collect(),
+        declared_classes: blob.state_diff.declared_classes,
+        deprecated_declared_classes: Vec::new(),
+        nonces: blob.state_diff.nonces.into_values().flatten().collect(),
+    };
+
+    let mut body = BlockBody::default();
+    for (written_tx, execution_info) in blob.transactions.into_iter().zip(blob.execution_infos) {
+        let deployed_contract_address = written_tx.tx.deployed_contract_address();
+        let (transaction, transaction_hash) = written_tx.tx.into_transaction();
+        body.transaction_outputs
+            .push(execution_info.into_transaction_output(&transaction, deployed_contract_address));
+        body.transactions.push(transaction);
+        body.transaction_hashes.push(transaction_hash);
+    }
+
+    let contract_classes: IndexMap<_, _> = blob
+        .contract_classes
+        .into_iter()
+        .map(|(class_hash, class)| (class_hash, class.contract_class))
+        .collect();
+    let compiled_classes: IndexMap<_, _> = blob
+        .compiled_classes
+        .into_iter()
+        .map(|(compiled_class_hash, casm)| (compiled_class_hash, casm.compiled_class))
+        .collect();
+
+    // The classes are stored in the order they are declared in the state diff.
+    let mut classes = Vec::with_capacity(state_diff.declared_classes.len());
+    let mut casms = Vec::with_capacity(state_diff.declared_classes.len());
+    for (class_hash, compiled_class_hash) in &state_diff.declared_classes {
+        let class = contract_classes
+            .get(class_hash)
+            .ok_or(ReplayError::MissingContractClass { block_number, class_hash: *class_hash })?;
+        let casm = compiled_classes
+            .get(compiled_class_hash)
+            .ok_or(ReplayError::MissingCompiledClass { block_number, class_hash: *class_hash })?;
+        classes.push((*class_hash, class));
+        casms.push((*class_hash, casm));
+    }
+
+    let txn = storage_writer.begin_rw_txn()?;
+    let parent_hash = match block_number.prev() {
+        Some(parent_number) => txn
+            .get_block_header(parent_number)?
+            .map(|parent_header| parent_header.block_hash)
+            .unwrap_or_default(),
+        None => BlockHash::default(),
+    };
+    let header = BlockHeader {
+        block_hash: BlockHash(Felt::from(block_number.0)),
+        block_header_without_hash: BlockHeaderWithoutHash {
+            parent_hash,
+            block_number,
+            l1_gas_price: block_info.l1_gas_price.into(),
+            l1_data_gas_price: block_info.l1_data_gas_price.into(),
+            l2_gas_price: block_info.l2_gas_price.into(),
+            state_root: Default::default(),
+            sequencer: SequencerContractAddress(block_info.sequencer_address),
+            timestamp: block_info.block_timestamp,
+            l1_da_mode: if block_info.use_kzg_da {
+                L1DataAvailabilityMode::Blob
+            } else {
+                L1DataAvailabilityMode::Calldata
+            },
+            starknet_version: block_info.starknet_version.unwrap_or_default(),
+            l2_gas_consumed: GasAmount(fee_market_info.l2_gas_consumed),
+            next_l2_gas_price: GasPrice(fee_market_info.next_l2_gas_price.into()),
+        },
+        state_diff_length: Some(state_diff.len()),
+        n_transactions: body.transactions.len(),
+        n_events: body.transaction_outputs.iter().map(|output| output.events().len()).sum(),
+        ..Default::default()
+    };
+
+    let mut txn = txn
+        .append_header(block_number, &header)?
+        .append_body(block_number, body)?
+        .append_state_diff(block_number, state_diff)?
+        .append_classes(block_number, &classes, &[])?;
+    for (class_hash, casm) in casms {
+        txn = txn.append_casm(&class_hash, casm)?;
+    }
+    txn.commit()?;
+    Ok(())
+}
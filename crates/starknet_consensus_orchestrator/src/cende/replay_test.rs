@@ -0,0 +1,278 @@
+use std::sync::Arc;
+
+use blockifier::execution::call_info::{CallExecution, CallInfo, OrderedEvent};
+use blockifier::execution::entry_point::CallEntryPoint;
+use blockifier::transaction::objects::TransactionExecutionInfo;
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use indexmap::indexmap;
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::class::ClassStorageReader;
+use papyrus_storage::compiled_class::CasmStorageReader;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::integrity::check_storage;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_test_utils::{get_rng, GetTestInstance};
+use starknet_api::block::{BlockHash, BlockInfo, BlockNumber, BlockTimestamp};
+use starknet_api::consensus_transaction::InternalConsensusTransaction;
+use starknet_api::core::{ClassHash, CompiledClassHash, EntryPointSelector, Nonce};
+use starknet_api::data_availability::DataAvailabilityMode;
+use starknet_api::executable_transaction::L1HandlerTransaction;
+use starknet_api::rpc_transaction::{
+    InternalRpcTransaction,
+    InternalRpcTransactionWithoutTxHash,
+    RpcInvokeTransaction,
+    RpcInvokeTransactionV3,
+};
+use starknet_api::state::{SierraContractClass, StateNumber, ThinStateDiff};
+use starknet_api::test_utils::read_json_file;
+use starknet_api::transaction::fields::{
+    AccountDeploymentData,
+    AllResourceBounds,
+    Calldata,
+    Fee,
+    PaymasterData,
+    Tip,
+    TransactionSignature,
+};
+use starknet_api::transaction::{EventContent, EventData, EventKey, Transaction, TransactionHash};
+use starknet_api::{contract_address, felt, storage_key};
+use starknet_class_manager_types::MockClassManagerClient;
+use starknet_types_core::felt::Felt;
+use tempfile::tempdir;
+
+use super::{replay_blobs, RecordedBlob, ReplayError};
+use crate::cende::{AerospikeBlob, BlobParameters, BlobSink};
+
+const CLASS_HASH: ClassHash = ClassHash(Felt::ONE);
+const COMPILED_CLASS_HASH: CompiledClassHash = CompiledClassHash(Felt::TWO);
+
+async fn blob(block_number: BlockNumber, state_diff: ThinStateDiff) -> AerospikeBlob {
+    AerospikeBlob::from_blob_parameters_and_class_manager(
+        BlobParameters {
+            block_info: BlockInfo { block_number, ..Default::default() },
+            state_diff,
+            ..Default::default()
+        },
+        Arc::new(MockClassManagerClient::new()),
+    )
+    .await
+    .unwrap()
+}
+
+fn casm() -> CasmContractClass {
+    let casm = CasmContractClass::get_test_instance(&mut get_rng());
+    // The blob always holds the pythonic hints.
+    CasmContractClass { pythonic_hints: Some(casm.pythonic_hints.unwrap_or_default()), ..casm }
+}
+
+#[tokio::test]
+async fn replay_written_blobs() {
+    let blobs_directory = tempdir().unwrap();
+    let sink = BlobSink::LocalDirectory(blobs_directory.path().to_path_buf());
+    let address = contract_address!("0x100");
+    let key = storage_key!("0x10");
+    let casm = casm();
+
+    let mut declaring_blob = blob(
+        BlockNumber(0),
+        ThinStateDiff {
+            deployed_contracts: indexmap! { address => CLASS_HASH },
+            declared_classes: indexmap! { CLASS_HASH => COMPILED_CLASS_HASH },
+            ..Default::default()
+        },
+    )
+    .await;
+    declaring_blob.contract_classes = vec![(CLASS_HASH, SierraContractClass::default().into())];
+    declaring_blob.compiled_classes = vec![(COMPILED_CLASS_HASH, casm.clone().into())];
+    assert!(sink.write(&declaring_blob).await);
+
+    let updating_blob = blob(
+        BlockNumber(1),
+        ThinStateDiff {
+            storage_diffs: indexmap! { address => indexmap! { key => felt!(7_u8) } },
+            nonces: indexmap! { address => Nonce(felt!(1_u8)) },
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(sink.write(&updating_blob).await);
+
+    let ((reader, mut writer), _storage_directory) = get_test_storage();
+    assert_eq!(replay_blobs(blobs_directory.path(), &mut writer).unwrap(), BlockNumber(2));
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(2));
+    let state_reader = txn.get_state_reader().unwrap();
+    let state_number = StateNumber::unchecked_right_after_block(BlockNumber(1));
+    assert_eq!(state_reader.get_class_hash_at(state_number, &address).unwrap(), Some(CLASS_HASH));
+    assert_eq!(state_reader.get_storage_at(state_number, &address, &key).unwrap(), felt!(7_u8));
+    assert_eq!(
+        state_reader.get_nonce_at(state_number, &address).unwrap(),
+        Some(Nonce(felt!(1_u8)))
+    );
+    assert_eq!(txn.get_class(&CLASS_HASH).unwrap(), Some(SierraContractClass::default()));
+    assert_eq!(txn.get_casm(&CLASS_HASH).unwrap(), Some(casm));
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(2));
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(2));
+    let report = check_storage(&reader).unwrap();
+    assert!(report.is_consistent(), "{:?}", report.issues);
+
+    // Replaying again continues from the storage's state marker.
+    assert_eq!(replay_blobs(blobs_directory.path(), &mut writer).unwrap(), BlockNumber(2));
+}
+
+#[tokio::test]
+async fn replay_fails_on_unexpected_block_number() {
+    let blobs_directory = tempdir().unwrap();
+    let sink = BlobSink::LocalDirectory(blobs_directory.path().to_path_buf());
+    assert!(sink.write(&blob(BlockNumber(0), ThinStateDiff::default()).await).await);
+    // Place the blob of block 2 where the blob of block 1 is expected.
+    let misplaced_blob = blob(BlockNumber(2), ThinStateDiff::default()).await;
+    assert!(sink.write(&misplaced_blob).await);
+    std::fs::rename(
+        super::blob_file_path(blobs_directory.path(), BlockNumber(2)),
+        super::blob_file_path(blobs_directory.path(), BlockNumber(1)),
+    )
+    .unwrap();
+
+    let ((_reader, mut writer), _storage_directory) = get_test_storage();
+    assert!(matches!(
+        replay_blobs(blobs_directory.path(), &mut writer),
+        Err(ReplayError::UnexpectedBlockNumber { expected: BlockNumber(1), found: BlockNumber(2) })
+    ));
+}
+
+#[tokio::test]
+async fn replay_headers_and_bodies() {
+    let blobs_directory = tempdir().unwrap();
+    let sink = BlobSink::LocalDirectory(blobs_directory.path().to_path_buf());
+    let account = contract_address!("0x100");
+    let emitter = contract_address!("0x200");
+    let invoke_hash = TransactionHash(felt!("0x1234"));
+    let l1_handler_hash = TransactionHash(felt!("0x5678"));
+
+    let invoke = InternalConsensusTransaction::RpcTransaction(InternalRpcTransaction {
+        tx: InternalRpcTransactionWithoutTxHash::Invoke(RpcInvokeTransaction::V3(
+            RpcInvokeTransactionV3 {
+                sender_address: account,
+                calldata: Calldata::default(),
+                signature: TransactionSignature::default(),
+                nonce: Nonce::default(),
+                resource_bounds: AllResourceBounds::default(),
+                tip: Tip::default(),
+                paymaster_data: PaymasterData::default(),
+                account_deployment_data: AccountDeploymentData::default(),
+                nonce_data_availability_mode: DataAvailabilityMode::L1,
+                fee_data_availability_mode: DataAvailabilityMode::L1,
+            },
+        )),
+        tx_hash: invoke_hash,
+    });
+    let l1_handler = InternalConsensusTransaction::L1Handler(L1HandlerTransaction {
+        tx: starknet_api::transaction::L1HandlerTransaction {
+            contract_address: emitter,
+            entry_point_selector: EntryPointSelector(felt!("0x2a")),
+            calldata: Calldata(Arc::new(vec![felt!(1_u8)])),
+            ..Default::default()
+        },
+        tx_hash: l1_handler_hash,
+        paid_fee_on_l1: Fee(1),
+    });
+    let event = |key: u8| EventContent {
+        keys: vec![EventKey(felt!(key))],
+        data: EventData(vec![felt!(key)]),
+    };
+    // The events are emitted by an inner call, out of the order of the call tree.
+    let emitting_execution_info = TransactionExecutionInfo {
+        execute_call_info: Some(CallInfo {
+            call: CallEntryPoint { storage_address: account, ..Default::default() },
+            execution: CallExecution {
+                events: vec![OrderedEvent { order: 1, event: event(2) }],
+                ..Default::default()
+            },
+            inner_calls: vec![CallInfo {
+                call: CallEntryPoint { storage_address: emitter, ..Default::default() },
+                execution: CallExecution {
+                    events: vec![OrderedEvent { order: 0, event: event(1) }],
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let blob_with_transactions = AerospikeBlob::from_blob_parameters_and_class_manager(
+        BlobParameters {
+            block_info: BlockInfo {
+                block_number: BlockNumber(0),
+                block_timestamp: BlockTimestamp(6),
+                ..Default::default()
+            },
+            transactions: vec![invoke, l1_handler],
+            execution_infos: vec![emitting_execution_info, TransactionExecutionInfo::default()],
+            ..Default::default()
+        },
+        Arc::new(MockClassManagerClient::new()),
+    )
+    .await
+    .unwrap();
+    assert!(sink.write(&blob_with_transactions).await);
+    assert!(sink.write(&blob(BlockNumber(1), ThinStateDiff::default()).await).await);
+
+    let ((reader, mut writer), _storage_directory) = get_test_storage();
+    assert_eq!(replay_blobs(blobs_directory.path(), &mut writer).unwrap(), BlockNumber(2));
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(2));
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(2));
+    let header = txn.get_block_header(BlockNumber(0)).unwrap().unwrap();
+    assert_eq!(header.block_header_without_hash.timestamp, BlockTimestamp(6));
+    assert_eq!(header.n_transactions, 2);
+    assert_eq!(header.n_events, 2);
+    let child_header = txn.get_block_header(BlockNumber(1)).unwrap().unwrap();
+    assert_eq!(child_header.block_header_without_hash.parent_hash, header.block_hash);
+    assert_ne!(child_header.block_hash, BlockHash::default());
+
+    assert_eq!(
+        txn.get_block_transaction_hashes(BlockNumber(0)).unwrap(),
+        Some(vec![invoke_hash, l1_handler_hash])
+    );
+    let transactions = txn.get_block_transactions(BlockNumber(0)).unwrap().unwrap();
+    assert!(matches!(transactions[0], Transaction::Invoke(_)));
+    assert!(matches!(transactions[1], Transaction::L1Handler(_)));
+    let outputs = txn.get_block_transaction_outputs(BlockNumber(0)).unwrap().unwrap();
+    let events: Vec<_> = outputs[0]
+        .events()
+        .iter()
+        .map(|event| (event.from_address, event.content.clone()))
+        .collect();
+    assert_eq!(events, vec![(emitter, event(1)), (account, event(2))]);
+    assert!(outputs[1].events().is_empty());
+
+    let report = check_storage(&reader).unwrap();
+    assert!(report.is_consistent(), "{:?}", report.issues);
+}
+
+#[test]
+fn read_all_transaction_types_of_written_blob() {
+    let blob: RecordedBlob = serde_json::from_value(read_json_file("central_blob.json")).unwrap();
+    let transactions: Vec<_> = blob
+        .transactions
+        .into_iter()
+        .map(|written_tx| written_tx.tx.into_transaction().0)
+        .collect();
+    assert!(matches!(
+        transactions.as_slice(),
+        [
+            Transaction::Declare(_),
+            Transaction::Invoke(_),
+            Transaction::DeployAccount(_),
+            Transaction::L1Handler(_),
+            Transaction::Declare(_),
+        ]
+    ));
+    assert_eq!(blob.execution_infos.len(), 1);
+}
@@ -2,7 +2,7 @@ use std::cmp::max;
 
 use serde::Serialize;
 
-use crate::orchestrator_versioned_constants;
+use crate::orchestrator_versioned_constants::VersionedConstants;
 
 #[cfg(test)]
 mod test;
@@ -21,17 +21,17 @@ pub struct FeeMarketInfo {
 /// # Parameters
 /// - `price`: The base gas price per unit (in fri) of the current block.
 /// - `gas_used`: The total gas used in the current block.
-/// - `gas_target`: The target gas usage per block (usually half of a block's gas limit).
-pub fn calculate_next_base_gas_price(price: u64, gas_used: u64, gas_target: u64) -> u64 {
-    let versioned_constants =
-        orchestrator_versioned_constants::VersionedConstants::latest_constants();
-    // Setting the target at 50% of the max block size balances the rate of gas price changes,
-    // helping to prevent sudden spikes, particularly during increases, for a better user
-    // experience.
-    assert_eq!(
-        gas_target,
-        versioned_constants.max_block_size / 2,
-        "Gas target must be 50% of max block size to balance price changes."
+/// - `versioned_constants`: The fee market constants of the current block's Starknet version, which
+///   set the gas target and the maximal rate of change of the price.
+pub fn calculate_next_base_gas_price(
+    price: u64,
+    gas_used: u64,
+    versioned_constants: &VersionedConstants,
+) -> u64 {
+    let gas_target = versioned_constants.gas_target;
+    assert!(
+        0 < gas_target && gas_target <= versioned_constants.max_block_size,
+        "The gas target must be positive and at most the max block size."
     );
     // To prevent precision loss during multiplication and division, we set a minimum gas price.
     // Additionally, a minimum gas price is established to prevent prolonged periods before the
@@ -62,15 +62,16 @@ pub fn calculate_next_base_gas_price(price: u64, gas_used: u64, gas_target: u64)
     let price_change_u128 =
         gas_delta_cost / (gas_target_u128 * versioned_constants.gas_price_max_change_denominator);
 
-    // Convert back to u64, as the price change should fit within the u64 range.
-    // Since the target is half the maximum block size (which fits within a u64), the gas delta
-    // is bounded by half the maximum block size. Therefore, after dividing by the gas target
-    // (which is half the maximum block size), the result is guaranteed to fit within a u64.
-    let price_change = u64::try_from(price_change_u128)
-        .expect("Result fits u64 after division of a bounded gas delta");
+    // When the block is below the target, the gas delta is bounded by the target, so the price
+    // change is at most the price itself. Above the target, a target lower than half the max block
+    // size allows changes larger than the price, so the result saturates at u64::MAX.
+    let price_change = u64::try_from(price_change_u128).unwrap_or(u64::MAX);
 
-    let adjusted_price =
-        if gas_used > gas_target { price + price_change } else { price - price_change };
+    let adjusted_price = if gas_used > gas_target {
+        price.saturating_add(price_change)
+    } else {
+        price - price_change
+    };
 
     assert!(
         gas_used > gas_target && adjusted_price >= price
@@ -1,7 +1,9 @@
 use std::sync::LazyLock;
 
+use starknet_api::block::StarknetVersion;
+
 use crate::fee_market::calculate_next_base_gas_price;
-use crate::orchestrator_versioned_constants::VersionedConstants;
+use crate::orchestrator_versioned_constants::{VersionedConstants, FIRST_FEE_MARKET_VERSION};
 
 static VERSIONED_CONSTANTS: LazyLock<&VersionedConstants> =
     LazyLock::new(VersionedConstants::latest_constants);
@@ -11,7 +13,7 @@ fn test_price_calculation_snapshot() {
     // Setup: using realistic arbitrary values.
     let init_price: u64 = 1_000_000;
     let max_block_size = VERSIONED_CONSTANTS.max_block_size;
-    let gas_target: u64 = max_block_size / 2;
+    let gas_target: u64 = VERSIONED_CONSTANTS.gas_target;
     let high_congestion_gas_used: u64 = max_block_size * 3 / 4;
     let low_congestion_gas_used: u64 = max_block_size / 4;
     let stable_congestion_gas_used: u64 = gas_target;
@@ -22,15 +24,15 @@ fn test_price_calculation_snapshot() {
 
     // Assert.
     assert_eq!(
-        calculate_next_base_gas_price(init_price, high_congestion_gas_used, gas_target),
+        calculate_next_base_gas_price(init_price, high_congestion_gas_used, &VERSIONED_CONSTANTS),
         increased_price
     );
     assert_eq!(
-        calculate_next_base_gas_price(init_price, low_congestion_gas_used, gas_target),
+        calculate_next_base_gas_price(init_price, low_congestion_gas_used, &VERSIONED_CONSTANTS),
         decreased_price
     );
     assert_eq!(
-        calculate_next_base_gas_price(init_price, stable_congestion_gas_used, gas_target),
+        calculate_next_base_gas_price(init_price, stable_congestion_gas_used, &VERSIONED_CONSTANTS),
         init_price
     );
 }
@@ -43,26 +45,74 @@ fn test_gas_price_with_extreme_values() {
     let gas_price_max_change_denominator = VERSIONED_CONSTANTS.gas_price_max_change_denominator;
 
     let price = min_gas_price;
-    let gas_target = max_block_size / 2;
     let gas_used = 0;
-    assert_eq!(calculate_next_base_gas_price(price, gas_used, gas_target), min_gas_price);
+    assert_eq!(calculate_next_base_gas_price(price, gas_used, &VERSIONED_CONSTANTS), min_gas_price);
 
     let price = min_gas_price;
-    let gas_target = max_block_size / 2;
     let gas_used = max_block_size;
-    assert!(calculate_next_base_gas_price(price, gas_used, gas_target) > min_gas_price);
+    assert!(calculate_next_base_gas_price(price, gas_used, &VERSIONED_CONSTANTS) > min_gas_price);
 
     let price = u64::MAX;
-    let gas_target = max_block_size / 2;
     let gas_used = 0;
-    calculate_next_base_gas_price(price, gas_used, gas_target); // Should not panic.
+    calculate_next_base_gas_price(price, gas_used, &VERSIONED_CONSTANTS); // Should not panic.
 
     // To avoid overflow when updating the price, the value is set below a certain threshold so that
     // the new price does not exceed u64::MAX.
     let max_u128 = u128::from(u64::MAX);
     let price_u128 =
         max_u128 * gas_price_max_change_denominator / (gas_price_max_change_denominator + 1);
-    let gas_target = max_block_size / 2;
     let gas_used = max_block_size;
-    calculate_next_base_gas_price(u64::try_from(price_u128).unwrap(), gas_used, gas_target); // Should not panic.
+    calculate_next_base_gas_price(
+        u64::try_from(price_u128).unwrap(),
+        gas_used,
+        &VERSIONED_CONSTANTS,
+    ); // Should not panic.
+}
+
+#[test]
+fn test_price_calculation_with_custom_constants() {
+    // A target of 25% utilization, with faster price changes.
+    let versioned_constants = VersionedConstants {
+        gas_price_max_change_denominator: 8,
+        gas_target: VERSIONED_CONSTANTS.max_block_size / 4,
+        ..VERSIONED_CONSTANTS.clone()
+    };
+    let init_price: u64 = 1_000_000;
+    let gas_target = versioned_constants.gas_target;
+
+    // 1000000 + (1000000 * 1 * gas_target) / (gas_target * 8);
+    assert_eq!(
+        calculate_next_base_gas_price(init_price, 2 * gas_target, &versioned_constants),
+        init_price + 125000
+    );
+    // 1000000 - (1000000 * 1 / 2 * gas_target) / (gas_target * 8);
+    assert_eq!(
+        calculate_next_base_gas_price(init_price, gas_target / 2, &versioned_constants),
+        init_price - 62500
+    );
+    assert_eq!(
+        calculate_next_base_gas_price(init_price, gas_target, &versioned_constants),
+        init_price
+    );
+
+    // A full block may change the price by more than the price itself, saturating at u64::MAX.
+    let versioned_constants = VersionedConstants {
+        gas_price_max_change_denominator: 1,
+        gas_target: 1,
+        ..VERSIONED_CONSTANTS.clone()
+    };
+    let max_block_size = versioned_constants.max_block_size;
+    assert_eq!(
+        calculate_next_base_gas_price(u64::MAX / 2, max_block_size, &versioned_constants),
+        u64::MAX
+    );
+}
+
+#[test]
+fn versioned_constants_of_pre_fee_market_blocks() {
+    let first_constants = VersionedConstants::get(&FIRST_FEE_MARKET_VERSION).unwrap();
+    for starknet_version in [StarknetVersion::V0_13_4, FIRST_FEE_MARKET_VERSION] {
+        let versioned_constants = VersionedConstants::get_for_block(&starknet_version).unwrap();
+        assert!(std::ptr::eq(versioned_constants, first_constants));
+    }
 }
@@ -14,7 +14,8 @@ pub struct VersionedConstants {
     pub min_gas_price: u64,
     /// The maximum block size in gas units.
     pub max_block_size: u64,
-    /// The target gas usage per block (usually half of a block's gas limit).
+    /// The target gas usage per block, at most `max_block_size`. Blocks using more gas than the
+    /// target raise the gas price of the next block, and blocks using less lower it.
     pub gas_target: u64,
 }
 
@@ -24,6 +25,19 @@ define_versioned_constants!(
     (V0_14_0, "../resources/orchestrator_versioned_constants_0_14_0.json"),
 );
 
+/// The first Starknet version with an EIP-1559 fee market for the L2 gas price.
+pub const FIRST_FEE_MARKET_VERSION: StarknetVersion = StarknetVersion::V0_14_0;
+
+impl VersionedConstants {
+    /// Gets the constants that apply to a block of the given Starknet version. Blocks preceding the
+    /// fee market use the constants of its first version.
+    pub fn get_for_block(
+        starknet_version: &StarknetVersion,
+    ) -> Result<&'static Self, VersionedConstantsError> {
+        Self::get(starknet_version.max(&FIRST_FEE_MARKET_VERSION))
+    }
+}
+
 /// Error type for the Consensus' versioned constants.
 #[derive(Debug, Error)]
 pub enum VersionedConstantsError {
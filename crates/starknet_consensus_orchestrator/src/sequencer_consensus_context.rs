@@ -34,6 +34,7 @@ use starknet_api::block::{
     GasPriceVector,
     GasPrices,
     NonzeroGasPrice,
+    StarknetVersion,
 };
 use starknet_api::consensus_transaction::InternalConsensusTransaction;
 use starknet_api::core::{ContractAddress, SequencerContractAddress};
@@ -68,6 +69,7 @@ use starknet_consensus::types::{
 use starknet_state_sync_types::communication::SharedStateSyncClient;
 use starknet_state_sync_types::state_sync_types::SyncBlock;
 use starknet_types_core::felt::Felt;
+use tokio::sync::watch;
 use tokio::task::JoinHandle;
 use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
@@ -86,9 +88,12 @@ struct BlockInfoValidation {
     block_timestamp_window: u64,
     last_block_timestamp: Option<u64>,
     l1_da_mode: L1DataAvailabilityMode,
+    l2_gas_price_fri: u128,
 }
 
 const EMPTY_BLOCK_COMMITMENT: BlockHash = BlockHash(Felt::ONE);
+// The Starknet version of the blocks built by this node.
+const BLOCK_STARKNET_VERSION: StarknetVersion = StarknetVersion::LATEST;
 
 // TODO(Dan, Matan): Remove this once and replace with real gas prices.
 const TEMPORARY_GAS_PRICES: GasPrices = GasPrices {
@@ -139,6 +144,8 @@ pub struct SequencerConsensusContext {
     // restarting.
     proposal_id: u64,
     current_height: Option<BlockNumber>,
+    // Publishes the current height to the validator of the votes topic.
+    current_height_sender: watch::Sender<BlockNumber>,
     current_round: Round,
     // The active proposal refers to the proposal being validated at the current height/round.
     // Building proposals are not tracked as active, as consensus can't move on to the next
@@ -160,6 +167,7 @@ pub struct SequencerConsensusContext {
 }
 
 impl SequencerConsensusContext {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         config: ContextConfig,
         class_manager_client: SharedClassManagerClient,
@@ -168,6 +176,7 @@ impl SequencerConsensusContext {
         outbound_proposal_sender: mpsc::Sender<(HeightAndRound, mpsc::Receiver<ProposalPart>)>,
         vote_broadcast_client: BroadcastTopicClient<Vote>,
         cende_ambassador: Arc<dyn CendeContext>,
+        current_height_sender: watch::Sender<BlockNumber>,
     ) -> Self {
         let chain_id = config.chain_id.clone();
         let num_validators = config.num_validators;
@@ -183,18 +192,18 @@ impl SequencerConsensusContext {
             batcher,
             outbound_proposal_sender,
             vote_broadcast_client,
-            // TODO(Matan): Set the actual validator IDs (contract addresses).
-            validators: (0..num_validators)
-                .map(|i| ValidatorId::from(DEFAULT_VALIDATOR_ID + i))
-                .collect(),
+            validators: validator_ids(num_validators),
             valid_proposals: Arc::new(Mutex::new(HeightToIdToContent::new())),
             proposal_id: 0,
             current_height: None,
+            current_height_sender,
             current_round: 0,
             active_proposal: None,
             queued_proposals: BTreeMap::new(),
             cende_ambassador,
-            l2_gas_price: VersionedConstants::latest_constants().min_gas_price,
+            l2_gas_price: VersionedConstants::get_for_block(&BLOCK_STARKNET_VERSION)
+                .expect("The blocks built by this node should have versioned constants.")
+                .min_gas_price,
             l1_da_mode,
             last_block_timestamp: None,
         }
@@ -210,6 +219,45 @@ impl SequencerConsensusContext {
             ..TEMPORARY_GAS_PRICES
         }
     }
+
+    // Restores the l2 gas price from the header of the block preceding `height`, so that the price
+    // series continues where it stopped before a restart.
+    async fn restore_l2_gas_price(&mut self, height: BlockNumber) {
+        let Some(parent_height) = height.prev() else {
+            return;
+        };
+        match self.state_sync_client.get_block(parent_height).await {
+            Ok(Some(sync_block)) => {
+                self.l2_gas_price = l2_gas_price_after(&sync_block.block_header_without_hash);
+            }
+            Ok(None) => {
+                warn!("Block {parent_height} is missing from sync, using the minimal l2 gas price.")
+            }
+            Err(e) => error!(
+                "Failed to get block {parent_height} from sync, using the minimal l2 gas price: \
+                 {e:?}"
+            ),
+        }
+    }
+}
+
+/// The ids of the validators in a network with the given number of validators.
+// TODO(Matan): Set the actual validator IDs (contract addresses).
+pub fn validator_ids(num_validators: u64) -> Vec<ValidatorId> {
+    (0..num_validators).map(|i| ValidatorId::from(DEFAULT_VALIDATOR_ID + i)).collect()
+}
+
+// The l2 gas price of the block following the given one. Blocks prior to the EIP-1559 fee market
+// don't record the next price, in which case the minimal price of the block's version is used.
+fn l2_gas_price_after(header: &BlockHeaderWithoutHash) -> u64 {
+    let next_l2_gas_price = u64::try_from(header.next_l2_gas_price.0).unwrap_or(u64::MAX);
+    match VersionedConstants::get_for_block(&header.starknet_version) {
+        Ok(versioned_constants) => next_l2_gas_price.max(versioned_constants.min_gas_price),
+        Err(e) => {
+            warn!("Block {} has no fee market constants: {e}", header.block_number);
+            next_l2_gas_price.max(1)
+        }
+    }
 }
 
 struct ProposalBuildArguments {
@@ -340,6 +388,7 @@ impl ConsensusContext for SequencerConsensusContext {
                     block_timestamp_window: self.config.block_timestamp_window,
                     last_block_timestamp: self.last_block_timestamp,
                     l1_da_mode: self.l1_da_mode,
+                    l2_gas_price_fri: self.l2_gas_price.into(),
                 };
                 self.validate_current_round_proposal(
                     block_info_validation,
@@ -468,15 +517,26 @@ impl ConsensusContext for SequencerConsensusContext {
             GasPricePerToken { price_in_fri: GasPrice(1), price_in_wei: GasPrice(1) };
         let l1_data_gas_price =
             GasPricePerToken { price_in_fri: GasPrice(1), price_in_wei: GasPrice(1) };
-        let l2_gas_price =
-            GasPricePerToken { price_in_fri: GasPrice(1), price_in_wei: GasPrice(1) };
+        let l2_gas_price = GasPricePerToken {
+            price_in_fri: GasPrice(block_info.l2_gas_price_fri),
+            price_in_wei: GasPrice(1),
+        };
         let sequencer = SequencerContractAddress(ContractAddress::from(123_u128));
+        // The price of a decided block was validated to be the price we hold, so it fits in u64.
+        let l2_gas_price_fri = u64::try_from(block_info.l2_gas_price_fri).unwrap_or(u64::MAX);
+        let versioned_constants = VersionedConstants::get_for_block(&BLOCK_STARKNET_VERSION)
+            .expect("The blocks built by this node should have versioned constants.");
+        let next_l2_gas_price =
+            calculate_next_base_gas_price(l2_gas_price_fri, l2_gas_used.0, versioned_constants);
         let block_header_without_hash = BlockHeaderWithoutHash {
             block_number: BlockNumber(height),
             l1_gas_price,
             l1_data_gas_price,
             l2_gas_price,
             sequencer,
+            starknet_version: BLOCK_STARKNET_VERSION,
+            l2_gas_consumed: l2_gas_used,
+            next_l2_gas_price: GasPrice(next_l2_gas_price.into()),
             ..Default::default()
         };
         let sync_block = SyncBlock {
@@ -488,11 +548,7 @@ impl ConsensusContext for SequencerConsensusContext {
         // `add_new_block` returns immediately, it doesn't wait for sync to fully process the block.
         state_sync_client.add_new_block(sync_block).await.expect("Failed to add new block.");
 
-        self.l2_gas_price = calculate_next_base_gas_price(
-            self.l2_gas_price,
-            l2_gas_used.0,
-            VersionedConstants::latest_constants().max_block_size / 2,
-        );
+        self.l2_gas_price = next_l2_gas_price;
 
         // TODO(dvir): pass here real `BlobParameters` info.
         // TODO(dvir): when passing here the correct `BlobParameters`, also test that
@@ -549,14 +605,19 @@ impl ConsensusContext for SequencerConsensusContext {
             );
             return false;
         }
+        self.l2_gas_price = l2_gas_price_after(&sync_block.block_header_without_hash);
         self.interrupt_active_proposal().await;
         self.batcher.add_sync_block(sync_block).await.unwrap();
         true
     }
 
     async fn set_height_and_round(&mut self, height: BlockNumber, round: Round) {
+        if self.current_height.is_none() {
+            self.restore_l2_gas_price(height).await;
+        }
         if self.current_height.map(|h| height > h).unwrap_or(true) {
             self.current_height = Some(height);
+            self.current_height_sender.send_replace(height);
             assert_eq!(round, 0);
             self.current_round = round;
             self.queued_proposals.clear();
@@ -599,6 +660,7 @@ impl ConsensusContext for SequencerConsensusContext {
             block_timestamp_window: self.config.block_timestamp_window,
             last_block_timestamp: self.last_block_timestamp,
             l1_da_mode: self.l1_da_mode,
+            l2_gas_price_fri: self.l2_gas_price.into(),
         };
         self.validate_current_round_proposal(
             block_info_validation,
@@ -942,6 +1004,7 @@ async fn is_block_info_valid(
         && block_info.timestamp >= block_info_validation.last_block_timestamp.unwrap_or(0)
         && block_info.timestamp <= now + block_info_validation.block_timestamp_window
         && block_info.l1_da_mode == block_info_validation.l1_da_mode
+        && block_info.l2_gas_price_fri == block_info_validation.l2_gas_price_fri
 }
 
 // The second proposal part when validating a proposal must be:
@@ -25,7 +25,13 @@ use papyrus_protobuf::consensus::{
     Vote,
 };
 use rstest::rstest;
-use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::block::{
+    BlockHash,
+    BlockHeaderWithoutHash,
+    BlockNumber,
+    GasPrice,
+    StarknetVersion,
+};
 use starknet_api::consensus_transaction::{ConsensusTransaction, InternalConsensusTransaction};
 use starknet_api::core::{ChainId, Nonce, StateDiffCommitment};
 use starknet_api::data_availability::L1DataAvailabilityMode;
@@ -53,10 +59,13 @@ use starknet_class_manager_types::transaction_converter::{
 use starknet_class_manager_types::EmptyClassManagerClient;
 use starknet_consensus::types::{ConsensusContext, Round};
 use starknet_state_sync_types::communication::MockStateSyncClient;
+use starknet_state_sync_types::state_sync_types::SyncBlock;
 use starknet_types_core::felt::Felt;
+use tokio::sync::watch;
 
 use crate::cende::MockCendeContext;
 use crate::config::ContextConfig;
+use crate::orchestrator_versioned_constants::{VersionedConstants, FIRST_FEE_MARKET_VERSION};
 use crate::sequencer_consensus_context::SequencerConsensusContext;
 
 const TIMEOUT: Duration = Duration::from_millis(1200);
@@ -90,7 +99,7 @@ fn block_info(height: BlockNumber) -> ConsensusBlockInfo {
         timestamp: chrono::Utc::now().timestamp().try_into().expect("Timestamp conversion failed"),
         builder: Default::default(),
         l1_da_mode: L1DataAvailabilityMode::Blob,
-        l2_gas_price_fri: 1,
+        l2_gas_price_fri: VersionedConstants::latest_constants().min_gas_price.into(),
         l1_gas_price_wei: 1,
         l1_data_gas_price_wei: 1,
         eth_to_fri_rate: 1,
@@ -105,6 +114,14 @@ struct NetworkDependencies {
 fn setup(
     batcher: MockBatcherClient,
     cende_ambassador: MockCendeContext,
+) -> (SequencerConsensusContext, NetworkDependencies) {
+    setup_with_state_sync(batcher, cende_ambassador, MockStateSyncClient::new())
+}
+
+fn setup_with_state_sync(
+    batcher: MockBatcherClient,
+    cende_ambassador: MockCendeContext,
+    state_sync_client: MockStateSyncClient,
 ) -> (SequencerConsensusContext, NetworkDependencies) {
     let (outbound_proposal_sender, outbound_proposal_receiver) =
         mpsc::channel::<(HeightAndRound, mpsc::Receiver<ProposalPart>)>(CHANNEL_SIZE);
@@ -113,7 +130,6 @@ fn setup(
         mock_register_broadcast_topic().expect("Failed to create mock network");
     let BroadcastTopicChannels { broadcast_topic_client: votes_topic_client, .. } =
         subscriber_channels;
-    let state_sync_client = MockStateSyncClient::new();
 
     let context = SequencerConsensusContext::new(
         ContextConfig {
@@ -129,6 +145,7 @@ fn setup(
         outbound_proposal_sender,
         votes_topic_client,
         Arc::new(cende_ambassador),
+        watch::channel(BlockNumber(0)).0,
     );
 
     let network_dependencies =
@@ -261,6 +278,87 @@ async fn dont_send_block_info() {
     assert!(fin_receiver.await.is_err());
 }
 
+#[tokio::test]
+async fn wrong_l2_gas_price() {
+    let mut batcher = MockBatcherClient::new();
+    batcher
+        .expect_start_height()
+        .withf(|input| input.height == BlockNumber(0))
+        .return_once(|_| Ok(()));
+    let (mut context, _network) = setup(batcher, success_cende_ammbassador());
+    context.set_height_and_round(BlockNumber(0), 0).await;
+
+    let (mut content_sender, content_receiver) = mpsc::channel(context.config.proposal_buffer_size);
+    let block_info = ConsensusBlockInfo {
+        l2_gas_price_fri: block_info(BlockNumber(0)).l2_gas_price_fri + 1,
+        ..block_info(BlockNumber(0))
+    };
+    content_sender.send(ProposalPart::BlockInfo(block_info)).await.unwrap();
+    let fin_receiver =
+        context.validate_proposal(ProposalInit::default(), TIMEOUT, content_receiver).await;
+    content_sender.close_channel();
+    // The block info doesn't match the l2 gas price derived from the previous block.
+    assert!(fin_receiver.await.is_err());
+}
+
+#[tokio::test]
+async fn l2_gas_price_restored_from_parent_header() {
+    const NEXT_L2_GAS_PRICE: u64 = 1_234_567;
+    let mut batcher = MockBatcherClient::new();
+    batcher
+        .expect_start_height()
+        .withf(|input| input.height == BlockNumber(1))
+        .return_once(|_| Ok(()));
+    let mut state_sync_client = MockStateSyncClient::new();
+    state_sync_client.expect_get_block().withf(|height| *height == BlockNumber(0)).return_once(
+        |_| {
+            Ok(Some(SyncBlock {
+                block_header_without_hash: BlockHeaderWithoutHash {
+                    next_l2_gas_price: GasPrice(NEXT_L2_GAS_PRICE.into()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }))
+        },
+    );
+    let (mut context, _network) =
+        setup_with_state_sync(batcher, success_cende_ammbassador(), state_sync_client);
+
+    context.set_height_and_round(BlockNumber(1), 0).await;
+    assert_eq!(context.l2_gas_price, NEXT_L2_GAS_PRICE);
+}
+
+#[tokio::test]
+async fn l2_gas_price_restored_from_pre_fee_market_header() {
+    let mut batcher = MockBatcherClient::new();
+    batcher
+        .expect_start_height()
+        .withf(|input| input.height == BlockNumber(1))
+        .return_once(|_| Ok(()));
+    let mut state_sync_client = MockStateSyncClient::new();
+    state_sync_client.expect_get_block().withf(|height| *height == BlockNumber(0)).return_once(
+        |_| {
+            Ok(Some(SyncBlock {
+                block_header_without_hash: BlockHeaderWithoutHash {
+                    starknet_version: StarknetVersion::V0_13_4,
+                    next_l2_gas_price: GasPrice(0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }))
+        },
+    );
+    let (mut context, _network) =
+        setup_with_state_sync(batcher, success_cende_ammbassador(), state_sync_client);
+
+    // The parent doesn't record the next price, so the series starts at the minimal price.
+    context.set_height_and_round(BlockNumber(1), 0).await;
+    assert_eq!(
+        context.l2_gas_price,
+        VersionedConstants::get(&FIRST_FEE_MARKET_VERSION).unwrap().min_gas_price
+    );
+}
+
 #[tokio::test]
 async fn repropose() {
     // Receive a proposal. Then re-retrieve it.
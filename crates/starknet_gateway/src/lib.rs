@@ -12,7 +12,7 @@ pub mod state_reader;
 #[cfg(any(feature = "testing", test))]
 pub mod state_reader_test_utils;
 mod stateful_transaction_validator;
-mod stateless_transaction_validator;
+pub mod stateless_transaction_validator;
 mod sync_state_reader;
 #[cfg(test)]
 mod sync_state_reader_test;
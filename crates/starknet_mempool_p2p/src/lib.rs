@@ -2,14 +2,24 @@ pub mod config;
 pub mod metrics;
 pub mod propagator;
 pub mod runner;
+#[cfg(test)]
+mod test;
 
 use futures::FutureExt;
 use papyrus_network::gossipsub_impl::Topic;
 use papyrus_network::network_manager::metrics::{BroadcastNetworkMetrics, NetworkMetrics};
-use papyrus_network::network_manager::{BroadcastTopicChannels, NetworkManager};
+use papyrus_network::network_manager::{
+    BroadcastTopicChannels,
+    NetworkManager,
+    TopicValidationResult,
+};
+use papyrus_protobuf::mempool::RpcTransactionWrapper;
 use starknet_class_manager_types::transaction_converter::TransactionConverter;
 use starknet_class_manager_types::SharedClassManagerClient;
+use starknet_gateway::config::StatelessTransactionValidatorConfig;
+use starknet_gateway::stateless_transaction_validator::StatelessTransactionValidator;
 use starknet_gateway_types::communication::SharedGatewayClient;
+use tracing::debug;
 
 use crate::config::MempoolP2pConfig;
 use crate::metrics::{
@@ -24,6 +34,7 @@ pub const MEMPOOL_TOPIC: &str = "starknet_mempool_transaction_propagation/0.1.0"
 
 pub fn create_p2p_propagator_and_runner(
     mempool_p2p_config: MempoolP2pConfig,
+    stateless_tx_validator_config: StatelessTransactionValidatorConfig,
     gateway_client: SharedGatewayClient,
     class_manager_client: SharedClassManagerClient,
 ) -> (MempoolP2pPropagator, MempoolP2pRunner) {
@@ -45,11 +56,18 @@ pub fn create_p2p_propagator_and_runner(
         None,
         network_manager_metrics,
     );
+    let stateless_tx_validator =
+        StatelessTransactionValidator { config: stateless_tx_validator_config };
     let BroadcastTopicChannels { broadcasted_messages_receiver, broadcast_topic_client } =
         network_manager
-            .register_broadcast_topic(
+            .register_broadcast_topic_with_validator::<RpcTransactionWrapper>(
                 Topic::new(MEMPOOL_TOPIC),
                 mempool_p2p_config.network_buffer_size,
+                // Transactions that can't be parsed are rejected before they're propagated. Those
+                // that pass stateless validation are then fully validated by the gateway.
+                Box::new(move |transaction| {
+                    validate_propagated_transaction(&stateless_tx_validator, transaction)
+                }),
             )
             .expect("Failed to register broadcast topic");
     let network_future = network_manager.run();
@@ -63,3 +81,18 @@ pub fn create_p2p_propagator_and_runner(
     );
     (mempool_p2p_propagator, mempool_p2p_runner)
 }
+
+// Rejects propagated transactions that fail the stateless validation of the gateway, so that they
+// aren't propagated further.
+fn validate_propagated_transaction(
+    stateless_tx_validator: &StatelessTransactionValidator,
+    transaction: &RpcTransactionWrapper,
+) -> TopicValidationResult {
+    match stateless_tx_validator.validate(&transaction.0) {
+        Ok(()) => TopicValidationResult::Accept,
+        Err(e) => {
+            debug!("Rejecting a propagated transaction that failed stateless validation: {e}");
+            TopicValidationResult::Reject
+        }
+    }
+}
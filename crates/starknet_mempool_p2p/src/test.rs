@@ -0,0 +1,38 @@
+use papyrus_network::network_manager::TopicValidationResult;
+use papyrus_protobuf::mempool::RpcTransactionWrapper;
+use starknet_api::test_utils::invoke::rpc_invoke_tx;
+use starknet_api::{calldata, contract_address, felt, invoke_tx_args};
+use starknet_gateway::config::StatelessTransactionValidatorConfig;
+use starknet_gateway::stateless_transaction_validator::StatelessTransactionValidator;
+
+use crate::validate_propagated_transaction;
+
+#[test]
+fn transactions_failing_stateless_validation_are_rejected() {
+    let stateless_tx_validator = StatelessTransactionValidator {
+        config: StatelessTransactionValidatorConfig {
+            validate_non_zero_l1_gas_fee: false,
+            max_calldata_length: 1,
+            ..Default::default()
+        },
+    };
+
+    let valid_transaction = RpcTransactionWrapper(rpc_invoke_tx(invoke_tx_args!(
+        sender_address: contract_address!("0x100"),
+        calldata: calldata![felt!(1_u8)],
+    )));
+    assert_eq!(
+        validate_propagated_transaction(&stateless_tx_validator, &valid_transaction),
+        TopicValidationResult::Accept
+    );
+
+    let too_long_calldata = calldata![felt!(1_u8), felt!(2_u8)];
+    let invalid_transaction = RpcTransactionWrapper(rpc_invoke_tx(invoke_tx_args!(
+        sender_address: contract_address!("0x100"),
+        calldata: too_long_calldata,
+    )));
+    assert_eq!(
+        validate_propagated_transaction(&stateless_tx_validator, &invalid_transaction),
+        TopicValidationResult::Reject
+    );
+}
@@ -8,6 +8,8 @@ use crate::storage_trait::DbKey;
 pub enum StorageError {
     #[error("The key {0:?} does not exist in storage.")]
     MissingKey(DbKey),
+    #[error(transparent)]
+    Mdbx(#[from] libmdbx::Error),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -34,5 +36,5 @@ pub enum DeserializationError {
     #[error("Encountered an invalid type when deserializing a leaf.")]
     LeafTypeError,
     #[error("Invalid value for deserialization: {0}.")]
-    ValueError(Box<dyn std::error::Error>),
+    ValueError(Box<dyn std::error::Error + Send + Sync>),
 }
@@ -1,4 +1,5 @@
 pub mod db_object;
 pub mod errors;
 pub mod map_storage;
+pub mod mdbx_storage;
 pub mod storage_trait;
@@ -1,8 +1,9 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 use serde::Serialize;
 
-use crate::storage_trait::{DbKey, DbValue, Storage};
+use crate::storage_trait::{DbKey, DbValue, Storage, StorageResult};
 
 #[derive(Serialize, Debug, Default)]
 #[cfg_attr(any(test, feature = "testing"), derive(Clone))]
@@ -11,24 +12,25 @@ pub struct MapStorage {
 }
 
 impl Storage for MapStorage {
-    fn get(&self, key: &DbKey) -> Option<&DbValue> {
-        self.storage.get(key)
+    fn get(&self, key: &DbKey) -> StorageResult<Option<Cow<'_, DbValue>>> {
+        Ok(self.storage.get(key).map(Cow::Borrowed))
     }
 
-    fn set(&mut self, key: DbKey, value: DbValue) -> Option<DbValue> {
-        self.storage.insert(key, value)
+    fn set(&mut self, key: DbKey, value: DbValue) -> StorageResult<Option<DbValue>> {
+        Ok(self.storage.insert(key, value))
     }
 
-    fn mget(&self, keys: &[DbKey]) -> Vec<Option<&DbValue>> {
-        keys.iter().map(|key| self.get(key)).collect::<Vec<_>>()
+    fn mget(&self, keys: &[DbKey]) -> StorageResult<Vec<Option<Cow<'_, DbValue>>>> {
+        keys.iter().map(|key| self.get(key)).collect()
     }
 
-    fn mset(&mut self, key_to_value: HashMap<DbKey, DbValue>) {
+    fn mset(&mut self, key_to_value: HashMap<DbKey, DbValue>) -> StorageResult<()> {
         self.storage.extend(key_to_value);
+        Ok(())
     }
 
-    fn delete(&mut self, key: &DbKey) -> Option<DbValue> {
-        self.storage.remove(key)
+    fn delete(&mut self, key: &DbKey) -> StorageResult<Option<DbValue>> {
+        Ok(self.storage.remove(key))
     }
 }
 
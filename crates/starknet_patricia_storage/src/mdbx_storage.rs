@@ -0,0 +1,193 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use libmdbx::{DatabaseFlags, Geometry, TableFlags, WriteFlags, WriteMap};
+use lru::LruCache;
+use metrics::counter;
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+
+use crate::storage_trait::{DbKey, DbValue, Storage, StorageResult};
+
+#[cfg(test)]
+#[path = "mdbx_storage_test.rs"]
+mod mdbx_storage_test;
+
+type Database = libmdbx::Database<WriteMap>;
+
+/// The configuration of an [MdbxStorage].
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MdbxStorageConfig {
+    /// The directory of the database files.
+    pub path: PathBuf,
+    /// The maximum size of the database.
+    pub max_size: usize,
+    /// The growth step of the database.
+    pub growth_step: isize,
+    /// The number of values held in the read cache. Zero disables the cache.
+    pub cache_size: usize,
+}
+
+impl Default for MdbxStorageConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("./patricia_data"),
+            max_size: 1 << 40,    // 1TB
+            growth_step: 1 << 30, // 1GB
+            cache_size: 1 << 20,
+        }
+    }
+}
+
+impl SerializeConfig for MdbxStorageConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "path",
+                &self.path,
+                "The directory of the Patricia storage. The directory is not created \
+                 automatically.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_size",
+                &self.max_size,
+                "The maximum size of the Patricia storage in bytes.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "growth_step",
+                &self.growth_step,
+                "The growth step in bytes, must be greater than zero to allow the database to \
+                 grow.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "cache_size",
+                &self.cache_size,
+                "The number of values held in the read cache of the Patricia storage. Zero \
+                 disables the cache.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+/// A persistent [Storage] backed by an MDBX database.
+///
+/// Reads go through an LRU cache. Writes are written through to the cache, and the values of an
+/// `mset` are written in a single transaction.
+pub struct MdbxStorage {
+    db: Database,
+    cache: Option<Mutex<LruCache<DbKey, DbValue>>>,
+}
+
+impl MdbxStorage {
+    /// Opens the database in the configured directory, creating it if it doesn't exist.
+    pub fn open(config: &MdbxStorageConfig) -> StorageResult<Self> {
+        let db = Database::new()
+            .set_geometry(Geometry {
+                size: Some(..config.max_size),
+                growth_step: Some(config.growth_step),
+                ..Default::default()
+            })
+            .set_flags(DatabaseFlags {
+                // Nodes are accessed by their hash, so there is no locality of pages and readahead
+                // only fills the RAM with garbage.
+                no_rdahead: true,
+                liforeclaim: true,
+                ..Default::default()
+            })
+            .open(&config.path)?;
+        // Make sure the main table exists before the first read.
+        let txn = db.begin_rw_txn()?;
+        txn.create_table(None, TableFlags::empty())?;
+        txn.commit()?;
+        let cache =
+            NonZeroUsize::new(config.cache_size).map(|size| Mutex::new(LruCache::new(size)));
+        Ok(Self { db, cache })
+    }
+
+    fn get_cached(&self, key: &DbKey) -> Option<DbValue> {
+        let value = self.cache.as_ref()?.lock().expect("Poisoned cache lock.").get(key).cloned();
+        match value {
+            Some(_) => counter!("patricia_storage_cache_hits").increment(1),
+            None => counter!("patricia_storage_cache_misses").increment(1),
+        }
+        value
+    }
+
+    fn cache_values(&self, values: impl IntoIterator<Item = (DbKey, DbValue)>) {
+        if let Some(cache) = &self.cache {
+            let mut cache = cache.lock().expect("Poisoned cache lock.");
+            for (key, value) in values {
+                cache.put(key, value);
+            }
+        }
+    }
+
+    fn write(&self, key_to_value: &HashMap<DbKey, DbValue>) -> StorageResult<()> {
+        let txn = self.db.begin_rw_txn()?;
+        let table = txn.open_table(None)?;
+        for (key, value) in key_to_value {
+            txn.put(&table, &key.0, &value.0, WriteFlags::UPSERT)?;
+        }
+        txn.commit()?;
+        counter!("patricia_storage_written_values")
+            .increment(u64::try_from(key_to_value.len()).expect("usize should fit in u64."));
+        Ok(())
+    }
+}
+
+impl Storage for MdbxStorage {
+    fn get(&self, key: &DbKey) -> StorageResult<Option<Cow<'_, DbValue>>> {
+        Ok(self.mget(std::slice::from_ref(key))?.pop().flatten())
+    }
+
+    fn set(&mut self, key: DbKey, value: DbValue) -> StorageResult<Option<DbValue>> {
+        let previous_value = self.get(&key)?.map(Cow::into_owned);
+        self.mset(HashMap::from([(key, value)]))?;
+        Ok(previous_value)
+    }
+
+    fn mget(&self, keys: &[DbKey]) -> StorageResult<Vec<Option<Cow<'_, DbValue>>>> {
+        let mut values: Vec<_> = keys.iter().map(|key| self.get_cached(key)).collect();
+        if values.iter().all(Option::is_some) {
+            return Ok(values.into_iter().map(|value| value.map(Cow::Owned)).collect());
+        }
+        let txn = self.db.begin_ro_txn()?;
+        let table = txn.open_table(None)?;
+        let mut read_values = Vec::new();
+        for (key, value) in keys.iter().zip(values.iter_mut()).filter(|(_, value)| value.is_none())
+        {
+            *value = txn.get::<Vec<u8>>(&table, &key.0)?.map(DbValue);
+            if let Some(value) = value {
+                read_values.push((key.clone(), value.clone()));
+            }
+        }
+        self.cache_values(read_values);
+        Ok(values.into_iter().map(|value| value.map(Cow::Owned)).collect())
+    }
+
+    fn mset(&mut self, key_to_value: HashMap<DbKey, DbValue>) -> StorageResult<()> {
+        self.write(&key_to_value)?;
+        self.cache_values(key_to_value);
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &DbKey) -> StorageResult<Option<DbValue>> {
+        let previous_value = self.get(key)?.map(Cow::into_owned);
+        let txn = self.db.begin_rw_txn()?;
+        let table = txn.open_table(None)?;
+        txn.del(&table, &key.0, None)?;
+        txn.commit()?;
+        if let Some(cache) = &self.cache {
+            cache.lock().expect("Poisoned cache lock.").pop(key);
+        }
+        Ok(previous_value)
+    }
+}
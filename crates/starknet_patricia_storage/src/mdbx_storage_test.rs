@@ -0,0 +1,70 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use tempfile::TempDir;
+
+use crate::mdbx_storage::{MdbxStorage, MdbxStorageConfig};
+use crate::storage_trait::{DbKey, DbValue, Storage};
+
+fn get_test_config(cache_size: usize) -> (MdbxStorageConfig, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let config = MdbxStorageConfig {
+        path: temp_dir.path().to_path_buf(),
+        max_size: 1 << 30,
+        growth_step: 1 << 20,
+        cache_size,
+    };
+    (config, temp_dir)
+}
+
+fn key(i: u8) -> DbKey {
+    DbKey(vec![i])
+}
+
+fn value(i: u8) -> DbValue {
+    DbValue(vec![i, i])
+}
+
+#[test]
+fn storage_operations() {
+    for cache_size in [0, 2] {
+        let (config, _temp_dir) = get_test_config(cache_size);
+        let mut storage = MdbxStorage::open(&config).unwrap();
+
+        assert_eq!(storage.get(&key(0)).unwrap(), None);
+        assert_eq!(storage.set(key(0), value(0)).unwrap(), None);
+        assert_eq!(storage.set(key(0), value(1)).unwrap(), Some(value(0)));
+        assert_eq!(storage.get(&key(0)).unwrap(), Some(Cow::Owned(value(1))));
+
+        storage
+            .mset(HashMap::from([(key(1), value(1)), (key(2), value(2)), (key(3), value(3))]))
+            .unwrap();
+        assert_eq!(
+            storage.mget(&[key(3), key(4), key(1), key(0)]).unwrap(),
+            vec![
+                Some(Cow::Owned(value(3))),
+                None,
+                Some(Cow::Owned(value(1))),
+                Some(Cow::Owned(value(1)))
+            ]
+        );
+
+        assert_eq!(storage.delete(&key(1)).unwrap(), Some(value(1)));
+        assert_eq!(storage.delete(&key(1)).unwrap(), None);
+        assert_eq!(storage.get(&key(1)).unwrap(), None);
+    }
+}
+
+#[test]
+fn values_persist_after_reopen() {
+    let (config, _temp_dir) = get_test_config(2);
+    let key_to_value: HashMap<_, _> = (0..10).map(|i| (key(i), value(i))).collect();
+    let mut storage = MdbxStorage::open(&config).unwrap();
+    storage.mset(key_to_value.clone()).unwrap();
+    drop(storage);
+
+    let storage = MdbxStorage::open(&config).unwrap();
+    for (key, value) in key_to_value {
+        assert_eq!(storage.get(&key).unwrap(), Some(Cow::Owned(value)));
+    }
+}
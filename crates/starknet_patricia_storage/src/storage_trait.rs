@@ -1,33 +1,37 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 use serde::{Serialize, Serializer};
 use starknet_types_core::felt::Felt;
 
-#[derive(Debug, Eq, Hash, PartialEq)]
-#[cfg_attr(any(test, feature = "testing"), derive(Clone))]
+use crate::errors::StorageError;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct DbKey(pub Vec<u8>);
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
-#[cfg_attr(any(test, feature = "testing"), derive(Clone))]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 pub struct DbValue(pub Vec<u8>);
 
-pub trait Storage: From<HashMap<DbKey, DbValue>> {
-    /// Returns value from storage, if it exists.
-    fn get(&self, key: &DbKey) -> Option<&DbValue>;
+pub type StorageResult<T> = Result<T, StorageError>;
+
+pub trait Storage {
+    /// Returns value from storage, if it exists. Storages that hold their values in memory return
+    /// them borrowed.
+    fn get(&self, key: &DbKey) -> StorageResult<Option<Cow<'_, DbValue>>>;
 
     /// Sets value in storage. If key already exists, its value is overwritten and the old value is
     /// returned.
-    fn set(&mut self, key: DbKey, value: DbValue) -> Option<DbValue>;
+    fn set(&mut self, key: DbKey, value: DbValue) -> StorageResult<Option<DbValue>>;
 
     /// Returns values from storage in same order of given keys. Value is None for keys that do not
     /// exist.
-    fn mget(&self, keys: &[DbKey]) -> Vec<Option<&DbValue>>;
+    fn mget(&self, keys: &[DbKey]) -> StorageResult<Vec<Option<Cow<'_, DbValue>>>>;
 
     /// Sets values in storage.
-    fn mset(&mut self, key_to_value: HashMap<DbKey, DbValue>);
+    fn mset(&mut self, key_to_value: HashMap<DbKey, DbValue>) -> StorageResult<()>;
 
     /// Deletes value from storage and returns its value if it exists. Returns None if not.
-    fn delete(&mut self, key: &DbKey) -> Option<DbValue>;
+    fn delete(&mut self, key: &DbKey) -> StorageResult<Option<DbValue>>;
 }
 
 #[derive(Debug)]
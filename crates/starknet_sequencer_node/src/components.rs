@@ -142,6 +142,7 @@ pub async fn create_node_components(
                     .expect("Class Manager Client should be available");
                 let (mempool_p2p_propagator, mempool_p2p_runner) = create_p2p_propagator_and_runner(
                     config.mempool_p2p_config.clone(),
+                    config.gateway_config.stateless_tx_validator_config.clone(),
                     gateway_client,
                     class_manager_client,
                 );
@@ -44,6 +44,7 @@ use papyrus_sync::{
     GENESIS_HASH,
 };
 use starknet_api::block::BlockHash;
+use starknet_api::core::ChainId;
 use starknet_api::felt;
 use starknet_class_manager_types::SharedClassManagerClient;
 use starknet_client::reader::objects::pending_data::{PendingBlock, PendingBlockOrDeprecated};
@@ -107,6 +108,7 @@ impl StateSyncRunner {
             revert_config,
         } = config;
 
+        let chain_id = storage_config.db_config.chain_id.clone();
         let (storage_reader, mut storage_writer) =
             open_storage(storage_config).expect("StateSyncRunner failed opening storage");
 
@@ -186,6 +188,7 @@ impl StateSyncRunner {
                         storage_reader.clone(),
                         storage_writer,
                         p2p_sync_client_config,
+                        chain_id,
                         &mut network_manager,
                         new_block_receiver,
                         class_manager_client,
@@ -232,6 +235,7 @@ impl StateSyncRunner {
         storage_reader: StorageReader,
         storage_writer: StorageWriter,
         p2p_sync_client_config: P2pSyncClientConfig,
+        chain_id: ChainId,
         network_manager: &mut NetworkManager,
         new_block_receiver: Receiver<SyncBlock>,
         class_manager_client: SharedClassManagerClient,
@@ -244,14 +248,18 @@ impl StateSyncRunner {
             .register_sqmr_protocol_client(Protocol::Transaction.into(), BUFFER_SIZE);
         let class_client_sender =
             network_manager.register_sqmr_protocol_client(Protocol::Class.into(), BUFFER_SIZE);
+        let event_client_sender =
+            network_manager.register_sqmr_protocol_client(Protocol::Event.into(), BUFFER_SIZE);
         let p2p_sync_client_channels = P2pSyncClientChannels::new(
             header_client_sender,
             state_diff_client_sender,
             transaction_client_sender,
             class_client_sender,
+            event_client_sender,
         );
         P2pSyncClient::new(
             p2p_sync_client_config,
+            chain_id,
             storage_reader,
             storage_writer,
             p2p_sync_client_channels,